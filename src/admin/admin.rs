@@ -2,8 +2,8 @@
 use tuwunel_core::Result;
 
 use crate::{
-	appservice, appservice::AppserviceCommand, check, check::CheckCommand, context::Context,
-	debug, debug::DebugCommand, federation, federation::FederationCommand, media,
+	appservice, appservice::AppserviceCommand, check, check::CheckCommand, context::Context, db,
+	db::DbCommand, debug, debug::DebugCommand, federation, federation::FederationCommand, media,
 	media::MediaCommand, query, query::QueryCommand, room, room::RoomCommand, server,
 	server::ServerCommand, user, user::UserCommand,
 };
@@ -39,6 +39,10 @@ pub(super) enum AdminCommand {
 	/// - Commands for checking integrity
 	Check(CheckCommand),
 
+	#[command(subcommand)]
+	/// - Commands for verifying and repairing the database
+	Db(DbCommand),
+
 	#[command(subcommand)]
 	/// - Commands for debugging things
 	Debug(DebugCommand),
@@ -62,5 +66,6 @@ pub(super) async fn process(command: AdminCommand, context: &Context<'_>) -> Res
 		| Debug(command) => debug::process(command, context).await,
 		| Query(command) => query::process(command, context).await,
 		| Check(command) => check::process(command, context).await,
+		| Db(command) => db::process(command, context).await,
 	}
 }
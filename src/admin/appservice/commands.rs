@@ -1,5 +1,13 @@
+use std::time::Duration;
+
 use futures::{FutureExt, StreamExt, TryFutureExt};
-use tuwunel_core::{Err, Result, checked};
+use ruma::api::appservice::ping::send_ping;
+use tokio::time::Instant;
+use tuwunel_core::{
+	Err, Result, checked,
+	utils::time::{now_millis, pretty},
+};
+use tuwunel_service::sending::Destination;
 
 use crate::admin_command;
 
@@ -78,3 +86,105 @@ pub(super) async fn list_registered(&self) -> Result {
 		})
 		.await
 }
+
+#[admin_command]
+pub(super) async fn ping(&self, appservice_identifier: String) -> Result {
+	let Some(registration) = self
+		.services
+		.appservice
+		.get_registration(&appservice_identifier)
+		.await
+	else {
+		return Err!("Appservice {appservice_identifier:?} not found.");
+	};
+
+	let timer = Instant::now();
+	let response = self
+		.services
+		.sending
+		.send_appservice_request(registration, send_ping::v1::Request { transaction_id: None })
+		.await?;
+
+	if response.is_none() {
+		return Err!("Appservice {appservice_identifier:?} does not have a URL set.");
+	}
+
+	let elapsed = timer.elapsed();
+	write!(self, "Appservice {appservice_identifier} responded in {elapsed:?}.").await
+}
+
+#[admin_command]
+pub(super) async fn status(&self, appservice_identifier: Option<String>) -> Result {
+	let ids: Vec<_> = match appservice_identifier {
+		| Some(id) => vec![id],
+		| None =>
+			self.services
+				.appservice
+				.iter_ids()
+				.collect()
+				.await,
+	};
+
+	let ago = |ts: Option<u64>| {
+		ts.map_or_else(
+			|| "never".to_owned(),
+			|ts| {
+				let elapsed = Duration::from_millis(now_millis().saturating_sub(ts));
+				format!("{} ago", pretty(elapsed))
+			},
+		)
+	};
+
+	writeln!(
+		self,
+		"| ID | Queued | In Flight | Last Success | Last Transaction | Failures | Last Failure \
+		 | Backoff |"
+	)
+	.await?;
+	writeln!(
+		self,
+		"| -- | ------ | --------- | ------------ | ---------------- | -------- | ------------ \
+		 | ------- |"
+	)
+	.await?;
+
+	let mut errors = Vec::new();
+	for id in ids {
+		let sending = &self.services.sending;
+		let dest = Destination::Appservice(id.clone());
+		let queued = sending.db.queued_requests(&dest).count().await;
+		let active = sending
+			.db
+			.active_requests_for(&dest)
+			.count()
+			.await;
+		let status = sending.db.appservice_status(&id).await;
+
+		// Unlike federation destinations, appservices are not backed off: a failed
+		// transaction is retried along with the next event queued for them.
+		let backoff = match status.failures {
+			| 0 => "none",
+			| _ => "retry with the next event",
+		};
+
+		writeln!(
+			self,
+			"| {id} | {queued} | {active} | {} | {} | {} | {} | {backoff} |",
+			ago(status.last_success),
+			status.last_txn_id.as_deref().unwrap_or("none"),
+			status.failures,
+			ago(status.last_failure),
+		)
+		.await?;
+
+		if let Some(error) = status.last_error.filter(|_| status.failures > 0) {
+			errors.push(format!("{id}: {error}"));
+		}
+	}
+
+	if !errors.is_empty() {
+		write!(self, "\nLast errors:\n```\n{}\n```", errors.join("\n")).await?;
+	}
+
+	Ok(())
+}
@@ -37,4 +37,20 @@ pub(super) enum AppserviceCommand {
 	/// - List all the currently registered appservices
 	#[clap(alias("list"))]
 	ListRegistered,
+
+	/// - Ping an appservice to check it can be reached and accepts our token
+	Ping {
+		/// The appservice to ping
+		appservice_identifier: String,
+	},
+
+	/// - Show the transaction backlog and recent failures of appservices
+	///
+	/// Queued events wait for the transaction in flight to be accepted. An
+	/// appservice which keeps failing is not backed off like federation
+	/// destinations; its transaction is retried with each new event.
+	Status {
+		/// Show only this appservice
+		appservice_identifier: Option<String>,
+	},
 }
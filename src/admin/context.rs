@@ -6,8 +6,8 @@
 	lock::Mutex,
 };
 use ruma::EventId;
-use tuwunel_core::Result;
-use tuwunel_service::Services;
+use tuwunel_core::{Result, debug_warn};
+use tuwunel_service::{Services, admin::Progress};
 
 pub(crate) struct Context<'a> {
 	pub(crate) services: &'a Services,
@@ -15,6 +15,7 @@ pub(crate) struct Context<'a> {
 	pub(crate) timer: SystemTime,
 	pub(crate) reply_id: Option<&'a EventId>,
 	pub(crate) output: Mutex<BufWriter<Vec<u8>>>,
+	pub(crate) progress: Progress,
 }
 
 impl Context<'_> {
@@ -42,4 +43,15 @@ pub(crate) fn write_str<'a>(
 				.await
 		})
 	}
+
+	/// Report intermediate progress of a long-running command, editing one
+	/// notice in the room or printing to the console. Updates are throttled and
+	/// failures are only logged, so this may be called freely.
+	pub(crate) async fn progress(&self, message: &str) {
+		self.services
+			.admin
+			.update_progress(&self.progress, message)
+			.await
+			.unwrap_or_else(|e| debug_warn!("Failed to report command progress: {e}"));
+	}
 }
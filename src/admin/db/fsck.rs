@@ -0,0 +1,285 @@
+//! Database integrity check.
+
+use std::{fmt::Write, sync::Arc};
+
+use futures::StreamExt;
+use ruma::{EventId, RoomId, UserId};
+use tokio::time::Instant;
+use tuwunel_core::{
+	Err, Result,
+	utils::stream::{ReadyExt, TryIgnore},
+};
+use tuwunel_database::{Map, SEP};
+
+use crate::admin_command;
+
+/// Encoding of a key or value.
+#[derive(Clone, Copy)]
+pub(super) enum Format {
+	/// Not checked.
+	Any,
+
+	/// Big-endian u64 count or short ID.
+	Count,
+
+	/// UTF-8 string.
+	Str,
+
+	UserId,
+
+	RoomId,
+
+	EventId,
+
+	/// User ID and device ID.
+	UserDevice,
+
+	/// Event type and state key.
+	StateKey,
+
+	Json,
+}
+
+/// Key and value formats of the columns whose entries are checked.
+const FORMATS: &[(&str, Format, Format)] = &[
+	("eventid_outlierpdu", Format::EventId, Format::Json),
+	("eventid_pduid", Format::EventId, Format::Any),
+	("eventid_shorteventid", Format::EventId, Format::Count),
+	("pduid_pdu", Format::Any, Format::Json),
+	("roomid_shortroomid", Format::RoomId, Format::Count),
+	("roomid_shortstatehash", Format::RoomId, Format::Count),
+	("shorteventid_eventid", Format::Count, Format::EventId),
+	("shortstatekey_statekey", Format::Count, Format::StateKey),
+	("statekey_shortstatekey", Format::StateKey, Format::Count),
+	("token_userdeviceid", Format::Str, Format::UserDevice),
+	("userdeviceid_metadata", Format::UserDevice, Format::Json),
+	("userdeviceid_token", Format::UserDevice, Format::Str),
+	("userid_avatarurl", Format::UserId, Format::Str),
+	("userid_displayname", Format::UserId, Format::Str),
+	("userid_password", Format::UserId, Format::Str),
+];
+
+/// Pairs of columns each mapping the other's values back to its keys. The
+/// first is authoritative; with `--repair` the second is changed to match.
+const INVERSES: &[(&str, &str)] = &[
+	("eventid_shorteventid", "shorteventid_eventid"),
+	("statekey_shortstatekey", "shortstatekey_statekey"),
+	("userdeviceid_token", "token_userdeviceid"),
+];
+
+#[derive(Default)]
+struct Scan {
+	entries: usize,
+	errors: usize,
+	bad_keys: usize,
+	bad_vals: usize,
+}
+
+#[derive(Default)]
+struct CrossReference {
+	/// Inverse entries absent from the derived column.
+	missing: Vec<(Vec<u8>, Vec<u8>)>,
+
+	/// Derived entries which map back to another key.
+	conflicting: usize,
+
+	/// Keys of derived entries without a matching authoritative entry.
+	dangling: Vec<Vec<u8>>,
+}
+
+#[admin_command]
+pub(super) async fn fsck(&self, column: Option<String>, repair: bool) -> Result {
+	if let Some(column) = &column {
+		if self.services.db.get(column).is_err() {
+			return Err!("Column {column:?} not found.");
+		}
+	}
+
+	let selected = |name: &str| {
+		column
+			.as_deref()
+			.is_none_or(|column| column == name)
+	};
+
+	let timer = Instant::now();
+	let mut out = String::new();
+	let (mut columns, mut entries, mut problems) = (0_usize, 0_usize, 0_usize);
+	for (&name, map) in self.services.db.iter() {
+		if !selected(name) {
+			continue;
+		}
+
+		self.progress(&format!("Checking {name}: {entries} entries in {columns} columns so far"))
+			.await;
+
+		let scan = scan(map).await;
+		columns = columns.saturating_add(1);
+		entries = entries.saturating_add(scan.entries);
+		if scan.errors > 0 || scan.bad_keys > 0 || scan.bad_vals > 0 {
+			writeln!(
+				out,
+				"{name}: {} read errors, {} malformed keys, {} malformed values in {} entries",
+				scan.errors, scan.bad_keys, scan.bad_vals, scan.entries
+			)?;
+
+			problems = problems
+				.saturating_add(scan.errors)
+				.saturating_add(scan.bad_keys)
+				.saturating_add(scan.bad_vals);
+		}
+	}
+
+	for &(primary, derived) in INVERSES {
+		if !selected(primary) && !selected(derived) {
+			continue;
+		}
+
+		let (primary, derived) = (self.services.db.get(primary)?, self.services.db.get(derived)?);
+		let xref = cross_reference(primary, derived).await;
+		let found = xref
+			.missing
+			.len()
+			.saturating_add(xref.conflicting)
+			.saturating_add(xref.dangling.len());
+
+		if found == 0 {
+			continue;
+		}
+
+		writeln!(
+			out,
+			"{} -> {}: {} missing, {} conflicting, {} dangling",
+			primary.name(),
+			derived.name(),
+			xref.missing.len(),
+			xref.conflicting,
+			xref.dangling.len(),
+		)?;
+
+		problems = problems.saturating_add(found);
+		if repair {
+			for (key, val) in &xref.missing {
+				derived.insert(key, val);
+			}
+
+			for key in &xref.dangling {
+				derived.remove(key);
+			}
+
+			writeln!(
+				out,
+				"{}: inserted {}, removed {}; conflicts are left for manual review",
+				derived.name(),
+				xref.missing.len(),
+				xref.dangling.len(),
+			)?;
+		}
+	}
+
+	let elapsed = timer.elapsed();
+	self.write_str(&format!(
+		"Checked {entries} entries in {columns} columns in {elapsed:?}; found {problems} \
+		 problems.\n\n```\n{out}```"
+	))
+	.await
+}
+
+/// Read every entry of the column, checking the formats of those listed in
+/// `FORMATS`.
+async fn scan(map: &Arc<Map>) -> Scan {
+	let (key_format, val_format) = FORMATS
+		.iter()
+		.find(|(name, ..)| *name == map.name())
+		.map_or((Format::Any, Format::Any), |&(_, key, val)| (key, val));
+
+	map.raw_stream()
+		.ready_fold(Scan::default(), |mut scan, item| {
+			scan.entries = scan.entries.saturating_add(1);
+			match item {
+				| Err(_) => scan.errors = scan.errors.saturating_add(1),
+				| Ok((key, val)) => {
+					if !key_format.is_valid(key) {
+						scan.bad_keys = scan.bad_keys.saturating_add(1);
+					}
+
+					if !val_format.is_valid(val) {
+						scan.bad_vals = scan.bad_vals.saturating_add(1);
+					}
+				},
+			}
+
+			scan
+		})
+		.await
+}
+
+/// Compare the entries of an authoritative column with its derived inverse.
+async fn cross_reference(primary: &Arc<Map>, derived: &Arc<Map>) -> CrossReference {
+	let mut xref = CrossReference::default();
+
+	primary
+		.raw_stream()
+		.ignore_err()
+		.map(|(key, val)| (key.to_vec(), val.to_vec()))
+		.then(|(key, val)| async move {
+			let inverse = derived
+				.get(&val)
+				.await
+				.map(|handle| *handle == *key);
+
+			(key, val, inverse)
+		})
+		.ready_for_each(|(key, val, inverse)| match inverse {
+			| Ok(true) => {},
+			| Ok(false) => xref.conflicting = xref.conflicting.saturating_add(1),
+			| Err(_) => xref.missing.push((val, key)),
+		})
+		.await;
+
+	derived
+		.raw_stream()
+		.ignore_err()
+		.map(|(key, val)| (key.to_vec(), val.to_vec()))
+		.then(|(key, val)| async move {
+			let inverse = primary.get(&val).await;
+
+			inverse
+				.is_ok_and(|handle| *handle == *key)
+				.then_some(())
+				.ok_or(key)
+		})
+		.ready_for_each(|inverse| {
+			if let Err(key) = inverse {
+				xref.dangling.push(key);
+			}
+		})
+		.await;
+
+	xref
+}
+
+impl Format {
+	pub(super) fn is_valid(self, bytes: &[u8]) -> bool {
+		let pair = || {
+			let pos = bytes.iter().position(|&b| b == SEP)?;
+			let (a, b) = bytes.split_at(pos);
+
+			Some((utf8(a)?, utf8(b.get(1..)?)?))
+		};
+
+		match self {
+			| Self::Any => true,
+			| Self::Count => bytes.len() == size_of::<u64>(),
+			| Self::Str => utf8(bytes).is_some(),
+			| Self::UserId => utf8(bytes).is_some_and(|s| UserId::parse(s).is_ok()),
+			| Self::RoomId => utf8(bytes).is_some_and(|s| RoomId::parse(s).is_ok()),
+			| Self::EventId => utf8(bytes).is_some_and(|s| EventId::parse(s).is_ok()),
+			| Self::UserDevice => pair()
+				.is_some_and(|(user, device)| UserId::parse(user).is_ok() && !device.is_empty()),
+			| Self::StateKey => pair().is_some_and(|(event_type, _)| !event_type.is_empty()),
+			| Self::Json => serde_json::from_slice::<serde_json::Value>(bytes).is_ok(),
+		}
+	}
+}
+
+fn utf8(bytes: &[u8]) -> Option<&str> { std::str::from_utf8(bytes).ok() }
@@ -0,0 +1,28 @@
+mod fsck;
+#[cfg(test)]
+mod tests;
+
+use clap::Subcommand;
+use tuwunel_core::Result;
+
+use crate::admin_command_dispatch;
+
+#[admin_command_dispatch]
+#[derive(Debug, Subcommand)]
+pub(super) enum DbCommand {
+	/// - Verify the database, reporting entries which are unreadable, malformed
+	///   or missing their counterpart in an inverse column
+	///
+	/// Inverse columns such as `token_userdeviceid` are repaired to match
+	/// their authoritative counterpart with `--repair`. Other problems are
+	/// only reported.
+	Fsck {
+		/// Check only this column
+		#[arg(long)]
+		column: Option<String>,
+
+		/// Repair inconsistent inverse columns
+		#[arg(long)]
+		repair: bool,
+	},
+}
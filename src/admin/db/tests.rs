@@ -0,0 +1,85 @@
+use clap::Parser;
+use tuwunel_database::SEP;
+
+use super::{DbCommand, fsck::Format};
+use crate::admin::AdminCommand;
+
+fn pair(a: &str, b: &str) -> Vec<u8> {
+	let mut bytes = a.as_bytes().to_vec();
+	bytes.push(SEP);
+	bytes.extend_from_slice(b.as_bytes());
+	bytes
+}
+
+#[test]
+fn parse_fsck() {
+	let command = AdminCommand::try_parse_from(["tuwunel", "db", "fsck"]).unwrap();
+	assert!(matches!(
+		command,
+		AdminCommand::Db(DbCommand::Fsck { column: None, repair: false })
+	));
+
+	let command = AdminCommand::try_parse_from([
+		"tuwunel",
+		"db",
+		"fsck",
+		"--column",
+		"token_userdeviceid",
+		"--repair",
+	])
+	.unwrap();
+
+	let AdminCommand::Db(DbCommand::Fsck { column, repair }) = command else {
+		panic!("not db fsck");
+	};
+
+	assert_eq!(column.as_deref(), Some("token_userdeviceid"));
+	assert!(repair);
+}
+
+#[test]
+fn format_count() {
+	assert!(Format::Count.is_valid(&42_u64.to_be_bytes()));
+	assert!(!Format::Count.is_valid(&42_u32.to_be_bytes()));
+	assert!(!Format::Count.is_valid(&[]));
+}
+
+#[test]
+fn format_ids() {
+	assert!(Format::UserId.is_valid(b"@alice:example.com"));
+	assert!(!Format::UserId.is_valid(b"alice"));
+	assert!(!Format::UserId.is_valid(b"@alice:example.com\xff"));
+	assert!(Format::RoomId.is_valid(b"!room:example.com"));
+	assert!(!Format::RoomId.is_valid(b"#alias:example.com"));
+	assert!(Format::EventId.is_valid(b"$Rqnc-F-dvnEYJTyHq_iKxU2bZ1CI92-kuZq3a5lr5Zg"));
+	assert!(!Format::EventId.is_valid(b"event"));
+}
+
+#[test]
+fn format_str() {
+	assert!(Format::Str.is_valid(b"token"));
+	assert!(!Format::Str.is_valid(&[0xFF, 0xFE]));
+	assert!(Format::Any.is_valid(&[0xFF, 0xFE]));
+}
+
+#[test]
+fn format_user_device() {
+	assert!(Format::UserDevice.is_valid(&pair("@alice:example.com", "DEVICE")));
+	assert!(!Format::UserDevice.is_valid(&pair("@alice:example.com", "")));
+	assert!(!Format::UserDevice.is_valid(&pair("alice", "DEVICE")));
+	assert!(!Format::UserDevice.is_valid(b"@alice:example.com"));
+}
+
+#[test]
+fn format_state_key() {
+	assert!(Format::StateKey.is_valid(&pair("m.room.member", "@alice:example.com")));
+	assert!(Format::StateKey.is_valid(&pair("m.room.create", "")));
+	assert!(!Format::StateKey.is_valid(&pair("", "@alice:example.com")));
+	assert!(!Format::StateKey.is_valid(b"m.room.create"));
+}
+
+#[test]
+fn format_json() {
+	assert!(Format::Json.is_valid(br#"{"type":"m.room.create"}"#));
+	assert!(!Format::Json.is_valid(b"{"));
+}
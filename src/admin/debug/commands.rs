@@ -2,21 +2,23 @@
 	collections::HashMap,
 	fmt::Write,
 	iter::once,
-	time::{Instant, SystemTime},
+	sync::atomic::Ordering,
+	time::{Duration, Instant, SystemTime},
 };
 
 use futures::{FutureExt, StreamExt, TryStreamExt};
 use ruma::{
 	CanonicalJsonObject, CanonicalJsonValue, EventId, OwnedEventId, OwnedRoomId,
-	OwnedRoomOrAliasId, OwnedServerName, RoomId, RoomVersionId,
+	OwnedRoomOrAliasId, OwnedServerName, OwnedUserId, RoomId, RoomVersionId,
 	api::federation::event::get_room_state, events::AnyStateEvent, serde::Raw,
 };
+use serde_json::{Map as JsonObject, Value as JsonValue};
 use tracing_subscriber::EnvFilter;
 use tuwunel_core::{
-	Err, Result, debug_error, err, info,
+	Err, Result, debug_error, err, implement, info,
 	matrix::{
 		Event,
-		pdu::{PduEvent, PduId, RawPduId},
+		pdu::{PduBuilder, PduEvent, PduId, RawPduId},
 	},
 	trace, utils,
 	utils::{
@@ -30,7 +32,7 @@
 	state_compressor::HashSetCompressStateEvent,
 };
 
-use crate::admin_command;
+use crate::{Context, admin_command};
 
 #[admin_command]
 pub(super) async fn echo(&self, message: Vec<String>) -> Result {
@@ -129,10 +131,75 @@ pub(super) async fn get_pdu(&self, event_id: OwnedEventId) -> Result {
 			} else {
 				"PDU found in our database"
 			};
-			write!(self, "{msg}\n```json\n{text}\n```",)
+			let markers = self.event_markers(&event_id).await;
+			write!(self, "{msg}\n{markers}\n```json\n{text}\n```",).await?;
 		},
 	}
-	.await
+
+	if let Ok(json) = self
+		.services
+		.rooms
+		.timeline
+		.get_unredacted_pdu_json(&event_id)
+		.await
+	{
+		let text = serde_json::to_string_pretty(&json)?;
+		write!(self, "\nOriginal content retained after redaction\n```json\n{text}\n```").await?;
+	}
+
+	Ok(())
+}
+
+#[admin_command]
+pub(super) async fn get_outlier(&self, event_id: OwnedEventId) -> Result {
+	let Ok(json) = self
+		.services
+		.rooms
+		.outlier
+		.get_outlier_pdu_json(&event_id)
+		.await
+	else {
+		return Err!("Outlier PDU not found locally.");
+	};
+
+	let text = serde_json::to_string_pretty(&json)?;
+	let markers = self.event_markers(&event_id).await;
+	self.write_str(&format!("Outlier PDU found in our database\n{markers}\n```json\n{text}\n```"))
+		.await
+}
+
+/// Summarizes where an event is stored and how it was handled.
+#[implement(Context, params = "<'_>")]
+async fn event_markers(&self, event_id: &EventId) -> String {
+	let rooms = &self.services.rooms;
+	let timeline = rooms
+		.timeline
+		.get_pdu_id(event_id)
+		.await
+		.map(PduId::from);
+
+	let outlier = rooms
+		.outlier
+		.get_outlier_pdu_json(event_id)
+		.await
+		.is_ok();
+
+	let soft_failed = rooms
+		.pdu_metadata
+		.is_event_soft_failed(event_id)
+		.await;
+
+	let timeline = match timeline {
+		| Ok(PduId { shortroomid, shorteventid }) =>
+			format!("yes (shortroomid {shortroomid}, count {shorteventid})"),
+		| Err(_) => "no".to_owned(),
+	};
+
+	format!(
+		"- In timeline: {timeline}\n- Outlier copy: {}\n- Soft failed: {}\n",
+		if outlier { "yes" } else { "no" },
+		if soft_failed { "yes" } else { "no" },
+	)
 }
 
 #[admin_command]
@@ -559,6 +626,109 @@ pub(super) async fn latest_pdu_in_room(&self, room_id: OwnedRoomId) -> Result {
 	self.write_str(&out).await
 }
 
+#[admin_command]
+pub(super) async fn send_event(&self, room_id: OwnedRoomOrAliasId) -> Result {
+	if self.body.len() < 2
+		|| !self.body[0].trim().starts_with("```")
+		|| self.body.last().unwrap_or(&EMPTY).trim() != "```"
+	{
+		return Err!("Expected code block in command body. Add --help for details.");
+	}
+
+	let string = self.body[1..self.body.len().saturating_sub(1)].join("\n");
+	let event: JsonObject<String, JsonValue> = match serde_json::from_str(&string) {
+		| Ok(event) => event,
+		| Err(e) => return Err!("Invalid json in command body: {e}"),
+	};
+
+	let Some(event_type) = event.get("type").and_then(JsonValue::as_str) else {
+		return Err!("Event is missing a string `type`.");
+	};
+
+	let Some(content) = event
+		.get("content")
+		.filter(|content| content.is_object())
+	else {
+		return Err!("Event is missing an object `content`.");
+	};
+
+	let state_key = match event.get("state_key") {
+		| None => None,
+		| Some(JsonValue::String(state_key)) => Some(state_key.as_str().into()),
+		| Some(_) => return Err!("Event `state_key` must be a string."),
+	};
+
+	let pdu_builder = PduBuilder {
+		event_type: event_type.into(),
+		content: serde_json::value::to_raw_value(content)?,
+		state_key,
+		..Default::default()
+	};
+
+	let server_user = &self.services.globals.server_user;
+	let room_id = self
+		.services
+		.rooms
+		.alias
+		.resolve(&room_id)
+		.await?;
+	let state_lock = self
+		.services
+		.rooms
+		.state
+		.mutex
+		.lock(&room_id)
+		.await;
+	self.services
+		.rooms
+		.state_accessor
+		.ensure_power_to_send(&pdu_builder, server_user, &room_id)
+		.await?;
+
+	let event_id = self
+		.services
+		.rooms
+		.timeline
+		.build_and_append_pdu(pdu_builder, server_user, &room_id, &state_lock)
+		.await?;
+
+	drop(state_lock);
+	self.write_str(&format!("Sent {event_id} into {room_id}."))
+		.await
+}
+
+#[admin_command]
+pub(super) async fn trace_user(&self, user_id: OwnedUserId, seconds: u64) -> Result {
+	self.start_trace(user_id.to_string(), seconds)
+		.await
+}
+
+#[admin_command]
+pub(super) async fn trace_room(&self, room_id: OwnedRoomId, seconds: u64) -> Result {
+	self.start_trace(room_id.to_string(), seconds)
+		.await
+}
+
+/// Longest time window of a request trace.
+const TRACE_MAX_SECONDS: u64 = 60 * 60;
+
+#[implement(Context, params = "<'_>")]
+async fn start_trace(&self, target: String, seconds: u64) -> Result {
+	if seconds == 0 || seconds > TRACE_MAX_SECONDS {
+		return Err!("Trace time window must be between 1 and {TRACE_MAX_SECONDS} seconds.");
+	}
+
+	self.services
+		.admin
+		.start_trace(target.clone(), Duration::from_secs(seconds));
+
+	self.write_str(&format!(
+		"Tracing requests of {target} for {seconds} seconds. The logs will be sent to this room \
+		 when done."
+	))
+	.await
+}
+
 #[admin_command]
 #[tracing::instrument(skip(self))]
 pub(super) async fn force_set_room_state_from_server(
@@ -875,6 +1045,35 @@ pub(super) async fn runtime_interval(&self) -> Result {
 		.await
 }
 
+#[admin_command]
+pub(super) async fn response_metrics(&self) -> Result {
+	let metrics = &self.services.server.metrics;
+	let count = metrics
+		.federation_state_responses
+		.load(Ordering::Relaxed);
+	let total = metrics
+		.federation_state_response_bytes
+		.load(Ordering::Relaxed);
+	let max = metrics
+		.federation_state_response_bytes_max
+		.load(Ordering::Relaxed);
+	let average = total.checked_div(count).unwrap_or(0);
+
+	let size = |bytes: u64| utils::bytes::pretty(bytes.try_into().unwrap_or(usize::MAX));
+	self.write_str(&format!(
+		"```rs
+federation_state_responses: {count}
+total: {}
+average: {}
+max: {}
+```",
+		size(total),
+		size(average),
+		size(max),
+	))
+	.await
+}
+
 #[admin_command]
 pub(super) async fn time(&self) -> Result {
 	let now = SystemTime::now();
@@ -1,12 +1,13 @@
 mod commands;
+pub(crate) mod profile;
 pub(crate) mod tester;
 
 use clap::Subcommand;
-use ruma::{OwnedEventId, OwnedRoomId, OwnedRoomOrAliasId, OwnedServerName};
+use ruma::{OwnedEventId, OwnedRoomId, OwnedRoomOrAliasId, OwnedServerName, OwnedUserId};
 use tuwunel_core::Result;
 use tuwunel_service::rooms::short::{ShortEventId, ShortRoomId};
 
-use self::tester::TesterCommand;
+use self::{profile::ProfileCommand, tester::TesterCommand};
 use crate::admin_command_dispatch;
 
 #[admin_command_dispatch]
@@ -38,6 +39,16 @@ pub(super) enum DebugCommand {
 		event_id: OwnedEventId,
 	},
 
+	/// - Retrieve and print an outlier PDU by EventID from the tuwunel database
+	///
+	/// Outliers are events stored without being part of the timeline, such
+	/// as auth events fetched over federation and events which were rejected
+	/// or soft failed.
+	GetOutlier {
+		/// An event ID (a $ followed by the base64 reference hash)
+		event_id: OwnedEventId,
+	},
+
 	/// - Retrieve and print a PDU by PduId from the tuwunel database
 	GetShortPdu {
 		/// Shortroomid integer
@@ -158,6 +169,42 @@ pub(super) enum DebugCommand {
 		room_id: OwnedRoomId,
 	},
 
+	/// - Sends an event into the room as the server user
+	///
+	/// The event is built, authorized and appended like any other local event,
+	/// which is useful for repairing rooms, e.g. restoring power levels. This
+	/// command needs a JSON object with `type`, `content` and, for state
+	/// events, `state_key` provided in a Markdown code block below the
+	/// command.
+	SendEvent {
+		/// The room ID or alias
+		room_id: OwnedRoomOrAliasId,
+	},
+
+	/// - Collect verbose logs of requests made by or referencing a user
+	///
+	/// Logs of the requests handled during the time window are sent to the
+	/// admin room when it ends.
+	TraceUser {
+		user_id: OwnedUserId,
+
+		/// Length of the time window in seconds
+		#[arg(long, default_value_t = 60)]
+		seconds: u64,
+	},
+
+	/// - Collect verbose logs of requests referencing a room
+	///
+	/// Logs of the requests handled during the time window are sent to the
+	/// admin room when it ends.
+	TraceRoom {
+		room_id: OwnedRoomId,
+
+		/// Length of the time window in seconds
+		#[arg(long, default_value_t = 60)]
+		seconds: u64,
+	},
+
 	/// - Forcefully replaces the room state of our local copy of the specified
 	///   room, with the copy (auth chain and room state events) the specified
 	///   remote server says.
@@ -206,6 +253,9 @@ pub(super) enum DebugCommand {
 	///   invocation.
 	RuntimeInterval,
 
+	/// - Print the count and sizes of streamed federation state responses.
+	ResponseMetrics,
+
 	/// - Print the current time
 	Time,
 
@@ -234,6 +284,10 @@ pub(super) enum DebugCommand {
 		level: Option<i32>,
 	},
 
+	/// - Record CPU or heap profiles
+	#[command(subcommand)]
+	Profile(ProfileCommand),
+
 	/// - Developer test stubs
 	#[command(subcommand)]
 	#[allow(non_snake_case)]
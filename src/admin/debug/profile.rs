@@ -0,0 +1,237 @@
+use std::path::{Path, PathBuf};
+#[cfg(feature = "pprof")]
+use std::time::Duration;
+
+use clap::Subcommand;
+use ruma::Mxc;
+use tokio::fs;
+#[cfg(feature = "pprof")]
+use tokio::time::sleep;
+#[cfg(feature = "pprof")]
+use tuwunel_core::err;
+use tuwunel_core::{
+	Err, Result, utils,
+	utils::{content_disposition::make_content_disposition, time::now_millis},
+};
+use tuwunel_service::{globals, media, media::MXC_LENGTH};
+
+use crate::{admin_command, admin_command_dispatch};
+
+/// Longest CPU profile recorded by one command.
+#[cfg(feature = "pprof")]
+const MAX_CPU_SECONDS: u64 = 300;
+
+/// Samples taken of each thread per second while recording a CPU profile.
+#[cfg(feature = "pprof")]
+const CPU_FREQUENCY: i32 = 99;
+
+#[admin_command_dispatch]
+#[derive(Debug, Subcommand)]
+pub(crate) enum ProfileCommand {
+	/// - Record a CPU profile for a number of seconds.
+	///
+	/// Every thread is sampled and the output is a flamegraph in SVG. The
+	/// recording runs in the background; its result is posted to the admin
+	/// room when done. Requires the `pprof` feature.
+	Cpu {
+		/// Duration of the recording, up to 300 seconds.
+		seconds: u64,
+
+		/// Output file; defaults to the system temporary directory.
+		#[arg(short, long)]
+		path: Option<PathBuf>,
+
+		/// Also upload the profile to the media repository.
+		#[arg(short, long)]
+		upload: bool,
+	},
+
+	/// - Dump a jemalloc heap profile.
+	///
+	/// Sampling must be activated beforehand with `--activate`; the output is
+	/// readable by jeprof or pprof. Requires the `jemalloc_prof` feature.
+	Heap {
+		/// Output file; defaults to the system temporary directory.
+		#[arg(short, long)]
+		path: Option<PathBuf>,
+
+		/// Also upload the profile to the media repository.
+		#[arg(short, long)]
+		upload: bool,
+
+		/// Start sampling allocations instead of dumping.
+		#[arg(long, conflicts_with = "deactivate")]
+		activate: bool,
+
+		/// Stop sampling allocations instead of dumping.
+		#[arg(long)]
+		deactivate: bool,
+	},
+}
+
+#[admin_command]
+async fn cpu(&self, seconds: u64, path: Option<PathBuf>, upload: bool) -> Result {
+	#[cfg(feature = "pprof")]
+	{
+		// Fails while another profile is being recorded; the sampler is
+		// process-wide.
+		let guard = pprof::ProfilerGuardBuilder::default()
+			.frequency(CPU_FREQUENCY)
+			.blocklist(&["libc", "libgcc", "pthread", "vdso"])
+			.build()
+			.map_err(|e| err!("Failed to start CPU profiler: {e}"))?;
+
+		let seconds = seconds.clamp(1, MAX_CPU_SECONDS);
+		let path = output_path(path, "cpu", "svg");
+		let server = self.services.server.clone();
+		let admin = self.services.admin.clone();
+		let media = self.services.media.clone();
+		let globals = self.services.globals.clone();
+		self.services.server.runtime().spawn(async move {
+			tokio::select! {
+				() = sleep(Duration::from_secs(seconds)) => {},
+				() = server.until_shutdown() => {},
+			}
+
+			let result = match write_flamegraph(guard, path.clone()).await {
+				| Ok(()) => report(&media, &globals, &path, upload).await,
+				| Err(e) => Err(e),
+			};
+
+			let message = result.unwrap_or_else(|e| format!("Failed to record CPU profile: {e}"));
+
+			admin.notice(&message).await;
+		});
+
+		self.write_str(&format!(
+			"Recording CPU profile for {seconds} seconds; the result is posted here when done."
+		))
+		.await
+	}
+
+	#[cfg(not(feature = "pprof"))]
+	{
+		_ = (seconds, path, upload);
+		Err!(FeatureDisabled("pprof"))
+	}
+}
+
+#[admin_command]
+async fn heap(
+	&self,
+	path: Option<PathBuf>,
+	upload: bool,
+	activate: bool,
+	deactivate: bool,
+) -> Result {
+	#[cfg(all(
+		not(target_env = "msvc"),
+		feature = "jemalloc",
+		feature = "jemalloc_prof"
+	))]
+	{
+		use std::ffi::CString;
+
+		use tuwunel_core::{alloc::je, err};
+
+		if activate || deactivate {
+			je::prof_enable(activate)?;
+			let state = if activate { "activated" } else { "deactivated" };
+			return self
+				.write_str(&format!("Heap profiling {state}."))
+				.await;
+		}
+
+		if !je::is_prof_enabled()? {
+			return Err!("Heap profiling is not active; run with --activate first.");
+		}
+
+		let path = output_path(path, "heap", "heap");
+		let cpath = CString::new(path.as_os_str().as_encoded_bytes())
+			.map_err(|e| err!("Invalid profile path: {e}"))?;
+		je::prof_dump(&cpath)?;
+
+		let report = report(&self.services.media, &self.services.globals, &path, upload).await?;
+		self.write_str(&report).await
+	}
+
+	#[cfg(not(all(
+		not(target_env = "msvc"),
+		feature = "jemalloc",
+		feature = "jemalloc_prof"
+	)))]
+	{
+		_ = (path, upload, activate, deactivate);
+		Err!(FeatureDisabled("jemalloc_prof"))
+	}
+}
+
+#[cfg(feature = "pprof")]
+async fn write_flamegraph(guard: pprof::ProfilerGuard<'static>, path: PathBuf) -> Result {
+	tokio::task::spawn_blocking(move || {
+		let report = guard
+			.report()
+			.build()
+			.map_err(|e| err!("Failed to build CPU profile: {e}"))?;
+
+		drop(guard);
+		let file = std::fs::File::create(&path)?;
+		report
+			.flamegraph(file)
+			.map_err(|e| err!("Failed to write flamegraph: {e}"))
+	})
+	.await?
+}
+
+/// Describe the location of the profile, uploading it to the media repository
+/// on behalf of the server user when requested.
+#[cfg_attr(not(feature = "pprof"), allow(dead_code))]
+async fn report(
+	media: &media::Service,
+	globals: &globals::Service,
+	path: &Path,
+	upload: bool,
+) -> Result<String> {
+	const CONTENT_TYPE: &str = "application/octet-stream";
+
+	let size = fs::metadata(path).await?.len();
+	let written = format!("Profile written to `{}` ({size} bytes).", path.display());
+	if !upload {
+		return Ok(written);
+	}
+
+	let content = fs::read(path).await?;
+	let filename = path
+		.file_name()
+		.map(|name| name.to_string_lossy());
+
+	let content_disposition =
+		make_content_disposition(None, Some(CONTENT_TYPE), filename.as_deref());
+
+	let mxc = Mxc {
+		server_name: globals.server_name(),
+		media_id: &utils::random_string(MXC_LENGTH),
+	};
+
+	media
+		.create(
+			&mxc,
+			Some(&globals.server_user),
+			Some(&content_disposition),
+			Some(CONTENT_TYPE),
+			&content,
+		)
+		.await?;
+
+	Ok(format!("{written}\nUploaded as {mxc}"))
+}
+
+#[cfg_attr(not(feature = "pprof"), allow(dead_code))]
+fn output_path(path: Option<PathBuf>, kind: &str, ext: &str) -> PathBuf {
+	let name = format!("tuwunel-{kind}-{}.{ext}", now_millis());
+	match path {
+		| Some(path) if path.is_dir() => path.join(name),
+		| Some(path) => path,
+		| None => std::env::temp_dir().join(name),
+	}
+}
@@ -1,8 +1,16 @@
-use std::fmt::Write;
+use std::{
+	fmt::Write,
+	sync::atomic::{AtomicU64, Ordering},
+	time::Duration,
+};
 
 use futures::StreamExt;
-use ruma::{OwnedRoomId, OwnedServerName, OwnedUserId};
-use tuwunel_core::{Err, Result};
+use ruma::{OwnedRoomId, OwnedServerName, OwnedUserId, ServerName};
+use tuwunel_core::{
+	Err, Result, utils,
+	utils::time::{now_millis, pretty},
+};
+use tuwunel_service::federation::{FederationRule, ServerStats};
 
 use crate::{admin_command, get_room_info};
 
@@ -126,3 +134,218 @@ pub(super) async fn remote_user_in_rooms(&self, user_id: OwnedUserId) -> Result
 	self.write_str(&format!("Rooms {user_id} shares with us ({num}):\n```\n{body}\n```",))
 		.await
 }
+
+#[admin_command]
+pub(super) async fn allow(&self, server_name: OwnedServerName) -> Result {
+	self.set_federation_rule(&server_name, FederationRule::Allow)
+		.await
+}
+
+#[admin_command]
+pub(super) async fn block(&self, server_name: OwnedServerName) -> Result {
+	self.set_federation_rule(&server_name, FederationRule::Block)
+		.await
+}
+
+#[admin_command]
+async fn set_federation_rule(&self, server_name: &ServerName, rule: FederationRule) -> Result {
+	if server_name == self.services.server.name {
+		return Err!("Cannot set a federation rule for our own server.");
+	}
+
+	self.services
+		.federation
+		.set_rule(server_name, rule);
+
+	self.write_str(&format!("Federation with {server_name} is now {rule}ed."))
+		.await
+}
+
+#[admin_command]
+pub(super) async fn clear_rule(&self, server_name: OwnedServerName) -> Result {
+	let Some(rule) = self
+		.services
+		.federation
+		.get_rule(&server_name)
+		.await
+	else {
+		return Err!("No federation rule is set for {server_name}.");
+	};
+
+	self.services.federation.remove_rule(&server_name);
+	self.write_str(&format!("Removed {rule} rule for {server_name}."))
+		.await
+}
+
+#[admin_command]
+pub(super) async fn list_rules(&self) -> Result {
+	let rules: Vec<_> = self
+		.services
+		.federation
+		.rules()
+		.map(|(server_name, rule)| format!("- {rule} {server_name}"))
+		.collect()
+		.await;
+
+	let patterns = &self
+		.services
+		.server
+		.config
+		.forbidden_remote_server_names;
+
+	writeln!(self, "Runtime rules ({}):", rules.len()).await?;
+	for rule in &rules {
+		writeln!(self, "{rule}").await?;
+	}
+
+	writeln!(self, "\nforbidden_remote_server_names ({}):", patterns.len()).await?;
+	for pattern in patterns.patterns() {
+		writeln!(self, "- `{pattern}`").await?;
+	}
+
+	Ok(())
+}
+
+#[admin_command]
+pub(super) async fn acl_rejects(&self) -> Result {
+	let rejects = self.services.rooms.event_handler.acl_rejects();
+
+	if rejects.is_empty() {
+		return self
+			.write_str("No servers have been denied by room ACLs.")
+			.await;
+	}
+
+	writeln!(self, "Servers denied by room ACLs ({}):", rejects.len()).await?;
+	for (server_name, count) in &rejects {
+		writeln!(self, "- {server_name}: {count}").await?;
+	}
+
+	Ok(())
+}
+
+#[admin_command]
+pub(super) async fn stats(&self, server_name: Option<OwnedServerName>, top: usize) -> Result {
+	let size = |bytes: u64| utils::bytes::pretty(bytes.try_into().unwrap_or(usize::MAX));
+	let ago = |ts: Option<u64>| {
+		ts.map_or_else(
+			|| "never".to_owned(),
+			|ts| {
+				let elapsed = Duration::from_millis(now_millis().saturating_sub(ts));
+				format!("{} ago", pretty(elapsed))
+			},
+		)
+	};
+
+	let latency = |stats: &ServerStats| {
+		stats
+			.median_latency()
+			.map_or_else(|| "-".to_owned(), pretty)
+	};
+
+	if let Some(server_name) = server_name {
+		let Some(stats) = self
+			.services
+			.federation
+			.server_stats(&server_name)
+		else {
+			return Err!("No transactions have been exchanged with {server_name}.");
+		};
+
+		return self
+			.write_str(&format!(
+				"```
+server: {server_name}
+pdus_received: {}
+edus_received: {}
+bytes_received: {}
+pdus_rejected: {}
+last_received: {}
+pdus_sent: {}
+edus_sent: {}
+bytes_sent: {}
+send_failures: {}
+last_sent: {}
+median_latency: {}
+```",
+				stats.pdus_received,
+				stats.edus_received,
+				size(stats.bytes_received),
+				stats.pdus_rejected,
+				ago(stats.last_received),
+				stats.pdus_sent,
+				stats.edus_sent,
+				size(stats.bytes_sent),
+				stats.send_failures,
+				ago(stats.last_sent),
+				latency(&stats),
+			))
+			.await;
+	}
+
+	let mut servers = self.services.federation.all_stats();
+	let events = |stats: &ServerStats| {
+		stats
+			.pdus_received
+			.saturating_add(stats.edus_received)
+			.saturating_add(stats.pdus_sent)
+			.saturating_add(stats.edus_sent)
+	};
+
+	servers.sort_by_key(|(_, stats)| std::cmp::Reverse(events(stats)));
+
+	let metrics = &self.services.server.metrics;
+	let load = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+	writeln!(
+		self,
+		"Since startup: {} PDUs and {} EDUs ({}) received, {} PDUs and {} EDUs ({}) sent, {} \
+		 failed transactions.\n",
+		load(&metrics.federation_pdus_received),
+		load(&metrics.federation_edus_received),
+		size(load(&metrics.federation_bytes_received)),
+		load(&metrics.federation_pdus_sent),
+		load(&metrics.federation_edus_sent),
+		size(load(&metrics.federation_bytes_sent)),
+		load(&metrics.federation_send_failures),
+	)
+	.await?;
+
+	writeln!(
+		self,
+		"Servers by events exchanged ({} of {}):\n",
+		top.min(servers.len()),
+		servers.len()
+	)
+	.await?;
+	writeln!(
+		self,
+		"| Server | PDUs in | EDUs in | Bytes in | Rejected | PDUs out | EDUs out | Bytes out | \
+		 Failures | Median latency |"
+	)
+	.await?;
+	writeln!(
+		self,
+		"| ------ | ------- | ------- | -------- | -------- | -------- | -------- | --------- | \
+		 -------- | -------------- |"
+	)
+	.await?;
+
+	for (server_name, stats) in servers.iter().take(top) {
+		writeln!(
+			self,
+			"| {server_name} | {} | {} | {} | {} | {} | {} | {} | {} | {} |",
+			stats.pdus_received,
+			stats.edus_received,
+			size(stats.bytes_received),
+			stats.pdus_rejected,
+			stats.pdus_sent,
+			stats.edus_sent,
+			size(stats.bytes_sent),
+			stats.send_failures,
+			latency(stats),
+		)
+		.await?;
+	}
+
+	Ok(())
+}
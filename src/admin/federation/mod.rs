@@ -1,9 +1,11 @@
 mod commands;
+mod resolver;
 
 use clap::Subcommand;
 use ruma::{OwnedRoomId, OwnedServerName, OwnedUserId};
 use tuwunel_core::Result;
 
+use self::resolver::ResolverCacheCommand;
 use crate::admin_command_dispatch;
 
 #[admin_command_dispatch]
@@ -39,4 +41,44 @@ pub(super) enum FederationCommand {
 	RemoteUserInRooms {
 		user_id: OwnedUserId,
 	},
+
+	/// - Allow federation with a server, even if it matches
+	///   `forbidden_remote_server_names`
+	Allow {
+		server_name: OwnedServerName,
+	},
+
+	/// - Block incoming and outgoing federation with a server
+	Block {
+		server_name: OwnedServerName,
+	},
+
+	/// - Remove the allow or block rule set for a server
+	ClearRule {
+		server_name: OwnedServerName,
+	},
+
+	/// - List federation rules set with `allow` and `block`, and the configured
+	///   `forbidden_remote_server_names`
+	ListRules,
+
+	/// - List servers denied by room ACLs, with the number of PDUs and EDUs
+	///   rejected from each since startup
+	AclRejects,
+
+	/// - Show statistics of the transactions exchanged with remote servers
+	///
+	/// Without a server name, lists the servers which exchanged the most
+	/// events with this one.
+	Stats {
+		server_name: Option<OwnedServerName>,
+
+		/// Number of servers to list
+		#[arg(long, default_value_t = 20)]
+		top: usize,
+	},
+
+	/// - Inspect and manage cached server name resolutions
+	#[command(subcommand)]
+	ResolverCache(ResolverCacheCommand),
 }
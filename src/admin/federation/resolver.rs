@@ -0,0 +1,74 @@
+use clap::Subcommand;
+use ruma::OwnedServerName;
+use tuwunel_core::{Err, Result};
+
+use crate::{admin_command, admin_command_dispatch};
+
+#[admin_command_dispatch]
+#[derive(Debug, Subcommand)]
+pub(crate) enum ResolverCacheCommand {
+	/// - Expire the cached destination of a server, forcing it to be resolved
+	///   again on next use. Pinned destinations are removed as well.
+	Expire {
+		server_name: Option<OwnedServerName>,
+
+		/// Expire all cached destinations and hostname overrides
+		#[arg(long)]
+		all: bool,
+	},
+
+	/// - Pin the destination of a server, bypassing well-known and SRV
+	///   resolution until expired with `expire`.
+	Pin {
+		server_name: OwnedServerName,
+
+		/// IP address or hostname, with optional port (default 8448)
+		destination: String,
+
+		/// Host header to send; defaults to the destination
+		#[arg(long)]
+		host: Option<String>,
+	},
+}
+
+#[admin_command]
+async fn expire(&self, server_name: Option<OwnedServerName>, all: bool) -> Result {
+	let cache = &self.services.resolver.cache;
+	match (server_name, all) {
+		| (Some(_), true) | (None, false) => {
+			Err!("Specify either a server name or --all.")
+		},
+		| (None, true) => {
+			cache.clear().await;
+			self.write_str("Expired all cached destinations and overrides.")
+				.await
+		},
+		| (Some(server_name), false) => {
+			cache.del_destination(&server_name);
+			self.write_str(&format!("Expired cached destination for {server_name}."))
+				.await
+		},
+	}
+}
+
+#[admin_command]
+async fn pin(
+	&self,
+	server_name: OwnedServerName,
+	destination: String,
+	host: Option<String>,
+) -> Result {
+	self.services
+		.resolver
+		.pin_destination(&server_name, &destination, host.as_deref());
+
+	let cached = self
+		.services
+		.resolver
+		.cache
+		.get_destination(&server_name)
+		.await?;
+
+	self.write_str(&format!("Pinned {server_name} to {} with Host {}.", cached.dest, cached.host))
+		.await
+}
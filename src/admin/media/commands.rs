@@ -1,13 +1,14 @@
-use std::time::Duration;
+use std::{fmt::Write, time::Duration};
 
 use ruma::{Mxc, OwnedEventId, OwnedMxcUri, OwnedServerName};
 use tuwunel_core::{
-	Err, Result, debug, debug_info, debug_warn, error, info, trace,
-	utils::time::parse_timepoint_ago, warn,
+	Err, Result, debug, debug_info, debug_warn, error, implement, info, trace,
+	utils::{self, time::parse_timepoint_ago},
+	warn,
 };
 use tuwunel_service::media::Dim;
 
-use crate::{admin_command, utils::parse_local_user_id};
+use crate::{Context, admin_command, utils::parse_local_user_id};
 
 #[admin_command]
 pub(super) async fn delete(
@@ -174,8 +175,8 @@ pub(super) async fn delete(
 	}
 
 	Err!(
-		"Please specify either an MXC using --mxc or an event ID using --event-id of the \
-		 message containing an image. See --help for details."
+		"Please specify either an MXC URL or an event ID using --event-id of the message \
+		 containing an image. See --help for details."
 	)
 }
 
@@ -331,6 +332,98 @@ pub(super) async fn delete_all_from_server(
 		.await
 }
 
+#[admin_command]
+pub(super) async fn list_user(&self, username: String, top: Option<usize>) -> Result {
+	let user_id = parse_local_user_id(self.services, &username)?;
+	let mut media = self.services.media.get_user_media(&user_id).await;
+
+	if media.is_empty() {
+		return self
+			.write_str(&format!("{user_id} has not uploaded any media."))
+			.await;
+	}
+
+	let count = media.len();
+	let total: u64 = media.iter().map(|file| file.size).sum();
+	if let Some(top) = top {
+		media.sort_by(|a, b| b.size.cmp(&a.size));
+		media.truncate(top);
+	} else {
+		media.sort_by(|a, b| b.uploaded.cmp(&a.uploaded));
+	}
+
+	let size = |bytes: u64| utils::bytes::pretty(bytes.try_into().unwrap_or(usize::MAX));
+	let mut out = format!("{user_id} uploaded {count} files totalling {}:\n\n", size(total));
+	writeln!(out, "| MXC | Size | Content type | Uploaded |")?;
+	writeln!(out, "| --- | ---- | ------------ | -------- |")?;
+	for file in media {
+		let content_type = file.content_type.unwrap_or_default();
+		let uploaded = file
+			.uploaded
+			.map(|ts| utils::time::format(ts, "%+"))
+			.unwrap_or_else(|| "unknown".to_owned());
+
+		writeln!(out, "| {} | {} | {content_type} | {uploaded} |", file.mxc, size(file.size))?;
+	}
+
+	self.write_str(&out).await
+}
+
+#[admin_command]
+pub(super) async fn list_server(&self, server_name: OwnedServerName) -> Result {
+	let mut mxcs: Vec<_> = self
+		.services
+		.media
+		.get_all_mxcs()
+		.await?
+		.into_iter()
+		.filter(|mxc| {
+			mxc.server_name()
+				.is_ok_and(|name| name == server_name)
+		})
+		.collect();
+
+	// thumbnails are stored under the same MXC as their original
+	mxcs.dedup();
+
+	self.write_mxc_list(&mxcs).await
+}
+
+#[admin_command]
+pub(super) async fn quarantine(&self, mxc: OwnedMxcUri) -> Result {
+	let mxc: Mxc<'_> = mxc.as_str().try_into()?;
+	if self.services.media.is_quarantined(&mxc).await {
+		return Err!("{mxc} is already quarantined.");
+	}
+
+	self.services.media.quarantine(&mxc);
+	self.write_str(&format!("Quarantined {mxc}."))
+		.await
+}
+
+#[admin_command]
+pub(super) async fn unquarantine(&self, mxc: OwnedMxcUri) -> Result {
+	let mxc: Mxc<'_> = mxc.as_str().try_into()?;
+	if !self.services.media.is_quarantined(&mxc).await {
+		return Err!("{mxc} is not quarantined.");
+	}
+
+	self.services.media.unquarantine(&mxc);
+	self.write_str(&format!("Released {mxc} from quarantine."))
+		.await
+}
+
+#[implement(Context, params = "<'_>")]
+async fn write_mxc_list(&self, mxcs: &[OwnedMxcUri]) -> Result {
+	let list = mxcs.iter().fold(String::new(), |mut list, mxc| {
+		writeln!(list, "{mxc}").expect("list written");
+		list
+	});
+
+	self.write_str(&format!("Found {} media files:\n```\n{list}```", mxcs.len()))
+		.await
+}
+
 #[admin_command]
 pub(super) async fn get_file_info(&self, mxc: OwnedMxcUri) -> Result {
 	let mxc: Mxc<'_> = mxc.as_str().try_into()?;
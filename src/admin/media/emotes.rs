@@ -0,0 +1,351 @@
+//! Server-wide image packs (`im.ponies.room_emotes`) kept in the emote room;
+//! see `emote_room`.
+
+use std::fmt::Write;
+
+use clap::Subcommand;
+use futures::StreamExt;
+use ruma::{Mxc, OwnedMxcUri, OwnedRoomId, events::StateEventType};
+use serde_json::{Map, Value as JsonValue, json};
+use tuwunel_core::{Err, Result, err, matrix::pdu::PduBuilder, utils::random_string};
+use tuwunel_service::media::MXC_LENGTH;
+
+use crate::Context;
+
+const IMAGE_PACK_EVENT_TYPE: &str = "im.ponies.room_emotes";
+
+#[derive(Debug, Subcommand)]
+pub(crate) enum MediaEmotesCommand {
+	/// - List the image packs in the emote room, or the images in one pack
+	List {
+		pack: Option<String>,
+	},
+
+	/// - Add an image to a pack, creating the pack if needed. The source is an
+	///   MXC URL, or an http(s) URL to download the image from and upload
+	Add {
+		pack: String,
+
+		/// Shortcode without colons, e.g. `party_parrot`
+		shortcode: String,
+
+		source: String,
+
+		/// Description of the image; defaults to the shortcode
+		#[arg(long)]
+		body: Option<String>,
+
+		/// Only offer the image as a sticker, or only as an emoticon
+		#[arg(long, value_parser = ["sticker", "emoticon"])]
+		usage: Option<String>,
+	},
+
+	/// - Remove an image from a pack
+	Remove {
+		pack: String,
+		shortcode: String,
+	},
+
+	/// - Set the display name of a pack
+	Rename {
+		pack: String,
+		display_name: String,
+	},
+
+	/// - Delete a pack and all its images from the emote room
+	DeletePack {
+		pack: String,
+	},
+}
+
+pub(super) async fn process(command: MediaEmotesCommand, context: &Context<'_>) -> Result {
+	let room_id = emote_room(context).await?;
+	match command {
+		| MediaEmotesCommand::List { pack: None } => {
+			let packs = list_packs(context, &room_id).await?;
+			if packs.is_empty() {
+				return context.write_str("No image packs.").await;
+			}
+
+			let mut body = String::new();
+			for (pack, content) in &packs {
+				let images = content
+					.get("images")
+					.and_then(JsonValue::as_object)
+					.map_or(0, Map::len);
+
+				let name = content
+					.get("pack")
+					.and_then(|pack| pack.get("display_name"))
+					.and_then(JsonValue::as_str)
+					.unwrap_or(pack);
+
+				writeln!(body, "{pack} | {name} | {images} images")?;
+			}
+
+			context
+				.write_str(&format!(
+					"Image packs in {room_id} ({}):\n```\n{body}```",
+					packs.len()
+				))
+				.await
+		},
+		| MediaEmotesCommand::List { pack: Some(pack) } => {
+			let content = get_pack(context, &room_id, &pack)
+				.await
+				.map_err(|_| err!("No image pack {pack:?} in {room_id}."))?;
+
+			let mut body = String::new();
+			for (shortcode, image) in images(&content) {
+				let url = image
+					.get("url")
+					.and_then(JsonValue::as_str)
+					.unwrap_or_default();
+
+				writeln!(body, ":{shortcode}: | {url}")?;
+			}
+
+			context
+				.write_str(&format!("Images in {pack}:\n```\n{body}```"))
+				.await
+		},
+		| MediaEmotesCommand::Add { pack, shortcode, source, body, usage } => {
+			let shortcode = shortcode.trim_matches(':');
+			if shortcode.is_empty() || shortcode.contains(char::is_whitespace) {
+				return Err!("Shortcodes must be non-empty and contain no whitespace.");
+			}
+
+			let url = match OwnedMxcUri::from(source.as_str()) {
+				| mxc if mxc.is_valid() => mxc,
+				| _ => upload(context, &source).await?,
+			};
+
+			let mut image = json!({
+				"url": url,
+				"body": body.as_deref().unwrap_or(shortcode),
+			});
+
+			if let Some(usage) = usage {
+				image["usage"] = json!([usage]);
+			}
+
+			let mut content = get_pack(context, &room_id, &pack)
+				.await
+				.unwrap_or_else(|_| json!({ "pack": { "display_name": pack } }));
+
+			object_mut(&mut content, "images")?.insert(shortcode.to_owned(), image);
+			send_pack(context, &room_id, &pack, content).await?;
+
+			context
+				.write_str(&format!("Added :{shortcode}: to {pack} as {url}."))
+				.await
+		},
+		| MediaEmotesCommand::Remove { pack, shortcode } => {
+			let shortcode = shortcode.trim_matches(':');
+			let mut content = get_pack(context, &room_id, &pack)
+				.await
+				.map_err(|_| err!("No image pack {pack:?} in {room_id}."))?;
+
+			let removed = content
+				.get_mut("images")
+				.and_then(JsonValue::as_object_mut)
+				.and_then(|images| images.remove(shortcode));
+
+			if removed.is_none() {
+				return Err!("No image :{shortcode}: in {pack}.");
+			}
+
+			send_pack(context, &room_id, &pack, content).await?;
+
+			context
+				.write_str(&format!("Removed :{shortcode}: from {pack}."))
+				.await
+		},
+		| MediaEmotesCommand::Rename { pack, display_name } => {
+			let mut content = get_pack(context, &room_id, &pack)
+				.await
+				.map_err(|_| err!("No image pack {pack:?} in {room_id}."))?;
+
+			object_mut(&mut content, "pack")?
+				.insert("display_name".to_owned(), display_name.clone().into());
+			send_pack(context, &room_id, &pack, content).await?;
+
+			context
+				.write_str(&format!("Renamed {pack} to {display_name:?}."))
+				.await
+		},
+		| MediaEmotesCommand::DeletePack { pack } => {
+			get_pack(context, &room_id, &pack)
+				.await
+				.map_err(|_| err!("No image pack {pack:?} in {room_id}."))?;
+
+			send_pack(context, &room_id, &pack, json!({})).await?;
+
+			context
+				.write_str(&format!("Deleted image pack {pack}."))
+				.await
+		},
+	}
+}
+
+async fn emote_room(context: &Context<'_>) -> Result<OwnedRoomId> {
+	let services = context.services;
+	match &services.server.config.emote_room {
+		| Some(room) => services.rooms.alias.resolve(room).await,
+		| None => services.admin.get_admin_room().await,
+	}
+}
+
+/// Packs in the room with their content; deleted packs are left out.
+async fn list_packs(
+	context: &Context<'_>,
+	room_id: &OwnedRoomId,
+) -> Result<Vec<(String, JsonValue)>> {
+	let services = context.services;
+	let event_type = StateEventType::from(IMAGE_PACK_EVENT_TYPE);
+	let shortstatehash = services
+		.rooms
+		.state
+		.get_room_shortstatehash(room_id)
+		.await?;
+
+	let pack_names: Vec<_> = services
+		.rooms
+		.state_accessor
+		.state_keys(shortstatehash, &event_type)
+		.collect()
+		.await;
+
+	let mut packs = Vec::with_capacity(pack_names.len());
+	for pack in pack_names {
+		if let Ok(content) = get_pack(context, room_id, &pack).await {
+			packs.push((pack.to_string(), content));
+		}
+	}
+
+	Ok(packs)
+}
+
+async fn get_pack(context: &Context<'_>, room_id: &OwnedRoomId, pack: &str) -> Result<JsonValue> {
+	let content: JsonValue = context
+		.services
+		.rooms
+		.state_accessor
+		.room_state_get_content(room_id, &IMAGE_PACK_EVENT_TYPE.into(), pack)
+		.await?;
+
+	if content.as_object().is_none_or(Map::is_empty) {
+		return Err!(Request(NotFound("Image pack was deleted.")));
+	}
+
+	Ok(content)
+}
+
+fn images(content: &JsonValue) -> impl Iterator<Item = (&String, &JsonValue)> {
+	content
+		.get("images")
+		.and_then(JsonValue::as_object)
+		.into_iter()
+		.flatten()
+}
+
+/// The object under `key` in the content of a pack, created when missing.
+fn object_mut<'a>(
+	content: &'a mut JsonValue,
+	key: &str,
+) -> Result<&'a mut Map<String, JsonValue>> {
+	let value = content
+		.as_object_mut()
+		.ok_or_else(|| err!("Image pack content is not an object."))?
+		.entry(key)
+		.or_insert(JsonValue::Null);
+
+	if value.is_null() {
+		*value = json!({});
+	}
+
+	value
+		.as_object_mut()
+		.ok_or_else(|| err!("Image pack {key:?} is not an object."))
+}
+
+async fn send_pack(
+	context: &Context<'_>,
+	room_id: &OwnedRoomId,
+	pack: &str,
+	content: JsonValue,
+) -> Result {
+	let services = context.services;
+	let pdu_builder = PduBuilder {
+		event_type: IMAGE_PACK_EVENT_TYPE.into(),
+		content: serde_json::value::to_raw_value(&content)?,
+		state_key: Some(pack.into()),
+		..Default::default()
+	};
+
+	let server_user = &services.globals.server_user;
+	let state_lock = services.rooms.state.mutex.lock(room_id).await;
+	services
+		.rooms
+		.state_accessor
+		.ensure_power_to_send(&pdu_builder, server_user, room_id)
+		.await?;
+
+	services
+		.rooms
+		.timeline
+		.build_and_append_pdu(pdu_builder, server_user, room_id, &state_lock)
+		.await?;
+
+	Ok(())
+}
+
+/// Download an image and store it as local media owned by the server user.
+async fn upload(context: &Context<'_>, url: &str) -> Result<OwnedMxcUri> {
+	let services = context.services;
+	let mut response = services.client.default.get(url).send().await?;
+	let content_type = response
+		.headers()
+		.get("content-type")
+		.and_then(|content_type| content_type.to_str().ok())
+		.map(ToOwned::to_owned);
+
+	if !content_type
+		.as_deref()
+		.is_some_and(|content_type| content_type.starts_with("image/"))
+	{
+		return Err!("{url} is not an image.");
+	}
+
+	let max_size = services.server.config.max_request_size;
+	let too_large = || err!("Image is larger than the maximum upload size of {max_size} bytes.");
+	if response
+		.content_length()
+		.is_some_and(|len| len > u64::try_from(max_size).unwrap_or(u64::MAX))
+	{
+		return Err(too_large());
+	}
+
+	// The length may be missing or wrong, so it is checked again while reading.
+	let mut image = Vec::new();
+	while let Some(chunk) = response.chunk().await? {
+		if image.len().saturating_add(chunk.len()) > max_size {
+			return Err(too_large());
+		}
+
+		image.extend_from_slice(&chunk);
+	}
+
+	let media_id = random_string(MXC_LENGTH);
+	let mxc = Mxc {
+		server_name: services.globals.server_name(),
+		media_id: &media_id,
+	};
+
+	services
+		.media
+		.create(&mxc, Some(&services.globals.server_user), None, content_type.as_deref(), &image)
+		.await?;
+
+	Ok(mxc.to_string().into())
+}
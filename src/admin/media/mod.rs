@@ -1,10 +1,12 @@
 #![allow(rustdoc::broken_intra_doc_links)]
 mod commands;
+mod emotes;
 
 use clap::Subcommand;
 use ruma::{OwnedEventId, OwnedMxcUri, OwnedServerName};
 use tuwunel_core::Result;
 
+use self::emotes::MediaEmotesCommand;
 use crate::admin_command_dispatch;
 
 #[admin_command_dispatch]
@@ -14,7 +16,6 @@ pub(super) enum MediaCommand {
 	///   via a single MXC URL or event ID (not redacted)
 	Delete {
 		/// The MXC URL to delete
-		#[arg(long)]
 		mxc: Option<OwnedMxcUri>,
 
 		/// - The message event ID which contains the media and thumbnail MXC
@@ -64,6 +65,36 @@ pub(super) enum MediaCommand {
 		yes_i_want_to_delete_local_media: bool,
 	},
 
+	/// - Lists the media uploaded by a local user with their sizes, content
+	///   types and upload times
+	///
+	/// Uploads are listed newest first, or largest first with `--top`.
+	ListUser {
+		username: String,
+
+		/// Only list this many of the largest uploads
+		#[arg(long)]
+		top: Option<usize>,
+	},
+
+	/// - Lists the media stored from a server
+	ListServer {
+		server_name: OwnedServerName,
+	},
+
+	/// - Quarantines a media file so it is no longer served to clients or
+	///   federation nor fetched again, while keeping it stored
+	Quarantine {
+		/// The MXC URL to quarantine
+		mxc: OwnedMxcUri,
+	},
+
+	/// - Releases a media file from quarantine so it is served again
+	Unquarantine {
+		/// The MXC URL to release
+		mxc: OwnedMxcUri,
+	},
+
 	GetFileInfo {
 		/// The MXC URL to lookup info for.
 		mxc: OwnedMxcUri,
@@ -96,4 +127,8 @@ pub(super) enum MediaCommand {
 		#[arg(short, long, default_value("800"))]
 		height: u32,
 	},
+
+	#[command(subcommand)]
+	/// - Manage the server-wide custom emoji and sticker packs
+	Emotes(MediaEmotesCommand),
 }
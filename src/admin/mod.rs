@@ -11,6 +11,7 @@
 
 pub(crate) mod appservice;
 pub(crate) mod check;
+pub(crate) mod db;
 pub(crate) mod debug;
 pub(crate) mod federation;
 pub(crate) mod media;
@@ -30,7 +30,7 @@
 };
 use tuwunel_service::{
 	Services,
-	admin::{CommandInput, CommandOutput, ProcessorFuture, ProcessorResult},
+	admin::{CommandInput, CommandOutput, ProcessorFuture, ProcessorResult, Progress},
 };
 
 use crate::{admin, admin::AdminCommand, context::Context};
@@ -64,6 +64,7 @@ async fn process_command(services: Arc<Services>, input: &CommandInput) -> Proce
 		timer: SystemTime::now(),
 		reply_id: input.reply_id.as_deref(),
 		output: BufWriter::new(Vec::new()).into(),
+		progress: Progress::new(input.reply_id.clone()),
 	};
 
 	let (result, mut logs) = process(&context, command, &args).await;
@@ -202,49 +203,91 @@ fn parse_command(line: &str) -> Result<(AdminCommand, Vec<String>)> {
 	Ok((command, argv))
 }
 
+/// Complete the subcommands and long options of each token, matching by prefix
+/// or else by subsequence, e.g. `rstpw` for `reset-password`.
 fn complete_command(mut cmd: clap::Command, line: &str) -> String {
 	let argv = parse_line(line);
 	let mut ret = Vec::<String>::with_capacity(argv.len().saturating_add(1));
+	let mut complete = true;
 
-	'token: for token in argv.into_iter().skip(1) {
-		let cmd_ = cmd.clone();
-		let mut choice = Vec::new();
-
-		for sub in cmd_.get_subcommands() {
-			let name = sub.get_name();
-			if *name == token {
-				// token already complete; recurse to subcommand
-				ret.push(token);
-				cmd.clone_from(sub);
-				continue 'token;
-			} else if name.starts_with(&token) {
-				// partial match; add to choices
-				choice.push(name);
-			}
+	for token in argv.into_iter().skip(1) {
+		complete = true;
+		if let Some(sub) = cmd.find_subcommand(&token) {
+			// token already complete; recurse to subcommand
+			let sub = sub.clone();
+			ret.push(token);
+			cmd = sub;
+			continue;
 		}
 
-		if choice.len() == 1 {
-			// One choice. Add extra space because it's complete
-			let choice = *choice.first().expect("only choice");
-			ret.push(choice.to_owned());
-			ret.push(String::new());
-		} else if choice.is_empty() {
-			// Nothing found, return original string
-			ret.push(token);
+		let names: Vec<String> = if token.starts_with('-') {
+			cmd.get_arguments()
+				.filter_map(clap::Arg::get_long)
+				.map(|long| format!("--{long}"))
+				.collect()
 		} else {
-			// Find the common prefix
-			ret.push(common_prefix(&choice).into());
+			cmd.get_subcommands()
+				.map(|sub| sub.get_name().to_owned())
+				.collect()
+		};
+
+		let mut choice: Vec<&str> = names
+			.iter()
+			.map(String::as_str)
+			.filter(|name| name.starts_with(&token))
+			.collect();
+
+		if choice.is_empty() {
+			choice = names
+				.iter()
+				.map(String::as_str)
+				.filter(|name| is_subsequence(&token, name))
+				.collect();
+		}
+
+		match choice.as_slice() {
+			| [] => {
+				// Nothing found, keep the original token; it may be a value
+				complete = false;
+				ret.push(token);
+			},
+			| [choice] => {
+				// One choice; recurse when it's a subcommand
+				if let Some(sub) = cmd.find_subcommand(choice) {
+					cmd = sub.clone();
+				}
+
+				ret.push((*choice).to_owned());
+			},
+			| choices => {
+				// Find the common prefix, unless it would drop some of the token
+				let prefix = common_prefix(choices);
+				ret.push(if prefix.len() > token.len() {
+					prefix.into()
+				} else {
+					token
+				});
+
+				// Return from completion
+				return ret.join(" ");
+			},
 		}
+	}
 
-		// Return from completion
-		return ret.join(" ");
+	// The last token was completed. Needs a space though.
+	if complete {
+		ret.push(String::new());
 	}
 
-	// Return from no completion. Needs a space though.
-	ret.push(String::new());
 	ret.join(" ")
 }
 
+/// Whether the characters of `token` appear in order within `name`.
+fn is_subsequence(token: &str, name: &str) -> bool {
+	let mut name = name.chars();
+	token.chars().all(|c| name.any(|n| n == c))
+}
+
 /// Parse chat messages from the admin room into an AdminCommand object
 fn parse_line(command_line: &str) -> Vec<String> {
 	let mut argv = command_line
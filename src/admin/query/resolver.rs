@@ -34,14 +34,22 @@ async fn destinations_cache(&self, server_name: Option<OwnedServerName>) -> Resu
 		.destinations()
 		.boxed();
 
-	while let Some((name, CachedDest { dest, host, expire })) = destinations.next().await {
+	while let Some((name, cached)) = destinations.next().await {
 		if let Some(server_name) = server_name.as_ref() {
 			if name != server_name {
 				continue;
 			}
 		}
 
-		let expire = time::format(expire, "%+");
+		let CachedDest { dest, host, expire, pinned } = &cached;
+		let expire = if *pinned {
+			"pinned".to_owned()
+		} else if !cached.valid() {
+			"expired".to_owned()
+		} else {
+			time::format(*expire, "%+")
+		};
+
 		self.write_str(&format!("| {name} | {dest} | {host} | {expire} |\n"))
 			.await?;
 	}
@@ -1,7 +1,9 @@
+use std::fmt::Write;
+
 use clap::Subcommand;
 use futures::StreamExt;
 use ruma::{OwnedServerName, OwnedUserId};
-use tuwunel_core::{Err, Result};
+use tuwunel_core::{Err, Result, utils::time::pretty};
 use tuwunel_service::sending::Destination;
 
 use crate::Context;
@@ -59,6 +61,12 @@ pub(crate) enum SendingCommand {
 	GetLatestEduCount {
 		server_name: OwnedServerName,
 	},
+
+	/// - Shows the destinations catching up after downtime, with how many rooms
+	///   have been covered and how many events remain queued
+	CatchUp {
+		server_name: Option<OwnedServerName>,
+	},
 }
 
 /// All the getters and iterators in key_value/sending.rs
@@ -236,5 +244,48 @@ pub(super) async fn process(subcommand: SendingCommand, context: &Context<'_>) -
 				))
 				.await
 		},
+		| SendingCommand::CatchUp { server_name } => {
+			let mut status = services.sending.catchup_status();
+			status.retain(|(server, _)| {
+				server_name
+					.as_ref()
+					.is_none_or(|server_name| server == server_name)
+			});
+
+			if status.is_empty() {
+				return context
+					.write_str("No destinations are catching up.")
+					.await;
+			}
+
+			status.sort_by(|(a, _), (b, _)| a.cmp(b));
+			let mut out = String::from(
+				"| Destination | Since | Rooms sent | Transactions | Queued |\n| --- | --- | \
+				 --- | --- | --- |\n",
+			);
+
+			for (server, catchup) in status {
+				let queued = services
+					.sending
+					.db
+					.queued_requests(&Destination::Federation(server.clone()))
+					.count()
+					.await;
+
+				let since = catchup
+					.since
+					.elapsed()
+					.map(|elapsed| format!("{} ago", pretty(elapsed)))
+					.unwrap_or_default();
+
+				writeln!(
+					out,
+					"| {server} | {since} | {} | {} | {queued} |",
+					catchup.rooms, catchup.transactions,
+				)?;
+			}
+
+			context.write_str(&out).await
+		},
 	}
 }
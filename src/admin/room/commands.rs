@@ -1,6 +1,35 @@
-use futures::StreamExt;
-use ruma::OwnedRoomId;
-use tuwunel_core::{Err, Result};
+use std::{borrow::Borrow, collections::HashMap, fmt::Write, path::PathBuf, sync::Arc};
+
+use futures::{FutureExt, StreamExt};
+use ruma::{
+	CanonicalJsonObject, CanonicalJsonValue, OwnedEventId, OwnedRoomId, OwnedUserId,
+	RoomVersionId,
+	api::client::room::{Visibility, create_room},
+	events::room::redaction::RoomRedactionEventContent,
+	serde::Raw,
+};
+use serde_json::{
+	Value as JsonValue, json,
+	value::{RawValue as RawJsonValue, to_raw_value},
+};
+use tokio::fs;
+use tuwunel_api::client::{ROOM_TEMPLATE_KEY, create_room_helper};
+use tuwunel_core::{
+	Err, Result, at, debug_warn, err,
+	matrix::{Event, pdu::PduEvent},
+	utils::{
+		ReadyExt,
+		bytes::pretty,
+		future::TryExtExt,
+		stream::{IterStream, WidebandExt},
+		time,
+		time::parse_timepoint_ago,
+	},
+};
+use tuwunel_service::rooms::{
+	state_compressor::{CompressedState, HashSetCompressStateEvent},
+	usage::Usage,
+};
 
 use crate::{PAGE_SIZE, admin_command, get_room_info};
 
@@ -83,3 +112,464 @@ pub(super) async fn exists(&self, room_id: OwnedRoomId) -> Result {
 
 	self.write_str(&format!("{result}")).await
 }
+
+#[admin_command]
+pub(super) async fn create_from_template(
+	&self,
+	template: String,
+	name: Option<String>,
+	alias: Option<String>,
+	invite: Vec<OwnedUserId>,
+	public: bool,
+) -> Result {
+	let creation_content = json!({ ROOM_TEMPLATE_KEY: &template });
+
+	let mut request = create_room::v3::Request::new();
+	request.creation_content = Some(Raw::from_json(to_raw_value(&creation_content)?));
+	request.name = name;
+	request.room_alias_name = alias;
+	request.invite = invite;
+	if public {
+		request.visibility = Visibility::Public;
+	}
+
+	let room_id =
+		create_room_helper(self.services, &self.services.globals.server_user, &request, None)
+			.boxed()
+			.await?;
+
+	self.write_str(&format!("Created room {room_id} from template {template:?}."))
+		.await
+}
+
+#[admin_command]
+pub(super) async fn usage(&self, room_id: Option<OwnedRoomId>, top: usize) -> Result {
+	let usage = &self.services.rooms.usage;
+	if let Some(room_id) = room_id {
+		let usage = usage.usage(&room_id).await?;
+		return self
+			.write_str(&format!("```\n{}\n```", format_usage(&room_id, &usage)))
+			.await;
+	}
+
+	let mut rooms: Vec<_> = self
+		.services
+		.rooms
+		.metadata
+		.iter_ids()
+		.filter_map(|room_id| async move {
+			let usage = usage.usage(room_id).await.ok()?;
+			Some((room_id.to_owned(), usage))
+		})
+		.collect()
+		.await;
+
+	rooms.sort_by_key(|(_, usage)| std::cmp::Reverse(usage.pdu_bytes));
+
+	let mut out = String::new();
+	for (room_id, usage) in rooms.iter().take(top) {
+		writeln!(out, "{}", format_usage(room_id, usage))?;
+	}
+
+	self.write_str(&format!(
+		"Rooms by storage ({} of {}):\n```\n{out}```",
+		top.min(rooms.len()),
+		rooms.len()
+	))
+	.await
+}
+
+#[admin_command]
+pub(super) async fn orphans(&self, purge: bool) -> Result {
+	let services = self.services;
+	let orphans: Vec<OwnedRoomId> = services
+		.rooms
+		.metadata
+		.iter_ids()
+		.filter_map(|room_id| async move {
+			let orphaned = services.rooms.metadata.exists(room_id).await
+				&& !services
+					.rooms
+					.state_cache
+					.has_local_members(room_id)
+					.await;
+
+			orphaned.then(|| room_id.to_owned())
+		})
+		.collect()
+		.await;
+
+	if orphans.is_empty() {
+		return self.write_str("No orphaned rooms found.").await;
+	}
+
+	let mut out = String::new();
+	let mut total = 0_u64;
+	for room_id in &orphans {
+		let usage = services.rooms.usage.usage(room_id).await?;
+		total = total.saturating_add(usage.pdu_bytes);
+		writeln!(out, "{}", format_usage(room_id, &usage))?;
+	}
+
+	let total = pretty(total.try_into().unwrap_or(usize::MAX));
+	self.write_str(&format!(
+		"Rooms without local members ({}, {total}):\n```\n{out}```",
+		orphans.len()
+	))
+	.await?;
+
+	if !purge {
+		return Ok(());
+	}
+
+	let mut purged = 0_usize;
+	for room_id in &orphans {
+		match services.rooms.timeline.purge_room(room_id).await {
+			| Ok(_) => purged = purged.saturating_add(1),
+			| Err(e) => {
+				self.write_str(&format!("Failed to purge {room_id}: {e}\n"))
+					.await?;
+			},
+		}
+	}
+
+	self.write_str(&format!("Purged {purged} of {} orphaned rooms.", orphans.len()))
+		.await
+}
+
+#[admin_command]
+pub(super) async fn export_state(
+	&self,
+	room_id: OwnedRoomId,
+	path: PathBuf,
+	full: bool,
+) -> Result {
+	let services = self.services;
+	let room_version = services
+		.rooms
+		.state
+		.get_room_version(&room_id)
+		.await
+		.map_err(|_| err!("We don't know about the room {room_id}."))?;
+
+	let shortstatehash = services
+		.rooms
+		.state
+		.get_room_shortstatehash(&room_id)
+		.await
+		.map_err(|_| err!("No state found for the room {room_id}."))?;
+
+	let state_ids: Vec<OwnedEventId> = services
+		.rooms
+		.state_accessor
+		.state_full_ids(shortstatehash)
+		.map(at!(1))
+		.collect()
+		.await;
+
+	let pdus: Vec<_> = state_ids
+		.iter()
+		.stream()
+		.wide_filter_map(|id| services.rooms.timeline.get_pdu_json(id).ok())
+		.wide_then(|pdu| {
+			services
+				.sending
+				.convert_to_outgoing_federation_event(pdu)
+		})
+		.collect()
+		.await;
+
+	let auth_chain: Vec<_> = services
+		.rooms
+		.auth_chain
+		.event_ids_iter(&room_id, state_ids.iter().map(Borrow::borrow))
+		.ready_filter_map(Result::ok)
+		.wide_filter_map(|id| async move {
+			services
+				.rooms
+				.timeline
+				.get_pdu_json(&id)
+				.await
+				.ok()
+		})
+		.wide_then(|pdu| {
+			services
+				.sending
+				.convert_to_outgoing_federation_event(pdu)
+		})
+		.collect()
+		.await;
+
+	let counts = format!("{} state events, {} auth chain events", pdus.len(), auth_chain.len());
+	let mut export = json!({
+		"room_id": room_id,
+		"room_version": room_version,
+		"pdus": pdus,
+		"auth_chain": auth_chain,
+	});
+
+	if full {
+		let events: Vec<_> = services
+			.rooms
+			.timeline
+			.pdus(None, &room_id, None)
+			.ready_filter_map(Result::ok)
+			.wide_filter_map(|(_, pdu)| async move {
+				services
+					.rooms
+					.timeline
+					.get_pdu_json(&pdu.event_id)
+					.await
+					.ok()
+			})
+			.wide_then(|pdu| {
+				services
+					.sending
+					.convert_to_outgoing_federation_event(pdu)
+			})
+			.collect()
+			.await;
+
+		export["events"] = json!(events);
+	}
+
+	let export = serde_json::to_vec(&export)?;
+	fs::write(&path, &export)
+		.await
+		.map_err(|e| err!("Failed to write {}: {e}", path.display()))?;
+
+	self.write_str(&format!(
+		"Exported {counts} of {room_id} to `{}` ({}).",
+		path.display(),
+		pretty(export.len()),
+	))
+	.await
+}
+
+#[admin_command]
+pub(super) async fn import_state(&self, room_id: OwnedRoomId, path: PathBuf) -> Result {
+	let export = fs::read(&path)
+		.await
+		.map_err(|e| err!("Failed to read {}: {e}", path.display()))?;
+
+	let export: JsonValue =
+		serde_json::from_slice(&export).map_err(|e| err!("Invalid JSON in the export: {e}"))?;
+
+	if let Some(exported_room_id) = export.get("room_id").and_then(JsonValue::as_str) {
+		if exported_room_id != room_id.as_str() {
+			return Err!("The export is of the room {exported_room_id}, not {room_id}.");
+		}
+	}
+
+	let services = self.services;
+	let room_version = match services
+		.rooms
+		.state
+		.get_room_version(&room_id)
+		.await
+	{
+		| Ok(room_version) => room_version,
+		| Err(_) => export
+			.get("room_version")
+			.and_then(JsonValue::as_str)
+			.map(RoomVersionId::try_from)
+			.transpose()
+			.map_err(|e| err!("Invalid room version in the export: {e}"))?
+			.ok_or_else(|| err!("The room is unknown and the export has no room version."))?,
+	};
+
+	let events = |key: &str| -> Result<Vec<Box<RawJsonValue>>> {
+		export
+			.get(key)
+			.and_then(JsonValue::as_array)
+			.into_iter()
+			.flatten()
+			.map(|event| to_raw_value(event).map_err(Into::into))
+			.collect()
+	};
+
+	let (pdus, others) = (events("pdus")?, [events("auth_chain")?, events("events")?].concat());
+	if pdus.is_empty() {
+		return Err!("The export has no state events.");
+	}
+
+	let in_room = |value: &CanonicalJsonObject| match value.get("room_id") {
+		| Some(CanonicalJsonValue::String(id)) => *id == room_id.as_str(),
+		| _ => false,
+	};
+
+	let mut rejected = 0_usize;
+	for pdu in &others {
+		match services
+			.server_keys
+			.validate_and_add_event_id(pdu, &room_version)
+			.await
+		{
+			| Ok((event_id, value)) if in_room(&value) => services
+				.rooms
+				.outlier
+				.add_pdu_outlier(&event_id, &value),
+			| Ok((event_id, _)) => {
+				debug_warn!("Skipping event {event_id} of another room.");
+				rejected = rejected.saturating_add(1);
+			},
+			| Err(e) => {
+				debug_warn!("Skipping event failing verification: {e}");
+				rejected = rejected.saturating_add(1);
+			},
+		}
+	}
+
+	let mut state = HashMap::new();
+	for pdu in &pdus {
+		let verified = services
+			.server_keys
+			.validate_and_add_event_id(pdu, &room_version)
+			.await
+			.and_then(|(event_id, value)| {
+				let pdu = PduEvent::from_id_val(&event_id, value.clone())?;
+				Ok((pdu, value))
+			});
+
+		let (pdu, value) = match verified {
+			| Ok(verified) => verified,
+			| Err(e) => {
+				debug_warn!("Skipping state event failing verification: {e}");
+				rejected = rejected.saturating_add(1);
+				continue;
+			},
+		};
+
+		let Some(state_key) = pdu.state_key.as_deref() else {
+			continue;
+		};
+
+		if pdu.room_id != room_id {
+			return Err!("The state event {} belongs to another room.", pdu.event_id);
+		}
+
+		services
+			.rooms
+			.outlier
+			.add_pdu_outlier(&pdu.event_id, &value);
+
+		let shortstatekey = services
+			.rooms
+			.short
+			.get_or_create_shortstatekey(&pdu.kind.to_string().into(), state_key)
+			.await;
+
+		state.insert(shortstatekey, pdu.event_id.clone());
+	}
+
+	if state.is_empty() {
+		return Err!("None of the state events could be verified.");
+	}
+
+	let new_state: CompressedState = services
+		.rooms
+		.state_compressor
+		.compress_state_events(state.iter().map(|(ssk, id)| (ssk, id.borrow())))
+		.collect()
+		.await;
+
+	services
+		.rooms
+		.short
+		.get_or_create_shortroomid(&room_id)
+		.await;
+
+	let HashSetCompressStateEvent { shortstatehash, added, removed } = services
+		.rooms
+		.state_compressor
+		.save_state(&room_id, Arc::new(new_state))
+		.await?;
+
+	let state_lock = services.rooms.state.mutex.lock(&room_id).await;
+	services
+		.rooms
+		.state
+		.force_state(&room_id, shortstatehash, added, removed, &state_lock)
+		.await?;
+
+	drop(state_lock);
+	self.write_str(&format!(
+		"Imported {} state events into {room_id}; {rejected} events failed verification or \
+		 belong to another room and were skipped.",
+		state.len()
+	))
+	.await
+}
+
+#[admin_command]
+pub(super) async fn redactions(
+	&self,
+	room_id: OwnedRoomId,
+	since: Option<String>,
+	limit: usize,
+) -> Result {
+	let since = since
+		.as_deref()
+		.map(parse_timepoint_ago)
+		.transpose()?;
+
+	let mut redactions: Vec<_> = self
+		.services
+		.rooms
+		.timeline
+		.redactions(&room_id)
+		.ready_filter(|(pdu, _)| {
+			since.is_none_or(|since| {
+				pdu.origin_server_ts()
+					.to_system_time()
+					.is_some_and(|ts| ts >= since)
+			})
+		})
+		.collect()
+		.await;
+
+	if redactions.is_empty() {
+		return self
+			.write_str(&format!("No redactions found in {room_id}."))
+			.await;
+	}
+
+	let total = redactions.len();
+	redactions.reverse();
+	redactions.truncate(limit);
+
+	let mut out = String::from(
+		"| Time | Moderator | Redacted event | Reason |\n| --- | --- | --- | --- |\n",
+	);
+
+	for (pdu, redacts) in &redactions {
+		let time = pdu
+			.origin_server_ts()
+			.to_system_time()
+			.map(|ts| time::format(ts, "%+"))
+			.unwrap_or_default();
+
+		let reason = pdu
+			.get_content::<RoomRedactionEventContent>()
+			.ok()
+			.and_then(|content| content.reason)
+			.unwrap_or_default()
+			.replace('|', "\\|");
+
+		writeln!(out, "| {time} | {} | {redacts} | {reason} |", pdu.sender())?;
+	}
+
+	self.write_str(&format!(
+		"Redactions in {room_id} ({} of {total}):\n\n{out}",
+		redactions.len()
+	))
+	.await
+}
+
+fn format_usage(room_id: &OwnedRoomId, usage: &Usage) -> String {
+	let bytes = pretty(usage.pdu_bytes.try_into().unwrap_or(usize::MAX));
+	format!(
+		"{room_id}\tEvents: {} ({bytes})\tState: {}\tMedia: {}",
+		usage.pdus, usage.state_events, usage.media,
+	)
+}
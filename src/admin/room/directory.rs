@@ -14,11 +14,43 @@ pub(crate) enum RoomDirectoryCommand {
 	},
 
 	/// - Unpublish a room to the room directory
+	///
+	/// Its pin and description are removed as well.
+	#[clap(alias("remove"))]
 	Unpublish {
 		/// The room id of the room to unpublish
 		room_id: OwnedRoomId,
 	},
 
+	/// - Feature a room at the top of the room directory, publishing it
+	Pin {
+		/// The room id of the room to pin
+		room_id: OwnedRoomId,
+
+		/// Position among the pinned rooms, lowest first; defaults to after
+		/// the others
+		#[arg(long)]
+		position: Option<u64>,
+	},
+
+	/// - Stop featuring a room at the top of the room directory
+	Unpin {
+		/// The room id of the room to unpin
+		room_id: OwnedRoomId,
+	},
+
+	/// - Set the description shown for a room in the room directory instead of
+	///   its topic
+	///
+	/// Omit the description to show the topic again.
+	Describe {
+		/// The room id of the room to describe
+		room_id: OwnedRoomId,
+
+		#[arg(trailing_var_arg)]
+		description: Vec<String>,
+	},
+
 	/// - List rooms that are published
 	List {
 		page: Option<usize>,
@@ -36,6 +68,30 @@ pub(super) async fn process(command: RoomDirectoryCommand, context: &Context<'_>
 			services.rooms.directory.set_not_public(&room_id);
 			context.write_str("Room unpublished").await
 		},
+		| RoomDirectoryCommand::Pin { room_id, position } => {
+			services
+				.rooms
+				.directory
+				.pin(&room_id, position)
+				.await;
+
+			context.write_str("Room pinned").await
+		},
+		| RoomDirectoryCommand::Unpin { room_id } => {
+			services.rooms.directory.unpin(&room_id).await;
+			context.write_str("Room unpinned").await
+		},
+		| RoomDirectoryCommand::Describe { room_id, description } => {
+			let description = description.join(" ");
+			let description = (!description.is_empty()).then_some(description);
+			services
+				.rooms
+				.directory
+				.set_description(&room_id, description)
+				.await;
+
+			context.write_str("Room description set").await
+		},
 		| RoomDirectoryCommand::List { page } => {
 			// TODO: i know there's a way to do this with clap, but i can't seem to find it
 			let page = page.unwrap_or(1);
@@ -50,6 +106,16 @@ pub(super) async fn process(command: RoomDirectoryCommand, context: &Context<'_>
 			rooms.sort_by_key(|r| r.1);
 			rooms.reverse();
 
+			let mut pinned: Vec<_> = services.rooms.directory.pinned().collect().await;
+
+			pinned.sort_by_key(|(_, pin)| *pin);
+			for (room_id, pin) in pinned.iter().rev() {
+				if let Some(index) = rooms.iter().position(|(id, ..)| id == room_id) {
+					let (id, members, name) = rooms.remove(index);
+					rooms.insert(0, (id, members, format!("{name} (pinned {pin})")));
+				}
+			}
+
 			let rooms: Vec<_> = rooms
 				.into_iter()
 				.skip(page.saturating_sub(1).saturating_mul(PAGE_SIZE))
@@ -4,8 +4,10 @@
 mod info;
 mod moderation;
 
+use std::path::PathBuf;
+
 use clap::Subcommand;
-use ruma::OwnedRoomId;
+use ruma::{OwnedRoomId, OwnedUserId};
 use tuwunel_core::Result;
 
 use self::{
@@ -56,4 +58,96 @@ pub(super) enum RoomCommand {
 	Exists {
 		room_id: OwnedRoomId,
 	},
+
+	/// - Create a room from one of the configured `room_templates`
+	///
+	/// The room is created by the server user.
+	CreateFromTemplate {
+		template: String,
+
+		/// Name of the new room
+		#[arg(long)]
+		name: Option<String>,
+
+		/// Local part of an alias for the new room
+		#[arg(long)]
+		alias: Option<String>,
+
+		/// Users to invite; may be repeated
+		#[arg(long)]
+		invite: Vec<OwnedUserId>,
+
+		/// Publish the room to the room directory
+		#[arg(long)]
+		public: bool,
+	},
+
+	/// - Approximate storage used by rooms
+	///
+	/// Reports the number and size of stored events, media references and
+	/// current state events. Without a room ID the rooms using the most space
+	/// are listed. The first run scans every room and may take a while.
+	Usage {
+		room_id: Option<OwnedRoomId>,
+
+		/// Number of rooms to list
+		#[arg(short, long, default_value("10"))]
+		top: usize,
+	},
+
+	/// - Export a room's current state as JSON to a file
+	///
+	/// The state events and their auth chain are dumped in federation format,
+	/// suitable for `import-state` on this or another server. With `--full`
+	/// every event of the room we have is included as well.
+	ExportState {
+		room_id: OwnedRoomId,
+
+		/// File to write the export to on the server
+		path: PathBuf,
+
+		/// Include every event of the room
+		#[arg(long)]
+		full: bool,
+	},
+
+	/// - Replace a room's state with an exported copy read from a file
+	///
+	/// This command reads the JSON written by `export-state`. The signatures
+	/// of every event are verified again, and events failing verification or
+	/// belonging to another room are skipped. The state events remaining
+	/// become the room's current state as they are, without state resolution;
+	/// this is meant for recovering broken rooms.
+	ImportState {
+		room_id: OwnedRoomId,
+
+		/// File on the server to read the export from
+		path: PathBuf,
+	},
+
+	/// - List the redactions in a room with their moderator and reason
+	///
+	/// Only redactions which were applied are listed, newest first.
+	Redactions {
+		room_id: OwnedRoomId,
+
+		/// Only list redactions made within this long ago, e.g. "7d"
+		#[arg(long)]
+		since: Option<String>,
+
+		/// Maximum number of redactions to list
+		#[arg(short, long, default_value("50"))]
+		limit: usize,
+	},
+
+	/// - List rooms no local user is joined or invited to
+	///
+	/// These are usually federated rooms every local user has since left.
+	/// With `--purge` their events, state pointers, memberships and aliases
+	/// are deleted from the database.
+	Orphans {
+		/// Delete the orphaned rooms from the database
+		#[arg(long)]
+		purge: bool,
+	},
 }
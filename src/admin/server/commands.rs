@@ -1,8 +1,10 @@
 use std::{fmt::Write, path::PathBuf, sync::Arc};
 
 use futures::TryStreamExt;
+use ruma::OwnedRoomId;
+use tracing_subscriber::EnvFilter;
 use tuwunel_core::{
-	Err, Result, info,
+	Err, Result, err, info,
 	utils::{stream::IterStream, time},
 	warn,
 };
@@ -78,8 +80,49 @@ pub(super) async fn memory_usage(&self) -> Result {
 }
 
 #[admin_command]
-pub(super) async fn clear_caches(&self) -> Result {
-	self.services.clear_cache().await;
+pub(super) async fn runtime_info(&self) -> Result {
+	let config = &self.services.server.config;
+	let unchanged = || "unchanged".to_owned();
+	let workers = self
+		.services
+		.server
+		.runtime()
+		.metrics()
+		.num_workers();
+
+	let mut out = String::new();
+	writeln!(out, "```")?;
+	writeln!(out, "worker_threads: {workers}")?;
+	writeln!(out, "max_blocking_threads: {}", config.runtime_max_blocking_threads)?;
+	writeln!(out, "worker_affinity: {}", config.runtime_worker_affinity)?;
+	writeln!(
+		out,
+		"worker_nice: {}",
+		config
+			.runtime_worker_nice
+			.map_or_else(unchanged, |nice| nice.to_string())
+	)?;
+	writeln!(
+		out,
+		"worker_io_priority: {}",
+		config
+			.runtime_worker_io_priority
+			.map_or_else(unchanged, |level| format!("best-effort {level}"))
+	)?;
+	write!(out, "```")?;
+
+	self.write_str(&out).await
+}
+
+#[admin_command]
+pub(super) async fn clear_caches(&self, services: Vec<String>) -> Result {
+	if services.is_empty() {
+		self.services.clear_cache().await;
+	}
+
+	for name in &services {
+		self.services.clear_cache_of(name).await?;
+	}
 
 	self.write_str("Done.").await
 }
@@ -152,3 +195,81 @@ pub(super) async fn shutdown(&self) -> Result {
 
 	self.write_str("Shutting down server...").await
 }
+
+#[admin_command]
+pub(super) async fn log_level(&self, filter: Option<String>) -> Result {
+	let handles = &["console"];
+	let log = &self.services.server.log;
+	let Some(filter) = filter else {
+		let current = log
+			.reload
+			.current("console")
+			.map_or_else(|| self.services.server.config.log.clone(), |filter| filter.to_string());
+
+		return self
+			.write_str(&format!("Current log filter: `{current}`"))
+			.await;
+	};
+
+	let (filter, reset) = match filter.as_str() {
+		| "reset" => (self.services.server.config.log.clone(), true),
+		| _ => (filter, false),
+	};
+
+	let new_filter =
+		EnvFilter::try_new(&filter).map_err(|e| err!("Invalid log filter {filter:?}: {e}"))?;
+
+	log.reload
+		.reload(&new_filter, Some(handles))
+		.map_err(|e| err!("Failed to reload the log filter: {e}"))?;
+
+	let out = if reset {
+		format!("Log filter reset to the config value `{filter}`.")
+	} else {
+		format!("Log filter changed to `{filter}`.")
+	};
+
+	self.write_str(&out).await
+}
+
+#[admin_command]
+pub(super) async fn repair_admin_room(&self, room_id: Option<OwnedRoomId>) -> Result {
+	let room_id = match room_id {
+		| Some(room_id) => room_id,
+		| None => self
+			.services
+			.rooms
+			.alias
+			.resolve_local_alias(&self.services.globals.admin_alias)
+			.await
+			.map_err(|_| {
+				err!(
+					"The admin alias points to no room; pass the room or recreate the admin \
+					 room."
+				)
+			})?,
+	};
+
+	let repairs = tuwunel_service::admin::repair_admin_room(self.services, &room_id).await?;
+	if repairs.is_empty() {
+		return self
+			.write_str(&format!("Admin room {room_id} needed no repairs."))
+			.await;
+	}
+
+	let mut body = String::new();
+	for repair in &repairs {
+		writeln!(body, "- {repair}")?;
+	}
+
+	self.write_str(&format!("Repaired admin room {room_id}:\n{body}"))
+		.await
+}
+
+#[admin_command]
+pub(super) async fn recreate_admin_room(&self) -> Result {
+	let room_id = tuwunel_service::admin::recreate_admin_room(self.services).await?;
+
+	self.write_str(&format!("Created new admin room {room_id}."))
+		.await
+}
@@ -3,6 +3,7 @@
 use std::path::PathBuf;
 
 use clap::Subcommand;
+use ruma::OwnedRoomId;
 use tuwunel_core::Result;
 
 use crate::admin_command_dispatch;
@@ -33,11 +34,21 @@ pub(super) enum ServerCommand {
 		comma: bool,
 	},
 
-	/// - Print database memory usage statistics
+	/// - Print memory usage of the allocator, database and each service's
+	///   caches
+	#[clap(alias = "memory")]
 	MemoryUsage,
 
 	/// - Clears all of Tuwunel's caches
-	ClearCaches,
+	///
+	/// Services may be named (e.g. `rooms::auth_chain`) to clear only their
+	/// caches; the names are listed by `memory-usage`.
+	ClearCaches {
+		services: Vec<String>,
+	},
+
+	/// - Show the effective runtime thread settings
+	RuntimeInfo,
 
 	/// - Performs an online backup of the database (only available for RocksDB
 	///   at the moment)
@@ -51,6 +62,32 @@ pub(super) enum ServerCommand {
 		message: Vec<String>,
 	},
 
+	/// - Show or change the log filter at runtime
+	///
+	/// Accepts the same format as the `log` config option, e.g.
+	/// `tuwunel_service::sending=trace`, or `reset` to return to the configured
+	/// filter. Without a filter the current one is shown.
+	LogLevel {
+		filter: Option<String>,
+	},
+
+	/// - Repair the admin room in place
+	///
+	/// Rejoins the server user when it has been invited back, and points the
+	/// admin alias back at the room. Fails when the room was not created by
+	/// the server user, or when the server user lacks the power to manage the
+	/// room, which a room admin has to restore. Defaults to the room the admin
+	/// alias points to.
+	RepairAdminRoom {
+		room_id: Option<OwnedRoomId>,
+	},
+
+	/// - Replace the admin room with a new one
+	///
+	/// The admin alias is moved to the new room, and the local members of the
+	/// previous admin room are made admins in it.
+	RecreateAdminRoom,
+
 	/// - Hot-reload the server
 	#[clap(alias = "reload")]
 	ReloadMods,
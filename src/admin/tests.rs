@@ -24,3 +24,35 @@ fn get_help_inner(input: &str) {
 	assert!(error.contains("Commands:"));
 	assert!(error.contains("Options:"));
 }
+
+#[test]
+fn complete_subcommand() {
+	use crate::processor::complete;
+
+	assert_eq!(complete("us"), "users ");
+	assert_eq!(complete("users"), "users ");
+	assert_eq!(complete("users reset-pa"), "users reset-password ");
+}
+
+#[test]
+fn complete_common_prefix() {
+	use crate::processor::complete;
+
+	assert_eq!(complete("users force-j"), "users force-join-");
+}
+
+#[test]
+fn complete_fuzzy() {
+	use crate::processor::complete;
+
+	assert_eq!(complete("users rstpw"), "users reset-password ");
+	assert_eq!(complete("users zzz"), "users zzz");
+	assert_eq!(complete("users create-user alice"), "users create-user alice");
+}
+
+#[test]
+fn complete_flag() {
+	use crate::processor::complete;
+
+	assert_eq!(complete("server list-features --av"), "server list-features --available ");
+}
@@ -1,8 +1,10 @@
-use std::{collections::BTreeMap, fmt::Write as _};
+use std::{collections::BTreeMap, fmt::Write as _, time::Duration};
 
 use futures::{FutureExt, StreamExt};
 use ruma::{
-	OwnedEventId, OwnedRoomId, OwnedRoomOrAliasId, OwnedUserId, UserId,
+	MilliSecondsSinceUnixEpoch, OwnedEventId, OwnedRoomId, OwnedRoomOrAliasId, OwnedUserId,
+	UserId,
+	api::client::backup::{BackupAlgorithm, RoomKeyBackup},
 	events::{
 		RoomAccountDataEventType, StateEventType,
 		room::{
@@ -11,13 +13,16 @@
 		},
 		tag::{TagEvent, TagEventContent, TagInfo},
 	},
+	serde::Raw,
 };
 use tuwunel_api::client::{
-	full_user_deactivate, join_room_by_id_helper, leave_all_rooms, leave_room, update_avatar_url,
-	update_displayname,
+	AutoJoinCohort, auto_join_rooms, full_user_deactivate, join_room_by_id_helper,
+	leave_all_rooms, leave_room, update_avatar_url, update_displayname,
 };
 use tuwunel_core::{
-	Err, Result, debug, debug_warn, error, info, is_equal_to,
+	Err, Result,
+	config::NoticeCategory,
+	debug, debug_warn, err, info, is_equal_to,
 	matrix::{Event, pdu::PduBuilder},
 	utils::{self, ReadyExt},
 	warn,
@@ -49,7 +54,12 @@ pub(super) async fn list_users(&self) -> Result {
 }
 
 #[admin_command]
-pub(super) async fn create_user(&self, username: String, password: Option<String>) -> Result {
+pub(super) async fn create_user(
+	&self,
+	username: String,
+	password: Option<String>,
+	bot: bool,
+) -> Result {
 	// Validate user id
 	let user_id = parse_local_user_id(self.services, &username)?;
 
@@ -64,119 +74,11 @@ pub(super) async fn create_user(&self, username: String, password: Option<String
 	}
 
 	let password = password.unwrap_or_else(|| utils::random_string(AUTO_GEN_PASSWORD_LENGTH));
-
-	// Create user
-	self.services
-		.users
-		.create(&user_id, Some(password.as_str()), None)
+	self.create_local_user(&user_id, &password, None)
 		.await?;
 
-	// Default to pretty displayname
-	let mut displayname = user_id.localpart().to_owned();
-
-	// If `new_user_displayname_suffix` is set, registration will push whatever
-	// content is set to the user's display name with a space before it
-	if !self
-		.services
-		.server
-		.config
-		.new_user_displayname_suffix
-		.is_empty()
-	{
-		write!(
-			displayname,
-			" {}",
-			self.services
-				.server
-				.config
-				.new_user_displayname_suffix
-		)?;
-	}
-
-	self.services
-		.users
-		.set_displayname(&user_id, Some(displayname));
-
-	// Initial account data
-	self.services
-		.account_data
-		.update(
-			None,
-			&user_id,
-			ruma::events::GlobalAccountDataEventType::PushRules
-				.to_string()
-				.into(),
-			&serde_json::to_value(ruma::events::push_rules::PushRulesEvent {
-				content: ruma::events::push_rules::PushRulesEventContent {
-					global: ruma::push::Ruleset::server_default(&user_id),
-				},
-			})?,
-		)
-		.await?;
-
-	if !self
-		.services
-		.server
-		.config
-		.auto_join_rooms
-		.is_empty()
-	{
-		for room in &self.services.server.config.auto_join_rooms {
-			let Ok(room_id) = self.services.rooms.alias.resolve(room).await else {
-				error!(
-					%user_id,
-					"Failed to resolve room alias to room ID when attempting to auto join {room}, skipping"
-				);
-				continue;
-			};
-
-			if !self
-				.services
-				.rooms
-				.state_cache
-				.server_in_room(self.services.globals.server_name(), &room_id)
-				.await
-			{
-				warn!(
-					"Skipping room {room} to automatically join as we have never joined before."
-				);
-				continue;
-			}
-
-			if let Some(room_server_name) = room.server_name() {
-				match join_room_by_id_helper(
-					self.services,
-					&user_id,
-					&room_id,
-					Some("Automatically joining this room upon registration".to_owned()),
-					&[
-						self.services.globals.server_name().to_owned(),
-						room_server_name.to_owned(),
-					],
-					None,
-					&None,
-				)
-				.await
-				{
-					| Ok(_response) => {
-						info!("Automatically joined room {room} for user {user_id}");
-					},
-					| Err(e) => {
-						// don't return this error so we don't fail registrations
-						error!(
-							"Failed to automatically join room {room} for user {user_id}: {e}"
-						);
-						self.services
-							.admin
-							.send_text(&format!(
-								"Failed to automatically join room {room} for user {user_id}: \
-								 {e}"
-							))
-							.await;
-					},
-				}
-			}
-		}
+	if bot {
+		self.services.users.set_bot(&user_id, true);
 	}
 
 	// we dont add a device since we're not the user, just the creator
@@ -332,7 +234,11 @@ pub(super) async fn deactivate_all(&self, no_leave_rooms: bool, force: bool) ->
 
 	let mut deactivation_count: usize = 0;
 
-	for user_id in user_ids {
+	let total = user_ids.len();
+	for (i, user_id) in user_ids.into_iter().enumerate() {
+		self.progress(&format!("Deactivating accounts: {i}/{total}"))
+			.await;
+
 		match self
 			.services
 			.users
@@ -512,7 +418,11 @@ pub(super) async fn force_join_list_of_local_users(
 	let mut failed_joins: usize = 0;
 	let mut successful_joins: usize = 0;
 
-	for user_id in user_ids {
+	let total = user_ids.len();
+	for (i, user_id) in user_ids.into_iter().enumerate() {
+		self.progress(&format!("Joining users to {room_id}: {i}/{total}"))
+			.await;
+
 		match join_room_by_id_helper(
 			self.services,
 			&user_id,
@@ -598,14 +508,18 @@ pub(super) async fn force_join_all_local_users(
 	let mut failed_joins: usize = 0;
 	let mut successful_joins: usize = 0;
 
-	for user_id in &self
+	let local_users: Vec<_> = self
 		.services
 		.users
 		.list_local_users()
 		.map(UserId::to_owned)
-		.collect::<Vec<_>>()
-		.await
-	{
+		.collect()
+		.await;
+
+	for (i, user_id) in local_users.iter().enumerate() {
+		self.progress(&format!("Joining users to {room_id}: {i}/{}", local_users.len()))
+			.await;
+
 		match join_room_by_id_helper(
 			self.services,
 			user_id,
@@ -940,3 +854,568 @@ pub(super) async fn redact_event(&self, event_id: OwnedEventId) -> Result {
 	))
 	.await
 }
+
+#[admin_command]
+pub(super) async fn whois(&self, user_id: String) -> Result {
+	let user_id = parse_local_user_id(self.services, &user_id)?;
+	let devices: Vec<_> = self
+		.services
+		.users
+		.all_devices_metadata(&user_id)
+		.collect()
+		.await;
+
+	if devices.is_empty() {
+		return Err!("User has no devices.");
+	}
+
+	writeln!(self, "| Device | Display Name | IP | User Agent | Last Seen |").await?;
+	writeln!(self, "| ------ | ------------ | -- | ---------- | --------- |").await?;
+
+	for device in devices {
+		let device_id = &device.device_id;
+		let name = device.display_name.as_deref().unwrap_or_default();
+		let connections = self
+			.services
+			.users
+			.connections(&user_id, device_id)
+			.await;
+
+		if connections.is_empty() {
+			let ip = device.last_seen_ip.as_deref().unwrap_or_default();
+			let last_seen = device
+				.last_seen_ts
+				.and_then(MilliSecondsSinceUnixEpoch::to_system_time)
+				.map(|ts| utils::time::format(ts, "%+"))
+				.unwrap_or_default();
+
+			writeln!(self, "| {device_id} | {name} | {ip} |  | {last_seen} |").await?;
+			continue;
+		}
+
+		for connection in connections {
+			let ip = &connection.ip;
+			let user_agent = connection
+				.user_agent
+				.as_deref()
+				.unwrap_or_default();
+			let last_seen = connection
+				.last_seen
+				.to_system_time()
+				.map(|ts| utils::time::format(ts, "%+"))
+				.unwrap_or_default();
+
+			writeln!(self, "| {device_id} | {name} | {ip} | {user_agent} | {last_seen} |")
+				.await?;
+		}
+	}
+
+	Ok(())
+}
+
+/// Creates an account with its initial profile and account data, and joins it
+/// to the configured auto-join rooms. Display name defaults to the localpart
+/// with `new_user_displayname_suffix`.
+#[admin_command]
+async fn create_local_user(
+	&self,
+	user_id: &UserId,
+	password: &str,
+	displayname: Option<&str>,
+) -> Result {
+	self.services
+		.users
+		.create(user_id, Some(password), None)
+		.await?;
+
+	let displayname = match displayname {
+		| Some(displayname) => displayname.to_owned(),
+		| None => {
+			// Default to pretty displayname
+			let mut displayname = user_id.localpart().to_owned();
+
+			// If `new_user_displayname_suffix` is set, registration will push whatever
+			// content is set to the user's display name with a space before it
+			if !self
+				.services
+				.server
+				.config
+				.new_user_displayname_suffix
+				.is_empty()
+			{
+				write!(
+					displayname,
+					" {}",
+					self.services
+						.server
+						.config
+						.new_user_displayname_suffix
+				)?;
+			}
+
+			displayname
+		},
+	};
+
+	self.services
+		.users
+		.set_displayname(user_id, Some(displayname));
+
+	// Initial account data
+	self.services
+		.account_data
+		.update(
+			None,
+			user_id,
+			ruma::events::GlobalAccountDataEventType::PushRules
+				.to_string()
+				.into(),
+			&serde_json::to_value(ruma::events::push_rules::PushRulesEvent {
+				content: ruma::events::push_rules::PushRulesEventContent {
+					global: ruma::push::Ruleset::server_default(user_id),
+				},
+			})?,
+		)
+		.await?;
+
+	let failed = auto_join_rooms(self.services, user_id, AutoJoinCohort::Password).await;
+	for (room, e) in failed {
+		self.services
+			.admin
+			.send_text(&format!(
+				"Failed to automatically join room {room} for user {user_id}: {e}"
+			))
+			.await;
+	}
+
+	Ok(())
+}
+
+#[admin_command]
+pub(super) async fn import(&self, login_tokens: bool, login_token_ttl: u64) -> Result {
+	if self.body.len() < 2
+		|| !self.body[0].trim().starts_with("```")
+		|| self.body.last().unwrap_or(&"").trim() != "```"
+	{
+		return Err!("Expected code block in command body. Add --help for details.",);
+	}
+
+	let rows = &self.body[1..self.body.len().saturating_sub(1)];
+	let expires_in = login_token_ttl.saturating_mul(1000);
+
+	writeln!(self, "| User | Email | Password | Login Token | Result |").await?;
+	writeln!(self, "| ---- | ----- | -------- | ----------- | ------ |").await?;
+
+	let (mut created, mut failed) = (0_usize, 0_usize);
+	for row in rows
+		.iter()
+		.map(|row| row.trim())
+		.filter(|row| !row.is_empty())
+	{
+		let mut fields = row.splitn(3, ',').map(str::trim);
+		let localpart = fields.next().unwrap_or_default();
+		let displayname = fields.next().filter(|name| !name.is_empty());
+		let email = fields.next().unwrap_or_default();
+
+		let password = utils::random_string(AUTO_GEN_PASSWORD_LENGTH);
+		let result: Result<(OwnedUserId, Option<String>)> = async {
+			let user_id = parse_local_user_id(self.services, localpart)?;
+			if let Err(e) = user_id.validate_strict() {
+				return Err!("Username contains disallowed characters or spaces: {e}");
+			}
+
+			if self.services.users.exists(&user_id).await {
+				return Err!("User already exists");
+			}
+
+			self.create_local_user(&user_id, &password, displayname)
+				.await?;
+
+			let login_token = login_tokens.then(|| {
+				let token = utils::random_string(AUTO_GEN_PASSWORD_LENGTH);
+				self.services
+					.users
+					.create_login_token_expiring(&user_id, &token, expires_in);
+
+				token
+			});
+
+			Ok((user_id, login_token))
+		}
+		.await;
+
+		match result {
+			| Ok((user_id, login_token)) => {
+				created = created.saturating_add(1);
+				let login_token = login_token.unwrap_or_default();
+				writeln!(
+					self,
+					"| {user_id} | {email} | `{password}` | `{login_token}` | created |"
+				)
+				.await?;
+			},
+			| Err(e) => {
+				failed = failed.saturating_add(1);
+				writeln!(self, "| {localpart} | {email} |  |  | {e} |").await?;
+			},
+		}
+	}
+
+	writeln!(self, "\nCreated {created} user(s), {failed} failed.").await
+}
+
+#[admin_command]
+pub(super) async fn renew_account(&self, user_id: String, period: Option<u64>) -> Result {
+	let user_id = parse_local_user_id(self.services, &user_id)?;
+	if !self.services.users.exists(&user_id).await {
+		return Err!("User {user_id} does not exist.");
+	}
+
+	let Some(expires_at) = self
+		.services
+		.users
+		.renew_account(&user_id, period.map(Duration::from_secs))
+	else {
+		return Err!("No period given and account_validity_period is not configured.");
+	};
+
+	let expires_at = format_millis(expires_at);
+	self.write_str(&format!("Account {user_id} is now valid until {expires_at}."))
+		.await
+}
+
+#[admin_command]
+pub(super) async fn list_expiring_accounts(&self, within: u64) -> Result {
+	let now = utils::millis_since_unix_epoch();
+	let until = now.saturating_add(within.saturating_mul(1000));
+	let accounts: Vec<_> = self
+		.services
+		.users
+		.accounts_expiring_before(until)
+		.map(|(user_id, expires_at)| (user_id.to_owned(), expires_at))
+		.collect()
+		.await;
+
+	if accounts.is_empty() {
+		return self.write_str("No accounts are expiring.").await;
+	}
+
+	writeln!(self, "| User | Expires | Expired |").await?;
+	writeln!(self, "| ---- | ------- | ------- |").await?;
+	for (user_id, expires_at) in accounts {
+		let expired = expires_at < now;
+		let expires_at = format_millis(expires_at);
+		writeln!(self, "| {user_id} | {expires_at} | {expired} |").await?;
+	}
+
+	Ok(())
+}
+
+#[admin_command]
+pub(super) async fn make_token(&self, user_id: String, ttl: u64) -> Result {
+	let user_id = parse_active_local_user_id(self.services, &user_id).await?;
+	if user_id == self.services.globals.server_user {
+		return Err!("Not allowed to create tokens for the server service account.");
+	}
+
+	let (device_id, token, expires_at) = self
+		.services
+		.users
+		.create_impersonation_token(&user_id, Duration::from_secs(ttl))
+		.await?;
+
+	let expires_at = format_millis(expires_at);
+	let notice = format!(
+		"Created impersonation token for {user_id} on device {device_id}, valid until \
+		 {expires_at}."
+	);
+
+	warn!("{notice}");
+	self.services
+		.admin
+		.category_notice(NoticeCategory::Impersonation, &notice)
+		.await;
+
+	writeln!(self, "{notice}\n\nAccess token: `{token}`").await
+}
+
+#[admin_command]
+pub(super) async fn revoke_tokens(&self, user_id: String) -> Result {
+	let user_id = parse_local_user_id(self.services, &user_id)?;
+	let revoked = self
+		.services
+		.users
+		.revoke_impersonation_tokens(&user_id)
+		.await;
+
+	let notice = format!("Revoked {revoked} impersonation token(s) for {user_id}.");
+
+	warn!("{notice}");
+	self.services
+		.admin
+		.category_notice(NoticeCategory::Impersonation, &notice)
+		.await;
+
+	self.write_str(&notice).await
+}
+
+#[admin_command]
+pub(super) async fn reset_cross_signing(&self, user_id: String) -> Result {
+	let user_id = parse_local_user_id(self.services, &user_id)?;
+	if !self.services.users.exists(&user_id).await {
+		return Err!("User {user_id} does not exist.");
+	}
+
+	match self
+		.services
+		.users
+		.remove_cross_signing_keys(&user_id)
+		.await
+	{
+		| 0 => Err!("User {user_id} has no cross-signing keys."),
+		| removed => {
+			info!("Reset cross-signing keys of {user_id}");
+			writeln!(self, "Removed {removed} cross-signing key(s) of {user_id}.").await
+		},
+	}
+}
+
+#[admin_command]
+pub(super) async fn export_room_keys(&self, user_id: String, version: String) -> Result {
+	let user_id = parse_local_user_id(self.services, &user_id)?;
+	let algorithm = self
+		.services
+		.key_backups
+		.get_backup(&user_id, &version)
+		.await
+		.map_err(|_| err!("User {user_id} has no key backup version {version}."))?;
+
+	let rooms = self
+		.services
+		.key_backups
+		.get_all(&user_id, &version)
+		.await;
+
+	let export = serde_json::json!({
+		"version": version,
+		"algorithm": algorithm,
+		"rooms": rooms,
+	});
+
+	let export = serde_json::to_string_pretty(&export)?;
+	writeln!(self, "```json\n{export}\n```").await
+}
+
+#[admin_command]
+pub(super) async fn import_room_keys(&self, user_id: String, version: Option<String>) -> Result {
+	if self.body.len() < 2
+		|| !self.body[0].trim().starts_with("```")
+		|| self.body.last().unwrap_or(&"").trim() != "```"
+	{
+		return Err!("Expected code block in command body. Add --help for details.");
+	}
+
+	let user_id = parse_active_local_user_id(self.services, &user_id).await?;
+	let body = self.body[1..self.body.len().saturating_sub(1)].join("\n");
+	let mut export: serde_json::Value =
+		serde_json::from_str(&body).map_err(|e| err!("Invalid key backup export: {e}"))?;
+
+	let Some(export) = export.as_object_mut() else {
+		return Err!("Invalid key backup export: expected a JSON object.");
+	};
+
+	let rooms: BTreeMap<OwnedRoomId, RoomKeyBackup> =
+		serde_json::from_value(export.remove("rooms").unwrap_or_default())
+			.map_err(|e| err!("Invalid rooms in key backup export: {e}"))?;
+
+	let version = match version {
+		| Some(version) => {
+			self.services
+				.key_backups
+				.get_backup(&user_id, &version)
+				.await
+				.map_err(|_| err!("User {user_id} has no key backup version {version}."))?;
+
+			version
+		},
+		| None => {
+			let Some(algorithm) = export.remove("algorithm") else {
+				return Err!("Invalid key backup export: the algorithm is missing.");
+			};
+
+			let algorithm: BackupAlgorithm = serde_json::from_value(algorithm)
+				.map_err(|e| err!("Invalid algorithm in key backup export: {e}"))?;
+
+			let algorithm = Raw::new(&algorithm)?;
+			self.services
+				.key_backups
+				.create_backup(&user_id, &algorithm)?
+		},
+	};
+
+	let mut imported: usize = 0;
+	for (room_id, room) in &rooms {
+		for (session_id, key_data) in &room.sessions {
+			self.services
+				.key_backups
+				.add_key(&user_id, &version, room_id, session_id, key_data)
+				.await?;
+
+			imported = imported.saturating_add(1);
+		}
+	}
+
+	writeln!(
+		self,
+		"Imported {imported} session(s) in {} room(s) into key backup version {version} of \
+		 {user_id}.",
+		rooms.len()
+	)
+	.await
+}
+
+#[admin_command]
+pub(super) async fn key_backup_stats(&self, user_id: String) -> Result {
+	let user_id = parse_local_user_id(self.services, &user_id)?;
+	let key_backups = &self.services.key_backups;
+	let mut versions: Vec<String> = key_backups
+		.backup_versions(&user_id)
+		.map(ToOwned::to_owned)
+		.collect()
+		.await;
+
+	if versions.is_empty() {
+		return Err!("User {user_id} has no key backups.");
+	}
+
+	versions.sort_by_key(|version| version.parse::<u64>().unwrap_or_default());
+	let latest = key_backups
+		.get_latest_backup_version(&user_id)
+		.await
+		.ok();
+
+	writeln!(self, "| Version | Keys | Etag | Latest |").await?;
+	writeln!(self, "| ------- | ---- | ---- | ------ |").await?;
+	for version in &versions {
+		let keys = key_backups.count_keys(&user_id, version).await;
+		let etag = key_backups.get_etag(&user_id, version).await;
+		let is_latest = latest.as_ref() == Some(version);
+		writeln!(self, "| {version} | {keys} | {etag} | {is_latest} |").await?;
+	}
+
+	Ok(())
+}
+
+pub(super) fn format_millis(millis: u64) -> String {
+	MilliSecondsSinceUnixEpoch(millis.try_into().unwrap_or_default())
+		.to_system_time()
+		.map(|ts| utils::time::format(ts, "%+"))
+		.unwrap_or_default()
+}
+
+#[admin_command]
+pub(super) async fn migrate_room_data(
+	&self,
+	old_room: OwnedRoomOrAliasId,
+	new_room: OwnedRoomOrAliasId,
+) -> Result {
+	let alias = &self.services.rooms.alias;
+	let old_room_id = alias.resolve(&old_room).await?;
+	let new_room_id = alias.resolve(&new_room).await?;
+	if old_room_id == new_room_id {
+		return Err!("The old and new rooms are the same.");
+	}
+
+	let users: Vec<OwnedUserId> = self
+		.services
+		.rooms
+		.state_cache
+		.local_users_in_room(&old_room_id)
+		.map(ToOwned::to_owned)
+		.collect()
+		.await;
+
+	let mut copied: usize = 0;
+	for user_id in &users {
+		copied = copied.saturating_add(
+			self.services
+				.account_data
+				.copy_room(&old_room_id, &new_room_id, user_id)
+				.await,
+		);
+	}
+
+	self.write_str(&format!(
+		"Copied {copied} room account data events of {} local users from {old_room_id} to \
+		 {new_room_id}.",
+		users.len()
+	))
+	.await
+}
+
+#[admin_command]
+pub(super) async fn set_bot(&self, user_id: String, unset: bool) -> Result {
+	let user_id = parse_local_user_id(self.services, &user_id)?;
+	if !self.services.users.exists(&user_id).await {
+		return Err!("User {user_id} does not exist.");
+	}
+
+	self.services.users.set_bot(&user_id, !unset);
+	let action = if unset { "no longer" } else { "now" };
+	self.write_str(&format!("{user_id} is {action} flagged as a bot."))
+		.await
+}
+
+#[admin_command]
+pub(super) async fn ratelimit(
+	&self,
+	user_id: String,
+	messages_per_second: Option<f64>,
+	burst: Option<u32>,
+	reset: bool,
+) -> Result {
+	let user_id = parse_local_user_id(self.services, &user_id)?;
+	let users = &self.services.users;
+
+	if reset {
+		users.reset_rate_limit_override(&user_id);
+		return self
+			.write_str(&format!("Reset the rate limit of {user_id} to the default."))
+			.await;
+	}
+
+	let stored = users.rate_limit_override(&user_id).await;
+	if messages_per_second.is_none() && burst.is_none() {
+		let (limit, source) = match stored {
+			| Some(limit) => (limit, "override"),
+			| None => (users.default_rate_limit(), "default"),
+		};
+
+		return self
+			.write_str(&format!(
+				"{user_id}: {} messages per second, burst {} ({source}).",
+				limit.per_second, limit.burst
+			))
+			.await;
+	}
+
+	let mut limit = stored.unwrap_or_else(|| users.default_rate_limit());
+	if let Some(per_second) = messages_per_second {
+		if !per_second.is_finite() || per_second < 0.0 {
+			return Err!("Messages per second must be a non-negative number.");
+		}
+
+		limit.per_second = per_second;
+	}
+
+	if let Some(burst) = burst {
+		limit.burst = burst;
+	}
+
+	users.set_rate_limit_override(&user_id, limit);
+	self.write_str(&format!(
+		"Set the rate limit of {user_id} to {} messages per second, burst {}.",
+		limit.per_second, limit.burst
+	))
+	.await
+}
@@ -0,0 +1,63 @@
+use std::fmt::Write;
+
+use clap::Subcommand;
+use futures::StreamExt;
+use ruma::{OwnedRoomId, OwnedUserId};
+use tuwunel_core::Result;
+
+use super::commands::format_millis;
+use crate::Context;
+
+#[derive(Debug, Subcommand)]
+pub(crate) enum UserInvitesCommand {
+	/// - List invites held from their recipients by `invite_unshared_servers`
+	List,
+
+	/// - Deliver a suppressed invite to its recipient
+	Release {
+		user_id: OwnedUserId,
+		room_id: OwnedRoomId,
+	},
+
+	/// - Drop a suppressed invite without delivering it
+	Discard {
+		user_id: OwnedUserId,
+		room_id: OwnedRoomId,
+	},
+}
+
+pub(super) async fn process(command: UserInvitesCommand, context: &Context<'_>) -> Result {
+	let state_cache = &context.services.rooms.state_cache;
+	match command {
+		| UserInvitesCommand::List => {
+			let invites: Vec<_> = state_cache.suppressed_invites().collect().await;
+			if invites.is_empty() {
+				return context.write_str("No suppressed invites.").await;
+			}
+
+			let mut body = String::new();
+			for (user_id, room_id, invite) in &invites {
+				writeln!(
+					body,
+					"{user_id} | {room_id} | from {} | {}",
+					invite.sender,
+					format_millis(invite.received),
+				)?;
+			}
+
+			context
+				.write_str(&format!("Suppressed invites ({}):\n```\n{body}```", invites.len()))
+				.await
+		},
+		| UserInvitesCommand::Release { user_id, room_id } => {
+			state_cache
+				.release_invite(&user_id, &room_id)
+				.await?;
+			context.write_str("Invite released").await
+		},
+		| UserInvitesCommand::Discard { user_id, room_id } => {
+			state_cache.discard_invite(&user_id, &room_id);
+			context.write_str("Invite discarded").await
+		},
+	}
+}
@@ -1,9 +1,12 @@
 mod commands;
+mod invites;
+mod throttle;
 
 use clap::Subcommand;
 use ruma::{OwnedEventId, OwnedRoomId, OwnedRoomOrAliasId};
 use tuwunel_core::Result;
 
+use self::{invites::UserInvitesCommand, throttle::UserRegistrationThrottleCommand};
 use crate::admin_command_dispatch;
 
 #[admin_command_dispatch]
@@ -16,6 +19,21 @@ pub(super) enum UserCommand {
 		username: String,
 		/// Password of the new user, if unspecified one is generated
 		password: Option<String>,
+		/// Flag the new user as a bot or service account
+		#[arg(long)]
+		bot: bool,
+	},
+
+	/// - Flag or unflag a local user as a bot or service account
+	///
+	/// Bots are left out of the user directory, presence and admin notices,
+	/// and are not rate limited unless given an override with `ratelimit`.
+	SetBot {
+		user_id: String,
+
+		/// Remove the flag
+		#[arg(long)]
+		unset: bool,
 	},
 
 	/// - Reset user password
@@ -153,4 +171,136 @@ pub(super) enum UserCommand {
 		#[arg(long)]
 		yes_i_want_to_do_this: bool,
 	},
+	/// - Create local users in bulk
+	///
+	/// This command needs rows of `localpart,displayname,email` provided in a
+	/// Markdown code block below the command. The display name and email may
+	/// be empty. Each account is given a random password, reported in the
+	/// results with the email so credentials can be distributed; the email is
+	/// not stored.
+	Import {
+		/// Also create a single-use login token (`m.login.token`) for each
+		/// account
+		#[arg(long)]
+		login_tokens: bool,
+
+		/// Seconds until the login tokens expire
+		#[arg(long, default_value_t = 60 * 60 * 24 * 7)]
+		login_token_ttl: u64,
+	},
+
+	/// - Lists a local user's devices with the addresses and clients each was
+	///   recently used from
+	Whois {
+		user_id: String,
+	},
+
+	/// - Extend a local user's account validity from now
+	///
+	/// Uses `account_validity_period` unless a period is given.
+	RenewAccount {
+		user_id: String,
+
+		/// Seconds the account remains valid for
+		#[arg(long)]
+		period: Option<u64>,
+	},
+
+	/// - Create a short-lived access token to act as a local user
+	///
+	/// The token belongs to a new device of the user and is reported to the
+	/// admin room. Use `revoke-tokens` to remove it before it expires.
+	MakeToken {
+		user_id: String,
+
+		/// Seconds until the token expires
+		#[arg(long, default_value_t = 60 * 60)]
+		ttl: u64,
+	},
+
+	/// - Revoke all access tokens created for a user with `make-token`
+	RevokeTokens {
+		user_id: String,
+	},
+
+	/// - Delete a local user's cross-signing keys
+	///
+	/// For users who lost their recovery key; their clients can then set up
+	/// cross-signing again. Other users will see the user's devices as
+	/// unverified until they verify them again.
+	ResetCrossSigning {
+		user_id: String,
+	},
+
+	/// - Export the contents of a user's server-side key backup as JSON
+	///
+	/// The sessions remain encrypted with the user's backup key, so this is
+	/// not the client key export format; it can only be restored with
+	/// `import-room-keys` and read by the user's clients.
+	ExportRoomKeys {
+		user_id: String,
+		version: String,
+	},
+
+	/// - Import the output of `export-room-keys` into a user's key backup
+	///
+	/// This command needs the exported JSON provided in a Markdown code block
+	/// below the command. Without a version a new backup is created using
+	/// the exported backup algorithm.
+	ImportRoomKeys {
+		user_id: String,
+		version: Option<String>,
+	},
+
+	/// - List a user's room key backup versions with their key counts and etags
+	KeyBackupStats {
+		user_id: String,
+	},
+
+	/// - List local accounts which have expired or will expire soon
+	ListExpiringAccounts {
+		/// Include accounts expiring within this many seconds
+		#[arg(long, default_value_t = 60 * 60 * 24 * 7)]
+		within: u64,
+	},
+
+	/// - Copy local users' room tags, fully read markers and other room account
+	///   data from one room to another, such as an upgraded room's successor
+	///
+	/// Types a user already has in the new room are kept. Upgrades copy this
+	/// automatically when users join the new room; this is for rooms replaced
+	/// by other means or joined before the upgrade.
+	MigrateRoomData {
+		old_room: OwnedRoomOrAliasId,
+		new_room: OwnedRoomOrAliasId,
+	},
+
+	/// - Show, set or reset a local user's message rate limit override
+	///
+	/// Without options the user's current rate limit is shown. An override
+	/// replaces `message_rate_limit_per_second` and `message_rate_limit_burst`
+	/// for the user; a rate of 0 exempts them, e.g. for bots.
+	Ratelimit {
+		user_id: String,
+
+		/// Sustained messages per second
+		#[arg(long)]
+		messages_per_second: Option<f64>,
+
+		/// Messages which may be sent at once after a pause
+		#[arg(long)]
+		burst: Option<u32>,
+
+		/// Remove the override, returning the user to the configured default
+		#[arg(long, conflicts_with_all = ["messages_per_second", "burst"])]
+		reset: bool,
+	},
+
+	#[command(subcommand)]
+	/// - Manage invites suppressed by `invite_unshared_servers`
+	Invites(UserInvitesCommand),
+
+	#[command(subcommand)]
+	/// - Manage registration throttling of client subnets
+	RegistrationThrottle(UserRegistrationThrottleCommand),
 }
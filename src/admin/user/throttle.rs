@@ -0,0 +1,97 @@
+use std::fmt::Write;
+
+use clap::Subcommand;
+use futures::StreamExt;
+use tuwunel_core::{
+	Result,
+	utils::{ReadyExt, millis_since_unix_epoch},
+};
+
+use super::commands::format_millis;
+use crate::Context;
+
+#[derive(Debug, Subcommand)]
+pub(crate) enum UserRegistrationThrottleCommand {
+	/// - List subnets with failed registration attempts counted by
+	///   `registration_throttle_attempts`, and whether they are locked out
+	List {
+		/// Only list locked out subnets
+		#[arg(long)]
+		locked: bool,
+	},
+
+	/// - Clear the failed attempts and any lockout of a subnet, as listed
+	Clear {
+		/// Subnet as listed, e.g. `203.0.113.0/24`
+		subnet: String,
+	},
+
+	/// - Clear the failed attempts and lockouts of all subnets
+	ClearAll,
+}
+
+pub(super) async fn process(
+	command: UserRegistrationThrottleCommand,
+	context: &Context<'_>,
+) -> Result {
+	let users = &context.services.users;
+	match command {
+		| UserRegistrationThrottleCommand::List { locked } => {
+			let now = millis_since_unix_epoch();
+			let throttles: Vec<_> = users
+				.registration_throttles()
+				.ready_filter(|(_, throttle)| {
+					let is_locked = throttle
+						.locked_until
+						.is_some_and(|locked_until| locked_until > now);
+
+					!locked || is_locked
+				})
+				.collect()
+				.await;
+
+			if throttles.is_empty() {
+				return context.write_str("No throttled subnets.").await;
+			}
+
+			let mut body = String::new();
+			for (subnet, throttle) in &throttles {
+				let lockout = throttle
+					.locked_until
+					.filter(|&locked_until| locked_until > now)
+					.map(|locked_until| format!("locked until {}", format_millis(locked_until)))
+					.unwrap_or_default();
+
+				writeln!(body, "{subnet} | {} failed attempts | {lockout}", throttle.attempts)?;
+			}
+
+			context
+				.write_str(&format!("Throttled subnets ({}):\n```\n{body}```", throttles.len()))
+				.await
+		},
+		| UserRegistrationThrottleCommand::Clear { subnet } => {
+			users.clear_registration_throttle(&subnet);
+			context
+				.write_str(&format!("Cleared registration throttle of {subnet}"))
+				.await
+		},
+		| UserRegistrationThrottleCommand::ClearAll => {
+			let subnets: Vec<_> = users
+				.registration_throttles()
+				.map(|(subnet, _)| subnet)
+				.collect()
+				.await;
+
+			for subnet in &subnets {
+				users.clear_registration_throttle(subnet);
+			}
+
+			context
+				.write_str(&format!(
+					"Cleared registration throttles of {} subnets",
+					subnets.len()
+				))
+				.await
+		},
+	}
+}
@@ -1,7 +1,7 @@
 use std::fmt::Write;
 
 use axum::extract::State;
-use axum_client_ip::InsecureClientIp;
+use axum_client_ip::{InsecureClientIp, SecureClientIp};
 use futures::{FutureExt, StreamExt};
 use register::RegistrationKind;
 use ruma::{
@@ -23,15 +23,17 @@
 	push,
 };
 use tuwunel_core::{
-	Err, Error, Result, debug_info, err, error, info, is_equal_to,
+	Err, Error, Result,
+	config::NoticeCategory,
+	debug_info, err, info, is_equal_to,
 	matrix::{Event, pdu::PduBuilder},
 	utils,
 	utils::{ReadyExt, stream::BroadbandExt},
 	warn,
 };
-use tuwunel_service::Services;
+use tuwunel_service::{Services, uiaa::TOTP_AUTH_TYPE, users};
 
-use super::{DEVICE_ID_LENGTH, SESSION_ID_LENGTH, TOKEN_LENGTH, join_room_by_id_helper};
+use super::{AutoJoinCohort, DEVICE_ID_LENGTH, SESSION_ID_LENGTH, TOKEN_LENGTH, auto_join_rooms};
 use crate::Ruma;
 
 const RANDOM_USER_ID_LENGTH: usize = 10;
@@ -84,6 +86,10 @@ pub(crate) async fn get_register_available_route(
 		body.username.to_lowercase()
 	};
 
+	services
+		.globals
+		.check_username_policy(&body_username, body.appservice_info.is_some())?;
+
 	// Validate user id
 	let user_id =
 		match UserId::parse_with_server_name(&body_username, services.globals.server_name()) {
@@ -153,6 +159,7 @@ pub(crate) async fn get_register_available_route(
 pub(crate) async fn register_route(
 	State(services): State<crate::State>,
 	InsecureClientIp(client): InsecureClientIp,
+	SecureClientIp(peer): SecureClientIp,
 	body: Ruma<register::v3::Request>,
 ) -> Result<register::v3::Response> {
 	let is_guest = body.kind == RegistrationKind::Guest;
@@ -221,6 +228,14 @@ pub(crate) async fn register_route(
 		return Err!(Request(Forbidden("Registration is temporarily disabled.")));
 	}
 
+	let throttled = body.appservice_info.is_none();
+	if throttled {
+		services
+			.users
+			.check_registration_throttle(peer)
+			.await?;
+	}
+
 	let user_id = match (body.username.as_ref(), is_guest) {
 		| (Some(username), false) => {
 			// workaround for https://github.com/matrix-org/matrix-appservice-irc/issues/1780 due to inactivity of fixing the issue
@@ -255,6 +270,12 @@ pub(crate) async fn register_route(
 				username.to_lowercase()
 			};
 
+			if !emergency_mode_enabled {
+				services
+					.globals
+					.check_username_policy(&body_username, body.appservice_info.is_some())?;
+			}
+
 			let proposed_user_id = match UserId::parse_with_server_name(
 				&body_username,
 				services.globals.server_name(),
@@ -345,6 +366,8 @@ pub(crate) async fn register_route(
 		body.appservice_info.is_some() || is_guest
 	};
 
+	let registered_with_token = services.globals.registration_token.is_some() && !skip_auth;
+
 	if !skip_auth {
 		match &body.auth {
 			| Some(auth) => {
@@ -359,6 +382,13 @@ pub(crate) async fn register_route(
 					)
 					.await?;
 				if !worked {
+					if throttled {
+						services
+							.users
+							.count_registration_failure(peer)
+							.await;
+					}
+
 					return Err(Error::Uiaa(uiaainfo));
 				}
 				// Success!
@@ -444,6 +474,14 @@ pub(crate) async fn register_route(
 	let device_id = if is_guest { None } else { body.device_id.clone() }
 		.unwrap_or_else(|| utils::random_string(DEVICE_ID_LENGTH).into());
 
+	if device_id.as_str().is_empty() {
+		return Err!(Request(InvalidParam("Device ID must not be empty.")));
+	}
+
+	if users::is_impersonation_device(&device_id) {
+		return Err!(Request(InvalidParam("Device ID uses a reserved prefix.")));
+	}
+
 	// Generate new token for the device
 	let token = utils::random_string(TOKEN_LENGTH);
 
@@ -475,16 +513,18 @@ pub(crate) async fn register_route(
 			);
 
 			info!("{notice}");
-			if services.server.config.admin_room_notices {
-				services.admin.notice(&notice).await;
-			}
+			services
+				.admin
+				.category_notice(NoticeCategory::Registration, &notice)
+				.await;
 		} else {
 			let notice = format!("New user \"{user_id}\" registered on this server.");
 
 			info!("{notice}");
-			if services.server.config.admin_room_notices {
-				services.admin.notice(&notice).await;
-			}
+			services
+				.admin
+				.category_notice(NoticeCategory::Registration, &notice)
+				.await;
 		}
 	}
 
@@ -493,26 +533,27 @@ pub(crate) async fn register_route(
 		debug_info!("New guest user \"{user_id}\" registered on this server.");
 
 		if !device_display_name.is_empty() {
-			if services.server.config.admin_room_notices {
-				services
-					.admin
-					.notice(&format!(
+			services
+				.admin
+				.category_notice(
+					NoticeCategory::Registration,
+					&format!(
 						"Guest user \"{user_id}\" with device display name \
 						 \"{device_display_name}\" registered on this server from IP {client}"
-					))
-					.await;
-			}
+					),
+				)
+				.await;
 		} else {
-			#[allow(clippy::collapsible_else_if)]
-			if services.server.config.admin_room_notices {
-				services
-					.admin
-					.notice(&format!(
+			services
+				.admin
+				.category_notice(
+					NoticeCategory::Registration,
+					&format!(
 						"Guest user \"{user_id}\" with no device display name registered on \
 						 this server from IP {client}",
-					))
-					.await;
-			}
+					),
+				)
+				.await;
 		}
 	}
 
@@ -534,54 +575,27 @@ pub(crate) async fn register_route(
 		}
 	}
 
-	if body.appservice_info.is_none()
-		&& !services.server.config.auto_join_rooms.is_empty()
-		&& (services.config.allow_guests_auto_join_rooms || !is_guest)
-	{
-		for room in &services.server.config.auto_join_rooms {
-			let Ok(room_id) = services.rooms.alias.resolve(room).await else {
-				error!(
-					"Failed to resolve room alias to room ID when attempting to auto join \
-					 {room}, skipping"
-				);
-				continue;
-			};
+	if body.appservice_info.is_none() {
+		let cohort = if is_guest {
+			AutoJoinCohort::Guest
+		} else if registered_with_token {
+			AutoJoinCohort::Token
+		} else {
+			AutoJoinCohort::Password
+		};
 
-			if !services
-				.rooms
-				.state_cache
-				.server_in_room(services.globals.server_name(), &room_id)
-				.await
-			{
-				warn!(
-					"Skipping room {room} to automatically join as we have never joined before."
-				);
-				continue;
-			}
+		auto_join_rooms(&services, &user_id, cohort)
+			.boxed()
+			.await;
 
-			if let Some(room_server_name) = room.server_name() {
-				match join_room_by_id_helper(
-					&services,
-					&user_id,
-					&room_id,
-					Some("Automatically joining this room upon registration".to_owned()),
-					&[services.globals.server_name().to_owned(), room_server_name.to_owned()],
-					None,
-					&body.appservice_info,
-				)
+		if !is_guest {
+			if let Err(e) = services
+				.admin
+				.send_welcome(&user_id)
 				.boxed()
 				.await
-				{
-					| Err(e) => {
-						// don't return this error so we don't fail registrations
-						error!(
-							"Failed to automatically join room {room} for user {user_id}: {e}"
-						);
-					},
-					| _ => {
-						info!("Automatically joined room {room} for user {user_id}");
-					},
-				}
+			{
+				warn!(%user_id, "Failed to send welcome message: {e}");
 			}
 		}
 	}
@@ -625,8 +639,13 @@ pub(crate) async fn change_password_route(
 		.as_ref()
 		.ok_or_else(|| err!(Request(MissingToken("Missing access token."))))?;
 
+	let mut stages = vec![AuthType::Password];
+	if services.users.totp_enabled(sender_user).await {
+		stages.push(AuthType::from(TOTP_AUTH_TYPE));
+	}
+
 	let mut uiaainfo = UiaaInfo {
-		flows: vec![AuthFlow { stages: vec![AuthType::Password] }],
+		flows: vec![AuthFlow { stages }],
 		completed: Vec::new(),
 		params: Box::default(),
 		session: None,
@@ -701,10 +720,13 @@ pub(crate) async fn change_password_route(
 
 	info!("User {sender_user} changed their password.");
 
-	if services.server.config.admin_room_notices {
+	if !services.users.is_bot(sender_user).await {
 		services
 			.admin
-			.notice(&format!("User {sender_user} changed their password."))
+			.category_notice(
+				NoticeCategory::Password,
+				&format!("User {sender_user} changed their password."),
+			)
 			.await;
 	}
 
@@ -807,10 +829,13 @@ pub(crate) async fn deactivate_route(
 
 	info!("User {sender_user} deactivated their account.");
 
-	if services.server.config.admin_room_notices {
+	if !services.users.is_bot(sender_user).await {
 		services
 			.admin
-			.notice(&format!("User {sender_user} deactivated their account."))
+			.category_notice(
+				NoticeCategory::Deactivation,
+				&format!("User {sender_user} deactivated their account."),
+			)
 			.await;
 	}
 
@@ -34,6 +34,12 @@ pub(crate) async fn create_alias_route(
 		return Err!(Request(Forbidden("Room alias is forbidden.")));
 	}
 
+	let privileged = body.appservice_info.is_some() || services.users.is_admin(sender_user).await;
+
+	services
+		.globals
+		.check_alias_policy(body.room_alias.alias(), privileged)?;
+
 	if services
 		.rooms
 		.alias
@@ -28,6 +28,17 @@ pub(crate) async fn create_backup_version_route(
 		.key_backups
 		.create_backup(body.sender_user(), &body.algorithm)?;
 
+	if let Some(retain) = services
+		.server
+		.config
+		.key_backup_versions_retained
+	{
+		services
+			.key_backups
+			.prune_backups(body.sender_user(), retain.max(1))
+			.await;
+	}
+
 	Ok(create_backup_version::v3::Response { version })
 }
 
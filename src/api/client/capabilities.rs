@@ -49,5 +49,9 @@ pub(crate) async fn get_capabilities_route(
 		json!({"enabled": services.config.forget_forced_upon_leave}),
 	)?;
 
+	for (capability, value) in &services.config.capabilities_extra {
+		capabilities.set(capability, value.clone())?;
+	}
+
 	Ok(get_capabilities::v3::Response { capabilities })
 }
@@ -5,18 +5,24 @@
 };
 use ruma::{OwnedEventId, UserId, api::client::context::get_context, events::StateEventType};
 use tuwunel_core::{
-	Err, Event, Result, at, debug_warn, err, ref_at,
+	Err, Event, Result, at, debug_warn, err,
+	matrix::event::Matches,
+	ref_at,
 	utils::{
 		IterStream,
 		future::TryExtExt,
 		stream::{BroadbandExt, ReadyExt, TryIgnore, WidebandExt},
 	},
 };
-use tuwunel_service::rooms::{lazy_loading, lazy_loading::Options, short::ShortStateKey};
+use tuwunel_service::rooms::{
+	lazy_loading,
+	lazy_loading::{Options, Witness},
+	short::ShortStateKey,
+};
 
 use crate::{
 	Ruma,
-	client::message::{event_filter, ignored_filter, lazy_loading_witness, visibility_filter},
+	client::message::{event_filter, ignored_filter, visibility_filter},
 };
 
 const LIMIT_MAX: usize = 100;
@@ -26,6 +32,10 @@
 ///
 /// Allows loading room history around an event.
 ///
+/// - The `filter` applies to the surrounding events and the returned state;
+///   with `lazy_load_members` only the membership of the returned events'
+///   senders is included.
+///
 /// - Only works if the user is joined (TODO: always allow, but only show events
 ///   if the user was joined, depending on history_visibility)
 pub(crate) async fn get_context_route(
@@ -117,16 +127,27 @@ pub(crate) async fn get_context_route(
 		options: Some(&filter.lazy_load_options),
 	};
 
+	// Only the senders of the returned events are witnessed; unlike /messages,
+	// read receipts are not returned here so their senders are not included.
 	let lazy_loading_witnessed: OptionFuture<_> = filter
 		.lazy_load_options
 		.is_enabled()
-		.then_some(
+		.then(|| {
 			base_event
 				.iter()
 				.chain(events_before.iter())
-				.chain(events_after.iter()),
-		)
-		.map(|witnessed| lazy_loading_witness(&services, &lazy_loading_context, witnessed))
+				.chain(events_after.iter())
+				.map(ref_at!(1))
+				.map(Event::sender)
+				.map(ToOwned::to_owned)
+				.collect::<Witness>()
+		})
+		.map(|witness| {
+			services
+				.rooms
+				.lazy_loading
+				.witness_retain(witness, &lazy_loading_context)
+		})
 		.into();
 
 	let state_at = events_after
@@ -188,6 +209,7 @@ pub(crate) async fn get_context_route(
 				.get_pdu(event_id.as_ref())
 				.ok()
 		})
+		.ready_filter(|pdu| filter.matches(pdu))
 		.map(Event::into_format)
 		.collect()
 		.await;
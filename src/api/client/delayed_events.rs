@@ -0,0 +1,209 @@
+//! Delayed events (MSC4140). Scheduling uses a dedicated endpoint rather than
+//! a query parameter on the send and state endpoints. Ruma does not define
+//! these endpoints, so their request types are defined here.
+
+use std::time::Duration;
+
+use axum::extract::State;
+use futures::StreamExt;
+use tuwunel_core::{Err, Result};
+
+use crate::Ruma;
+
+/// `POST /_matrix/client/unstable/org.matrix.msc4140/rooms/{roomId}/send_delay`
+pub(crate) mod send_delayed_event {
+	use ruma::{
+		OwnedRoomId,
+		api::{Metadata, request, response},
+		events::TimelineEventType,
+		metadata,
+	};
+	use serde_json::value::RawValue as RawJsonValue;
+
+	const METADATA: Metadata = metadata! {
+		method: POST,
+		rate_limited: true,
+		authentication: AccessToken,
+		history: {
+			unstable => "/_matrix/client/unstable/org.matrix.msc4140/rooms/{room_id}/send_delay",
+		}
+	};
+
+	#[request]
+	pub struct Request {
+		#[ruma_api(path)]
+		pub room_id: OwnedRoomId,
+
+		#[serde(rename = "type")]
+		pub event_type: TimelineEventType,
+
+		/// Present for state events.
+		#[serde(skip_serializing_if = "Option::is_none")]
+		pub state_key: Option<String>,
+
+		pub content: Box<RawJsonValue>,
+
+		/// Delay in milliseconds.
+		pub delay: u64,
+	}
+
+	#[response]
+	pub struct Response {
+		pub delay_id: String,
+	}
+}
+
+/// `GET /_matrix/client/unstable/org.matrix.msc4140/delayed_events`
+pub(crate) mod get_delayed_events {
+	use ruma::{
+		api::{Metadata, request, response},
+		metadata,
+	};
+	use serde::{Deserialize, Serialize};
+	use tuwunel_service::rooms::delayed_events::DelayedEvent;
+
+	const METADATA: Metadata = metadata! {
+		method: GET,
+		rate_limited: false,
+		authentication: AccessToken,
+		history: {
+			unstable => "/_matrix/client/unstable/org.matrix.msc4140/delayed_events",
+		}
+	};
+
+	#[request]
+	pub struct Request {}
+
+	#[response]
+	pub struct Response {
+		pub delayed_events: Vec<PendingEvent>,
+	}
+
+	#[derive(Clone, Debug, Deserialize, Serialize)]
+	pub struct PendingEvent {
+		pub delay_id: String,
+
+		#[serde(flatten)]
+		pub event: DelayedEvent,
+	}
+}
+
+/// `POST /_matrix/client/unstable/org.matrix.msc4140/delayed_events/{delayId}`
+pub(crate) mod update_delayed_event {
+	use ruma::{
+		api::{Metadata, request, response},
+		metadata,
+	};
+	use serde::{Deserialize, Serialize};
+
+	const METADATA: Metadata = metadata! {
+		method: POST,
+		rate_limited: true,
+		authentication: AccessToken,
+		history: {
+			unstable => "/_matrix/client/unstable/org.matrix.msc4140/delayed_events/{delay_id}",
+		}
+	};
+
+	#[request]
+	pub struct Request {
+		#[ruma_api(path)]
+		pub delay_id: String,
+
+		pub action: UpdateAction,
+	}
+
+	#[response]
+	pub struct Response {}
+
+	#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+	#[serde(rename_all = "snake_case")]
+	pub enum UpdateAction {
+		Cancel,
+		Restart,
+		Send,
+	}
+}
+
+/// # `POST /_matrix/client/unstable/org.matrix.msc4140/rooms/{roomId}/send_delay`
+///
+/// Schedules an event of `type` with `content`, and a `state_key` for state
+/// events, to be sent into the room after `delay` milliseconds. Returns the
+/// `delay_id` by which it can be updated.
+pub(crate) async fn send_delayed_event_route(
+	State(services): State<crate::State>,
+	body: Ruma<send_delayed_event::Request>,
+) -> Result<send_delayed_event::Response> {
+	let sender_user = body.sender_user();
+	if !services
+		.rooms
+		.state_cache
+		.is_joined(sender_user, &body.room_id)
+		.await
+	{
+		return Err!(Request(Forbidden("You are not joined to this room.")));
+	}
+
+	let delay_id = services
+		.rooms
+		.delayed_events
+		.schedule(
+			sender_user,
+			body.room_id.clone(),
+			body.event_type.clone(),
+			body.state_key.clone(),
+			body.content.clone(),
+			Duration::from_millis(body.delay),
+		)
+		.await?;
+
+	Ok(send_delayed_event::Response { delay_id })
+}
+
+/// # `GET /_matrix/client/unstable/org.matrix.msc4140/delayed_events`
+///
+/// Lists the user's pending delayed events.
+pub(crate) async fn get_delayed_events_route(
+	State(services): State<crate::State>,
+	body: Ruma<get_delayed_events::Request>,
+) -> Result<get_delayed_events::Response> {
+	let delayed_events = services
+		.rooms
+		.delayed_events
+		.delayed_events(body.sender_user())
+		.map(|(delay_id, event)| get_delayed_events::PendingEvent { delay_id, event })
+		.collect()
+		.await;
+
+	Ok(get_delayed_events::Response { delayed_events })
+}
+
+/// # `POST /_matrix/client/unstable/org.matrix.msc4140/delayed_events/{delayId}`
+///
+/// Cancels, restarts or immediately sends a pending delayed event according to
+/// the `action` in the body.
+pub(crate) async fn update_delayed_event_route(
+	State(services): State<crate::State>,
+	body: Ruma<update_delayed_event::Request>,
+) -> Result<update_delayed_event::Response> {
+	use update_delayed_event::UpdateAction;
+
+	let sender_user = body.sender_user();
+	let delayed_events = &services.rooms.delayed_events;
+	match body.action {
+		| UpdateAction::Cancel =>
+			delayed_events
+				.cancel(sender_user, &body.delay_id)
+				.await?,
+		| UpdateAction::Restart =>
+			delayed_events
+				.restart(sender_user, &body.delay_id)
+				.await?,
+		| UpdateAction::Send =>
+			delayed_events
+				.send_now(sender_user, &body.delay_id)
+				.await?,
+	}
+
+	Ok(update_delayed_event::Response {})
+}
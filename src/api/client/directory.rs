@@ -27,7 +27,9 @@
 	uint,
 };
 use tuwunel_core::{
-	Err, Result, err, info,
+	Err, Result,
+	config::NoticeCategory,
+	err, info,
 	matrix::Event,
 	utils::{
 		TryFutureExtExt,
@@ -51,18 +53,8 @@ pub(crate) async fn get_public_rooms_filtered_route(
 	InsecureClientIp(client): InsecureClientIp,
 	body: Ruma<get_public_rooms_filtered::v3::Request>,
 ) -> Result<get_public_rooms_filtered::v3::Response> {
-	if let Some(server) = &body.server {
-		if services
-			.config
-			.forbidden_remote_room_directory_server_names
-			.is_match(server.host())
-			|| services
-				.config
-				.forbidden_remote_server_names
-				.is_match(server.host())
-		{
-			return Err!(Request(Forbidden("Server is banned on this homeserver.")));
-		}
+	if let Some(server) = body.server.as_deref() {
+		check_remote_directory_server(&services, server).await?;
 	}
 
 	let response = get_public_rooms_filtered_helper(
@@ -92,18 +84,8 @@ pub(crate) async fn get_public_rooms_route(
 	InsecureClientIp(client): InsecureClientIp,
 	body: Ruma<get_public_rooms::v3::Request>,
 ) -> Result<get_public_rooms::v3::Response> {
-	if let Some(server) = &body.server {
-		if services
-			.config
-			.forbidden_remote_room_directory_server_names
-			.is_match(server.host())
-			|| services
-				.config
-				.forbidden_remote_server_names
-				.is_match(server.host())
-		{
-			return Err!(Request(Forbidden("Server is banned on this homeserver.")));
-		}
+	if let Some(server) = body.server.as_deref() {
+		check_remote_directory_server(&services, server).await?;
 	}
 
 	let response = get_public_rooms_filtered_helper(
@@ -177,16 +159,17 @@ pub(crate) async fn set_room_visibility_route(
 					body.room_id
 				);
 
-				if services.server.config.admin_room_notices {
-					services
-						.admin
-						.send_text(&format!(
+				services
+					.admin
+					.category_text(
+						NoticeCategory::Directory,
+						&format!(
 							"Non-admin user {sender_user} tried to publish {0} to the room \
 							 directory while \"lockdown_public_room_directory\" is enabled",
 							body.room_id
-						))
-						.await;
-				}
+						),
+					)
+					.await;
 
 				return Err!(Request(Forbidden(
 					"Publishing rooms to the room directory is not allowed",
@@ -195,15 +178,13 @@ pub(crate) async fn set_room_visibility_route(
 
 			services.rooms.directory.set_public(&body.room_id);
 
-			if services.server.config.admin_room_notices {
-				services
-					.admin
-					.send_text(&format!(
-						"{sender_user} made {} public to the room directory",
-						body.room_id
-					))
-					.await;
-			}
+			services
+				.admin
+				.category_text(
+					NoticeCategory::Directory,
+					&format!("{sender_user} made {} public to the room directory", body.room_id),
+				)
+				.await;
 			info!("{sender_user} made {0} public to the room directory", body.room_id);
 		},
 		| room::Visibility::Private => services
@@ -308,13 +289,13 @@ pub(crate) async fn get_public_rooms_filtered_helper(
 		}
 	}
 
-	let mut all_rooms: Vec<PublicRoomsChunk> = services
+	let mut all_rooms: Vec<(Option<u64>, PublicRoomsChunk)> = services
 		.rooms
 		.directory
 		.public_rooms()
 		.map(ToOwned::to_owned)
-		.wide_then(|room_id| public_rooms_chunk(services, room_id))
-		.ready_filter_map(|chunk| {
+		.wide_then(|room_id| curated_rooms_chunk(services, room_id))
+		.ready_filter_map(|(pin, chunk)| {
 			if !filter.room_types.is_empty() && !filter.room_types.contains(&RoomTypeFilter::from(chunk.room_type.clone())) {
 				return None;
 			}
@@ -322,19 +303,19 @@ pub(crate) async fn get_public_rooms_filtered_helper(
 			if let Some(query) = filter.generic_search_term.as_ref().map(|q| q.to_lowercase()) {
 				if let Some(name) = &chunk.name {
 					if name.as_str().to_lowercase().contains(&query) {
-						return Some(chunk);
+						return Some((pin, chunk));
 					}
 				}
 
 				if let Some(topic) = &chunk.topic {
 					if topic.to_lowercase().contains(&query) {
-						return Some(chunk);
+						return Some((pin, chunk));
 					}
 				}
 
 				if let Some(canonical_alias) = &chunk.canonical_alias {
 					if canonical_alias.as_str().to_lowercase().contains(&query) {
-						return Some(chunk);
+						return Some((pin, chunk));
 					}
 				}
 
@@ -342,13 +323,20 @@ pub(crate) async fn get_public_rooms_filtered_helper(
 			}
 
 			// No search term
-			Some(chunk)
+			Some((pin, chunk))
 		})
 		// We need to collect all, so we can sort by member count
 		.collect()
 		.await;
 
-	all_rooms.sort_by(|l, r| r.num_joined_members.cmp(&l.num_joined_members));
+	// Pinned rooms first in their order, then the rest by member count.
+	all_rooms.sort_by(|(l_pin, l), (r_pin, r)| {
+		l_pin
+			.is_none()
+			.cmp(&r_pin.is_none())
+			.then(l_pin.cmp(r_pin))
+			.then(r.num_joined_members.cmp(&l.num_joined_members))
+	});
 
 	let total_room_count_estimate = UInt::try_from(all_rooms.len())
 		.unwrap_or_else(|_| uint!(0))
@@ -356,6 +344,7 @@ pub(crate) async fn get_public_rooms_filtered_helper(
 
 	let chunk: Vec<_> = all_rooms
 		.into_iter()
+		.map(|(_, chunk)| chunk)
 		.skip(num_since)
 		.take(limit)
 		.collect();
@@ -377,6 +366,35 @@ pub(crate) async fn get_public_rooms_filtered_helper(
 	})
 }
 
+/// Check whether our users' directory requests may be forwarded to `server`.
+/// Requests for our own directory are never forwarded, so always pass.
+async fn check_remote_directory_server(services: &Services, server: &ServerName) -> Result {
+	if services.globals.server_is_ours(server) {
+		return Ok(());
+	}
+
+	let config = &services.config;
+	if config
+		.forbidden_remote_room_directory_server_names
+		.is_match(server.host())
+		|| !services
+			.federation
+			.is_federation_allowed(server)
+			.await
+	{
+		return Err!(Request(Forbidden("Server is banned on this homeserver.")));
+	}
+
+	let allowed = &config.allowed_remote_room_directory_server_names;
+	if !allowed.is_empty() && !allowed.is_match(server.host()) {
+		return Err!(Request(Forbidden(
+			"Room directory requests to this server are not allowed."
+		)));
+	}
+
+	Ok(())
+}
+
 /// Check whether the user can publish to the room directory via power levels of
 /// room history visibility event or room creator
 async fn user_can_publish_room(
@@ -410,6 +428,21 @@ async fn user_can_publish_room(
 	}
 }
 
+/// Chunk of a room in our directory with the presentation set by
+/// administrators applied, and its pinned position.
+async fn curated_rooms_chunk(
+	services: &Services,
+	room_id: OwnedRoomId,
+) -> (Option<u64>, PublicRoomsChunk) {
+	let curation = services.rooms.directory.curation(&room_id).await;
+	let mut chunk = public_rooms_chunk(services, room_id).await;
+	if let Some(description) = curation.description {
+		chunk.topic = Some(description);
+	}
+
+	(curation.pin, chunk)
+}
+
 async fn public_rooms_chunk(services: &Services, room_id: OwnedRoomId) -> PublicRoomsChunk {
 	let name = services
 		.rooms
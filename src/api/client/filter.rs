@@ -24,13 +24,15 @@ pub(crate) async fn get_filter_route(
 /// # `PUT /_matrix/client/r0/user/{userId}/filter`
 ///
 /// Creates a new filter to be used by other endpoints.
+///
+/// - Uploading an identical filter again returns the existing filter ID
 pub(crate) async fn create_filter_route(
 	State(services): State<crate::State>,
 	body: Ruma<create_filter::v3::Request>,
 ) -> Result<create_filter::v3::Response> {
 	let filter_id = services
 		.users
-		.create_filter(body.sender_user(), &body.filter);
+		.create_filter(body.sender_user(), &body.filter)?;
 
 	Ok(create_filter::v3::Response::new(filter_id))
 }
@@ -20,8 +20,13 @@
 	serde::Raw,
 };
 use serde_json::json;
-use tuwunel_core::{Err, Error, Result, debug, debug_warn, err, result::NotFound, utils};
-use tuwunel_service::{Services, users::parse_master_key};
+use tuwunel_core::{
+	Err, Error, Result, StreamToken, debug, debug_warn, err, result::NotFound, utils,
+};
+use tuwunel_service::{
+	Services,
+	users::{RemoteKeys, parse_master_key},
+};
 
 use super::SESSION_ID_LENGTH;
 use crate::Ruma;
@@ -378,13 +383,15 @@ pub(crate) async fn get_key_changes_route(
 
 	let from = body
 		.from
-		.parse()
-		.map_err(|_| Error::BadRequest(ErrorKind::InvalidParam, "Invalid `from`."))?;
+		.parse::<StreamToken>()
+		.map_err(|_| Error::BadRequest(ErrorKind::InvalidParam, "Invalid `from`."))?
+		.global();
 
 	let to = body
 		.to
-		.parse()
-		.map_err(|_| Error::BadRequest(ErrorKind::InvalidParam, "Invalid `to`."))?;
+		.parse::<StreamToken>()
+		.map_err(|_| Error::BadRequest(ErrorKind::InvalidParam, "Invalid `to`."))?
+		.global();
 
 	device_list_updates.extend(
 		services
@@ -432,7 +439,15 @@ pub(crate) async fn get_keys_helper<F>(
 	let mut master_keys = BTreeMap::new();
 	let mut self_signing_keys = BTreeMap::new();
 	let mut user_signing_keys = BTreeMap::new();
-	let mut device_keys = BTreeMap::new();
+	let mut device_keys = if services.server.config.appservice_key_proxy {
+		let local_users = device_keys_input
+			.iter()
+			.filter(|(user_id, _)| services.globals.user_is_local(user_id));
+
+		services.appservice.query_keys(local_users).await
+	} else {
+		BTreeMap::new()
+	};
 
 	let mut get_over_federation = HashMap::new();
 
@@ -440,6 +455,34 @@ pub(crate) async fn get_keys_helper<F>(
 		let user_id: &UserId = user_id;
 
 		if !services.globals.user_is_local(user_id) {
+			if let Some(cached) = services.users.cached_remote_keys(user_id) {
+				let devices = cached
+					.device_keys
+					.into_iter()
+					.filter(|(device_id, _)| {
+						device_ids.is_empty() || device_ids.contains(device_id)
+					});
+
+				device_keys
+					.entry(user_id.to_owned())
+					.or_default()
+					.extend(devices);
+
+				if let Ok(master_key) = services
+					.users
+					.get_master_key(sender_user, user_id, &allowed_signatures)
+					.await
+				{
+					master_keys.insert(user_id.to_owned(), master_key);
+				}
+
+				if let Some(self_signing_key) = cached.self_signing_key {
+					self_signing_keys.insert(user_id.to_owned(), self_signing_key);
+				}
+
+				continue;
+			}
+
 			get_over_federation
 				.entry(user_id.server_name())
 				.or_insert_with(Vec::new)
@@ -472,7 +515,10 @@ pub(crate) async fn get_keys_helper<F>(
 				}
 			}
 
-			device_keys.insert(user_id.to_owned(), container);
+			device_keys
+				.entry(user_id.to_owned())
+				.or_default()
+				.extend(container);
 		} else {
 			for device_id in device_ids {
 				let mut container = BTreeMap::new();
@@ -497,7 +543,10 @@ pub(crate) async fn get_keys_helper<F>(
 					container.insert(device_id.to_owned(), keys);
 				}
 
-				device_keys.insert(user_id.to_owned(), container);
+				device_keys
+					.entry(user_id.to_owned())
+					.or_default()
+					.extend(container);
 			}
 		}
 
@@ -528,7 +577,7 @@ pub(crate) async fn get_keys_helper<F>(
 		.into_iter()
 		.map(|(server, vec)| async move {
 			let mut device_keys_input_fed = BTreeMap::new();
-			for (user_id, keys) in vec {
+			for &(user_id, keys) in &vec {
 				device_keys_input_fed.insert(user_id.to_owned(), keys.clone());
 			}
 
@@ -540,13 +589,30 @@ pub(crate) async fn get_keys_helper<F>(
 				.send_federation_request(server, request)
 				.await;
 
-			(server, response)
+			(server, vec, response)
 		})
 		.collect();
 
-	while let Some((server, response)) = futures.next().await {
+	while let Some((server, users, response)) = futures.next().await {
 		match response {
 			| Ok(response) => {
+				// Only queries for all of a user's devices are complete enough to cache.
+				for (user_id, _) in users
+					.iter()
+					.filter(|(_, device_ids)| device_ids.is_empty())
+				{
+					services
+						.users
+						.cache_remote_keys(user_id, RemoteKeys {
+							device_keys: response
+								.device_keys
+								.get(*user_id)
+								.cloned()
+								.unwrap_or_default(),
+							self_signing_key: response.self_signing_keys.get(*user_id).cloned(),
+						});
+				}
+
 				for (user, master_key) in response.master_keys {
 					let (master_key_id, mut master_key) = parse_master_key(&user, &master_key)?;
 
@@ -625,7 +691,15 @@ pub(crate) async fn claim_keys_helper(
 	services: &Services,
 	one_time_keys_input: &BTreeMap<OwnedUserId, BTreeMap<OwnedDeviceId, OneTimeKeyAlgorithm>>,
 ) -> Result<claim_keys::v3::Response> {
-	let mut one_time_keys = BTreeMap::new();
+	let mut one_time_keys = if services.server.config.appservice_key_proxy {
+		let local_users = one_time_keys_input
+			.iter()
+			.filter(|(user_id, _)| services.globals.user_is_local(user_id));
+
+		services.appservice.claim_keys(local_users).await
+	} else {
+		BTreeMap::new()
+	};
 
 	let mut get_over_federation = BTreeMap::new();
 
@@ -639,6 +713,15 @@ pub(crate) async fn claim_keys_helper(
 
 		let mut container = BTreeMap::new();
 		for (device_id, key_algorithm) in map {
+			let claimed = one_time_keys
+				.get(user_id)
+				.is_some_and(|devices| devices.contains_key(device_id));
+
+			// Already claimed from the appservice.
+			if claimed {
+				continue;
+			}
+
 			if let Ok(one_time_keys) = services
 				.users
 				.take_one_time_key(user_id, device_id, key_algorithm)
@@ -649,7 +732,10 @@ pub(crate) async fn claim_keys_helper(
 				container.insert(device_id.clone(), c);
 			}
 		}
-		one_time_keys.insert(user_id.clone(), container);
+		one_time_keys
+			.entry(user_id.clone())
+			.or_default()
+			.extend(container);
 	}
 
 	let mut failures = BTreeMap::new();
@@ -14,7 +14,9 @@
 	},
 };
 use tuwunel_core::{
-	Err, Result, err,
+	Err, Result,
+	config::RouteClass,
+	err,
 	utils::{self, content_disposition::make_content_disposition, math::ruma_from_usize},
 };
 use tuwunel_service::{
@@ -30,7 +32,12 @@ pub(crate) async fn get_media_config_route(
 	_body: Ruma<get_media_config::v1::Request>,
 ) -> Result<get_media_config::v1::Response> {
 	Ok(get_media_config::v1::Response {
-		upload_size: ruma_from_usize(services.server.config.max_request_size),
+		upload_size: ruma_from_usize(
+			services
+				.server
+				.config
+				.request_size(RouteClass::Upload),
+		),
 	})
 }
 
@@ -66,6 +73,8 @@ pub(crate) async fn create_content_route(
 		.create(mxc, Some(user), Some(&content_disposition), content_type, &body.file)
 		.await?;
 
+	services.media.pregenerate_thumbnails(mxc);
+
 	let blurhash = body.generate_blurhash.then(|| {
 		services
 			.media
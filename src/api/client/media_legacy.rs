@@ -11,7 +11,9 @@
 	},
 };
 use tuwunel_core::{
-	Err, Result, err,
+	Err, Result,
+	config::RouteClass,
+	err,
 	utils::{content_disposition::make_content_disposition, math::ruma_from_usize},
 };
 use tuwunel_service::media::{CACHE_CONTROL_IMMUTABLE, CORP_CROSS_ORIGIN, Dim, FileMeta};
@@ -26,7 +28,12 @@ pub(crate) async fn get_media_config_legacy_route(
 	_body: Ruma<get_media_config::v3::Request>,
 ) -> Result<get_media_config::v3::Response> {
 	Ok(get_media_config::v3::Response {
-		upload_size: ruma_from_usize(services.server.config.max_request_size),
+		upload_size: ruma_from_usize(
+			services
+				.server
+				.config
+				.request_size(RouteClass::Upload),
+		),
 	})
 }
 
@@ -145,6 +152,7 @@ pub(crate) async fn get_content_legacy_route(
 		media_id: &body.media_id,
 	};
 
+	services.media.check_legacy_freeze(&mxc).await?;
 	match services.media.get(&mxc).await? {
 		| Some(FileMeta {
 			content,
@@ -236,6 +244,7 @@ pub(crate) async fn get_content_as_filename_legacy_route(
 		media_id: &body.media_id,
 	};
 
+	services.media.check_legacy_freeze(&mxc).await?;
 	match services.media.get(&mxc).await? {
 		| Some(FileMeta {
 			content,
@@ -326,6 +335,8 @@ pub(crate) async fn get_content_thumbnail_legacy_route(
 		media_id: &body.media_id,
 	};
 
+	services.media.check_legacy_freeze(&mxc).await?;
+
 	let dim = Dim::from_ruma(body.width, body.height, body.method.clone())?;
 	match services.media.get_thumbnail(&mxc, &dim).await? {
 		| Some(FileMeta {
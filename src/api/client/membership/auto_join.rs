@@ -0,0 +1,118 @@
+use futures::FutureExt;
+use ruma::{OwnedRoomOrAliasId, UserId};
+use tuwunel_core::{Error, Result, debug_warn, error, info};
+use tuwunel_service::Services;
+
+use super::{invite_helper, join_room_by_id_helper};
+
+/// How a new account came to exist; selects the configured auto-join lists
+/// which apply to it.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AutoJoinCohort {
+	/// Registered with a password, or created by an admin.
+	Password,
+
+	/// Registered using a registration token.
+	Token,
+
+	/// Created on first login through LDAP.
+	Ldap,
+
+	/// Created on first login through SSO.
+	Sso,
+
+	/// Guest registration.
+	Guest,
+}
+
+/// Joins (or invites, per `auto_join.invite`) a newly created user to the
+/// configured auto-join rooms for their cohort. Failures are logged and
+/// returned but never abort the caller, so account creation cannot fail here.
+#[tracing::instrument(skip(services), level = "debug")]
+pub async fn auto_join_rooms<'a>(
+	services: &'a Services,
+	user_id: &UserId,
+	cohort: AutoJoinCohort,
+) -> Vec<(&'a OwnedRoomOrAliasId, Error)> {
+	let config = &services.server.config;
+	let common = (cohort != AutoJoinCohort::Guest || config.allow_guests_auto_join_rooms)
+		.then_some(&config.auto_join_rooms);
+
+	let cohort_rooms: &[OwnedRoomOrAliasId] = match cohort {
+		| AutoJoinCohort::Password => &[],
+		| AutoJoinCohort::Token => &config.auto_join.token,
+		| AutoJoinCohort::Ldap => &config.auto_join.ldap,
+		| AutoJoinCohort::Sso => &config.auto_join.sso,
+		| AutoJoinCohort::Guest => &config.auto_join.guest,
+	};
+
+	let mut failed = Vec::new();
+	for room in common.into_iter().flatten().chain(cohort_rooms) {
+		match auto_join_room(services, user_id, room).await {
+			| Ok(true) => {
+				info!(%user_id, invite = config.auto_join.invite, "Automatically joined room {room}");
+			},
+			| Ok(false) => {},
+			| Err(e) => {
+				// don't return this error so we don't fail registrations
+				error!(%user_id, "Failed to automatically join room {room}: {e}");
+				failed.push((room, e));
+			},
+		}
+	}
+
+	failed
+}
+
+async fn auto_join_room(
+	services: &Services,
+	user_id: &UserId,
+	room: &OwnedRoomOrAliasId,
+) -> Result<bool> {
+	let room_id = services.rooms.alias.resolve(room).await?;
+
+	if !services
+		.rooms
+		.state_cache
+		.server_in_room(services.globals.server_name(), &room_id)
+		.await
+	{
+		debug_warn!("Skipping room {room} to automatically join as we have never joined before.");
+		return Ok(false);
+	}
+
+	if services.server.config.auto_join.invite {
+		invite_helper(
+			services,
+			&services.globals.server_user,
+			user_id,
+			&room_id,
+			Some("Automatically inviting to this room upon registration".to_owned()),
+			false,
+		)
+		.boxed()
+		.await?;
+
+		return Ok(true);
+	}
+
+	let servers: Vec<_> = Some(services.globals.server_name())
+		.into_iter()
+		.chain(room.server_name())
+		.map(ToOwned::to_owned)
+		.collect();
+
+	join_room_by_id_helper(
+		services,
+		user_id,
+		&room_id,
+		Some("Automatically joining this room upon registration".to_owned()),
+		&servers,
+		None,
+		&None,
+	)
+	.boxed()
+	.await?;
+
+	Ok(true)
+}
@@ -0,0 +1,47 @@
+use std::sync::Arc;
+
+use futures::FutureExt;
+use ruma::{OwnedRoomId, OwnedServerName, OwnedUserId};
+use tuwunel_core::{Result, err};
+use tuwunel_service::{Services, rooms::state_cache::UpgradeHook};
+
+use super::join_room_by_id_helper;
+
+/// Build the hook by which the membership service joins local users to the
+/// replacement of a tombstoned room. The predecessor is tagged low priority
+/// once the join succeeds, so the tag is not copied to the new room.
+pub(crate) fn follow_room_upgrade_hook(services: &Arc<Services>) -> Arc<UpgradeHook> {
+	let services = Arc::downgrade(services);
+	Arc::new(
+		move |user_id: OwnedUserId,
+		      predecessor: OwnedRoomId,
+		      replacement: OwnedRoomId,
+		      via: Vec<OwnedServerName>| {
+			let services = services.clone();
+			async move {
+				let services = services
+					.upgrade()
+					.ok_or_else(|| err!("Services are shutting down"))?;
+
+				join_room_by_id_helper(
+					&services,
+					&user_id,
+					&replacement,
+					Some("Following room upgrade".to_owned()),
+					&via,
+					None,
+					&None,
+				)
+				.boxed()
+				.await?;
+
+				services
+					.rooms
+					.state_cache
+					.mark_low_priority(&predecessor, &user_id)
+					.await
+			}
+			.boxed()
+		},
+	)
+}
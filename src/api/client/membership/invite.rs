@@ -76,6 +76,12 @@ pub(crate) async fn invite_user_route(
 				return Ok(invite_user::v3::Response {});
 			}
 
+			services
+				.rooms
+				.membership_limit
+				.check(sender_user, &body.room_id)
+				.await?;
+
 			invite_helper(
 				&services,
 				sender_user,
@@ -87,6 +93,12 @@ pub(crate) async fn invite_user_route(
 			.boxed()
 			.await?;
 
+			services
+				.rooms
+				.membership_limit
+				.count(sender_user, &body.room_id)
+				.await;
+
 			Ok(invite_user::v3::Response {})
 		},
 		| _ => {
@@ -112,6 +124,17 @@ pub(crate) async fn invite_helper(
 	}
 
 	if !services.globals.user_is_local(user_id) {
+		if !services
+			.rooms
+			.state_accessor
+			.is_federated(room_id)
+			.await
+		{
+			return Err!(Request(Forbidden(
+				"This room is not federated; users of other servers cannot be invited."
+			)));
+		}
+
 		let (pdu, pdu_json, invite_room_state) = {
 			let state_lock = services.rooms.state.mutex.lock(room_id).await;
 
@@ -5,7 +5,7 @@
 use futures::{FutureExt, StreamExt};
 use ruma::{
 	CanonicalJsonObject, CanonicalJsonValue, OwnedRoomId, OwnedServerName, OwnedUserId, RoomId,
-	RoomVersionId, UserId,
+	RoomVersionId, ServerName, UserId,
 	api::{
 		client::{
 			error::ErrorKind,
@@ -105,7 +105,13 @@ pub(crate) async fn join_room_by_id_route(
 	servers.dedup();
 	shuffle(&mut servers);
 
-	join_room_by_id_helper(
+	services
+		.rooms
+		.membership_limit
+		.check(sender_user, &body.room_id)
+		.await?;
+
+	let response = join_room_by_id_helper(
 		&services,
 		sender_user,
 		&body.room_id,
@@ -115,7 +121,15 @@ pub(crate) async fn join_room_by_id_route(
 		&body.appservice_info,
 	)
 	.boxed()
-	.await
+	.await?;
+
+	services
+		.rooms
+		.membership_limit
+		.count(sender_user, &body.room_id)
+		.await;
+
+	Ok(response)
 }
 
 /// # `POST /_matrix/client/r0/join/{roomIdOrAlias}`
@@ -231,6 +245,12 @@ pub(crate) async fn join_room_by_id_or_alias_route(
 		},
 	};
 
+	services
+		.rooms
+		.membership_limit
+		.check(sender_user, &room_id)
+		.await?;
+
 	let join_room_response = join_room_by_id_helper(
 		&services,
 		sender_user,
@@ -243,6 +263,12 @@ pub(crate) async fn join_room_by_id_or_alias_route(
 	.boxed()
 	.await?;
 
+	services
+		.rooms
+		.membership_limit
+		.count(sender_user, &room_id)
+		.await;
+
 	Ok(join_room_by_id_or_alias::v3::Response { room_id: join_room_response.room_id })
 }
 
@@ -319,7 +345,10 @@ pub async fn join_room_by_id_helper(
 		.boxed()
 		.await?;
 	} else {
-		// Ask a remote server if we are not participating in this room
+		// Ask a remote server if we are not participating in this room. The room
+		// lock is not held across the federation handshake; it is reacquired once
+		// the response is ready to be applied.
+		drop(state_lock);
 		join_room_by_id_helper_remote(
 			services,
 			sender_user,
@@ -327,7 +356,6 @@ pub async fn join_room_by_id_helper(
 			reason,
 			servers,
 			third_party_signed,
-			state_lock,
 		)
 		.boxed()
 		.await?;
@@ -344,7 +372,6 @@ async fn join_room_by_id_helper_remote(
 	reason: Option<String>,
 	servers: &[OwnedServerName],
 	_third_party_signed: Option<&ThirdPartySigned>,
-	state_lock: RoomMutexGuard,
 ) -> Result {
 	info!("Joining {room_id} over federation.");
 
@@ -373,21 +400,8 @@ async fn join_room_by_id_helper_remote(
 			)))
 		})?;
 
-	let join_authorized_via_users_server = {
-		use RoomVersionId::*;
-		if !matches!(room_version_id, V1 | V2 | V3 | V4 | V5 | V6 | V7) {
-			join_event_stub
-				.get("content")
-				.map(|s| {
-					s.as_object()?
-						.get("join_authorised_via_users_server")?
-						.as_str()
-				})
-				.and_then(|s| OwnedUserId::try_from(s.unwrap_or_default()).ok())
-		} else {
-			None
-		}
-	};
+	let join_authorized_via_users_server =
+		join_authorised_via(&join_event_stub, &room_version_id, &remote_server)?;
 
 	join_event_stub.insert(
 		"origin".to_owned(),
@@ -653,6 +667,8 @@ async fn join_room_by_id_helper_remote(
 		.save_state(room_id, Arc::new(compressed))
 		.await?;
 
+	let state_lock = services.rooms.state.mutex.lock(room_id).await;
+
 	debug!("Forcing state for new room");
 	services
 		.rooms
@@ -749,20 +765,9 @@ async fn join_room_by_id_helper_local(
 		{
 			services
 				.rooms
-				.state_cache
-				.local_users_in_room(room_id)
-				.filter(|user| {
-					services.rooms.state_accessor.user_can_invite(
-						room_id,
-						user,
-						sender_user,
-						&state_lock,
-					)
-				})
-				.boxed()
-				.next()
+				.state_accessor
+				.restricted_join_authoriser(room_id, sender_user, &state_lock)
 				.await
-				.map(ToOwned::to_owned)
 		} else {
 			None
 		}
@@ -799,6 +804,18 @@ async fn join_room_by_id_helper_local(
 		return Err(error);
 	}
 
+	if !services
+		.rooms
+		.state_accessor
+		.is_federated(room_id)
+		.await
+	{
+		return Err(error);
+	}
+
+	// The incoming PDU handler takes the lock itself once the remote server has
+	// signed our join.
+	drop(state_lock);
 	warn!(
 		"We couldn't do the join locally, maybe federation can help to satisfy the restricted \
 		 join requirements"
@@ -827,14 +844,8 @@ async fn join_room_by_id_helper_local(
 			err!(BadServerResponse("Invalid make_join event json received from server: {e:?}"))
 		})?;
 
-	let join_authorized_via_users_server = join_event_stub
-		.get("content")
-		.map(|s| {
-			s.as_object()?
-				.get("join_authorised_via_users_server")?
-				.as_str()
-		})
-		.and_then(|s| OwnedUserId::try_from(s.unwrap_or_default()).ok());
+	let join_authorized_via_users_server =
+		join_authorised_via(&join_event_stub, &room_version_id, &remote_server)?;
 
 	join_event_stub.insert(
 		"origin".to_owned(),
@@ -914,7 +925,6 @@ async fn join_room_by_id_helper_local(
 			)));
 		}
 
-		drop(state_lock);
 		services
 			.rooms
 			.event_handler
@@ -1004,3 +1014,48 @@ async fn make_join_request(
 
 	make_join_response_and_server
 }
+
+/// Extracts the user a remote server selected in its make_join template to
+/// authorise a restricted join. Only a user of the server which made the
+/// template can sign for it, so any other selection is rejected.
+fn join_authorised_via(
+	join_event_stub: &CanonicalJsonObject,
+	room_version_id: &RoomVersionId,
+	remote_server: &ServerName,
+) -> Result<Option<OwnedUserId>> {
+	use RoomVersionId::*;
+
+	if matches!(room_version_id, V1 | V2 | V3 | V4 | V5 | V6 | V7) {
+		return Ok(None);
+	}
+
+	let Some(authorising_user) = join_event_stub
+		.get("content")
+		.and_then(CanonicalJsonValue::as_object)
+		.and_then(|content| content.get("join_authorised_via_users_server"))
+	else {
+		return Ok(None);
+	};
+
+	let authorising_user: OwnedUserId = authorising_user
+		.as_str()
+		.map(OwnedUserId::try_from)
+		.transpose()
+		.map_err(|e| {
+			err!(BadServerResponse(warn!(
+				"Invalid join_authorised_via_users_server from {remote_server}: {e}"
+			)))
+		})?
+		.ok_or_else(|| {
+			err!(BadServerResponse("join_authorised_via_users_server is not a string."))
+		})?;
+
+	if authorising_user.server_name() != remote_server {
+		return Err!(BadServerResponse(warn!(
+			"{remote_server} selected {authorising_user} to authorise the join, who does not \
+			 belong to it."
+		)));
+	}
+
+	Ok(Some(authorising_user))
+}
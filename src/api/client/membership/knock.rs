@@ -143,9 +143,24 @@ pub(crate) async fn knock_room_route(
 		},
 	};
 
-	knock_room_by_id_helper(&services, sender_user, &room_id, body.reason.clone(), &servers)
-		.boxed()
-		.await
+	services
+		.rooms
+		.membership_limit
+		.check(sender_user, &room_id)
+		.await?;
+
+	let response =
+		knock_room_by_id_helper(&services, sender_user, &room_id, body.reason.clone(), &servers)
+			.boxed()
+			.await?;
+
+	services
+		.rooms
+		.membership_limit
+		.count(sender_user, &room_id)
+		.await;
+
+	Ok(response)
 }
 
 async fn knock_room_by_id_helper(
@@ -216,7 +231,9 @@ async fn knock_room_by_id_helper(
 			.boxed()
 			.await?;
 	} else {
-		knock_room_helper_remote(services, sender_user, room_id, reason, servers, state_lock)
+		// The room lock is reacquired once the remote server has answered.
+		drop(state_lock);
+		knock_room_helper_remote(services, sender_user, room_id, reason, servers)
 			.boxed()
 			.await?;
 	}
@@ -280,6 +297,7 @@ async fn knock_room_helper_local(
 		return Err(error);
 	}
 
+	drop(state_lock);
 	warn!("We couldn't do the knock locally, maybe federation can help to satisfy the knock");
 
 	let (make_knock_response, remote_server) =
@@ -373,6 +391,8 @@ async fn knock_room_helper_local(
 	let parsed_knock_pdu = PduEvent::from_id_val(&event_id, knock_event.clone())
 		.map_err(|e| err!(BadServerResponse("Invalid knock event PDU: {e:?}")))?;
 
+	let state_lock = services.rooms.state.mutex.lock(room_id).await;
+
 	info!("Updating membership locally to knock state with provided stripped state events");
 	services
 		.rooms
@@ -411,7 +431,6 @@ async fn knock_room_helper_remote(
 	room_id: &RoomId,
 	reason: Option<String>,
 	servers: &[OwnedServerName],
-	state_lock: RoomMutexGuard,
 ) -> Result {
 	info!("Knocking {room_id} over federation.");
 
@@ -569,6 +588,8 @@ async fn knock_room_helper_remote(
 		.save_state(room_id, Arc::new(compressed))
 		.await?;
 
+	let state_lock = services.rooms.state.mutex.lock(room_id).await;
+
 	debug!("Forcing state for new room");
 	services
 		.rooms
@@ -1,4 +1,6 @@
+mod auto_join;
 mod ban;
+mod follow;
 mod forget;
 mod invite;
 mod join;
@@ -13,11 +15,17 @@
 use axum::extract::State;
 use futures::{FutureExt, StreamExt};
 use ruma::{OwnedRoomId, RoomId, ServerName, UserId, api::client::membership::joined_rooms};
-use tuwunel_core::{Err, Result, warn};
+use tuwunel_core::{Err, Result, config::NoticeCategory, warn};
 use tuwunel_service::Services;
 
+pub use self::{
+	auto_join::{AutoJoinCohort, auto_join_rooms},
+	join::join_room_by_id_helper,
+	leave::{leave_all_rooms, leave_room},
+};
 pub(crate) use self::{
 	ban::ban_user_route,
+	follow::follow_room_upgrade_hook,
 	forget::forget_room_route,
 	invite::{invite_helper, invite_user_route},
 	join::{join_room_by_id_or_alias_route, join_room_by_id_route},
@@ -27,10 +35,6 @@
 	members::{get_member_events_route, joined_members_route},
 	unban::unban_user_route,
 };
-pub use self::{
-	join::join_room_by_id_helper,
-	leave::{leave_all_rooms, leave_room},
-};
 use crate::{Ruma, client::full_user_deactivate};
 
 /// # `POST /_matrix/client/r0/joined_rooms`
@@ -93,15 +97,16 @@ pub(crate) async fn banned_room_check(
 					"Automatically deactivating user {user_id} due to attempted banned room join"
 				);
 
-				if services.server.config.admin_room_notices {
-					services
-						.admin
-						.send_text(&format!(
+				services
+					.admin
+					.category_text(
+						NoticeCategory::Deactivation,
+						&format!(
 							"Automatically deactivating user {user_id} due to attempted banned \
 							 room join from IP {client_ip}"
-						))
-						.await;
-				}
+						),
+					)
+					.await;
 
 				let all_joined_rooms: Vec<OwnedRoomId> = services
 					.rooms
@@ -138,15 +143,16 @@ pub(crate) async fn banned_room_check(
 					"Automatically deactivating user {user_id} due to attempted banned room join"
 				);
 
-				if services.server.config.admin_room_notices {
-					services
-						.admin
-						.send_text(&format!(
+				services
+					.admin
+					.category_text(
+						NoticeCategory::Deactivation,
+						&format!(
 							"Automatically deactivating user {user_id} due to attempted banned \
 							 room join from IP {client_ip}"
-						))
-						.await;
-				}
+						),
+					)
+					.await;
 
 				let all_joined_rooms: Vec<OwnedRoomId> = services
 					.rooms
@@ -10,7 +10,7 @@
 	serde::Raw,
 };
 use tuwunel_core::{
-	Err, Result, at,
+	Err, Result, StreamToken, at,
 	matrix::{
 		event::{Event, Matches},
 		pdu::PduCount,
@@ -82,12 +82,22 @@ pub(crate) async fn get_message_events_route(
 		.as_deref()
 		.map(str::parse)
 		.transpose()?
-		.unwrap_or_else(|| match body.dir {
-			| Direction::Forward => PduCount::min(),
-			| Direction::Backward => PduCount::max(),
-		});
-
-	let to: Option<PduCount> = body.to.as_deref().map(str::parse).flat_ok();
+		.as_ref()
+		.map_or_else(
+			|| match body.dir {
+				| Direction::Forward => PduCount::min(),
+				| Direction::Backward => PduCount::max(),
+			},
+			StreamToken::pdu_count,
+		);
+
+	let to: Option<PduCount> = body
+		.to
+		.as_deref()
+		.map(str::parse)
+		.flat_ok()
+		.as_ref()
+		.map(StreamToken::pdu_count);
 
 	let limit: usize = body
 		.limit
@@ -122,7 +132,7 @@ pub(crate) async fn get_message_events_route(
 			.boxed(),
 	};
 
-	let events: Vec<_> = it
+	let mut events: Vec<_> = it
 		.ready_take_while(|(count, _)| Some(*count) != to)
 		.ready_filter_map(|item| event_filter(item, filter))
 		.wide_filter_map(|item| ignored_filter(&services, item, sender_user))
@@ -157,6 +167,16 @@ pub(crate) async fn get_message_events_route(
 		.collect()
 		.await;
 
+	for (_, pdu) in &mut events {
+		services
+			.rooms
+			.pdu_metadata
+			.bundle_annotations(pdu)
+			.await
+			.log_err()
+			.ok();
+	}
+
 	let next_token = events.last().map(at!(0));
 
 	let chunk = events
@@ -166,8 +186,11 @@ pub(crate) async fn get_message_events_route(
 		.collect();
 
 	Ok(get_message_events::v3::Response {
-		start: from.to_string(),
-		end: next_token.as_ref().map(ToString::to_string),
+		start: StreamToken::from(from).to_string(),
+		end: next_token
+			.map(StreamToken::from)
+			.as_ref()
+			.map(ToString::to_string),
 		chunk,
 		state,
 	})
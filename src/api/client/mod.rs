@@ -5,6 +5,7 @@
 pub(super) mod backup;
 pub(super) mod capabilities;
 pub(super) mod context;
+pub(super) mod delayed_events;
 pub(super) mod device;
 pub(super) mod directory;
 pub(super) mod filter;
@@ -13,7 +14,9 @@
 pub(super) mod media_legacy;
 pub(super) mod membership;
 pub(super) mod message;
+pub(super) mod notifications;
 pub(super) mod openid;
+pub(super) mod passkey;
 pub(super) mod presence;
 pub(super) mod profile;
 pub(super) mod push;
@@ -22,6 +25,7 @@
 pub(super) mod relations;
 pub(super) mod report;
 pub(super) mod room;
+pub(super) mod scim;
 pub(super) mod search;
 pub(super) mod send;
 pub(super) mod session;
@@ -32,12 +36,14 @@
 pub(super) mod thirdparty;
 pub(super) mod threads;
 pub(super) mod to_device;
+pub(super) mod totp;
 pub(super) mod typing;
 pub(super) mod unstable;
 pub(super) mod unversioned;
 pub(super) mod user_directory;
 pub(super) mod voip;
 pub(super) mod well_known;
+pub(super) mod whois;
 
 pub use account::full_user_deactivate;
 pub(super) use account::*;
@@ -47,6 +53,7 @@
 pub(super) use backup::*;
 pub(super) use capabilities::*;
 pub(super) use context::*;
+pub(super) use delayed_events::*;
 pub(super) use device::*;
 pub(super) use directory::*;
 pub(super) use filter::*;
@@ -54,9 +61,13 @@
 pub(super) use media::*;
 pub(super) use media_legacy::*;
 pub(super) use membership::*;
-pub use membership::{join_room_by_id_helper, leave_all_rooms, leave_room};
+pub use membership::{
+	AutoJoinCohort, auto_join_rooms, join_room_by_id_helper, leave_all_rooms, leave_room,
+};
 pub(super) use message::*;
+pub(super) use notifications::*;
 pub(super) use openid::*;
+pub(super) use passkey::*;
 pub(super) use presence::*;
 pub(super) use profile::*;
 pub use profile::{update_all_rooms, update_avatar_url, update_displayname};
@@ -66,6 +77,8 @@
 pub(super) use relations::*;
 pub(super) use report::*;
 pub(super) use room::*;
+pub use room::{ROOM_TEMPLATE_KEY, create_room_helper};
+pub(super) use scim::*;
 pub(super) use search::*;
 pub(super) use send::*;
 pub(super) use session::*;
@@ -76,12 +89,14 @@
 pub(super) use thirdparty::*;
 pub(super) use threads::*;
 pub(super) use to_device::*;
+pub(super) use totp::*;
 pub(super) use typing::*;
 pub(super) use unstable::*;
 pub(super) use unversioned::*;
 pub(super) use user_directory::*;
 pub(super) use voip::*;
 pub(super) use well_known::*;
+pub(super) use whois::*;
 
 /// generated device ID length
 const DEVICE_ID_LENGTH: usize = 10;
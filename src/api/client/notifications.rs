@@ -0,0 +1,88 @@
+use axum::extract::State;
+use futures::StreamExt;
+use ruma::{
+	api::client::push::get_notifications::{self, v3::Notification},
+	uint,
+};
+use tuwunel_core::{Result, err, matrix::Event, utils::ReadyExt};
+use tuwunel_service::pusher::Notified;
+
+use crate::Ruma;
+
+/// # `GET /_matrix/client/v3/notifications`
+///
+/// Paginates backwards through the events which notified the user, newest
+/// first. Only highlights are returned when `only=highlight`.
+pub(crate) async fn get_notifications_route(
+	State(services): State<crate::State>,
+	ref body: Ruma<get_notifications::v3::Request>,
+) -> Result<get_notifications::v3::Response> {
+	let sender_user = body.sender_user();
+
+	// Use limit or else 10, with maximum 100
+	let limit = body
+		.limit
+		.unwrap_or_else(|| uint!(10))
+		.try_into()
+		.unwrap_or(10)
+		.min(100);
+
+	let from: u64 = body
+		.from
+		.as_deref()
+		.map(str::parse)
+		.transpose()
+		.map_err(|_| err!(Request(InvalidParam("Invalid from token."))))?
+		.unwrap_or(u64::MAX);
+
+	let only_highlight = body.only.as_deref() == Some("highlight");
+
+	let notified: Vec<(u64, Notified)> = services
+		.pusher
+		.notifications_until(sender_user, from)
+		.ready_filter(|(_, notified)| !only_highlight || notified.highlight)
+		.take(limit)
+		.collect()
+		.await;
+
+	let next_token = notified
+		.last()
+		.filter(|_| notified.len() >= limit)
+		.map(|(count, _)| count.to_string());
+
+	let notifications = futures::stream::iter(notified)
+		.filter_map(|(count, notified)| async move {
+			let pdu = services
+				.rooms
+				.timeline
+				.get_pdu(&notified.event_id)
+				.await
+				.ok()?;
+
+			services
+				.rooms
+				.state_accessor
+				.user_can_see_event(sender_user, &notified.room_id, &notified.event_id)
+				.await
+				.then_some(())?;
+
+			let read = count
+				<= services
+					.rooms
+					.user
+					.last_notification_read(sender_user, &notified.room_id)
+					.await;
+
+			Some(Notification::new(
+				notified.actions,
+				pdu.into_format(),
+				read,
+				notified.room_id,
+				notified.ts,
+			))
+		})
+		.collect()
+		.await;
+
+	Ok(get_notifications::v3::Response { next_token, notifications })
+}
@@ -1,8 +1,9 @@
 use std::time::Duration;
 
 use axum::extract::State;
-use ruma::{api::client::account, authentication::TokenType};
+use ruma::{CanonicalJsonValue, api::client::account, authentication::TokenType};
 use tuwunel_core::{Err, Result, utils};
+use tuwunel_service::users::OpenIdRestrictions;
 
 use super::TOKEN_LENGTH;
 use crate::Ruma;
@@ -12,6 +13,8 @@
 /// Request an OpenID token to verify identity with third-party services.
 ///
 /// - The token generated is only valid for the OpenID API
+/// - An `audience` in the body restricts the token to the service with that
+///   server name or client ID, and a space-separated `scope` to those scopes
 pub(crate) async fn create_openid_token_route(
 	State(services): State<crate::State>,
 	body: Ruma<account::request_openid_token::v3::Request>,
@@ -24,10 +27,31 @@ pub(crate) async fn create_openid_token_route(
 		)));
 	}
 
+	// Non-standard: the service the token is for and what it may be used for.
+	let field = |name: &str| match &body.json_body {
+		| Some(CanonicalJsonValue::Object(json)) => match json.get(name) {
+			| None => Ok(None),
+			| Some(CanonicalJsonValue::String(value)) => Ok(Some(value.as_str())),
+			| Some(_) => Err!(Request(InvalidParam("{name} must be a string"))),
+		},
+		| _ => Ok(None),
+	};
+
+	let restrictions = OpenIdRestrictions::parse(field("audience")?, field("scope")?)?;
+	if restrictions.audience.is_none()
+		&& services
+			.server
+			.config
+			.openid_token_require_audience
+	{
+		return Err!(Request(MissingParam("An audience is required for OpenID tokens.")));
+	}
+
 	let access_token = utils::random_string(TOKEN_LENGTH);
-	let expires_in = services
-		.users
-		.create_openid_token(&body.user_id, &access_token)?;
+	let expires_in =
+		services
+			.users
+			.create_openid_token(&body.user_id, &access_token, &restrictions)?;
 
 	Ok(account::request_openid_token::v3::Response {
 		access_token,
@@ -0,0 +1,243 @@
+//! Passkey registration and management. There are no Matrix endpoints for
+//! these, so they are tuwunel-specific and their request types are defined
+//! here.
+
+use axum::extract::State;
+use ruma::UserId;
+use tuwunel_core::{Err, Result, err};
+
+use crate::Ruma;
+
+/// `POST /_tuwunel/passkey/register/start`
+pub(crate) mod start_passkey_registration {
+	use ruma::{
+		api::{Metadata, request, response},
+		metadata,
+	};
+	use serde_json::Value as JsonValue;
+
+	const METADATA: Metadata = metadata! {
+		method: POST,
+		rate_limited: true,
+		authentication: AccessToken,
+		history: {
+			unstable => "/_tuwunel/passkey/register/start",
+		}
+	};
+
+	#[request]
+	pub struct Request {}
+
+	#[response]
+	pub struct Response {
+		/// Options for `navigator.credentials.create()`.
+		#[ruma_api(body)]
+		pub challenge: JsonValue,
+	}
+}
+
+/// `POST /_tuwunel/passkey/register/finish`
+pub(crate) mod finish_passkey_registration {
+	use ruma::{
+		api::{Metadata, request, response},
+		metadata,
+	};
+	use serde_json::Value as JsonValue;
+
+	const METADATA: Metadata = metadata! {
+		method: POST,
+		rate_limited: true,
+		authentication: AccessToken,
+		history: {
+			unstable => "/_tuwunel/passkey/register/finish",
+		}
+	};
+
+	#[request]
+	pub struct Request {
+		/// The credential from `navigator.credentials.create()`.
+		pub credential: JsonValue,
+
+		/// Tells the user's passkeys apart.
+		#[serde(skip_serializing_if = "Option::is_none")]
+		pub name: Option<String>,
+	}
+
+	#[response]
+	pub struct Response {}
+}
+
+/// `POST /_tuwunel/passkey/login/start`
+pub(crate) mod start_passkey_login {
+	use ruma::{
+		api::{Metadata, request, response},
+		metadata,
+	};
+	use serde_json::Value as JsonValue;
+
+	const METADATA: Metadata = metadata! {
+		method: POST,
+		rate_limited: true,
+		authentication: None,
+		history: {
+			unstable => "/_tuwunel/passkey/login/start",
+		}
+	};
+
+	#[request]
+	pub struct Request {
+		/// User ID or localpart of the user logging in.
+		pub user: String,
+	}
+
+	#[response]
+	pub struct Response {
+		/// Session to log in with.
+		pub session: String,
+
+		/// Options for `navigator.credentials.get()`.
+		pub challenge: JsonValue,
+	}
+}
+
+/// `GET /_tuwunel/passkey/credentials`
+pub(crate) mod get_passkeys {
+	use ruma::{
+		api::{Metadata, request, response},
+		metadata,
+	};
+	use serde_json::Value as JsonValue;
+
+	const METADATA: Metadata = metadata! {
+		method: GET,
+		rate_limited: false,
+		authentication: AccessToken,
+		history: {
+			unstable => "/_tuwunel/passkey/credentials",
+		}
+	};
+
+	#[request]
+	pub struct Request {}
+
+	#[response]
+	pub struct Response {
+		pub credentials: Vec<JsonValue>,
+	}
+}
+
+/// `POST /_tuwunel/passkey/credentials/delete`
+pub(crate) mod delete_passkey {
+	use ruma::{
+		api::{Metadata, request, response},
+		metadata,
+	};
+
+	const METADATA: Metadata = metadata! {
+		method: POST,
+		rate_limited: false,
+		authentication: AccessToken,
+		history: {
+			unstable => "/_tuwunel/passkey/credentials/delete",
+		}
+	};
+
+	#[request]
+	pub struct Request {
+		/// ID of the passkey as listed.
+		pub id: String,
+	}
+
+	#[response]
+	pub struct Response {}
+}
+
+/// # `POST /_tuwunel/passkey/register/start`
+///
+/// Begins registering a passkey, returning the options for
+/// `navigator.credentials.create()`.
+pub(crate) async fn start_passkey_registration_route(
+	State(services): State<crate::State>,
+	body: Ruma<start_passkey_registration::Request>,
+) -> Result<start_passkey_registration::Response> {
+	let challenge = services
+		.users
+		.start_passkey_registration(body.sender_user())
+		.await?;
+
+	Ok(start_passkey_registration::Response { challenge })
+}
+
+/// # `POST /_tuwunel/passkey/register/finish`
+///
+/// Completes registration with the created `credential` and an optional
+/// `name` to tell passkeys apart.
+pub(crate) async fn finish_passkey_registration_route(
+	State(services): State<crate::State>,
+	body: Ruma<finish_passkey_registration::Request>,
+) -> Result<finish_passkey_registration::Response> {
+	services
+		.users
+		.finish_passkey_registration(
+			body.sender_user(),
+			body.credential.clone(),
+			body.name.clone(),
+		)
+		.await?;
+
+	Ok(finish_passkey_registration::Response {})
+}
+
+/// # `POST /_tuwunel/passkey/login/start`
+///
+/// Begins a passkey login for `user`, returning the `session` to log in with
+/// and the options for `navigator.credentials.get()`.
+pub(crate) async fn start_passkey_login_route(
+	State(services): State<crate::State>,
+	body: Ruma<start_passkey_login::Request>,
+) -> Result<start_passkey_login::Response> {
+	let user_id =
+		UserId::parse_with_server_name(body.user.as_str(), services.globals.server_name())
+			.map_err(|e| err!(Request(InvalidUsername("Invalid user: {e}"))))?;
+
+	if !services.globals.user_is_local(&user_id) {
+		return Err!(Request(InvalidParam("User does not belong to this homeserver.")));
+	}
+
+	let (session, challenge) = services
+		.users
+		.start_passkey_login(&user_id)
+		.await?;
+
+	Ok(start_passkey_login::Response { session, challenge })
+}
+
+/// # `GET /_tuwunel/passkey/credentials`
+///
+/// Lists the user's passkeys.
+pub(crate) async fn get_passkeys_route(
+	State(services): State<crate::State>,
+	body: Ruma<get_passkeys::Request>,
+) -> Result<get_passkeys::Response> {
+	let credentials = services
+		.users
+		.list_passkeys(body.sender_user())
+		.await?;
+
+	Ok(get_passkeys::Response { credentials })
+}
+
+/// # `POST /_tuwunel/passkey/credentials/delete`
+///
+/// Removes the passkey with the listed `id`.
+pub(crate) async fn delete_passkey_route(
+	State(services): State<crate::State>,
+	body: Ruma<delete_passkey::Request>,
+) -> Result<delete_passkey::Response> {
+	services
+		.users
+		.delete_passkey(body.sender_user(), &body.id)
+		.await?;
+
+	Ok(delete_passkey::Response {})
+}
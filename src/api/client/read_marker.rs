@@ -1,8 +1,9 @@
 use std::collections::BTreeMap;
 
 use axum::extract::State;
+use futures::future::{OptionFuture, join3};
 use ruma::{
-	MilliSecondsSinceUnixEpoch,
+	EventId, MilliSecondsSinceUnixEpoch, RoomId,
 	api::client::{read_marker::set_read_marker, receipt::create_receipt},
 	events::{
 		RoomAccountDataEventType,
@@ -10,6 +11,7 @@
 	},
 };
 use tuwunel_core::{Err, PduCount, Result, err};
+use tuwunel_service::Services;
 
 use crate::Ruma;
 
@@ -17,14 +19,43 @@
 ///
 /// Sets different types of read markers.
 ///
+/// - Every given event must exist in the room; nothing is updated otherwise
 /// - Updates fully-read account data event to `fully_read`
-/// - If `read_receipt` is set: Update private marker and public read receipt
-///   EDU
+/// - If `read_receipt` is set: Update public read receipt EDU
+/// - If `private_read_receipt` is set: Advance the private marker to the later
+///   of both receipts; markers older than the current one are ignored
+/// - Notification counts are reset once if either receipt was applied
 pub(crate) async fn set_read_marker_route(
 	State(services): State<crate::State>,
 	body: Ruma<set_read_marker::v3::Request>,
 ) -> Result<set_read_marker::v3::Response> {
 	let sender_user = body.sender_user();
+	let room_id = &body.room_id;
+
+	let fully_read: OptionFuture<_> = body
+		.fully_read
+		.as_deref()
+		.map(|event_id| marker_count(&services, room_id, event_id))
+		.into();
+
+	let read_receipt: OptionFuture<_> = body
+		.read_receipt
+		.as_deref()
+		.map(|event_id| marker_count(&services, room_id, event_id))
+		.into();
+
+	let private_read_receipt: OptionFuture<_> = body
+		.private_read_receipt
+		.as_deref()
+		.map(|event_id| marker_count(&services, room_id, event_id))
+		.into();
+
+	let (fully_read, read_receipt, private_read_receipt) =
+		join3(fully_read, read_receipt, private_read_receipt).await;
+
+	fully_read.transpose()?;
+	let read_receipt = read_receipt.transpose()?;
+	let private_read_receipt = private_read_receipt.transpose()?;
 
 	if let Some(event) = &body.fully_read {
 		let fully_read_event = ruma::events::fully_read::FullyReadEvent {
@@ -34,7 +65,7 @@ pub(crate) async fn set_read_marker_route(
 		services
 			.account_data
 			.update(
-				Some(&body.room_id),
+				Some(room_id),
 				sender_user,
 				RoomAccountDataEventType::FullyRead,
 				&serde_json::to_value(fully_read_event)?,
@@ -42,13 +73,6 @@ pub(crate) async fn set_read_marker_route(
 			.await?;
 	}
 
-	if body.private_read_receipt.is_some() || body.read_receipt.is_some() {
-		services
-			.rooms
-			.user
-			.reset_notification_counts(sender_user, &body.room_id);
-	}
-
 	// ping presence
 	if services.config.allow_local_presence {
 		services
@@ -72,40 +96,76 @@ pub(crate) async fn set_read_marker_route(
 		services
 			.rooms
 			.read_receipt
-			.readreceipt_update(
-				sender_user,
-				&body.room_id,
-				&ruma::events::receipt::ReceiptEvent {
-					content: ruma::events::receipt::ReceiptEventContent(receipt_content),
-					room_id: body.room_id.clone(),
-				},
-			)
+			.readreceipt_update(sender_user, room_id, &ruma::events::receipt::ReceiptEvent {
+				content: ruma::events::receipt::ReceiptEventContent(receipt_content),
+				room_id: room_id.clone(),
+			})
 			.await;
 	}
 
-	if let Some(event) = &body.private_read_receipt {
-		let count = services
+	// When both receipts are given the private marker is set once, to whichever
+	// is later, rather than possibly moving twice.
+	let private_read_advanced: OptionFuture<_> = private_read_receipt
+		.map(|count| {
+			services.rooms.read_receipt.private_read_advance(
+				room_id,
+				sender_user,
+				count,
+				read_receipt,
+			)
+		})
+		.into();
+
+	let private_read_advanced = private_read_advanced.await.unwrap_or(false);
+
+	if read_receipt.is_some() || private_read_advanced {
+		let _lock = services
 			.rooms
 			.timeline
-			.get_pdu_count(event)
-			.await
-			.map_err(|_| err!(Request(NotFound("Event not found."))))?;
-
-		let PduCount::Normal(count) = count else {
-			return Err!(Request(InvalidParam(
-				"Event is a backfilled PDU and cannot be marked as read."
-			)));
-		};
+			.mutex_insert
+			.lock(room_id)
+			.await;
 
 		services
 			.rooms
-			.read_receipt
-			.private_read_set(&body.room_id, sender_user, count);
+			.user
+			.reset_notification_counts(sender_user, room_id)
+			.await;
 	}
 
 	Ok(set_read_marker::v3::Response {})
 }
 
+/// Position of a marker's event, which must be a non-backfilled event in the
+/// room.
+async fn marker_count(services: &Services, room_id: &RoomId, event_id: &EventId) -> Result<u64> {
+	let pdu_id = services
+		.rooms
+		.timeline
+		.get_pdu_id(event_id)
+		.await
+		.map_err(|_| err!(Request(NotFound("Event not found."))))?;
+
+	let shortroomid = services
+		.rooms
+		.short
+		.get_shortroomid(room_id)
+		.await
+		.map_err(|_| err!(Request(NotFound("Event not found."))))?;
+
+	if pdu_id.shortroomid() != shortroomid.to_be_bytes() {
+		return Err!(Request(NotFound("Event not found in this room.")));
+	}
+
+	let PduCount::Normal(count) = pdu_id.pdu_count() else {
+		return Err!(Request(InvalidParam(
+			"Event is a backfilled PDU and cannot be marked as read."
+		)));
+	};
+
+	Ok(count)
+}
+
 /// # `POST /_matrix/client/r0/rooms/{roomId}/receipt/{receiptType}/{eventId}`
 ///
 /// Sets private read marker and public read receipt EDU.
@@ -119,10 +179,25 @@ pub(crate) async fn create_receipt_route(
 		&body.receipt_type,
 		create_receipt::v3::ReceiptType::Read | create_receipt::v3::ReceiptType::ReadPrivate
 	) {
-		services
+		let _lock = services
 			.rooms
-			.user
-			.reset_notification_counts(sender_user, &body.room_id);
+			.timeline
+			.mutex_insert
+			.lock(&body.room_id)
+			.await;
+
+		let user = &services.rooms.user;
+		match &body.thread {
+			| ReceiptThread::Thread(thread_id) =>
+				user.reset_thread_notification_counts(sender_user, &body.room_id, thread_id)
+					.await,
+			| ReceiptThread::Main =>
+				user.reset_main_notification_counts(sender_user, &body.room_id)
+					.await,
+			| _ =>
+				user.reset_notification_counts(sender_user, &body.room_id)
+					.await,
+		}
 	}
 
 	// ping presence
@@ -159,7 +234,7 @@ pub(crate) async fn create_receipt_route(
 						sender_user.to_owned(),
 						ruma::events::receipt::Receipt {
 							ts: Some(MilliSecondsSinceUnixEpoch::now()),
-							thread: ReceiptThread::Unthreaded,
+							thread: body.thread.clone(),
 						},
 					)]),
 				)]),
@@ -14,12 +14,13 @@
 use tuwunel_core::{
 	Result, at,
 	matrix::{
+		StreamToken,
 		event::{Event, RelationTypeEqual},
 		pdu::PduCount,
 	},
-	utils::{IterStream, ReadyExt, result::FlatOk, stream::WidebandExt},
+	utils::{ReadyExt, result::FlatOk, stream::WidebandExt},
 };
-use tuwunel_service::Services;
+use tuwunel_service::{Services, rooms::pdu_metadata::MAX_RELATION_DEPTH};
 
 use crate::Ruma;
 
@@ -115,12 +116,20 @@ async fn paginate_relations_with_filter(
 	let start: PduCount = from
 		.map(str::parse)
 		.transpose()?
-		.unwrap_or_else(|| match dir {
-			| Direction::Forward => PduCount::min(),
-			| Direction::Backward => PduCount::max(),
-		});
+		.as_ref()
+		.map_or_else(
+			|| match dir {
+				| Direction::Forward => PduCount::min(),
+				| Direction::Backward => PduCount::max(),
+			},
+			StreamToken::pdu_count,
+		);
 
-	let to: Option<PduCount> = to.map(str::parse).flat_ok();
+	let to: Option<PduCount> = to
+		.map(str::parse)
+		.flat_ok()
+		.as_ref()
+		.map(StreamToken::pdu_count);
 
 	// Use limit or else 30, with maximum 100
 	let limit: usize = limit
@@ -129,39 +138,44 @@ async fn paginate_relations_with_filter(
 		.unwrap_or(30)
 		.min(100);
 
-	// Spec (v1.10) recommends depth of at least 3
-	let depth: u8 = if recurse { 3 } else { 1 };
+	let depth: u8 = if recurse { MAX_RELATION_DEPTH } else { 1 };
 
-	let events: Vec<_> = services
+	// One more than the limit is taken to tell whether there is a next batch.
+	let mut events: Vec<_> = services
 		.rooms
 		.pdu_metadata
-		.get_relations(sender_user, room_id, target, start, limit, depth, dir)
+		.get_relations(sender_user, room_id, target, start, depth, dir)
 		.await
-		.into_iter()
-		.filter(|(_, pdu)| {
+		.ready_take_while(|(count, _)| {
+			to.is_none_or(|to| match dir {
+				| Direction::Forward => *count < to,
+				| Direction::Backward => *count > to,
+			})
+		})
+		.ready_filter(|(_, pdu)| {
 			filter_event_type
 				.as_ref()
 				.is_none_or(|kind| kind == pdu.kind())
 		})
-		.filter(|(_, pdu)| {
+		.ready_filter(|(_, pdu)| {
 			filter_rel_type
 				.as_ref()
 				.is_none_or(|rel_type| rel_type.relation_type_equal(pdu))
 		})
-		.stream()
-		.ready_take_while(|(count, _)| Some(*count) != to)
 		.wide_filter_map(|item| visibility_filter(services, sender_user, item))
-		.take(limit)
+		.take(limit.saturating_add(1))
 		.collect()
 		.await;
 
-	let next_batch = match dir {
-		| Direction::Forward => events.last(),
-		| Direction::Backward => events.first(),
-	}
-	.map(at!(0))
-	.as_ref()
-	.map(ToString::to_string);
+	let next_batch = (events.len() > limit)
+		.then(|| {
+			events.truncate(limit);
+			events.last().map(at!(0))
+		})
+		.flatten()
+		.map(StreamToken::from)
+		.as_ref()
+		.map(ToString::to_string);
 
 	Ok(get_relating_events::v1::Response {
 		next_batch,
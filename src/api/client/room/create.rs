@@ -4,12 +4,14 @@
 use futures::FutureExt;
 use ruma::{
 	CanonicalJsonObject, Int, OwnedRoomAliasId, OwnedRoomId, OwnedUserId, RoomId, RoomVersionId,
+	UserId,
 	api::client::room::{self, create_room},
 	events::{
 		TimelineEventType,
 		room::{
 			canonical_alias::RoomCanonicalAliasEventContent,
 			create::RoomCreateEventContent,
+			encryption::RoomEncryptionEventContent,
 			guest_access::{GuestAccess, RoomGuestAccessEventContent},
 			history_visibility::{HistoryVisibility, RoomHistoryVisibilityEventContent},
 			join_rules::{JoinRule, RoomJoinRulesEventContent},
@@ -24,7 +26,9 @@
 };
 use serde_json::{json, value::to_raw_value};
 use tuwunel_core::{
-	Err, Result, debug_info, debug_warn, err, info,
+	Err, Result,
+	config::{NoticeCategory, RoomTemplate},
+	debug_info, debug_warn, err, info,
 	matrix::{StateKey, pdu::PduBuilder},
 	warn,
 };
@@ -32,6 +36,10 @@
 
 use crate::{Ruma, client::invite_helper};
 
+/// Key in the creation content selecting one of the configured
+/// `room_templates`; it is removed before the create event is sent.
+pub const ROOM_TEMPLATE_KEY: &str = "chat.tuwunel.template";
+
 /// # `POST /_matrix/client/v3/createRoom`
 ///
 /// Creates a new room.
@@ -45,27 +53,40 @@
 /// - Send join rules
 /// - Send history visibility
 /// - Send guest access
+/// - Send events from the room template selected in the creation content
 /// - Send events listed in initial state
 /// - Send events implied by `name` and `topic`
 /// - Send invite events
-#[allow(clippy::large_stack_frames)]
 pub(crate) async fn create_room_route(
 	State(services): State<crate::State>,
 	body: Ruma<create_room::v3::Request>,
 ) -> Result<create_room::v3::Response> {
-	use create_room::v3::RoomPreset;
+	create_room_helper(&services, body.sender_user(), &body.body, body.appservice_info.as_ref())
+		.boxed()
+		.await
+		.map(create_room::v3::Response::new)
+}
 
-	let sender_user = body.sender_user();
+/// Creates a room on behalf of `sender_user` as described by a createRoom
+/// request, applying any room template selected in its creation content.
+#[allow(clippy::large_stack_frames)]
+pub async fn create_room_helper(
+	services: &Services,
+	sender_user: &UserId,
+	body: &create_room::v3::Request,
+	appservice_info: Option<&RegistrationInfo>,
+) -> Result<OwnedRoomId> {
+	use create_room::v3::RoomPreset;
 
 	if !services.globals.allow_room_creation()
-		&& body.appservice_info.is_none()
+		&& appservice_info.is_none()
 		&& !services.users.is_admin(sender_user).await
 	{
 		return Err!(Request(Forbidden("Room creation has been disabled.",)));
 	}
 
 	let room_id: OwnedRoomId = match &body.room_id {
-		| Some(custom_room_id) => custom_room_id_check(&services, custom_room_id)?,
+		| Some(custom_room_id) => custom_room_id_check(services, custom_room_id)?,
 		| _ => RoomId::new(&services.server.name),
 	};
 
@@ -86,26 +107,29 @@ pub(crate) async fn create_room_route(
 			.config
 			.lockdown_public_room_directory
 		&& !services.users.is_admin(sender_user).await
-		&& body.appservice_info.is_none()
+		&& appservice_info.is_none()
 	{
 		warn!(
 			"Non-admin user {sender_user} tried to publish {room_id} to the room directory \
 			 while \"lockdown_public_room_directory\" is enabled"
 		);
 
-		if services.server.config.admin_room_notices {
-			services
-				.admin
-				.notice(&format!(
+		services
+			.admin
+			.category_notice(
+				NoticeCategory::Directory,
+				&format!(
 					"Non-admin user {sender_user} tried to publish {room_id} to the room \
 					 directory while \"lockdown_public_room_directory\" is enabled"
-				))
-				.await;
-		}
+				),
+			)
+			.await;
 
 		return Err!(Request(Forbidden("Publishing rooms to the room directory is not allowed")));
 	}
 
+	let template = room_template(services, body.creation_content.as_ref())?;
+
 	let _short_id = services
 		.rooms
 		.short
@@ -115,7 +139,7 @@ pub(crate) async fn create_room_route(
 
 	let alias: Option<OwnedRoomAliasId> = match body.room_alias_name.as_ref() {
 		| Some(alias) =>
-			Some(room_alias_check(&services, alias, body.appservice_info.as_ref()).await?),
+			Some(room_alias_check(services, sender_user, alias, appservice_info).await?),
 		| _ => None,
 	};
 
@@ -163,6 +187,7 @@ pub(crate) async fn create_room_route(
 					// V11+ removed the "creator" key
 				},
 			}
+			content.remove(ROOM_TEMPLATE_KEY);
 			content.insert(
 				"room_version".into(),
 				json!(room_version.as_str())
@@ -260,6 +285,7 @@ pub(crate) async fn create_room_route(
 	}
 
 	let power_levels_content = default_power_levels_content(
+		template.and_then(|template| template.power_levels.as_ref()),
 		body.power_level_content_override.as_ref(),
 		&body.visibility,
 		users,
@@ -357,7 +383,19 @@ pub(crate) async fn create_room_route(
 		.boxed()
 		.await?;
 
-	// 6. Events listed in initial_state
+	// 6.1 Events from the room template
+	if let Some(template) = template {
+		for pdu_builder in template_events(services, template)? {
+			services
+				.rooms
+				.timeline
+				.build_and_append_pdu(pdu_builder, sender_user, &room_id, &state_lock)
+				.boxed()
+				.await?;
+		}
+	}
+
+	// 6.2 Events listed in initial_state
 	for event in &body.initial_state {
 		let mut pdu_builder = event
 			.deserialize_as::<PduBuilder>()
@@ -445,7 +483,7 @@ pub(crate) async fn create_room_route(
 		}
 
 		if let Err(e) =
-			invite_helper(&services, sender_user, user_id, &room_id, None, body.is_direct)
+			invite_helper(services, sender_user, user_id, &room_id, None, body.is_direct)
 				.boxed()
 				.await
 		{
@@ -464,25 +502,24 @@ pub(crate) async fn create_room_route(
 	if body.visibility == room::Visibility::Public {
 		services.rooms.directory.set_public(&room_id);
 
-		if services.server.config.admin_room_notices {
-			services
-				.admin
-				.send_text(&format!(
-					"{sender_user} made {} public to the room directory",
-					&room_id
-				))
-				.await;
-		}
+		services
+			.admin
+			.category_text(
+				NoticeCategory::Directory,
+				&format!("{sender_user} made {} public to the room directory", &room_id),
+			)
+			.await;
 		info!("{sender_user} made {0} public to the room directory", &room_id);
 	}
 
 	info!("{sender_user} created a room with room ID {room_id}");
 
-	Ok(create_room::v3::Response::new(room_id))
+	Ok(room_id)
 }
 
 /// creates the power_levels_content for the PDU builder
 fn default_power_levels_content(
+	template_override: Option<&JsonObject>,
 	power_level_content_override: Option<&Raw<RoomPowerLevelsEventContent>>,
 	visibility: &room::Visibility,
 	users: BTreeMap<OwnedUserId, Int>,
@@ -526,6 +563,12 @@ fn default_power_levels_content(
 			serde_json::to_value(50).expect("50 is valid Value");
 	}
 
+	if let Some(template_override) = template_override {
+		for (key, value) in template_override {
+			power_levels_content[key] = value.clone();
+		}
+	}
+
 	if let Some(power_level_content_override) = power_level_content_override {
 		let json: JsonObject = serde_json::from_str(power_level_content_override.json().get())
 			.map_err(|e| err!(Request(BadJson("Invalid power_level_content_override: {e:?}"))))?;
@@ -538,9 +581,55 @@ fn default_power_levels_content(
 	Ok(power_levels_content)
 }
 
+/// Look up the room template named in the creation content, if any.
+fn room_template<'a>(
+	services: &'a Services,
+	creation_content: Option<&Raw<create_room::v3::CreationContent>>,
+) -> Result<Option<&'a RoomTemplate>> {
+	let Some(name) = creation_content
+		.map(|content| content.get_field::<String>(ROOM_TEMPLATE_KEY))
+		.transpose()
+		.map_err(|e| err!(Request(BadJson("Invalid {ROOM_TEMPLATE_KEY}: {e}"))))?
+		.flatten()
+	else {
+		return Ok(None);
+	};
+
+	services
+		.config
+		.room_templates
+		.get(&name)
+		.map(Some)
+		.ok_or_else(|| err!(Request(InvalidParam("Unknown room template {name:?}."))))
+}
+
+/// State events implied by a room template.
+fn template_events(services: &Services, template: &RoomTemplate) -> Result<Vec<PduBuilder>> {
+	let mut events = Vec::with_capacity(template.initial_state.len().saturating_add(1));
+
+	if template.encrypted && services.config.allow_encryption {
+		events.push(PduBuilder::state(
+			String::new(),
+			&RoomEncryptionEventContent::with_recommended_defaults(),
+		));
+	}
+
+	for event in &template.initial_state {
+		events.push(PduBuilder {
+			event_type: event.kind.clone().into(),
+			content: to_raw_value(&event.content)?,
+			state_key: Some(event.state_key.as_str().into()),
+			..Default::default()
+		});
+	}
+
+	Ok(events)
+}
+
 /// if a room is being created with a room alias, run our checks
 async fn room_alias_check(
 	services: &Services,
+	sender_user: &UserId,
 	room_alias_name: &str,
 	appservice_info: Option<&RegistrationInfo>,
 ) -> Result<OwnedRoomAliasId> {
@@ -565,6 +654,12 @@ async fn room_alias_check(
 		return Err!(Request(Unknown("Room alias name is forbidden.")));
 	}
 
+	let privileged = appservice_info.is_some() || services.users.is_admin(sender_user).await;
+
+	services
+		.globals
+		.check_alias_policy(room_alias_name, privileged)?;
+
 	let server_name = services.globals.server_name();
 	let full_room_alias = OwnedRoomAliasId::parse(format!("#{room_alias_name}:{server_name}"))
 		.map_err(|e| {
@@ -1,7 +1,7 @@
 use axum::extract::State;
 use futures::{FutureExt, TryFutureExt, future::try_join};
 use ruma::api::client::room::get_room_event;
-use tuwunel_core::{Err, Event, Result, err};
+use tuwunel_core::{Err, Event, Result, err, result::LogErr};
 
 use crate::{Ruma, client::is_ignored_pdu};
 
@@ -39,6 +39,13 @@ pub(crate) async fn get_room_event_route(
 	);
 
 	event.add_age().ok();
+	services
+		.rooms
+		.pdu_metadata
+		.bundle_annotations(&mut event)
+		.await
+		.log_err()
+		.ok();
 
 	Ok(get_room_event::v3::Response { event: event.into_format() })
 }
@@ -0,0 +1,381 @@
+use std::collections::HashSet;
+
+use axum::{
+	extract::{Path, Query, State},
+	response::Response,
+};
+use bytes::Bytes;
+use futures::{FutureExt, StreamExt};
+use http::StatusCode;
+use ruma::{
+	OwnedRoomId, OwnedUserId, RoomId, UserId,
+	api::client::room::create_room::{self, v3::RoomPreset},
+	events::room::name::RoomNameEventContent,
+	room::RoomType,
+	serde::Raw,
+};
+use serde::Deserialize;
+use serde_json::{Value, json, value::to_raw_value};
+use tuwunel_core::{Err, Result, err, matrix::pdu::PduBuilder, utils::ReadyExt};
+use tuwunel_service::Services;
+
+use super::{
+	BASE_PATH, ListQuery, PatchRequest, SCHEMA_GROUP, Token, check_token, eq_filter,
+	list_response, page, parse_body, respond,
+};
+use crate::client::{create_room_helper, invite_helper, join_room_by_id_helper, leave_room};
+
+/// Attributes of a Group resource read from create requests.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GroupRequest {
+	display_name: String,
+	#[serde(default)]
+	members: Vec<Member>,
+}
+
+#[derive(Deserialize)]
+struct Member {
+	value: String,
+}
+
+/// # `GET /_tuwunel/scim/v2/Groups`
+///
+/// Lists the spaces the server user is joined to, optionally filtered by
+/// `displayName eq "<name>"`.
+pub(crate) async fn scim_list_groups_route(
+	State(services): State<crate::State>,
+	token: Token,
+	Query(query): Query<ListQuery>,
+) -> Response {
+	respond(StatusCode::OK, list_groups(&services, &token, &query).await)
+}
+
+/// # `POST /_tuwunel/scim/v2/Groups`
+///
+/// Creates a private space owned by the server user and joins the members to
+/// it.
+pub(crate) async fn scim_create_group_route(
+	State(services): State<crate::State>,
+	token: Token,
+	body: Bytes,
+) -> Response {
+	respond(StatusCode::CREATED, create_group(&services, &token, &body).await)
+}
+
+/// # `GET /_tuwunel/scim/v2/Groups/{id}`
+pub(crate) async fn scim_get_group_route(
+	State(services): State<crate::State>,
+	token: Token,
+	Path(id): Path<String>,
+) -> Response {
+	let result = async {
+		check_token(&services, &token)?;
+		let room_id = group_space(&services, &id).await?;
+		Ok(group_resource(&services, &room_id).await)
+	};
+
+	respond(StatusCode::OK, result.await)
+}
+
+/// # `PATCH /_tuwunel/scim/v2/Groups/{id}`
+///
+/// Applies `add`, `remove` and `replace` operations on `members`, and
+/// `replace` operations on `displayName`. Added members are invited and
+/// joined to the space; removed members leave it.
+pub(crate) async fn scim_patch_group_route(
+	State(services): State<crate::State>,
+	token: Token,
+	Path(id): Path<String>,
+	body: Bytes,
+) -> Response {
+	let result = async {
+		check_token(&services, &token)?;
+		let room_id = group_space(&services, &id).await?;
+		let request: PatchRequest = parse_body(&body)?;
+		for operation in request.operations {
+			patch_group(&services, &room_id, &operation.op, operation.path, operation.value)
+				.await?;
+		}
+
+		Ok(group_resource(&services, &room_id).await)
+	};
+
+	respond(StatusCode::OK, result.await)
+}
+
+async fn list_groups(services: &Services, token: &Token, query: &ListQuery) -> Result<Value> {
+	check_token(services, token)?;
+
+	let display_name = query
+		.filter
+		.as_deref()
+		.map(|filter| eq_filter(filter, "displayName"))
+		.transpose()?;
+
+	let room_ids: Vec<OwnedRoomId> = services
+		.rooms
+		.state_cache
+		.rooms_joined(&services.globals.server_user)
+		.filter_map(|room_id| async move {
+			let is_space = services
+				.rooms
+				.state_accessor
+				.get_room_type(room_id)
+				.await
+				.is_ok_and(|room_type| room_type == RoomType::Space);
+
+			let matches = match display_name {
+				| Some(display_name) => services
+					.rooms
+					.state_accessor
+					.get_name(room_id)
+					.await
+					.is_ok_and(|name| name == display_name),
+				| None => true,
+			};
+
+			(is_space && matches).then(|| room_id.to_owned())
+		})
+		.collect()
+		.await;
+
+	let resources = futures::stream::iter(page(query, &room_ids))
+		.then(|room_id| group_resource(services, room_id))
+		.collect()
+		.await;
+
+	Ok(list_response(query, room_ids.len(), resources))
+}
+
+async fn create_group(services: &Services, token: &Token, body: &[u8]) -> Result<Value> {
+	check_token(services, token)?;
+
+	let request: GroupRequest = parse_body(body)?;
+	let creation_content = json!({ "type": RoomType::Space });
+
+	let mut create = create_room::v3::Request::new();
+	create.creation_content = Some(Raw::from_json(to_raw_value(&creation_content)?));
+	create.name = Some(request.display_name);
+	create.preset = Some(RoomPreset::PrivateChat);
+
+	let room_id = create_room_helper(services, &services.globals.server_user, &create, None)
+		.boxed()
+		.await?;
+
+	for member in &request.members {
+		add_member(services, &room_id, &member_user(services, &member.value).await?).await?;
+	}
+
+	Ok(group_resource(services, &room_id).await)
+}
+
+async fn patch_group(
+	services: &Services,
+	room_id: &RoomId,
+	op: &str,
+	path: Option<String>,
+	value: Option<Value>,
+) -> Result {
+	let path = path.unwrap_or_default();
+	let op = op.to_ascii_lowercase();
+	match (op.as_str(), path.as_str()) {
+		| ("add", "members") =>
+			for user_id in member_values(services, value).await? {
+				add_member(services, room_id, &user_id).await?;
+			},
+		| ("remove", "members") =>
+			for user_id in member_values(services, value).await? {
+				remove_member(services, room_id, &user_id).await?;
+			},
+		| ("remove", path) if path.starts_with("members[") => {
+			let filter = path
+				.strip_prefix("members[")
+				.and_then(|filter| filter.strip_suffix(']'))
+				.ok_or_else(|| err!(Request(InvalidParam("Invalid SCIM members path."))))?;
+
+			let user_id = member_user(services, eq_filter(filter, "value")?).await?;
+			remove_member(services, room_id, &user_id).await?;
+		},
+		| ("replace", "members") => {
+			let members: HashSet<_> = member_values(services, value)
+				.await?
+				.into_iter()
+				.collect();
+
+			for user_id in group_members(services, room_id).await {
+				if !members.contains(&user_id) {
+					remove_member(services, room_id, &user_id).await?;
+				}
+			}
+
+			for user_id in &members {
+				add_member(services, room_id, user_id).await?;
+			}
+		},
+		| ("replace", "displayName") => {
+			let Some(Value::String(name)) = value else {
+				return Err!(Request(InvalidParam("displayName must be a string.")));
+			};
+
+			set_space_name(services, room_id, name).await?;
+		},
+		| ("replace", "") => {
+			let Some(Value::String(name)) = value
+				.as_ref()
+				.and_then(|value| value.get("displayName"))
+				.cloned()
+			else {
+				return Err!(Request(InvalidParam("Invalid SCIM replace operation.")));
+			};
+
+			set_space_name(services, room_id, name).await?;
+		},
+		| _ => {
+			return Err!(Request(InvalidParam("Unsupported SCIM operation {op} on {path:?}.")));
+		},
+	}
+
+	Ok(())
+}
+
+/// Resolves the resource ID of a Group, its room ID, to a space the server
+/// user is joined to.
+async fn group_space(services: &Services, id: &str) -> Result<OwnedRoomId> {
+	let room_id = RoomId::parse(id).map_err(|_| err!(Request(NotFound("No such group."))))?;
+
+	let is_space = services
+		.rooms
+		.state_accessor
+		.get_room_type(&room_id)
+		.await
+		.is_ok_and(|room_type| room_type == RoomType::Space);
+
+	if !is_space
+		|| !services
+			.rooms
+			.state_cache
+			.is_joined(&services.globals.server_user, &room_id)
+			.await
+	{
+		return Err!(Request(NotFound("No such group.")));
+	}
+
+	Ok(room_id)
+}
+
+async fn group_resource(services: &Services, room_id: &RoomId) -> Value {
+	let mut members = Vec::new();
+	for user_id in group_members(services, room_id).await {
+		members.push(json!({
+			"value": user_id,
+			"display": services.users.displayname(&user_id).await.ok(),
+			"$ref": format!("{BASE_PATH}/Users/{user_id}"),
+		}));
+	}
+
+	json!({
+		"schemas": [SCHEMA_GROUP],
+		"id": room_id,
+		"displayName": services.rooms.state_accessor.get_name(room_id).await.ok(),
+		"members": members,
+		"meta": {
+			"resourceType": "Group",
+			"location": format!("{BASE_PATH}/Groups/{room_id}"),
+		},
+	})
+}
+
+/// Local users joined to the space, other than the server user which owns it.
+async fn group_members(services: &Services, room_id: &RoomId) -> Vec<OwnedUserId> {
+	services
+		.rooms
+		.state_cache
+		.local_users_in_room(room_id)
+		.ready_filter(|user_id| **user_id != *services.globals.server_user)
+		.map(ToOwned::to_owned)
+		.collect()
+		.await
+}
+
+/// Reads the users of a `members` operation value, an array of
+/// `{"value": "<user id>"}` objects.
+async fn member_values(services: &Services, value: Option<Value>) -> Result<Vec<OwnedUserId>> {
+	let members: Vec<Member> = value
+		.map(serde_json::from_value)
+		.transpose()
+		.map_err(|e| err!(Request(BadJson("Invalid SCIM members: {e}"))))?
+		.unwrap_or_default();
+
+	let mut user_ids = Vec::with_capacity(members.len());
+	for member in &members {
+		user_ids.push(member_user(services, &member.value).await?);
+	}
+
+	Ok(user_ids)
+}
+
+async fn member_user(services: &Services, value: &str) -> Result<OwnedUserId> {
+	let user_id = UserId::parse(value)
+		.map_err(|e| err!(Request(InvalidParam("Invalid member {value}: {e}"))))?;
+
+	if !services.globals.user_is_local(&user_id) || !services.users.is_active(&user_id).await {
+		return Err!(Request(InvalidParam("Member {user_id} is not an active local user.")));
+	}
+
+	Ok(user_id)
+}
+
+async fn add_member(services: &Services, room_id: &RoomId, user_id: &UserId) -> Result {
+	if services
+		.rooms
+		.state_cache
+		.is_joined(user_id, room_id)
+		.await
+	{
+		return Ok(());
+	}
+
+	invite_helper(services, &services.globals.server_user, user_id, room_id, None, false)
+		.boxed()
+		.await?;
+
+	join_room_by_id_helper(services, user_id, room_id, None, &[], None, &None)
+		.boxed()
+		.await?;
+
+	Ok(())
+}
+
+async fn remove_member(services: &Services, room_id: &RoomId, user_id: &UserId) -> Result {
+	if !services
+		.rooms
+		.state_cache
+		.is_joined(user_id, room_id)
+		.await
+	{
+		return Ok(());
+	}
+
+	leave_room(services, user_id, room_id, None)
+		.boxed()
+		.await
+}
+
+async fn set_space_name(services: &Services, room_id: &RoomId, name: String) -> Result {
+	let state_lock = services.rooms.state.mutex.lock(room_id).await;
+
+	services
+		.rooms
+		.timeline
+		.build_and_append_pdu(
+			PduBuilder::state(String::new(), &RoomNameEventContent::new(name)),
+			&services.globals.server_user,
+			room_id,
+			&state_lock,
+		)
+		.boxed()
+		.await?;
+
+	Ok(())
+}
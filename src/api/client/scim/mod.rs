@@ -0,0 +1,154 @@
+//! SCIM 2.0 (RFC 7643, RFC 7644) provisioning of local users, and of the
+//! members of spaces as SCIM Groups, for enterprise identity systems. These
+//! are not Matrix endpoints; requests authenticate with the configured
+//! `scim_token` rather than an access token.
+
+mod groups;
+#[cfg(test)]
+mod tests;
+mod users;
+
+use axum::response::{IntoResponse, Response};
+use axum_extra::{
+	TypedHeader,
+	headers::{Authorization, authorization::Bearer},
+};
+use http::{StatusCode, header::CONTENT_TYPE};
+use serde::{Deserialize, de::DeserializeOwned};
+use serde_json::{Value, json};
+use tuwunel_core::{Err, Result, err, utils::hash};
+use tuwunel_service::Services;
+
+pub(crate) use self::{groups::*, users::*};
+
+const SCHEMA_USER: &str = "urn:ietf:params:scim:schemas:core:2.0:User";
+const SCHEMA_GROUP: &str = "urn:ietf:params:scim:schemas:core:2.0:Group";
+const SCHEMA_LIST: &str = "urn:ietf:params:scim:api:messages:2.0:ListResponse";
+const SCHEMA_ERROR: &str = "urn:ietf:params:scim:api:messages:2.0:Error";
+
+const BASE_PATH: &str = "/_tuwunel/scim/v2";
+
+/// Page size when the client does not request one.
+const DEFAULT_COUNT: usize = 100;
+
+/// Length of the random password set on users created or reactivated without
+/// one.
+const PASSWORD_LENGTH: usize = 32;
+
+type Token = Option<TypedHeader<Authorization<Bearer>>>;
+
+/// Query parameters of a list request.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ListQuery {
+	filter: Option<String>,
+	start_index: Option<usize>,
+	count: Option<usize>,
+}
+
+/// Body of a PATCH request.
+#[derive(Deserialize)]
+struct PatchRequest {
+	#[serde(rename = "Operations")]
+	operations: Vec<PatchOperation>,
+}
+
+#[derive(Deserialize)]
+struct PatchOperation {
+	op: String,
+	path: Option<String>,
+	value: Option<Value>,
+}
+
+/// Fails unless the request carries the configured bearer token.
+fn check_token(services: &Services, token: &Token) -> Result {
+	let given = token
+		.as_ref()
+		.map(|TypedHeader(Authorization(bearer))| bearer.token());
+
+	let (Some(given), Some(expected)) = (given, services.config.scim_token.as_deref()) else {
+		return Err!(Request(Unauthorized("Invalid SCIM bearer token.")));
+	};
+
+	if !hash::constant_time_eq(given.as_bytes(), expected.as_bytes()) {
+		return Err!(Request(Unauthorized("Invalid SCIM bearer token.")));
+	}
+
+	Ok(())
+}
+
+/// Deserializes a request body. SCIM clients send `application/scim+json`,
+/// which the axum JSON extractor rejects.
+fn parse_body<T: DeserializeOwned>(body: &[u8]) -> Result<T> {
+	serde_json::from_slice(body).map_err(|e| err!(Request(BadJson("Invalid SCIM request: {e}"))))
+}
+
+/// Responds with the resource, or with a SCIM error carrying the status of
+/// the failure.
+fn respond(status: StatusCode, result: Result<Value>) -> Response {
+	let (status, body) = match result {
+		| Ok(_) if status == StatusCode::NO_CONTENT => return status.into_response(),
+		| Ok(body) => (status, body),
+		| Err(e) => {
+			let status = e.status_code();
+			(
+				status,
+				json!({
+					"schemas": [SCHEMA_ERROR],
+					"status": status.as_u16().to_string(),
+					"detail": e.sanitized_message(),
+				}),
+			)
+		},
+	};
+
+	(status, [(CONTENT_TYPE, "application/scim+json")], body.to_string()).into_response()
+}
+
+/// Selects the page of items requested by a list query, so resources are
+/// only built for the items returned.
+fn page<'a, T>(query: &ListQuery, items: &'a [T]) -> &'a [T] {
+	let start = start_index(query)
+		.saturating_sub(1)
+		.min(items.len());
+
+	let end = start
+		.saturating_add(query.count.unwrap_or(DEFAULT_COUNT))
+		.min(items.len());
+
+	items.get(start..end).unwrap_or_default()
+}
+
+/// Wraps a page of resources in a ListResponse; `total` counts the items on
+/// every page.
+fn list_response(query: &ListQuery, total: usize, resources: Vec<Value>) -> Value {
+	json!({
+		"schemas": [SCHEMA_LIST],
+		"totalResults": total,
+		"startIndex": start_index(query),
+		"itemsPerPage": resources.len(),
+		"Resources": resources,
+	})
+}
+
+/// The 1-based index of the first item requested.
+fn start_index(query: &ListQuery) -> usize { query.start_index.unwrap_or(1).max(1) }
+
+/// Parses the one filter supported, `<attribute> eq "<value>"`, returning the
+/// value.
+fn eq_filter<'a>(filter: &'a str, attribute: &str) -> Result<&'a str> {
+	let mut parts = filter.trim().splitn(3, ' ');
+	let (Some(attr), Some(op), Some(value)) = (parts.next(), parts.next(), parts.next()) else {
+		return Err!(Request(InvalidParam("Unsupported SCIM filter.")));
+	};
+
+	if !attr.eq_ignore_ascii_case(attribute) || !op.eq_ignore_ascii_case("eq") {
+		return Err!(Request(InvalidParam("Only filtering by {attribute} eq is supported.")));
+	}
+
+	value
+		.trim()
+		.strip_prefix('"')
+		.and_then(|value| value.strip_suffix('"'))
+		.ok_or_else(|| err!(Request(InvalidParam("SCIM filter value must be quoted."))))
+}
@@ -0,0 +1,63 @@
+use serde_json::json;
+
+use super::{ListQuery, eq_filter, list_response, page, users::active_value};
+
+fn query(start_index: Option<usize>, count: Option<usize>) -> ListQuery {
+	ListQuery { filter: None, start_index, count }
+}
+
+#[test]
+fn eq_filter_value() {
+	assert_eq!(eq_filter(r#"userName eq "alice""#, "userName").unwrap(), "alice");
+	assert_eq!(eq_filter(r#" username EQ "alice" "#, "userName").unwrap(), "alice");
+	assert_eq!(eq_filter(r#"displayName eq "Team A""#, "displayName").unwrap(), "Team A");
+	assert_eq!(eq_filter(r#"userName eq """#, "userName").unwrap(), "");
+}
+
+#[test]
+fn eq_filter_unsupported() {
+	assert!(eq_filter("", "userName").is_err());
+	assert!(eq_filter("userName eq", "userName").is_err());
+	assert!(eq_filter(r#"userName ne "alice""#, "userName").is_err());
+	assert!(eq_filter(r#"displayName eq "alice""#, "userName").is_err());
+	assert!(eq_filter("userName eq alice", "userName").is_err());
+	assert!(eq_filter(r#"userName eq "alice"#, "userName").is_err());
+}
+
+#[test]
+fn page_bounds() {
+	let items: Vec<_> = (1..=5).collect();
+
+	assert_eq!(page(&query(None, None), &items), [1, 2, 3, 4, 5]);
+	assert_eq!(page(&query(Some(1), Some(2)), &items), [1, 2]);
+	assert_eq!(page(&query(Some(0), Some(2)), &items), [1, 2]);
+	assert_eq!(page(&query(Some(4), Some(10)), &items), [4, 5]);
+	assert_eq!(page(&query(Some(2), Some(0)), &items), [] as [i32; 0]);
+	assert_eq!(page(&query(Some(6), None), &items), [] as [i32; 0]);
+	assert_eq!(page(&query(Some(usize::MAX), Some(usize::MAX)), &items), [] as [i32; 0]);
+}
+
+#[test]
+fn list_response_counts() {
+	let response = list_response(&query(Some(3), Some(1)), 5, vec![json!({"id": "c"})]);
+
+	assert_eq!(response["totalResults"], 5);
+	assert_eq!(response["startIndex"], 3);
+	assert_eq!(response["itemsPerPage"], 1);
+	assert_eq!(response["Resources"], json!([{"id": "c"}]));
+
+	let response = list_response(&query(Some(0), None), 0, Vec::new());
+	assert_eq!(response["startIndex"], 1);
+	assert_eq!(response["itemsPerPage"], 0);
+}
+
+#[test]
+fn active_value_forms() {
+	assert_eq!(active_value(&json!(true)), Some(true));
+	assert_eq!(active_value(&json!(false)), Some(false));
+	assert_eq!(active_value(&json!("true")), Some(true));
+	assert_eq!(active_value(&json!("false")), Some(false));
+	assert_eq!(active_value(&json!("False")), None);
+	assert_eq!(active_value(&json!(1)), None);
+	assert_eq!(active_value(&json!(null)), None);
+}
@@ -0,0 +1,345 @@
+use axum::{
+	extract::{Path, Query, State},
+	response::Response,
+};
+use bytes::Bytes;
+use futures::{FutureExt, StreamExt};
+use http::StatusCode;
+use ruma::{
+	OwnedRoomId, OwnedUserId, UserId,
+	events::{
+		GlobalAccountDataEventType,
+		push_rules::{PushRulesEvent, PushRulesEventContent},
+	},
+	push,
+};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use tuwunel_core::{Err, Result, err, utils, utils::ReadyExt};
+use tuwunel_service::Services;
+
+use super::{
+	BASE_PATH, ListQuery, PASSWORD_LENGTH, PatchRequest, SCHEMA_USER, Token, check_token,
+	eq_filter, list_response, page, parse_body, respond,
+};
+use crate::client::{
+	AutoJoinCohort, auto_join_rooms, full_user_deactivate, leave_all_rooms, update_displayname,
+};
+
+/// Attributes of a User resource read from create and replace requests.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UserRequest {
+	user_name: String,
+	display_name: Option<String>,
+	active: Option<bool>,
+	password: Option<String>,
+}
+
+/// # `GET /_tuwunel/scim/v2/Users`
+///
+/// Lists local users, optionally filtered by `userName eq "<localpart>"`.
+pub(crate) async fn scim_list_users_route(
+	State(services): State<crate::State>,
+	token: Token,
+	Query(query): Query<ListQuery>,
+) -> Response {
+	respond(StatusCode::OK, list_users(&services, &token, &query).await)
+}
+
+/// # `POST /_tuwunel/scim/v2/Users`
+///
+/// Creates a local user.
+pub(crate) async fn scim_create_user_route(
+	State(services): State<crate::State>,
+	token: Token,
+	body: Bytes,
+) -> Response {
+	respond(StatusCode::CREATED, create_user(&services, &token, &body).await)
+}
+
+/// # `GET /_tuwunel/scim/v2/Users/{id}`
+pub(crate) async fn scim_get_user_route(
+	State(services): State<crate::State>,
+	token: Token,
+	Path(id): Path<String>,
+) -> Response {
+	let result = async {
+		check_token(&services, &token)?;
+		let user_id = local_user(&services, &id).await?;
+		Ok(user_resource(&services, &user_id).await)
+	};
+
+	respond(StatusCode::OK, result.await)
+}
+
+/// # `PUT /_tuwunel/scim/v2/Users/{id}`
+///
+/// Replaces the display name and active state of a user. The user name is
+/// the Matrix ID's localpart and cannot be changed.
+pub(crate) async fn scim_replace_user_route(
+	State(services): State<crate::State>,
+	token: Token,
+	Path(id): Path<String>,
+	body: Bytes,
+) -> Response {
+	let result = async {
+		check_token(&services, &token)?;
+		let user_id = local_user(&services, &id).await?;
+		let request: UserRequest = parse_body(&body)?;
+		if request.user_name != user_id.localpart() {
+			return Err!(Request(InvalidParam("userName cannot be changed.")));
+		}
+
+		if let Some(display_name) = request.display_name {
+			set_display_name(&services, &user_id, display_name).await;
+		}
+
+		set_active(&services, &user_id, request.active.unwrap_or(true)).await?;
+		Ok(user_resource(&services, &user_id).await)
+	};
+
+	respond(StatusCode::OK, result.await)
+}
+
+/// # `PATCH /_tuwunel/scim/v2/Users/{id}`
+///
+/// Applies `replace` operations on `displayName` and `active`.
+pub(crate) async fn scim_patch_user_route(
+	State(services): State<crate::State>,
+	token: Token,
+	Path(id): Path<String>,
+	body: Bytes,
+) -> Response {
+	let result = async {
+		check_token(&services, &token)?;
+		let user_id = local_user(&services, &id).await?;
+		let request: PatchRequest = parse_body(&body)?;
+		for operation in request.operations {
+			if !operation.op.eq_ignore_ascii_case("replace") {
+				let op = operation.op;
+				return Err!(Request(InvalidParam("Unsupported SCIM operation {op}.")));
+			}
+
+			// Without a path the value is an object of the attributes to replace.
+			let attributes = match (operation.path, operation.value) {
+				| (Some(path), Some(value)) =>
+					Value::Object([(path, value)].into_iter().collect()),
+				| (None, Some(value @ Value::Object(_))) => value,
+				| _ => return Err!(Request(InvalidParam("Invalid SCIM replace operation."))),
+			};
+
+			if let Some(display_name) = attributes
+				.get("displayName")
+				.and_then(Value::as_str)
+			{
+				set_display_name(&services, &user_id, display_name.to_owned()).await;
+			}
+
+			if let Some(active) = attributes.get("active").and_then(active_value) {
+				set_active(&services, &user_id, active).await?;
+			}
+		}
+
+		Ok(user_resource(&services, &user_id).await)
+	};
+
+	respond(StatusCode::OK, result.await)
+}
+
+/// # `DELETE /_tuwunel/scim/v2/Users/{id}`
+///
+/// Deactivates the user. Matrix IDs cannot be reused, so the account is kept.
+pub(crate) async fn scim_delete_user_route(
+	State(services): State<crate::State>,
+	token: Token,
+	Path(id): Path<String>,
+) -> Response {
+	let result = async {
+		check_token(&services, &token)?;
+		let user_id = local_user(&services, &id).await?;
+		set_active(&services, &user_id, false).await?;
+		Ok(Value::Null)
+	};
+
+	respond(StatusCode::NO_CONTENT, result.await)
+}
+
+async fn list_users(services: &Services, token: &Token, query: &ListQuery) -> Result<Value> {
+	check_token(services, token)?;
+
+	let user_ids: Vec<OwnedUserId> = match query.filter.as_deref() {
+		| Some(filter) => {
+			let user_name = eq_filter(filter, "userName")?;
+			let user_id = UserId::parse_with_server_name(
+				user_name.to_lowercase(),
+				services.globals.server_name(),
+			);
+
+			match user_id {
+				| Ok(user_id) if services.users.exists(&user_id).await => vec![user_id],
+				| _ => Vec::new(),
+			}
+		},
+		| None =>
+			services
+				.users
+				.stream()
+				.ready_filter(|user_id| services.globals.user_is_local(user_id))
+				.ready_filter(|user_id| **user_id != *services.globals.server_user)
+				.map(ToOwned::to_owned)
+				.collect()
+				.await,
+	};
+
+	let resources = futures::stream::iter(page(query, &user_ids))
+		.then(|user_id| user_resource(services, user_id))
+		.collect()
+		.await;
+
+	Ok(list_response(query, user_ids.len(), resources))
+}
+
+async fn create_user(services: &Services, token: &Token, body: &[u8]) -> Result<Value> {
+	check_token(services, token)?;
+
+	let request: UserRequest = parse_body(body)?;
+	let user_id = UserId::parse_with_server_name(
+		request.user_name.to_lowercase(),
+		services.globals.server_name(),
+	)
+	.map_err(|e| err!(Request(InvalidUsername("Invalid userName: {e}"))))?;
+
+	if user_id.validate_strict().is_err() {
+		return Err!(Request(InvalidUsername("userName contains disallowed characters.")));
+	}
+
+	services
+		.globals
+		.check_username_policy(user_id.localpart(), true)?;
+
+	if services.users.exists(&user_id).await {
+		return Err!(Conflict("User {user_id} already exists."));
+	}
+
+	let password = request
+		.password
+		.unwrap_or_else(|| utils::random_string(PASSWORD_LENGTH));
+
+	services
+		.users
+		.create(&user_id, Some(&password), Some("scim"))
+		.await?;
+
+	let display_name = request
+		.display_name
+		.unwrap_or_else(|| user_id.localpart().to_owned());
+
+	services
+		.users
+		.set_displayname(&user_id, Some(display_name));
+
+	services
+		.account_data
+		.update(
+			None,
+			&user_id,
+			GlobalAccountDataEventType::PushRules
+				.to_string()
+				.into(),
+			&serde_json::to_value(PushRulesEvent {
+				content: PushRulesEventContent {
+					global: push::Ruleset::server_default(&user_id),
+				},
+			})?,
+		)
+		.await?;
+
+	auto_join_rooms(services, &user_id, AutoJoinCohort::Password)
+		.boxed()
+		.await;
+
+	if request.active == Some(false) {
+		set_active(services, &user_id, false).await?;
+	}
+
+	Ok(user_resource(services, &user_id).await)
+}
+
+/// Resolves the resource ID of a User, its Matrix ID, to an existing local
+/// user.
+async fn local_user(services: &Services, id: &str) -> Result<OwnedUserId> {
+	let user_id = UserId::parse(id).map_err(|_| err!(Request(NotFound("No such user."))))?;
+
+	if !services.globals.user_is_local(&user_id)
+		|| user_id == services.globals.server_user
+		|| !services.users.exists(&user_id).await
+	{
+		return Err!(Request(NotFound("No such user.")));
+	}
+
+	Ok(user_id)
+}
+
+async fn user_resource(services: &Services, user_id: &UserId) -> Value {
+	let active = !services
+		.users
+		.is_deactivated(user_id)
+		.await
+		.unwrap_or(true);
+
+	json!({
+		"schemas": [SCHEMA_USER],
+		"id": user_id,
+		"userName": user_id.localpart(),
+		"displayName": services.users.displayname(user_id).await.ok(),
+		"active": active,
+		"meta": {
+			"resourceType": "User",
+			"location": format!("{BASE_PATH}/Users/{user_id}"),
+		},
+	})
+}
+
+async fn set_display_name(services: &Services, user_id: &UserId, display_name: String) {
+	let all_joined_rooms = joined_rooms(services, user_id).await;
+	update_displayname(services, user_id, Some(display_name), &all_joined_rooms).await;
+}
+
+/// Deactivates the user as the admin `deactivate` command does, or gives a
+/// deactivated user a random password so their account can be used again.
+async fn set_active(services: &Services, user_id: &UserId, active: bool) -> Result {
+	let deactivated = services.users.is_deactivated(user_id).await?;
+	if active && deactivated {
+		let password = utils::random_string(PASSWORD_LENGTH);
+		services
+			.users
+			.set_password(user_id, Some(&password))
+			.await?;
+	} else if !active && !deactivated {
+		let all_joined_rooms = joined_rooms(services, user_id).await;
+		full_user_deactivate(services, user_id, &all_joined_rooms).await?;
+		leave_all_rooms(services, user_id).await;
+	}
+
+	Ok(())
+}
+
+async fn joined_rooms(services: &Services, user_id: &UserId) -> Vec<OwnedRoomId> {
+	services
+		.rooms
+		.state_cache
+		.rooms_joined(user_id)
+		.map(ToOwned::to_owned)
+		.collect()
+		.await
+}
+
+/// Reads `active` as a boolean, or as the string some clients send.
+pub(super) fn active_value(value: &Value) -> Option<bool> {
+	match value {
+		| Value::Bool(active) => Some(*active),
+		| Value::String(active) => active.parse().ok(),
+		| _ => None,
+	}
+}
@@ -68,6 +68,22 @@ pub(crate) async fn send_message_event_route(
 		});
 	}
 
+	services
+		.users
+		.check_message_rate(sender_user, appservice_info.is_some())
+		.await?;
+
+	services
+		.rooms
+		.pdu_metadata
+		.check_annotation(
+			&body.room_id,
+			sender_user,
+			&body.event_type.clone().into(),
+			body.body.body.json(),
+		)
+		.await?;
+
 	let mut unsigned = BTreeMap::new();
 	unsigned.insert("transaction_id".to_owned(), body.txn_id.to_string().into());
 
@@ -1,8 +1,10 @@
+use futures::FutureExt;
 use ruma::{OwnedUserId, UserId};
 use tuwunel_core::{Err, Result, debug};
 use tuwunel_service::Services;
 
 use super::password_login;
+use crate::client::{AutoJoinCohort, auto_join_rooms};
 
 /// Authenticates the given user through the configured LDAP server.
 ///
@@ -52,6 +54,10 @@ pub(super) async fn ldap_login(
 			.users
 			.create(lowercased_user_id, Some("*"), Some("ldap"))
 			.await?;
+
+		auto_join_rooms(services, lowercased_user_id, AutoJoinCohort::Ldap)
+			.boxed()
+			.await;
 	}
 
 	let is_tuwunel_admin = services
@@ -1,30 +1,50 @@
 mod appservice;
 mod ldap;
 mod logout;
+mod passkey;
 mod password;
+mod password_provider;
+mod sso;
+#[cfg(test)]
+mod tests;
 mod token;
 
 use axum::extract::State;
 use axum_client_ip::InsecureClientIp;
-use ruma::api::client::session::{
-	get_login_types::{
-		self,
-		v3::{ApplicationServiceLoginType, LoginType, PasswordLoginType, TokenLoginType},
-	},
-	login::{
-		self,
-		v3::{DiscoveryInfo, HomeserverInfo, LoginInfo},
+use futures::FutureExt;
+use ruma::{
+	UserId,
+	api::client::session::{
+		get_login_types::{
+			self,
+			v3::{
+				ApplicationServiceLoginType, LoginType, PasswordLoginType, SsoLoginType,
+				TokenLoginType,
+			},
+		},
+		login::{
+			self,
+			v3::{DiscoveryInfo, HomeserverInfo, LoginInfo},
+		},
 	},
+	serde::JsonObject,
 };
 use tuwunel_core::{Err, Result, info, utils, utils::stream::ReadyExt};
+use tuwunel_service::{Services, users};
 
-use self::{ldap::ldap_login, password::password_login};
+use self::{
+	ldap::ldap_login, password::password_login, password_provider::password_provider_login,
+};
 pub(crate) use self::{
 	logout::{logout_all_route, logout_route},
+	sso::{sso_callback_route, sso_redirect_route},
 	token::login_token_route,
 };
 use super::{DEVICE_ID_LENGTH, TOKEN_LENGTH};
-use crate::Ruma;
+use crate::{
+	Ruma,
+	client::{AutoJoinCohort, auto_join_rooms},
+};
 
 /// # `GET /_matrix/client/v3/login`
 ///
@@ -36,13 +56,23 @@ pub(crate) async fn get_login_types_route(
 	InsecureClientIp(client): InsecureClientIp,
 	_body: Ruma<get_login_types::v3::Request>,
 ) -> Result<get_login_types::v3::Response> {
-	Ok(get_login_types::v3::Response::new(vec![
+	let mut login_types = vec![
 		LoginType::Password(PasswordLoginType::default()),
 		LoginType::ApplicationService(ApplicationServiceLoginType::default()),
 		LoginType::Token(TokenLoginType {
 			get_login_token: services.config.login_via_existing_session,
 		}),
-	]))
+	];
+
+	if services.config.sso.enable {
+		login_types.push(LoginType::Sso(SsoLoginType::default()));
+	}
+
+	if services.users.passkeys_available() {
+		login_types.push(LoginType::new(passkey::LOGIN_TYPE, JsonObject::new())?);
+	}
+
+	Ok(get_login_types::v3::Response::new(login_types))
 }
 
 /// # `POST /_matrix/client/v3/login`
@@ -71,6 +101,8 @@ pub(crate) async fn login_route(
 		| LoginInfo::Token(info) => token::handle_login(&services, &body, info).await?,
 		| LoginInfo::ApplicationService(info) =>
 			appservice::handle_login(&services, &body, info).await?,
+		| _ if passkey::is_passkey_login(&body) =>
+			passkey::handle_login(&services, &body).await?,
 		| _ => {
 			return Err!(Request(Unknown(debug_warn!(
 				?body.login_info,
@@ -89,6 +121,14 @@ pub(crate) async fn login_route(
 		.clone()
 		.unwrap_or_else(|| utils::random_string(DEVICE_ID_LENGTH).into());
 
+	if device_id.as_str().is_empty() {
+		return Err!(Request(InvalidParam("Device ID must not be empty.")));
+	}
+
+	if users::is_impersonation_device(&device_id) {
+		return Err!(Request(InvalidParam("Device ID uses a reserved prefix.")));
+	}
+
 	// Determine if device_id was provided and exists in the db for this user
 	let device_exists = services
 		.users
@@ -139,3 +179,36 @@ pub(crate) async fn login_route(
 		refresh_token: None,
 	})
 }
+
+/// Creates the local account of a user authenticated by an external provider,
+/// recorded as `origin`, subject to this server's username policy. Like LDAP
+/// users, these accounts get a dummy password which is never read; an empty
+/// password is reserved for deactivated accounts.
+async fn create_external_user(
+	services: &Services,
+	user_id: &UserId,
+	origin: &str,
+	displayname: Option<String>,
+	cohort: AutoJoinCohort,
+) -> Result {
+	services
+		.globals
+		.check_username_policy(user_id.localpart(), false)?;
+
+	services
+		.users
+		.create(user_id, Some("*"), Some(origin))
+		.await?;
+
+	if let Some(displayname) = displayname {
+		services
+			.users
+			.set_displayname(user_id, Some(displayname));
+	}
+
+	auto_join_rooms(services, user_id, cohort)
+		.boxed()
+		.await;
+
+	Ok(())
+}
@@ -0,0 +1,37 @@
+use ruma::{CanonicalJsonValue, OwnedUserId, api::client::session::login::v3::Request};
+use tuwunel_core::{Result, err};
+use tuwunel_service::Services;
+
+use crate::Ruma;
+
+/// Login type for passkeys; the request carries the `session` from
+/// `/_tuwunel/passkey/login/start` and the WebAuthn `credential`.
+pub(super) const LOGIN_TYPE: &str = "org.tuwunel.login.passkey";
+
+pub(super) fn is_passkey_login(body: &Ruma<Request>) -> bool {
+	body_field(body, "type").and_then(CanonicalJsonValue::as_str) == Some(LOGIN_TYPE)
+}
+
+pub(super) async fn handle_login(
+	services: &Services,
+	body: &Ruma<Request>,
+) -> Result<OwnedUserId> {
+	let session = body_field(body, "session")
+		.and_then(CanonicalJsonValue::as_str)
+		.ok_or_else(|| err!(Request(MissingParam("Missing passkey login session."))))?;
+
+	let credential = body_field(body, "credential")
+		.ok_or_else(|| err!(Request(MissingParam("Missing passkey credential."))))?;
+
+	services
+		.users
+		.finish_passkey_login(session, serde_json::to_value(credential)?)
+		.await
+}
+
+fn body_field<'a>(body: &'a Ruma<Request>, field: &str) -> Option<&'a CanonicalJsonValue> {
+	match &body.json_body {
+		| Some(CanonicalJsonValue::Object(json)) => json.get(field),
+		| _ => None,
+	}
+}
@@ -1,15 +1,22 @@
 use futures::{FutureExt, TryFutureExt};
 use ruma::{
-	OwnedUserId, UserId,
+	CanonicalJsonObject, CanonicalJsonValue, OwnedUserId, UserId,
 	api::client::{
 		session::login::v3::{Password, Request},
 		uiaa,
 	},
 };
-use tuwunel_core::{Err, Result, debug_error, err, utils::hash};
-use tuwunel_service::Services;
+use tuwunel_core::{
+	Err, Result, debug_error, err,
+	utils::{self, hash},
+	warn,
+};
+use tuwunel_service::{
+	Services,
+	uiaa::{SESSION_ID_LENGTH, TOTP_AUTH_TYPE},
+};
 
-use super::ldap_login;
+use super::{ldap_login, password_provider_login};
 use crate::Ruma;
 
 pub(super) async fn handle_login(
@@ -46,13 +53,82 @@ pub(super) async fn handle_login(
 		return Err!(Request(Unknown("User ID does not belong to this homeserver")));
 	}
 
-	if cfg!(feature = "ldap") && services.config.ldap.enable {
+	let user_id = if cfg!(feature = "ldap") && services.config.ldap.enable {
 		ldap_login(services, &user_id, &lowercased_user_id, password)
 			.boxed()
-			.await
+			.await?
+	} else if services.config.password_provider.enable {
+		password_provider_login(services, &user_id, &lowercased_user_id, password)
+			.boxed()
+			.await?
 	} else {
-		password_login(services, &user_id, &lowercased_user_id, password).await
+		password_login(services, &user_id, &lowercased_user_id, password).await?
+	};
+
+	second_factor(services, body, &user_id).await?;
+
+	Ok(user_id)
+}
+
+/// Requires users with two-factor authentication to also complete the TOTP
+/// stage, given as user-interactive `auth` alongside the login request. The
+/// first attempt without it starts a session and returns the UIAA response.
+/// The session belongs to the device being logged in, so the client must name
+/// it.
+async fn second_factor(services: &Services, body: &Ruma<Request>, user_id: &UserId) -> Result {
+	if !services.users.totp_enabled(user_id).await {
+		return Ok(());
 	}
+
+	let Some(device_id) = body
+		.device_id
+		.as_deref()
+		.filter(|device_id| !device_id.as_str().is_empty())
+	else {
+		return Err!(Request(MissingParam(
+			"A device_id is required to log in with two-factor authentication."
+		)));
+	};
+
+	let mut uiaainfo = uiaa::UiaaInfo {
+		flows: vec![uiaa::AuthFlow {
+			stages: vec![uiaa::AuthType::from(TOTP_AUTH_TYPE)],
+		}],
+		completed: Vec::new(),
+		params: Box::default(),
+		session: None,
+		auth_error: None,
+	};
+
+	let auth = match &body.json_body {
+		| Some(CanonicalJsonValue::Object(json)) => json.get("auth"),
+		| _ => None,
+	};
+
+	let Some(auth) = auth else {
+		uiaainfo.session = Some(utils::random_string(SESSION_ID_LENGTH));
+		let request = CanonicalJsonValue::Object(CanonicalJsonObject::new());
+		services
+			.uiaa
+			.create(user_id, device_id, &uiaainfo, &request);
+
+		return Err!(Uiaa(uiaainfo));
+	};
+
+	let auth: uiaa::AuthData = serde_json::to_value(auth)
+		.and_then(serde_json::from_value)
+		.map_err(|e| err!(Request(BadJson("Invalid auth: {e}"))))?;
+
+	let (worked, uiaainfo) = services
+		.uiaa
+		.try_auth(user_id, device_id, &auth, &uiaainfo)
+		.await?;
+
+	if !worked {
+		return Err!(Uiaa(uiaainfo));
+	}
+
+	Ok(())
 }
 
 /// Authenticates the given user by its ID and its password.
@@ -81,9 +157,22 @@ pub(super) async fn password_login(
 		return Err!(Request(UserDeactivated("The user has been deactivated")));
 	}
 
-	hash::verify_password(password, &hash)
+	let config = &services.config;
+	hash::verify_password(password, &hash, config.synapse_password_pepper.as_deref())
 		.inspect_err(|e| debug_error!("{e}"))
 		.map_err(|_| err!(Request(Forbidden("Wrong username or password."))))?;
 
+	// Replace imported or outdated hashes now that the password is known.
+	if hash::password_needs_rehash(&hash, &config.password_params()) {
+		services
+			.users
+			.set_password(user_id, Some(password))
+			.await
+			.inspect_err(|e| warn!("Failed to rehash password: {e}"))
+			.ok();
+	}
+
+	services.emergency.password_used(user_id).await?;
+
 	Ok(user_id.to_owned())
 }
@@ -0,0 +1,93 @@
+use ruma::{OwnedUserId, UserId};
+use tuwunel_core::{Err, Result, err, warn};
+use tuwunel_service::{Services, users::PasswordProviderOutcome};
+
+use super::{create_external_user, password_login};
+use crate::client::AutoJoinCohort;
+
+/// Authenticates the given user through the configured HTTP password
+/// provider.
+///
+/// Creates the user if the provider authenticates them and they do not already
+/// have an account, unless `create_users` is disabled or the username is
+/// against this server's username policy.
+#[tracing::instrument(skip_all, fields(%user_id), name = "password_provider")]
+pub(super) async fn password_provider_login(
+	services: &Services,
+	user_id: &UserId,
+	lowercased_user_id: &UserId,
+	password: &str,
+) -> Result<OwnedUserId> {
+	let config = &services.config.password_provider;
+	let auth = services
+		.users
+		.auth_password_provider(lowercased_user_id, password)
+		.await;
+
+	match PasswordProviderOutcome::of(&auth, config.fallback_to_local) {
+		| PasswordProviderOutcome::CheckLocal => {
+			if let Err(e) = &auth {
+				warn!("Password provider failed, checking the local password: {e}");
+			}
+
+			return password_login(services, user_id, lowercased_user_id, password).await;
+		},
+		| PasswordProviderOutcome::Refused => {
+			return Err!(Request(Forbidden("Wrong username or password.")));
+		},
+		| PasswordProviderOutcome::Authenticated | PasswordProviderOutcome::Failed => {},
+	}
+
+	let auth = auth?;
+
+	match services
+		.users
+		.is_deactivated(lowercased_user_id)
+		.await
+	{
+		| Ok(true) => {
+			return Err!(Request(UserDeactivated("The user has been deactivated")));
+		},
+		| Ok(false) => {},
+		| Err(_) if !config.create_users => {
+			return Err!(Request(Forbidden("Wrong username or password.")));
+		},
+		| Err(_) => {
+			lowercased_user_id
+				.validate_strict()
+				.map_err(|e| err!(Request(InvalidUsername("Username is invalid: {e}"))))?;
+
+			create_external_user(
+				services,
+				lowercased_user_id,
+				"password_provider",
+				auth.displayname,
+				AutoJoinCohort::Password,
+			)
+			.await?;
+		},
+	}
+
+	let is_tuwunel_admin = services
+		.admin
+		.user_is_admin(lowercased_user_id)
+		.await;
+
+	match auth.admin {
+		| Some(true) if !is_tuwunel_admin => {
+			services
+				.admin
+				.make_user_admin(lowercased_user_id)
+				.await?;
+		},
+		| Some(false) if is_tuwunel_admin => {
+			services
+				.admin
+				.revoke_admin(lowercased_user_id)
+				.await?;
+		},
+		| _ => {},
+	}
+
+	Ok(lowercased_user_id.to_owned())
+}
@@ -0,0 +1,179 @@
+use axum::{
+	extract::{Query, State},
+	response::{Html, IntoResponse, Response},
+};
+use axum_client_ip::SecureClientIp;
+use http::{StatusCode, header::LOCATION};
+use ruma::{OwnedUserId, UserId};
+use serde::Deserialize;
+use tuwunel_core::{Err, Result, err, info, utils, utils::HtmlEscape};
+use tuwunel_service::{
+	Services,
+	sso::{SsoIdentity, client_url},
+};
+
+use super::{TOKEN_LENGTH, create_external_user};
+use crate::client::AutoJoinCohort;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct RedirectQuery {
+	redirect_url: String,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct CallbackQuery {
+	code: Option<String>,
+	state: Option<String>,
+	error: Option<String>,
+}
+
+/// # `GET /_matrix/client/v3/login/sso/redirect`
+///
+/// Sends the user to the SSO provider to authenticate, after which they are
+/// sent back to the client's `redirectUrl` with a login token.
+///
+/// <https://spec.matrix.org/v1.13/client-server-api/#get_matrixclientv3loginssoredirect>
+pub(crate) async fn sso_redirect_route(
+	State(services): State<crate::State>,
+	SecureClientIp(client): SecureClientIp,
+	Query(query): Query<RedirectQuery>,
+) -> Result<Response> {
+	client_url(&query.redirect_url)?;
+
+	let url = services
+		.sso
+		.authorization_url(&query.redirect_url, client)
+		.await?;
+
+	Ok(redirect(url.as_str()))
+}
+
+/// # `GET /_matrix/client/v3/login/sso/callback`
+///
+/// Where the SSO provider sends the user back to. Logs them in, creating their
+/// account on first login, and sends them on to the client with a login token
+/// for `m.login.token`.
+pub(crate) async fn sso_callback_route(
+	State(services): State<crate::State>,
+	Query(query): Query<CallbackQuery>,
+) -> Result<Response> {
+	if let Some(error) = query.error {
+		return Err!(Request(Forbidden("The SSO provider refused the login: {error}")));
+	}
+
+	let (Some(state), Some(code)) = (query.state, query.code) else {
+		return Err!(Request(MissingParam("Missing state or code.")));
+	};
+
+	let identity = services.sso.authenticate(&state, &code).await?;
+	let user_id = sso_login(&services, &identity).await?;
+
+	let login_token = utils::random_string(TOKEN_LENGTH);
+	services
+		.users
+		.create_login_token(&user_id, &login_token);
+
+	let mut url = client_url(&identity.redirect_url)?;
+	url.query_pairs_mut()
+		.append_pair("loginToken", &login_token);
+
+	let trusted = services
+		.config
+		.sso
+		.client_redirect_urls
+		.iter()
+		.any(|prefix| identity.redirect_url.starts_with(prefix.as_str()));
+
+	if trusted {
+		return Ok(redirect(url.as_str()));
+	}
+
+	// Clients not configured as trusted could be phishing for the login token,
+	// so the user is shown where it is going before it is sent.
+	let host = url.host_str().unwrap_or(url.scheme());
+	let page = format!(
+		"<!DOCTYPE html><html><head><title>Continue to your client</title></head><body><p>You \
+		 are logged in as {user_id}. Continue to <a href=\"{url}\">{host}</a> to finish logging \
+		 in.</p><p>If you did not start this login, close this page.</p></body></html>",
+		user_id = HtmlEscape(user_id.as_str()),
+		url = HtmlEscape(url.as_str()),
+		host = HtmlEscape(host),
+	);
+
+	Ok(Html(page).into_response())
+}
+
+/// The user the SSO provider authenticated, linking or creating their account
+/// on their first login.
+#[tracing::instrument(skip_all, fields(subject = %identity.subject), name = "sso")]
+async fn sso_login(services: &Services, identity: &SsoIdentity) -> Result<OwnedUserId> {
+	if let Ok(user_id) = services
+		.sso
+		.user_for_subject(&identity.subject)
+		.await
+	{
+		if services
+			.users
+			.is_deactivated(&user_id)
+			.await
+			.unwrap_or(true)
+		{
+			return Err!(Request(UserDeactivated("The user has been deactivated")));
+		}
+
+		return Ok(user_id);
+	}
+
+	let config = &services.config.sso;
+	let Some(username) = identity.username.as_deref() else {
+		let claim = &config.username_claim;
+		return Err!(Request(Forbidden("The SSO provider did not return the {claim} claim.")));
+	};
+
+	let localpart = username.to_lowercase();
+	let user_id =
+		UserId::parse_with_server_name(localpart.as_str(), services.globals.server_name())
+			.map_err(|e| err!(Request(InvalidUsername("Invalid username {username}: {e}"))))?;
+
+	user_id
+		.validate_strict()
+		.map_err(|e| err!(Request(InvalidUsername("Invalid username {username}: {e}"))))?;
+
+	if services.users.exists(&user_id).await {
+		if !config.link_existing_users {
+			return Err!(Request(Forbidden(
+				"An account named {user_id} already exists and is not linked to this SSO user."
+			)));
+		}
+
+		if services.users.is_deactivated(&user_id).await? {
+			return Err!(Request(UserDeactivated("The user has been deactivated")));
+		}
+	} else {
+		if !config.create_users {
+			return Err!(Request(Forbidden("No account is linked to this SSO user.")));
+		}
+
+		create_external_user(
+			services,
+			&user_id,
+			"sso",
+			identity.displayname.clone(),
+			AutoJoinCohort::Sso,
+		)
+		.await?;
+
+		info!("New SSO user {user_id} registered on this server");
+	}
+
+	services
+		.sso
+		.link_subject(&identity.subject, &user_id)?;
+
+	Ok(user_id)
+}
+
+fn redirect(location: &str) -> Response {
+	(StatusCode::FOUND, [(LOCATION, location.to_owned())]).into_response()
+}
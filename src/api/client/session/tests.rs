@@ -0,0 +1,34 @@
+use ruma::api::client::uiaa::AuthData;
+use serde_json::json;
+
+use super::token::continued_session;
+
+fn password_auth(session: Option<&str>) -> AuthData {
+	let mut auth = json!({
+		"type": "m.login.password",
+		"identifier": { "type": "m.id.user", "user": "alice" },
+		"password": "hunter2",
+	});
+
+	if let Some(session) = session {
+		auth["session"] = session.into();
+	}
+
+	serde_json::from_value(auth).expect("valid auth data")
+}
+
+#[test]
+fn login_token_auth_continues_session() {
+	let auth = password_auth(Some("abcdef"));
+
+	assert_eq!(continued_session(&auth).unwrap(), "abcdef");
+}
+
+#[test]
+fn login_token_auth_without_session_refused() {
+	// Completing the flow in one request would start a fresh session for every
+	// request, none of which could be recognized when replayed.
+	let auth = password_auth(None);
+
+	assert!(continued_session(&auth).is_err());
+}
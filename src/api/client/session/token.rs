@@ -12,7 +12,7 @@
 		uiaa,
 	},
 };
-use tuwunel_core::{Err, Result, utils::random_string};
+use tuwunel_core::{Err, Result, err, utils::random_string};
 use tuwunel_service::{Services, uiaa::SESSION_ID_LENGTH};
 
 use super::TOKEN_LENGTH;
@@ -25,7 +25,8 @@ pub(super) async fn handle_login(
 ) -> Result<OwnedUserId> {
 	let Token { token } = info;
 
-	if !services.config.login_via_existing_session {
+	// Login tokens are also issued at the end of SSO logins.
+	if !services.config.login_via_existing_session && !services.config.sso.enable {
 		return Err!(Request(Unknown("Token login is not enabled.")));
 	}
 
@@ -48,8 +49,8 @@ pub(crate) async fn login_token_route(
 		return Err!(Request(Forbidden("Login via an existing session is not enabled")));
 	}
 
-	// This route SHOULD have UIA
-	// TODO: How do we make only UIA sessions that have not been used before valid?
+	// This route SHOULD have UIA. Each completed session may issue only one
+	// token, so the auth must continue a session issued by this route.
 	let (sender_user, sender_device) = body.sender();
 
 	let password_flow = uiaa::AuthFlow { stages: vec![uiaa::AuthType::Password] };
@@ -64,6 +65,8 @@ pub(crate) async fn login_token_route(
 
 	match &body.auth {
 		| Some(auth) => {
+			let session = continued_session(auth)?;
+
 			let (worked, uiaainfo) = services
 				.uiaa
 				.try_auth(sender_user, sender_device, auth, &uiaainfo)
@@ -73,7 +76,13 @@ pub(crate) async fn login_token_route(
 				return Err!(Uiaa(uiaainfo));
 			}
 
-			// Success!
+			if !services
+				.uiaa
+				.consume_session(sender_user, sender_device, session)
+				.await
+			{
+				return Err!(Request(Forbidden("UIA session has already been used.")));
+			}
 		},
 		| _ => match body.json_body.as_ref() {
 			| Some(json) => {
@@ -98,3 +107,10 @@ pub(crate) async fn login_token_route(
 		login_token,
 	})
 }
+
+/// The UIA session the auth continues. Auth which does not continue a session
+/// issued by the route is refused, since only a session can be spent once.
+pub(super) fn continued_session(auth: &uiaa::AuthData) -> Result<&str> {
+	auth.session()
+		.ok_or_else(|| err!(Request(Forbidden("Authentication must continue a UIA session."))))
+}
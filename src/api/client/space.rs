@@ -15,7 +15,8 @@
 use tuwunel_service::{
 	Services,
 	rooms::spaces::{
-		PaginationToken, SummaryAccessibility, get_parent_children_via, summary_to_chunk,
+		PaginationState, PaginationToken, SummaryAccessibility, get_parent_children_via,
+		summary_to_chunk,
 	},
 };
 
@@ -53,6 +54,29 @@ pub(crate) async fn get_hierarchy_route(
 		}
 	}
 
+	let resume: OptionFuture<_> = key
+		.as_ref()
+		.and(body.from.as_deref())
+		.map(|from| {
+			services
+				.rooms
+				.spaces
+				.pagination_take(body.sender_user(), from)
+		})
+		.into();
+
+	let resume = resume
+		.await
+		.flatten()
+		.filter(|state| state.parents.contains(&body.room_id));
+
+	// The stored traversal state supersedes walking down the token's path
+	let short_room_ids = key
+		.as_ref()
+		.filter(|_| resume.is_none())
+		.into_iter()
+		.flat_map(|t| t.short_room_ids.iter());
+
 	get_client_hierarchy(
 		&services,
 		body.sender_user(),
@@ -60,13 +84,13 @@ pub(crate) async fn get_hierarchy_route(
 		limit.try_into().unwrap_or(10),
 		max_depth.try_into().unwrap_or(usize::MAX),
 		body.suggested_only,
-		key.as_ref()
-			.into_iter()
-			.flat_map(|t| t.short_room_ids.iter()),
+		short_room_ids,
+		resume,
 	)
 	.await
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn get_client_hierarchy<'a, ShortRoomIds>(
 	services: &Services,
 	sender_user: &UserId,
@@ -75,6 +99,7 @@ async fn get_client_hierarchy<'a, ShortRoomIds>(
 	max_depth: usize,
 	suggested_only: bool,
 	short_room_ids: ShortRoomIds,
+	resume: Option<PaginationState>,
 ) -> Result<get_hierarchy::v1::Response>
 where
 	ShortRoomIds: Iterator<Item = &'a u64> + Clone + Send + Sync + 'a,
@@ -83,23 +108,32 @@ async fn get_client_hierarchy<'a, ShortRoomIds>(
 	type Entry = (OwnedRoomId, Via);
 	type Rooms = VecDeque<Entry>;
 
-	let mut queue: Rooms = [(
-		room_id.to_owned(),
-		room_id
-			.server_name()
-			.map(ToOwned::to_owned)
-			.into_iter()
-			.collect(),
-	)]
-	.into();
+	let (mut queue, mut parents): (Rooms, _) = match resume {
+		| Some(PaginationState { queue, parents }) => (queue, parents),
+		| None => {
+			let via = room_id
+				.server_name()
+				.map(ToOwned::to_owned)
+				.into_iter()
+				.collect();
+
+			([(room_id.to_owned(), via)].into(), BTreeSet::new())
+		},
+	};
 
 	let mut rooms = Vec::with_capacity(limit);
-	let mut parents = BTreeSet::new();
+	let mut remote_budget = services.rooms.spaces.remote_request_budget();
 	while let Some((current_room, via)) = queue.pop_front() {
 		let summary = services
 			.rooms
 			.spaces
-			.get_summary_and_children_client(&current_room, suggested_only, sender_user, &via)
+			.get_summary_and_children_client(
+				&current_room,
+				suggested_only,
+				sender_user,
+				&via,
+				&mut remote_budget,
+			)
 			.await?;
 
 		match (summary, current_room == room_id) {
@@ -151,19 +185,22 @@ async fn get_client_hierarchy<'a, ShortRoomIds>(
 				}
 
 				parents.insert(current_room.clone());
-				if rooms.len() >= limit {
-					break;
+				if parents.len() <= max_depth {
+					queue.extend(children);
 				}
 
-				if parents.len() > max_depth {
-					continue;
+				if rooms.len() >= limit {
+					break;
 				}
-
-				queue.extend(children);
 			},
 		}
 	}
 
+	let resume = PaginationState {
+		queue: queue.clone(),
+		parents: parents.clone(),
+	};
+
 	let next_batch: OptionFuture<_> = queue
 		.pop_front()
 		.map(|(room, _)| async move {
@@ -188,8 +225,14 @@ async fn get_client_hierarchy<'a, ShortRoomIds>(
 		})
 		.into();
 
-	Ok(get_hierarchy::v1::Response {
-		next_batch: next_batch.await.flatten(),
-		rooms,
-	})
+	let next_batch = next_batch.await.flatten();
+	if let Some(next_batch) = next_batch.as_deref() {
+		services
+			.rooms
+			.spaces
+			.pagination_put(sender_user, next_batch, resume)
+			.await;
+	}
+
+	Ok(get_hierarchy::v1::Response { next_batch, rooms })
 }
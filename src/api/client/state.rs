@@ -34,6 +34,11 @@ pub(crate) async fn send_state_event_for_key_route(
 ) -> Result<send_state_event::v3::Response> {
 	let sender_user = body.sender_user();
 
+	services
+		.users
+		.check_message_rate(sender_user, body.appservice_info.is_some())
+		.await?;
+
 	Ok(send_state_event::v3::Response {
 		event_id: send_state_event_for_key_helper(
 			&services,
@@ -1,16 +1,21 @@
+#[cfg(test)]
+mod tests;
 mod v3;
 mod v5;
 
-use futures::{StreamExt, pin_mut};
+use std::{cmp, time::Duration};
+
+use futures::{Future, StreamExt, pin_mut};
 use ruma::{
 	RoomId, UserId,
+	api::client::filter::RoomEventFilter,
 	events::TimelineEventType::{
 		self, Beacon, CallInvite, PollStart, RoomEncrypted, RoomMessage, Sticker,
 	},
 };
 use tuwunel_core::{
 	Error, PduCount, Result,
-	matrix::pdu::PduEvent,
+	matrix::{event::Matches, pdu::PduEvent},
 	utils::stream::{BroadbandExt, ReadyExt, TryIgnore},
 };
 use tuwunel_service::Services;
@@ -20,6 +25,21 @@
 pub(crate) const DEFAULT_BUMP_TYPES: &[TimelineEventType; 6] =
 	&[CallInvite, PollStart, Beacon, RoomEncrypted, RoomMessage, Sticker];
 
+/// Hang a few seconds so requests are not spammed. Stop hanging if new info
+/// arrives, or when the server is shutting down so the client is answered with
+/// its current position instead of being cut off.
+async fn long_poll<W>(services: &Services, timeout: Option<Duration>, watcher: W)
+where
+	W: Future + Send,
+{
+	let default = Duration::from_secs(30);
+	let duration = cmp::min(timeout.unwrap_or(default), default);
+	tokio::select! {
+		_ = tokio::time::timeout(duration, watcher) => {},
+		() = services.server.until_shutdown() => {},
+	}
+}
+
 async fn load_timeline(
 	services: &Services,
 	sender_user: &UserId,
@@ -27,6 +47,7 @@ async fn load_timeline(
 	roomsincecount: PduCount,
 	next_batch: Option<PduCount>,
 	limit: usize,
+	filter: Option<&RoomEventFilter>,
 ) -> Result<(Vec<(PduCount, PduEvent)>, bool), Error> {
 	let last_timeline_count = services
 		.rooms
@@ -44,7 +65,18 @@ async fn load_timeline(
 		.pdus_rev(Some(sender_user), room_id, None)
 		.ignore_err()
 		.ready_skip_while(|&(pducount, _)| pducount > next_batch.unwrap_or_else(PduCount::max))
-		.ready_take_while(|&(pducount, _)| pducount > roomsincecount);
+		.ready_take_while(|&(pducount, _)| pducount > roomsincecount)
+		.ready_filter(|(_, pdu)| filter.is_none_or(|filter| filter.matches(pdu)))
+		.filter(|(_, pdu)| {
+			let event_id = pdu.event_id.clone();
+			async move {
+				!services
+					.rooms
+					.timeline
+					.is_expired(&event_id)
+					.await
+			}
+		});
 
 	// Take the last events for the timeline
 	pin_mut!(non_timeline_pdus);
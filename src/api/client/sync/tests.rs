@@ -0,0 +1,53 @@
+use ruma::{owned_room_id, room_id, serde::Raw, uint};
+use serde_json::json;
+
+use super::v3::{filter_limit, room_included, type_included};
+
+fn event(kind: &str) -> Raw<serde_json::Value> {
+	Raw::new(&json!({ "type": kind, "content": {} })).expect("valid event")
+}
+
+#[test]
+fn rooms_unfiltered() {
+	assert!(room_included(None, &[], room_id!("!a:example.com")));
+}
+
+#[test]
+fn rooms_allowed_and_denied() {
+	let rooms = [owned_room_id!("!a:example.com"), owned_room_id!("!b:example.com")];
+	let not_rooms = [owned_room_id!("!b:example.com")];
+
+	assert!(room_included(Some(&rooms), &not_rooms, room_id!("!a:example.com")));
+	assert!(!room_included(Some(&rooms), &not_rooms, room_id!("!b:example.com")));
+	assert!(!room_included(Some(&rooms), &not_rooms, room_id!("!c:example.com")));
+	assert!(!room_included(Some(&[]), &[], room_id!("!a:example.com")));
+}
+
+#[test]
+fn types_with_wildcards() {
+	let types = ["m.tag".to_owned(), "im.vector.*".to_owned()];
+	let not_types = ["im.vector.setting.*".to_owned()];
+
+	assert!(type_included(Some(&types), &not_types, &event("m.tag")));
+	assert!(type_included(Some(&types), &not_types, &event("im.vector.web.settings")));
+	assert!(!type_included(
+		Some(&types),
+		&not_types,
+		&event("im.vector.setting.breadcrumbs")
+	));
+	assert!(!type_included(Some(&types), &not_types, &event("m.fully_read")));
+	assert!(!type_included(None, &["*".to_owned()], &event("m.tag")));
+}
+
+#[test]
+fn types_unreadable_included() {
+	let raw = Raw::new(&json!({ "content": {} })).expect("valid event");
+	assert!(type_included::<serde_json::Value>(Some(&[]), &[], &raw));
+}
+
+#[test]
+fn limits() {
+	assert_eq!(filter_limit(None, 10), 10);
+	assert_eq!(filter_limit(Some(uint!(3)), 10), 3);
+	assert_eq!(filter_limit(Some(uint!(0)), 10), 0);
+}
@@ -1,8 +1,4 @@
-use std::{
-	cmp::{self},
-	collections::{BTreeMap, HashMap, HashSet},
-	time::Duration,
-};
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 use axum::extract::State;
 use futures::{
@@ -11,7 +7,7 @@
 	pin_mut,
 };
 use ruma::{
-	DeviceId, EventId, OwnedEventId, OwnedRoomId, OwnedUserId, RoomId, UserId,
+	DeviceId, EventId, OwnedEventId, OwnedRoomId, OwnedUserId, RoomId, UInt, UserId,
 	api::client::{
 		filter::FilterDefinition,
 		sync::sync_events::{
@@ -34,9 +30,10 @@
 	uint,
 };
 use tuwunel_core::{
-	Result, at, err, error, extract_variant, is_equal_to,
+	Result, StreamToken, at, err, error, extract_variant, is_equal_to,
 	matrix::{
 		Event,
+		event::type_matches,
 		pdu::{EventHash, PduCount, PduEvent},
 	},
 	pair_of, ref_at,
@@ -58,9 +55,15 @@
 	},
 };
 
-use super::{load_timeline, share_encrypted_room};
+use super::{load_timeline, long_poll, share_encrypted_room};
 use crate::{Ruma, RumaResponse, client::ignored_filter};
 
+/// Timeline events per room when the filter does not specify a limit.
+const TIMELINE_LIMIT_DEFAULT: usize = 10;
+
+/// Upper bound on the per-room timeline limit requested by a filter.
+const TIMELINE_LIMIT_MAX: usize = 100;
+
 #[derive(Default)]
 struct StateChanges {
 	heroes: Option<Vec<OwnedUserId>>,
@@ -144,11 +147,7 @@ pub(crate) async fn sync_events_route(
 		return Ok(response);
 	}
 
-	// Hang a few seconds so requests are not spammed
-	// Stop hanging if new info arrives
-	let default = Duration::from_secs(30);
-	let duration = cmp::min(body.body.timeout.unwrap_or(default), default);
-	_ = tokio::time::timeout(duration, watcher).await;
+	long_poll(&services, body.body.timeout, watcher).await;
 
 	// Retry returning data
 	build_sync_events(&services, &body).await
@@ -164,9 +163,11 @@ pub(crate) async fn build_sync_events(
 	let since = body
 		.body
 		.since
+		.as_deref()
+		.map(str::parse)
+		.flat_ok()
 		.as_ref()
-		.and_then(|string| string.parse().ok())
-		.unwrap_or(0);
+		.map_or(0, StreamToken::global);
 
 	let full_state = body.body.full_state;
 	let filter = match body.body.filter.as_ref() {
@@ -183,6 +184,9 @@ pub(crate) async fn build_sync_events(
 		.rooms
 		.state_cache
 		.rooms_joined(sender_user)
+		.ready_filter(|room_id| {
+			room_included(filter.room.rooms.as_deref(), &filter.room.not_rooms, room_id)
+		})
 		.map(ToOwned::to_owned)
 		.broad_filter_map(|room_id| {
 			load_joined_room(
@@ -216,6 +220,9 @@ pub(crate) async fn build_sync_events(
 		.rooms
 		.state_cache
 		.rooms_left(sender_user)
+		.ready_filter(|(room_id, _)| {
+			room_included(filter.room.rooms.as_deref(), &filter.room.not_rooms, room_id)
+		})
 		.broad_filter_map(|(room_id, _)| {
 			handle_left_room(
 				services,
@@ -237,6 +244,9 @@ pub(crate) async fn build_sync_events(
 		.rooms
 		.state_cache
 		.rooms_invited(sender_user)
+		.ready_filter(|(room_id, _)| {
+			room_included(filter.room.rooms.as_deref(), &filter.room.not_rooms, room_id)
+		})
 		.fold_default(|mut invited_rooms: BTreeMap<_, _>, (room_id, invite_state)| async move {
 			let invite_count = services
 				.rooms
@@ -262,6 +272,9 @@ pub(crate) async fn build_sync_events(
 		.rooms
 		.state_cache
 		.rooms_knocked(sender_user)
+		.ready_filter(|(room_id, _)| {
+			room_included(filter.room.rooms.as_deref(), &filter.room.not_rooms, room_id)
+		})
 		.fold_default(|mut knocked_rooms: BTreeMap<_, _>, (room_id, knock_state)| async move {
 			let knock_count = services
 				.rooms
@@ -293,6 +306,14 @@ pub(crate) async fn build_sync_events(
 		.account_data
 		.changes_since(None, sender_user, since, Some(next_batch))
 		.ready_filter_map(|e| extract_variant!(e, AnyRawAccountDataEvent::Global))
+		.ready_filter(|event| {
+			type_included(
+				filter.account_data.types.as_deref(),
+				&filter.account_data.not_types,
+				event,
+			)
+		})
+		.take(filter_limit(filter.account_data.limit, usize::MAX))
 		.collect();
 
 	// Look for device list updates of this account
@@ -352,7 +373,7 @@ pub(crate) async fn build_sync_events(
 		device_one_time_keys_count,
 		// Fallback keys are not yet supported
 		device_unused_fallback_key_types: None,
-		next_batch: next_batch.to_string(),
+		next_batch: StreamToken::from(next_batch).to_string(),
 		presence: Presence {
 			events: presence_updates
 				.into_iter()
@@ -469,7 +490,7 @@ async fn handle_left_room(
 			account_data: RoomAccountData { events: Vec::new() },
 			timeline: Timeline {
 				limited: false,
-				prev_batch: Some(next_batch.to_string()),
+				prev_batch: Some(StreamToken::from(next_batch).to_string()),
 				events: Vec::new(),
 			},
 			state: RoomState { events: vec![event.into_format()] },
@@ -568,7 +589,7 @@ async fn handle_left_room(
 		timeline: Timeline {
 			// TODO: support left timeline events so we dont need to set limited to true
 			limited: true,
-			prev_batch: Some(next_batch.to_string()),
+			prev_batch: Some(StreamToken::from(next_batch).to_string()),
 			events: Vec::new(), // and so we dont need to set this to empty vec
 		},
 		state: RoomState { events: left_state_events },
@@ -610,13 +631,23 @@ async fn load_joined_room(
 		.ok()
 		.map(Ok);
 
+	let timeline_filter = &filter.room.timeline;
+	let timeline_limit =
+		room_included(timeline_filter.rooms.as_deref(), &timeline_filter.not_rooms, room_id)
+			.then(|| {
+				filter_limit(timeline_filter.limit, TIMELINE_LIMIT_DEFAULT)
+					.min(TIMELINE_LIMIT_MAX)
+			})
+			.unwrap_or(0);
+
 	let timeline = load_timeline(
 		services,
 		sender_user,
 		room_id,
 		sincecount,
 		Some(next_batchcount),
-		10_usize,
+		timeline_limit,
+		Some(timeline_filter),
 	);
 
 	let receipt_events = services
@@ -767,10 +798,26 @@ async fn load_joined_room(
 		.map(Event::into_format)
 		.collect::<Vec<_>>();
 
+	let account_data_filter = &filter.room.account_data;
+	let account_data_included = room_included(
+		account_data_filter.rooms.as_deref(),
+		&account_data_filter.not_rooms,
+		room_id,
+	);
+
 	let account_data_events = services
 		.account_data
 		.changes_since(Some(room_id), sender_user, since, Some(next_batch))
+		.ready_filter(|_| account_data_included)
 		.ready_filter_map(|e| extract_variant!(e, AnyRawAccountDataEvent::Room))
+		.ready_filter(|event| {
+			type_included(
+				account_data_filter.types.as_deref(),
+				&account_data_filter.not_types,
+				event,
+			)
+		})
+		.take(filter_limit(account_data_filter.limit, usize::MAX))
 		.collect();
 
 	// Look for device list updates in this room
@@ -805,6 +852,17 @@ async fn load_joined_room(
 		})
 		.into();
 
+	// Thread counts are only reported apart from the room's when the filter asks.
+	let thread_notification_counts: OptionFuture<_> = (send_notification_counts
+		&& timeline_filter.unread_thread_notifications)
+		.then(|| {
+			services
+				.rooms
+				.user
+				.thread_notification_counts(sender_user, room_id)
+		})
+		.into();
+
 	let typing_events = services
 		.rooms
 		.typing
@@ -824,7 +882,8 @@ async fn load_joined_room(
 		})
 		.unwrap_or(Vec::new());
 
-	let unread_notifications = join(notification_count, highlight_count);
+	let unread_notifications =
+		join3(notification_count, highlight_count, thread_notification_counts);
 	let events = join3(room_events, account_data_events, typing_events);
 	let (unread_notifications, events, device_updates) =
 		join3(unread_notifications, events, device_updates)
@@ -832,7 +891,34 @@ async fn load_joined_room(
 			.await;
 
 	let (room_events, account_data_events, typing_events) = events;
-	let (notification_count, highlight_count) = unread_notifications;
+	let (notification_count, highlight_count, thread_notification_counts) = unread_notifications;
+
+	// The room's counts include its threads, which are then left out of the counts
+	// of the main timeline.
+	let thread_notification_counts = thread_notification_counts.unwrap_or_default();
+	let (thread_notifications, thread_highlights) = thread_notification_counts
+		.values()
+		.fold((0_u64, 0_u64), |(n, h), (tn, th)| {
+			(n.saturating_add(*tn), h.saturating_add(*th))
+		});
+
+	let notification_count =
+		notification_count.map(|count| count.saturating_sub(ruma_from_u64(thread_notifications)));
+
+	let highlight_count =
+		highlight_count.map(|count| count.saturating_sub(ruma_from_u64(thread_highlights)));
+
+	let unread_thread_notifications = thread_notification_counts
+		.into_iter()
+		.map(|(thread_id, (notifications, highlights))| {
+			let counts = UnreadNotificationsCount {
+				highlight_count: Some(ruma_from_u64(highlights)),
+				notification_count: Some(ruma_from_u64(notifications)),
+			};
+
+			(thread_id, counts)
+		})
+		.collect();
 
 	device_list_updates.extend(device_updates);
 
@@ -892,7 +978,7 @@ async fn load_joined_room(
 				.collect(),
 		},
 		ephemeral: Ephemeral { events: edus },
-		unread_thread_notifications: BTreeMap::new(),
+		unread_thread_notifications,
 	};
 
 	Ok((joined_room, device_list_updates, left_encrypted_users))
@@ -1275,3 +1361,33 @@ async fn fold_hero(
 	heroes.push(user_id.to_owned());
 	heroes
 }
+
+/// Whether a room passes the `rooms` and `not_rooms` lists of a filter.
+pub(super) fn room_included(
+	rooms: Option<&[OwnedRoomId]>,
+	not_rooms: &[OwnedRoomId],
+	room_id: &RoomId,
+) -> bool {
+	!not_rooms.iter().any(is_equal_to!(room_id))
+		&& rooms.is_none_or(|rooms| rooms.iter().any(is_equal_to!(room_id)))
+}
+
+/// Whether an event's type passes the `types` and `not_types` lists of a
+/// filter, which may contain `*` wildcards. Events whose type cannot be read
+/// are included.
+pub(super) fn type_included<T>(
+	types: Option<&[String]>,
+	not_types: &[String],
+	event: &Raw<T>,
+) -> bool {
+	let Ok(Some(kind)) = event.get_field::<String>("type") else {
+		return true;
+	};
+
+	let matches = |pattern: &String| type_matches(pattern, &kind);
+	!not_types.iter().any(matches) && types.is_none_or(|types| types.iter().any(matches))
+}
+
+pub(super) fn filter_limit(limit: Option<UInt>, default: usize) -> usize {
+	limit.map_or(default, |limit| limit.try_into().unwrap_or(usize::MAX))
+}
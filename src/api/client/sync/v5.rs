@@ -1,8 +1,7 @@
 use std::{
-	cmp::{self, Ordering},
+	cmp::Ordering,
 	collections::{BTreeMap, BTreeSet, HashMap, HashSet},
 	ops::Deref,
-	time::Duration,
 };
 
 use axum::extract::State;
@@ -23,7 +22,7 @@
 	uint,
 };
 use tuwunel_core::{
-	Err, Error, Result, at, error, extract_variant, is_equal_to,
+	Err, Error, Result, StreamToken, at, error, extract_variant, is_equal_to,
 	matrix::{Event, TypeStateKey, pdu::PduCount},
 	trace,
 	utils::{
@@ -35,7 +34,7 @@
 };
 use tuwunel_service::{Services, rooms::read_receipt::pack_receipts, sync::into_snake_key};
 
-use super::share_encrypted_room;
+use super::{long_poll, share_encrypted_room};
 use crate::{
 	Ruma,
 	client::{DEFAULT_BUMP_TYPES, ignored_filter, sync::load_timeline},
@@ -81,7 +80,8 @@ pub(crate) async fn sync_events_v5_route(
 		.pos
 		.as_ref()
 		.and_then(|string| string.parse().ok())
-		.unwrap_or(0);
+		.as_ref()
+		.map_or(0, StreamToken::global);
 
 	let snake_key = into_snake_key(sender_user, sender_device, conn_id);
 
@@ -135,7 +135,7 @@ pub(crate) async fn sync_events_v5_route(
 		.chain(all_invited_rooms.clone())
 		.chain(all_knocked_rooms.clone());
 
-	let pos = next_batch.clone().to_string();
+	let pos = StreamToken::from(next_batch).to_string();
 
 	let mut todo_rooms: TodoRooms = BTreeMap::new();
 
@@ -207,11 +207,7 @@ pub(crate) async fn sync_events_v5_route(
 		.clone()
 		.is_none_or(|to| to.events.is_empty())
 	{
-		// Hang a few seconds so requests are not spammed
-		// Stop hanging if new info arrives
-		let default = Duration::from_secs(30);
-		let duration = cmp::min(body.timeout.unwrap_or(default), default);
-		_ = tokio::time::timeout(duration, watcher).await;
+		long_poll(&services, body.timeout, watcher).await;
 	}
 
 	trace!(
@@ -427,6 +423,7 @@ async fn process_rooms<'a, Rooms>(
 				roomsincecount,
 				Some(PduCount::from(next_batch)),
 				*timeline_limit,
+				None,
 			)
 			.await
 			{
@@ -515,12 +512,12 @@ async fn process_rooms<'a, Rooms>(
 						error!("timeline in backfill state?!");
 						"0".to_owned()
 					},
-					| PduCount::Normal(c) => c.to_string(),
+					| PduCount::Normal(c) => StreamToken::from(c).to_string(),
 				}))
 			})?
 			.or_else(|| {
 				if roomsince != &0 {
-					Some(roomsince.to_string())
+					Some(StreamToken::from(*roomsince).to_string())
 				} else {
 					None
 				}
@@ -944,7 +941,7 @@ async fn collect_to_device(
 		.await;
 
 	Some(sync_events::v5::response::ToDevice {
-		next_batch: next_batch.to_string(),
+		next_batch: StreamToken::from(next_batch).to_string(),
 		events: services
 			.users
 			.get_to_device_events(sender_user, sender_device, None, Some(next_batch))
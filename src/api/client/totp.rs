@@ -0,0 +1,187 @@
+//! Enrollment in TOTP two-factor authentication. There are no Matrix
+//! endpoints for this, so these are tuwunel-specific and defined here.
+
+use axum::extract::State;
+use ruma::api::client::uiaa::{AuthFlow, AuthType, UiaaInfo};
+use tuwunel_core::{Err, Result, err, utils};
+use tuwunel_service::uiaa::TOTP_AUTH_TYPE;
+
+use super::SESSION_ID_LENGTH;
+use crate::Ruma;
+
+/// `POST /_tuwunel/totp/enroll`
+pub(crate) mod enroll_totp {
+	use ruma::{
+		api::{Metadata, request, response},
+		metadata,
+	};
+
+	const METADATA: Metadata = metadata! {
+		method: POST,
+		rate_limited: true,
+		authentication: AccessToken,
+		history: {
+			unstable => "/_tuwunel/totp/enroll",
+		}
+	};
+
+	#[request]
+	pub struct Request {}
+
+	#[response]
+	pub struct Response {
+		/// Base32 secret for manual entry.
+		pub secret: String,
+
+		/// `otpauth://` URI, usually shown as a QR code.
+		pub uri: String,
+	}
+}
+
+/// `POST /_tuwunel/totp/confirm`
+pub(crate) mod confirm_totp {
+	use ruma::{
+		api::{Metadata, request, response},
+		metadata,
+	};
+
+	const METADATA: Metadata = metadata! {
+		method: POST,
+		rate_limited: true,
+		authentication: AccessToken,
+		history: {
+			unstable => "/_tuwunel/totp/confirm",
+		}
+	};
+
+	#[request]
+	pub struct Request {
+		/// Code from the authenticator app.
+		pub code: String,
+	}
+
+	#[response]
+	pub struct Response {
+		/// Single-use codes for when the app is unavailable.
+		pub recovery_codes: Vec<String>,
+	}
+}
+
+/// `POST /_tuwunel/totp/disable`
+pub(crate) mod disable_totp {
+	use ruma::{
+		api::{Metadata, client::uiaa::AuthData, request, response},
+		metadata,
+	};
+
+	const METADATA: Metadata = metadata! {
+		method: POST,
+		rate_limited: true,
+		authentication: AccessToken,
+		history: {
+			unstable => "/_tuwunel/totp/disable",
+		}
+	};
+
+	#[request]
+	pub struct Request {
+		/// Additional authentication information for the user-interactive
+		/// authentication API.
+		#[serde(skip_serializing_if = "Option::is_none")]
+		pub auth: Option<AuthData>,
+	}
+
+	#[response]
+	pub struct Response {}
+}
+
+/// # `POST /_tuwunel/totp/enroll`
+///
+/// Starts enrolling the user, returning the secret for their authenticator
+/// app. Two-factor authentication is not required until confirmed.
+pub(crate) async fn enroll_totp_route(
+	State(services): State<crate::State>,
+	body: Ruma<enroll_totp::Request>,
+) -> Result<enroll_totp::Response> {
+	if !services.users.totp_available() {
+		return Err!(Request(Forbidden("Two-factor authentication is not enabled.")));
+	}
+
+	let enrollment = services
+		.users
+		.enroll_totp(body.sender_user())
+		.await?;
+
+	Ok(enroll_totp::Response {
+		secret: enrollment.secret,
+		uri: enrollment.uri,
+	})
+}
+
+/// # `POST /_tuwunel/totp/confirm`
+///
+/// Completes enrollment with a `code` from the authenticator app, enabling
+/// two-factor authentication. Returns the single-use recovery codes.
+pub(crate) async fn confirm_totp_route(
+	State(services): State<crate::State>,
+	body: Ruma<confirm_totp::Request>,
+) -> Result<confirm_totp::Response> {
+	let recovery_codes = services
+		.users
+		.confirm_totp(body.sender_user(), &body.code)
+		.await?;
+
+	Ok(confirm_totp::Response { recovery_codes })
+}
+
+/// # `POST /_tuwunel/totp/disable`
+///
+/// Disables two-factor authentication after user-interactive authentication
+/// with the password and a code.
+pub(crate) async fn disable_totp_route(
+	State(services): State<crate::State>,
+	body: Ruma<disable_totp::Request>,
+) -> Result<disable_totp::Response> {
+	let (sender_user, sender_device) = body.sender();
+	if services.users.totp_enabled(sender_user).await {
+		let mut uiaainfo = UiaaInfo {
+			flows: vec![AuthFlow {
+				stages: vec![AuthType::Password, AuthType::from(TOTP_AUTH_TYPE)],
+			}],
+			completed: Vec::new(),
+			params: Box::default(),
+			session: None,
+			auth_error: None,
+		};
+
+		match &body.auth {
+			| Some(auth) => {
+				let (worked, uiaainfo) = services
+					.uiaa
+					.try_auth(sender_user, sender_device, auth, &uiaainfo)
+					.await?;
+
+				if !worked {
+					return Err!(Uiaa(uiaainfo));
+				}
+			},
+			| None => {
+				let json = body
+					.json_body
+					.as_ref()
+					.ok_or_else(|| err!(Request(NotJson("No JSON body was sent."))))?;
+
+				uiaainfo.session = Some(utils::random_string(SESSION_ID_LENGTH));
+				services
+					.uiaa
+					.create(sender_user, sender_device, &uiaainfo, json);
+
+				return Err!(Uiaa(uiaainfo));
+			},
+		}
+	}
+
+	services.users.disable_totp(sender_user);
+
+	Ok(disable_totp::Response {})
+}
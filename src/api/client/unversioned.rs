@@ -1,9 +1,18 @@
-use std::collections::BTreeMap;
+use std::{
+	collections::BTreeMap,
+	time::{Duration, UNIX_EPOCH},
+};
 
-use axum::{Json, extract::State, response::IntoResponse};
+use axum::{
+	Json,
+	extract::{Query, State},
+	response::IntoResponse,
+};
 use futures::StreamExt;
+use http::header::CONTENT_TYPE;
 use ruma::api::client::discovery::get_supported_versions;
-use tuwunel_core::Result;
+use serde::Deserialize;
+use tuwunel_core::{Result, utils};
 
 use crate::Ruma;
 
@@ -85,3 +94,47 @@ pub(crate) async fn tuwunel_local_user_count(
 		"count": user_count
 	})))
 }
+
+/// # `GET /_tuwunel/metrics`
+///
+/// Tuwunel-specific API exporting server metrics and the federation statistics
+/// of each remote server in the Prometheus text format. Only routed when
+/// `allow_metrics_endpoint` is enabled.
+pub(crate) async fn tuwunel_metrics(
+	State(services): State<crate::State>,
+) -> Result<impl IntoResponse> {
+	let mut out = String::new();
+	services.server.metrics.export(&mut out)?;
+	services.federation.export_stats(&mut out)?;
+
+	Ok(([(CONTENT_TYPE, "text/plain; version=0.0.4")], out))
+}
+
+/// Query of the renewal links sent to users of accounts about to expire.
+#[derive(Deserialize)]
+pub(crate) struct RenewQuery {
+	token: String,
+}
+
+/// # `GET /_tuwunel/account_validity/renew`
+///
+/// Tuwunel-specific API renewing the account a renewal link was sent to,
+/// opened by the user in a browser. Only routed when `account_validity_period`
+/// is set.
+pub(crate) async fn tuwunel_renew_account(
+	State(services): State<crate::State>,
+	Query(query): Query<RenewQuery>,
+) -> Result<impl IntoResponse> {
+	let (user_id, expires_at) = services
+		.users
+		.renew_account_with_token(&query.token)
+		.await?;
+
+	let expires_at = UNIX_EPOCH
+		.checked_add(Duration::from_millis(expires_at))
+		.unwrap_or(UNIX_EPOCH);
+
+	let expires_at = utils::time::format(expires_at, "%Y-%m-%d %H:%M UTC");
+
+	Ok(format!("Your account {user_id} has been renewed until {expires_at}."))
+}
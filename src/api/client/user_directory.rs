@@ -21,6 +21,7 @@
 ///
 /// - Hides any local users that aren't in any public rooms (i.e. those that
 ///   have the join rule set to public) and don't share a room with the sender
+/// - Hides bot and service accounts
 pub(crate) async fn search_users_route(
 	State(services): State<crate::State>,
 	body: Ruma<search_users::v3::Request>,
@@ -36,6 +37,10 @@ pub(crate) async fn search_users_route(
 		.stream()
 		.map(ToOwned::to_owned)
 		.broad_filter_map(async |user_id| {
+			if services.users.is_bot(&user_id).await {
+				return None;
+			}
+
 			let display_name = services.users.displayname(&user_id).await.ok();
 
 			let user_id_matches = user_id
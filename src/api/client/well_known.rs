@@ -1,33 +1,50 @@
 use axum::{Json, extract::State, response::IntoResponse};
+use futures::StreamExt;
 use ruma::api::client::{
-	discovery::{
-		discover_homeserver::{self, HomeserverInfo, SlidingSyncProxyInfo},
-		discover_support::{self, Contact},
-	},
+	discovery::discover_support::{self, Contact, ContactRole},
 	error::ErrorKind,
 };
-use tuwunel_core::{Error, Result};
+use serde_json::json;
+use tuwunel_core::{Error, Result, config::WellKnownContact, utils::ReadyExt};
+use tuwunel_service::Services;
 
 use crate::Ruma;
 
 /// # `GET /.well-known/matrix/client`
 ///
-/// Returns the .well-known URL if it is configured, otherwise returns 404.
+/// Returns the .well-known URL if it is configured, otherwise returns 404. The
+/// response is built from config rather than the ruma type so that
+/// `client_extra` can add arbitrary keys.
 pub(crate) async fn well_known_client(
 	State(services): State<crate::State>,
-	_body: Ruma<discover_homeserver::Request>,
-) -> Result<discover_homeserver::Response> {
-	let client_url = match services.server.config.well_known.client.as_ref() {
-		| Some(url) => url.to_string(),
-		| None => return Err(Error::BadRequest(ErrorKind::NotFound, "Not found.")),
+) -> Result<impl IntoResponse> {
+	let config = &services.server.config.well_known;
+	let Some(client_url) = config.client.as_ref() else {
+		return Err(Error::BadRequest(ErrorKind::NotFound, "Not found."));
 	};
 
-	Ok(discover_homeserver::Response {
-		homeserver: HomeserverInfo { base_url: client_url.clone() },
-		identity_server: None,
-		sliding_sync_proxy: Some(SlidingSyncProxyInfo { url: client_url }),
-		tile_server: None,
-	})
+	let sliding_sync_proxy = config
+		.client_sliding_sync_proxy
+		.as_ref()
+		.unwrap_or(client_url);
+
+	let mut response = serde_json::Map::new();
+	response.insert("m.homeserver".into(), json!({ "base_url": client_url.as_str() }));
+	response
+		.insert("org.matrix.msc3575.proxy".into(), json!({ "url": sliding_sync_proxy.as_str() }));
+
+	if let Some(identity_server) = &config.client_identity_server {
+		response
+			.insert("m.identity_server".into(), json!({ "base_url": identity_server.as_str() }));
+	}
+
+	if let Some(tile_server) = &config.client_tile_server {
+		response.insert("m.tile_server".into(), json!({ "map_style_url": tile_server.as_str() }));
+	}
+
+	response.extend(config.client_extra.clone());
+
+	Ok(Json(response))
 }
 
 /// # `GET /.well-known/matrix/support`
@@ -37,54 +54,48 @@ pub(crate) async fn well_known_support(
 	State(services): State<crate::State>,
 	_body: Ruma<discover_support::Request>,
 ) -> Result<discover_support::Response> {
-	let support_page = services
-		.server
-		.config
-		.well_known
+	let config = &services.server.config.well_known;
+	let support_page = config
 		.support_page
 		.as_ref()
 		.map(ToString::to_string);
 
-	let role = services
-		.server
-		.config
-		.well_known
+	let role = config
 		.support_role
-		.clone();
+		.clone()
+		.map(|role| WellKnownContact {
+			role,
+			email_address: config.support_email.clone(),
+			matrix_id: config.support_mxid.clone(),
+		});
 
-	// support page or role must be either defined for this to be valid
-	if support_page.is_none() && role.is_none() {
-		return Err(Error::BadRequest(ErrorKind::NotFound, "Not found."));
-	}
-
-	let email_address = services
-		.server
-		.config
-		.well_known
-		.support_email
-		.clone();
-	let matrix_id = services
-		.server
-		.config
-		.well_known
-		.support_mxid
-		.clone();
-
-	// if a role is specified, an email address or matrix id is required
-	if role.is_some() && (email_address.is_none() && matrix_id.is_none()) {
-		return Err(Error::BadRequest(ErrorKind::NotFound, "Not found."));
-	}
+	// a contact requires an email address or matrix id
+	let mut contacts: Vec<Contact> = role
+		.iter()
+		.chain(&config.support_contacts)
+		.filter(|contact| contact.email_address.is_some() || contact.matrix_id.is_some())
+		.map(|contact| Contact {
+			role: contact.role.clone(),
+			email_address: contact.email_address.clone(),
+			matrix_id: contact.matrix_id.clone(),
+		})
+		.collect();
 
-	// TODO: support defining multiple contacts in the config
-	let mut contacts: Vec<Contact> = vec![];
+	if config.support_list_admins {
+		let admins = admin_contacts(&services).await;
+		let admins: Vec<_> = admins
+			.into_iter()
+			.filter(|admin| {
+				!contacts
+					.iter()
+					.any(|contact| contact.matrix_id == admin.matrix_id)
+			})
+			.collect();
 
-	if let Some(role) = role {
-		let contact = Contact { role, email_address, matrix_id };
-
-		contacts.push(contact);
+		contacts.extend(admins);
 	}
 
-	// support page or role+contacts must be either defined for this to be valid
+	// support page or contacts must be either defined for this to be valid
 	if contacts.is_empty() && support_page.is_none() {
 		return Err(Error::BadRequest(ErrorKind::NotFound, "Not found."));
 	}
@@ -92,6 +103,26 @@ pub(crate) async fn well_known_support(
 	Ok(discover_support::Response { contacts, support_page })
 }
 
+/// Local members of the admin room, other than the server user.
+async fn admin_contacts(services: &Services) -> Vec<Contact> {
+	let Ok(admin_room) = services.admin.get_admin_room().await else {
+		return Vec::new();
+	};
+
+	services
+		.rooms
+		.state_cache
+		.local_users_in_room(&admin_room)
+		.ready_filter(|&user_id| user_id != services.globals.server_user.as_ref())
+		.map(|user_id| Contact {
+			role: ContactRole::Admin,
+			email_address: None,
+			matrix_id: Some(user_id.to_owned()),
+		})
+		.collect()
+		.await
+}
+
 /// # `GET /client/server.json`
 ///
 /// Endpoint provided by sliding sync proxy used by some clients such as Element
@@ -0,0 +1,53 @@
+use axum::extract::State;
+use futures::StreamExt;
+use ruma::api::client::admin::get_user_info::{
+	self,
+	v3::{ConnectionInfo, DeviceInfo, SessionInfo},
+};
+use tuwunel_core::{Err, Result};
+
+use crate::Ruma;
+
+/// # `GET /_matrix/client/v3/admin/whois/{userId}`
+///
+/// Lists the devices of a local user with the addresses and clients each was
+/// recently used from. Users may query themselves; anyone else requires a
+/// server admin.
+pub(crate) async fn get_user_info_route(
+	State(services): State<crate::State>,
+	body: Ruma<get_user_info::v3::Request>,
+) -> Result<get_user_info::v3::Response> {
+	let sender_user = body.sender_user();
+	if sender_user != body.user_id && !services.users.is_admin(sender_user).await {
+		return Err!(Request(Forbidden("Only server admins can look up other users.")));
+	}
+
+	if !services.globals.user_is_local(&body.user_id) {
+		return Err!(Request(InvalidParam("User does not belong to this server.")));
+	}
+
+	let (services, user_id) = (&services, &body.user_id);
+	let devices = services
+		.users
+		.all_device_ids(user_id)
+		.then(|device_id| async move {
+			let connections = services
+				.users
+				.connections(user_id, device_id)
+				.await
+				.into_iter()
+				.map(|connection| ConnectionInfo {
+					ip: Some(connection.ip),
+					last_seen: Some(connection.last_seen),
+					user_agent: connection.user_agent,
+				})
+				.collect();
+
+			let session = SessionInfo { connections };
+			(device_id.to_string(), DeviceInfo { sessions: vec![session] })
+		})
+		.collect()
+		.await;
+
+	Ok(get_user_info::v3::Response { user_id: Some(user_id.clone()), devices })
+}
@@ -5,7 +5,22 @@
 pub mod router;
 pub mod server;
 
+use std::sync::Arc;
+
+use tuwunel_service::Services;
+
 pub(crate) use self::router::{Ruma, RumaResponse, State};
 
 tuwunel_core::mod_ctor! {}
 tuwunel_core::mod_dtor! {}
+
+/// Install callbacks by which services invoke client API functionality.
+pub fn init(services: &Arc<Services>) {
+	services
+		.rooms
+		.state_cache
+		.set_upgrade_hook(Some(client::follow_room_upgrade_hook(services)));
+}
+
+/// Remove the callbacks installed by `init`.
+pub fn fini(services: &Arc<Services>) { services.rooms.state_cache.set_upgrade_hook(None); }
@@ -4,6 +4,7 @@
 mod request;
 mod response;
 pub mod state;
+mod stream;
 
 use std::str::FromStr;
 
@@ -16,7 +17,12 @@
 use tuwunel_core::{Server, err};
 
 use self::handler::RouterExt;
-pub(super) use self::{args::Args as Ruma, response::RumaResponse, state::State};
+pub(super) use self::{
+	args::Args as Ruma,
+	response::RumaResponse,
+	state::State,
+	stream::{json_arrays_response, json_elements},
+};
 use crate::{client, server};
 
 pub fn build(router: Router<State>, server: &Server) -> Router<State> {
@@ -158,6 +164,7 @@ pub fn build(router: Router<State>, server: &Server) -> Router<State> {
 		.ruma_route(&client::get_media_preview_route)
 		.ruma_route(&client::get_media_config_route)
 		.ruma_route(&client::get_devices_route)
+		.ruma_route(&client::get_user_info_route)
 		.ruma_route(&client::get_device_route)
 		.ruma_route(&client::update_device_route)
 		.ruma_route(&client::delete_device_route)
@@ -170,6 +177,7 @@ pub fn build(router: Router<State>, server: &Server) -> Router<State> {
 		.ruma_route(&client::get_key_changes_route)
 		.ruma_route(&client::get_pushers_route)
 		.ruma_route(&client::set_pushers_route)
+		.ruma_route(&client::get_notifications_route)
 		.ruma_route(&client::upgrade_room_route)
 		.ruma_route(&client::get_threads_route)
 		.ruma_route(&client::get_relating_events_with_rel_type_and_event_type_route)
@@ -183,8 +191,19 @@ pub fn build(router: Router<State>, server: &Server) -> Router<State> {
 			get(client::get_room_summary_legacy)
 		)
 		.ruma_route(&client::well_known_support)
-		.ruma_route(&client::well_known_client)
+		.route("/.well-known/matrix/client", get(client::well_known_client))
 		.route("/_tuwunel/server_version", get(client::tuwunel_server_version))
+		.ruma_route(&client::enroll_totp_route)
+		.ruma_route(&client::confirm_totp_route)
+		.ruma_route(&client::disable_totp_route)
+		.ruma_route(&client::start_passkey_registration_route)
+		.ruma_route(&client::finish_passkey_registration_route)
+		.ruma_route(&client::start_passkey_login_route)
+		.ruma_route(&client::get_passkeys_route)
+		.ruma_route(&client::delete_passkey_route)
+		.ruma_route(&client::send_delayed_event_route)
+		.ruma_route(&client::get_delayed_events_route)
+		.ruma_route(&client::update_delayed_event_route)
 		.ruma_route(&client::room_initial_sync_route)
 		.route("/client/server.json", get(client::syncv3_client_server_json));
 
@@ -203,8 +222,8 @@ pub fn build(router: Router<State>, server: &Server) -> Router<State> {
 			.ruma_route(&server::get_backfill_route)
 			.ruma_route(&server::get_missing_events_route)
 			.ruma_route(&server::get_event_authorization_route)
-			.ruma_route(&server::get_room_state_route)
-			.ruma_route(&server::get_room_state_ids_route)
+			.ruma_stream_route(&server::get_room_state_route)
+			.ruma_stream_route(&server::get_room_state_ids_route)
 			.ruma_route(&server::create_leave_event_template_route)
 			.ruma_route(&server::create_knock_event_template_route)
 			.ruma_route(&server::create_leave_event_v1_route)
@@ -271,6 +290,45 @@ pub fn build(router: Router<State>, server: &Server) -> Router<State> {
 			.route("/_matrix/media/r0/preview_url", any(redirect_legacy_preview));
 	}
 
+	if config.sso.enable {
+		router = router
+			.route("/_matrix/client/v3/login/sso/redirect", get(client::sso_redirect_route))
+			.route("/_matrix/client/r0/login/sso/redirect", get(client::sso_redirect_route))
+			.route("/_matrix/client/v3/login/sso/callback", get(client::sso_callback_route));
+	}
+
+	if config.allow_metrics_endpoint {
+		router = router.route("/_tuwunel/metrics", get(client::tuwunel_metrics));
+	}
+
+	if config.account_validity_period.is_some() {
+		router =
+			router.route("/_tuwunel/account_validity/renew", get(client::tuwunel_renew_account));
+	}
+
+	if config.scim_token.is_some() {
+		router = router
+			.route(
+				"/_tuwunel/scim/v2/Users",
+				get(client::scim_list_users_route).post(client::scim_create_user_route),
+			)
+			.route(
+				"/_tuwunel/scim/v2/Users/{id}",
+				get(client::scim_get_user_route)
+					.put(client::scim_replace_user_route)
+					.patch(client::scim_patch_user_route)
+					.delete(client::scim_delete_user_route),
+			)
+			.route(
+				"/_tuwunel/scim/v2/Groups",
+				get(client::scim_list_groups_route).post(client::scim_create_group_route),
+			)
+			.route(
+				"/_tuwunel/scim/v2/Groups/{id}",
+				get(client::scim_get_group_route).patch(client::scim_patch_group_route),
+			);
+	}
+
 	router
 }
 
@@ -1,13 +1,14 @@
-use std::{mem, ops::Deref};
+use std::{mem, ops::Deref, sync::Arc};
 
-use axum::{body::Body, extract::FromRequest};
+use axum::{RequestPartsExt, body::Body, extract::FromRequest};
+use axum_client_ip::InsecureClientIp;
 use bytes::{BufMut, Bytes, BytesMut};
 use ruma::{
 	CanonicalJsonObject, CanonicalJsonValue, DeviceId, OwnedDeviceId, OwnedServerName,
 	OwnedUserId, ServerName, UserId, api::IncomingRequest,
 };
 use tuwunel_core::{Error, Result, debug, debug_warn, err, trace, utils::string::EMPTY};
-use tuwunel_service::{Services, appservice::RegistrationInfo};
+use tuwunel_service::{Services, admin::Trace, appservice::RegistrationInfo};
 
 use super::{auth, auth::Auth, request, request::Request};
 use crate::State;
@@ -36,6 +37,10 @@ pub(crate) struct Args<T> {
 	/// Parsed JSON content.
 	/// None when body is not a valid string
 	pub(crate) json_body: Option<CanonicalJsonValue>,
+
+	/// Request trace started by an admin for this user or room.
+	/// None when the request is not being traced.
+	pub(crate) trace: Option<Arc<Trace>>,
 }
 
 impl<T> Args<T>
@@ -107,6 +112,11 @@ async fn from_request(
 			json_body = Some(CanonicalJsonValue::Object(CanonicalJsonObject::new()));
 		}
 		let auth = auth::auth(services, &mut request, json_body.as_ref(), &T::METADATA).await?;
+		update_last_seen(services, &mut request, &auth).await;
+		let trace = services
+			.admin
+			.request_trace(auth.sender_user.as_deref(), request.parts.uri.path());
+
 		Ok(Self {
 			body: make_body::<T>(services, &mut request, json_body.as_mut(), &auth)?,
 			origin: auth.origin,
@@ -114,10 +124,34 @@ async fn from_request(
 			sender_device: auth.sender_device,
 			appservice_info: auth.appservice_info,
 			json_body,
+			trace,
 		})
 	}
 }
 
+async fn update_last_seen(services: &Services, request: &mut Request, auth: &Auth) {
+	let (Some(user_id), Some(device_id), None) =
+		(&auth.sender_user, &auth.sender_device, &auth.appservice_info)
+	else {
+		return;
+	};
+
+	let Ok(InsecureClientIp(ip)) = request.parts.extract::<InsecureClientIp>().await else {
+		return;
+	};
+
+	let user_agent = request
+		.parts
+		.headers
+		.get(http::header::USER_AGENT)
+		.and_then(|user_agent| user_agent.to_str().ok());
+
+	services
+		.users
+		.update_last_seen(user_id, device_id, &ip.to_string(), user_agent)
+		.await;
+}
+
 fn make_body<T>(
 	services: &Services,
 	request: &mut Request,
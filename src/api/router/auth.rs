@@ -14,6 +14,7 @@
 			profile::{
 				get_avatar_url, get_display_name, get_profile, get_profile_key, get_timezone_key,
 			},
+			session::{logout, logout_all},
 			voip::get_turn_server_info,
 		},
 		federation::{authentication::XMatrix, openid::get_openid_userinfo},
@@ -68,6 +69,16 @@ pub(super) async fn auth(
 		Token::None
 	};
 
+	if let Token::User((user_id, _)) = &token {
+		if services.users.is_account_expired(user_id).await
+			&& !matches!(
+				*metadata,
+				logout::v3::Request::METADATA | logout_all::v3::Request::METADATA
+			) {
+			return Err(account_expired());
+		}
+	}
+
 	if metadata.authentication == AuthScheme::None {
 		match metadata {
 			| &get_public_rooms::v3::Request::METADATA => {
@@ -238,7 +249,7 @@ async fn auth_server(
 	type Value = CanonicalJsonValue;
 
 	let x_matrix = parse_x_matrix(request).await?;
-	auth_server_checks(services, &x_matrix)?;
+	auth_server_checks(services, &x_matrix).await?;
 
 	let destination = services.globals.server_name();
 	let origin = &x_matrix.origin;
@@ -305,7 +316,16 @@ async fn auth_server(
 	})
 }
 
-fn auth_server_checks(services: &Services, x_matrix: &XMatrix) -> Result<()> {
+/// Error for requests from an account past its `account_validity_period`.
+fn account_expired() -> Error {
+	Error::Request(
+		ErrorKind::UserLocked,
+		"This account has expired.".into(),
+		http::StatusCode::UNAUTHORIZED,
+	)
+}
+
+async fn auth_server_checks(services: &Services, x_matrix: &XMatrix) -> Result<()> {
 	if !services.server.config.allow_federation {
 		return Err!(Config("allow_federation", "Federation is disabled."));
 	}
@@ -316,10 +336,10 @@ fn auth_server_checks(services: &Services, x_matrix: &XMatrix) -> Result<()> {
 	}
 
 	let origin = &x_matrix.origin;
-	if services
-		.config
-		.forbidden_remote_server_names
-		.is_match(origin.host())
+	if !services
+		.federation
+		.is_federation_allowed(origin)
+		.await
 	{
 		return Err!(Request(Forbidden(debug_warn!(
 			"Federation requests from {origin} denied."
@@ -1,13 +1,14 @@
 use axum::{
 	Router,
 	extract::FromRequestParts,
-	response::IntoResponse,
+	response::{IntoResponse, Response},
 	routing::{MethodFilter, on},
 };
 use futures::{Future, TryFutureExt};
 use http::Method;
 use ruma::api::IncomingRequest;
 use tuwunel_core::Result;
+use tuwunel_service::admin::traced;
 
 use super::{Ruma, RumaResponse, State};
 
@@ -20,6 +21,14 @@ pub(in super::super) trait RouterExt {
 	fn ruma_route<H, T>(self, handler: &'static H) -> Self
 	where
 		H: RumaHandler<T>;
+
+	/// Routes a handler which builds its own (e.g. streamed) response rather
+	/// than returning the Ruma response type.
+	fn ruma_stream_route<Req, Fun, Fut>(self, handler: &'static Fun) -> Self
+	where
+		Fun: Fn(axum::extract::State<State>, Ruma<Req>) -> Fut + Send + Sync + 'static,
+		Fut: Future<Output = Result<Response>> + Send,
+		Req: IncomingRequest + Send + Sync + 'static;
 }
 
 impl RouterExt for Router<State> {
@@ -29,6 +38,26 @@ fn ruma_route<H, T>(self, handler: &'static H) -> Self
 	{
 		handler.add_routes(self)
 	}
+
+	fn ruma_stream_route<Req, Fun, Fut>(self, handler: &'static Fun) -> Self
+	where
+		Fun: Fn(axum::extract::State<State>, Ruma<Req>) -> Fut + Send + Sync + 'static,
+		Fut: Future<Output = Result<Response>> + Send,
+		Req: IncomingRequest + Send + Sync + 'static,
+	{
+		let method = method_to_filter(&Req::METADATA.method);
+		Req::METADATA
+			.history
+			.all_paths()
+			.fold(self, |router, path| {
+				let action = |state, req: Ruma<Req>| {
+					let trace = req.trace.clone();
+					traced(trace, handler(state, req))
+				};
+
+				router.route(path, on(method, action))
+			})
+	}
 }
 
 macro_rules! ruma_handler {
@@ -51,7 +80,10 @@ fn add_routes(&'static self, router: Router<State>) -> Router<State> {
 			}
 
 			fn add_route(&'static self, router: Router<State>, path: &str) -> Router<State> {
-				let action = |$($tx,)* req| self($($tx,)* req).map_ok(RumaResponse);
+				let action = |$($tx,)* req: Ruma<Req>| {
+					let trace = req.trace.clone();
+					traced(trace, self($($tx,)* req)).map_ok(RumaResponse)
+				};
 				let method = method_to_filter(&Req::METADATA.method);
 				router.route(path, on(method, action))
 			}
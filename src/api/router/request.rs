@@ -4,7 +4,7 @@
 use bytes::Bytes;
 use http::request::Parts;
 use serde::Deserialize;
-use tuwunel_core::{Result, err};
+use tuwunel_core::{Result, config::RouteClass, err};
 use tuwunel_service::Services;
 
 #[derive(Deserialize)]
@@ -32,7 +32,10 @@ pub(super) async fn from(
 	let query = serde_html_form::from_str(query)
 		.map_err(|e| err!(Request(Unknown("Failed to read query parameters: {e}"))))?;
 
-	let max_body_size = services.server.config.max_request_size;
+	let max_body_size = services
+		.server
+		.config
+		.request_size(RouteClass::of(parts.uri.path()));
 
 	let body = axum::body::to_bytes(body, max_body_size)
 		.await
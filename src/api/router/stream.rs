@@ -0,0 +1,88 @@
+use std::sync::{
+	Arc,
+	atomic::{AtomicU64, Ordering},
+};
+
+use axum::{
+	body::Body,
+	response::{IntoResponse, Response},
+};
+use bytes::{BufMut, Bytes, BytesMut};
+use futures::{
+	Stream, StreamExt,
+	future::{lazy, ready},
+	stream::{self, BoxStream},
+};
+use http::header::CONTENT_TYPE;
+use tuwunel_core::{Result, Server, debug};
+
+/// A JSON array member of a streamed response object, each item being one
+/// serialized array element.
+pub(crate) type JsonArray = (&'static str, BoxStream<'static, Result<Bytes>>);
+
+/// Streams a JSON object whose members are arrays, serializing one element
+/// per body chunk with chunked transfer so the response is never held in
+/// memory as a whole. Errors after the first chunk abort the response.
+pub(crate) fn json_arrays_response(server: Arc<Server>, arrays: Vec<JsonArray>) -> Response {
+	let length = Arc::new(AtomicU64::new(0));
+	let counted = length.clone();
+	let members = stream::iter(arrays)
+		.enumerate()
+		.flat_map(|(i, (key, elems))| {
+			let open = if i == 0 { "{" } else { "," };
+			let open = Bytes::from(format!("{open}\"{key}\":["));
+			stream::once(ready(Ok(open)))
+				.chain(elems.enumerate().map(|(j, elem)| {
+					elem.map(|elem| {
+						if j == 0 {
+							return elem;
+						}
+
+						let mut buf = BytesMut::with_capacity(elem.len().saturating_add(1));
+						buf.put_u8(b',');
+						buf.put(elem);
+						buf.freeze()
+					})
+				}))
+				.chain(stream::once(ready(Ok(Bytes::from_static(b"]")))))
+		})
+		.inspect(move |chunk| {
+			if let Ok(chunk) = chunk {
+				counted.fetch_add(chunk.len().try_into().unwrap_or(0), Ordering::Relaxed);
+			}
+		});
+
+	let close = stream::once(lazy(move |_| {
+		let length = length.load(Ordering::Relaxed).saturating_add(1);
+		record_metrics(&server, length);
+		Ok(Bytes::from_static(b"}"))
+	}));
+
+	let body: BoxStream<'static, Result<Bytes>> = members.chain(close).boxed();
+	([(CONTENT_TYPE, "application/json")], Body::from_stream(body)).into_response()
+}
+
+/// Serializes each item of the stream as a JSON array element.
+pub(crate) fn json_elements<S, T>(elems: S) -> BoxStream<'static, Result<Bytes>>
+where
+	S: Stream<Item = Result<T>> + Send + 'static,
+	T: serde::Serialize,
+{
+	elems
+		.map(|elem| Ok(serde_json::to_vec(&elem?)?.into()))
+		.boxed()
+}
+
+fn record_metrics(server: &Server, length: u64) {
+	debug!(length, "Streamed federation state response");
+	let metrics = &server.metrics;
+	metrics
+		.federation_state_responses
+		.fetch_add(1, Ordering::Relaxed);
+	metrics
+		.federation_state_response_bytes
+		.fetch_add(length, Ordering::Relaxed);
+	metrics
+		.federation_state_response_bytes_max
+		.fetch_max(length, Ordering::Relaxed);
+}
@@ -1,19 +1,28 @@
+use std::time::Duration;
+
 use axum::extract::State;
 use axum_client_ip::InsecureClientIp;
 use base64::{Engine as _, engine::general_purpose};
+use futures::StreamExt;
 use ruma::{
-	CanonicalJsonValue, OwnedUserId, UserId,
+	CanonicalJsonValue, OwnedUserId, ServerName, UserId,
 	api::{client::error::ErrorKind, federation::membership::create_invite},
-	events::room::member::{MembershipState, RoomMemberEventContent},
+	events::{
+		StateEventType,
+		room::member::{MembershipState, RoomMemberEventContent},
+	},
 	serde::JsonObject,
 };
 use tuwunel_core::{
-	Err, Error, Result, err,
+	Err, Error, Result,
+	config::{InvitePolicy, NoticeCategory},
+	err,
 	matrix::{Event, PduEvent, event::gen_event_id},
 	utils,
-	utils::hash::sha256,
+	utils::{ReadyExt, hash::sha256, time::pretty},
 	warn,
 };
+use tuwunel_service::Services;
 
 use crate::Ruma;
 
@@ -119,6 +128,28 @@ pub(crate) async fn create_invite_route(
 		return Err!(Request(Forbidden("This server does not allow room invites.")));
 	}
 
+	services
+		.rooms
+		.membership_limit
+		.check(sender, &body.room_id)
+		.await?;
+
+	let policy = invite_policy(&services, body.origin(), sender, &invited_user).await;
+	if let Some((InvitePolicy::Reject, reason)) = &policy {
+		services
+			.admin
+			.category_notice(
+				NoticeCategory::Invite,
+				&format!(
+					"Rejected invite from {sender} to {invited_user} for {}: {reason}.",
+					body.room_id,
+				),
+			)
+			.await;
+
+		return Err!(Request(Forbidden("This server does not accept this invite.")));
+	}
+
 	let mut invite_state = body.invite_room_state.clone();
 
 	let mut event: JsonObject = serde_json::from_str(body.event.get())
@@ -131,6 +162,12 @@ pub(crate) async fn create_invite_route(
 
 	invite_state.push(pdu.to_format());
 
+	services
+		.rooms
+		.membership_limit
+		.count(sender, &body.room_id)
+		.await;
+
 	// If we are active in the room, the remote server will notify us about the
 	// join/invite through /send. If we are not in the room, we need to manually
 	// record the invited state for client /sync through update_membership(), and
@@ -141,6 +178,35 @@ pub(crate) async fn create_invite_route(
 		.server_in_room(services.globals.server_name(), &body.room_id)
 		.await
 	{
+		if let Some((InvitePolicy::Suppress, reason)) = &policy {
+			services.rooms.state_cache.suppress_invite(
+				&invited_user,
+				&body.room_id,
+				sender,
+				invite_state,
+				body.via.clone(),
+			);
+
+			services
+				.admin
+				.category_notice(
+					NoticeCategory::Invite,
+					&format!(
+						"Suppressed invite from {sender} to {invited_user} for {}: {reason}. \
+						 Release it with `!admin users invites release {invited_user} {}`.",
+						body.room_id, body.room_id,
+					),
+				)
+				.await;
+
+			return Ok(create_invite::v2::Response {
+				event: services
+					.sending
+					.convert_to_outgoing_federation_event(signed_event)
+					.await,
+			});
+		}
+
 		services
 			.rooms
 			.state_cache
@@ -182,3 +248,71 @@ pub(crate) async fn create_invite_route(
 			.await,
 	})
 }
+
+/// The policy applying to an invite, with the reason, when it is not simply
+/// allowed: the origin shares no rooms with us, or the sender's account is
+/// young.
+async fn invite_policy(
+	services: &Services,
+	origin: &ServerName,
+	sender: &UserId,
+	invited_user: &UserId,
+) -> Option<(InvitePolicy, String)> {
+	let config = &services.config;
+	if config
+		.invite_unshared_servers_allowed
+		.is_match(origin.host())
+		|| services.users.is_admin(invited_user).await
+	{
+		return None;
+	}
+
+	if config.invite_unshared_servers != InvitePolicy::Allow
+		&& !services
+			.rooms
+			.state_cache
+			.shares_room_with(origin)
+			.await
+	{
+		let reason = format!("{origin} shares no rooms with this server");
+		return Some((config.invite_unshared_servers, reason));
+	}
+
+	if config.invite_young_accounts != InvitePolicy::Allow {
+		let min_age = Duration::from_secs(config.invite_young_account_age);
+		let age = account_age(services, sender).await;
+		if age.is_some_and(|age| age < min_age) {
+			let reason = format!("{sender} was first seen less than {} ago", pretty(min_age));
+			return Some((config.invite_young_accounts, reason));
+		}
+	}
+
+	None
+}
+
+/// Lower bound of the age of a remote account: the time since its earliest
+/// current membership in the rooms we share with it. None when it is in none
+/// of them.
+async fn account_age(services: &Services, user_id: &UserId) -> Option<Duration> {
+	let first_seen = services
+		.rooms
+		.state_cache
+		.rooms_joined(user_id)
+		.then(|room_id| {
+			services.rooms.state_accessor.room_state_get(
+				room_id,
+				&StateEventType::RoomMember,
+				user_id.as_str(),
+			)
+		})
+		.ready_filter_map(Result::ok)
+		.map(|event| event.origin_server_ts().get().into())
+		.ready_fold(None, |first: Option<u64>, ts: u64| {
+			Some(first.map_or(ts, |first| first.min(ts)))
+		})
+		.await?;
+
+	let age = utils::millis_since_unix_epoch().saturating_sub(first_seen);
+
+	Some(Duration::from_millis(age))
+}
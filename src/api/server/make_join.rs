@@ -6,14 +6,17 @@
 	events::{
 		StateEventType,
 		room::{
-			join_rules::{AllowRule, JoinRule, RoomJoinRulesEventContent},
+			join_rules::{JoinRule, RoomJoinRulesEventContent},
 			member::{MembershipState, RoomMemberEventContent},
 		},
 	},
 };
 use serde_json::value::to_raw_value;
 use tuwunel_core::{
-	Err, Error, Result, debug_info, matrix::pdu::PduBuilder, utils::IterStream, warn,
+	Err, Error, Result, debug_info,
+	matrix::{pdu::PduBuilder, state_res::event_auth::restricted_join_rooms},
+	utils::IterStream,
+	warn,
 };
 use tuwunel_service::Services;
 
@@ -107,20 +110,9 @@ pub(crate) async fn create_join_event_template_route(
 		{
 			let Some(auth_user) = services
 				.rooms
-				.state_cache
-				.local_users_in_room(&body.room_id)
-				.filter(|user| {
-					services.rooms.state_accessor.user_can_invite(
-						&body.room_id,
-						user,
-						&body.user_id,
-						&state_lock,
-					)
-				})
-				.boxed()
-				.next()
+				.state_accessor
+				.restricted_join_authoriser(&body.room_id, &body.user_id, &state_lock)
 				.await
-				.map(ToOwned::to_owned)
 			else {
 				return Err!(Request(UnableToGrantJoin(
 					"No user on this server is able to assist in joining."
@@ -195,7 +187,7 @@ pub(crate) async fn user_can_perform_restricted_join(
 	};
 
 	let (JoinRule::Restricted(r) | JoinRule::KnockRestricted(r)) =
-		join_rules_event_content.join_rule
+		&join_rules_event_content.join_rule
 	else {
 		return Ok(false);
 	};
@@ -205,21 +197,13 @@ pub(crate) async fn user_can_perform_restricted_join(
 		return Ok(false);
 	}
 
-	if r.allow
-		.iter()
-		.filter_map(|rule| {
-			if let AllowRule::RoomMembership(membership) = rule {
-				Some(membership)
-			} else {
-				None
-			}
-		})
+	if restricted_join_rooms(&join_rules_event_content.join_rule)
 		.stream()
-		.any(|m| {
+		.any(|allowed| {
 			services
 				.rooms
 				.state_cache
-				.is_joined(user_id, &m.room_id)
+				.is_joined(user_id, allowed)
 		})
 		.await
 	{
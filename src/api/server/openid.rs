@@ -1,20 +1,38 @@
-use axum::extract::State;
+use axum::extract::{Query, State};
 use ruma::api::federation::openid::get_openid_userinfo;
+use serde::Deserialize;
 use tuwunel_core::Result;
+use tuwunel_service::users::OpenIdVerifier;
 
 use crate::Ruma;
 
+/// Non-standard parameters naming the service verifying the token.
+#[derive(Deserialize)]
+pub(crate) struct VerifierQuery {
+	audience: Option<String>,
+	scope: Option<String>,
+}
+
 /// # `GET /_matrix/federation/v1/openid/userinfo`
 ///
 /// Get information about the user that generated the OpenID token.
+///
+/// - A token restricted to an audience or scopes is only accepted when the
+///   `audience` and `scope` query parameters are within them
 pub(crate) async fn get_openid_userinfo_route(
 	State(services): State<crate::State>,
+	Query(query): Query<VerifierQuery>,
 	body: Ruma<get_openid_userinfo::v1::Request>,
 ) -> Result<get_openid_userinfo::v1::Response> {
+	let verifier = OpenIdVerifier {
+		audience: query.audience.as_deref(),
+		scope: query.scope.as_deref(),
+	};
+
 	Ok(get_openid_userinfo::v1::Response::new(
 		services
 			.users
-			.find_from_openid_token(&body.access_token)
+			.find_from_openid_token(&body.access_token, &verifier)
 			.await?,
 	))
 }
@@ -1,6 +1,7 @@
 use axum::extract::State;
 use axum_client_ip::InsecureClientIp;
 use ruma::{
+	ServerName,
 	api::{
 		client::error::ErrorKind,
 		federation::directory::{get_public_rooms, get_public_rooms_filtered},
@@ -8,6 +9,7 @@
 	directory::Filter,
 };
 use tuwunel_core::{Error, Result};
+use tuwunel_service::Services;
 
 use crate::Ruma;
 
@@ -20,13 +22,7 @@ pub(crate) async fn get_public_rooms_filtered_route(
 	InsecureClientIp(client): InsecureClientIp,
 	body: Ruma<get_public_rooms_filtered::v1::Request>,
 ) -> Result<get_public_rooms_filtered::v1::Response> {
-	if !services
-		.server
-		.config
-		.allow_public_room_directory_over_federation
-	{
-		return Err(Error::BadRequest(ErrorKind::forbidden(), "Room directory is not public"));
-	}
+	check_directory_access(&services, body.origin())?;
 
 	let response = crate::client::get_public_rooms_filtered_helper(
 		&services,
@@ -58,12 +54,7 @@ pub(crate) async fn get_public_rooms_route(
 	InsecureClientIp(client): InsecureClientIp,
 	body: Ruma<get_public_rooms::v1::Request>,
 ) -> Result<get_public_rooms::v1::Response> {
-	if !services
-		.globals
-		.allow_public_room_directory_over_federation()
-	{
-		return Err(Error::BadRequest(ErrorKind::forbidden(), "Room directory is not public"));
-	}
+	check_directory_access(&services, body.origin())?;
 
 	let response = crate::client::get_public_rooms_filtered_helper(
 		&services,
@@ -85,3 +76,27 @@ pub(crate) async fn get_public_rooms_route(
 		total_room_count_estimate: response.total_room_count_estimate,
 	})
 }
+
+/// Whether `origin` may query our public room directory.
+fn check_directory_access(services: &Services, origin: &ServerName) -> Result {
+	if !services
+		.globals
+		.allow_public_room_directory_over_federation()
+	{
+		return Err(Error::BadRequest(ErrorKind::forbidden(), "Room directory is not public"));
+	}
+
+	let allowed = &services
+		.server
+		.config
+		.public_room_directory_federation_servers;
+
+	if !allowed.is_empty() && !allowed.is_match(origin.host()) {
+		return Err(Error::BadRequest(
+			ErrorKind::forbidden(),
+			"Room directory is not available to this server",
+		));
+	}
+
+	Ok(())
+}
@@ -19,6 +19,7 @@
 	},
 	events::receipt::{ReceiptEvent, ReceiptEventContent, ReceiptType},
 	serde::Raw,
+	signatures::Verified,
 	to_device::DeviceIdOrAllDevices,
 };
 use tuwunel_core::{
@@ -42,6 +43,7 @@
 
 type ResolvedMap = BTreeMap<OwnedEventId, Result>;
 type Pdu = (OwnedRoomId, OwnedEventId, CanonicalJsonObject);
+type VerifiedPdu = (OwnedRoomId, OwnedEventId, CanonicalJsonObject, Result<Verified>);
 
 /// # `PUT /_matrix/federation/v1/send/{txnId}`
 ///
@@ -109,7 +111,33 @@ pub(crate) async fn send_transaction_message_route(
 		.filter_map(Result::ok)
 		.stream();
 
-	let results = handle(&services, &client, body.origin(), txn_start_time, pdus, edus).await?;
+	let results = handle(&services, &client, body.origin(), txn_start_time, pdus, edus).await;
+
+	let bytes = body
+		.pdus
+		.iter()
+		.map(|pdu| pdu.get().len())
+		.chain(body.edus.iter().map(|edu| edu.json().get().len()))
+		.sum();
+
+	let rejected = results
+		.as_ref()
+		.map_or(body.pdus.len(), |results| {
+			results
+				.values()
+				.filter(|result| result.is_err())
+				.count()
+		});
+
+	services.federation.record_received(
+		body.origin(),
+		body.pdus.len(),
+		body.edus.len(),
+		bytes,
+		rejected,
+	);
+
+	let results = results?;
 
 	debug!(
 		pdus = body.pdus.len(),
@@ -143,6 +171,25 @@ async fn handle(
 	pdus: impl Stream<Item = Pdu> + Send,
 	edus: impl Stream<Item = Edu> + Send,
 ) -> Result<ResolvedMap> {
+	// verify signatures concurrently; buffered keeps the order within each room
+	let parallelism = services
+		.server
+		.config
+		.federation_verify_parallelism
+		.max(1);
+
+	let pdus = pdus
+		.map(|(room_id, event_id, value)| async move {
+			let verified = services
+				.rooms
+				.event_handler
+				.verify_incoming_pdu(&room_id, &value)
+				.await;
+
+			(room_id, event_id, value, verified)
+		})
+		.buffered(parallelism);
+
 	// group pdus by room
 	let pdus = pdus
 		.collect()
@@ -154,8 +201,9 @@ async fn handle(
 		})
 		.await;
 
-	// we can evaluate rooms concurrently
-	let results: ResolvedMap = pdus
+	// we can evaluate rooms concurrently; the writes of every pdu in the
+	// transaction are flushed together once all have been appended.
+	let results = pdus
 		.into_iter()
 		.try_stream()
 		.broad_and_then(|(room_id, pdus): (_, Vec<_>)| {
@@ -164,7 +212,11 @@ async fn handle(
 				.map_ok(IterStream::try_stream)
 		})
 		.try_flatten()
-		.try_collect()
+		.try_collect::<ResolvedMap>();
+
+	let results = services
+		.db
+		.deferred_flush(results)
 		.boxed()
 		.await?;
 
@@ -182,7 +234,7 @@ async fn handle_room(
 	origin: &ServerName,
 	txn_start_time: Instant,
 	room_id: OwnedRoomId,
-	pdus: impl Iterator<Item = Pdu> + Send,
+	pdus: impl Iterator<Item = VerifiedPdu> + Send,
 ) -> Result<Vec<(OwnedEventId, Result)>> {
 	let _room_lock = services
 		.rooms
@@ -193,13 +245,20 @@ async fn handle_room(
 
 	let room_id = &room_id;
 	pdus.try_stream()
-		.and_then(|(_, event_id, value)| async move {
+		.and_then(|(_, event_id, value, verified)| async move {
 			services.server.check_running()?;
 			let pdu_start_time = Instant::now();
 			let result = services
 				.rooms
 				.event_handler
-				.handle_incoming_pdu(origin, room_id, &event_id, value, true)
+				.handle_incoming_verified_pdu(
+					origin,
+					room_id,
+					&event_id,
+					value,
+					true,
+					Some(verified),
+				)
 				.await
 				.map(|_| ());
 
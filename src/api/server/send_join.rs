@@ -141,6 +141,12 @@ async fn create_join_event(
 		return Err!(Request(BadJson("State key does not match sender user.")));
 	}
 
+	services
+		.rooms
+		.membership_limit
+		.check(&sender, room_id)
+		.await?;
+
 	if let Some(authorising_user) = content.join_authorized_via_users_server {
 		use ruma::RoomVersionId::*;
 
@@ -160,13 +166,13 @@ async fn create_join_event(
 
 		if !services
 			.rooms
-			.state_cache
-			.is_joined(&authorising_user, room_id)
+			.state_accessor
+			.user_can_authorise_join(room_id, &authorising_user)
 			.await
 		{
 			return Err!(Request(InvalidParam(
-				"Authorising user {authorising_user} is not in the room you are trying to join, \
-				 they cannot authorise your join."
+				"Authorising user {authorising_user} is not in the room you are trying to join \
+				 or lacks the power to invite, they cannot authorise your join."
 			)));
 		}
 
@@ -215,6 +221,12 @@ async fn create_join_event(
 
 	drop(mutex_lock);
 
+	services
+		.rooms
+		.membership_limit
+		.count(&sender, room_id)
+		.await;
+
 	let state_ids: Vec<OwnedEventId> = services
 		.rooms
 		.state_accessor
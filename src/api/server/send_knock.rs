@@ -150,6 +150,12 @@ pub(crate) async fn create_knock_event_v1_route(
 		return Err!(Request(InvalidParam("state_key does not match sender user of event.")));
 	}
 
+	services
+		.rooms
+		.membership_limit
+		.check(&sender, &body.room_id)
+		.await?;
+
 	let origin: OwnedServerName = serde_json::from_value(
 		value
 			.get("origin")
@@ -184,6 +190,12 @@ pub(crate) async fn create_knock_event_v1_route(
 
 	drop(mutex_lock);
 
+	services
+		.rooms
+		.membership_limit
+		.count(&sender, &body.room_id)
+		.await;
+
 	services
 		.sending
 		.send_pdu_room(&body.room_id, &pdu_id)
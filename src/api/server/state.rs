@@ -1,20 +1,30 @@
 use std::{borrow::Borrow, iter::once};
 
-use axum::extract::State;
-use futures::{FutureExt, StreamExt, TryStreamExt};
+use axum::{extract::State, response::Response};
+use futures::{StreamExt, TryStreamExt};
 use ruma::{OwnedEventId, api::federation::event::get_room_state};
-use tuwunel_core::{Result, at, err, utils::IterStream};
+use tuwunel_core::{
+	Err, Result, at, err,
+	utils::stream::{BroadbandExt, IterStream},
+};
 
 use super::AccessCheck;
-use crate::Ruma;
+use crate::{
+	Ruma,
+	router::{json_arrays_response, json_elements},
+};
 
 /// # `GET /_matrix/federation/v1/state/{roomId}`
 ///
 /// Retrieves a snapshot of a room's state at a given event.
+///
+/// The response is streamed: only the event IDs are collected and checked up
+/// front, so a missing event is an error rather than a truncated body, and
+/// each PDU is loaded as its part of the body is sent.
 pub(crate) async fn get_room_state_route(
 	State(services): State<crate::State>,
 	body: Ruma<get_room_state::v1::Request>,
-) -> Result<get_room_state::v1::Response> {
+) -> Result<Response> {
 	AccessCheck {
 		services: &services,
 		origin: body.origin(),
@@ -39,32 +49,50 @@ pub(crate) async fn get_room_state_route(
 		.collect()
 		.await;
 
-	let pdus = state_ids
+	let auth_chain_ids: Vec<OwnedEventId> = services
+		.rooms
+		.auth_chain
+		.event_ids_iter(&body.room_id, once(body.event_id.borrow()))
+		.try_collect()
+		.await?;
+
+	if let Some(missing) = state_ids
 		.iter()
+		.chain(&auth_chain_ids)
+		.stream()
+		.broad_filter_map(|id| async move {
+			(!services.rooms.timeline.pdu_exists(id).await).then_some(id)
+		})
+		.next()
+		.await
+	{
+		return Err!(Request(NotFound("Event {missing} of the state was not found.")));
+	}
+
+	let pdus = state_ids
+		.into_iter()
 		.try_stream()
-		.and_then(|id| services.rooms.timeline.get_pdu_json(id))
-		.and_then(|pdu| {
-			services
+		.and_then(move |id| async move { services.rooms.timeline.get_pdu_json(&id).await })
+		.and_then(move |pdu| async move {
+			Ok(services
 				.sending
 				.convert_to_outgoing_federation_event(pdu)
-				.map(Ok)
-		})
-		.try_collect()
-		.await?;
+				.await)
+		});
 
-	let auth_chain = services
-		.rooms
-		.auth_chain
-		.event_ids_iter(&body.room_id, once(body.event_id.borrow()))
-		.and_then(|id| async move { services.rooms.timeline.get_pdu_json(&id).await })
-		.and_then(|pdu| {
-			services
+	let auth_chain = auth_chain_ids
+		.into_iter()
+		.try_stream()
+		.and_then(move |id| async move { services.rooms.timeline.get_pdu_json(&id).await })
+		.and_then(move |pdu| async move {
+			Ok(services
 				.sending
 				.convert_to_outgoing_federation_event(pdu)
-				.map(Ok)
-		})
-		.try_collect()
-		.await?;
+				.await)
+		});
 
-	Ok(get_room_state::v1::Response { auth_chain, pdus })
+	Ok(json_arrays_response(services.server.clone(), vec![
+		("auth_chain", json_elements(auth_chain)),
+		("pdus", json_elements(pdus)),
+	]))
 }
@@ -1,21 +1,26 @@
 use std::{borrow::Borrow, iter::once};
 
-use axum::extract::State;
+use axum::{extract::State, response::Response};
 use futures::{StreamExt, TryStreamExt};
 use ruma::{OwnedEventId, api::federation::event::get_room_state_ids};
-use tuwunel_core::{Result, at, err};
+use tuwunel_core::{Result, at, err, utils::IterStream};
 
 use super::AccessCheck;
-use crate::Ruma;
+use crate::{
+	Ruma,
+	router::{json_arrays_response, json_elements},
+};
 
 /// # `GET /_matrix/federation/v1/state_ids/{roomId}`
 ///
 /// Retrieves a snapshot of a room's state at a given event, in the form of
 /// event IDs.
+///
+/// The response is streamed from the collected event IDs.
 pub(crate) async fn get_room_state_ids_route(
 	State(services): State<crate::State>,
 	body: Ruma<get_room_state_ids::v1::Request>,
-) -> Result<get_room_state_ids::v1::Response> {
+) -> Result<Response> {
 	AccessCheck {
 		services: &services,
 		origin: body.origin(),
@@ -40,12 +45,15 @@ pub(crate) async fn get_room_state_ids_route(
 		.collect()
 		.await;
 
-	let auth_chain_ids = services
+	let auth_chain_ids: Vec<OwnedEventId> = services
 		.rooms
 		.auth_chain
 		.event_ids_iter(&body.room_id, once(body.event_id.borrow()))
 		.try_collect()
 		.await?;
 
-	Ok(get_room_state_ids::v1::Response { auth_chain_ids, pdu_ids })
+	Ok(json_arrays_response(services.server.clone(), vec![
+		("auth_chain_ids", json_elements(auth_chain_ids.into_iter().try_stream())),
+		("pdu_ids", json_elements(pdu_ids.into_iter().try_stream())),
+	]))
 }
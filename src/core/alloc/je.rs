@@ -34,7 +34,7 @@
 );
 
 #[cfg(all(feature = "jemalloc_conf", feature = "jemalloc_prof"))]
-const MALLOC_CONF_PROF: &str = ",prof_active:false";
+const MALLOC_CONF_PROF: &str = ",prof:true,prof_active:false";
 #[cfg(all(feature = "jemalloc_conf", not(feature = "jemalloc_prof")))]
 const MALLOC_CONF_PROF: &str = "";
 
@@ -246,6 +246,18 @@ pub fn is_prof_enabled() -> Result<bool> {
 	get::<u8>(&mallctl!("prof.active")).map(is_nonzero!())
 }
 
+/// Write a heap profile to the file at `path`. The output is readable by
+/// jeprof; samples are only collected while `prof.active` is enabled.
+#[cfg(feature = "jemalloc_prof")]
+pub fn prof_dump(path: &CStr) -> Result {
+	let _lock = CONTROL.write()?;
+
+	// SAFETY: prof.dump is write-only and expects a pointer to a null-terminated
+	// filename which only has to remain valid for the duration of the call.
+	unsafe { mallctl::raw::write_mib(mallctl!("prof.dump").as_slice(), path.as_ptr()) }
+		.map_err(map_err)
+}
+
 pub fn trim<I: Into<Option<usize>> + Copy>(arena: I) -> Result {
 	decay(arena).and_then(|()| purge(arena))
 }
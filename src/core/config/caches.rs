@@ -0,0 +1,163 @@
+//! In-memory cache capacities; see `CachesConfig`.
+
+use super::Config;
+use crate::{
+	Result, err,
+	utils::{math::usize_from_f64, sys},
+};
+
+/// Cache whose capacity can be configured in `[global.caches]`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CacheKind {
+	/// Event data by PDU ID, including outliers.
+	Pdu,
+
+	/// Auth chains by event.
+	AuthChain,
+
+	/// Event IDs by short event ID.
+	ShortEventId,
+
+	/// Short event IDs by event ID.
+	EventIdShort,
+
+	/// PDU IDs by event ID.
+	EventIdPdu,
+
+	/// State keys by short state key.
+	ShortStateKey,
+
+	/// Short state keys by state key.
+	StateKeyShort,
+
+	/// Per-server federation event data.
+	ServerNameEventData,
+
+	/// Decompressed room state.
+	StateInfo,
+
+	/// Space hierarchy summaries.
+	SpaceHierarchy,
+
+	/// Pagination states of space hierarchy requests.
+	SpacePagination,
+
+	/// Resolved remote room aliases.
+	Alias,
+
+	/// Device keys of remote users.
+	RemoteDeviceKeys,
+}
+
+impl CacheKind {
+	/// Default capacity as a fixed number of entries and a number of entries
+	/// per unit of scale; see `scale_units()`.
+	fn default_size(self) -> (u32, u32) {
+		match self {
+			| Self::Pdu | Self::AuthChain | Self::ShortStateKey | Self::StateKeyShort =>
+				(100_000, 10_000),
+			| Self::ShortEventId => (100_000, 50_000),
+			| Self::EventIdShort | Self::EventIdPdu => (100_000, 25_000),
+			| Self::ServerNameEventData => (500_000, 100_000),
+			| Self::StateInfo => (0, 100),
+			| Self::SpaceHierarchy => (0, 1000),
+			| Self::SpacePagination => (1024, 0),
+			| Self::Alias => (0, 500),
+			| Self::RemoteDeviceKeys => (0, 1000),
+		}
+	}
+}
+
+impl Config {
+	/// Number of entries the cache may hold, after `cache_capacity_modifier`.
+	pub fn cache_capacity(&self, cache: CacheKind) -> Result<usize> {
+		let capacity = self
+			.caches
+			.capacity(cache)
+			.or_else(|| self.legacy_cache_capacity(cache))
+			.unwrap_or_else(|| default_capacity(cache, self.caches.auto));
+
+		usize_from_f64(f64::from(capacity) * self.cache_capacity_modifier)
+			.map_err(|e| err!(Config("cache_capacity_modifier", "Invalid cache size: {e}")))
+	}
+
+	fn legacy_cache_capacity(&self, cache: CacheKind) -> Option<u32> {
+		match cache {
+			| CacheKind::Pdu => self.pdu_cache_capacity,
+			| CacheKind::AuthChain => self.auth_chain_cache_capacity,
+			| CacheKind::ShortEventId => self.shorteventid_cache_capacity,
+			| CacheKind::EventIdShort => self.eventidshort_cache_capacity,
+			| CacheKind::EventIdPdu => self.eventid_pdu_cache_capacity,
+			| CacheKind::ShortStateKey => self.shortstatekey_cache_capacity,
+			| CacheKind::StateKeyShort => self.statekeyshort_cache_capacity,
+			| CacheKind::ServerNameEventData => self.servernameevent_data_cache_capacity,
+			| CacheKind::StateInfo => self.stateinfo_cache_capacity,
+			| CacheKind::SpaceHierarchy => self.roomid_spacehierarchy_cache_capacity,
+			| CacheKind::Alias => self.alias_cache_capacity,
+			| CacheKind::SpacePagination | CacheKind::RemoteDeviceKeys => None,
+		}
+	}
+}
+
+impl super::CachesConfig {
+	fn capacity(&self, cache: CacheKind) -> Option<u32> {
+		match cache {
+			| CacheKind::Pdu => self.pdu,
+			| CacheKind::AuthChain => self.auth_chain,
+			| CacheKind::ShortEventId => self.shorteventid,
+			| CacheKind::EventIdShort => self.eventidshort,
+			| CacheKind::EventIdPdu => self.eventid_pdu,
+			| CacheKind::ShortStateKey => self.shortstatekey,
+			| CacheKind::StateKeyShort => self.statekeyshort,
+			| CacheKind::ServerNameEventData => self.servernameevent_data,
+			| CacheKind::StateInfo => self.stateinfo,
+			| CacheKind::SpaceHierarchy => self.roomid_spacehierarchy,
+			| CacheKind::SpacePagination => self.space_pagination,
+			| CacheKind::Alias => self.alias,
+			| CacheKind::RemoteDeviceKeys => self.remote_device_keys,
+		}
+	}
+}
+
+fn default_capacity(cache: CacheKind, auto: bool) -> u32 {
+	let (fixed, per_unit) = cache.default_size();
+
+	per_unit
+		.saturating_mul(scale_units(auto))
+		.saturating_add(fixed)
+}
+
+/// Units by which default capacities scale: CPU cores, or in auto mode whole
+/// GiB of memory available to the server.
+fn scale_units(auto: bool) -> u32 {
+	let units = if auto {
+		sys::memory::total_memory()
+			.map(|bytes| bytes / (1024 * 1024 * 1024))
+			.and_then(|gib| usize::try_from(gib).ok())
+			.unwrap_or_else(sys::available_parallelism)
+	} else {
+		sys::available_parallelism()
+	};
+
+	u32::try_from(units.max(1)).unwrap_or(u32::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{CacheKind, default_capacity};
+
+	#[test]
+	fn default_capacity_scales() {
+		let pdu = default_capacity(CacheKind::Pdu, false);
+		assert!(pdu >= 110_000, "{pdu}");
+
+		let stateinfo = default_capacity(CacheKind::StateInfo, false);
+		assert!(stateinfo >= 100, "{stateinfo}");
+	}
+
+	#[test]
+	fn default_capacity_fixed() {
+		assert_eq!(default_capacity(CacheKind::SpacePagination, false), 1024);
+		assert_eq!(default_capacity(CacheKind::SpacePagination, true), 1024);
+	}
+}
@@ -4,7 +4,11 @@
 use figment::Figment;
 
 use super::DEPRECATED_KEYS;
-use crate::{Config, Err, Result, Server, debug, debug_info, debug_warn, error, warn};
+use crate::{Config, Err, Result, Server, debug, debug_info, debug_warn, error, utils, warn};
+
+/// Names accepted by `rocksdb_compression_algo` and
+/// `rocksdb_compression_per_level`.
+const COMPRESSION_ALGOS: &[&str] = &["zstd", "snappy", "zlib", "bz2", "lz4", "lz4hc", "none"];
 
 /// Performs check() with additional checks specific to reloading old config
 /// with new config.
@@ -45,6 +49,35 @@ pub fn check(config: &Config) -> Result {
 		));
 	}
 
+	utils::hash::check_password_params(&config.password_params())?;
+
+	if config.password_provider.enable && config.password_provider.url.is_none() {
+		return Err!(Config(
+			"password_provider.url",
+			"The password provider cannot be enabled without a URL set"
+		));
+	}
+
+	if config.sso.enable {
+		if config.sso.issuer.is_none() {
+			return Err!(Config("sso.issuer", "SSO cannot be enabled without an issuer set"));
+		}
+
+		if config.sso.client_id.is_none() {
+			return Err!(Config(
+				"sso.client_id",
+				"SSO cannot be enabled without a client ID set"
+			));
+		}
+
+		if config.well_known.client.is_none() {
+			return Err!(Config(
+				"well_known.client",
+				"SSO cannot be enabled without the client URL the provider redirects back to"
+			));
+		}
+	}
+
 	if cfg!(all(feature = "hardened_malloc", feature = "jemalloc", not(target_env = "msvc"))) {
 		debug_warn!(
 			"hardened_malloc and jemalloc compile-time features are both enabled, this causes \
@@ -117,6 +150,70 @@ pub fn check(config: &Config) -> Result {
 		));
 	}
 
+	if config
+		.rocksdb_compaction_style
+		.as_deref()
+		.is_some_and(|style| !matches!(style, "level" | "universal"))
+	{
+		return Err!(Config(
+			"rocksdb_compaction_style",
+			"rocksdb_compaction_style must be either \"level\" or \"universal\"."
+		));
+	}
+
+	if !COMPRESSION_ALGOS.contains(&config.rocksdb_compression_algo.as_str()) {
+		return Err!(Config(
+			"rocksdb_compression_algo",
+			"Unknown compression algorithm {:?}; expected one of {COMPRESSION_ALGOS:?}.",
+			config.rocksdb_compression_algo
+		));
+	}
+
+	if let Some(algo) = config
+		.rocksdb_compression_per_level
+		.iter()
+		.find(|algo| !COMPRESSION_ALGOS.contains(&algo.as_str()))
+	{
+		return Err!(Config(
+			"rocksdb_compression_per_level",
+			"Unknown compression algorithm {algo:?}; expected one of {COMPRESSION_ALGOS:?}."
+		));
+	}
+
+	if config.database_restore && config.database_backup_path.is_none() {
+		return Err!(Config(
+			"database_restore",
+			"Restoring the database requires backups in \"database_backup_path\"."
+		));
+	}
+
+	if config.database_restore && (config.rocksdb_read_only || config.rocksdb_secondary) {
+		return Err!(Config(
+			"database_restore",
+			"The database cannot be restored in read-only or secondary mode."
+		));
+	}
+
+	if let Some(until) = config.database_restore_until.as_deref() {
+		if !config.database_restore {
+			return Err!(Config(
+				"database_restore_until",
+				"database_restore_until has no effect without database_restore."
+			));
+		}
+
+		if config.database_wal_archive_path.is_none() {
+			return Err!(Config(
+				"database_restore_until",
+				"Restoring to a point in time requires \"database_wal_archive_path\"."
+			));
+		}
+
+		if let Err(e) = utils::time::parse_rfc3339(until) {
+			return Err!(Config("database_restore_until", "{e}"));
+		}
+	}
+
 	// yeah, unless the user built a debug build hopefully for local testing only
 	if cfg!(not(debug_assertions)) && config.server_name == "your.server.name" {
 		return Err!(Config(
@@ -177,12 +274,18 @@ pub fn check(config: &Config) -> Result {
 	}
 
 	// check if user specified valid IP CIDR ranges on startup
-	for cidr in &config.ip_range_denylist {
-		if let Err(e) = ipaddress::IPAddress::parse(cidr) {
-			return Err!(Config(
-				"ip_range_denylist",
-				"Parsing specified IP CIDR range from string failed: {e}."
-			));
+	for (name, ranges) in [
+		("ip_range_denylist", &config.ip_range_denylist),
+		("federation_ip_range_allowlist", &config.federation_ip_range_allowlist),
+		("pusher_ip_range_allowlist", &config.pusher_ip_range_allowlist),
+		("url_preview_ip_range_allowlist", &config.url_preview_ip_range_allowlist),
+	] {
+		if let Some(e) = ranges
+			.iter()
+			.find_map(|cidr| ipaddress::IPAddress::parse(cidr).err())
+		{
+			error!(config = %name, "Parsing specified IP CIDR range from string failed: {e}.");
+			return Err(crate::Error::Config(name, e.into()));
 		}
 	}
 
@@ -277,6 +380,10 @@ pub fn check(config: &Config) -> Result {
 		));
 	}
 
+	if config.membership_rate_window == 0 {
+		return Err!(Config("membership_rate_window", "Must be at least one second."));
+	}
+
 	Ok(())
 }
 
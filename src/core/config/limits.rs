@@ -0,0 +1,88 @@
+//! Request limits by class of route; see `RouteLimitsConfig`.
+
+use std::time::Duration;
+
+use super::Config;
+
+/// Class of request for which limits can be configured separately.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RouteClass {
+	/// Media uploads.
+	Upload,
+
+	/// Client sync, including sliding sync.
+	Sync,
+
+	/// Federation transactions (`/send`).
+	FederationSend,
+
+	/// Everything else.
+	Other,
+}
+
+impl RouteClass {
+	#[must_use]
+	pub fn of(path: &str) -> Self {
+		if path.starts_with("/_matrix/media/") && path.contains("/upload") {
+			Self::Upload
+		} else if path.starts_with("/_matrix/client/") && path.ends_with("/sync") {
+			Self::Sync
+		} else if path.starts_with("/_matrix/federation/v1/send/") {
+			Self::FederationSend
+		} else {
+			Self::Other
+		}
+	}
+}
+
+impl Config {
+	/// Maximum time to process a request of the class.
+	#[must_use]
+	pub fn request_timeout(&self, class: RouteClass) -> Duration {
+		let limits = &self.route_limits;
+		let timeout = match class {
+			| RouteClass::Upload => limits.upload_timeout,
+			| RouteClass::Sync => limits.sync_timeout,
+			| RouteClass::FederationSend => limits.federation_send_timeout,
+			| RouteClass::Other => None,
+		};
+
+		Duration::from_secs(timeout.unwrap_or(self.client_request_timeout))
+	}
+
+	/// Maximum body size in bytes of a request of the class.
+	#[must_use]
+	pub fn request_size(&self, class: RouteClass) -> usize {
+		let limits = &self.route_limits;
+		let size = match class {
+			| RouteClass::Upload => limits.upload_max_request_size,
+			| RouteClass::Sync => limits.sync_max_request_size,
+			| RouteClass::FederationSend => limits.federation_send_max_request_size,
+			| RouteClass::Other => None,
+		};
+
+		size.unwrap_or(self.max_request_size)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::RouteClass;
+
+	#[test]
+	fn route_class_of_path() {
+		let cases = [
+			("/_matrix/media/v3/upload", RouteClass::Upload),
+			("/_matrix/media/v3/upload/example.com/abc", RouteClass::Upload),
+			("/_matrix/client/v3/sync", RouteClass::Sync),
+			("/_matrix/client/unstable/org.matrix.simplified_msc3575/sync", RouteClass::Sync),
+			("/_matrix/federation/v1/send/1234", RouteClass::FederationSend),
+			("/_matrix/federation/v1/send_join/!a:b/$c", RouteClass::Other),
+			("/_matrix/client/v3/rooms/!a:b/send/m.room.message/1", RouteClass::Other),
+		];
+
+		for (path, class) in cases {
+			assert_eq!(RouteClass::of(path), class, "{path}");
+		}
+	}
+}
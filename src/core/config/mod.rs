@@ -1,4 +1,6 @@
+pub mod caches;
 pub mod check;
+pub mod limits;
 pub mod manager;
 pub mod proxy;
 
@@ -24,8 +26,12 @@
 use url::Url;
 
 use self::proxy::ProxyConfig;
-pub use self::{check::check, manager::Manager};
-use crate::{Result, err, error::Error, utils::sys};
+pub use self::{caches::CacheKind, check::check, limits::RouteClass, manager::Manager};
+use crate::{
+	Result, err,
+	error::Error,
+	utils::{hash::PasswordParams, sys},
+};
 
 /// All the config options for tuwunel.
 #[allow(clippy::struct_excessive_bools)]
@@ -52,7 +58,8 @@
 ### For more information, see:
 ### https://tuwunel.chat/configuration.html
 "#,
-	ignore = "catchall well_known tls blurhashing allow_invalid_tls_certificates ldap"
+	ignore = "catchall well_known tls blurhashing allow_invalid_tls_certificates ldap auto_join \
+	          password_provider sso database_restore database_restore_until"
 )]
 pub struct Config {
 	/// The server_name is the pretty name of this server. It is used as a
@@ -147,6 +154,46 @@ pub struct Config {
 	#[serde(default = "default_database_backups_to_keep")]
 	pub database_backups_to_keep: i16,
 
+	/// Directory to which the database write-ahead log is continuously
+	/// archived, enabling point-in-time recovery. Together with the backups
+	/// in "database_backup_path" the database can then be restored to any
+	/// moment since the latest backup rather than only to the backup itself.
+	///
+	/// Each backup deletes the archived writes older than the oldest backup
+	/// kept, so "database_backups_to_keep" also bounds the archive.
+	///
+	/// An object store may be used by mounting it as a directory. Take a new
+	/// backup after enabling this so the archive covers every write since.
+	///
+	/// example: "/opt/tuwunel-wal-archive"
+	pub database_wal_archive_path: Option<PathBuf>,
+
+	/// Seconds between appending new writes to the WAL archive. Writes are
+	/// timestamped when archived, so a point-in-time restore is only accurate
+	/// to this interval.
+	///
+	/// default: 10
+	#[serde(default = "default_database_wal_archive_interval")]
+	pub database_wal_archive_interval: u64,
+
+	/// Replace the database with the latest backup in "database_backup_path"
+	/// during startup, then replay the WAL archive when
+	/// "database_wal_archive_path" is configured. This is only set by the
+	/// `--restore` command line argument, which also starts the server in
+	/// maintenance mode; the configuration file cannot set it, so a restore
+	/// happens once rather than on every startup.
+	///
+	/// After restoring to an earlier point, move the existing WAL archive
+	/// aside and take a new backup to resume archiving.
+	#[serde(default)]
+	pub database_restore: bool,
+
+	/// Stop replaying the WAL archive at this RFC 3339 timestamp when
+	/// restoring. Only set by the `--restore-until` command line argument.
+	///
+	/// example: "2025-01-01T12:00:00Z"
+	pub database_restore_until: Option<String>,
+
 	/// Text which will be added to the end of the user's displayname upon
 	/// registration with a space before the text. In Conduit, this was the
 	/// lightning bolt emoji.
@@ -157,16 +204,28 @@ pub struct Config {
 	#[serde(default = "default_new_user_displayname_suffix")]
 	pub new_user_displayname_suffix: String,
 
+	/// Markdown message the server user sends in a direct message to every
+	/// newly registered local account. Guests and appservice users are
+	/// excluded. Unset to disable.
+	///
+	/// The placeholders `{localpart}`, `{user_id}` and `{server_name}` are
+	/// replaced with the new user's values.
+	///
+	/// example: "Welcome to {server_name}, {localpart}! Reply here if you need
+	/// help."
+	pub welcome_message: Option<String>,
+
 	/// Set this to any float value to multiply tuwunel's in-memory LRU caches
-	/// with such as "auth_chain_cache_capacity".
+	/// with such as "auth_chain" in `[global.caches]`.
 	///
 	/// May be useful if you have significant memory to spare to increase
 	/// performance.
 	///
 	/// If you have low memory, reducing this may be viable.
 	///
-	/// By default, the individual caches such as "auth_chain_cache_capacity"
-	/// are scaled by your CPU core count.
+	/// By default, the individual caches such as "auth_chain" are scaled by
+	/// your CPU core count, or by system memory with `auto` in
+	/// `[global.caches]`.
 	///
 	/// default: 1.0
 	#[serde(
@@ -207,45 +266,87 @@ pub struct Config {
 	#[serde(default = "default_db_write_buffer_capacity_mb")]
 	pub db_write_buffer_capacity_mb: f64,
 
-	/// default: varies by system
-	#[serde(default = "default_pdu_cache_capacity")]
-	pub pdu_cache_capacity: u32,
+	/// Deprecated; use `pdu` in `[global.caches]` instead, which takes
+	/// precedence.
+	pub pdu_cache_capacity: Option<u32>,
 
-	/// default: varies by system
-	#[serde(default = "default_auth_chain_cache_capacity")]
-	pub auth_chain_cache_capacity: u32,
+	/// Deprecated; use `auth_chain` in `[global.caches]` instead, which takes
+	/// precedence.
+	pub auth_chain_cache_capacity: Option<u32>,
 
-	/// default: varies by system
-	#[serde(default = "default_shorteventid_cache_capacity")]
-	pub shorteventid_cache_capacity: u32,
+	/// Deprecated; use `shorteventid` in `[global.caches]` instead, which takes
+	/// precedence.
+	pub shorteventid_cache_capacity: Option<u32>,
 
-	/// default: varies by system
-	#[serde(default = "default_eventidshort_cache_capacity")]
-	pub eventidshort_cache_capacity: u32,
+	/// Deprecated; use `eventidshort` in `[global.caches]` instead, which takes
+	/// precedence.
+	pub eventidshort_cache_capacity: Option<u32>,
 
-	/// default: varies by system
-	#[serde(default = "default_eventid_pdu_cache_capacity")]
-	pub eventid_pdu_cache_capacity: u32,
+	/// Deprecated; use `eventid_pdu` in `[global.caches]` instead, which takes
+	/// precedence.
+	pub eventid_pdu_cache_capacity: Option<u32>,
 
-	/// default: varies by system
-	#[serde(default = "default_shortstatekey_cache_capacity")]
-	pub shortstatekey_cache_capacity: u32,
+	/// Deprecated; use `shortstatekey` in `[global.caches]` instead, which
+	/// takes precedence.
+	pub shortstatekey_cache_capacity: Option<u32>,
 
-	/// default: varies by system
-	#[serde(default = "default_statekeyshort_cache_capacity")]
-	pub statekeyshort_cache_capacity: u32,
+	/// Deprecated; use `statekeyshort` in `[global.caches]` instead, which
+	/// takes precedence.
+	pub statekeyshort_cache_capacity: Option<u32>,
 
-	/// default: varies by system
-	#[serde(default = "default_servernameevent_data_cache_capacity")]
-	pub servernameevent_data_cache_capacity: u32,
+	/// Deprecated; use `servernameevent_data` in `[global.caches]` instead,
+	/// which takes precedence.
+	pub servernameevent_data_cache_capacity: Option<u32>,
 
-	/// default: varies by system
-	#[serde(default = "default_stateinfo_cache_capacity")]
-	pub stateinfo_cache_capacity: u32,
+	/// Deprecated; use `stateinfo` in `[global.caches]` instead, which takes
+	/// precedence.
+	pub stateinfo_cache_capacity: Option<u32>,
 
-	/// default: varies by system
-	#[serde(default = "default_roomid_spacehierarchy_cache_capacity")]
-	pub roomid_spacehierarchy_cache_capacity: u32,
+	/// Deprecated; use `roomid_spacehierarchy` in `[global.caches]` instead,
+	/// which takes precedence.
+	pub roomid_spacehierarchy_cache_capacity: Option<u32>,
+
+	/// Seconds to retain space hierarchy summaries fetched from remote
+	/// servers before asking them again.
+	///
+	/// default: 600
+	#[serde(default = "default_hierarchy_remote_cache_ttl")]
+	pub hierarchy_remote_cache_ttl: u64,
+
+	/// Maximum number of federation requests a single client `/hierarchy`
+	/// request may cause. Remote rooms beyond the budget are omitted from the
+	/// response.
+	///
+	/// default: 32
+	#[serde(default = "default_hierarchy_max_remote_requests")]
+	pub hierarchy_max_remote_requests: usize,
+
+	/// Deprecated; use `alias` in `[global.caches]` instead, which takes
+	/// precedence.
+	pub alias_cache_capacity: Option<u32>,
+
+	/// Seconds to remember the room ID a remote room alias resolved to. Set
+	/// to 0 to always ask the remote server.
+	///
+	/// default: 300
+	#[serde(default = "default_alias_cache_ttl")]
+	pub alias_cache_ttl: u64,
+
+	/// Seconds to remember that a remote room alias could not be resolved.
+	/// Set to 0 to disable negative caching.
+	///
+	/// default: 60
+	#[serde(default = "default_alias_cache_negative_ttl")]
+	pub alias_cache_negative_ttl: u64,
+
+	/// Seconds to answer `/keys/query` for remote users from their cached
+	/// device and cross-signing keys before asking their server again. The
+	/// cache of a user is dropped early when their server sends a device
+	/// list or signing key update. Set to 0 to always ask the remote server.
+	///
+	/// default: 300
+	#[serde(default = "default_remote_device_keys_max_staleness")]
+	pub remote_device_keys_max_staleness: u64,
 
 	/// Maximum entries stored in DNS memory-cache. The size of an entry may
 	/// vary so please take care if raising this value excessively. Only
@@ -278,6 +379,20 @@ pub struct Config {
 	#[serde(default = "default_dns_min_ttl_nxdomain")]
 	pub dns_min_ttl_nxdomain: u64,
 
+	/// Time-to-live in seconds for resolved federation destinations (server
+	/// name to delegated host and port, via well-known and SRV). When unset,
+	/// entries expire at a random time between 18 and 36 hours to spread out
+	/// re-resolution. Pinned destinations never expire.
+	///
+	/// The cache can be inspected with `query resolver destinations-cache`
+	/// and managed with the `federation resolver-cache` admin commands.
+	pub resolver_destination_ttl: Option<u64>,
+
+	/// Time-to-live in seconds for resolved federation hostname overrides
+	/// (delegated hostname to IP addresses). When unset, entries expire at a
+	/// random time between 6 and 12 hours.
+	pub resolver_override_ttl: Option<u64>,
+
 	/// Number of DNS nameserver retries after a timeout or error.
 	///
 	/// default: 10
@@ -339,7 +454,8 @@ pub struct Config {
 	#[serde(default = "default_ip_lookup_strategy")]
 	pub ip_lookup_strategy: u8,
 
-	/// Max request size for file uploads in bytes. Defaults to 20MB.
+	/// Max request size for file uploads in bytes. Defaults to 20MB. This is
+	/// the limit for all requests unless overridden in `[global.route_limits]`.
 	///
 	/// default: 20971520
 	#[serde(default = "default_max_request_size")]
@@ -349,6 +465,14 @@ pub struct Config {
 	#[serde(default = "default_max_fetch_prev_events")]
 	pub max_fetch_prev_events: u16,
 
+	/// Number of PDUs in an incoming federation transaction whose signatures
+	/// are verified at once on the blocking pool. Verification runs ahead of
+	/// handling, which stays in order within each room.
+	///
+	/// default: varies by system
+	#[serde(default = "sys::available_parallelism")]
+	pub federation_verify_parallelism: usize,
+
 	/// Default/base connection timeout (seconds). This is used only by URL
 	/// previews and update/news endpoint checks.
 	///
@@ -440,6 +564,62 @@ pub struct Config {
 	#[serde(default = "default_sender_retry_backoff_limit")]
 	pub sender_retry_backoff_limit: u64,
 
+	/// Maximum number of queued events (PDUs, and EDUs queued for delivery)
+	/// sent in one outgoing transaction. Federation does not allow more than
+	/// 50 PDUs in a transaction; larger values are reduced to that.
+	///
+	/// default: 48
+	#[serde(default = "default_sender_transaction_pdu_limit")]
+	pub sender_transaction_pdu_limit: usize,
+
+	/// Maximum number of EDUs such as device list updates gathered into one
+	/// outgoing federation transaction. Federation does not allow more than
+	/// 100 EDUs in a transaction; larger values are reduced to that less the
+	/// room kept for receipts and presence.
+	///
+	/// default: 98
+	#[serde(default = "default_sender_transaction_edu_limit")]
+	pub sender_transaction_edu_limit: usize,
+
+	/// Maximum number of outgoing transactions in flight at once for each
+	/// sender worker (see `sender_workers`). Each destination never has more
+	/// than one transaction in flight; this bounds how many destinations are
+	/// sent to concurrently, which can help small servers catching up with
+	/// many peers. Zero means no limit.
+	///
+	/// default: 0
+	#[serde(default)]
+	pub sender_max_inflight_transactions: usize,
+
+	/// Minimum time between consecutive outgoing transactions to the same
+	/// destination (milliseconds). When a destination has a backlog, each
+	/// following transaction waits this long after the previous one is
+	/// acknowledged, pacing catch-up for both sides. Zero sends them
+	/// immediately.
+	///
+	/// default: 0
+	#[serde(default)]
+	pub sender_transaction_interval_ms: u64,
+
+	/// Catch up with destinations after downtime by sending them the latest
+	/// queued event of each room first. This applies when resuming after this
+	/// server restarts and when retrying a destination which was unreachable.
+	/// Remote servers fetch any events they are missing in those rooms; the
+	/// rest of the backlog is still sent afterwards, in order. Destinations
+	/// catching up continue to do so after a restart. Disable to always send
+	/// the backlog strictly in order.
+	#[serde(default = "true_fn")]
+	pub sender_catchup: bool,
+
+	/// Interval in seconds at which the per-server federation statistics
+	/// shown by `!admin federation stats` are written to the database, so
+	/// they are kept across restarts. They are also written at shutdown. Set
+	/// to 0 to only keep them in memory.
+	///
+	/// default: 300
+	#[serde(default = "default_federation_stats_interval")]
+	pub federation_stats_interval: u64,
+
 	/// Appservice URL request connection timeout. Defaults to 35 seconds as
 	/// generally appservices are hosted within the same network.
 	///
@@ -453,6 +633,13 @@ pub struct Config {
 	#[serde(default = "default_appservice_idle_timeout")]
 	pub appservice_idle_timeout: u64,
 
+	/// Ask appservices for the one-time keys and device keys of users in their
+	/// exclusive namespaces, so encrypting bridges need not upload keys for
+	/// every device they masquerade as (MSC3983 and MSC3984). Keys uploaded to
+	/// the server are used for devices the appservice doesn't return.
+	#[serde(default)]
+	pub appservice_key_proxy: bool,
+
 	/// Notification gateway pusher idle connection pool timeout.
 	///
 	/// default: 15
@@ -466,6 +653,7 @@ pub struct Config {
 	pub client_receive_timeout: u64,
 
 	/// Maximum time to process a request received from a client (seconds).
+	/// This can be overridden for some requests in `[global.route_limits]`.
 	///
 	/// default: 180
 	#[serde(default = "default_client_request_timeout")]
@@ -489,6 +677,57 @@ pub struct Config {
 	#[serde(default = "default_sender_shutdown_timeout")]
 	pub sender_shutdown_timeout: u64,
 
+	/// Maximum time for services to stop on shutdown (seconds), after client
+	/// connections have drained. This includes the federation sender finishing
+	/// its transactions. Service workers still running afterwards are aborted
+	/// and the database is closed.
+	///
+	/// default: 30
+	#[serde(default = "default_shutdown_drain_timeout")]
+	pub shutdown_drain_timeout: u64,
+
+	/// Number of tokio runtime worker threads. The `--worker-threads` argument
+	/// or `TOKIO_WORKER_THREADS` environment variable apply when this is not
+	/// set in the config file. Runtime settings take effect at startup.
+	///
+	/// default: varies by system
+	#[serde(default = "sys::available_parallelism")]
+	pub runtime_worker_threads: usize,
+
+	/// Maximum number of threads in the runtime's pool for blocking
+	/// operations.
+	///
+	/// default: 1024
+	#[serde(default = "default_runtime_max_blocking_threads")]
+	pub runtime_max_blocking_threads: usize,
+
+	/// Pin each runtime worker thread to its own core. The
+	/// `--worker-affinity` argument applies when this is not set in the config
+	/// file.
+	#[serde(default = "true_fn")]
+	pub runtime_worker_affinity: bool,
+
+	/// Nice level (-20 to 19) for runtime threads, lowering or raising their
+	/// scheduling priority relative to other processes on the host. Raising
+	/// priority requires privileges. Linux only; unchanged when unset.
+	///
+	/// example: 5
+	pub runtime_worker_nice: Option<i32>,
+
+	/// Best-effort IO priority level (0 highest to 7 lowest) for runtime
+	/// threads, as with `ionice -c2`. Linux only; unchanged when unset.
+	///
+	/// example: 4
+	pub runtime_worker_io_priority: Option<u8>,
+
+	/// Warn when a room's state lock has been held for longer than this many
+	/// seconds, which usually indicates a stuck or deadlocked request. The
+	/// check runs periodically; set to 0 to disable it.
+	///
+	/// default: 120
+	#[serde(default = "default_room_lock_watchdog_timeout")]
+	pub room_lock_watchdog_timeout: u64,
+
 	/// Enables registration. If set to false, no users can register on this
 	/// server.
 	///
@@ -528,6 +767,58 @@ pub struct Config {
 	/// example: "/etc/tuwunel/.reg_token"
 	pub registration_token_file: Option<PathBuf>,
 
+	/// Bearer token for the SCIM 2.0 provisioning API served under
+	/// `/_tuwunel/scim/v2`, through which an identity provider can create,
+	/// update and deactivate local users and manage the members of spaces
+	/// (SCIM Groups). The API is disabled while this is unset.
+	///
+	/// example: "Zq3x8VtLr0cE5nYw1KbH"
+	///
+	/// display: sensitive
+	pub scim_token: Option<String>,
+
+	/// Failed registration attempts allowed from each subnet within
+	/// `registration_throttle_window` before the subnet is locked out for
+	/// `registration_throttle_lockout`. Attempts failing user-interactive
+	/// authentication count. Subnets are of the address of the connecting
+	/// peer, never of forwarding headers a client could forge; behind a
+	/// reverse proxy every client shares the proxy's address, so only enable
+	/// this when clients connect directly. Attempts are stored in the
+	/// database so restarting does not reset them. Appservices are exempt. Set
+	/// to 0 to disable.
+	///
+	/// default: 0
+	#[serde(default)]
+	pub registration_throttle_attempts: u32,
+
+	/// Window over which failed registration attempts are counted (seconds).
+	///
+	/// default: 3600
+	#[serde(default = "default_registration_throttle_window")]
+	pub registration_throttle_window: u64,
+
+	/// How long a subnet that reached `registration_throttle_attempts` may not
+	/// register (seconds). Locked out subnets can be listed and cleared with
+	/// the `users registration-throttle` admin commands.
+	///
+	/// default: 86400
+	#[serde(default = "default_registration_throttle_lockout")]
+	pub registration_throttle_lockout: u64,
+
+	/// Prefix length of the IPv4 subnets registration attempts are counted
+	/// by.
+	///
+	/// default: 24
+	#[serde(default = "default_registration_throttle_ipv4_prefix")]
+	pub registration_throttle_ipv4_prefix: u8,
+
+	/// Prefix length of the IPv6 subnets registration attempts are counted
+	/// by.
+	///
+	/// default: 64
+	#[serde(default = "default_registration_throttle_ipv6_prefix")]
+	pub registration_throttle_ipv6_prefix: u8,
+
 	/// Controls whether encrypted rooms and events are allowed.
 	#[serde(default = "true_fn")]
 	pub allow_encryption: bool,
@@ -550,6 +841,16 @@ pub struct Config {
 	#[serde(default)]
 	pub forget_forced_upon_leave: bool,
 
+	/// Additional capabilities advertised in `/capabilities`, e.g. for
+	/// MSC-specific features clients look for. Keys here override the
+	/// built-in ones.
+	///
+	/// example: { "org.example.feature" = { enabled = true } }
+	///
+	/// default: {}
+	#[serde(default)]
+	pub capabilities_extra: serde_json::Map<String, serde_json::Value>,
+
 	/// Set this to true to require authentication on the normally
 	/// unauthenticated profile retrieval endpoints (GET)
 	/// "/_matrix/client/v3/profile/{userId}".
@@ -566,6 +867,18 @@ pub struct Config {
 	#[serde(default)]
 	pub allow_public_room_directory_over_federation: bool,
 
+	/// Restricts which remote servers may query your server's public room
+	/// directory over federation when
+	/// `allow_public_room_directory_over_federation` is enabled. If this list
+	/// is empty, any server may. Matched against the requesting server's
+	/// name.
+	///
+	/// example: ["^partner\.example$", "\.trusted\.example$"]
+	///
+	/// default: []
+	#[serde(default, with = "serde_regex")]
+	pub public_room_directory_federation_servers: RegexSet,
+
 	/// Set this to true to allow your server's public room directory to be
 	/// queried without client authentication (access token) through the Client
 	/// APIs. Set this to false to protect against /publicRooms spiders.
@@ -618,6 +931,22 @@ pub struct Config {
 	#[serde(default = "true_fn")]
 	pub allow_room_creation: bool,
 
+	/// Named presets for room creation. Clients select one by setting
+	/// `chat.tuwunel.template` in the `creation_content` of a createRoom
+	/// request; admins can use `!admin rooms create-from-template`.
+	///
+	/// Each template may set `encrypted`, a partial `power_levels` content
+	/// applied before any client override, and `initial_state` events which
+	/// are sent before the client's own initial state.
+	///
+	/// example: { announcements = { encrypted = false, power_levels = {
+	/// events_default = 50 }, initial_state = [{ type = "m.room.topic",
+	/// content = { topic = "Read only" } }] } }
+	///
+	/// default: {}
+	#[serde(default)]
+	pub room_templates: BTreeMap<String, RoomTemplate>,
+
 	/// Set to false to disable users from joining or creating room versions
 	/// that aren't officially supported by tuwunel.
 	///
@@ -685,6 +1014,19 @@ pub struct Config {
 	///       include = ["*.onion", "matrix.myspecial.onion"]
 	///       exclude = ["*.myspecial.onion"]
 	///
+	/// - Proxies may require basic-auth credentials, set with `username` and
+	///   `password` alongside `url` in either form.
+	///
+	/// - Entries in `by_domain` may also override certificate verification of
+	///   the destinations they match, with or without a `url`. `ca_file` is a
+	///   PEM file of CA certificates trusted instead of the system roots, and
+	///   `insecure = true` disables verification entirely; the latter only
+	///   applies when `include` is given explicitly:
+	///
+	///       [[global.proxy.by_domain]]
+	///       include = ["*.internal.example"]
+	///       ca_file = "/etc/ssl/internal-ca.pem"
+	///
 	/// Include vs. Exclude:
 	///
 	/// - If include is an empty list, it is assumed to be `["*"]`.
@@ -796,6 +1138,14 @@ pub struct Config {
 	#[serde(default = "default_openid_token_ttl")]
 	pub openid_token_ttl: u64,
 
+	/// Only issue OpenID tokens bound to an audience. Clients name the server
+	/// name or client ID of the service the token is for in the `audience`
+	/// field of the request. The service must then pass the same `audience`
+	/// query parameter when it looks the token up, so a token given to one
+	/// service cannot be replayed against another.
+	#[serde(default)]
+	pub openid_token_require_audience: bool,
+
 	/// Allow an existing session to mint a login token for another client.
 	/// This requires interactive authentication, but has security ramifications
 	/// as a malicious client could use the mechanism to spawn more than one
@@ -814,6 +1164,134 @@ pub struct Config {
 	#[serde(default = "default_login_token_ttl")]
 	pub login_token_ttl: u64,
 
+	/// Period in seconds after which local accounts expire unless renewed.
+	/// Requests from expired accounts are refused with `M_USER_LOCKED` until
+	/// they are renewed, by an admin with `users renew-account` or by the
+	/// user through their renewal link. Accounts existing before this is
+	/// enabled begin their period on their next request. Disabled when unset.
+	///
+	/// example: 31536000
+	pub account_validity_period: Option<u64>,
+
+	/// Number of seconds before an account expires to send its user a link
+	/// renewing it, in a direct message from the server user. The link is
+	/// served under `well_known.client`, which must be set. Set to 0 to send
+	/// no renewal links.
+	///
+	/// default: 604800
+	#[serde(default = "default_account_validity_renewal_notice")]
+	pub account_validity_renewal_notice: u64,
+
+	/// Number of seconds a sync filter may go unused before it is deleted.
+	/// Clients usually re-upload their filter when it is not found. Set to 0
+	/// to keep filters forever.
+	///
+	/// default: 7776000
+	#[serde(default = "default_filter_max_age")]
+	pub filter_max_age: u64,
+
+	/// Number of seconds a notification is kept in the history served by
+	/// `GET /notifications` before it is deleted. Set to 0 to keep
+	/// notifications forever.
+	///
+	/// default: 2592000
+	#[serde(default = "default_notification_max_age")]
+	pub notification_max_age: u64,
+
+	/// Number of seconds the original content of redacted events is kept
+	/// for review by server admins (see `debug get-pdu`) before it is deleted.
+	/// Clients only ever receive the redacted form. Set to 0 to delete the
+	/// content immediately upon redaction.
+	///
+	/// default: 0
+	#[serde(default)]
+	pub redaction_retention_period: u64,
+
+	/// Maximum lifetime in seconds of self-destructing events, which carry
+	/// an `org.matrix.self_destruct_after` timestamp (milliseconds since the
+	/// epoch) in their content, as in MSC2228. These are only honored in
+	/// rooms which opt in with an `im.tuwunel.self_destruct` state event
+	/// whose content is `{"enabled": true}`. Expired events are redacted on
+	/// this server and omitted from new syncs. Expiry times further out are
+	/// capped at this many seconds after the event was sent. Set to 0 to
+	/// ignore self-destructing events.
+	///
+	/// default: 2592000
+	#[serde(default = "default_max_event_ttl")]
+	pub max_event_ttl: u64,
+
+	/// Longest delay in seconds clients may schedule events to be sent after
+	/// (MSC4140). MatrixRTC clients rely on delayed events to end calls left
+	/// by disconnected members. Set to 0 to disable delayed events.
+	///
+	/// default: 86400
+	#[serde(default = "default_max_event_delay")]
+	pub max_event_delay: u64,
+
+	/// Maximum number of delayed events each user may have pending.
+	///
+	/// default: 100
+	#[serde(default = "default_max_delayed_events_per_user")]
+	pub max_delayed_events_per_user: usize,
+
+	/// Enables TOTP two-factor authentication. Users may then enroll an
+	/// authenticator app, after which password logins and password changes
+	/// also require a code from it or one of their recovery codes. Their
+	/// TOTP secrets are encrypted in the database with a key derived from
+	/// this value; changing it invalidates every enrollment.
+	///
+	/// Logging in with a second factor uses user-interactive authentication
+	/// on `/login`, which is an extension only some clients support.
+	///
+	/// display: sensitive
+	pub totp_encryption_key: Option<String>,
+
+	/// Origin of the web client through which users register and log in with
+	/// passkeys (WebAuthn). Its host is the relying party ID, so passkeys
+	/// are bound to it and its subdomains. Setting this enables passkeys;
+	/// it requires tuwunel built with the `passkey` feature.
+	///
+	/// example: "https://app.example.com"
+	pub passkey_origin: Option<Url>,
+
+	/// Argon2id memory cost in KiB for new password hashes. Together with
+	/// the time cost and parallelism below, the default follows the OWASP
+	/// recommendation. Raising these makes each login slower and more
+	/// memory-hungry. Existing hashes with other parameters are rehashed when
+	/// their user next logs in with a password.
+	///
+	/// default: 19456
+	#[serde(default = "default_password_hash_memory_cost")]
+	pub password_hash_memory_cost: u32,
+
+	/// Argon2id iteration count for new password hashes.
+	///
+	/// default: 2
+	#[serde(default = "default_password_hash_time_cost")]
+	pub password_hash_time_cost: u32,
+
+	/// Argon2id degree of parallelism for new password hashes.
+	///
+	/// default: 1
+	#[serde(default = "default_password_hash_parallelism")]
+	pub password_hash_parallelism: u32,
+
+	/// The `password_config.pepper` of a Synapse server whose users were
+	/// imported. Imported bcrypt hashes are verified with it appended to the
+	/// password. Imported bcrypt and PBKDF2 hashes are replaced with Argon2id
+	/// on the user's next successful password login.
+	///
+	/// display: sensitive
+	pub synapse_password_pepper: Option<String>,
+
+	/// Number of server-side room key backup versions to keep per user. When
+	/// a client creates a new backup version, the oldest versions beyond this
+	/// count are deleted with their keys. The new version is always kept.
+	/// All versions are kept when unset.
+	///
+	/// example: 2
+	pub key_backup_versions_retained: Option<usize>,
+
 	/// Static TURN username to provide the client if not using a shared secret
 	/// ("turn_secret"), It is recommended to use a shared secret over static
 	/// credentials.
@@ -878,6 +1356,18 @@ pub struct Config {
 	#[serde(default = "Vec::new")]
 	pub auto_join_rooms: Vec<OwnedRoomOrAliasId>,
 
+	// external structure; separate section
+	#[serde(default)]
+	pub auto_join: AutoJoinConfig,
+
+	// external structure; separate section
+	#[serde(default)]
+	pub route_limits: RouteLimitsConfig,
+
+	// external structure; separate section
+	#[serde(default)]
+	pub caches: CachesConfig,
+
 	/// Config option to automatically deactivate the account of any user who
 	/// attempts to join a:
 	/// - banned room
@@ -1152,6 +1642,57 @@ pub struct Config {
 	#[serde(default = "default_rocksdb_stats_level")]
 	pub rocksdb_stats_level: u8,
 
+	/// RocksDB tuning profile selecting defaults for the database caches,
+	/// write buffers, background jobs and WAL limits:
+	///
+	/// "low-memory" shrinks caches and buffers for small servers.
+	/// "balanced" suits most servers.
+	/// "throughput" trades memory for fewer stalls on busy servers.
+	///
+	/// The options below override individual values of the profile. The
+	/// values in effect are logged at startup.
+	///
+	/// default: "balanced"
+	#[serde(default)]
+	pub db_profile: DbProfile,
+
+	/// Size of the RocksDB block cache in megabytes, overriding the share of
+	/// `db_cache_capacity_mb` the profile gives it.
+	///
+	/// example: 512.0
+	pub db_block_cache_mb: Option<f64>,
+
+	/// Compaction style for all columns which are not size-limited: "level"
+	/// or "universal". By default each column uses the style suited to its
+	/// data.
+	///
+	/// example: "universal"
+	pub rocksdb_compaction_style: Option<String>,
+
+	/// Compression algorithm for each level of the database, from the top;
+	/// the last entry also applies to any deeper level. Accepts the same
+	/// names as `rocksdb_compression_algo`. By default the upper levels are
+	/// left uncompressed and the rest use `rocksdb_compression_algo`.
+	///
+	/// example: ["none", "none", "lz4", "zstd"]
+	///
+	/// default: []
+	#[serde(default)]
+	pub rocksdb_compression_per_level: Vec<String>,
+
+	/// Total size in megabytes of live write-ahead logs, after which column
+	/// families are flushed to free the oldest of them. Overrides the
+	/// profile.
+	///
+	/// example: 512
+	pub rocksdb_max_total_wal_size_mb: Option<u64>,
+
+	/// Total size in megabytes of archived write-ahead logs to keep.
+	/// Overrides the profile.
+	///
+	/// example: 1024
+	pub rocksdb_wal_size_limit_mb: Option<u64>,
+
 	/// This is a password that can be configured that will let you login to the
 	/// server bot account (currently `@conduit`) for emergency troubleshooting
 	/// purposes such as recovering/recreating your admin room, or inviting
@@ -1167,6 +1708,22 @@ pub struct Config {
 	/// display: sensitive
 	pub emergency_password: Option<String>,
 
+	/// Invalidate the emergency password after it has been used to log in
+	/// once. Setting a different emergency_password makes it usable again.
+	#[serde(default)]
+	pub emergency_password_single_use: bool,
+
+	/// Number of seconds after startup the emergency password remains valid
+	/// for. Logins with it are rejected afterwards until the server restarts.
+	///
+	/// example: 3600
+	pub emergency_password_lifetime: Option<u64>,
+
+	/// Send a notice to the admin room whenever the emergency password is
+	/// used to log in.
+	#[serde(default = "true_fn")]
+	pub emergency_password_notice: bool,
+
 	/// default: "/_matrix/push/v1/notify"
 	#[serde(default = "default_notification_push_path")]
 	pub notification_push_path: String,
@@ -1195,6 +1752,16 @@ pub struct Config {
 	#[serde(default = "true_fn")]
 	pub allow_outgoing_presence: bool,
 
+	/// List of server names via regex patterns that presence updates may be
+	/// sent to. If this list is empty, presence is sent to every server
+	/// sharing a room with the user, subject to `allow_outgoing_presence`.
+	///
+	/// example: ["^matrix\.example\.com$"]
+	///
+	/// default: []
+	#[serde(default, with = "serde_regex")]
+	pub outgoing_presence_servers: RegexSet,
+
 	/// How many seconds without presence updates before you become idle.
 	/// Defaults to 5 minutes.
 	///
@@ -1226,10 +1793,30 @@ pub struct Config {
 	#[serde(default = "true_fn")]
 	pub allow_outgoing_read_receipts: bool,
 
+	/// List of server names via regex patterns that read receipts may be sent
+	/// to. If this list is empty, read receipts are sent to every server in
+	/// the room, subject to `allow_outgoing_read_receipts`.
+	///
+	/// example: ["^matrix\.example\.com$"]
+	///
+	/// default: []
+	#[serde(default, with = "serde_regex")]
+	pub outgoing_read_receipts_servers: RegexSet,
+
 	/// Allow outgoing typing updates to federation.
 	#[serde(default = "true_fn")]
 	pub allow_outgoing_typing: bool,
 
+	/// List of server names via regex patterns that typing updates may be
+	/// sent to. If this list is empty, typing updates are sent to every
+	/// server in the room, subject to `allow_outgoing_typing`.
+	///
+	/// example: ["^matrix\.example\.com$"]
+	///
+	/// default: []
+	#[serde(default, with = "serde_regex")]
+	pub outgoing_typing_servers: RegexSet,
+
 	/// Allow incoming typing updates from federation.
 	#[serde(default = "true_fn")]
 	pub allow_incoming_typing: bool,
@@ -1299,6 +1886,15 @@ pub struct Config {
 	#[serde(default)]
 	pub allow_guests_auto_join_rooms: bool,
 
+	/// Automatically join local users to the replacement room when a room
+	/// they are in is upgraded, and tag the old room as low priority. Users
+	/// can override this with a `chat.tuwunel.follow_room_upgrades` global
+	/// account data event whose content is `{"enabled": true|false}`.
+	///
+	/// Requires the server to be able to join the replacement room.
+	#[serde(default)]
+	pub follow_room_upgrades: bool,
+
 	/// Enable the legacy unauthenticated Matrix media repository endpoints.
 	/// These endpoints consist of:
 	/// - /_matrix/media/*/config
@@ -1313,6 +1909,19 @@ pub struct Config {
 	#[serde(default)]
 	pub allow_legacy_media: bool,
 
+	/// Serve server metrics in the Prometheus text format at
+	/// `/_tuwunel/metrics`. The endpoint is unauthenticated, so restrict
+	/// access to it at your reverse proxy.
+	///
+	/// Defaults to false.
+	#[serde(default)]
+	pub allow_metrics_endpoint: bool,
+
+	/// Freeze the legacy unauthenticated media endpoints when they are
+	/// enabled by `allow_legacy_media`. Only media stored before the freeze is
+	/// served by them; newer media, including remote media not yet cached,
+	/// must be requested from the authenticated endpoints. The freeze takes
+	/// effect the first time the server starts with legacy media enabled.
 	#[serde(default = "true_fn")]
 	pub freeze_legacy_media: bool,
 
@@ -1353,6 +1962,22 @@ pub struct Config {
 	#[serde(default)]
 	pub prune_missing_media: bool,
 
+	/// Thumbnail sizes the server generates and caches, as `WIDTHxHEIGHT`
+	/// followed by the method `crop` or `scale`. A requested thumbnail is
+	/// served at the smallest of these sizes containing it; requests larger
+	/// than all of them are served the original media.
+	///
+	/// Defaults to:
+	/// ["32x32 crop", "96x96 crop", "320x240 scale", "640x480 scale",
+	/// "800x600 scale"]
+	#[serde(default = "default_thumbnail_sizes")]
+	pub thumbnail_sizes: Vec<String>,
+
+	/// Generate all `thumbnail_sizes` of images uploaded by local users in the
+	/// background after the upload completes, rather than on first view.
+	#[serde(default = "true_fn")]
+	pub thumbnail_pregenerate: bool,
+
 	/// Vector list of regex patterns of server names that tuwunel will refuse
 	/// to download remote media from.
 	///
@@ -1372,6 +1997,10 @@ pub struct Config {
 	///
 	/// Basically "global" ACLs.
 	///
+	/// For federation requests, servers can additionally be allowed or blocked
+	/// at runtime with the `federation allow` and `federation block` admin
+	/// commands, which take precedence over this list.
+	///
 	/// example: ["badserver\.tld$", "badphrase", "19dollarfortnitecards"]
 	///
 	/// default: []
@@ -1388,6 +2017,29 @@ pub struct Config {
 	#[serde(default, with = "serde_regex")]
 	pub forbidden_remote_room_directory_server_names: RegexSet,
 
+	/// List of server names via regex patterns that our users' room directory
+	/// requests may be forwarded to. If this list is empty, requests may be
+	/// forwarded to any server not otherwise forbidden. Requests for our own
+	/// directory are not affected.
+	///
+	/// example: ["^matrix\.org$"]
+	///
+	/// default: []
+	#[serde(default, with = "serde_regex")]
+	pub allowed_remote_room_directory_server_names: RegexSet,
+
+	/// List of request paths via regex patterns to disable. Requests whose
+	/// path matches any of these are rejected with `M_FORBIDDEN` before
+	/// reaching their handler. Useful for turning off endpoints such as the
+	/// public room directory, profile lookups or third-party identifiers.
+	///
+	/// example: ["^/_matrix/client/[^/]+/publicRooms$",
+	/// "^/_matrix/client/[^/]+/profile/", "/3pid"]
+	///
+	/// default: []
+	#[serde(default, with = "serde_regex")]
+	pub disabled_routes: RegexSet,
+
 	#[allow(clippy::doc_link_with_quotes)]
 	/// Vector list of IPv4 and IPv6 CIDR ranges / subnets *in quotes* that you
 	/// do not want tuwunel to send outbound requests to. Defaults to
@@ -1400,6 +2052,13 @@ pub struct Config {
 	///
 	/// Currently this does not account for proxies in use like Synapse does.
 	///
+	/// This is enforced when resolving the destination of every outbound
+	/// federation, push, URL preview and remote media request, in addition to
+	/// IP literals being checked before sending. Requests to destinations the
+	/// admin configured, such as appservices, the SSO provider and the
+	/// password provider, are exempt. Exceptions can be made for each of the
+	/// others with the `*_ip_range_allowlist` options below.
+	///
 	/// To disable, set this to be an empty vector (`[]`).
 	///
 	/// Defaults to:
@@ -1411,6 +2070,32 @@ pub struct Config {
 	#[serde(default = "default_ip_range_denylist")]
 	pub ip_range_denylist: Vec<String>,
 
+	/// IPv4 and IPv6 CIDR ranges which federation requests (including
+	/// well-known lookups and remote media) are allowed to reach even though
+	/// they are covered by `ip_range_denylist`. Useful for federating with
+	/// servers on a private network.
+	///
+	/// example: ["10.10.0.0/16"]
+	///
+	/// default: []
+	#[serde(default)]
+	pub federation_ip_range_allowlist: Vec<String>,
+
+	/// IPv4 and IPv6 CIDR ranges which push gateways are allowed to be
+	/// reached at even though they are covered by `ip_range_denylist`, e.g.
+	/// a self-hosted push gateway on the local network.
+	///
+	/// default: []
+	#[serde(default)]
+	pub pusher_ip_range_allowlist: Vec<String>,
+
+	/// IPv4 and IPv6 CIDR ranges which URL previews are allowed to fetch
+	/// from even though they are covered by `ip_range_denylist`.
+	///
+	/// default: []
+	#[serde(default)]
+	pub url_preview_ip_range_allowlist: Vec<String>,
+
 	/// Optional IP address or network interface-name to bind as the source of
 	/// URL preview requests. If not set, it will not bind to a specific
 	/// address or interface.
@@ -1529,19 +2214,75 @@ pub struct Config {
 	#[serde(default, with = "serde_regex")]
 	pub forbidden_usernames: RegexSet,
 
-	/// Retry failed and incomplete messages to remote servers immediately upon
-	/// startup. This is called bursting. If this is disabled, said messages may
-	/// not be delivered until more messages are queued for that server. Do not
-	/// change this option unless server resources are extremely limited or the
-	/// scale of the server's deployment is huge. Do not disable this unless you
-	/// know what you are doing.
-	#[serde(default = "true_fn")]
-	pub startup_netburst: bool,
+	/// Maximum length in bytes of the localpart of newly registered usernames.
+	/// The Matrix specification limits whole user IDs to 255 bytes.
+	///
+	/// default: 255
+	#[serde(default = "default_max_localpart_length")]
+	pub max_username_length: usize,
 
-	/// Messages are dropped and not reattempted. The `startup_netburst` option
-	/// must be enabled for this value to have any effect. Do not change this
-	/// value unless you know what you are doing. Set this value to -1 to
-	/// reattempt every message without trimming the queues; this may consume
+	/// Maximum length in bytes of the localpart of newly created room aliases.
+	/// The Matrix specification limits whole room aliases to 255 bytes.
+	///
+	/// default: 255
+	#[serde(default = "default_max_localpart_length")]
+	pub max_alias_length: usize,
+
+	/// Regex patterns of which newly registered usernames must match at least
+	/// one, narrowing the characters allowed by the Matrix specification. If
+	/// this list is empty, any valid username is allowed.
+	///
+	/// Usernames are lowercased before being matched.
+	///
+	/// example: ["^[a-z0-9_]+$"]
+	///
+	/// default: []
+	#[serde(default, with = "serde_regex")]
+	pub allowed_username_patterns: RegexSet,
+
+	/// Regex patterns of which the localparts of newly created room aliases
+	/// must match at least one. If this list is empty, any valid alias is
+	/// allowed.
+	///
+	/// example: ["^[a-z0-9_-]+$"]
+	///
+	/// default: []
+	#[serde(default, with = "serde_regex")]
+	pub allowed_alias_patterns: RegexSet,
+
+	/// Username prefixes which only appservices may register, e.g. to keep
+	/// bridged users' namespaces free before the bridge is set up. Prefixes
+	/// match regardless of case.
+	///
+	/// example: ["admin", "_bridge_"]
+	///
+	/// default: []
+	#[serde(default)]
+	pub reserved_username_prefixes: Vec<String>,
+
+	/// Room alias localpart prefixes which only appservices and server admins
+	/// may create. Prefixes match regardless of case.
+	///
+	/// example: ["admin", "_bridge_"]
+	///
+	/// default: []
+	#[serde(default)]
+	pub reserved_alias_prefixes: Vec<String>,
+
+	/// Retry failed and incomplete messages to remote servers immediately upon
+	/// startup. This is called bursting. If this is disabled, said messages may
+	/// not be delivered until more messages are queued for that server. Do not
+	/// change this option unless server resources are extremely limited or the
+	/// scale of the server's deployment is huge. Do not disable this unless you
+	/// know what you are doing. Transactions to appservices are always retried
+	/// upon startup, in full.
+	#[serde(default = "true_fn")]
+	pub startup_netburst: bool,
+
+	/// Messages are dropped and not reattempted. The `startup_netburst` option
+	/// must be enabled for this value to have any effect. Do not change this
+	/// value unless you know what you are doing. Set this value to -1 to
+	/// reattempt every message without trimming the queues; this may consume
 	/// significant disk. Set this value to 0 to drop all messages without any
 	/// attempt at redelivery.
 	///
@@ -1556,6 +2297,100 @@ pub struct Config {
 	#[serde(default)]
 	pub block_non_admin_invites: bool,
 
+	/// What to do with federated invites from servers which share no rooms
+	/// with this server, a common trait of invite spam: "allow" them,
+	/// "reject" them, or "suppress" them. Suppressed invites are accepted but
+	/// held from the invited user until an admin releases them with
+	/// `!admin users invites release`. Rejected and suppressed invites are
+	/// reported to the admin room. Invites to admins are always allowed.
+	///
+	/// default: "allow"
+	#[serde(default)]
+	pub invite_unshared_servers: InvitePolicy,
+
+	/// What to do with federated invites from accounts first seen by this
+	/// server less than `invite_young_account_age` ago: "allow" them,
+	/// "reject" them, or "suppress" them, as with `invite_unshared_servers`.
+	/// Remote account age is not known over federation; it is taken from the
+	/// earliest membership of the sender in the rooms this server shares with
+	/// them, and invites from senders in none of them are not affected.
+	///
+	/// default: "allow"
+	#[serde(default)]
+	pub invite_young_accounts: InvitePolicy,
+
+	/// Age in seconds below which accounts are subject to
+	/// `invite_young_accounts`.
+	///
+	/// default: 604800
+	#[serde(default = "default_invite_young_account_age")]
+	pub invite_young_account_age: u64,
+
+	/// Servers whose invites are allowed regardless of
+	/// `invite_unshared_servers` and `invite_young_accounts`.
+	///
+	/// example: ["trusted\.example\.com$"]
+	///
+	/// default: []
+	#[serde(default, with = "serde_regex")]
+	pub invite_unshared_servers_allowed: RegexSet,
+
+	/// Length of the window over which membership changes (joins, invites and
+	/// knocks) are counted for the limits below, in seconds.
+	///
+	/// default: 60
+	#[serde(default = "default_membership_rate_window")]
+	pub membership_rate_window: u64,
+
+	/// Maximum membership changes by one user, local or remote, in each
+	/// window. Further requests are refused until the window ends. Disabled
+	/// when 0.
+	///
+	/// default: 0
+	#[serde(default)]
+	pub membership_rate_limit_user: u32,
+
+	/// Maximum membership changes into one room in each window, damping join
+	/// floods. Local users and the users of each remote server are counted
+	/// apart, so no one server can exhaust the limit for the others. Disabled
+	/// when 0.
+	///
+	/// default: 0
+	#[serde(default)]
+	pub membership_rate_limit_room: u32,
+
+	/// Maximum membership changes by one appservice user in each window,
+	/// replacing `membership_rate_limit_user`. Disabled when 0.
+	///
+	/// default: 0
+	#[serde(default)]
+	pub membership_rate_limit_appservice_user: u32,
+
+	/// Maximum membership changes into one room by appservice users in each
+	/// window. These are counted apart from those of other users, so bridged
+	/// joins don't exhaust `membership_rate_limit_room`. Disabled when 0.
+	///
+	/// default: 0
+	#[serde(default)]
+	pub membership_rate_limit_appservice_room: u32,
+
+	/// Sustained rate at which each local user may send messages and state
+	/// events, per second. Appservice users are exempt. Overrides for
+	/// particular users, such as bots, can be set with the admin command
+	/// `user ratelimit`, which apply even when this is disabled. Disabled when
+	/// 0.
+	///
+	/// default: 0.0
+	#[serde(default)]
+	pub message_rate_limit_per_second: f64,
+
+	/// Messages a local user may send at once after a pause, above the
+	/// sustained `message_rate_limit_per_second`.
+	///
+	/// default: 50
+	#[serde(default = "default_message_rate_limit_burst")]
+	pub message_rate_limit_burst: u32,
+
 	/// Allow admins to enter commands in rooms other than "#admins" (admin
 	/// room) by prefixing your message with "\!admin" or "\\!admin" followed up
 	/// a normal tuwunel admin command. The reply will be publicly visible to
@@ -1571,6 +2406,18 @@ pub struct Config {
 	#[serde(default)]
 	pub admin_console_automatic: bool,
 
+	/// Keep the admin console's command history across restarts in the file
+	/// `admin_console_history` within the database directory. Commands are
+	/// stored as typed, including any secrets given as arguments, in a file
+	/// readable only by the server's user.
+	#[serde(default)]
+	pub admin_console_history: bool,
+
+	/// Show admin console output taller than the terminal through the pager
+	/// in the `PAGER` environment variable, or `less -R` when unset.
+	#[serde(default = "true_fn")]
+	pub admin_console_pager: bool,
+
 	#[allow(clippy::doc_link_with_quotes)]
 	/// List of admin commands to execute on startup.
 	///
@@ -1593,6 +2440,12 @@ pub struct Config {
 	#[serde(default)]
 	pub admin_execute_errors_ignore: bool,
 
+	/// Shut down once the startup commands in `admin_execute` complete. Errors
+	/// are then never ignored and fail the process. This is set by the
+	/// `tuwunel admin <command>` offline mode.
+	#[serde(default)]
+	pub admin_execute_exit: bool,
+
 	/// List of admin commands to execute on SIGUSR2.
 	///
 	/// Similar to admin_execute, but these commands are executed when the
@@ -1672,6 +2525,42 @@ pub struct Config {
 	#[serde(default = "default_sentry_filter")]
 	pub sentry_filter: String,
 
+	/// Replace access tokens in Sentry events and breadcrumbs, including
+	/// `Authorization` headers, federation `X-Matrix` signatures and
+	/// `access_token` query parameters.
+	#[serde(default = "true_fn")]
+	pub sentry_scrub_access_tokens: bool,
+
+	/// Replace Matrix user IDs in Sentry events and breadcrumbs, including the
+	/// event's user. The user's email and IP address are never sent.
+	#[serde(default)]
+	pub sentry_scrub_user_ids: bool,
+
+	/// Replace Matrix room IDs and aliases in Sentry events and breadcrumbs.
+	#[serde(default)]
+	pub sentry_scrub_room_ids: bool,
+
+	/// Sample rates for Sentry events by the module they are logged from. Keys
+	/// are module path prefixes; the longest matching prefix applies. Events
+	/// from other modules are all sent.
+	///
+	/// example: { "tuwunel_service::sending" = 0.1, "tuwunel_api" = 0.5 }
+	///
+	/// default: {}
+	#[serde(default)]
+	pub sentry_module_sample_rates: BTreeMap<String, f32>,
+
+	/// Environment tag of Sentry events, such as "production" or "staging".
+	///
+	/// example: "production"
+	pub sentry_environment: Option<String>,
+
+	/// Release tag of Sentry events. Defaults to the name and version of this
+	/// build.
+	///
+	/// example: "tuwunel@1.2.0-custom"
+	pub sentry_release: Option<String>,
+
 	/// Enable the tokio-console. This option is only relevant to developers.
 	///
 	///	For more information, see:
@@ -1689,6 +2578,31 @@ pub struct Config {
 	#[serde(default = "true_fn")]
 	pub admin_room_notices: bool,
 
+	/// Categories of admin room notices to batch into a periodic digest with
+	/// counts instead of sending each one: "registration", "password",
+	/// "deactivation", "directory", "impersonation" and "invite". Useful on
+	/// busy servers where these would flood the admin room.
+	///
+	/// example: ["registration", "password"]
+	///
+	/// default: []
+	#[serde(default)]
+	pub admin_room_notices_digest: BTreeSet<NoticeCategory>,
+
+	/// Interval between admin room notice digests (seconds).
+	///
+	/// default: 3600
+	#[serde(default = "default_admin_room_notices_digest_interval")]
+	pub admin_room_notices_digest_interval: u64,
+
+	/// Room or space holding the server-wide image packs (custom emoji and
+	/// stickers) managed with the `media emotes` admin commands. The server
+	/// user must be able to send state events there. Defaults to the admin
+	/// room.
+	///
+	/// example: "#emotes:example.com"
+	pub emote_room: Option<OwnedRoomOrAliasId>,
+
 	/// Enable database pool affinity support. On supporting systems, block
 	/// device queue topologies are detected and the request pool is optimized
 	/// for the hardware; db_pool_workers is determined automatically.
@@ -1814,6 +2728,14 @@ pub struct Config {
 	#[serde(default)]
 	pub ldap: LdapConfig,
 
+	// external structure; separate section
+	#[serde(default)]
+	pub password_provider: PasswordProviderConfig,
+
+	// external structure; separate section
+	#[serde(default)]
+	pub sso: SsoConfig,
+
 	#[serde(flatten)]
 	#[allow(clippy::zero_sized_map_values)]
 	// this is a catchall, the map shouldn't be zero at runtime
@@ -1858,13 +2780,81 @@ pub struct WellKnownConfig {
 	/// example: "matrix.example.com:443"
 	pub server: Option<OwnedServerName>,
 
+	/// Identity server the client well-known file advertises as
+	/// `m.identity_server`.
+	///
+	/// example: "https://vector.im"
+	pub client_identity_server: Option<Url>,
+
+	/// Map tile server style URL the client well-known file advertises as
+	/// `m.tile_server` (MSC3488), for location sharing.
+	///
+	/// example: "https://tiles.example.com/style.json"
+	pub client_tile_server: Option<Url>,
+
+	/// Sliding sync proxy the client well-known file advertises as
+	/// `org.matrix.msc3575.proxy`. Defaults to the `client` URL, as sliding
+	/// sync is served natively.
+	///
+	/// example: "https://sync.example.com"
+	pub client_sliding_sync_proxy: Option<Url>,
+
+	/// Additional keys in the client well-known file, e.g. client-specific
+	/// settings. Keys here override the ones above.
+	///
+	/// example: { "io.element.e2ee" = { default = false } }
+	///
+	/// default: {}
+	#[serde(default)]
+	pub client_extra: serde_json::Map<String, serde_json::Value>,
+
+	/// URL of a page with support information for this server, served in
+	/// `/.well-known/matrix/support` (MSC1929).
+	///
+	/// example: "https://example.com/support"
 	pub support_page: Option<Url>,
 
+	/// Role of the contact given by `support_email` and `support_mxid`.
+	///
+	/// example: "m.role.admin"
 	pub support_role: Option<ContactRole>,
 
+	/// Email address of the support contact.
+	///
+	/// example: "admin@example.com"
 	pub support_email: Option<String>,
 
+	/// Matrix ID of the support contact.
+	///
+	/// example: "@admin:example.com"
 	pub support_mxid: Option<OwnedUserId>,
+
+	/// Additional support contacts, each with a `role` and at least one of
+	/// `email_address` or `matrix_id`.
+	///
+	/// example: [{ role = "m.role.security", email_address =
+	/// "security@example.com" }]
+	///
+	/// default: []
+	#[serde(default)]
+	pub support_contacts: Vec<WellKnownContact>,
+
+	/// List the local members of the admin room as `m.role.admin` support
+	/// contacts.
+	#[serde(default)]
+	pub support_list_admins: bool,
+}
+
+/// Contact listed in `/.well-known/matrix/support`; see `support_contacts`.
+#[derive(Clone, Debug, Deserialize)]
+pub struct WellKnownContact {
+	pub role: ContactRole,
+
+	#[serde(default)]
+	pub email_address: Option<String>,
+
+	#[serde(default)]
+	pub matrix_id: Option<OwnedUserId>,
 }
 
 #[derive(Clone, Copy, Debug, Deserialize, Default)]
@@ -1991,6 +2981,383 @@ pub struct LdapConfig {
 	pub admin_filter: String,
 }
 
+#[derive(Clone, Debug, Default, Deserialize)]
+#[config_example_generator(
+	filename = "tuwunel-example.toml",
+	section = "global.password_provider"
+)]
+pub struct PasswordProviderConfig {
+	/// Whether to check passwords at login with an external HTTP password
+	/// provider instead of the local database.
+	///
+	/// The provider is sent a POST request with the JSON body `{"user_id":
+	/// "@alice:example.com", "password": "..."}` and must respond with
+	/// `{"authenticated": true}` or `{"authenticated": false}`. It may also
+	/// include `"admin": true` or `false` to grant or revoke server admin, and
+	/// `"displayname"` for accounts created on first login.
+	///
+	/// example: "true"
+	#[serde(default)]
+	pub enable: bool,
+
+	/// URL the password checks are posted to.
+	///
+	/// example: "https://auth.example.com/check_password"
+	pub url: Option<Url>,
+
+	/// Seconds to wait for the provider to respond.
+	///
+	/// default: 10
+	#[serde(default = "default_password_provider_timeout")]
+	pub timeout: u64,
+
+	/// Create accounts on first login for users the provider authenticates.
+	/// Otherwise only existing users may log in.
+	#[serde(default = "true_fn")]
+	pub create_users: bool,
+
+	/// Check the local password of users the provider does not authenticate
+	/// or when it cannot be reached.
+	#[serde(default)]
+	pub fallback_to_local: bool,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[config_example_generator(filename = "tuwunel-example.toml", section = "global.sso")]
+pub struct SsoConfig {
+	/// Whether to enable single sign-on (`m.login.sso`) through an upstream
+	/// OpenID Connect provider.
+	///
+	/// The provider must allow the redirect URI
+	/// `<well_known.client>/_matrix/client/v3/login/sso/callback`, so
+	/// `well_known.client` must be set.
+	///
+	/// example: "true"
+	#[serde(default)]
+	pub enable: bool,
+
+	/// Issuer URL of the provider. Its configuration is discovered from
+	/// `<issuer>/.well-known/openid-configuration`.
+	///
+	/// example: "https://accounts.example.com/realms/matrix"
+	pub issuer: Option<Url>,
+
+	/// Client ID registered with the provider.
+	///
+	/// example: "tuwunel"
+	pub client_id: Option<String>,
+
+	/// Client secret registered with the provider.
+	///
+	/// display: sensitive
+	pub client_secret: Option<String>,
+
+	/// Space-separated scopes to request. `openid` is required.
+	///
+	/// default: "openid profile"
+	#[serde(default = "default_sso_scopes")]
+	pub scopes: String,
+
+	/// Claim whose value becomes the localpart of users created on first
+	/// login.
+	///
+	/// example: "preferred_username" or "email"
+	///
+	/// default: "preferred_username"
+	#[serde(default = "default_sso_username_claim")]
+	pub username_claim: String,
+
+	/// Claim whose value becomes the display name of users created on first
+	/// login.
+	///
+	/// default: "name"
+	#[serde(default = "default_sso_displayname_claim")]
+	pub displayname_claim: String,
+
+	/// Create accounts on first login for users the provider authenticates.
+	/// Otherwise only users already linked to the provider may log in.
+	#[serde(default = "true_fn")]
+	pub create_users: bool,
+
+	/// Link the provider's users to existing accounts of the same username on
+	/// their first login. Only enable this if the provider controls every
+	/// username, as anyone able to choose that username at the provider can
+	/// otherwise take over the account.
+	#[serde(default)]
+	pub link_existing_users: bool,
+
+	/// Prefixes of client URLs which are sent the login token without asking
+	/// the user to confirm. Clients at other URLs are only sent the token
+	/// once the user follows a link on a confirmation page.
+	///
+	/// example: ["https://app.element.io/"]
+	///
+	/// default: []
+	#[serde(default = "Vec::new")]
+	pub client_redirect_urls: Vec<String>,
+
+	/// Seconds to wait for the provider to respond.
+	///
+	/// default: 10
+	#[serde(default = "default_sso_timeout")]
+	pub timeout: u64,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+#[config_example_generator(
+	filename = "tuwunel-example.toml",
+	section = "global.route_limits"
+)]
+pub struct RouteLimitsConfig {
+	/// Maximum time to process a media upload (seconds). Defaults to
+	/// `client_request_timeout`.
+	///
+	/// example: 600
+	pub upload_timeout: Option<u64>,
+
+	/// Max request size for media uploads in bytes. This is also the upload
+	/// limit advertised to clients. Defaults to `max_request_size`.
+	///
+	/// example: 104857600
+	pub upload_max_request_size: Option<usize>,
+
+	/// Maximum time to process a client sync request (seconds), including
+	/// any long-polling timeout the client asks for. Defaults to
+	/// `client_request_timeout`.
+	///
+	/// example: 90
+	pub sync_timeout: Option<u64>,
+
+	/// Max request size for client sync requests in bytes. Defaults to
+	/// `max_request_size`.
+	///
+	/// example: 65536
+	pub sync_max_request_size: Option<usize>,
+
+	/// Maximum time to process a federation transaction (`/send`) from
+	/// another server (seconds). Defaults to `client_request_timeout`.
+	///
+	/// example: 120
+	pub federation_send_timeout: Option<u64>,
+
+	/// Max request size for federation transactions (`/send`) in bytes.
+	/// Defaults to `max_request_size`.
+	///
+	/// example: 10485760
+	pub federation_send_max_request_size: Option<usize>,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+#[config_example_generator(filename = "tuwunel-example.toml", section = "global.caches")]
+pub struct CachesConfig {
+	/// Size caches from the memory available to the server rather than its
+	/// CPU core count: capacities which are not set below scale with each
+	/// GiB of memory, limited by the cgroup when running in a container.
+	#[serde(default)]
+	pub auto: bool,
+
+	/// Preload frequently used data in the background after startup to
+	/// reduce the latency spike following a restart: the current state of
+	/// rooms this server is in, the signing keys of servers in those rooms,
+	/// and the keys of local devices seen within the last week.
+	#[serde(default)]
+	pub warmup: bool,
+
+	/// Number of events cached by PDU ID.
+	///
+	/// example: 100000
+	pub pdu: Option<u32>,
+
+	/// Number of auth chains cached.
+	///
+	/// example: 100000
+	pub auth_chain: Option<u32>,
+
+	/// Number of event IDs cached by short event ID.
+	///
+	/// example: 100000
+	pub shorteventid: Option<u32>,
+
+	/// Number of short event IDs cached by event ID.
+	///
+	/// example: 100000
+	pub eventidshort: Option<u32>,
+
+	/// Number of PDU IDs cached by event ID.
+	///
+	/// example: 100000
+	pub eventid_pdu: Option<u32>,
+
+	/// Number of state keys cached by short state key.
+	///
+	/// example: 100000
+	pub shortstatekey: Option<u32>,
+
+	/// Number of short state keys cached by state key.
+	///
+	/// example: 100000
+	pub statekeyshort: Option<u32>,
+
+	/// Number of per-server federation event data entries cached.
+	///
+	/// example: 500000
+	pub servernameevent_data: Option<u32>,
+
+	/// Number of decompressed room states cached.
+	///
+	/// example: 1000
+	pub stateinfo: Option<u32>,
+
+	/// Number of space hierarchy summaries cached.
+	///
+	/// example: 10000
+	pub roomid_spacehierarchy: Option<u32>,
+
+	/// Number of in-progress space hierarchy paginations retained.
+	///
+	/// example: 1024
+	pub space_pagination: Option<u32>,
+
+	/// Number of resolved remote room aliases cached.
+	///
+	/// example: 5000
+	pub alias: Option<u32>,
+
+	/// Number of remote users whose device keys are cached; see
+	/// `remote_device_keys_max_staleness`.
+	///
+	/// example: 10000
+	pub remote_device_keys: Option<u32>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[config_example_generator(
+	filename = "tuwunel-example.toml",
+	section = "global.auto_join"
+)]
+pub struct AutoJoinConfig {
+	/// Invite new users to the auto-join rooms instead of joining them. The
+	/// invites are sent by the server user, which must be joined to each room
+	/// with permission to invite.
+	///
+	/// This applies to `auto_join_rooms` as well as the lists below.
+	#[serde(default)]
+	pub invite: bool,
+
+	/// Rooms and spaces users created on their first LDAP login will
+	/// additionally join, after those in `auto_join_rooms`.
+	///
+	/// default: []
+	#[serde(default = "Vec::new")]
+	pub ldap: Vec<OwnedRoomOrAliasId>,
+
+	/// Rooms and spaces users created on their first single sign-on login
+	/// will additionally join, after those in `auto_join_rooms`.
+	///
+	/// default: []
+	#[serde(default = "Vec::new")]
+	pub sso: Vec<OwnedRoomOrAliasId>,
+
+	/// Rooms and spaces users who registered with a registration token will
+	/// additionally join, after those in `auto_join_rooms`.
+	///
+	/// default: []
+	#[serde(default = "Vec::new")]
+	pub token: Vec<OwnedRoomOrAliasId>,
+
+	/// Rooms and spaces guest users will join. Unlike `auto_join_rooms` this
+	/// list applies to guests regardless of `allow_guests_auto_join_rooms`.
+	///
+	/// default: []
+	#[serde(default = "Vec::new")]
+	pub guest: Vec<OwnedRoomOrAliasId>,
+}
+
+/// Room creation preset; see `room_templates`.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct RoomTemplate {
+	/// Enable end-to-end encryption in the new room.
+	#[serde(default)]
+	pub encrypted: bool,
+
+	/// Partial `m.room.power_levels` content merged over the defaults.
+	#[serde(default)]
+	pub power_levels: Option<serde_json::Map<String, serde_json::Value>>,
+
+	/// State events to send on creation.
+	#[serde(default)]
+	pub initial_state: Vec<RoomTemplateEvent>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct RoomTemplateEvent {
+	#[serde(rename = "type")]
+	pub kind: String,
+
+	#[serde(default)]
+	pub state_key: String,
+
+	pub content: serde_json::Value,
+}
+
+/// RocksDB tuning profile; see `db_profile`.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum DbProfile {
+	LowMemory,
+	#[default]
+	Balanced,
+	Throughput,
+}
+
+impl DbProfile {
+	#[must_use]
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			| Self::LowMemory => "low-memory",
+			| Self::Balanced => "balanced",
+			| Self::Throughput => "throughput",
+		}
+	}
+}
+
+/// Handling of invites from servers sharing no rooms; see
+/// `invite_unshared_servers`.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum InvitePolicy {
+	#[default]
+	Allow,
+	Reject,
+	Suppress,
+}
+
+/// Category of admin room notice; see `admin_room_notices_digest`.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[serde(rename_all = "snake_case")]
+pub enum NoticeCategory {
+	Registration,
+	Password,
+	Deactivation,
+	Directory,
+	Impersonation,
+	Invite,
+}
+
+impl NoticeCategory {
+	#[must_use]
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			| Self::Registration => "registration",
+			| Self::Password => "password",
+			| Self::Deactivation => "deactivation",
+			| Self::Directory => "directory",
+			| Self::Impersonation => "impersonation",
+			| Self::Invite => "invite",
+		}
+	}
+}
+
 #[derive(Deserialize, Clone, Debug)]
 #[serde(transparent)]
 struct ListeningPort {
@@ -2085,6 +3452,16 @@ fn get_bind_ports(&self) -> Vec<u16> {
 	}
 
 	pub fn check(&self) -> Result<(), Error> { check(self) }
+
+	/// Argon2id parameters for new password hashes.
+	#[must_use]
+	pub fn password_params(&self) -> PasswordParams {
+		PasswordParams {
+			m_cost: self.password_hash_memory_cost,
+			t_cost: self.password_hash_time_cost,
+			p_cost: self.password_hash_parallelism,
+		}
+	}
 }
 
 fn true_fn() -> bool { true }
@@ -2101,45 +3478,23 @@ fn default_unix_socket_perms() -> u32 { 660 }
 
 fn default_database_backups_to_keep() -> i16 { 1 }
 
+fn default_database_wal_archive_interval() -> u64 { 10 }
+
 fn default_db_write_buffer_capacity_mb() -> f64 { 48.0 + parallelism_scaled_f64(4.0) }
 
 fn default_db_cache_capacity_mb() -> f64 { 128.0 + parallelism_scaled_f64(64.0) }
 
-fn default_pdu_cache_capacity() -> u32 { parallelism_scaled_u32(10_000).saturating_add(100_000) }
-
 fn default_cache_capacity_modifier() -> f64 { 1.0 }
 
-fn default_auth_chain_cache_capacity() -> u32 {
-	parallelism_scaled_u32(10_000).saturating_add(100_000)
-}
-
-fn default_shorteventid_cache_capacity() -> u32 {
-	parallelism_scaled_u32(50_000).saturating_add(100_000)
-}
-
-fn default_eventidshort_cache_capacity() -> u32 {
-	parallelism_scaled_u32(25_000).saturating_add(100_000)
-}
-
-fn default_eventid_pdu_cache_capacity() -> u32 {
-	parallelism_scaled_u32(25_000).saturating_add(100_000)
-}
-
-fn default_shortstatekey_cache_capacity() -> u32 {
-	parallelism_scaled_u32(10_000).saturating_add(100_000)
-}
+fn default_hierarchy_remote_cache_ttl() -> u64 { 600 }
 
-fn default_statekeyshort_cache_capacity() -> u32 {
-	parallelism_scaled_u32(10_000).saturating_add(100_000)
-}
+fn default_hierarchy_max_remote_requests() -> usize { 32 }
 
-fn default_servernameevent_data_cache_capacity() -> u32 {
-	parallelism_scaled_u32(100_000).saturating_add(500_000)
-}
+fn default_alias_cache_ttl() -> u64 { 300 }
 
-fn default_stateinfo_cache_capacity() -> u32 { parallelism_scaled_u32(100) }
+fn default_alias_cache_negative_ttl() -> u64 { 60 }
 
-fn default_roomid_spacehierarchy_cache_capacity() -> u32 { parallelism_scaled_u32(1000) }
+fn default_remote_device_keys_max_staleness() -> u64 { 300 }
 
 fn default_dns_cache_entries() -> u32 { 32768 }
 
@@ -2183,6 +3538,12 @@ fn default_sender_idle_timeout() -> u64 { 180 }
 
 fn default_sender_retry_backoff_limit() -> u64 { 86400 }
 
+fn default_sender_transaction_pdu_limit() -> usize { 48 }
+
+fn default_sender_transaction_edu_limit() -> usize { 98 }
+
+fn default_federation_stats_interval() -> u64 { 300 }
+
 fn default_appservice_timeout() -> u64 { 35 }
 
 fn default_appservice_idle_timeout() -> u64 { 300 }
@@ -2227,8 +3588,24 @@ fn default_notification_push_path() -> String { "/_matrix/push/v1/notify".to_own
 
 fn default_openid_token_ttl() -> u64 { 60 * 60 }
 
+fn default_max_event_ttl() -> u64 { 60 * 60 * 24 * 30 }
+
+fn default_max_event_delay() -> u64 { 60 * 60 * 24 }
+
+fn default_max_delayed_events_per_user() -> usize { 100 }
+
+fn default_filter_max_age() -> u64 { 60 * 60 * 24 * 90 }
+
+fn default_notification_max_age() -> u64 { 60 * 60 * 24 * 30 }
+
 fn default_login_token_ttl() -> u64 { 2 * 60 * 1000 }
 
+fn default_password_hash_memory_cost() -> u32 { 19_456 }
+
+fn default_password_hash_time_cost() -> u32 { 2 }
+
+fn default_password_hash_parallelism() -> u32 { 1 }
+
 fn default_turn_ttl() -> u64 { 60 * 60 * 24 }
 
 fn default_presence_idle_timeout_s() -> u64 { 5 * 60 }
@@ -2282,6 +3659,16 @@ fn default_rocksdb_stats_level() -> u8 { 1 }
 #[inline]
 pub fn default_default_room_version() -> RoomVersionId { RoomVersionId::V11 }
 
+fn default_thumbnail_sizes() -> Vec<String> {
+	vec![
+		"32x32 crop".to_owned(),
+		"96x96 crop".to_owned(),
+		"320x240 scale".to_owned(),
+		"640x480 scale".to_owned(),
+		"800x600 scale".to_owned(),
+	]
+}
+
 fn default_ip_range_denylist() -> Vec<String> {
 	vec![
 		"127.0.0.0/8".to_owned(),
@@ -2306,6 +3693,8 @@ fn default_ip_range_denylist() -> Vec<String> {
 	]
 }
 
+fn default_max_localpart_length() -> usize { 255 }
+
 fn default_url_preview_max_spider_size() -> usize {
 	256_000 // 256KB
 }
@@ -2326,6 +3715,12 @@ fn default_sentry_filter() -> String { "info".to_owned() }
 
 fn default_startup_netburst_keep() -> i64 { 50 }
 
+fn default_invite_young_account_age() -> u64 { 60 * 60 * 24 * 7 }
+
+fn default_membership_rate_window() -> u64 { 60 }
+
+fn default_message_rate_limit_burst() -> u32 { 50 }
+
 fn default_admin_log_capture() -> String {
 	cfg!(debug_assertions)
 		.then_some("debug")
@@ -2335,19 +3730,18 @@ fn default_admin_log_capture() -> String {
 
 fn default_admin_room_tag() -> String { "m.server_notice".to_owned() }
 
-#[allow(clippy::as_conversions, clippy::cast_precision_loss)]
-fn parallelism_scaled_f64(val: f64) -> f64 { val * (sys::available_parallelism() as f64) }
+fn default_registration_throttle_window() -> u64 { 60 * 60 }
 
-fn parallelism_scaled_u32(val: u32) -> u32 {
-	let val = val
-		.try_into()
-		.expect("failed to cast u32 to usize");
-	parallelism_scaled(val)
-		.try_into()
-		.unwrap_or(u32::MAX)
-}
+fn default_registration_throttle_lockout() -> u64 { 60 * 60 * 24 }
+
+fn default_registration_throttle_ipv4_prefix() -> u8 { 24 }
+
+fn default_registration_throttle_ipv6_prefix() -> u8 { 64 }
 
-fn parallelism_scaled(val: usize) -> usize { val.saturating_mul(sys::available_parallelism()) }
+fn default_admin_room_notices_digest_interval() -> u64 { 60 * 60 }
+
+#[allow(clippy::as_conversions, clippy::cast_precision_loss)]
+fn parallelism_scaled_f64(val: f64) -> f64 { val * (sys::available_parallelism() as f64) }
 
 fn default_trusted_server_batch_size() -> usize { 256 }
 
@@ -2377,6 +3771,12 @@ fn default_client_shutdown_timeout() -> u64 { 15 }
 
 fn default_sender_shutdown_timeout() -> u64 { 5 }
 
+fn default_shutdown_drain_timeout() -> u64 { 30 }
+
+fn default_runtime_max_blocking_threads() -> usize { 1024 }
+
+fn default_room_lock_watchdog_timeout() -> u64 { 120 }
+
 // blurhashing defaults recommended by https://blurha.sh/
 // 2^25
 fn default_blurhash_max_raw_size() -> u64 { 33_554_432 }
@@ -2385,10 +3785,22 @@ fn default_blurhash_x_component() -> u32 { 4 }
 
 fn default_blurhash_y_component() -> u32 { 3 }
 
+fn default_password_provider_timeout() -> u64 { 10 }
+
+fn default_sso_scopes() -> String { "openid profile".to_owned() }
+
+fn default_sso_username_claim() -> String { "preferred_username".to_owned() }
+
+fn default_sso_displayname_claim() -> String { "name".to_owned() }
+
+fn default_sso_timeout() -> u64 { 10 }
+
 fn default_ldap_search_filter() -> String { "(objectClass=*)".to_owned() }
 
 fn default_ldap_uid_attribute() -> String { String::from("uid") }
 
+fn default_account_validity_renewal_notice() -> u64 { 60 * 60 * 24 * 7 }
+
 fn default_ldap_mail_attribute() -> String { String::from("mail") }
 
 fn default_ldap_name_attribute() -> String { String::from("givenName") }
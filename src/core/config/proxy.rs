@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 use reqwest::{Proxy, Url};
 use serde::Deserialize;
 
@@ -21,6 +23,13 @@
 /// include = ["*.onion", "matrix.myspecial.onion"]
 /// exclude = ["*.myspecial.onion"]
 /// ```
+/// - Trust a private CA for some domains, without proxying them
+/// ```toml
+/// [global.proxy]
+/// [[global.proxy.by_domain]]
+/// include = ["*.internal.example"]
+/// ca_file = "/etc/ssl/internal-ca.pem"
+/// ```
 /// ## Include vs. Exclude
 /// If include is an empty list, it is assumed to be `["*"]`.
 ///
@@ -36,6 +45,8 @@ pub enum ProxyConfig {
 	Global {
 		#[serde(deserialize_with = "crate::utils::deserialize_from_str")]
 		url: Url,
+		#[serde(flatten)]
+		auth: ProxyAuth,
 	},
 	ByDomain(Vec<PartialProxyConfig>),
 }
@@ -43,31 +54,84 @@ impl ProxyConfig {
 	pub fn to_proxy(&self) -> Result<Option<Proxy>> {
 		Ok(match self.clone() {
 			| Self::None => None,
-			| Self::Global { url } => Some(Proxy::all(url)?),
+			| Self::Global { url, auth } => Some(Proxy::all(auth.apply(url))?),
 			| Self::ByDomain(proxies) => Some(Proxy::custom(move |url| {
 				// first matching proxy
 				proxies
 					.iter()
 					.find_map(|proxy| proxy.for_url(url))
-					.cloned()
 			})),
 		})
 	}
+
+	/// Entries overriding certificate verification, in order of precedence.
+	pub fn tls_overrides(&self) -> impl Iterator<Item = &PartialProxyConfig> {
+		let entries = match self {
+			| Self::ByDomain(proxies) => proxies.as_slice(),
+			| _ => &[],
+		};
+
+		entries
+			.iter()
+			.filter(|entry| entry.ca_file.is_some() || entry.tls_insecure())
+	}
+}
+
+/// Basic-auth credentials for a proxy.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct ProxyAuth {
+	#[serde(default)]
+	username: Option<String>,
+	#[serde(default)]
+	password: Option<String>,
+}
+impl ProxyAuth {
+	fn apply(&self, mut url: Url) -> Url {
+		if let Some(username) = self.username.as_deref() {
+			url.set_username(username).ok();
+			url.set_password(self.password.as_deref()).ok();
+		}
+
+		url
+	}
 }
 
 #[derive(Clone, Debug, Deserialize)]
 pub struct PartialProxyConfig {
-	#[serde(deserialize_with = "crate::utils::deserialize_from_str")]
-	url: Url,
+	/// Proxy for matching destinations; entries without one only override
+	/// certificate verification.
+	#[serde(default)]
+	url: Option<Url>,
+	#[serde(flatten)]
+	auth: ProxyAuth,
 	#[serde(default)]
 	include: Vec<WildCardedDomain>,
 	#[serde(default)]
 	exclude: Vec<WildCardedDomain>,
+
+	/// PEM file of CA certificates trusted, instead of the system roots, for
+	/// matching destinations.
+	#[serde(default)]
+	pub ca_file: Option<PathBuf>,
+
+	/// Skip certificate verification for matching destinations. Only honored
+	/// when `include` is not empty, so it can never apply to every domain.
+	#[serde(default)]
+	insecure: bool,
 }
 impl PartialProxyConfig {
 	#[must_use]
-	pub fn for_url(&self, url: &Url) -> Option<&Url> {
-		let domain = url.domain()?;
+	pub fn for_url(&self, url: &Url) -> Option<Url> {
+		let proxy = self.url.clone()?;
+		self.matches(url.domain()?)
+			.then(|| self.auth.apply(proxy))
+	}
+
+	#[must_use]
+	pub fn tls_insecure(&self) -> bool { self.insecure && !self.include.is_empty() }
+
+	#[must_use]
+	pub fn matches(&self, domain: &str) -> bool {
 		let mut included_because = None; // most specific reason it was included
 		let mut excluded_because = None; // most specific reason it was excluded
 		if self.include.is_empty() {
@@ -91,9 +155,9 @@ pub fn for_url(&self, url: &Url) -> Option<&Url> {
 			}
 		}
 		match (included_because, excluded_because) {
-			| (Some(a), Some(b)) if a.more_specific_than(b) => Some(&self.url),
-			| (Some(_), None) => Some(&self.url),
-			| _ => None,
+			| (Some(a), Some(b)) if a.more_specific_than(b) => true,
+			| (Some(_), None) => true,
+			| _ => false,
 		}
 	}
 }
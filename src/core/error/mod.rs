@@ -139,6 +139,21 @@ impl Error {
 	#[must_use]
 	pub fn from_errno() -> Self { Self::Io(std::io::Error::last_os_error()) }
 
+	/// Request error with an errcode ruma has no kind for, such as one from a
+	/// proposal. It is carried by ruma's catch-all kind, which is built from
+	/// the errcode's wire form, so clients receive the errcode unchanged.
+	pub fn custom_request<M>(errcode: &str, message: M, status: http::StatusCode) -> Self
+	where
+		M: Into<Cow<'static, str>>,
+	{
+		use ruma::api::client::error::ErrorKind;
+
+		let kind = serde_json::from_value(serde_json::json!({ "errcode": errcode }))
+			.unwrap_or(ErrorKind::Unknown);
+
+		Self::Request(kind, message.into(), status)
+	}
+
 	//#[deprecated]
 	pub fn bad_database(message: &'static str) -> Self {
 		crate::err!(Database(error!("{message}")))
@@ -60,6 +60,12 @@ pub(super) fn bad_request_code(kind: &ErrorKind) -> StatusCode {
 	use ErrorKind::*;
 
 	match kind {
+		// 504
+		| ConnectionTimeout => StatusCode::GATEWAY_TIMEOUT,
+
+		// 502
+		| BadStatus { .. } | ConnectionFailed => StatusCode::BAD_GATEWAY,
+
 		// 429
 		| LimitExceeded { .. } => StatusCode::TOO_MANY_REQUESTS,
 
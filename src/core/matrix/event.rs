@@ -4,6 +4,8 @@
 mod id;
 mod redact;
 mod relation;
+#[cfg(test)]
+mod tests;
 mod type_ext;
 mod unsigned;
 
@@ -16,7 +18,12 @@
 use serde::Deserialize;
 use serde_json::{Value as JsonValue, value::RawValue as RawJsonValue};
 
-pub use self::{filter::Matches, id::*, relation::RelationTypeEqual, type_ext::TypeExt};
+pub use self::{
+	filter::{Matches, type_matches},
+	id::*,
+	relation::RelationTypeEqual,
+	type_ext::TypeExt,
+};
 use super::{pdu::Pdu, state_key::StateKey};
 use crate::{Result, utils};
 
@@ -70,12 +70,19 @@ fn matches_sender<E: Event>(event: &E, filter: &RoomEventFilter) -> bool {
 fn matches_type<E: Event>(event: &E, filter: &RoomEventFilter) -> bool {
 	let kind = event.kind().to_cow_str();
 
-	if filter.not_types.iter().any(is_equal_to!(&kind)) {
+	if filter
+		.not_types
+		.iter()
+		.any(|pattern| type_matches(pattern, &kind))
+	{
 		return false;
 	}
 
 	if let Some(types) = filter.types.as_ref() {
-		if !types.iter().any(is_equal_to!(&kind)) {
+		if !types
+			.iter()
+			.any(|pattern| type_matches(pattern, &kind))
+		{
 			return false;
 		}
 	}
@@ -83,6 +90,33 @@ fn matches_type<E: Event>(event: &E, filter: &RoomEventFilter) -> bool {
 	true
 }
 
+/// Whether an event type matches a type in a filter, where `*` matches any
+/// sequence of characters.
+#[must_use]
+pub fn type_matches(pattern: &str, kind: &str) -> bool {
+	let Some((first, rest)) = pattern.split_once('*') else {
+		return pattern == kind;
+	};
+
+	let (middle, last) = rest.rsplit_once('*').unwrap_or(("", rest));
+	let Some(mut kind) = kind
+		.strip_prefix(first)
+		.and_then(|kind| kind.strip_suffix(last))
+	else {
+		return false;
+	};
+
+	for part in middle.split('*') {
+		let Some((_, after)) = kind.split_once(part) else {
+			return false;
+		};
+
+		kind = after;
+	}
+
+	true
+}
+
 fn matches_url<E: Event>(event: &E, filter: &RoomEventFilter) -> bool {
 	let Some(url_filter) = filter.url_filter.as_ref() else {
 		return true;
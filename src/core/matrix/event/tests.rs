@@ -0,0 +1,33 @@
+use super::type_matches;
+
+#[test]
+fn type_exact() {
+	assert!(type_matches("m.room.message", "m.room.message"));
+	assert!(!type_matches("m.room.message", "m.room.member"));
+	assert!(!type_matches("m.room", "m.room.message"));
+}
+
+#[test]
+fn type_wildcard_suffix() {
+	assert!(type_matches("m.room.*", "m.room.message"));
+	assert!(type_matches("m.room.*", "m.room."));
+	assert!(!type_matches("m.room.*", "m.call.invite"));
+	assert!(type_matches("*", "anything"));
+	assert!(type_matches("*", ""));
+}
+
+#[test]
+fn type_wildcard_prefix_and_middle() {
+	assert!(type_matches("*.message", "m.room.message"));
+	assert!(!type_matches("*.message", "m.room.member"));
+	assert!(type_matches("m.*.message", "m.room.message"));
+	assert!(type_matches("m.*o*.message", "m.room.message"));
+	assert!(!type_matches("m.*x*.message", "m.room.message"));
+}
+
+#[test]
+fn type_wildcard_no_overlap() {
+	assert!(!type_matches("ab*ba", "aba"));
+	assert!(type_matches("ab*ba", "abba"));
+	assert!(!type_matches("a*b*c", "acb"));
+}
@@ -4,8 +4,10 @@
 pub mod pdu;
 pub mod state_key;
 pub mod state_res;
+pub mod stream_token;
 
 pub use event::{Event, TypeExt as EventTypeExt};
 pub use pdu::{Pdu, PduBuilder, PduCount, PduEvent, PduId, RawPduId, ShortId};
 pub use state_key::StateKey;
 pub use state_res::{RoomVersion, StateMap, TypeStateKey};
+pub use stream_token::StreamToken;
@@ -73,3 +73,27 @@ pub fn add_relation(&mut self, name: &str, pdu: Option<&Pdu>) -> Result {
 
 	Ok(())
 }
+
+/// Bundle an aggregation of the events relating to this one under `rel_type`
+/// in its `unsigned` relations.
+#[implement(Pdu)]
+pub fn add_aggregation(&mut self, rel_type: &str, aggregation: JsonValue) -> Result {
+	use serde_json::Map;
+
+	let mut unsigned: Map<String, JsonValue> = self
+		.unsigned
+		.as_deref()
+		.map(RawJsonValue::get)
+		.map_or_else(|| Ok(Map::new()), serde_json::from_str)
+		.map_err(|e| err!(Database("Invalid unsigned in pdu event: {e}")))?;
+
+	unsigned
+		.entry("m.relations")
+		.or_insert(JsonValue::Object(Map::new()))
+		.as_object_mut()
+		.map(|object| object.insert(rel_type.to_owned(), aggregation));
+
+	self.unsigned = Some(to_raw_value(&unsigned)?);
+
+	Ok(())
+}
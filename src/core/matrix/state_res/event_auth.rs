@@ -5,10 +5,10 @@
 	future::{OptionFuture, join3},
 };
 use ruma::{
-	Int, OwnedUserId, RoomVersionId, UserId,
+	Int, OwnedUserId, RoomId, RoomVersionId, UserId,
 	events::room::{
 		create::RoomCreateEventContent,
-		join_rules::{JoinRule, RoomJoinRulesEventContent},
+		join_rules::{AllowRule, JoinRule, RoomJoinRulesEventContent},
 		member::{MembershipState, ThirdPartyInvite},
 		power_levels::RoomPowerLevelsEventContent,
 		third_party_invite::RoomThirdPartyInviteEventContent,
@@ -505,6 +505,21 @@ struct RoomCreateContentFederate {
 	Ok(true)
 }
 
+/// Rooms whose members may join a room with a restricted join rule, through a
+/// resident server's user authorising the join. Empty for other join rules.
+pub fn restricted_join_rooms(join_rule: &JoinRule) -> impl Iterator<Item = &RoomId> + Send {
+	let allow = match join_rule {
+		| JoinRule::Restricted(restricted) | JoinRule::KnockRestricted(restricted) =>
+			restricted.allow.as_slice(),
+		| _ => &[],
+	};
+
+	allow.iter().filter_map(|rule| match rule {
+		| AllowRule::RoomMembership(membership) => Some(membership.room_id.as_ref()),
+		| _ => None,
+	})
+}
+
 // TODO deserializing the member, power, join_rules event contents is done in
 // conduit just before this is called. Could they be passed in?
 /// Does the user who sent this member event have required power levels to do
@@ -1138,24 +1153,30 @@ fn verify_third_party_invite(
 
 #[cfg(test)]
 mod tests {
-	use ruma::events::{
-		StateEventType, TimelineEventType,
-		room::{
-			join_rules::{
-				AllowRule, JoinRule, Restricted, RoomJoinRulesEventContent, RoomMembership,
+	use std::{collections::HashMap, future::ready};
+
+	use ruma::{
+		EventId, OwnedEventId, RoomId,
+		events::{
+			StateEventType, TimelineEventType,
+			room::{
+				join_rules::{
+					AllowRule, JoinRule, Restricted, RoomJoinRulesEventContent, RoomMembership,
+				},
+				member::{MembershipState, RoomMemberEventContent},
 			},
-			member::{MembershipState, RoomMemberEventContent},
 		},
+		room_id, user_id,
 	};
-	use serde_json::value::to_raw_value as to_raw_json_value;
+	use serde_json::{json, value::to_raw_value as to_raw_json_value};
 
 	use crate::{
 		matrix::{Event, EventTypeExt, Pdu as PduEvent},
 		state_res::{
 			RoomVersion, StateMap,
-			event_auth::valid_membership_change,
+			event_auth::{auth_check, restricted_join_rooms, valid_membership_change},
 			test_utils::{
-				INITIAL_EVENTS, INITIAL_EVENTS_CREATE_ROOM, alice, charlie, ella, event_id,
+				INITIAL_EVENTS, INITIAL_EVENTS_CREATE_ROOM, alice, bob, charlie, ella, event_id,
 				member_content_ban, member_content_join, room_id, to_pdu_event,
 			},
 		},
@@ -1461,6 +1482,336 @@ fn test_restricted_join_rule() {
 		);
 	}
 
+	#[test]
+	fn test_restricted_join_via_space() {
+		let _ = tracing::subscriber::set_default(
+			tracing_subscriber::fmt()
+				.with_test_writer()
+				.finish(),
+		);
+		let mut events = INITIAL_EVENTS();
+		*events.get_mut(&event_id("IPOWER")).unwrap() = to_pdu_event(
+			"IPOWER",
+			alice(),
+			TimelineEventType::RoomPowerLevels,
+			Some(""),
+			to_raw_json_value(&json!({ "users": { alice(): 100 }, "invite": 50 })).unwrap(),
+			&["CREATE", "IMA"],
+			&["IMA"],
+		);
+		*events.get_mut(&event_id("IJR")).unwrap() = to_pdu_event(
+			"IJR",
+			alice(),
+			TimelineEventType::RoomJoinRules,
+			Some(""),
+			to_raw_json_value(&RoomJoinRulesEventContent::new(JoinRule::Restricted(
+				Restricted::new(vec![AllowRule::RoomMembership(RoomMembership::new(
+					room_id!("!space:foo").to_owned(),
+				))]),
+			)))
+			.unwrap(),
+			&["CREATE", "IMA", "IPOWER"],
+			&["IPOWER"],
+		);
+
+		let auth_events = events
+			.values()
+			.map(|ev| {
+				(
+					ev.event_type()
+						.with_state_key(ev.state_key().unwrap()),
+					ev.clone(),
+				)
+			})
+			.collect::<StateMap<_>>();
+
+		let requester = to_pdu_event(
+			"HELLO",
+			ella(),
+			TimelineEventType::RoomMember,
+			Some(ella().as_str()),
+			to_raw_json_value(&RoomMemberEventContent::new(MembershipState::Join)).unwrap(),
+			&["CREATE", "IJR", "IPOWER", "new"],
+			&["new"],
+		);
+
+		let fetch_state = |ty, key| auth_events.get(&(ty, key)).cloned();
+		let target_user = ella();
+		let sender = ella();
+		let join_via = |auth_user, auth_user_membership| {
+			valid_membership_change(
+				&RoomVersion::V9,
+				target_user,
+				fetch_state(StateEventType::RoomMember, target_user.as_str().into()).as_ref(),
+				sender,
+				fetch_state(StateEventType::RoomMember, sender.as_str().into()).as_ref(),
+				&requester,
+				None::<&PduEvent>,
+				fetch_state(StateEventType::RoomPowerLevels, "".into()).as_ref(),
+				fetch_state(StateEventType::RoomJoinRules, "".into()).as_ref(),
+				auth_user,
+				auth_user_membership,
+				&fetch_state(StateEventType::RoomCreate, "".into()).unwrap(),
+			)
+			.unwrap()
+		};
+
+		// Authorised by a joined member with the power to invite.
+		assert!(join_via(Some(alice()), &MembershipState::Join));
+
+		// Authorised by a joined member below the invite level.
+		assert!(!join_via(Some(bob()), &MembershipState::Join));
+
+		// Authorised by a member with power who has since left.
+		assert!(!join_via(Some(alice()), &MembershipState::Leave));
+
+		// No authorising user.
+		assert!(!join_via(None, &MembershipState::Leave));
+	}
+
+	#[test]
+	fn test_knock_restricted_join_via_space() {
+		let _ = tracing::subscriber::set_default(
+			tracing_subscriber::fmt()
+				.with_test_writer()
+				.finish(),
+		);
+		let mut events = INITIAL_EVENTS();
+		*events.get_mut(&event_id("IJR")).unwrap() = to_pdu_event(
+			"IJR",
+			alice(),
+			TimelineEventType::RoomJoinRules,
+			Some(""),
+			to_raw_json_value(&RoomJoinRulesEventContent::new(JoinRule::KnockRestricted(
+				Restricted::new(vec![AllowRule::RoomMembership(RoomMembership::new(
+					room_id!("!space:foo").to_owned(),
+				))]),
+			)))
+			.unwrap(),
+			&["CREATE", "IMA", "IPOWER"],
+			&["IPOWER"],
+		);
+
+		let auth_events = events
+			.values()
+			.map(|ev| {
+				(
+					ev.event_type()
+						.with_state_key(ev.state_key().unwrap()),
+					ev.clone(),
+				)
+			})
+			.collect::<StateMap<_>>();
+
+		let requester = to_pdu_event(
+			"HELLO",
+			ella(),
+			TimelineEventType::RoomMember,
+			Some(ella().as_str()),
+			to_raw_json_value(&RoomMemberEventContent::new(MembershipState::Join)).unwrap(),
+			&["CREATE", "IJR", "IPOWER", "new"],
+			&["new"],
+		);
+
+		let fetch_state = |ty, key| auth_events.get(&(ty, key)).cloned();
+		let target_user = ella();
+		let sender = ella();
+		let join_in = |room_version| {
+			valid_membership_change(
+				room_version,
+				target_user,
+				fetch_state(StateEventType::RoomMember, target_user.as_str().into()).as_ref(),
+				sender,
+				fetch_state(StateEventType::RoomMember, sender.as_str().into()).as_ref(),
+				&requester,
+				None::<&PduEvent>,
+				fetch_state(StateEventType::RoomPowerLevels, "".into()).as_ref(),
+				fetch_state(StateEventType::RoomJoinRules, "".into()).as_ref(),
+				Some(alice()),
+				&MembershipState::Join,
+				&fetch_state(StateEventType::RoomCreate, "".into()).unwrap(),
+			)
+			.unwrap()
+		};
+
+		assert!(join_in(&RoomVersion::V10));
+
+		// knock_restricted is not supported before room version 10.
+		assert!(!join_in(&RoomVersion::V9));
+	}
+
+	#[test]
+	fn test_restricted_join_decided_by_space_membership() {
+		let _ = tracing::subscriber::set_default(
+			tracing_subscriber::fmt()
+				.with_test_writer()
+				.finish(),
+		);
+		let space = room_id!("!space:foo");
+		let other_space = room_id!("!other:foo");
+		let unrelated = room_id!("!unrelated:foo");
+		let allow = || {
+			Restricted::new(vec![
+				AllowRule::RoomMembership(RoomMembership::new(space.to_owned())),
+				AllowRule::RoomMembership(RoomMembership::new(other_space.to_owned())),
+			])
+		};
+
+		for join_rule in [JoinRule::Restricted(allow()), JoinRule::KnockRestricted(allow())] {
+			let mut events = INITIAL_EVENTS();
+			*events.get_mut(&event_id("IJR")).unwrap() = to_pdu_event(
+				"IJR",
+				alice(),
+				TimelineEventType::RoomJoinRules,
+				Some(""),
+				to_raw_json_value(&RoomJoinRulesEventContent::new(join_rule.clone())).unwrap(),
+				&["CREATE", "IMA", "IPOWER"],
+				&["IPOWER"],
+			);
+
+			let auth_events = events
+				.values()
+				.map(|ev| {
+					(
+						ev.event_type()
+							.with_state_key(ev.state_key().unwrap()),
+						ev.clone(),
+					)
+				})
+				.collect::<StateMap<_>>();
+
+			let requester = to_pdu_event(
+				"HELLO",
+				ella(),
+				TimelineEventType::RoomMember,
+				Some(ella().as_str()),
+				to_raw_json_value(&RoomMemberEventContent::new(MembershipState::Join)).unwrap(),
+				&["CREATE", "IJR", "IPOWER", "new"],
+				&["new"],
+			);
+
+			let fetch_state = |ty, key| auth_events.get(&(ty, key)).cloned();
+			let target_user = ella();
+			let sender = ella();
+
+			// The resident server only names a user authorising the join when the
+			// joining user is in one of the allowed rooms.
+			let join_as_member_of = |rooms: &[&RoomId]| {
+				let authoriser = restricted_join_rooms(&join_rule)
+					.any(|allowed| rooms.contains(&allowed))
+					.then(alice);
+
+				valid_membership_change(
+					&RoomVersion::V10,
+					target_user,
+					fetch_state(StateEventType::RoomMember, target_user.as_str().into()).as_ref(),
+					sender,
+					fetch_state(StateEventType::RoomMember, sender.as_str().into()).as_ref(),
+					&requester,
+					None::<&PduEvent>,
+					fetch_state(StateEventType::RoomPowerLevels, "".into()).as_ref(),
+					fetch_state(StateEventType::RoomJoinRules, "".into()).as_ref(),
+					authoriser,
+					&MembershipState::Join,
+					&fetch_state(StateEventType::RoomCreate, "".into()).unwrap(),
+				)
+				.unwrap()
+			};
+
+			// A member of the allowed space.
+			assert!(join_as_member_of(&[space]));
+
+			// A member of the second of the allowed spaces.
+			assert!(join_as_member_of(&[unrelated, other_space]));
+
+			// Only a member of a room which is not allowed.
+			assert!(!join_as_member_of(&[unrelated]));
+
+			// Not a member of any room.
+			assert!(!join_as_member_of(&[]));
+		}
+	}
+
+	#[test]
+	fn test_restricted_join_rooms() {
+		let space = room_id!("!space:foo");
+		let restricted = Restricted::new(vec![AllowRule::RoomMembership(RoomMembership::new(
+			space.to_owned(),
+		))]);
+
+		let rooms: Vec<_> =
+			restricted_join_rooms(&JoinRule::Restricted(restricted.clone())).collect();
+		assert_eq!(rooms, [space]);
+
+		let rooms: Vec<_> =
+			restricted_join_rooms(&JoinRule::KnockRestricted(restricted)).collect();
+		assert_eq!(rooms, [space]);
+
+		let empty = JoinRule::Restricted(Restricted::new(Vec::new()));
+		assert_eq!(restricted_join_rooms(&empty).count(), 0);
+		assert_eq!(restricted_join_rooms(&JoinRule::Public).count(), 0);
+		assert_eq!(restricted_join_rooms(&JoinRule::Invite).count(), 0);
+	}
+
+	#[tokio::test]
+	async fn test_unfederated_room() {
+		let _ = tracing::subscriber::set_default(
+			tracing_subscriber::fmt()
+				.with_test_writer()
+				.finish(),
+		);
+		let remote_join = |events: &HashMap<OwnedEventId, PduEvent>| {
+			let auth_events = events
+				.values()
+				.map(|ev| {
+					(
+						ev.event_type()
+							.with_state_key(ev.state_key().unwrap()),
+						ev.clone(),
+					)
+				})
+				.collect::<StateMap<_>>();
+
+			let requester = to_pdu_event(
+				"HELLO",
+				user_id!("@mallory:bar"),
+				TimelineEventType::RoomMember,
+				Some("@mallory:bar"),
+				member_content_join(),
+				&["CREATE", "IJR", "IPOWER"],
+				&["IMC"],
+			);
+
+			async move {
+				let fetch_state = |ty: &StateEventType, key: &str| {
+					ready(
+						auth_events
+							.get(&(ty.clone(), key.into()))
+							.cloned(),
+					)
+				};
+
+				auth_check(&RoomVersion::V6, &requester, None, fetch_state)
+					.await
+					.unwrap()
+			}
+		};
+
+		let mut events = INITIAL_EVENTS();
+		assert!(remote_join(&events).await);
+
+		*events.get_mut(&event_id("CREATE")).unwrap() = to_pdu_event::<&EventId>(
+			"CREATE",
+			alice(),
+			TimelineEventType::RoomCreate,
+			Some(""),
+			to_raw_json_value(&json!({ "creator": alice(), "m.federate": false })).unwrap(),
+			&[],
+			&[],
+		);
+		assert!(!remote_join(&events).await);
+	}
+
 	#[test]
 	fn test_knock() {
 		let _ = tracing::subscriber::set_default(
@@ -0,0 +1,55 @@
+use super::StreamToken;
+use crate::matrix::PduCount;
+
+#[test]
+fn normal_round_trip() {
+	let token: StreamToken = "987654".parse().expect("parse() failed");
+
+	assert_eq!(token.pdu_count(), PduCount::Normal(987_654));
+	assert_eq!(token.global(), 987_654);
+	assert_eq!(token.to_string(), "987654");
+}
+
+#[test]
+fn backfilled_round_trip() {
+	let token: StreamToken = "-42".parse().expect("parse() failed");
+
+	assert_eq!(token.pdu_count(), PduCount::Backfilled(-42));
+	assert_eq!(token.to_string(), "-42");
+}
+
+#[test]
+fn backfilled_precedes_global_stream() {
+	let token: StreamToken = "-42".parse().expect("parse() failed");
+
+	assert_eq!(token.global(), 0);
+}
+
+#[test]
+fn issued_as_bare_count() {
+	let token = StreamToken::from(1234_u64);
+
+	assert_eq!(token.to_string(), "1234");
+	assert_eq!(token.to_string().parse::<StreamToken>().ok(), Some(token));
+}
+
+#[test]
+fn ordered_by_count() {
+	let earlier = StreamToken::from(5_u64);
+	let later = StreamToken::from(6_u64);
+
+	assert!(earlier < later);
+}
+
+#[test]
+fn unknown_version_rejected() {
+	assert!("v1_1234".parse::<StreamToken>().is_err());
+	assert!("v0_1234".parse::<StreamToken>().is_err());
+}
+
+#[test]
+fn malformed_rejected() {
+	for token in ["", "v", "v1", "vx_1234", "abc", "12ab", "1.5"] {
+		assert!(token.parse::<StreamToken>().is_err(), "accepted {token:?}");
+	}
+}
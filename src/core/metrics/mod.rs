@@ -1,4 +1,10 @@
-use std::sync::atomic::AtomicU32;
+#[cfg(test)]
+mod tests;
+
+use std::{
+	fmt::{self, Write},
+	sync::atomic::{AtomicU32, AtomicU64, Ordering},
+};
 
 use tokio::runtime;
 use tokio_metrics::TaskMonitor;
@@ -22,6 +28,20 @@ pub struct Metrics {
 	pub requests_handle_active: AtomicU32,
 	pub requests_handle_finished: AtomicU32,
 	pub requests_panic: AtomicU32,
+
+	/// Streamed federation /state and /state_ids responses.
+	pub federation_state_responses: AtomicU64,
+	pub federation_state_response_bytes: AtomicU64,
+	pub federation_state_response_bytes_max: AtomicU64,
+
+	/// Federation transactions since startup, summed over all servers.
+	pub federation_pdus_received: AtomicU64,
+	pub federation_edus_received: AtomicU64,
+	pub federation_bytes_received: AtomicU64,
+	pub federation_pdus_sent: AtomicU64,
+	pub federation_edus_sent: AtomicU64,
+	pub federation_bytes_sent: AtomicU64,
+	pub federation_send_failures: AtomicU64,
 }
 
 impl Metrics {
@@ -51,6 +71,18 @@ pub fn new(runtime: Option<runtime::Handle>) -> Self {
 			requests_handle_active: AtomicU32::new(0),
 			requests_handle_finished: AtomicU32::new(0),
 			requests_panic: AtomicU32::new(0),
+
+			federation_state_responses: AtomicU64::new(0),
+			federation_state_response_bytes: AtomicU64::new(0),
+			federation_state_response_bytes_max: AtomicU64::new(0),
+
+			federation_pdus_received: AtomicU64::new(0),
+			federation_edus_received: AtomicU64::new(0),
+			federation_bytes_received: AtomicU64::new(0),
+			federation_pdus_sent: AtomicU64::new(0),
+			federation_edus_sent: AtomicU64::new(0),
+			federation_bytes_sent: AtomicU64::new(0),
+			federation_send_failures: AtomicU64::new(0),
 		}
 	}
 
@@ -77,4 +109,76 @@ pub fn num_workers(&self) -> usize {
 	pub fn runtime_metrics(&self) -> Option<&runtime::RuntimeMetrics> {
 		self.runtime_metrics.as_ref()
 	}
+
+	/// Writes the counters in the Prometheus text exposition format.
+	pub fn export<W: Write>(&self, out: &mut W) -> fmt::Result {
+		let load = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+		let load32 = |counter: &AtomicU32| u64::from(counter.load(Ordering::Relaxed));
+		let metrics = [
+			("requests_handle_active", "gauge", load32(&self.requests_handle_active)),
+			("requests_handle_finished", "counter", load32(&self.requests_handle_finished)),
+			("requests_panic", "counter", load32(&self.requests_panic)),
+			("federation_state_responses", "counter", load(&self.federation_state_responses)),
+			(
+				"federation_state_response_bytes",
+				"counter",
+				load(&self.federation_state_response_bytes),
+			),
+			(
+				"federation_state_response_bytes_max",
+				"gauge",
+				load(&self.federation_state_response_bytes_max),
+			),
+			("federation_pdus_received", "counter", load(&self.federation_pdus_received)),
+			("federation_edus_received", "counter", load(&self.federation_edus_received)),
+			("federation_bytes_received", "counter", load(&self.federation_bytes_received)),
+			("federation_pdus_sent", "counter", load(&self.federation_pdus_sent)),
+			("federation_edus_sent", "counter", load(&self.federation_edus_sent)),
+			("federation_bytes_sent", "counter", load(&self.federation_bytes_sent)),
+			("federation_send_failures", "counter", load(&self.federation_send_failures)),
+		];
+
+		for (name, kind, value) in metrics {
+			write_type(out, name, kind)?;
+			write_sample(out, name, &[], value)?;
+		}
+
+		Ok(())
+	}
+}
+
+/// Writes the type of a metric, once before its samples. Names are prefixed
+/// with `tuwunel_`.
+pub fn write_type<W: Write>(out: &mut W, name: &str, kind: &str) -> fmt::Result {
+	writeln!(out, "# TYPE tuwunel_{name} {kind}")
+}
+
+/// Writes one sample of a metric with its labels.
+pub fn write_sample<W: Write>(
+	out: &mut W,
+	name: &str,
+	labels: &[(&str, &str)],
+	value: u64,
+) -> fmt::Result {
+	write!(out, "tuwunel_{name}")?;
+	for (i, (label, val)) in labels.iter().enumerate() {
+		let open = if i == 0 { '{' } else { ',' };
+		write!(out, "{open}{label}=\"")?;
+		for c in val.chars() {
+			match c {
+				| '\\' => out.write_str("\\\\")?,
+				| '"' => out.write_str("\\\"")?,
+				| '\n' => out.write_str("\\n")?,
+				| c => out.write_char(c)?,
+			}
+		}
+
+		out.write_char('"')?;
+	}
+
+	if !labels.is_empty() {
+		out.write_char('}')?;
+	}
+
+	writeln!(out, " {value}")
 }
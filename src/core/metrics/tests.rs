@@ -0,0 +1,26 @@
+use super::{write_sample, write_type};
+
+#[test]
+fn sample_without_labels() {
+	let mut out = String::new();
+	write_type(&mut out, "requests_panic", "counter").unwrap();
+	write_sample(&mut out, "requests_panic", &[], 3).unwrap();
+
+	assert_eq!(out, "# TYPE tuwunel_requests_panic counter\ntuwunel_requests_panic 3\n");
+}
+
+#[test]
+fn sample_with_labels() {
+	let mut out = String::new();
+	write_sample(&mut out, "pdus", &[("server", "example.com"), ("dir", "in")], 7).unwrap();
+
+	assert_eq!(out, "tuwunel_pdus{server=\"example.com\",dir=\"in\"} 7\n");
+}
+
+#[test]
+fn label_values_escaped() {
+	let mut out = String::new();
+	write_sample(&mut out, "x", &[("v", "a\"b\\c\nd")], 0).unwrap();
+
+	assert_eq!(out, "tuwunel_x{v=\"a\\\"b\\\\c\\nd\"} 0\n");
+}
@@ -23,7 +23,7 @@
 pub use error::Error;
 pub use info::{rustc_flags_capture, version, version::version};
 pub use matrix::{
-	Event, EventTypeExt, Pdu, PduCount, PduEvent, PduId, RoomVersion, pdu, state_res,
+	Event, EventTypeExt, Pdu, PduCount, PduEvent, PduId, RoomVersion, StreamToken, pdu, state_res,
 };
 pub use server::Server;
 pub use utils::{ctor, dtor, implement, result, result::Result};
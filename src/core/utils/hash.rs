@@ -1,10 +1,67 @@
 mod argon;
+mod legacy;
 pub mod sha256;
 
 use crate::Result;
 
-pub fn verify_password(password: &str, password_hash: &str) -> Result {
-	argon::verify_password(password, password_hash)
+/// Argon2id parameters for new password hashes, read from the config when a
+/// password is hashed so that a reload applies to the next hash. Existing
+/// hashes are verified with their own parameters.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PasswordParams {
+	pub m_cost: u32,
+	pub t_cost: u32,
+	pub p_cost: u32,
 }
 
-pub fn password(password: &str) -> Result<String> { argon::password(password) }
+/// Defaults to 19456 KiB memory, 2 iterations and parallelism 1:
+/// * <https://cheatsheetseries.owasp.org/cheatsheets/Password_Storage_Cheat_Sheet.html#argon2id>
+impl Default for PasswordParams {
+	fn default() -> Self { Self { m_cost: 19_456, t_cost: 2, p_cost: 1 } }
+}
+
+/// Fails when Argon2 would refuse the parameters.
+pub fn check_password_params(params: &PasswordParams) -> Result { argon::check(params) }
+
+/// Verifies the password against its hash. `legacy_pepper` is appended to the
+/// password for imported Synapse bcrypt hashes.
+pub fn verify_password(
+	password: &str,
+	password_hash: &str,
+	legacy_pepper: Option<&str>,
+) -> Result {
+	if legacy::is_legacy(password_hash) {
+		legacy::verify_password(password, password_hash, legacy_pepper)
+	} else {
+		argon::verify_password(password, password_hash)
+	}
+}
+
+/// Whether a hash which verified should be replaced by a new hash of the
+/// password: it is an imported legacy hash, or Argon2 with other than the
+/// given parameters.
+#[must_use]
+pub fn password_needs_rehash(password_hash: &str, params: &PasswordParams) -> bool {
+	legacy::is_legacy(password_hash) || argon::is_outdated(password_hash, params)
+}
+
+pub fn password(password: &str, params: &PasswordParams) -> Result<String> {
+	argon::password(password, params)
+}
+
+/// Fills `key` with a key derived from the passphrase and a salt of at least
+/// eight bytes.
+pub fn derive_key(passphrase: &[u8], salt: &[u8], key: &mut [u8]) -> Result {
+	argon::derive_key(passphrase, salt, key)
+}
+
+/// Compares secrets, such as configured tokens, in time independent of their
+/// contents. The digests are compared so the lengths are not revealed either.
+#[must_use]
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+	let (a, b) = (sha256::hash(a), sha256::hash(b));
+	a.iter()
+		.zip(b.iter())
+		.fold(0_u8, |diff, (a, b)| diff | (a ^ b))
+		== 0
+}
@@ -1,68 +1,99 @@
-use std::sync::OnceLock;
-
 use argon2::{
 	Algorithm, Argon2, Params, PasswordHash, PasswordHasher, PasswordVerifier, Version,
 	password_hash, password_hash::SaltString,
 };
 
+use super::PasswordParams;
 use crate::{Error, Result, err};
 
-const M_COST: u32 = Params::DEFAULT_M_COST; // memory size in 1 KiB blocks
-const T_COST: u32 = Params::DEFAULT_T_COST; // nr of iterations
-const P_COST: u32 = Params::DEFAULT_P_COST; // parallelism
-
-static ARGON: OnceLock<Argon2<'static>> = OnceLock::new();
-
-fn init_argon() -> Argon2<'static> {
-	// 19456 Kib blocks, iterations = 2, parallelism = 1
-	// * <https://cheatsheetseries.owasp.org/cheatsheets/Password_Storage_Cheat_Sheet.html#argon2id>
-	debug_assert!(M_COST == 19_456, "M_COST default changed");
-	debug_assert!(T_COST == 2, "T_COST default changed");
-	debug_assert!(P_COST == 1, "P_COST default changed");
+pub(super) fn check(params: &PasswordParams) -> Result { argon_params(params).map(|_| ()) }
 
-	let algorithm = Algorithm::Argon2id;
-	let version = Version::default();
-	let out_len: Option<usize> = None;
-	let params = Params::new(M_COST, T_COST, P_COST, out_len).expect("valid parameters");
-	Argon2::new(algorithm, version, params)
-}
-
-pub(super) fn password(password: &str) -> Result<String> {
+pub(super) fn password(password: &str, params: &PasswordParams) -> Result<String> {
 	let salt = SaltString::generate(rand::thread_rng());
-	ARGON
-		.get_or_init(init_argon)
+	Argon2::new(Algorithm::Argon2id, Version::default(), argon_params(params)?)
 		.hash_password(password.as_bytes(), &salt)
 		.map(|it| it.to_string())
 		.map_err(map_err)
 }
 
+/// Argon2id with the default parameters, for keys derived from passphrases.
+pub(super) fn derive_key(passphrase: &[u8], salt: &[u8], key: &mut [u8]) -> Result {
+	Argon2::default()
+		.hash_password_into(passphrase, salt, key)
+		.map_err(|e| err!("Failed to derive key: {e}"))
+}
+
+/// Verification takes the algorithm and parameters from the hash itself.
 pub(super) fn verify_password(password: &str, password_hash: &str) -> Result<()> {
 	let password_hash = PasswordHash::new(password_hash).map_err(map_err)?;
-	ARGON
-		.get_or_init(init_argon)
+	Argon2::default()
 		.verify_password(password.as_bytes(), &password_hash)
 		.map_err(map_err)
 }
 
+/// Whether the hash was made with other than Argon2id and the given
+/// parameters.
+pub(super) fn is_outdated(password_hash: &str, current: &PasswordParams) -> bool {
+	let Ok(password_hash) = PasswordHash::new(password_hash) else {
+		return false;
+	};
+
+	password_hash.algorithm != Algorithm::Argon2id.ident()
+		|| Params::try_from(&password_hash)
+			.ok()
+			.is_none_or(|params| {
+				params.m_cost() != current.m_cost
+					|| params.t_cost() != current.t_cost
+					|| params.p_cost() != current.p_cost
+			})
+}
+
+fn argon_params(params: &PasswordParams) -> Result<Params> {
+	Params::new(params.m_cost, params.t_cost, params.p_cost, None)
+		.map_err(|e| err!(Config("password_hash_memory_cost", "Invalid Argon2 parameters: {e}")))
+}
+
 fn map_err(e: password_hash::Error) -> Error { err!("{e}") }
 
 #[cfg(test)]
 mod tests {
+	use crate::utils::hash::{self, PasswordParams};
+
 	#[test]
 	fn password_hash_and_verify() {
-		use crate::utils::hash;
 		let preimage = "temp123";
-		let digest = hash::password(preimage).expect("digest");
-		hash::verify_password(preimage, &digest).expect("verified");
+		let digest = hash::password(preimage, &PasswordParams::default()).expect("digest");
+		hash::verify_password(preimage, &digest, None).expect("verified");
 	}
 
 	#[test]
 	#[should_panic(expected = "unverified")]
 	fn password_hash_and_verify_fail() {
-		use crate::utils::hash;
 		let preimage = "temp123";
 		let fakeimage = "temp321";
-		let digest = hash::password(preimage).expect("digest");
-		hash::verify_password(fakeimage, &digest).expect("unverified");
+		let digest = hash::password(preimage, &PasswordParams::default()).expect("digest");
+		hash::verify_password(fakeimage, &digest, None).expect("unverified");
+	}
+
+	#[test]
+	fn password_hash_current_not_outdated() {
+		let params = PasswordParams::default();
+		let digest = hash::password("temp123", &params).expect("digest");
+		assert!(!hash::password_needs_rehash(&digest, &params));
+	}
+
+	#[test]
+	fn password_hash_outdated_after_params_change() {
+		let old = PasswordParams { m_cost: 8192, t_cost: 1, p_cost: 1 };
+		let digest = hash::password("temp123", &old).expect("digest");
+		hash::verify_password("temp123", &digest, None).expect("verified");
+		assert!(hash::password_needs_rehash(&digest, &PasswordParams::default()));
+	}
+
+	#[test]
+	fn password_params_invalid() {
+		let params = PasswordParams { m_cost: 1, t_cost: 0, p_cost: 1 };
+		assert!(hash::check_password_params(&params).is_err());
+		assert!(hash::password("temp123", &params).is_err());
 	}
 }
@@ -0,0 +1,69 @@
+//! Verification of password hashes imported from other homeservers: bcrypt as
+//! used by Synapse, including its optional pepper, and PBKDF2 in PHC format.
+//! These are only verified; successful logins replace them with Argon2.
+
+use argon2::{PasswordHash, PasswordVerifier};
+use pbkdf2::Pbkdf2;
+
+use crate::{Err, Result, err};
+
+const BCRYPT_PREFIXES: [&str; 3] = ["$2a$", "$2b$", "$2y$"];
+
+pub(super) fn is_legacy(password_hash: &str) -> bool {
+	is_bcrypt(password_hash) || password_hash.starts_with("$pbkdf2")
+}
+
+pub(super) fn verify_password(
+	password: &str,
+	password_hash: &str,
+	pepper: Option<&str>,
+) -> Result {
+	if is_bcrypt(password_hash) {
+		let password = [password, pepper.unwrap_or_default()].concat();
+		return match bcrypt::verify(password, password_hash) {
+			| Ok(true) => Ok(()),
+			| Ok(false) => Err!("Password does not match."),
+			| Err(e) => Err!("{e}"),
+		};
+	}
+
+	let password_hash = PasswordHash::new(password_hash).map_err(|e| err!("{e}"))?;
+	Pbkdf2
+		.verify_password(password.as_bytes(), &password_hash)
+		.map_err(|e| err!("{e}"))
+}
+
+fn is_bcrypt(password_hash: &str) -> bool {
+	BCRYPT_PREFIXES
+		.iter()
+		.any(|prefix| password_hash.starts_with(prefix))
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::utils::hash::{self, PasswordParams};
+
+	#[test]
+	fn bcrypt_verify_and_rehash() {
+		let digest = bcrypt::hash("temp123", 4).expect("digest");
+		hash::verify_password("temp123", &digest, None).expect("verified");
+		assert!(hash::verify_password("temp321", &digest, None).is_err());
+		assert!(hash::password_needs_rehash(&digest, &PasswordParams::default()));
+	}
+
+	#[test]
+	fn bcrypt_verify_peppered() {
+		let digest = bcrypt::hash("temp123pepper", 4).expect("digest");
+		hash::verify_password("temp123", &digest, Some("pepper")).expect("verified");
+		assert!(hash::verify_password("temp123", &digest, None).is_err());
+	}
+
+	#[test]
+	fn pbkdf2_verify_and_rehash() {
+		let digest = "$pbkdf2-sha256$i=1000,\
+		              l=32$c2FsdHNhbHRzYWx0c2FsdA$OgTDyblHbTvUb3CPVZSYAe5wlYIPbMl1ENNnDdpW70I";
+		hash::verify_password("temp123", digest, None).expect("verified");
+		assert!(hash::verify_password("temp321", digest, None).is_err());
+		assert!(hash::password_needs_rehash(digest, &PasswordParams::default()));
+	}
+}
@@ -12,6 +12,7 @@
 pub mod mutex_map;
 pub mod rand;
 pub mod result;
+pub mod secret;
 pub mod set;
 pub mod stream;
 pub mod string;
@@ -19,6 +20,7 @@
 #[cfg(test)]
 mod tests;
 pub mod time;
+pub mod totp;
 
 pub use ::ctor::{ctor, dtor};
 pub use ::tuwunel_macros::implement;
@@ -33,7 +35,7 @@
 	html::Escape as HtmlEscape,
 	json::{deserialize_from_str, to_canonical_object},
 	math::clamp,
-	mutex_map::{Guard as MutexMapGuard, MutexMap},
+	mutex_map::{Guard as MutexMapGuard, LockStats, MutexMap},
 	rand::{shuffle, string as random_string},
 	stream::{IterStream, ReadyExt, Tools as StreamTools, TryReadyExt},
 	string::{str_from_bytes, string_from_bytes},
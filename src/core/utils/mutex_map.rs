@@ -1,7 +1,12 @@
 use std::{
 	fmt::Debug,
 	hash::Hash,
-	sync::{Arc, TryLockError::WouldBlock},
+	sync::{
+		Arc,
+		TryLockError::WouldBlock,
+		atomic::{AtomicU64, Ordering},
+	},
+	time::{Duration, Instant},
 };
 
 use tokio::sync::OwnedMutexGuard as Omg;
@@ -11,18 +16,56 @@
 /// Map of Mutexes
 pub struct MutexMap<Key, Val> {
 	map: Map<Key, Val>,
+	stats: Stats,
 }
 
 pub struct Guard<Key, Val> {
 	map: Map<Key, Val>,
+	held_since: HeldSince,
 	val: Omg<Val>,
 }
 
+/// Snapshot of lock acquisition statistics for a MutexMap.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LockStats {
+	/// Total number of locks acquired.
+	pub acquired: u64,
+
+	/// Number of acquisitions which had to wait for another holder.
+	pub contended: u64,
+
+	/// Cumulative time spent waiting by contended acquisitions.
+	pub wait_total: Duration,
+
+	/// Longest time any single acquisition waited.
+	pub wait_max: Duration,
+}
+
+struct Stats {
+	/// Reference point of the acquisition times recorded by each lock.
+	epoch: Instant,
+	acquired: AtomicU64,
+	contended: AtomicU64,
+	wait_total: AtomicU64,
+	wait_max: AtomicU64,
+}
+
+/// Entry of the map: the lock, and when its holder acquired it.
+#[derive(Default)]
+struct Slot<Val> {
+	val: Value<Val>,
+	held_since: HeldSince,
+}
+
 type Map<Key, Val> = Arc<MapMutex<Key, Val>>;
-type MapMutex<Key, Val> = std::sync::Mutex<HashMap<Key, Val>>;
-type HashMap<Key, Val> = std::collections::HashMap<Key, Value<Val>>;
+type MapMutex<Key, Val> = std::sync::Mutex<HashMap<Key, Slot<Val>>>;
+type HashMap<Key, Val> = std::collections::HashMap<Key, Val>;
 type Value<Val> = Arc<tokio::sync::Mutex<Val>>;
 
+/// Microseconds after `Stats::epoch` at which the lock was acquired, plus one;
+/// zero while it is not held.
+type HeldSince = Arc<AtomicU64>;
+
 impl<Key, Val> MutexMap<Key, Val>
 where
 	Key: Clone + Eq + Hash + Send,
@@ -32,6 +75,7 @@ impl<Key, Val> MutexMap<Key, Val>
 	pub fn new() -> Self {
 		Self {
 			map: Map::new(MapMutex::new(HashMap::new())),
+			stats: Stats::default(),
 		}
 	}
 
@@ -42,18 +86,24 @@ pub async fn lock<'a, K>(&'a self, k: &'a K) -> Guard<Key, Val>
 		Key: TryFrom<&'a K>,
 		<Key as TryFrom<&'a K>>::Error: Debug,
 	{
-		let val = self
+		let (val, held_since) = self
 			.map
 			.lock()
 			.expect("locked")
 			.entry(k.try_into().expect("failed to construct key"))
 			.or_default()
-			.clone();
+			.clone_parts();
 
-		Guard::<Key, Val> {
-			map: Arc::clone(&self.map),
-			val: val.lock_owned().await,
-		}
+		let (val, waited) = match Arc::clone(&val).try_lock_owned() {
+			| Ok(val) => (val, None),
+			| Err(_) => {
+				let timer = Instant::now();
+				let val = val.lock_owned().await;
+				(val, Some(timer.elapsed()))
+			},
+		};
+
+		self.guard(val, held_since, waited)
 	}
 
 	#[tracing::instrument(level = "trace", skip(self))]
@@ -63,20 +113,19 @@ pub fn try_lock<'a, K>(&self, k: &'a K) -> Result<Guard<Key, Val>>
 		Key: TryFrom<&'a K>,
 		<Key as TryFrom<&'a K>>::Error: Debug,
 	{
-		let val = self
+		let (val, held_since) = self
 			.map
 			.lock()
 			.expect("locked")
 			.entry(k.try_into().expect("failed to construct key"))
 			.or_default()
-			.clone();
+			.clone_parts();
 
-		Ok(Guard::<Key, Val> {
-			map: Arc::clone(&self.map),
-			val: val
-				.try_lock_owned()
-				.map_err(|_| err!("would yield"))?,
-		})
+		let val = val
+			.try_lock_owned()
+			.map_err(|_| err!("would yield"))?;
+
+		Ok(self.guard(val, held_since, None))
 	}
 
 	#[tracing::instrument(level = "trace", skip(self))]
@@ -86,7 +135,7 @@ pub fn try_try_lock<'a, K>(&self, k: &'a K) -> Result<Guard<Key, Val>>
 		Key: TryFrom<&'a K>,
 		<Key as TryFrom<&'a K>>::Error: Debug,
 	{
-		let val = self
+		let (val, held_since) = self
 			.map
 			.try_lock()
 			.map_err(|e| match e {
@@ -95,14 +144,59 @@ pub fn try_try_lock<'a, K>(&self, k: &'a K) -> Result<Guard<Key, Val>>
 			})?
 			.entry(k.try_into().expect("failed to construct key"))
 			.or_default()
-			.clone();
+			.clone_parts();
+
+		let val = val
+			.try_lock_owned()
+			.map_err(|_| err!("would yield"))?;
+
+		Ok(self.guard(val, held_since, None))
+	}
+
+	fn guard(
+		&self,
+		val: Omg<Val>,
+		held_since: HeldSince,
+		waited: Option<Duration>,
+	) -> Guard<Key, Val> {
+		self.stats.acquire(&held_since, waited);
 
-		Ok(Guard::<Key, Val> {
+		Guard::<Key, Val> {
 			map: Arc::clone(&self.map),
-			val: val
-				.try_lock_owned()
-				.map_err(|_| err!("would yield"))?,
-		})
+			held_since,
+			val,
+		}
+	}
+
+	/// Locks which have been held for longer than `threshold`, with the time
+	/// each has been held so far.
+	#[must_use]
+	pub fn held_longer_than(&self, threshold: Duration) -> Vec<(Key, Duration)> {
+		let now = self.stats.now();
+		self.map
+			.lock()
+			.expect("locked")
+			.iter()
+			.filter_map(|(key, slot)| {
+				let since = slot
+					.held_since
+					.load(Ordering::Relaxed)
+					.checked_sub(1)?;
+				let held = Duration::from_micros(now.saturating_sub(since));
+
+				(held > threshold).then(|| (key.clone(), held))
+			})
+			.collect()
+	}
+
+	#[must_use]
+	pub fn stats(&self) -> LockStats {
+		LockStats {
+			acquired: self.stats.acquired.load(Ordering::Relaxed),
+			contended: self.stats.contended.load(Ordering::Relaxed),
+			wait_total: Duration::from_micros(self.stats.wait_total.load(Ordering::Relaxed)),
+			wait_max: Duration::from_micros(self.stats.wait_max.load(Ordering::Relaxed)),
+		}
 	}
 
 	#[must_use]
@@ -126,10 +220,51 @@ fn default() -> Self { Self::new() }
 impl<Key, Val> Drop for Guard<Key, Val> {
 	#[tracing::instrument(name = "unlock", level = "trace", skip_all)]
 	fn drop(&mut self) {
+		self.held_since.store(0, Ordering::Relaxed);
+
 		if Arc::strong_count(Omg::mutex(&self.val)) <= 2 {
-			self.map.lock().expect("locked").retain(|_, val| {
-				!Arc::ptr_eq(val, Omg::mutex(&self.val)) || Arc::strong_count(val) > 2
-			});
+			self.map
+				.lock()
+				.expect("locked")
+				.retain(|_, slot| {
+					!Arc::ptr_eq(&slot.val, Omg::mutex(&self.val))
+						|| Arc::strong_count(&slot.val) > 2
+				});
+		}
+	}
+}
+
+impl<Val> Slot<Val> {
+	fn clone_parts(&self) -> (Value<Val>, HeldSince) {
+		(Arc::clone(&self.val), Arc::clone(&self.held_since))
+	}
+}
+
+impl Stats {
+	fn acquire(&self, held_since: &AtomicU64, waited: Option<Duration>) {
+		self.acquired.fetch_add(1, Ordering::Relaxed);
+		if let Some(waited) = waited {
+			let waited = u64::try_from(waited.as_micros()).unwrap_or(u64::MAX);
+			self.contended.fetch_add(1, Ordering::Relaxed);
+			self.wait_total
+				.fetch_add(waited, Ordering::Relaxed);
+			self.wait_max.fetch_max(waited, Ordering::Relaxed);
+		}
+
+		held_since.store(self.now().saturating_add(1), Ordering::Relaxed);
+	}
+
+	fn now(&self) -> u64 { u64::try_from(self.epoch.elapsed().as_micros()).unwrap_or(u64::MAX) }
+}
+
+impl Default for Stats {
+	fn default() -> Self {
+		Self {
+			epoch: Instant::now(),
+			acquired: AtomicU64::default(),
+			contended: AtomicU64::default(),
+			wait_total: AtomicU64::default(),
+			wait_max: AtomicU64::default(),
 		}
 	}
 }
@@ -0,0 +1,75 @@
+//! Encryption of small secrets stored at rest, keyed by an operator-provided
+//! passphrase. Each secret has its own salt, from which and the passphrase its
+//! key is derived with Argon2id.
+
+use ring::{
+	aead::{AES_256_GCM, Aad, LessSafeKey, NONCE_LEN, Nonce, UnboundKey},
+	rand::{SecureRandom, SystemRandom},
+};
+
+use super::hash;
+use crate::{Err, Result, err};
+
+const SALT_LEN: usize = 16;
+
+const KEY_LEN: usize = 32;
+
+/// Encrypt `plaintext` bound to `context` (e.g. the owning user ID). The
+/// output is the random salt and nonce followed by the ciphertext and tag.
+pub fn seal(passphrase: &str, context: &[u8], plaintext: &[u8]) -> Result<Vec<u8>> {
+	let mut salt = [0_u8; SALT_LEN];
+	random_bytes(&mut salt)?;
+
+	let mut nonce = [0_u8; NONCE_LEN];
+	random_bytes(&mut nonce)?;
+
+	let mut sealed = plaintext.to_vec();
+	key(passphrase, &salt)?
+		.seal_in_place_append_tag(
+			Nonce::assume_unique_for_key(nonce),
+			Aad::from(context),
+			&mut sealed,
+		)
+		.map_err(|_| err!("Failed to encrypt secret."))?;
+
+	Ok([&salt[..], &nonce[..], &sealed].concat())
+}
+
+/// Decrypt the output of [`seal`] given the same passphrase and context.
+pub fn open(passphrase: &str, context: &[u8], sealed: &[u8]) -> Result<Vec<u8>> {
+	let Some((salt, sealed)) = sealed.split_at_checked(SALT_LEN) else {
+		return Err!("Encrypted secret is truncated.");
+	};
+
+	let Some((nonce, ciphertext)) = sealed.split_at_checked(NONCE_LEN) else {
+		return Err!("Encrypted secret is truncated.");
+	};
+
+	let nonce = Nonce::try_assume_unique_for_key(nonce)
+		.map_err(|_| err!("Encrypted secret has an invalid nonce."))?;
+
+	let mut plaintext = ciphertext.to_vec();
+	let len = key(passphrase, salt)?
+		.open_in_place(nonce, Aad::from(context), &mut plaintext)
+		.map_err(|_| err!("Failed to decrypt secret; was the key changed?"))?
+		.len();
+
+	plaintext.truncate(len);
+	Ok(plaintext)
+}
+
+/// Fill `buf` from the system's secure random number generator.
+pub fn random_bytes(buf: &mut [u8]) -> Result {
+	SystemRandom::new()
+		.fill(buf)
+		.map_err(|_| err!("Failed to generate random bytes."))
+}
+
+fn key(passphrase: &str, salt: &[u8]) -> Result<LessSafeKey> {
+	let mut key = [0_u8; KEY_LEN];
+	hash::derive_key(passphrase.as_bytes(), salt, &mut key)?;
+
+	UnboundKey::new(&AES_256_GCM, &key)
+		.map(LessSafeKey::new)
+		.map_err(|_| err!("Invalid secret encryption key."))
+}
@@ -1,4 +1,5 @@
 pub mod compute;
+pub mod memory;
 pub mod storage;
 
 use std::path::PathBuf;
@@ -156,6 +156,51 @@ pub fn getcpu() -> Result<usize> {
 #[inline]
 pub fn getcpu() -> Result<usize> { Err(crate::Error::Io(std::io::ErrorKind::Unsupported.into())) }
 
+/// Set the nice level of this thread.
+#[cfg(target_os = "linux")]
+pub fn set_nice(nice: i32) -> Result {
+	use crate::{Error, utils::math};
+
+	// SAFETY: Always successful; returns the caller's thread ID.
+	let tid: libc::id_t = math::try_into(unsafe { libc::gettid() })?;
+
+	// SAFETY: Only alters the scheduling priority of this thread.
+	if unsafe { libc::setpriority(libc::PRIO_PROCESS, tid, nice) } == -1 {
+		return Err(Error::from_errno());
+	}
+
+	Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn set_nice(_nice: i32) -> Result {
+	Err(crate::Error::Io(std::io::ErrorKind::Unsupported.into()))
+}
+
+/// Set the best-effort IO priority level (0-7) of this thread.
+#[cfg(target_os = "linux")]
+pub fn set_io_priority(level: u8) -> Result {
+	use crate::Error;
+
+	const IOPRIO_WHO_PROCESS: libc::c_int = 1;
+	const IOPRIO_CLASS_BE: libc::c_int = 2 << 13;
+
+	let prio = IOPRIO_CLASS_BE | libc::c_int::from(level.min(7));
+
+	// SAFETY: Only alters the IO priority of this thread; a tid of 0 is the
+	// calling thread.
+	if unsafe { libc::syscall(libc::SYS_ioprio_set, IOPRIO_WHO_PROCESS, 0, prio) } == -1 {
+		return Err(Error::from_errno());
+	}
+
+	Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn set_io_priority(_level: u8) -> Result {
+	Err(crate::Error::Io(std::io::ErrorKind::Unsupported.into()))
+}
+
 fn query_cores_available() -> impl Iterator<Item = Id> {
 	core_affinity::get_core_ids()
 		.unwrap_or_default()
@@ -0,0 +1,43 @@
+//! System utilities related to memory
+
+use std::{fs, sync::LazyLock};
+
+/// Memory available to the process in bytes (at startup).
+static TOTAL_MEMORY: LazyLock<Option<u64>> = LazyLock::new(query_total_memory);
+
+/// Physical memory available to the process in bytes, limited by the cgroup
+/// the process runs in, if any. None when it cannot be determined.
+#[inline]
+#[must_use]
+pub fn total_memory() -> Option<u64> { *TOTAL_MEMORY }
+
+fn query_total_memory() -> Option<u64> {
+	let physical = physical_memory();
+	let cgroup = cgroup_memory_limit();
+
+	physical.into_iter().chain(cgroup).min()
+}
+
+#[cfg(unix)]
+fn physical_memory() -> Option<u64> {
+	// SAFETY: sysconf() has no preconditions.
+	let (pages, page_size) =
+		unsafe { (libc::sysconf(libc::_SC_PHYS_PAGES), libc::sysconf(libc::_SC_PAGESIZE)) };
+
+	let pages = u64::try_from(pages).ok()?;
+	let page_size = u64::try_from(page_size).ok()?;
+
+	pages.checked_mul(page_size)
+}
+
+#[cfg(not(unix))]
+fn physical_memory() -> Option<u64> { None }
+
+/// The cgroup v2 memory limit; "max" means unlimited.
+fn cgroup_memory_limit() -> Option<u64> {
+	fs::read_to_string("/sys/fs/cgroup/memory.max")
+		.ok()?
+		.trim()
+		.parse()
+		.ok()
+}
@@ -108,6 +108,51 @@ async fn mutex_map_contend() {
 	assert!(map.is_empty(), "Must be empty");
 }
 
+#[tokio::test]
+async fn mutex_map_held() {
+	use std::time::Duration;
+
+	use crate::utils::MutexMap;
+
+	let map = MutexMap::<String, ()>::new();
+	let lock = map.lock("foo").await;
+	let _other = map.lock("bar").await;
+	tokio::time::sleep(Duration::from_millis(2)).await;
+
+	let held = map.held_longer_than(Duration::ZERO);
+	assert_eq!(held.len(), 2, "both locks must be held");
+	assert!(
+		map.held_longer_than(Duration::from_secs(3600))
+			.is_empty()
+	);
+
+	drop(lock);
+	let held = map.held_longer_than(Duration::ZERO);
+	assert_eq!(held.len(), 1, "only one lock must be held");
+	assert_eq!(held[0].0, "bar");
+
+	let stats = map.stats();
+	assert_eq!(stats.acquired, 2);
+	assert_eq!(stats.contended, 0);
+}
+
+#[tokio::test]
+async fn mutex_map_reacquired() {
+	use std::time::Duration;
+
+	use crate::utils::MutexMap;
+
+	let map = MutexMap::<String, ()>::new();
+	let lock = map.lock("foo").await;
+	assert!(map.try_lock("foo").is_err(), "must not lock twice");
+	drop(lock);
+
+	let _lock = map.try_lock("foo").expect("unlocked");
+	tokio::time::sleep(Duration::from_millis(2)).await;
+	assert_eq!(map.held_longer_than(Duration::ZERO).len(), 1);
+	assert_eq!(map.stats().acquired, 2);
+}
+
 #[test]
 #[allow(clippy::iter_on_single_items, clippy::many_single_char_names)]
 fn set_intersection_none() {
@@ -276,3 +321,74 @@ async fn set_intersection_sorted_stream2() {
 		.await;
 	assert!(r.eq(&["ccc", "ggg", "iii"]));
 }
+
+#[test]
+fn totp_rfc6238_vectors() {
+	use utils::totp::{code, step};
+
+	let secret = b"12345678901234567890";
+	assert_eq!(code(secret, step(59)), 287_082);
+	assert_eq!(code(secret, step(1_111_111_109)), 81_804);
+	assert_eq!(code(secret, step(1_111_111_111)), 50_471);
+	assert_eq!(code(secret, step(1_234_567_890)), 5_924);
+	assert_eq!(code(secret, step(2_000_000_000)), 279_037);
+}
+
+#[test]
+fn totp_verify_window() {
+	use utils::totp::{step, verify};
+
+	let secret = b"12345678901234567890";
+	let time = 1_111_111_109;
+	assert_eq!(verify(secret, "081804", time), Some(step(time)));
+	assert_eq!(verify(secret, " 081804 ", time + 30), Some(step(time)));
+	assert_eq!(verify(secret, "081804", time + 90), None);
+	assert_eq!(verify(secret, "81804", time), None);
+	assert_eq!(verify(secret, "+81804", time), None);
+}
+
+#[test]
+fn totp_base32() {
+	use utils::totp::base32;
+
+	assert_eq!(base32(b""), "");
+	assert_eq!(base32(b"f"), "MY");
+	assert_eq!(base32(b"fo"), "MZXQ");
+	assert_eq!(base32(b"foobar"), "MZXW6YTBOI");
+	assert_eq!(base32(b"12345678901234567890"), "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ");
+}
+
+#[test]
+fn secret_seal_open() {
+	use utils::secret::{open, seal};
+
+	let sealed = seal("passphrase", b"@alice:example.com", b"secret").unwrap();
+	assert_ne!(&sealed[sealed.len() - 6..], b"secret");
+	assert_eq!(open("passphrase", b"@alice:example.com", &sealed).unwrap(), b"secret");
+	assert!(open("other", b"@alice:example.com", &sealed).is_err());
+	assert!(open("passphrase", b"@bob:example.com", &sealed).is_err());
+}
+
+#[test]
+fn hash_constant_time_eq() {
+	use utils::hash::constant_time_eq;
+
+	assert!(constant_time_eq(b"token", b"token"));
+	assert!(constant_time_eq(b"", b""));
+	assert!(!constant_time_eq(b"token", b"tokem"));
+	assert!(!constant_time_eq(b"token", b"token2"));
+	assert!(!constant_time_eq(b"", b"token"));
+}
+
+#[test]
+fn hash_derive_key() {
+	use utils::hash::derive_key;
+
+	let (mut a, mut b, mut c) = ([0_u8; 32], [0_u8; 32], [0_u8; 32]);
+	derive_key(b"passphrase", b"salt1234", &mut a).unwrap();
+	derive_key(b"passphrase", b"salt1234", &mut b).unwrap();
+	derive_key(b"passphrase", b"salt5678", &mut c).unwrap();
+	assert_eq!(a, b);
+	assert_ne!(a, c);
+	assert!(derive_key(b"passphrase", b"salt", &mut c).is_err());
+}
@@ -39,6 +39,12 @@ pub fn parse_duration(duration: &str) -> Result<Duration> {
 		.map_err(|error| err!("'{duration:?}' is not a valid duration string: {error:?}"))
 }
 
+pub fn parse_rfc3339(ts: &str) -> Result<SystemTime> {
+	chrono::DateTime::parse_from_rfc3339(ts)
+		.map(SystemTime::from)
+		.map_err(|error| err!("'{ts:?}' is not a valid RFC 3339 timestamp: {error}"))
+}
+
 #[must_use]
 pub fn rfc2822_from_seconds(epoch: i64) -> String {
 	use chrono::{DateTime, Utc};
@@ -15,3 +15,105 @@ fn ser_str(b: &mut test::Bencher) {
 		let _s = serialize_to_vec(key).expect("failed to serialize user_id");
 	});
 }
+
+/// Number of appends between write-ahead log flushes when batched, as for the
+/// PDUs of one federation transaction.
+#[cfg(tuwunel_bench)]
+const APPEND_BURST: u64 = 50;
+
+#[cfg(tuwunel_bench)]
+#[cfg_attr(tuwunel_bench, bench)]
+fn append_writes_individual(b: &mut test::Bencher) { append_writes(b, false); }
+
+#[cfg(tuwunel_bench)]
+#[cfg_attr(tuwunel_bench, bench)]
+fn append_writes_batched(b: &mut test::Bencher) { append_writes(b, true); }
+
+/// Writes the indexes of a timeline append through the maps: each key on its
+/// own with a flush per append, or in one write batch with a flush per burst
+/// of appends.
+#[cfg(tuwunel_bench)]
+fn append_writes(b: &mut test::Bencher, batched: bool) {
+	use crate::Batch;
+
+	let runtime = tokio::runtime::Runtime::new().expect("runtime built");
+	let (db, path) = open_scratch(&runtime);
+	let pduid_pdu = &db["pduid_pdu"];
+	let eventid_pduid = &db["eventid_pduid"];
+	let eventid_outlierpdu = &db["eventid_outlierpdu"];
+
+	let pdu = [0_u8; 512];
+	let mut count: u64 = 0;
+	let mut burst = None;
+	b.iter(|| {
+		count = count.wrapping_add(1);
+		let pdu_id = count.to_be_bytes();
+		let event_id = format!("$event{count}");
+
+		if batched {
+			burst.get_or_insert_with(|| db.cork_and_flush());
+
+			let mut batch = Batch::default();
+			batch.insert(pduid_pdu, &pdu_id, pdu);
+			batch.insert(eventid_pduid, &event_id, pdu_id);
+			batch.remove(eventid_outlierpdu, &event_id);
+			batch.commit();
+
+			if count % APPEND_BURST == 0 {
+				burst.take();
+			}
+		} else {
+			let _cork = db.cork_and_flush();
+			pduid_pdu.insert(&pdu_id, pdu);
+			eventid_pduid.insert(&event_id, pdu_id);
+			eventid_outlierpdu.remove(&event_id);
+		}
+	});
+
+	drop(burst);
+	drop(db);
+	std::fs::remove_dir_all(path).ok();
+}
+
+/// Opens a new database in a temporary directory.
+#[cfg(tuwunel_bench)]
+fn open_scratch(
+	runtime: &tokio::runtime::Runtime,
+) -> (std::sync::Arc<crate::Database>, std::path::PathBuf) {
+	use std::{
+		process,
+		sync::{
+			Arc,
+			atomic::{AtomicU64, Ordering},
+		},
+	};
+
+	use tuwunel_core::{
+		Server,
+		config::{Config, Figment},
+		log::{Log, LogLevelReloadHandles, capture},
+	};
+
+	static RUN: AtomicU64 = AtomicU64::new(0);
+
+	let run = RUN.fetch_add(1, Ordering::Relaxed);
+	let path = std::env::temp_dir().join(format!("tuwunel_bench_{}_{run}", process::id()));
+
+	let raw = Figment::new()
+		.merge(("server_name", "bench.example"))
+		.merge(("database_path", &path));
+
+	let config = Config::new(&raw).expect("valid config");
+	let log = Log {
+		reload: LogLevelReloadHandles::default(),
+		capture: Arc::new(capture::State::new()),
+		profiler: None,
+	};
+
+	let server = Arc::new(Server::new(config, Some(runtime.handle().clone()), log));
+	let db = runtime
+		.block_on(crate::Database::open(&server))
+		.expect("database opened");
+
+	(db, path)
+}
@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::{cell::Cell, future::Future, sync::Arc};
 
 use crate::{Database, Engine};
 
@@ -8,6 +8,12 @@ pub struct Cork {
 	sync: bool,
 }
 
+tokio::task_local! {
+	/// Set within a `deferred_flush()` scope; records whether a cork released
+	/// in the scope skipped its flush.
+	static DEFERRED: Cell<bool>;
+}
+
 impl Database {
 	#[inline]
 	#[must_use]
@@ -20,6 +26,28 @@ pub fn cork_and_flush(&self) -> Cork { Cork::new(&self.db, true, false) }
 	#[inline]
 	#[must_use]
 	pub fn cork_and_sync(&self) -> Cork { Cork::new(&self.db, true, true) }
+
+	/// Runs the future deferring the flushes of corks released by this task
+	/// until it completes, then flushes once. This groups the writes of a
+	/// burst of operations, each of which would otherwise flush on its own.
+	pub async fn deferred_flush<F: Future>(&self, fut: F) -> F::Output {
+		if DEFERRED.try_with(|_| ()).is_ok() {
+			return fut.await;
+		}
+
+		let (pending, output) = DEFERRED
+			.scope(Cell::new(false), async {
+				let output = fut.await;
+				(DEFERRED.with(Cell::get), output)
+			})
+			.await;
+
+		if pending {
+			self.db.flush().ok();
+		}
+
+		output
+	}
 }
 
 impl Cork {
@@ -33,6 +61,15 @@ pub(super) fn new(db: &Arc<Engine>, flush: bool, sync: bool) -> Self {
 impl Drop for Cork {
 	fn drop(&mut self) {
 		self.db.uncork();
+		if self.flush
+			&& !self.sync
+			&& DEFERRED
+				.try_with(|pending| pending.set(true))
+				.is_ok()
+		{
+			return;
+		}
+
 		if self.flush {
 			self.db.flush().ok();
 		}
@@ -7,7 +7,9 @@
 mod logger;
 mod memory_usage;
 mod open;
+mod profile;
 mod repair;
+mod wal_archive;
 
 use std::{
 	ffi::CStr,
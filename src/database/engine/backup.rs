@@ -1,12 +1,12 @@
 use std::{ffi::OsString, path::PathBuf};
 
-use rocksdb::backup::{BackupEngine, BackupEngineOptions};
+use rocksdb::backup::{BackupEngine, BackupEngineOptions, RestoreOptions};
 use tuwunel_core::{
 	Err, Result, error, implement, info, utils::time::rfc2822_from_seconds, warn,
 };
 
 use super::Engine;
-use crate::util::map_err;
+use crate::{Context, util::map_err};
 
 #[implement(Engine)]
 #[tracing::instrument(skip(self))]
@@ -36,6 +36,15 @@ pub fn backup(&self) -> Result {
 		}
 	}
 
+	if config.database_wal_archive_path.is_some() {
+		if let Some(oldest) = engine.get_backup_info().first() {
+			let before = u64::try_from(oldest.timestamp)?.saturating_mul(1000);
+			if let Err(e) = self.wal_archive_prune(before) {
+				error!("Failed to prune WAL archive: {e:?}");
+			}
+		}
+	}
+
 	if config.database_backups_to_keep == 0 {
 		warn!("Configuration item `database_backups_to_keep` is set to 0.");
 	}
@@ -71,17 +80,41 @@ pub fn backup_count(&self) -> Result<usize> {
 	Ok(info.len())
 }
 
+/// Replace the database with the latest backup. The database must not be
+/// open.
+#[implement(Engine)]
+#[tracing::instrument(skip_all)]
+pub(super) fn restore_latest_backup(ctx: &Context) -> Result {
+	let path = &ctx.server.config.database_path;
+	let mut engine = Self::open_backup_engine(ctx)?;
+	let Some(info) = engine.get_backup_info().pop() else {
+		return Err!("No backups found.");
+	};
+
+	warn!(
+		backup = info.backup_id,
+		created = rfc2822_from_seconds(info.timestamp),
+		"Restoring database from backup..."
+	);
+
+	engine
+		.restore_from_latest_backup(path, path, &RestoreOptions::default())
+		.map_err(map_err)
+}
+
+#[implement(Engine)]
+fn backup_engine(&self) -> Result<BackupEngine> { Self::open_backup_engine(&self.ctx) }
+
 #[implement(Engine)]
-fn backup_engine(&self) -> Result<BackupEngine> {
-	let path = self.backup_path()?;
+fn open_backup_engine(ctx: &Context) -> Result<BackupEngine> {
+	let path = Self::backup_path(ctx)?;
 	let options = BackupEngineOptions::new(path).map_err(map_err)?;
-	BackupEngine::open(&options, &*self.ctx.env.lock()?).map_err(map_err)
+	BackupEngine::open(&options, &*ctx.env.lock()?).map_err(map_err)
 }
 
 #[implement(Engine)]
-fn backup_path(&self) -> Result<OsString> {
-	let path = self
-		.ctx
+fn backup_path(ctx: &Context) -> Result<OsString> {
+	let path = ctx
 		.server
 		.config
 		.database_backup_path
@@ -1,11 +1,15 @@
 use rocksdb::{
 	BlockBasedIndexType, BlockBasedOptions, BlockBasedPinningTier, Cache,
-	DBCompressionType as CompressionType, DataBlockIndexType, FifoCompactOptions,
-	LruCacheOptions, Options, UniversalCompactOptions, UniversalCompactionStopStyle,
+	DBCompactionStyle as CompactionStyle, DBCompressionType as CompressionType,
+	DataBlockIndexType, FifoCompactOptions, LruCacheOptions, Options, UniversalCompactOptions,
+	UniversalCompactionStopStyle,
 };
-use tuwunel_core::{Config, Result, err, utils::math::Expected};
+use tuwunel_core::{Config, Result, config::CacheKind, utils::math::Expected};
 
-use super::descriptor::{CacheDisp, Descriptor};
+use super::{
+	descriptor::{CacheDisp, Descriptor},
+	profile::{Tuning, compression_type},
+};
 use crate::{Context, util::map_err};
 
 pub(super) const SENTINEL_COMPRESSION_LEVEL: i32 = 32767;
@@ -16,20 +20,25 @@
 pub(crate) fn cf_options(ctx: &Context, opts: Options, desc: &Descriptor) -> Result<Options> {
 	let cache = get_cache(ctx, desc);
 	let config = &ctx.server.config;
-	descriptor_cf_options(opts, *desc, config, cache.as_ref())
+	descriptor_cf_options(opts, *desc, config, &ctx.tuning, cache.as_ref())
 }
 
 fn descriptor_cf_options(
 	mut opts: Options,
 	mut desc: Descriptor,
 	config: &Config,
+	tuning: &Tuning,
 	cache: Option<&Cache>,
 ) -> Result<Options> {
 	set_compression(&mut desc, config);
 	set_table_options(&mut opts, &desc, cache)?;
 
+	if desc.compaction != CompactionStyle::Fifo {
+		desc.compaction = tuning.compaction_style.unwrap_or(desc.compaction);
+	}
+
 	opts.set_min_write_buffer_number(1);
-	opts.set_max_write_buffer_number(2);
+	opts.set_max_write_buffer_number(tuning.max_write_buffers);
 	opts.set_write_buffer_size(desc.write_size);
 
 	opts.set_target_file_size_base(desc.file_size);
@@ -48,12 +57,19 @@ fn descriptor_cf_options(
 	opts.set_universal_compaction_options(&uc_options(&desc));
 	opts.set_fifo_compaction_options(&fifo_options(&desc));
 
-	let compression_shape: Vec<_> = desc
-		.compression_shape
-		.into_iter()
-		.map(|val| (val > 0).then_some(desc.compression))
-		.map(|val| val.unwrap_or(CompressionType::None))
-		.collect();
+	let compression_shape: Vec<_> = if tuning.compression_per_level.is_empty() {
+		desc.compression_shape
+			.into_iter()
+			.map(|val| (val > 0).then_some(desc.compression))
+			.map(|val| val.unwrap_or(CompressionType::None))
+			.collect()
+	} else {
+		let last = tuning.compression_per_level.last().copied();
+		(0..desc.compression_shape.len())
+			.map(|level| tuning.compression_per_level.get(level).copied())
+			.map(|val| val.or(last).unwrap_or(desc.compression))
+			.collect()
+	};
 
 	opts.set_compression_type(desc.compression);
 	opts.set_compression_per_level(compression_shape.as_slice());
@@ -114,15 +130,7 @@ fn set_table_options(opts: &mut Options, desc: &Descriptor, cache: Option<&Cache
 }
 
 fn set_compression(desc: &mut Descriptor, config: &Config) {
-	desc.compression = match config.rocksdb_compression_algo.as_ref() {
-		| "snappy" => CompressionType::Snappy,
-		| "zlib" => CompressionType::Zlib,
-		| "bz2" => CompressionType::Bz2,
-		| "lz4" => CompressionType::Lz4,
-		| "lz4hc" => CompressionType::Lz4hc,
-		| "none" => CompressionType::None,
-		| _ => CompressionType::Zstd,
-	};
+	desc.compression = compression_type(&config.rocksdb_compression_algo);
 
 	let can_override_level = config.rocksdb_compression_level == SENTINEL_COMPRESSION_LEVEL
 		&& desc.compression == CompressionType::Zstd;
@@ -198,19 +206,19 @@ fn get_cache(ctx: &Context, desc: &Descriptor) -> Option<Cache> {
 	// legacy-compat way
 	let config = &ctx.server.config;
 	let cap = match desc.name {
-		| "eventid_pduid" => Some(config.eventid_pdu_cache_capacity),
-		| "eventid_shorteventid" => Some(config.eventidshort_cache_capacity),
-		| "shorteventid_eventid" => Some(config.shorteventid_cache_capacity),
-		| "shorteventid_authchain" => Some(config.auth_chain_cache_capacity),
-		| "shortstatekey_statekey" => Some(config.shortstatekey_cache_capacity),
-		| "statekey_shortstatekey" => Some(config.statekeyshort_cache_capacity),
-		| "servernameevent_data" => Some(config.servernameevent_data_cache_capacity),
-		| "pduid_pdu" | "eventid_outlierpdu" => Some(config.pdu_cache_capacity),
+		| "eventid_pduid" => Some(CacheKind::EventIdPdu),
+		| "eventid_shorteventid" => Some(CacheKind::EventIdShort),
+		| "shorteventid_eventid" => Some(CacheKind::ShortEventId),
+		| "shorteventid_authchain" => Some(CacheKind::AuthChain),
+		| "shortstatekey_statekey" => Some(CacheKind::ShortStateKey),
+		| "statekey_shortstatekey" => Some(CacheKind::StateKeyShort),
+		| "servernameevent_data" => Some(CacheKind::ServerNameEventData),
+		| "pduid_pdu" | "eventid_outlierpdu" => Some(CacheKind::Pdu),
 		| _ => None,
 	}
-	.map(TryInto::try_into)
+	.map(|cache| config.cache_capacity(cache))
 	.transpose()
-	.expect("u32 to usize");
+	.expect("invalid cache size");
 
 	let ent_size: usize = desc
 		.key_size_hint
@@ -218,7 +226,9 @@ fn get_cache(ctx: &Context, desc: &Descriptor) -> Option<Cache> {
 		.expected_add(desc.val_size_hint.unwrap_or_default());
 
 	let size = match cap {
-		| Some(cap) => cache_size(config, cap, ent_size),
+		| Some(cap) => cap
+			.checked_mul(ent_size)
+			.expect("cache size is too large"),
 		| _ => desc.cache_size,
 	};
 
@@ -263,21 +273,3 @@ fn get_cache(ctx: &Context, desc: &Descriptor) -> Option<Cache> {
 		),
 	}
 }
-
-pub(crate) fn cache_size(config: &Config, base_size: u32, entity_size: usize) -> usize {
-	cache_size_f64(config, f64::from(base_size), entity_size)
-}
-
-#[allow(
-	clippy::as_conversions,
-	clippy::cast_sign_loss,
-	clippy::cast_possible_truncation
-)]
-pub(crate) fn cache_size_f64(config: &Config, base_size: f64, entity_size: usize) -> usize {
-	let ents = base_size * config.cache_capacity_modifier;
-
-	(ents as usize)
-		.checked_mul(entity_size)
-		.ok_or_else(|| err!(Config("cache_capacity_modifier", "Cache size is too large.")))
-		.expect("invalid cache size")
-}
@@ -4,8 +4,9 @@
 };
 
 use rocksdb::{Cache, Env, LruCacheOptions};
-use tuwunel_core::{Result, Server, debug, utils::math::usize_from_f64};
+use tuwunel_core::{Result, Server, debug};
 
+use super::profile::Tuning;
 use crate::{or_else, pool::Pool};
 
 /// Some components are constructed prior to opening the database and must
@@ -17,19 +18,21 @@ pub(crate) struct Context {
 	pub(crate) col_cache: Mutex<BTreeMap<String, Cache>>,
 	pub(crate) row_cache: Mutex<Cache>,
 	pub(crate) env: Mutex<Env>,
+	pub(crate) tuning: Tuning,
 	pub(crate) server: Arc<Server>,
 }
 
 impl Context {
 	pub(crate) fn new(server: &Arc<Server>) -> Result<Arc<Self>> {
 		let config = &server.config;
-		let cache_capacity_bytes = config.db_cache_capacity_mb * 1024.0 * 1024.0;
+		let tuning = Tuning::new(config)?;
+		tuning.log();
 
 		let col_shard_bits = 7;
-		let col_cache_capacity_bytes = usize_from_f64(cache_capacity_bytes * 0.50)?;
+		let col_cache_capacity_bytes = tuning.block_cache;
 
 		let row_shard_bits = 7;
-		let row_cache_capacity_bytes = usize_from_f64(cache_capacity_bytes * 0.50)?;
+		let row_cache_capacity_bytes = tuning.row_cache;
 
 		let mut row_cache_opts = LruCacheOptions::default();
 		row_cache_opts.set_num_shard_bits(row_shard_bits);
@@ -57,6 +60,7 @@ pub(crate) fn new(server: &Arc<Server>) -> Result<Arc<Self>> {
 			col_cache: col_cache.into(),
 			row_cache: row_cache.into(),
 			env: env.into(),
+			tuning,
 			server: server.clone(),
 		}))
 	}
@@ -1,16 +1,19 @@
-use std::{cmp, convert::TryFrom};
-
 use rocksdb::{Cache, DBRecoveryMode, Env, LogLevel, Options, statistics::StatsLevel};
-use tuwunel_core::{Config, Result, utils};
+use tuwunel_core::{Config, Result, utils::math::try_into};
 
-use super::{cf_opts::cache_size_f64, logger::handle as handle_log};
+use super::{logger::handle as handle_log, profile::Tuning};
 
 /// Create database-wide options suitable for opening the database. This also
 /// sets our default column options in case of opening a column with the same
 /// resulting value. Note that we require special per-column options on some
 /// columns, therefor columns should only be opened after passing this result
 /// through cf_options().
-pub(crate) fn db_options(config: &Config, env: &Env, row_cache: &Cache) -> Result<Options> {
+pub(crate) fn db_options(
+	config: &Config,
+	tuning: &Tuning,
+	env: &Env,
+	row_cache: &Cache,
+) -> Result<Options> {
 	const DEFAULT_STATS_LEVEL: StatsLevel = if cfg!(debug_assertions) {
 		StatsLevel::ExceptDetailedTimers
 	} else {
@@ -23,8 +26,8 @@ pub(crate) fn db_options(config: &Config, env: &Env, row_cache: &Cache) -> Resul
 	set_logging_defaults(&mut opts, config);
 
 	// Processing
-	opts.set_max_background_jobs(num_threads::<i32>(config)?);
-	opts.set_max_subcompactions(num_threads::<u32>(config)?);
+	opts.set_max_background_jobs(try_into(tuning.background_jobs)?);
+	opts.set_max_subcompactions(try_into(tuning.background_jobs)?);
 	opts.set_avoid_unnecessary_blocking_io(true);
 	opts.set_max_file_opening_threads(0);
 
@@ -47,16 +50,12 @@ pub(crate) fn db_options(config: &Config, env: &Env, row_cache: &Cache) -> Resul
 
 	// Blocks
 	opts.set_row_cache(row_cache);
-	opts.set_db_write_buffer_size(cache_size_f64(
-		config,
-		config.db_write_buffer_capacity_mb,
-		1_048_576,
-	));
+	opts.set_db_write_buffer_size(tuning.write_buffer);
 
 	// Files
 	opts.set_table_cache_num_shard_bits(7);
-	opts.set_wal_size_limit_mb(1024);
-	opts.set_max_total_wal_size(1024 * 1024 * 512);
+	opts.set_wal_size_limit_mb(tuning.wal_size_limit_mb);
+	opts.set_max_total_wal_size(tuning.max_total_wal_size);
 	opts.set_writable_file_max_buffer_size(1024 * 1024 * 2);
 
 	// Misc
@@ -126,15 +125,3 @@ fn set_logging_defaults(opts: &mut Options, config: &Config) {
 		opts.set_callback_logger(rocksdb_log_level, &handle_log);
 	}
 }
-
-fn num_threads<T: TryFrom<usize>>(config: &Config) -> Result<T> {
-	const MIN_PARALLELISM: usize = 2;
-
-	let requested = if config.rocksdb_parallelism_threads != 0 {
-		config.rocksdb_parallelism_threads
-	} else {
-		utils::available_parallelism()
-	};
-
-	utils::math::try_into::<T, usize>(cmp::max(MIN_PARALLELISM, requested))
-}
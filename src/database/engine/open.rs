@@ -25,6 +25,7 @@ pub(crate) async fn open(ctx: Arc<Context>, desc: &[Descriptor]) -> Result<Arc<S
 
 	let db_opts = db_options(
 		config,
+		&ctx.tuning,
 		&ctx.env.lock().expect("environment locked"),
 		&ctx.row_cache.lock().expect("row cache locked"),
 	)?;
@@ -38,6 +39,10 @@ pub(crate) async fn open(ctx: Arc<Context>, desc: &[Descriptor]) -> Result<Arc<S
 		repair(&db_opts, &config.database_path)?;
 	}
 
+	if config.database_restore {
+		Self::restore_latest_backup(&ctx)?;
+	}
+
 	debug!("Opening database...");
 	let db = if config.rocksdb_read_only {
 		Db::open_cf_descriptors_read_only(&db_opts, path, cfds, false)
@@ -55,7 +60,7 @@ pub(crate) async fn open(ctx: Arc<Context>, desc: &[Descriptor]) -> Result<Arc<S
 		"Opened database."
 	);
 
-	Ok(Arc::new(Self {
+	let engine = Arc::new(Self {
 		db,
 		pool: ctx.pool.clone(),
 		ctx: ctx.clone(),
@@ -63,7 +68,13 @@ pub(crate) async fn open(ctx: Arc<Context>, desc: &[Descriptor]) -> Result<Arc<S
 		secondary: config.rocksdb_secondary,
 		checksums: config.rocksdb_checksums,
 		corks: AtomicU32::new(0),
-	}))
+	});
+
+	if config.database_restore && config.database_wal_archive_path.is_some() {
+		engine.wal_replay()?;
+	}
+
+	Ok(engine)
 }
 
 #[implement(Engine)]
@@ -0,0 +1,133 @@
+//! Tuning values resolved from `db_profile` and the individual overrides.
+
+use rocksdb::{DBCompactionStyle as CompactionStyle, DBCompressionType as CompressionType};
+use tuwunel_core::{
+	Config, Result,
+	config::DbProfile,
+	info,
+	utils::{self, bytes::pretty, math::usize_from_f64},
+};
+
+#[derive(Clone, Debug)]
+pub(crate) struct Tuning {
+	pub(crate) profile: DbProfile,
+
+	/// Block cache capacity in bytes, shared by columns without their own.
+	pub(crate) block_cache: usize,
+
+	/// Row cache capacity in bytes.
+	pub(crate) row_cache: usize,
+
+	/// Total write buffer capacity in bytes.
+	pub(crate) write_buffer: usize,
+
+	/// Write buffers each column may fill before writes stall.
+	pub(crate) max_write_buffers: i32,
+
+	/// Background flush and compaction jobs.
+	pub(crate) background_jobs: usize,
+
+	/// Live WAL size in bytes after which the oldest is freed by flushing.
+	pub(crate) max_total_wal_size: u64,
+
+	/// Archived WAL size to keep in megabytes.
+	pub(crate) wal_size_limit_mb: u64,
+
+	/// Compaction style replacing that of each column, except FIFO columns.
+	pub(crate) compaction_style: Option<CompactionStyle>,
+
+	/// Compression of each level from the top, replacing that of each column.
+	pub(crate) compression_per_level: Vec<CompressionType>,
+}
+
+const MIB: f64 = 1_048_576.0;
+
+impl Tuning {
+	pub(crate) fn new(config: &Config) -> Result<Self> {
+		let profile = config.db_profile;
+
+		// (cache scale, block cache share, write buffer scale, write buffers,
+		// max live WAL MiB, archived WAL MiB)
+		let (cache_scale, block_share, write_scale, max_write_buffers, max_wal_mb, wal_limit_mb) =
+			match profile {
+				| DbProfile::LowMemory => (0.25, 0.75, 0.5, 2, 128, 256),
+				| DbProfile::Balanced => (1.0, 0.5, 1.0, 2, 512, 1024),
+				| DbProfile::Throughput => (2.0, 0.5, 2.0, 4, 2048, 4096),
+			};
+
+		let cache_budget = config.db_cache_capacity_mb * cache_scale * MIB;
+		let block_cache = config
+			.db_block_cache_mb
+			.map_or(cache_budget * block_share, |mb| mb * MIB);
+
+		let row_cache = cache_budget * (1.0 - block_share);
+		let write_buffer = config.db_write_buffer_capacity_mb
+			* write_scale
+			* config.cache_capacity_modifier
+			* MIB;
+
+		let background_jobs = match config.rocksdb_parallelism_threads {
+			| 0 if profile == DbProfile::LowMemory => 2,
+			| 0 => utils::available_parallelism(),
+			| threads => threads,
+		};
+
+		Ok(Self {
+			profile,
+			block_cache: usize_from_f64(block_cache)?,
+			row_cache: usize_from_f64(row_cache)?,
+			write_buffer: usize_from_f64(write_buffer)?,
+			max_write_buffers,
+			background_jobs: background_jobs.max(2),
+			max_total_wal_size: config
+				.rocksdb_max_total_wal_size_mb
+				.unwrap_or(max_wal_mb)
+				.saturating_mul(1_048_576),
+			wal_size_limit_mb: config
+				.rocksdb_wal_size_limit_mb
+				.unwrap_or(wal_limit_mb),
+			compaction_style: config.rocksdb_compaction_style.as_deref().map(
+				|style| match style {
+					| "universal" => CompactionStyle::Universal,
+					| _ => CompactionStyle::Level,
+				},
+			),
+			compression_per_level: config
+				.rocksdb_compression_per_level
+				.iter()
+				.map(String::as_str)
+				.map(compression_type)
+				.collect(),
+		})
+	}
+
+	pub(crate) fn log(&self) {
+		info!(
+			profile = self.profile.as_str(),
+			block_cache = %pretty(self.block_cache),
+			row_cache = %pretty(self.row_cache),
+			write_buffer = %pretty(self.write_buffer),
+			max_write_buffers = self.max_write_buffers,
+			background_jobs = self.background_jobs,
+			max_total_wal_size = self.max_total_wal_size,
+			wal_size_limit_mb = self.wal_size_limit_mb,
+			compaction_style = ?self.compaction_style,
+			compression_per_level = ?self.compression_per_level,
+			"Database tuning."
+		);
+	}
+}
+
+/// Compression algorithm by its configured name, as accepted by the config
+/// check.
+pub(crate) fn compression_type(name: &str) -> CompressionType {
+	match name {
+		| "snappy" => CompressionType::Snappy,
+		| "zlib" => CompressionType::Zlib,
+		| "bz2" => CompressionType::Bz2,
+		| "lz4" => CompressionType::Lz4,
+		| "lz4hc" => CompressionType::Lz4hc,
+		| "none" => CompressionType::None,
+		| _ => CompressionType::Zstd,
+	}
+}
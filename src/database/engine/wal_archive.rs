@@ -0,0 +1,288 @@
+//! Continuous archiving of the write-ahead log and point-in-time restore; see
+//! `database_wal_archive_path`.
+//!
+//! The archive is a directory of segment files named by the sequence number of
+//! their first write batch. Each record is the batch's sequence number, the
+//! time it was archived and the length of the batch, all big-endian u64,
+//! followed by the batch itself.
+
+use std::{
+	ffi::OsStr,
+	fs,
+	io::{Read, Write},
+	path::{Path, PathBuf},
+	time::UNIX_EPOCH,
+};
+
+use rocksdb::WriteBatch;
+use tuwunel_core::{
+	Err, Result, debug, implement, info,
+	utils::time::{now_millis, parse_rfc3339},
+	warn,
+};
+
+use super::Engine;
+use crate::util::map_err;
+
+struct Record {
+	seq: u64,
+	ts: u64,
+	data: Vec<u8>,
+}
+
+const SEGMENT_EXT: &str = "wal";
+const HEADER_LEN: usize = 24;
+
+/// Append write batches since `next` to the archive, returning the sequence
+/// number following the last batch archived. Zero archives from the oldest WAL
+/// the database still retains.
+#[implement(Engine)]
+#[tracing::instrument(level = "debug", skip(self))]
+pub fn wal_archive(&self, next: u64) -> Result<u64> {
+	let dir = self.wal_archive_path()?;
+
+	self.flush()?;
+	if next > self.current_sequence() {
+		return Ok(next);
+	}
+
+	let ts = now_millis();
+	let (mut first, mut expect) = (None, next);
+	let mut segment = Vec::new();
+	for update in self.db.get_updates_since(next).map_err(map_err)? {
+		let (seq, batch) = update.map_err(map_err)?;
+		if seq < expect {
+			continue;
+		}
+
+		if seq > expect && next > 0 {
+			warn!(
+				expected = expect,
+				found = seq,
+				"WAL archive has a gap; writes were purged from the WAL before being archived."
+			);
+		}
+
+		first.get_or_insert(seq);
+		expect = seq.saturating_add(batch.len().try_into()?);
+		write_record(&mut segment, seq, ts, batch.data())?;
+	}
+
+	let Some(first) = first else {
+		return Ok(next);
+	};
+
+	let path = dir.join(format!("{first:020}.{SEGMENT_EXT}"));
+	let partial = path.with_extension("partial");
+	let mut file = fs::File::create(&partial)?;
+	file.write_all(&segment)?;
+	file.sync_all()?;
+	fs::rename(&partial, &path)?;
+
+	debug!(first, next = expect, bytes = segment.len(), "Archived WAL segment.");
+
+	Ok(expect)
+}
+
+/// Sequence number following the last batch in the archive, or zero when the
+/// archive is empty.
+#[implement(Engine)]
+pub fn wal_archive_next(&self) -> Result<u64> {
+	let dir = self.wal_archive_path()?;
+	let Some(last) = segments(&dir)?.pop() else {
+		return Ok(0);
+	};
+
+	let next = read_segment(&last)?
+		.last()
+		.map(|record| -> Result<u64> {
+			let count = WriteBatch::from_data(&record.data).len();
+
+			Ok(record.seq.saturating_add(count.try_into()?))
+		})
+		.transpose()?
+		.unwrap_or(0);
+
+	if next > self.current_sequence().saturating_add(1) {
+		return Err!(Database(
+			"WAL archive extends past the database at sequence {}; it was likely restored to an \
+			 earlier point. Move the archive aside and take a new backup to resume.",
+			self.current_sequence()
+		));
+	}
+
+	Ok(next)
+}
+
+/// Replay archived write batches newer than the database, stopping at
+/// `database_restore_until` when configured.
+#[implement(Engine)]
+#[tracing::instrument(skip(self))]
+pub(super) fn wal_replay(&self) -> Result {
+	let config = &self.ctx.server.config;
+	let dir = self.wal_archive_path()?;
+	let until = config
+		.database_restore_until
+		.as_deref()
+		.map(parse_rfc3339)
+		.transpose()?
+		.map(|until| {
+			let since_epoch = until
+				.duration_since(UNIX_EPOCH)
+				.unwrap_or_default();
+
+			u64::try_from(since_epoch.as_millis())
+		})
+		.transpose()?;
+
+	let start = self.current_sequence();
+	let mut next = start.saturating_add(1);
+	let mut batches = 0_usize;
+	'segments: for segment in segments(&dir)? {
+		for record in read_segment(&segment)? {
+			if record.seq < next {
+				continue;
+			}
+
+			if until.is_some_and(|until| record.ts > until) {
+				break 'segments;
+			}
+
+			if record.seq > next {
+				warn!(
+					expected = next,
+					found = record.seq,
+					"WAL archive has a gap; restore stops before it."
+				);
+				break 'segments;
+			}
+
+			let batch = WriteBatch::from_data(&record.data);
+			next = record.seq.saturating_add(batch.len().try_into()?);
+			self.db.write(batch).map_err(map_err)?;
+			batches = batches.saturating_add(1);
+		}
+	}
+
+	self.sync()?;
+	info!(
+		batches,
+		from = start,
+		sequence = self.current_sequence(),
+		until = ?config.database_restore_until,
+		"Replayed WAL archive."
+	);
+
+	Ok(())
+}
+
+/// Delete segments archived before `before` (milliseconds since the epoch),
+/// whose writes are all contained in a backup taken since. The newest segment
+/// is kept so archiving resumes where it left off. Returns the number deleted.
+#[implement(Engine)]
+#[tracing::instrument(level = "debug", skip(self))]
+pub(super) fn wal_archive_prune(&self, before: u64) -> Result<usize> {
+	let dir = self.wal_archive_path()?;
+	let mut segments = segments(&dir)?;
+	segments.pop();
+
+	let mut pruned = 0_usize;
+	for segment in segments {
+		if segment_time(&segment)? >= before {
+			break;
+		}
+
+		fs::remove_file(&segment)?;
+		pruned = pruned.saturating_add(1);
+	}
+
+	if pruned > 0 {
+		info!(pruned, "Pruned WAL archive segments older than the oldest backup.");
+	}
+
+	Ok(pruned)
+}
+
+#[implement(Engine)]
+fn wal_archive_path(&self) -> Result<PathBuf> {
+	let Some(path) = self
+		.ctx
+		.server
+		.config
+		.database_wal_archive_path
+		.clone()
+	else {
+		return Err!(Config(
+			"database_wal_archive_path",
+			"Configure path to enable WAL archiving"
+		));
+	};
+
+	fs::create_dir_all(&path)?;
+
+	Ok(path)
+}
+
+/// Complete segment files in the archive, oldest first.
+fn segments(dir: &Path) -> Result<Vec<PathBuf>> {
+	let mut segments: Vec<_> = fs::read_dir(dir)?
+		.map(|entry| entry.map(|entry| entry.path()))
+		.collect::<Result<_, _>>()?;
+
+	segments.retain(|path| path.extension() == Some(OsStr::new(SEGMENT_EXT)));
+	segments.sort();
+
+	Ok(segments)
+}
+
+/// Time the segment was archived, from the header of its first record.
+fn segment_time(path: &Path) -> Result<u64> {
+	let mut header = [0_u8; HEADER_LEN];
+	fs::File::open(path)?.read_exact(&mut header)?;
+
+	let ts = header[8..16]
+		.try_into()
+		.expect("eight bytes of header");
+
+	Ok(u64::from_be_bytes(ts))
+}
+
+fn read_segment(path: &Path) -> Result<Vec<Record>> {
+	let buf = fs::read(path)?;
+	let mut records = Vec::new();
+	let mut pos = 0_usize;
+	while pos < buf.len() {
+		let Some(header) = buf.get(pos..pos.saturating_add(HEADER_LEN)) else {
+			return Err!(Database("Truncated record header in WAL archive {path:?}"));
+		};
+
+		let [seq, ts, len] = [0, 8, 16].map(|i| {
+			let field = header[i..i.saturating_add(8)]
+				.try_into()
+				.expect("eight bytes of header");
+
+			u64::from_be_bytes(field)
+		});
+
+		let start = pos.saturating_add(HEADER_LEN);
+		let end = start.saturating_add(len.try_into()?);
+		let Some(data) = buf.get(start..end) else {
+			return Err!(Database("Truncated record in WAL archive {path:?}"));
+		};
+
+		records.push(Record { seq, ts, data: data.to_vec() });
+		pos = end;
+	}
+
+	Ok(records)
+}
+
+fn write_record(out: &mut Vec<u8>, seq: u64, ts: u64, data: &[u8]) -> Result {
+	let len: u64 = data.len().try_into()?;
+	out.extend_from_slice(&seq.to_be_bytes());
+	out.extend_from_slice(&ts.to_be_bytes());
+	out.extend_from_slice(&len.to_be_bytes());
+	out.extend_from_slice(data);
+
+	Ok(())
+}
@@ -1,9 +1,11 @@
+mod batch;
 mod clear;
 pub mod compact;
 mod contains;
 mod count;
 mod get;
 mod get_batch;
+mod increment;
 mod insert;
 mod keys;
 mod keys_from;
@@ -30,7 +32,7 @@
 	fmt::{Debug, Display},
 	future::Future,
 	pin::Pin,
-	sync::Arc,
+	sync::{Arc, Mutex},
 };
 
 use rocksdb::{AsColumnFamilyRef, ColumnFamily, ReadOptions, WriteOptions};
@@ -40,7 +42,7 @@
 	cache_iter_options_default, cache_read_options_default, iter_options_default,
 	read_options_default, write_options_default,
 };
-pub use self::{get_batch::Get, qry_batch::Qry};
+pub use self::{batch::Batch, get_batch::Get, qry_batch::Qry};
 use crate::{Engine, watchers::Watchers};
 
 pub struct Map {
@@ -51,6 +53,7 @@ pub struct Map {
 	read_options: ReadOptions,
 	cache_read_options: ReadOptions,
 	write_options: WriteOptions,
+	counter: Mutex<()>,
 }
 
 impl Map {
@@ -63,6 +66,7 @@ pub(crate) fn open(db: &Arc<Engine>, name: &'static str) -> Result<Arc<Self>> {
 			read_options: read_options_default(db),
 			cache_read_options: cache_read_options_default(db),
 			write_options: write_options_default(db),
+			counter: Mutex::default(),
 		}))
 	}
 
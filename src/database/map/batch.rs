@@ -0,0 +1,165 @@
+//! Group writes to one or more maps into a single atomic operation.
+//!
+//! Writes are staged in the batch and only become visible once committed;
+//! dropping an uncommitted batch discards them. Watchers of the affected
+//! prefixes are woken after the commit.
+
+use std::{convert::AsRef, fmt::Debug, sync::Arc};
+
+use rocksdb::WriteBatchWithTransaction;
+use serde::Serialize;
+use tuwunel_core::utils;
+
+use super::{Map, write_options_default};
+use crate::{
+	Engine,
+	keyval::{KeyBuf, ValBuf},
+	ser,
+	util::or_else,
+};
+
+#[must_use]
+#[derive(Default)]
+pub struct Batch<'a> {
+	batch: WriteBatchWithTransaction<false>,
+	db: Option<&'a Arc<Engine>>,
+	wake: Vec<(&'a Map, Vec<u8>)>,
+}
+
+impl<'a> Batch<'a> {
+	/// Stage Key/Value insertion
+	///
+	/// - Key is serialized
+	/// - Val is serialized
+	#[inline]
+	pub fn put<K, V>(&mut self, map: &'a Map, key: K, val: V)
+	where
+		K: Serialize + Debug,
+		V: Serialize,
+	{
+		let mut val_buf = ValBuf::new();
+		let val = ser::serialize(&mut val_buf, val).expect("failed to serialize insertion val");
+		self.put_raw(map, key, val);
+	}
+
+	/// Stage Key/Value insertion
+	///
+	/// - Key is serialized
+	/// - Val is raw
+	#[inline]
+	pub fn put_raw<K, V>(&mut self, map: &'a Map, key: K, val: V)
+	where
+		K: Serialize + Debug,
+		V: AsRef<[u8]>,
+	{
+		let mut key_buf = KeyBuf::new();
+		let key = ser::serialize(&mut key_buf, key).expect("failed to serialize insertion key");
+		self.insert(map, key, val);
+	}
+
+	/// Stage Key/Value insertion
+	///
+	/// - Key is raw
+	/// - Val is serialized
+	#[inline]
+	pub fn raw_put<K, V>(&mut self, map: &'a Map, key: K, val: V)
+	where
+		K: AsRef<[u8]>,
+		V: Serialize,
+	{
+		let mut val_buf = ValBuf::new();
+		let val = ser::serialize(&mut val_buf, val).expect("failed to serialize insertion val");
+		self.insert(map, &key, val);
+	}
+
+	/// Stage Key/Value insertion
+	///
+	/// - Key is raw
+	/// - Val is raw
+	#[tracing::instrument(skip_all, fields(%map), level = "trace")]
+	pub fn insert<K, V>(&mut self, map: &'a Map, key: &K, val: V)
+	where
+		K: AsRef<[u8]> + ?Sized,
+		V: AsRef<[u8]>,
+	{
+		self.batch
+			.put_cf(&map.cf(), key.as_ref(), val.as_ref());
+
+		self.db.get_or_insert(map.db());
+		self.wake.push((map, key.as_ref().to_vec()));
+	}
+
+	/// Stage Key deletion
+	///
+	/// - Key is serialized
+	#[inline]
+	pub fn del<K>(&mut self, map: &'a Map, key: K)
+	where
+		K: Serialize + Debug,
+	{
+		let mut buf = KeyBuf::new();
+		let key = ser::serialize(&mut buf, key).expect("failed to serialize deletion key");
+		self.remove(map, key);
+	}
+
+	/// Stage Key deletion
+	///
+	/// - Key is raw
+	#[tracing::instrument(skip_all, fields(%map), level = "trace")]
+	pub fn remove<K>(&mut self, map: &'a Map, key: &K)
+	where
+		K: AsRef<[u8]> + ?Sized + Debug,
+	{
+		self.batch.delete_cf(&map.cf(), key);
+		self.db.get_or_insert(map.db());
+	}
+
+	/// Stage incrementing the counter at Key, returning the new value. A
+	/// missing or malformed value is treated as zero.
+	///
+	/// The current value is read now but the new one is only written on
+	/// commit, so the caller must serialize increments of the key until then,
+	/// and increment it at most once per batch.
+	#[tracing::instrument(skip(self), fields(%map), level = "trace")]
+	pub fn increment<K>(&mut self, map: &'a Map, key: &K) -> u64
+	where
+		K: AsRef<[u8]> + ?Sized + Debug,
+	{
+		let old = map.get_blocking(key);
+		let new = utils::increment(old.ok().as_deref());
+		self.insert(map, key, new);
+
+		u64::from_be_bytes(new)
+	}
+
+	/// Atomically write all staged operations to the database.
+	#[tracing::instrument(skip(self), fields(len = self.len()), level = "trace")]
+	pub fn commit(self) {
+		let Some(db) = self.db else {
+			return;
+		};
+
+		let write_options = write_options_default(db);
+		db.db
+			.write_opt(self.batch, &write_options)
+			.or_else(or_else)
+			.expect("database write batch error");
+
+		if !db.corked() {
+			db.flush().expect("database flush error");
+		}
+
+		for (map, key) in self.wake {
+			map.watchers.wake(&key);
+		}
+	}
+
+	/// Number of staged operations.
+	#[inline]
+	#[must_use]
+	pub fn len(&self) -> usize { self.batch.len() }
+
+	#[inline]
+	#[must_use]
+	pub fn is_empty(&self) -> bool { self.batch.is_empty() }
+}
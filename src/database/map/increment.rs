@@ -0,0 +1,25 @@
+//! Atomic counters stored as big-endian u64 values.
+
+use std::{convert::AsRef, fmt::Debug};
+
+use tuwunel_core::{implement, utils};
+
+/// Increment the counter at Key, returning the new value. A missing or
+/// malformed value is treated as zero.
+///
+/// The read-modify-write is serialized with all other increments on this
+/// map, so concurrent callers cannot lose an update.
+#[implement(super::Map)]
+#[tracing::instrument(skip(self), fields(%self), level = "trace")]
+pub fn increment<K>(&self, key: &K) -> u64
+where
+	K: AsRef<[u8]> + ?Sized + Debug,
+{
+	let _lock = self.counter.lock().expect("locked");
+
+	let old = self.get_blocking(key);
+	let new = utils::increment(old.ok().as_deref());
+	self.insert(key, new);
+
+	u64::from_be_bytes(new)
+}
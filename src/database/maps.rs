@@ -33,6 +33,10 @@ pub(super) fn open_list(db: &Arc<Engine>, maps: &[Descriptor]) -> Result<Maps> {
 		name: "aliasid_alias",
 		..descriptor::RANDOM_SMALL
 	},
+	Descriptor {
+		name: "appserviceid_txnstatus",
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "backupid_algorithm",
 		..descriptor::RANDOM_SMALL
@@ -53,6 +57,10 @@ pub(super) fn open_list(db: &Arc<Engine>, maps: &[Descriptor]) -> Result<Maps> {
 		name: "disabledroomids",
 		..descriptor::RANDOM_SMALL
 	},
+	Descriptor {
+		name: "eventid_expiresat",
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "eventid_outlierpdu",
 		cache_disp: CacheDisp::SharedWith("pduid_pdu"),
@@ -80,6 +88,22 @@ pub(super) fn open_list(db: &Arc<Engine>, maps: &[Descriptor]) -> Result<Maps> {
 		index_size: 512,
 		..descriptor::RANDOM
 	},
+	Descriptor {
+		name: "eventid_unredactedpdu",
+		key_size_hint: Some(48),
+		val_size_hint: Some(1488),
+		..descriptor::RANDOM_SMALL
+	},
+	Descriptor {
+		name: "expiresat_eventid",
+		..descriptor::SEQUENTIAL_SMALL
+	},
+	Descriptor {
+		name: "fromto_relation",
+		key_size_hint: Some(16),
+		val_size_hint: Some(1),
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "global",
 		..descriptor::RANDOM_SMALL
@@ -104,10 +128,18 @@ pub(super) fn open_list(db: &Arc<Engine>, maps: &[Descriptor]) -> Result<Maps> {
 		name: "mediaid_file",
 		..descriptor::RANDOM_SMALL
 	},
+	Descriptor {
+		name: "mediaid_quarantine",
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "mediaid_user",
 		..descriptor::RANDOM_SMALL
 	},
+	Descriptor {
+		name: "oidcsubject_userid",
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "onetimekeyid_onetimekeys",
 		..descriptor::RANDOM_SMALL
@@ -121,6 +153,16 @@ pub(super) fn open_list(db: &Arc<Engine>, maps: &[Descriptor]) -> Result<Maps> {
 		index_size: 512,
 		..descriptor::SEQUENTIAL
 	},
+	Descriptor {
+		name: "pduid_redactedeventid",
+		key_size_hint: Some(16),
+		val_size_hint: Some(48),
+		..descriptor::SEQUENTIAL_SMALL
+	},
+	Descriptor {
+		name: "publicroomid_curation",
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "publicroomids",
 		..descriptor::RANDOM_SMALL
@@ -137,10 +179,18 @@ pub(super) fn open_list(db: &Arc<Engine>, maps: &[Descriptor]) -> Result<Maps> {
 		name: "readreceiptid_readreceipt",
 		..descriptor::RANDOM
 	},
+	Descriptor {
+		name: "redactedexpiresat_eventid",
+		..descriptor::SEQUENTIAL_SMALL
+	},
 	Descriptor {
 		name: "referencedevents",
 		..descriptor::RANDOM
 	},
+	Descriptor {
+		name: "renewaltoken_userid",
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "roomid_invitedcount",
 		..descriptor::RANDOM_SMALL
@@ -167,6 +217,10 @@ pub(super) fn open_list(db: &Arc<Engine>, maps: &[Descriptor]) -> Result<Maps> {
 		val_size_hint: Some(8),
 		..descriptor::RANDOM_SMALL
 	},
+	Descriptor {
+		name: "roomid_usage",
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "roomserverids",
 		..descriptor::RANDOM_SMALL
@@ -219,6 +273,10 @@ pub(super) fn open_list(db: &Arc<Engine>, maps: &[Descriptor]) -> Result<Maps> {
 		name: "roomusertype_roomuserdataid",
 		..descriptor::RANDOM_SMALL
 	},
+	Descriptor {
+		name: "sendat_userdelayid",
+		..descriptor::SEQUENTIAL_SMALL
+	},
 	Descriptor {
 		name: "senderkey_pusher",
 		..descriptor::RANDOM_SMALL
@@ -231,6 +289,10 @@ pub(super) fn open_list(db: &Arc<Engine>, maps: &[Descriptor]) -> Result<Maps> {
 		name: "servercurrentevent_data",
 		..descriptor::RANDOM_SMALL
 	},
+	Descriptor {
+		name: "servername_catchup",
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "servername_destination",
 		..descriptor::RANDOM_SMALL_CACHE
@@ -239,6 +301,14 @@ pub(super) fn open_list(db: &Arc<Engine>, maps: &[Descriptor]) -> Result<Maps> {
 		name: "servername_educount",
 		..descriptor::RANDOM_SMALL
 	},
+	Descriptor {
+		name: "servername_federationrule",
+		..descriptor::RANDOM_SMALL
+	},
+	Descriptor {
+		name: "servername_federationstats",
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "servername_override",
 		..descriptor::RANDOM_SMALL_CACHE
@@ -303,6 +373,14 @@ pub(super) fn open_list(db: &Arc<Engine>, maps: &[Descriptor]) -> Result<Maps> {
 		val_size_hint: Some(8),
 		..descriptor::RANDOM
 	},
+	Descriptor {
+		name: "subnet_registrationthrottle",
+		..descriptor::RANDOM_SMALL
+	},
+	Descriptor {
+		name: "targetkeysender_eventid",
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "threadid_userids",
 		..descriptor::SEQUENTIAL_SMALL
@@ -330,6 +408,22 @@ pub(super) fn open_list(db: &Arc<Engine>, maps: &[Descriptor]) -> Result<Maps> {
 		name: "url_previews",
 		..descriptor::RANDOM
 	},
+	Descriptor {
+		name: "userdelayid_delayedevent",
+		..descriptor::RANDOM_SMALL
+	},
+	Descriptor {
+		name: "useridcount_notification",
+		..descriptor::RANDOM_SMALL
+	},
+	Descriptor {
+		name: "userdeviceid_connections",
+		..descriptor::RANDOM_SMALL
+	},
+	Descriptor {
+		name: "userdeviceid_impersonationexpiresat",
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "userdeviceid_metadata",
 		..descriptor::RANDOM_SMALL
@@ -338,6 +432,10 @@ pub(super) fn open_list(db: &Arc<Engine>, maps: &[Descriptor]) -> Result<Maps> {
 		name: "userdeviceid_token",
 		..descriptor::RANDOM_SMALL
 	},
+	Descriptor {
+		name: "userdevicesessionid_consumed",
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "userdevicesessionid_uiaainfo",
 		..descriptor::RANDOM_SMALL
@@ -350,10 +448,18 @@ pub(super) fn open_list(db: &Arc<Engine>, maps: &[Descriptor]) -> Result<Maps> {
 		name: "userfilterid_filter",
 		..descriptor::RANDOM_SMALL
 	},
+	Descriptor {
+		name: "userfilterid_lastused",
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "userid_avatarurl",
 		..descriptor::RANDOM_SMALL
 	},
+	Descriptor {
+		name: "userid_bot",
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "userid_blurhash",
 		..descriptor::RANDOM_SMALL
@@ -366,6 +472,10 @@ pub(super) fn open_list(db: &Arc<Engine>, maps: &[Descriptor]) -> Result<Maps> {
 		name: "userid_displayname",
 		..descriptor::RANDOM_SMALL
 	},
+	Descriptor {
+		name: "userid_expiresat",
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "userid_lastonetimekeyupdate",
 		..descriptor::RANDOM_SMALL
@@ -378,6 +488,10 @@ pub(super) fn open_list(db: &Arc<Engine>, maps: &[Descriptor]) -> Result<Maps> {
 		name: "userid_origin",
 		..descriptor::RANDOM
 	},
+	Descriptor {
+		name: "userid_passkeys",
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "userid_password",
 		..descriptor::RANDOM
@@ -386,10 +500,22 @@ pub(super) fn open_list(db: &Arc<Engine>, maps: &[Descriptor]) -> Result<Maps> {
 		name: "userid_presenceid",
 		..descriptor::RANDOM_SMALL
 	},
+	Descriptor {
+		name: "userid_ratelimit",
+		..descriptor::RANDOM_SMALL
+	},
+	Descriptor {
+		name: "userid_renewaltoken",
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "userid_selfsigningkeyid",
 		..descriptor::RANDOM_SMALL
 	},
+	Descriptor {
+		name: "userid_totp",
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "userid_usersigningkeyid",
 		..descriptor::RANDOM_SMALL
@@ -426,8 +552,20 @@ pub(super) fn open_list(db: &Arc<Engine>, maps: &[Descriptor]) -> Result<Maps> {
 		name: "userroomid_knockedstate",
 		..descriptor::RANDOM_SMALL
 	},
+	Descriptor {
+		name: "userroomid_suppressedinvite",
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "userroomid_notificationcount",
 		..descriptor::RANDOM
 	},
+	Descriptor {
+		name: "userroomthreadid_highlightcount",
+		..descriptor::RANDOM
+	},
+	Descriptor {
+		name: "userroomthreadid_notificationcount",
+		..descriptor::RANDOM
+	},
 ];
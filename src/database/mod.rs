@@ -33,7 +33,7 @@
 	deserialized::Deserialized,
 	handle::Handle,
 	keyval::{KeyVal, Slice, serialize_key, serialize_val},
-	map::{Get, Map, Qry, compact},
+	map::{Batch, Get, Map, Qry, compact},
 	ser::{Cbor, Interfix, Json, SEP, Separator, serialize, serialize_to, serialize_to_vec},
 };
 pub(crate) use self::{
@@ -2,7 +2,7 @@
 
 use std::path::PathBuf;
 
-use clap::{ArgAction, Parser};
+use clap::{ArgAction, Parser, Subcommand};
 use tuwunel_core::{
 	Err, Result,
 	config::{Figment, FigmentValue},
@@ -19,6 +19,9 @@
 	version = tuwunel_core::version(),
 )]
 pub(crate) struct Args {
+	#[command(subcommand)]
+	pub(crate) command: Option<Command>,
+
 	#[arg(short, long)]
 	/// Path to the config TOML file (optional)
 	pub(crate) config: Option<Vec<PathBuf>>,
@@ -35,6 +38,16 @@ pub(crate) struct Args {
 	#[arg(long)]
 	pub(crate) maintenance: bool,
 
+	/// Restore the database from the latest backup and replay any WAL
+	/// archive, then run in --maintenance mode.
+	#[arg(long)]
+	pub(crate) restore: bool,
+
+	/// Stop replaying the WAL archive at this RFC 3339 timestamp. Implies
+	/// --restore.
+	#[arg(long)]
+	pub(crate) restore_until: Option<String>,
+
 	#[cfg(feature = "console")]
 	/// Activate admin command console automatically after startup.
 	#[arg(long, num_args(0))]
@@ -148,6 +161,21 @@ pub(crate) struct Args {
 	pub(crate) gc_muzzy: Option<bool>,
 }
 
+#[derive(Subcommand, Debug)]
+pub(crate) enum Command {
+	/// Run a single admin command offline and exit, e.g. `tuwunel admin users
+	/// reset-password alice`. No connections are accepted and the server must
+	/// not already be running on the database.
+	Admin {
+		#[arg(
+			required = true,
+			trailing_var_arg = true,
+			allow_hyphen_values = true
+		)]
+		command: Vec<String>,
+	},
+}
+
 /// Parse commandline arguments into structured data
 #[must_use]
 pub(super) fn parse() -> Args { Args::parse() }
@@ -158,11 +186,26 @@ pub(crate) fn update(mut config: Figment, args: &Args) -> Result<Figment> {
 		config = config.join(("rocksdb_read_only", true));
 	}
 
-	if args.maintenance || args.read_only {
+	// Restoring is one-shot; the configuration file can't request it.
+	let restore = args.restore || args.restore_until.is_some();
+	config = config.merge(("database_restore", restore));
+	config = config.merge(("database_restore_until", &args.restore_until));
+
+	if args.maintenance || args.read_only || args.restore || args.restore_until.is_some() {
 		config = config.join(("startup_netburst", false));
 		config = config.join(("listening", false));
 	}
 
+	// The offline admin mode overrides the configuration file so it never serves
+	// requests nor runs commands other than the one given.
+	if let Some(Command::Admin { command }) = &args.command {
+		config = config.merge(("listening", false));
+		config = config.merge(("startup_netburst", false));
+		config = config.merge(("admin_console_automatic", false));
+		config = config.merge(("admin_execute", [command.join(" ")]));
+		config = config.merge(("admin_execute_exit", true));
+	}
+
 	#[cfg(feature = "console")]
 	// Indicate the admin console should be spawned automatically if the
 	// configuration file hasn't already.
@@ -176,6 +219,10 @@ pub(crate) fn update(mut config: Figment, args: &Args) -> Result<Figment> {
 	// Update config with names of any functional-tests
 	config = config.adjoin(("test", &args.test));
 
+	// Runtime arguments apply unless the configuration file sets them.
+	config = config.join(("runtime_worker_threads", args.worker_threads));
+	config = config.join(("runtime_worker_affinity", args.worker_affinity));
+
 	// All other individual overrides can go last in case we have options which
 	// set multiple conf items at once and the user still needs granular overrides.
 	for option in &args.option {
@@ -19,8 +19,9 @@
 
 fn main() -> Result {
 	let args = clap::parse();
-	let runtime = runtime::new(&args)?;
-	let server = Server::new(&args, Some(runtime.handle()))?;
+	let config = server::load_config(&args)?;
+	let runtime = runtime::new(&args, &config)?;
+	let server = Server::new(config, Some(runtime.handle()))?;
 
 	runtime.spawn(signal::signal(server.clone()));
 	runtime.block_on(async_main(&server))?;
@@ -68,16 +69,7 @@ async fn async_main(server: &Arc<Server>) -> Result<(), Error> {
 		return Err(error);
 	}
 
-	if let Err(error) = router::stop(
-		server
-			.services
-			.lock()
-			.await
-			.take()
-			.expect("services initialized"),
-	)
-	.await
-	{
+	if let Err(error) = server.stop(router::stop).await {
 		error!("Critical error stopping server: {error}");
 		return Err(error);
 	}
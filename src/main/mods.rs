@@ -79,16 +79,7 @@ pub(crate) async fn run(server: &Arc<Server>, starts: bool) -> Result<(bool, boo
 	let starts = reloads && stops;
 	if stops {
 		let stop = main_mod.get::<StopFuncProto>("stop")?;
-		if let Err(error) = stop(
-			server
-				.services
-				.lock()
-				.await
-				.take()
-				.expect("services initialized"),
-		)
-		.await
-		{
+		if let Err(error) = server.stop(stop).await {
 			error!("Stopping server: {error}");
 			return Err(error);
 		}
@@ -12,8 +12,10 @@
 #[cfg(all(not(target_env = "msvc"), feature = "jemalloc"))]
 use tuwunel_core::result::LogDebugErr;
 use tuwunel_core::{
-	Result, debug, is_true,
-	utils::sys::compute::{nth_core_available, set_affinity},
+	Result,
+	config::Config,
+	debug, debug_warn, is_true,
+	utils::sys::compute::{nth_core_available, set_affinity, set_io_priority, set_nice},
 };
 
 use crate::{clap::Args, server::Server};
@@ -21,19 +23,28 @@
 const WORKER_NAME: &str = "tuwunel:worker";
 const WORKER_MIN: usize = 2;
 const WORKER_KEEPALIVE: u64 = 36;
-const MAX_BLOCKING_THREADS: usize = 1024;
 const SHUTDOWN_TIMEOUT: Duration = Duration::from_millis(10000);
 #[cfg(all(not(target_env = "msvc"), feature = "jemalloc"))]
 const DISABLE_MUZZY_THRESHOLD: usize = 4;
 
 static WORKER_AFFINITY: OnceLock<bool> = OnceLock::new();
+static WORKER_NICE: OnceLock<Option<i32>> = OnceLock::new();
+static WORKER_IO_PRIORITY: OnceLock<Option<u8>> = OnceLock::new();
 static GC_ON_PARK: OnceLock<Option<bool>> = OnceLock::new();
 static GC_MUZZY: OnceLock<Option<bool>> = OnceLock::new();
 
-pub(super) fn new(args: &Args) -> Result<tokio::runtime::Runtime> {
+pub(super) fn new(args: &Args, config: &Config) -> Result<tokio::runtime::Runtime> {
 	WORKER_AFFINITY
-		.set(args.worker_affinity)
-		.expect("set WORKER_AFFINITY from program argument");
+		.set(config.runtime_worker_affinity)
+		.expect("set WORKER_AFFINITY from configuration");
+
+	WORKER_NICE
+		.set(config.runtime_worker_nice)
+		.expect("set WORKER_NICE from configuration");
+
+	WORKER_IO_PRIORITY
+		.set(config.runtime_worker_io_priority)
+		.expect("set WORKER_IO_PRIORITY from configuration");
 
 	GC_ON_PARK
 		.set(args.gc_on_park)
@@ -48,8 +59,8 @@ pub(super) fn new(args: &Args) -> Result<tokio::runtime::Runtime> {
 		.enable_io()
 		.enable_time()
 		.thread_name(WORKER_NAME)
-		.worker_threads(args.worker_threads.max(WORKER_MIN))
-		.max_blocking_threads(MAX_BLOCKING_THREADS)
+		.worker_threads(config.runtime_worker_threads.max(WORKER_MIN))
+		.max_blocking_threads(config.runtime_max_blocking_threads.max(1))
 		.thread_keep_alive(Duration::from_secs(WORKER_KEEPALIVE))
 		.global_queue_interval(args.global_event_interval)
 		.event_interval(args.kernel_event_interval)
@@ -148,6 +159,18 @@ fn thread_start() {
 	if WORKER_AFFINITY.get().is_some_and(is_true!()) {
 		set_worker_affinity();
 	}
+
+	if let Some(&Some(nice)) = WORKER_NICE.get() {
+		set_nice(nice)
+			.inspect_err(|e| debug_warn!(?nice, "Failed to set nice level: {e}"))
+			.ok();
+	}
+
+	if let Some(&Some(level)) = WORKER_IO_PRIORITY.get() {
+		set_io_priority(level)
+			.inspect_err(|e| debug_warn!(?level, "Failed to set IO priority: {e}"))
+			.ok();
+	}
 }
 
 fn set_worker_affinity() {
@@ -1,12 +1,16 @@
 #![cfg(feature = "sentry_telemetry")]
 
 use std::{
+	borrow::Cow,
+	cmp::Reverse,
 	str::FromStr,
 	sync::{Arc, OnceLock},
 };
 
+use regex::Regex;
 use sentry::{
 	Breadcrumb, ClientOptions, Level,
+	protocol::{Map, Value},
 	types::{
 		Dsn,
 		protocol::v7::{Context, Event},
@@ -16,6 +20,18 @@
 
 static SEND_PANIC: OnceLock<bool> = OnceLock::new();
 static SEND_ERROR: OnceLock<bool> = OnceLock::new();
+static SCRUB: OnceLock<Vec<(Regex, &'static str)>> = OnceLock::new();
+static SAMPLE_RATES: OnceLock<Vec<(String, f32)>> = OnceLock::new();
+
+const ACCESS_TOKEN_PATTERNS: [&str; 3] = [
+	r#"(?i)(access_token=)[^&\s"]+"#,
+	r#"(?i)(bearer\s+)[^\s"]+"#,
+	r"(?i)(x-matrix\s+)(?:[^\s,]+,\s*)*[^\s,]+",
+];
+const USER_ID_PATTERN: &str = r"@[a-z0-9._=/+\-]+:[a-zA-Z0-9.\-]+(?::[0-9]+)?";
+const ROOM_ID_PATTERN: &str =
+	r"[!#][a-zA-Z0-9._=~\-]+:[a-zA-Z0-9.\-]+(?::[0-9]+)?|![a-zA-Z0-9_\-]{43}";
+const REDACTED: &str = "<redacted>";
 
 pub(crate) fn init(config: &Config) -> Option<sentry::ClientInitGuard> {
 	config
@@ -30,6 +46,12 @@ fn options(config: &Config) -> ClientOptions {
 	SEND_ERROR
 		.set(config.sentry_send_error)
 		.expect("SEND_ERROR was not previously set");
+	SCRUB
+		.set(scrub_patterns(config))
+		.expect("SCRUB was not previously set");
+	SAMPLE_RATES
+		.set(sample_rates(config))
+		.expect("SAMPLE_RATES was not previously set");
 
 	let dsn = config
 		.sentry_endpoint
@@ -46,7 +68,12 @@ fn options(config: &Config) -> ClientOptions {
 		server_name,
 		traces_sample_rate: config.sentry_traces_sample_rate,
 		debug: cfg!(debug_assertions),
-		release: sentry::release_name!(),
+		release: config
+			.sentry_release
+			.clone()
+			.map(Cow::Owned)
+			.or_else(|| sentry::release_name!()),
+		environment: config.sentry_environment.clone().map(Cow::Owned),
 		user_agent: tuwunel_core::version::user_agent().into(),
 		attach_stacktrace: config.sentry_attach_stacktrace,
 		before_send: Some(Arc::new(before_send)),
@@ -55,7 +82,138 @@ fn options(config: &Config) -> ClientOptions {
 	}
 }
 
-fn before_send(event: Event<'static>) -> Option<Event<'static>> {
+fn scrub_patterns(config: &Config) -> Vec<(Regex, &'static str)> {
+	let access_tokens = ACCESS_TOKEN_PATTERNS
+		.into_iter()
+		.filter(|_| config.sentry_scrub_access_tokens)
+		.map(|pattern| (pattern, "${1}<redacted>"));
+
+	let user_ids = Some(USER_ID_PATTERN)
+		.filter(|_| config.sentry_scrub_user_ids)
+		.map(|pattern| (pattern, REDACTED));
+
+	let room_ids = Some(ROOM_ID_PATTERN)
+		.filter(|_| config.sentry_scrub_room_ids)
+		.map(|pattern| (pattern, REDACTED));
+
+	access_tokens
+		.chain(user_ids)
+		.chain(room_ids)
+		.map(|(pattern, replacement)| {
+			(Regex::new(pattern).expect("valid scrub pattern"), replacement)
+		})
+		.collect()
+}
+
+fn sample_rates(config: &Config) -> Vec<(String, f32)> {
+	let mut rates: Vec<_> = config
+		.sentry_module_sample_rates
+		.iter()
+		.map(|(module, rate)| (module.clone(), rate.clamp(0.0, 1.0)))
+		.collect();
+
+	// longest prefix first so the most specific module matches
+	rates.sort_by_key(|(module, _)| Reverse(module.len()));
+	rates
+}
+
+fn sampled(event: &Event<'_>) -> bool {
+	let Some(module) = event.logger.as_deref() else {
+		return true;
+	};
+
+	SAMPLE_RATES
+		.get()
+		.into_iter()
+		.flatten()
+		.find(|(prefix, _)| module.starts_with(prefix.as_str()))
+		.is_none_or(|&(_, rate)| rand::random::<f32>() < rate)
+}
+
+fn scrub(string: &mut String) {
+	for (regex, replacement) in SCRUB.get().into_iter().flatten() {
+		if regex.is_match(string) {
+			*string = regex
+				.replace_all(string, *replacement)
+				.into_owned();
+		}
+	}
+}
+
+fn scrub_value(value: &mut Value) {
+	match value {
+		| Value::String(string) => scrub(string),
+		| Value::Array(values) => values.iter_mut().for_each(scrub_value),
+		| Value::Object(map) => scrub_map(map),
+		| _ => {},
+	}
+}
+
+fn scrub_map(map: &mut Map<String, Value>) { map.values_mut().for_each(scrub_value); }
+
+fn scrub_breadcrumb(crumb: &mut Breadcrumb) {
+	crumb.message.iter_mut().for_each(scrub);
+	scrub_map(&mut crumb.data);
+}
+
+fn scrub_event(event: &mut Event<'_>) {
+	// The user's email and address are never wanted, scrubbing or not.
+	if let Some(user) = event.user.as_mut() {
+		user.email = None;
+		user.ip_address = None;
+	}
+
+	if SCRUB.get().is_none_or(Vec::is_empty) {
+		return;
+	}
+
+	event.message.iter_mut().for_each(scrub);
+	event.transaction.iter_mut().for_each(scrub);
+	event.culprit.iter_mut().for_each(scrub);
+	if let Some(logentry) = event.logentry.as_mut() {
+		scrub(&mut logentry.message);
+		logentry.params.iter_mut().for_each(scrub_value);
+	}
+
+	for exception in &mut event.exception.values {
+		exception.value.iter_mut().for_each(scrub);
+	}
+
+	event
+		.breadcrumbs
+		.values
+		.iter_mut()
+		.for_each(scrub_breadcrumb);
+
+	event.tags.values_mut().for_each(scrub);
+	scrub_map(&mut event.extra);
+
+	if let Some(user) = event.user.as_mut() {
+		user.id.iter_mut().for_each(scrub);
+		user.username.iter_mut().for_each(scrub);
+		scrub_map(&mut user.other);
+	}
+
+	if let Some(request) = event.request.as_mut() {
+		request.query_string.iter_mut().for_each(scrub);
+		request.headers.values_mut().for_each(scrub);
+		if let Some(url) = request.url.as_mut() {
+			let mut string = url.to_string();
+			scrub(&mut string);
+			if let Ok(scrubbed) = string.parse() {
+				*url = scrubbed;
+			}
+		}
+	}
+}
+
+fn before_send(mut event: Event<'static>) -> Option<Event<'static>> {
+	if !sampled(&event) {
+		return None;
+	}
+
+	scrub_event(&mut event);
+
 	if event.exception.iter().any(|e| e.ty == "panic") && !SEND_PANIC.get().unwrap_or(&true) {
 		return None;
 	}
@@ -85,11 +243,13 @@ fn before_send(event: Event<'static>) -> Option<Event<'static>> {
 	Some(event)
 }
 
-fn before_breadcrumb(crumb: Breadcrumb) -> Option<Breadcrumb> {
+fn before_breadcrumb(mut crumb: Breadcrumb) -> Option<Breadcrumb> {
 	if crumb.ty == "log" && crumb.level == Level::Debug {
 		return None;
 	}
 
+	scrub_breadcrumb(&mut crumb);
+
 	trace!("Sentry breadcrumb: {crumb:?}");
 	Some(crumb)
 }
@@ -1,16 +1,20 @@
-use std::{path::PathBuf, sync::Arc};
+use std::{future::Future, path::PathBuf, sync::Arc, time::Duration};
 
 use tokio::{runtime, sync::Mutex};
 use tuwunel_core::{
-	Error, Result,
+	Err, Error, Result,
 	config::Config,
-	info,
+	debug, info,
 	log::Log,
-	utils::{stream, sys},
+	utils::{stream, sys, time},
 };
 
 use crate::{clap::Args, logging::TracingFlameGuard};
 
+/// Time allowed to the shutdown phase beyond `shutdown_drain_timeout`, for
+/// flushing and closing the database once the service workers have stopped.
+const STOP_GRACE: Duration = Duration::from_secs(10);
+
 /// Server runtime state; complete
 pub(crate) struct Server {
 	/// Server runtime state; public portion
@@ -28,24 +32,28 @@ pub(crate) struct Server {
 	pub(crate) mods: tokio::sync::RwLock<Vec<tuwunel_core::mods::Module>>,
 }
 
+/// Load the configuration, synthesized with the command line arguments. This
+/// precedes building the runtime, which is configured by it.
+pub(crate) fn load_config(args: &Args) -> Result<Config> {
+	let config_paths = args
+		.config
+		.as_deref()
+		.into_iter()
+		.flat_map(<[_]>::iter)
+		.map(PathBuf::as_path);
+
+	Config::load(config_paths)
+		.and_then(|raw| crate::clap::update(raw, args))
+		.and_then(|raw| Config::new(&raw))
+}
+
 impl Server {
 	pub(crate) fn new(
-		args: &Args,
+		config: Config,
 		runtime: Option<&runtime::Handle>,
 	) -> Result<Arc<Self>, Error> {
 		let _runtime_guard = runtime.map(runtime::Handle::enter);
 
-		let config_paths = args
-			.config
-			.as_deref()
-			.into_iter()
-			.flat_map(<[_]>::iter)
-			.map(PathBuf::as_path);
-
-		let config = Config::load(config_paths)
-			.and_then(|raw| crate::clap::update(raw, args))
-			.and_then(|raw| Config::new(&raw))?;
-
 		let (tracing_reload_handle, tracing_flame_guard, capture) =
 			crate::logging::init(&config)?;
 
@@ -85,4 +93,32 @@ pub(crate) fn new(
 			mods: tokio::sync::RwLock::new(Vec::new()),
 		}))
 	}
+
+	/// Shutdown phase, entered once the router has stopped accepting requests
+	/// and drained its client connections. The services are handed to `stop`,
+	/// which stops their workers, flushes their state and closes the database
+	/// as the last reference is dropped. The phase is bounded so a stuck
+	/// service cannot hold the process open; on timeout the services are
+	/// dropped regardless.
+	pub(crate) async fn stop<F, Fut>(&self, stop: F) -> Result
+	where
+		F: FnOnce(Arc<tuwunel_service::Services>) -> Fut,
+		Fut: Future<Output = Result>,
+	{
+		let services = self
+			.services
+			.lock()
+			.await
+			.take()
+			.expect("services initialized");
+
+		let timeout = Duration::from_secs(self.server.config.shutdown_drain_timeout)
+			.saturating_add(STOP_GRACE);
+
+		debug!(?timeout, "Stopping services...");
+		match tokio::time::timeout(timeout, stop(services)).await {
+			| Ok(result) => result,
+			| Err(_) => Err!("Services did not stop within {}.", time::pretty(timeout)),
+		}
+	}
 }
@@ -1,9 +1,6 @@
 use std::{any::Any, sync::Arc, time::Duration};
 
-use axum::{
-	Router,
-	extract::{DefaultBodyLimit, MatchedPath},
-};
+use axum::{Router, extract::MatchedPath};
 use axum_client_ip::SecureClientIpSource;
 use http::{
 	HeaderValue, Method, StatusCode,
@@ -15,7 +12,7 @@
 	cors::{self, CorsLayer},
 	sensitive_headers::SetSensitiveHeadersLayer,
 	set_header::SetResponseHeaderLayer,
-	timeout::{RequestBodyTimeoutLayer, ResponseBodyTimeoutLayer, TimeoutLayer},
+	timeout::{RequestBodyTimeoutLayer, ResponseBodyTimeoutLayer},
 	trace::{DefaultOnFailure, DefaultOnRequest, DefaultOnResponse, TraceLayer},
 };
 use tracing::Level;
@@ -23,7 +20,7 @@
 use tuwunel_core::{Result, Server, debug, error};
 use tuwunel_service::Services;
 
-use crate::{request, router};
+use crate::{limits, request, router};
 
 const TUWUNEL_CSP: &[&str; 5] = &[
 	"default-src 'none'",
@@ -67,7 +64,7 @@ pub(crate) fn build(services: &Arc<Services>) -> Result<(Router, Guard)> {
 		.layer(RequestBodyTimeoutLayer::new(Duration::from_secs(
 			server.config.client_receive_timeout,
 		)))
-		.layer(TimeoutLayer::new(Duration::from_secs(server.config.client_request_timeout)))
+		.layer(axum::middleware::from_fn_with_state(Arc::clone(services), limits::handle))
 		.layer(SetResponseHeaderLayer::if_not_present(
 			HeaderName::from_static("origin-agent-cluster"), // https://developer.mozilla.org/en-US/docs/Web/HTTP/Headers/Origin-Agent-Cluster
 			HeaderValue::from_static("?1"),
@@ -93,7 +90,6 @@ pub(crate) fn build(services: &Arc<Services>) -> Result<(Router, Guard)> {
 			HeaderValue::from_str(&TUWUNEL_CSP.join(";"))?,
 		))
 		.layer(cors_layer(server))
-		.layer(body_limit_layer(server))
 		.layer(CatchPanicLayer::custom(move |panic| catch_panic(panic, services_.clone())));
 
 	let (router, guard) = router::build(services);
@@ -164,10 +160,6 @@ fn cors_layer(_server: &Server) -> CorsLayer {
 		.max_age(Duration::from_secs(86400))
 }
 
-fn body_limit_layer(server: &Server) -> DefaultBodyLimit {
-	DefaultBodyLimit::max(server.config.max_request_size)
-}
-
 #[tracing::instrument(name = "panic", level = "error", skip_all)]
 #[allow(clippy::needless_pass_by_value)]
 fn catch_panic(
@@ -0,0 +1,34 @@
+use std::sync::Arc;
+
+use axum::{
+	extract::{DefaultBodyLimit, State},
+	response::{IntoResponse, Response},
+};
+use http::StatusCode;
+use tokio::time::timeout;
+use tower::{Layer, ServiceExt};
+use tuwunel_core::{config::RouteClass, debug_warn};
+use tuwunel_service::Services;
+
+/// Applies the timeout and body size limit configured for the class of the
+/// requested route.
+pub(crate) async fn handle(
+	State(services): State<Arc<Services>>,
+	req: http::Request<axum::body::Body>,
+	next: axum::middleware::Next,
+) -> Response {
+	let config = &services.server.config;
+	let class = RouteClass::of(req.uri().path());
+	let duration = config.request_timeout(class);
+	let service = DefaultBodyLimit::max(config.request_size(class)).layer(next);
+
+	let uri = req.uri().clone();
+	match timeout(duration, service.oneshot(req)).await {
+		| Ok(Ok(response)) => response,
+		| Ok(Err(infallible)) => match infallible {},
+		| Err(_) => {
+			debug_warn!(?class, %uri, ?duration, "request timed out");
+			StatusCode::REQUEST_TIMEOUT.into_response()
+		},
+	}
+}
@@ -1,6 +1,7 @@
 #![type_length_limit = "32768"] //TODO: reduce me
 
 mod layers;
+mod limits;
 mod request;
 mod router;
 mod run;
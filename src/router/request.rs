@@ -31,6 +31,21 @@ pub(crate) async fn handle(
 		return Err(StatusCode::SERVICE_UNAVAILABLE);
 	}
 
+	if services
+		.server
+		.config
+		.disabled_routes
+		.is_match(req.uri().path())
+	{
+		debug_warn!(
+			method = %req.method(),
+			uri = %req.uri(),
+			"route disabled by configuration"
+		);
+
+		return Ok(err!(Request(Forbidden("This endpoint is disabled."))).into_response());
+	}
+
 	let uri = req.uri().clone();
 	let method = req.method().clone();
 	let services_ = services.clone();
@@ -22,6 +22,7 @@ pub(crate) async fn run(services: Arc<Services>) -> Result<()> {
 
 	// Install the admin room callback here for now
 	tuwunel_admin::init(&services.admin).await;
+	tuwunel_api::init(&services);
 
 	// Setup shutdown/signal handling
 	let handle = ServerHandle::new();
@@ -48,6 +49,7 @@ pub(crate) async fn run(services: Arc<Services>) -> Result<()> {
 
 	// Remove the admin room callback
 	tuwunel_admin::fini(&services.admin).await;
+	tuwunel_api::fini(&services);
 
 	debug_info!("Finish");
 	res
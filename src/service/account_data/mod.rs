@@ -14,7 +14,7 @@
 	Err, Result, err, implement,
 	utils::{ReadyExt, result::LogErr, stream::TryIgnore},
 };
-use tuwunel_database::{Deserialized, Handle, Ignore, Json, Map};
+use tuwunel_database::{Deserialized, Handle, Ignore, Interfix, Json, Map};
 
 use crate::{Dep, globals};
 
@@ -87,6 +87,64 @@ pub async fn update(
 	Ok(())
 }
 
+/// Copies the user's account data in one room, such as tags and the fully read
+/// marker, to another, e.g. the successor of an upgraded room. Types the user
+/// already has in the target room are kept. Returns the number copied.
+#[implement(Service)]
+pub async fn copy_room(&self, from: &RoomId, to: &RoomId, user_id: &UserId) -> usize {
+	let events: Vec<_> = self
+		.changes_since(Some(from), user_id, 0, None)
+		.ready_filter_map(|event| match event {
+			| AnyRawAccountDataEvent::Room(event) =>
+				serde_json::from_str::<serde_json::Value>(event.json().get()).ok(),
+			| AnyRawAccountDataEvent::Global(_) => None,
+		})
+		.collect()
+		.await;
+
+	let mut copied: usize = 0;
+	for event in events {
+		let Some(kind) = event
+			.get("type")
+			.and_then(serde_json::Value::as_str)
+		else {
+			continue;
+		};
+
+		if self
+			.get_raw(Some(to), user_id, kind)
+			.await
+			.is_ok()
+		{
+			continue;
+		}
+
+		if self
+			.update(Some(to), user_id, kind.into(), &event)
+			.await
+			.log_err()
+			.is_ok()
+		{
+			copied = copied.saturating_add(1);
+		}
+	}
+
+	copied
+}
+
+/// Removes the account data of every user in a room, as part of purging the
+/// room from the database.
+#[implement(Service)]
+pub async fn purge_room(&self, room_id: &RoomId) {
+	let prefix = (room_id, Interfix);
+	for map in [&self.db.roomuserdataid_accountdata, &self.db.roomusertype_roomuserdataid] {
+		map.keys_prefix_raw(&prefix)
+			.ignore_err()
+			.ready_for_each(|key| map.remove(key))
+			.await;
+	}
+}
+
 /// Searches the room account data for a specific kind.
 #[implement(Service)]
 pub async fn get_global<T>(&self, user_id: &UserId, kind: GlobalAccountDataEventType) -> Result<T>
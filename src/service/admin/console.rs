@@ -2,6 +2,11 @@
 
 use std::{
 	collections::VecDeque,
+	fs,
+	io::{IsTerminal, Write},
+	mem::take,
+	path::{Path, PathBuf},
+	process::{Command, Stdio},
 	sync::{Arc, Mutex},
 };
 
@@ -10,7 +15,7 @@
 use rustyline_async::{Readline, ReadlineError, ReadlineEvent};
 use termimad::MadSkin;
 use tokio::task::JoinHandle;
-use tuwunel_core::{Server, debug, defer, error, log, log::is_systemd_mode};
+use tuwunel_core::{Config, Server, debug, debug_warn, defer, error, log, log::is_systemd_mode};
 
 use crate::{Dep, admin};
 
@@ -25,7 +30,10 @@ pub struct Console {
 }
 
 const PROMPT: &str = "uwu> ";
-const HISTORY_LIMIT: usize = 48;
+const CONTINUATION_PROMPT: &str = "...> ";
+const HISTORY_LIMIT: usize = 256;
+const HISTORY_FILE: &str = "admin_console_history";
+const DEFAULT_PAGER: &str = "less -R";
 
 impl Console {
 	pub(super) fn new(args: &crate::Args<'_>) -> Arc<Self> {
@@ -35,7 +43,7 @@ pub(super) fn new(args: &crate::Args<'_>) -> Arc<Self> {
 			worker_join: None.into(),
 			input_abort: None.into(),
 			command_abort: None.into(),
-			history: VecDeque::with_capacity(HISTORY_LIMIT).into(),
+			history: load_history(&args.server.config).into(),
 			output: configure_output(MadSkin::default_dark()),
 		})
 	}
@@ -100,11 +108,16 @@ async fn worker(self: Arc<Self>) {
 		self.output
 			.print_text("\"help\" for help, ^D to exit the console, ^\\ to stop the server\n");
 
+		let mut input = String::new();
 		while self.server.running() {
-			match self.readline().await {
+			let prompt = if input.is_empty() { PROMPT } else { CONTINUATION_PROMPT };
+			match self.readline(prompt).await {
 				| Ok(event) => match event {
-					| ReadlineEvent::Line(string) => self.clone().handle(string).await,
-					| ReadlineEvent::Interrupted => continue,
+					| ReadlineEvent::Line(string) =>
+						if !append_input(&mut input, &string) {
+							self.clone().handle(take(&mut input)).await;
+						},
+					| ReadlineEvent::Interrupted => input.clear(),
 					| ReadlineEvent::Eof => break,
 					| ReadlineEvent::Quit => self
 						.server
@@ -125,10 +138,10 @@ async fn worker(self: Arc<Self>) {
 		self.worker_join.lock().expect("locked").take();
 	}
 
-	async fn readline(self: &Arc<Self>) -> Result<ReadlineEvent, ReadlineError> {
+	async fn readline(self: &Arc<Self>, prompt: &str) -> Result<ReadlineEvent, ReadlineError> {
 		let _suppression = (!is_systemd_mode()).then(|| log::Suppress::new(&self.server));
 
-		let (mut readline, _writer) = Readline::new(PROMPT.to_owned())?;
+		let (mut readline, _writer) = Readline::new(prompt.to_owned())?;
 		let self_ = Arc::clone(self);
 		readline.set_tab_completer(move |line| self_.tab_complete(line));
 		self.set_history(&mut readline);
@@ -177,20 +190,38 @@ async fn handle(self: Arc<Self>, line: String) {
 	}
 
 	async fn process(self: Arc<Self>, line: String) {
-		match self.admin.command_in_place(line, None).await {
-			| Ok(Some(ref content)) => self.output(content),
-			| Err(ref content) => self.output_err(content),
+		let (content, error) = match self.admin.command_in_place(line, None).await {
+			| Ok(Some(content)) => (content, false),
+			| Err(content) => (content, true),
 			| _ => unreachable!(),
-		}
-	}
+		};
 
-	fn output_err(self: Arc<Self>, output_content: &RoomMessageEventContent) {
-		let output = configure_output_err(self.output.clone());
-		output.print_text(output_content.body());
+		// The pager blocks until the user quits it.
+		let self_ = self.clone();
+		_ = self
+			.server
+			.runtime()
+			.spawn_blocking(move || self_.output(&content, error))
+			.await;
 	}
 
-	fn output(self: Arc<Self>, output_content: &RoomMessageEventContent) {
-		self.output.print_text(output_content.body());
+	fn output(&self, output_content: &RoomMessageEventContent, error: bool) {
+		let output = if error {
+			configure_output_err(self.output.clone())
+		} else {
+			self.output.clone()
+		};
+
+		let markdown = output_content.body();
+		if self.server.config.admin_console_pager && exceeds_terminal(&output, markdown) {
+			let text = output.term_text(markdown).to_string();
+			match page(&text) {
+				| Ok(()) => return,
+				| Err(e) => debug_warn!("Failed to page console output: {e}"),
+			}
+		}
+
+		output.print_text(markdown);
 	}
 
 	fn set_history(&self, readline: &mut Readline) {
@@ -210,6 +241,19 @@ fn add_history(&self, line: String) {
 		let mut history = self.history.lock().expect("locked");
 		history.push_front(line);
 		history.truncate(HISTORY_LIMIT);
+
+		if let Some(path) = history_path(&self.server.config) {
+			let lines: String = history
+				.iter()
+				.rev()
+				.filter_map(|entry| serde_json::to_string(entry).ok())
+				.map(|entry| entry + "\n")
+				.collect();
+
+			if let Err(e) = save_history(&path, &lines) {
+				debug_warn!(?path, "Failed to save console history: {e}");
+			}
+		}
 	}
 
 	fn tab_complete(&self, line: &str) -> String {
@@ -219,6 +263,96 @@ fn tab_complete(&self, line: &str) -> String {
 	}
 }
 
+/// Append a line of input to the command, returning true while the command
+/// continues on the next line: within an unterminated code block or after a
+/// trailing backslash.
+fn append_input(input: &mut String, line: &str) -> bool {
+	let in_code_block = |input: &str| input.matches("```").count() % 2 == 1;
+
+	if !in_code_block(input) {
+		if let Some(line) = line.strip_suffix('\\') {
+			input.push_str(line);
+			return true;
+		}
+	}
+
+	input.push_str(line);
+	let incomplete = in_code_block(input);
+	if incomplete {
+		input.push('\n');
+	}
+
+	incomplete
+}
+
+fn history_path(config: &Config) -> Option<PathBuf> {
+	config
+		.admin_console_history
+		.then(|| config.database_path.join(HISTORY_FILE))
+}
+
+/// Write the history readable only by the server's user, as it may contain
+/// secrets.
+fn save_history(path: &Path, lines: &str) -> std::io::Result<()> {
+	let mut options = fs::OpenOptions::new();
+	options.write(true).create(true).truncate(true);
+
+	#[cfg(unix)]
+	{
+		use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+
+		options.mode(0o600);
+		if path.exists() {
+			fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+		}
+	}
+
+	options.open(path)?.write_all(lines.as_bytes())
+}
+
+/// Load the persisted history, newest first.
+fn load_history(config: &Config) -> VecDeque<String> {
+	let mut history: VecDeque<String> = history_path(config)
+		.and_then(|path| fs::read_to_string(path).ok())
+		.unwrap_or_default()
+		.lines()
+		.rev()
+		.filter_map(|line| serde_json::from_str(line).ok())
+		.collect();
+
+	history.truncate(HISTORY_LIMIT);
+	history
+}
+
+fn exceeds_terminal(output: &MadSkin, markdown: &str) -> bool {
+	use termimad::crossterm::terminal;
+
+	if !std::io::stdout().is_terminal() {
+		return false;
+	}
+
+	terminal::size()
+		.is_ok_and(|(_, rows)| output.term_text(markdown).lines.len() >= usize::from(rows))
+}
+
+/// Show the text through `$PAGER` or else `less -R`.
+fn page(text: &str) -> std::io::Result<()> {
+	let pager = std::env::var("PAGER").unwrap_or_else(|_| DEFAULT_PAGER.to_owned());
+	let mut args = pager.split_whitespace();
+	let program = args.next().unwrap_or("less");
+	let mut child = Command::new(program)
+		.args(args)
+		.stdin(Stdio::piped())
+		.spawn()?;
+
+	// The pager may be quit before reading everything.
+	if let Some(mut stdin) = child.stdin.take() {
+		_ = stdin.write_all(text.as_bytes());
+	}
+
+	child.wait().map(|_| ())
+}
+
 /// Standalone/static markdown printer for errors.
 pub fn print_err(markdown: &str) {
 	let output = configure_output_err(MadSkin::default_dark());
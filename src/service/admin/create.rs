@@ -2,7 +2,7 @@
 
 use futures::FutureExt;
 use ruma::{
-	RoomId, RoomVersionId,
+	OwnedRoomId, RoomId, RoomVersionId,
 	events::room::{
 		canonical_alias::RoomCanonicalAliasEventContent,
 		create::RoomCreateEventContent,
@@ -16,15 +16,31 @@
 		topic::RoomTopicEventContent,
 	},
 };
-use tuwunel_core::{Result, pdu::PduBuilder};
+use tuwunel_core::{Result, debug_info, pdu::PduBuilder};
 
 use crate::Services;
 
-/// Create the admin room.
+/// Create the admin room, unless it already exists.
 ///
 /// Users in this room are considered admins by tuwunel, and the room can be
 /// used to issue admin commands by talking to the server user inside it.
 pub async fn create_admin_room(services: &Services) -> Result {
+	if let Ok(room_id) = services.admin.get_admin_room().await {
+		debug_info!("Admin room {room_id} already exists");
+		return Ok(());
+	}
+
+	let room_id = build_admin_room(services).await?;
+	services.rooms.alias.set_alias(
+		&services.globals.admin_alias,
+		&room_id,
+		&services.globals.server_user,
+	)
+}
+
+/// Create a new admin room, regardless of any existing admin room. The caller
+/// points the admin alias at it.
+pub(super) async fn build_admin_room(services: &Services) -> Result<OwnedRoomId> {
 	let room_id = RoomId::new(services.globals.server_name());
 	let room_version = &services.config.default_room_version;
 
@@ -38,10 +54,12 @@ pub async fn create_admin_room(services: &Services) -> Result {
 
 	// Create a user for the server
 	let server_user = services.globals.server_user.as_ref();
-	services
-		.users
-		.create(server_user, None, None)
-		.await?;
+	if !services.users.exists(server_user).await {
+		services
+			.users
+			.create(server_user, None, None)
+			.await?;
+	}
 
 	let create_content = {
 		use RoomVersionId::*;
@@ -195,11 +213,6 @@ pub async fn create_admin_room(services: &Services) -> Result {
 		.boxed()
 		.await?;
 
-	services
-		.rooms
-		.alias
-		.set_alias(alias, &room_id, server_user)?;
-
 	// 7. (ad-hoc) Disable room URL previews for everyone by default
 	services
 		.rooms
@@ -213,5 +226,5 @@ pub async fn create_admin_room(services: &Services) -> Result {
 		.boxed()
 		.await?;
 
-	Ok(())
+	Ok(room_id)
 }
@@ -0,0 +1,109 @@
+use std::{
+	collections::{BTreeMap, VecDeque},
+	fmt::Write,
+	sync::Mutex,
+};
+
+use ruma::events::room::message::RoomMessageEventContent;
+use tuwunel_core::{Result, config::NoticeCategory, implement};
+
+/// Notices held for the next digest, by category.
+#[derive(Default)]
+pub(super) struct Digest {
+	pending: Mutex<BTreeMap<NoticeCategory, Pending>>,
+}
+
+#[derive(Default)]
+struct Pending {
+	count: usize,
+	recent: VecDeque<String>,
+}
+
+/// Number of the most recent notices of each category quoted in a digest.
+const DIGEST_RECENT: usize = 5;
+
+/// Sends a markdown notice of the category to the admin room, or holds it for
+/// the next digest when the category is configured for one. Nothing is sent
+/// when `admin_room_notices` is disabled.
+#[implement(super::Service)]
+pub async fn category_notice(&self, category: NoticeCategory, body: &str) {
+	if self.hold_for_digest(category, body) {
+		return;
+	}
+
+	self.notice(body).await;
+}
+
+/// As `category_notice()` but sent as a message which notifies the room.
+#[implement(super::Service)]
+pub async fn category_text(&self, category: NoticeCategory, body: &str) {
+	if self.hold_for_digest(category, body) {
+		return;
+	}
+
+	self.send_text(body).await;
+}
+
+/// Sends a summary of the held notices and clears them. Nothing is sent when
+/// none are held.
+#[implement(super::Service)]
+pub(super) async fn send_digest(&self) -> Result {
+	let pending = std::mem::take(
+		&mut *self
+			.digest
+			.pending
+			.lock()
+			.expect("locked for writing"),
+	);
+
+	if pending.is_empty() {
+		return Ok(());
+	}
+
+	let mut body = String::from("### Notice digest\n");
+	for (category, Pending { count, recent }) in &pending {
+		writeln!(body, "\n**{}**: {count}", category.as_str())?;
+		for notice in recent {
+			writeln!(body, "- {notice}")?;
+		}
+
+		if *count > recent.len() {
+			writeln!(body, "- _and {} more_", count.saturating_sub(recent.len()))?;
+		}
+	}
+
+	self.send_message(RoomMessageEventContent::notice_markdown(body))
+		.await
+}
+
+/// True when the notice was consumed: held for the digest, or dropped because
+/// notices are disabled.
+#[implement(super::Service)]
+fn hold_for_digest(&self, category: NoticeCategory, body: &str) -> bool {
+	let config = &self.services.server.config;
+	if !config.admin_room_notices {
+		return true;
+	}
+
+	if !config
+		.admin_room_notices_digest
+		.contains(&category)
+	{
+		return false;
+	}
+
+	let mut pending = self
+		.digest
+		.pending
+		.lock()
+		.expect("locked for writing");
+
+	let pending = pending.entry(category).or_default();
+	pending.count = pending.count.saturating_add(1);
+	pending.recent.push_back(body.to_owned());
+	if pending.recent.len() > DIGEST_RECENT {
+		pending.recent.pop_front();
+	}
+
+	true
+}
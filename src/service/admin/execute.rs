@@ -36,8 +36,12 @@ pub(super) async fn startup_execute(&self) -> Result {
 	// Determine if we're running in smoketest-mode which will change some behaviors
 	let smoketest = self.services.server.config.test.contains("smoke");
 
+	// Shut down after the commands, as in the offline `tuwunel admin` mode.
+	let exit = self.services.server.config.admin_execute_exit;
+
 	// When true, errors are ignored and startup continues.
 	let errors = !smoketest
+		&& !exit
 		&& self
 			.services
 			.server
@@ -59,13 +63,13 @@ pub(super) async fn startup_execute(&self) -> Result {
 
 	// The smoketest functionality is placed here for now and simply initiates
 	// shutdown after all commands have executed.
-	if smoketest {
-		debug_info!("Smoketest mode. All commands complete. Shutting down now...");
+	if smoketest || exit {
+		debug_info!("All commands complete. Shutting down now...");
 		self.services
 			.server
 			.shutdown()
 			.inspect_err(error::inspect_log)
-			.expect("Error shutting down from smoketest");
+			.expect("Error shutting down after executing commands");
 	}
 
 	Ok(())
@@ -50,21 +50,25 @@ pub async fn make_user_admin(&self, user_id: &UserId) -> Result {
 	// Use the server user to grant the new admin's power level
 	let server_user = self.services.globals.server_user.as_ref();
 
+	let invite = || {
+		PduBuilder::state(
+			String::from(user_id),
+			&RoomMemberEventContent::new(MembershipState::Invite),
+		)
+	};
+
+	self.services
+		.state_accessor
+		.ensure_power_to_send(&invite(), server_user, &room_id)
+		.await?;
+
 	// if this is our local user, just forcefully join them in the room. otherwise,
 	// invite the remote user.
 	if self.services.globals.user_is_local(user_id) {
 		debug_info!("Inviting local user {user_id} to admin room {room_id}");
 		self.services
 			.timeline
-			.build_and_append_pdu(
-				PduBuilder::state(
-					String::from(user_id),
-					&RoomMemberEventContent::new(MembershipState::Invite),
-				),
-				server_user,
-				&room_id,
-				&state_lock,
-			)
+			.build_and_append_pdu(invite(), server_user, &room_id, &state_lock)
 			.await?;
 
 		debug_info!("Force joining local user {user_id} to admin room {room_id}");
@@ -84,15 +88,7 @@ pub async fn make_user_admin(&self, user_id: &UserId) -> Result {
 		debug_info!("Inviting remote user {user_id} to admin room {room_id}");
 		self.services
 			.timeline
-			.build_and_append_pdu(
-				PduBuilder::state(
-					user_id.to_string(),
-					&RoomMemberEventContent::new(MembershipState::Invite),
-				),
-				server_user,
-				&room_id,
-				&state_lock,
-			)
+			.build_and_append_pdu(invite(), server_user, &room_id, &state_lock)
 			.await?;
 	}
 
@@ -115,14 +111,15 @@ pub async fn make_user_admin(&self, user_id: &UserId) -> Result {
 		.users
 		.insert(user_id.into(), 100.into());
 
+	let power_levels = PduBuilder::state(String::new(), &room_power_levels);
+	self.services
+		.state_accessor
+		.ensure_power_to_send(&power_levels, server_user, &room_id)
+		.await?;
+
 	self.services
 		.timeline
-		.build_and_append_pdu(
-			PduBuilder::state(String::new(), &room_power_levels),
-			server_user,
-			&room_id,
-			&state_lock,
-		)
+		.build_and_append_pdu(power_levels, server_user, &room_id, &state_lock)
 		.await?;
 
 	// Set room tag
@@ -222,21 +219,24 @@ pub async fn revoke_admin(&self, user_id: &UserId) -> Result {
 		},
 	};
 
+	let server_user = self.services.globals.server_user.as_ref();
+	let kick = PduBuilder::state(user_id.to_string(), &RoomMemberEventContent {
+		membership: Leave,
+		reason: Some("Admin Revoked".into()),
+		is_direct: None,
+		join_authorized_via_users_server: None,
+		third_party_invite: None,
+		..event
+	});
+
+	self.services
+		.state_accessor
+		.ensure_power_to_send(&kick, server_user, &room_id)
+		.await?;
+
 	self.services
 		.timeline
-		.build_and_append_pdu(
-			PduBuilder::state(user_id.to_string(), &RoomMemberEventContent {
-				membership: Leave,
-				reason: Some("Admin Revoked".into()),
-				is_direct: None,
-				join_authorized_via_users_server: None,
-				third_party_invite: None,
-				..event
-			}),
-			self.services.globals.server_user.as_ref(),
-			&room_id,
-			&state_lock,
-		)
+		.build_and_append_pdu(kick, server_user, &room_id, &state_lock)
 		.await
 		.map(|_| ())
 }
@@ -1,22 +1,31 @@
 pub mod console;
 mod create;
+mod digest;
 mod execute;
 mod grant;
+mod progress;
+mod repair;
+mod trace;
+mod welcome;
 
 use std::{
 	pin::Pin,
 	sync::{Arc, RwLock as StdRwLock, Weak},
+	time::Duration,
 };
 
 use async_trait::async_trait;
 pub use create::create_admin_room;
 use futures::{Future, FutureExt, TryFutureExt};
 use loole::{Receiver, Sender};
+pub use progress::Progress;
+pub use repair::{recreate_admin_room, repair_admin_room};
 use ruma::{
 	OwnedEventId, OwnedRoomId, RoomId, UserId,
 	events::room::message::{Relation, RoomMessageEventContent},
 };
-use tokio::sync::RwLock;
+use tokio::{sync::RwLock, time};
+pub use trace::{Trace, traced};
 use tuwunel_core::{
 	Error, Event, Result, Server, debug, err, error, error::default_log, pdu::PduBuilder,
 };
@@ -26,6 +35,8 @@
 pub struct Service {
 	services: Services,
 	channel: (Sender<CommandInput>, Receiver<CommandInput>),
+	digest: digest::Digest,
+	traces: StdRwLock<Vec<Arc<Trace>>>,
 	pub handle: RwLock<Option<Processor>>,
 	pub complete: StdRwLock<Option<Completer>>,
 	#[cfg(feature = "console")]
@@ -36,6 +47,7 @@ struct Services {
 	server: Arc<Server>,
 	globals: Dep<globals::Service>,
 	alias: Dep<rooms::alias::Service>,
+	short: Dep<rooms::short::Service>,
 	timeline: Dep<rooms::timeline::Service>,
 	state: Dep<rooms::state::Service>,
 	state_cache: Dep<rooms::state_cache::Service>,
@@ -82,6 +94,7 @@ fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 				server: args.server.clone(),
 				globals: args.depend::<globals::Service>("globals"),
 				alias: args.depend::<rooms::alias::Service>("rooms::alias"),
+				short: args.depend::<rooms::short::Service>("rooms::short"),
 				timeline: args.depend::<rooms::timeline::Service>("rooms::timeline"),
 				state: args.depend::<rooms::state::Service>("rooms::state"),
 				state_cache: args.depend::<rooms::state_cache::Service>("rooms::state_cache"),
@@ -91,6 +104,8 @@ fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 				services: None.into(),
 			},
 			channel: loole::bounded(COMMAND_QUEUE_LIMIT),
+			digest: digest::Digest::default(),
+			traces: StdRwLock::new(Vec::new()),
 			handle: RwLock::new(None),
 			complete: StdRwLock::new(None),
 			#[cfg(feature = "console")]
@@ -102,6 +117,16 @@ async fn worker(self: Arc<Self>) -> Result<()> {
 		let mut signals = self.services.server.signal.subscribe();
 		let receiver = self.channel.1.clone();
 
+		let digest_interval = self
+			.services
+			.server
+			.config
+			.admin_room_notices_digest_interval;
+
+		let digest_interval = Duration::from_secs(digest_interval.max(1));
+		let mut digest = time::interval(digest_interval);
+		digest.reset();
+
 		self.startup_execute().await?;
 		self.console_auto_start().await;
 
@@ -115,9 +140,13 @@ async fn worker(self: Arc<Self>) -> Result<()> {
 					Ok(sig) => self.handle_signal(sig).await,
 					Err(_) => continue,
 				},
+				_ = digest.tick() => self.send_digest().await.unwrap_or_else(default_log),
 			}
 		}
 
+		self.send_digest()
+			.await
+			.unwrap_or_else(default_log);
 		self.console_auto_stop().await; //TODO: not unwind safe
 
 		Ok(())
@@ -0,0 +1,101 @@
+//! Intermediate output of long-running commands.
+
+use std::{
+	sync::Mutex,
+	time::{Duration, Instant},
+};
+
+use ruma::{
+	OwnedEventId,
+	events::{
+		relation::{InReplyTo, Replacement},
+		room::message::{
+			Relation, RoomMessageEventContent, RoomMessageEventContentWithoutRelation,
+		},
+	},
+};
+use tuwunel_core::{Event, Result, implement, pdu::PduBuilder};
+
+/// Progress of one command. Updates replying to a command in a room are posted
+/// as a single notice which each later update edits; otherwise they're printed
+/// to the console or log.
+pub struct Progress {
+	reply_id: Option<OwnedEventId>,
+	notice_id: Mutex<Option<OwnedEventId>>,
+	last: Mutex<Option<Instant>>,
+}
+
+/// Updates more frequent than this are dropped, so they don't flood the room.
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(2);
+
+impl Progress {
+	#[must_use]
+	pub fn new(reply_id: Option<OwnedEventId>) -> Self {
+		Self {
+			reply_id,
+			notice_id: None.into(),
+			last: None.into(),
+		}
+	}
+
+	fn throttled(&self) -> bool {
+		let now = Instant::now();
+		let mut last = self.last.lock().expect("locked");
+		if last.is_some_and(|last| now.duration_since(last) < PROGRESS_INTERVAL) {
+			return true;
+		}
+
+		*last = Some(now);
+		false
+	}
+}
+
+/// Report the progress of a command; see `Progress`.
+#[implement(super::Service)]
+pub async fn update_progress(&self, progress: &Progress, message: &str) -> Result {
+	if progress.throttled() {
+		return Ok(());
+	}
+
+	let Some(reply_id) = progress.reply_id.clone() else {
+		#[cfg(feature = "console")]
+		super::console::print(message);
+
+		#[cfg(not(feature = "console"))]
+		tuwunel_core::info!("{message}");
+
+		return Ok(());
+	};
+
+	let pdu = self.services.timeline.get_pdu(&reply_id).await?;
+	let room_id = pdu.room_id();
+	let sender = if self.is_admin_room(room_id).await {
+		&self.services.globals.server_user
+	} else {
+		pdu.sender()
+	};
+
+	let notice_id = progress.notice_id.lock().expect("locked").clone();
+
+	let mut content = RoomMessageEventContent::notice_markdown(message);
+	content.relates_to = Some(match notice_id.clone() {
+		| Some(notice_id) => Relation::Replacement(Replacement::new(
+			notice_id,
+			RoomMessageEventContentWithoutRelation::notice_markdown(message),
+		)),
+		| None => Relation::Reply { in_reply_to: InReplyTo::new(reply_id) },
+	});
+
+	let state_lock = self.services.state.mutex.lock(room_id).await;
+	let event_id = self
+		.services
+		.timeline
+		.build_and_append_pdu(PduBuilder::timeline(&content), sender, room_id, &state_lock)
+		.await?;
+
+	if notice_id.is_none() {
+		*progress.notice_id.lock().expect("locked") = Some(event_id);
+	}
+
+	Ok(())
+}
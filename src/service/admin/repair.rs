@@ -0,0 +1,195 @@
+use futures::{FutureExt, StreamExt};
+use ruma::{
+	OwnedRoomId, OwnedUserId, RoomId,
+	events::{
+		StateEventType,
+		room::{
+			canonical_alias::RoomCanonicalAliasEventContent,
+			member::{MembershipState, RoomMemberEventContent},
+			power_levels::RoomPowerLevelsEventContent,
+		},
+	},
+};
+use tuwunel_core::{
+	Err, Result,
+	matrix::{Event, pdu::PduBuilder},
+	utils::ReadyExt,
+	warn,
+};
+
+use super::create::build_admin_room;
+use crate::Services;
+
+/// Repair a broken admin room in place: rejoin the server user and point the
+/// admin alias back at the room, then check the server user can still change
+/// power levels. Returns a description of each repair made.
+pub async fn repair_admin_room(services: &Services, room_id: &RoomId) -> Result<Vec<String>> {
+	if !services.rooms.metadata.exists(room_id).await {
+		return Err!("Room {room_id} is not known to this server; recreate the admin room.");
+	}
+
+	let server_user = services.globals.server_user.as_ref();
+	if !services
+		.rooms
+		.state_accessor
+		.room_state_get(room_id, &StateEventType::RoomCreate, "")
+		.await
+		.is_ok_and(|create| create.sender() == server_user)
+	{
+		return Err!("Room {room_id} was not created by {server_user}; it is not an admin room.");
+	}
+
+	let alias = &services.globals.admin_alias;
+	let state_lock = services.rooms.state.mutex.lock(room_id).await;
+	let mut repairs = Vec::new();
+
+	if !services
+		.rooms
+		.state_cache
+		.is_joined(server_user, room_id)
+		.await
+	{
+		// The server user only acts as itself, so a member has to invite it back.
+		if !services
+			.rooms
+			.state_cache
+			.is_invited(server_user, room_id)
+			.await
+		{
+			return Err!(
+				"{server_user} is not in {room_id} and has no invite; a room admin has to \
+				 invite it back, or recreate the admin room."
+			);
+		}
+
+		services
+			.rooms
+			.timeline
+			.build_and_append_pdu(
+				PduBuilder::state(
+					String::from(server_user),
+					&RoomMemberEventContent::new(MembershipState::Join),
+				),
+				server_user,
+				room_id,
+				&state_lock,
+			)
+			.boxed()
+			.await?;
+
+		repairs.push(format!("{server_user} rejoined."));
+	}
+
+	if services
+		.rooms
+		.alias
+		.resolve_local_alias(alias)
+		.await
+		.ok()
+		.as_deref()
+		!= Some(room_id)
+	{
+		services
+			.rooms
+			.alias
+			.remove_alias(alias, server_user)
+			.await
+			.ok();
+
+		services
+			.rooms
+			.alias
+			.set_alias(alias, room_id, server_user)?;
+
+		repairs.push(format!("{alias} points to {room_id}."));
+	}
+
+	// The server user needs to be able to change power levels to grant admins.
+	let power_levels_event =
+		PduBuilder::state(String::new(), &RoomPowerLevelsEventContent::default());
+	services
+		.rooms
+		.state_accessor
+		.ensure_power_to_send(&power_levels_event, server_user, room_id)
+		.await?;
+
+	let canonical_alias = services
+		.rooms
+		.state_accessor
+		.get_canonical_alias(room_id)
+		.await
+		.ok();
+
+	if canonical_alias.as_ref() != Some(alias) {
+		services
+			.rooms
+			.timeline
+			.build_and_append_pdu(
+				PduBuilder::state(String::new(), &RoomCanonicalAliasEventContent {
+					alias: Some(alias.clone()),
+					alt_aliases: Vec::new(),
+				}),
+				server_user,
+				room_id,
+				&state_lock,
+			)
+			.boxed()
+			.await?;
+
+		repairs.push(format!("Canonical alias set to {alias}."));
+	}
+
+	Ok(repairs)
+}
+
+/// Replace the admin room with a new one, moving the admin alias over and
+/// granting admin to the local members of the previous room.
+pub async fn recreate_admin_room(services: &Services) -> Result<OwnedRoomId> {
+	let server_user = services.globals.server_user.as_ref();
+	let alias = &services.globals.admin_alias;
+	let previous = services
+		.rooms
+		.alias
+		.resolve_local_alias(alias)
+		.await
+		.ok();
+
+	// The alias keeps pointing at the previous room until the new one is built.
+	let room_id = build_admin_room(services).await?;
+	if previous.is_some() {
+		services
+			.rooms
+			.alias
+			.remove_alias(alias, server_user)
+			.await?;
+	}
+
+	services
+		.rooms
+		.alias
+		.set_alias(alias, &room_id, server_user)?;
+
+	let admins: Vec<OwnedUserId> = match &previous {
+		| Some(previous) =>
+			services
+				.rooms
+				.state_cache
+				.local_users_in_room(previous)
+				.ready_filter(|user_id| *user_id != server_user)
+				.map(ToOwned::to_owned)
+				.collect()
+				.await,
+		| None => Vec::new(),
+	};
+
+	for user_id in &admins {
+		services
+			.admin
+			.make_user_admin(user_id)
+			.await
+			.inspect_err(|e| warn!("Failed to grant admin to {user_id} in new admin room: {e}"))
+			.ok();
+	}
+
+	Ok(room_id)
+}
@@ -0,0 +1,171 @@
+use std::{
+	fmt::Write,
+	sync::{
+		Arc, Mutex,
+		atomic::{AtomicU64, AtomicUsize, Ordering},
+	},
+	time::{Duration, Instant},
+};
+
+use futures::Future;
+use ruma::UserId;
+use tokio::time::sleep;
+use tuwunel_core::{
+	debug, implement,
+	log::{
+		capture,
+		capture::{Capture, Guard},
+		fmt::{markdown_table, markdown_table_head},
+	},
+	utils::string::collect_stream,
+};
+
+/// An active request trace of a user or a room.
+pub struct Trace {
+	id: u64,
+	target: String,
+	encoded: String,
+	until: Instant,
+	logs: Arc<Mutex<String>>,
+	requests: AtomicUsize,
+	_guard: Guard,
+}
+
+tokio::task_local! {
+	/// The trace which the request handled by this task belongs to.
+	static TRACE: Arc<Trace>;
+}
+
+/// Upper bound on the size of the logs collected by one trace. Lines beyond
+/// this are discarded.
+const TRACE_LOG_LIMIT: usize = 64 * 1024;
+
+static TRACE_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Runs the future with the trace in scope, if any, so that log events it
+/// produces are collected by the trace.
+pub async fn traced<F: Future>(trace: Option<Arc<Trace>>, fut: F) -> F::Output {
+	match trace {
+		| Some(trace) => TRACE.scope(trace, fut).await,
+		| None => fut.await,
+	}
+}
+
+/// Starts collecting verbose logs for requests made by a user or whose path
+/// references a user or room. After the duration the trace ends and the
+/// collected logs are sent to the admin room.
+#[implement(super::Service)]
+pub fn start_trace(self: &Arc<Self>, target: String, duration: Duration) {
+	let id = TRACE_ID.fetch_add(1, Ordering::Relaxed);
+	let logs = Arc::new(Mutex::new(
+		collect_stream(|s| markdown_table_head(s)).expect("markdown table header"),
+	));
+
+	let filter = move |data: capture::Data<'_>| {
+		data.our_modules()
+			&& TRACE
+				.try_with(|trace| trace.id == id)
+				.unwrap_or(false)
+	};
+
+	let out = logs.clone();
+	let closure = move |data: capture::Data<'_>| {
+		let mut out = out.lock().expect("locked");
+		if out.len() < TRACE_LOG_LIMIT {
+			markdown_table(&mut *out, &data.level(), data.span_name(), data.message())
+				.expect("log line appended");
+		}
+	};
+
+	let capture = Capture::new(&self.services.server.log.capture, Some(filter), closure);
+	let trace = Arc::new(Trace {
+		id,
+		encoded: encode(&target),
+		target,
+		until: Instant::now() + duration,
+		logs,
+		requests: AtomicUsize::new(0),
+		_guard: capture.start(),
+	});
+
+	debug!(target = %trace.target, ?duration, "Starting request trace");
+	self.traces
+		.write()
+		.expect("locked for writing")
+		.push(trace.clone());
+
+	let self_ = self.clone();
+	self.services.server.runtime().spawn(async move {
+		tokio::select! {
+			() = sleep(duration) => {},
+			() = self_.services.server.until_shutdown() => {},
+		}
+
+		let report = self_.end_trace(&trace);
+		self_.send_text(&report).await;
+	});
+}
+
+/// Finds the active trace a request belongs to, if any.
+#[implement(super::Service)]
+pub fn request_trace(&self, sender_user: Option<&UserId>, path: &str) -> Option<Arc<Trace>> {
+	let traces = self.traces.read().expect("locked for reading");
+	if traces.is_empty() {
+		return None;
+	}
+
+	let now = Instant::now();
+	let trace = traces
+		.iter()
+		.filter(|trace| trace.until > now)
+		.find(|trace| {
+			sender_user.is_some_and(|user_id| user_id.as_str() == trace.target)
+				|| path.contains(&trace.target)
+				|| path.contains(&trace.encoded)
+		})?;
+
+	trace.requests.fetch_add(1, Ordering::Relaxed);
+	Some(trace.clone())
+}
+
+/// Stops the trace and formats a report of its collected logs.
+#[implement(super::Service)]
+fn end_trace(&self, trace: &Arc<Trace>) -> String {
+	self.traces
+		.write()
+		.expect("locked for writing")
+		.retain(|active| !Arc::ptr_eq(active, trace));
+
+	let requests = trace.requests.load(Ordering::Relaxed);
+	let logs = trace.logs.lock().expect("locked");
+
+	let mut report = format!("Trace of {} ended after {requests} requests.\n\n", trace.target);
+	if logs.lines().count() > 2 {
+		writeln!(report, "{logs}").expect("report written");
+	}
+
+	if logs.len() >= TRACE_LOG_LIMIT {
+		writeln!(report, "Logs were truncated at {TRACE_LOG_LIMIT} bytes.")
+			.expect("report written");
+	}
+
+	report
+}
+
+/// Percent-encodes the sigils and separators of a matrix identifier as they
+/// appear in request paths.
+fn encode(target: &str) -> String {
+	target
+		.chars()
+		.fold(String::with_capacity(target.len()), |mut out, c| {
+			match c {
+				| '!' => out.push_str("%21"),
+				| '#' => out.push_str("%23"),
+				| ':' => out.push_str("%3A"),
+				| '@' => out.push_str("%40"),
+				| c => out.push(c),
+			}
+
+			out
+		})
+}
@@ -0,0 +1,112 @@
+use std::collections::BTreeMap;
+
+use futures::FutureExt;
+use ruma::{
+	OwnedRoomId, RoomId, RoomVersionId, UserId,
+	events::room::{
+		create::RoomCreateEventContent,
+		guest_access::{GuestAccess, RoomGuestAccessEventContent},
+		history_visibility::{HistoryVisibility, RoomHistoryVisibilityEventContent},
+		join_rules::{JoinRule, RoomJoinRulesEventContent},
+		member::{MembershipState, RoomMemberEventContent},
+		message::RoomMessageEventContent,
+		power_levels::RoomPowerLevelsEventContent,
+	},
+};
+use tuwunel_core::{Result, debug_info, implement, pdu::PduBuilder};
+
+/// Send the configured `welcome_message` to a newly registered user in a
+/// direct message from the server user. Does nothing when no message is
+/// configured.
+#[implement(super::Service)]
+pub async fn send_welcome(&self, user_id: &UserId) -> Result<Option<OwnedRoomId>> {
+	let Some(template) = self
+		.services
+		.server
+		.config
+		.welcome_message
+		.as_deref()
+	else {
+		return Ok(None);
+	};
+
+	let server_name = self.services.globals.server_name();
+	let body = template
+		.replace("{localpart}", user_id.localpart())
+		.replace("{user_id}", user_id.as_str())
+		.replace("{server_name}", server_name.as_str());
+
+	let room_id = self.send_direct_message(user_id, &body).await?;
+	debug_info!(%user_id, %room_id, "Sent welcome message");
+
+	Ok(Some(room_id))
+}
+
+/// Send a markdown message to a local user in a new direct message room with
+/// the server user, which the user is invited to.
+#[implement(super::Service)]
+pub async fn send_direct_message(&self, user_id: &UserId, body: &str) -> Result<OwnedRoomId> {
+	let server_name = self.services.globals.server_name();
+	let room_id = RoomId::new(server_name);
+	let room_version = &self.services.server.config.default_room_version;
+	let server_user = self.services.globals.server_user.as_ref();
+
+	let _short_id = self
+		.services
+		.short
+		.get_or_create_shortroomid(&room_id)
+		.await;
+
+	let state_lock = self.services.state.mutex.lock(&room_id).await;
+
+	let create_content = {
+		use RoomVersionId::*;
+		match room_version {
+			| V1 | V2 | V3 | V4 | V5 | V6 | V7 | V8 | V9 | V10 =>
+				RoomCreateEventContent::new_v1(server_user.into()),
+			| _ => RoomCreateEventContent::new_v11(),
+		}
+	};
+
+	let users =
+		BTreeMap::from_iter([(server_user.into(), 100.into()), (user_id.into(), 100.into())]);
+
+	let mut invite = RoomMemberEventContent::new(MembershipState::Invite);
+	invite.is_direct = Some(true);
+
+	let events = [
+		PduBuilder::state(String::new(), &RoomCreateEventContent {
+			room_version: room_version.clone(),
+			..create_content
+		}),
+		PduBuilder::state(
+			String::from(server_user),
+			&RoomMemberEventContent::new(MembershipState::Join),
+		),
+		PduBuilder::state(String::new(), &RoomPowerLevelsEventContent {
+			users,
+			..Default::default()
+		}),
+		PduBuilder::state(String::new(), &RoomJoinRulesEventContent::new(JoinRule::Invite)),
+		PduBuilder::state(
+			String::new(),
+			&RoomHistoryVisibilityEventContent::new(HistoryVisibility::Shared),
+		),
+		PduBuilder::state(
+			String::new(),
+			&RoomGuestAccessEventContent::new(GuestAccess::Forbidden),
+		),
+		PduBuilder::state(String::from(user_id), &invite),
+		PduBuilder::timeline(&RoomMessageEventContent::text_markdown(body)),
+	];
+
+	for pdu in events {
+		self.services
+			.timeline
+			.build_and_append_pdu(pdu, server_user, &room_id, &state_lock)
+			.boxed()
+			.await?;
+	}
+
+	Ok(room_id)
+}
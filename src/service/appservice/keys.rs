@@ -0,0 +1,146 @@
+//! Key claims and queries answered by appservices for the users they
+//! masquerade as (MSC3983 and MSC3984); see `appservice_key_proxy`.
+
+use std::collections::BTreeMap;
+
+use ruma::{
+	OneTimeKeyAlgorithm, OwnedDeviceId, OwnedOneTimeKeyId, OwnedUserId,
+	api::appservice::{
+		Registration,
+		keys::{claim_keys, query_keys},
+	},
+	encryption::{DeviceKeys, OneTimeKey},
+	serde::Raw,
+};
+use tuwunel_core::{debug_warn, implement};
+
+pub type OneTimeKeys =
+	BTreeMap<OwnedUserId, BTreeMap<OwnedDeviceId, BTreeMap<OwnedOneTimeKeyId, Raw<OneTimeKey>>>>;
+
+pub type DeviceKeysMap = BTreeMap<OwnedUserId, BTreeMap<OwnedDeviceId, Raw<DeviceKeys>>>;
+
+/// Claim one-time keys from the appservices whose exclusive namespaces cover
+/// the users. Users of no appservice, and appservices which fail, are omitted.
+#[implement(super::Service)]
+pub async fn claim_keys<'a, I>(&self, users: I) -> OneTimeKeys
+where
+	I: Iterator<Item = (&'a OwnedUserId, &'a BTreeMap<OwnedDeviceId, OneTimeKeyAlgorithm>)>
+		+ Send,
+{
+	let mut claimed = OneTimeKeys::new();
+	for (registration, users) in self.exclusive_users(users).await {
+		let id = registration.id.clone();
+		let one_time_keys = users
+			.iter()
+			.map(|&(user_id, devices)| {
+				let devices = devices
+					.iter()
+					.map(|(device_id, algorithm)| (device_id.clone(), vec![algorithm.clone()]))
+					.collect();
+
+				(user_id.clone(), devices)
+			})
+			.collect();
+
+		match self
+			.services
+			.sending
+			.send_appservice_request(
+				registration,
+				claim_keys::unstable::Request::new(one_time_keys),
+			)
+			.await
+		{
+			| Ok(Some(response)) =>
+				claimed.extend(requested_only(&id, &users, response.one_time_keys)),
+			| Ok(None) => {},
+			| Err(e) => debug_warn!(%id, "Appservice failed to claim one-time keys: {e}"),
+		}
+	}
+
+	claimed
+}
+
+/// Query device keys from the appservices whose exclusive namespaces cover the
+/// users. An empty list of devices queries all of a user's devices.
+#[implement(super::Service)]
+pub async fn query_keys<'a, I>(&self, users: I) -> DeviceKeysMap
+where
+	I: Iterator<Item = (&'a OwnedUserId, &'a Vec<OwnedDeviceId>)> + Send,
+{
+	let mut queried = DeviceKeysMap::new();
+	for (registration, users) in self.exclusive_users(users).await {
+		let id = registration.id.clone();
+		let device_keys = users
+			.iter()
+			.map(|&(user_id, devices)| (user_id.clone(), devices.clone()))
+			.collect();
+
+		match self
+			.services
+			.sending
+			.send_appservice_request(
+				registration,
+				query_keys::unstable::Request::new(device_keys),
+			)
+			.await
+		{
+			| Ok(Some(response)) =>
+				queried.extend(requested_only(&id, &users, response.device_keys)),
+			| Ok(None) => {},
+			| Err(e) => debug_warn!(%id, "Appservice failed to query device keys: {e}"),
+		}
+	}
+
+	queried
+}
+
+/// Group the users by the appservice whose exclusive namespace covers them.
+#[implement(super::Service)]
+async fn exclusive_users<'a, T, I>(
+	&self,
+	users: I,
+) -> Vec<(Registration, Vec<(&'a OwnedUserId, &'a T)>)>
+where
+	I: Iterator<Item = (&'a OwnedUserId, &'a T)> + Send,
+	T: Sync + 'a,
+{
+	let registrations = self.read().await;
+	let mut grouped = BTreeMap::<&str, (Registration, Vec<_>)>::new();
+	for (user_id, val) in users {
+		let Some(info) = registrations
+			.values()
+			.find(|info| info.is_exclusive_user_match(user_id))
+		else {
+			continue;
+		};
+
+		grouped
+			.entry(info.registration.id.as_str())
+			.or_insert_with(|| (info.registration.clone(), Vec::new()))
+			.1
+			.push((user_id, val));
+	}
+
+	grouped.into_values().collect()
+}
+
+/// Keep only the keys of users the appservice was asked about, which are all in
+/// its exclusive namespace; an appservice cannot supply keys for other users.
+fn requested_only<T, V>(
+	id: &str,
+	requested: &[(&OwnedUserId, &T)],
+	keys: BTreeMap<OwnedUserId, V>,
+) -> impl Iterator<Item = (OwnedUserId, V)> {
+	keys.into_iter().filter(move |(user_id, _)| {
+		let is_requested = requested
+			.iter()
+			.any(|(requested, _)| *requested == user_id);
+
+		if !is_requested {
+			debug_warn!(%id, %user_id, "Appservice returned keys for a user it was not asked about");
+		}
+
+		is_requested
+	})
+}
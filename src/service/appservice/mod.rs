@@ -1,3 +1,4 @@
+mod keys;
 mod namespace_regex;
 mod registration_info;
 
@@ -10,7 +11,11 @@
 use tuwunel_core::{Result, err, utils::stream::IterStream};
 use tuwunel_database::Map;
 
-pub use self::{namespace_regex::NamespaceRegex, registration_info::RegistrationInfo};
+pub use self::{
+	keys::{DeviceKeysMap, OneTimeKeys},
+	namespace_regex::NamespaceRegex,
+	registration_info::RegistrationInfo,
+};
 use crate::{Dep, sending};
 
 pub struct Service {
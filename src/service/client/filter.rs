@@ -0,0 +1,67 @@
+//! Outbound address filtering applied at resolution, so every connection a
+//! client makes to a hostname is subject to `ip_range_denylist`.
+
+use std::{io, net::SocketAddr, sync::Arc};
+
+use futures::FutureExt;
+use ipaddress::IPAddress;
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+
+/// Denied ranges with the exceptions allowed for one kind of request.
+pub struct IpRanges {
+	deny: Arc<[IPAddress]>,
+	allow: Vec<IPAddress>,
+}
+
+/// Resolver which drops addresses denied by its [`IpRanges`]; resolution
+/// fails if none remain.
+pub(super) struct Filtered {
+	inner: Arc<dyn Resolve>,
+	ranges: Arc<IpRanges>,
+}
+
+type ResolvingResult = Result<Addrs, Box<dyn std::error::Error + Send + Sync>>;
+
+impl IpRanges {
+	pub(super) fn new(deny: Arc<[IPAddress]>, allow: Vec<IPAddress>) -> Arc<Self> {
+		Arc::new(Self { deny, allow })
+	}
+
+	#[must_use]
+	pub fn allowed(&self, ip: &IPAddress) -> bool {
+		self.allow.iter().any(|cidr| cidr.includes(ip))
+			|| self.deny.iter().all(|cidr| !cidr.includes(ip))
+	}
+
+	fn allowed_addr(&self, addr: &SocketAddr) -> bool {
+		IPAddress::parse(addr.ip().to_string()).is_ok_and(|ip| self.allowed(&ip))
+	}
+}
+
+impl Filtered {
+	pub(super) fn new(inner: Arc<dyn Resolve>, ranges: &Arc<IpRanges>) -> Arc<Self> {
+		Arc::new(Self { inner, ranges: ranges.clone() })
+	}
+}
+
+impl Resolve for Filtered {
+	fn resolve(&self, name: Name) -> Resolving {
+		filter(self.ranges.clone(), self.inner.resolve(name)).boxed()
+	}
+}
+
+async fn filter(ranges: Arc<IpRanges>, resolving: Resolving) -> ResolvingResult {
+	let addrs: Vec<_> = resolving
+		.await?
+		.filter(|addr| ranges.allowed_addr(addr))
+		.collect();
+
+	if addrs.is_empty() {
+		return Err(Box::new(io::Error::new(
+			io::ErrorKind::PermissionDenied,
+			"All resolved addresses are denied by ip_range_denylist",
+		)));
+	}
+
+	Ok(Box::new(addrs.into_iter()))
+}
@@ -1,10 +1,16 @@
+mod filter;
+mod tls;
+
 use std::{sync::Arc, time::Duration};
 
 use either::Either;
 use ipaddress::IPAddress;
-use reqwest::redirect;
-use tuwunel_core::{Config, Result, err, implement, trace};
+use reqwest::{dns::Resolve, redirect};
+use rustls::ClientConfig;
+use tuwunel_core::{Config, Result, err, trace};
 
+use self::filter::Filtered;
+pub use self::filter::IpRanges;
 use crate::{resolver, service};
 
 pub struct Service {
@@ -18,7 +24,9 @@ pub struct Service {
 	pub appservice: reqwest::Client,
 	pub pusher: reqwest::Client,
 
-	pub cidr_range_denylist: Vec<IPAddress>,
+	pub federation_ranges: Arc<IpRanges>,
+	pub pusher_ranges: Arc<IpRanges>,
+	pub url_preview_ranges: Arc<IpRanges>,
 }
 
 impl crate::Service for Service {
@@ -36,27 +44,59 @@ fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 			.clone()
 			.and_then(Either::right);
 
+		let tls = tls::client_config(config)?;
+
+		let cidr_range_denylist: Arc<[IPAddress]> = config
+			.ip_range_denylist
+			.iter()
+			.map(IPAddress::parse)
+			.inspect(|cidr| trace!("Denied CIDR range: {cidr:?}"))
+			.collect::<Result<_, String>>()
+			.map_err(|e| err!(Config("ip_range_denylist", e)))?;
+
+		let federation_ranges = IpRanges::new(
+			cidr_range_denylist.clone(),
+			parse_ranges(&config.federation_ip_range_allowlist)
+				.map_err(|e| err!(Config("federation_ip_range_allowlist", e)))?,
+		);
+
+		let pusher_ranges = IpRanges::new(
+			cidr_range_denylist.clone(),
+			parse_ranges(&config.pusher_ip_range_allowlist)
+				.map_err(|e| err!(Config("pusher_ip_range_allowlist", e)))?,
+		);
+
+		let url_preview_ranges = IpRanges::new(
+			cidr_range_denylist,
+			parse_ranges(&config.url_preview_ip_range_allowlist)
+				.map_err(|e| err!(Config("url_preview_ip_range_allowlist", e)))?,
+		);
+
+		let plain: Arc<dyn Resolve> = resolver.resolver.clone();
+		let hooked: Arc<dyn Resolve> = resolver.resolver.hooked.clone();
+
 		Ok(Arc::new(Self {
-			default: base(config)?
-				.dns_resolver(resolver.resolver.clone())
+			// Only for destinations the admin configured or asked for, so unfiltered.
+			default: base(config, tls.as_ref())?
+				.dns_resolver(plain.clone())
 				.build()?,
 
-			url_preview: base(config)
+			url_preview: base(config, tls.as_ref())
 				.and_then(|builder| {
 					builder_interface(builder, url_preview_bind_iface.as_deref())
 				})?
 				.local_address(url_preview_bind_addr)
-				.dns_resolver(resolver.resolver.clone())
+				.dns_resolver(Filtered::new(plain.clone(), &url_preview_ranges))
 				.redirect(redirect::Policy::limited(3))
 				.build()?,
 
-			extern_media: base(config)?
-				.dns_resolver(resolver.resolver.clone())
+			extern_media: base(config, tls.as_ref())?
+				.dns_resolver(Filtered::new(plain.clone(), &federation_ranges))
 				.redirect(redirect::Policy::limited(3))
 				.build()?,
 
-			well_known: base(config)?
-				.dns_resolver(resolver.resolver.clone())
+			well_known: base(config, tls.as_ref())?
+				.dns_resolver(Filtered::new(plain.clone(), &federation_ranges))
 				.connect_timeout(Duration::from_secs(config.well_known_conn_timeout))
 				.read_timeout(Duration::from_secs(config.well_known_timeout))
 				.timeout(Duration::from_secs(config.well_known_timeout))
@@ -64,23 +104,23 @@ fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 				.redirect(redirect::Policy::limited(4))
 				.build()?,
 
-			federation: base(config)?
-				.dns_resolver(resolver.resolver.hooked.clone())
+			federation: base(config, tls.as_ref())?
+				.dns_resolver(Filtered::new(hooked.clone(), &federation_ranges))
 				.read_timeout(Duration::from_secs(config.federation_timeout))
 				.pool_max_idle_per_host(config.federation_idle_per_host.into())
 				.pool_idle_timeout(Duration::from_secs(config.federation_idle_timeout))
 				.redirect(redirect::Policy::limited(3))
 				.build()?,
 
-			synapse: base(config)?
-				.dns_resolver(resolver.resolver.hooked.clone())
+			synapse: base(config, tls.as_ref())?
+				.dns_resolver(Filtered::new(hooked.clone(), &federation_ranges))
 				.read_timeout(Duration::from_secs(305))
 				.pool_max_idle_per_host(0)
 				.redirect(redirect::Policy::limited(3))
 				.build()?,
 
-			sender: base(config)?
-				.dns_resolver(resolver.resolver.hooked.clone())
+			sender: base(config, tls.as_ref())?
+				.dns_resolver(Filtered::new(hooked, &federation_ranges))
 				.read_timeout(Duration::from_secs(config.sender_timeout))
 				.timeout(Duration::from_secs(config.sender_timeout))
 				.pool_max_idle_per_host(1)
@@ -88,7 +128,7 @@ fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 				.redirect(redirect::Policy::limited(2))
 				.build()?,
 
-			appservice: base(config)?
+			appservice: base(config, tls.as_ref())?
 				.dns_resolver(resolver.resolver.clone())
 				.connect_timeout(Duration::from_secs(5))
 				.read_timeout(Duration::from_secs(config.appservice_timeout))
@@ -98,27 +138,31 @@ fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 				.redirect(redirect::Policy::limited(2))
 				.build()?,
 
-			pusher: base(config)?
-				.dns_resolver(resolver.resolver.clone())
+			pusher: base(config, tls.as_ref())?
+				.dns_resolver(Filtered::new(plain, &pusher_ranges))
 				.pool_max_idle_per_host(1)
 				.pool_idle_timeout(Duration::from_secs(config.pusher_idle_timeout))
 				.redirect(redirect::Policy::limited(2))
 				.build()?,
 
-			cidr_range_denylist: config
-				.ip_range_denylist
-				.iter()
-				.map(IPAddress::parse)
-				.inspect(|cidr| trace!("Denied CIDR range: {cidr:?}"))
-				.collect::<Result<_, String>>()
-				.map_err(|e| err!(Config("ip_range_denylist", e)))?,
+			federation_ranges,
+			pusher_ranges,
+			url_preview_ranges,
 		}))
 	}
 
 	fn name(&self) -> &str { service::make_name(std::module_path!()) }
 }
 
-fn base(config: &Config) -> Result<reqwest::ClientBuilder> {
+fn parse_ranges(ranges: &[String]) -> Result<Vec<IPAddress>, String> {
+	ranges
+		.iter()
+		.map(IPAddress::parse)
+		.inspect(|cidr| trace!("Allowed CIDR range: {cidr:?}"))
+		.collect()
+}
+
+fn base(config: &Config, tls: Option<&ClientConfig>) -> Result<reqwest::ClientBuilder> {
 	let mut builder = reqwest::Client::builder()
 		.hickory_dns(true)
 		.connect_timeout(Duration::from_secs(config.request_conn_timeout))
@@ -173,6 +217,10 @@ fn base(config: &Config) -> Result<reqwest::ClientBuilder> {
 		builder = builder.no_zstd();
 	};
 
+	if let Some(tls) = tls {
+		builder = builder.use_preconfigured_tls(tls.clone());
+	}
+
 	match config.proxy.to_proxy()? {
 		| Some(proxy) => Ok(builder.proxy(proxy)),
 		| _ => Ok(builder),
@@ -212,12 +260,3 @@ fn builder_interface(
 		Ok(builder)
 	}
 }
-
-#[inline]
-#[must_use]
-#[implement(Service)]
-pub fn valid_cidr_range(&self, ip: &IPAddress) -> bool {
-	self.cidr_range_denylist
-		.iter()
-		.all(|cidr| !cidr.includes(ip))
-}
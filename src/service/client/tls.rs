@@ -0,0 +1,160 @@
+//! Destination-specific certificate verification, configured on
+//! `[[global.proxy.by_domain]]` entries.
+
+use std::{path::Path, sync::Arc};
+
+use rustls::{
+	ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme,
+	client::{
+		WebPkiServerVerifier,
+		danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+	},
+	crypto::{CryptoProvider, aws_lc_rs},
+	pki_types::{CertificateDer, ServerName, UnixTime, pem::PemObject},
+};
+use tuwunel_core::{Config, Result, config::proxy::PartialProxyConfig, debug_warn, err};
+
+#[derive(Debug)]
+struct Verifier {
+	default: Arc<WebPkiServerVerifier>,
+	overrides: Vec<(PartialProxyConfig, Override)>,
+}
+
+#[derive(Debug)]
+enum Override {
+	Insecure,
+	Roots(Arc<WebPkiServerVerifier>),
+}
+
+/// TLS configuration for clients when any destination overrides certificate
+/// verification; otherwise `None`, leaving reqwest's own configuration.
+pub(super) fn client_config(config: &Config) -> Result<Option<ClientConfig>> {
+	if config.allow_invalid_tls_certificates {
+		return Ok(None);
+	}
+
+	let provider = Arc::new(aws_lc_rs::default_provider());
+	let overrides: Vec<_> = config
+		.proxy
+		.tls_overrides()
+		.filter_map(|entry| {
+			let verify = if entry.tls_insecure() {
+				Ok(Override::Insecure)
+			} else {
+				load_roots(entry.ca_file.as_deref()?)
+					.and_then(|roots| verifier(roots, &provider))
+					.map(Override::Roots)
+			};
+
+			Some(verify.map(|verify| (entry.clone(), verify)))
+		})
+		.collect::<Result<_>>()?;
+
+	if overrides.is_empty() {
+		return Ok(None);
+	}
+
+	let native = rustls_native_certs::load_native_certs();
+	for error in native.errors {
+		debug_warn!("Failed to load system certificate: {error}");
+	}
+
+	let mut roots = RootCertStore::empty();
+	roots.add_parsable_certificates(native.certs);
+
+	let verifier = Arc::new(Verifier {
+		default: verifier(roots, &provider)?,
+		overrides,
+	});
+
+	let mut tls = ClientConfig::builder_with_provider(provider)
+		.with_safe_default_protocol_versions()
+		.map_err(|e| err!(Config("proxy", "{e}")))?
+		.dangerous()
+		.with_custom_certificate_verifier(verifier)
+		.with_no_client_auth();
+
+	tls.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+
+	Ok(Some(tls))
+}
+
+fn load_roots(path: &Path) -> Result<RootCertStore> {
+	let mut roots = RootCertStore::empty();
+	for cert in CertificateDer::pem_file_iter(path)
+		.map_err(|e| err!(Config("proxy", "Failed to read {path:?}: {e}")))?
+	{
+		let cert =
+			cert.map_err(|e| err!(Config("proxy", "Invalid certificate in {path:?}: {e}")))?;
+		roots
+			.add(cert)
+			.map_err(|e| err!(Config("proxy", "Invalid CA certificate in {path:?}: {e}")))?;
+	}
+
+	Ok(roots)
+}
+
+fn verifier(
+	roots: RootCertStore,
+	provider: &Arc<CryptoProvider>,
+) -> Result<Arc<WebPkiServerVerifier>> {
+	WebPkiServerVerifier::builder_with_provider(Arc::new(roots), provider.clone())
+		.build()
+		.map_err(|e| err!(Config("proxy", "{e}")))
+}
+
+impl Verifier {
+	fn select(&self, server_name: &ServerName<'_>) -> Option<&Override> {
+		let ServerName::DnsName(name) = server_name else {
+			return None;
+		};
+
+		self.overrides
+			.iter()
+			.find(|(entry, _)| entry.matches(name.as_ref()))
+			.map(|(_, verify)| verify)
+	}
+}
+
+impl ServerCertVerifier for Verifier {
+	fn verify_server_cert(
+		&self,
+		end_entity: &CertificateDer<'_>,
+		intermediates: &[CertificateDer<'_>],
+		server_name: &ServerName<'_>,
+		ocsp_response: &[u8],
+		now: UnixTime,
+	) -> Result<ServerCertVerified, rustls::Error> {
+		let verifier = match self.select(server_name) {
+			| Some(Override::Insecure) => return Ok(ServerCertVerified::assertion()),
+			| Some(Override::Roots(verifier)) => verifier,
+			| None => &self.default,
+		};
+
+		verifier.verify_server_cert(end_entity, intermediates, server_name, ocsp_response, now)
+	}
+
+	fn verify_tls12_signature(
+		&self,
+		message: &[u8],
+		cert: &CertificateDer<'_>,
+		dss: &DigitallySignedStruct,
+	) -> Result<HandshakeSignatureValid, rustls::Error> {
+		self.default
+			.verify_tls12_signature(message, cert, dss)
+	}
+
+	fn verify_tls13_signature(
+		&self,
+		message: &[u8],
+		cert: &CertificateDer<'_>,
+		dss: &DigitallySignedStruct,
+	) -> Result<HandshakeSignatureValid, rustls::Error> {
+		self.default
+			.verify_tls13_signature(message, cert, dss)
+	}
+
+	fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+		self.default.supported_verify_schemes()
+	}
+}
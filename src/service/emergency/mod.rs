@@ -1,38 +1,57 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use async_trait::async_trait;
 use ruma::{
+	UserId,
 	events::{
 		GlobalAccountDataEvent, GlobalAccountDataEventType, push_rules::PushRulesEventContent,
 	},
 	push::Ruleset,
 };
-use tuwunel_core::{Result, debug_warn, error, warn};
+use tuwunel_core::{Err, Result, Server, debug_warn, error, utils, utils::hash::sha256, warn};
+use tuwunel_database::Map;
 
-use crate::{Dep, account_data, config, globals, users};
+use crate::{Dep, account_data, admin, config, globals, users};
 
 pub struct Service {
 	services: Services,
+	db: Data,
+}
+
+struct Data {
+	global: Arc<Map>,
 }
 
 struct Services {
+	server: Arc<Server>,
 	account_data: Dep<account_data::Service>,
+	admin: Dep<admin::Service>,
 	config: Dep<config::Service>,
 	globals: Dep<globals::Service>,
 	users: Dep<users::Service>,
 }
 
+/// Key in the global map holding the digest of the last consumed single-use
+/// emergency password.
+const EMERGENCY_PASSWORD_USED: &[u8] = b"emergency_password_used";
+
+/// Length of the random password replacing a consumed emergency password.
+const PASSWORD_LENGTH: usize = 64;
+
 #[async_trait]
 impl crate::Service for Service {
 	fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 		Ok(Arc::new(Self {
 			services: Services {
+				server: args.server.clone(),
 				account_data: args.depend::<account_data::Service>("account_data"),
+				admin: args.depend::<admin::Service>("admin"),
 				config: args.depend::<config::Service>("config"),
 
 				globals: args.depend::<globals::Service>("globals"),
 				users: args.depend::<users::Service>("users"),
 			},
+			db: Data { global: args.db["global"].clone() },
 		}))
 	}
 
@@ -57,6 +76,28 @@ async fn worker(self: Arc<Self>) -> Result {
 			return Ok(());
 		}
 
+		if self.services.config.password_provider.enable
+			&& !self
+				.services
+				.config
+				.password_provider
+				.fallback_to_local
+		{
+			warn!(
+				"emergency password feature not available with a password provider enabled \
+				 without fallback_to_local."
+			);
+			return Ok(());
+		}
+
+		if self.is_consumed().await {
+			warn!(
+				"The emergency password has already been used once; set a new one to regain \
+				 access to the server account."
+			);
+			return Ok(());
+		}
+
 		self.set_emergency_access()
 			.await
 			.inspect_err(|e| {
@@ -68,6 +109,73 @@ fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
 }
 
 impl Service {
+	/// Called by the auth path after the server user has logged in or completed
+	/// a UIA password stage with the emergency password. Rejects the
+	/// authentication once `emergency_password_lifetime` has elapsed, alerts
+	/// the admin room, and when `emergency_password_single_use` is enabled,
+	/// invalidates the password so it cannot be used again.
+	pub async fn password_used(&self, user_id: &UserId) -> Result {
+		let config = &self.services.config;
+		let Some(password) = config.emergency_password.as_deref() else {
+			return Ok(());
+		};
+
+		if user_id != self.services.globals.server_user.as_ref() {
+			return Ok(());
+		}
+
+		if config
+			.emergency_password_lifetime
+			.map(Duration::from_secs)
+			.is_some_and(|lifetime| {
+				self.services
+					.server
+					.started
+					.elapsed()
+					.is_ok_and(|elapsed| elapsed > lifetime)
+			}) {
+			warn!("Rejected authentication as {user_id} with an expired emergency password.");
+			return Err!(Request(Forbidden("The emergency password has expired.")));
+		}
+
+		warn!("The emergency password was used to authenticate as {user_id}.");
+		if config.emergency_password_notice {
+			self.services
+				.admin
+				.notice(&format!("The emergency password was used to authenticate as {user_id}."))
+				.await;
+		}
+
+		if config.emergency_password_single_use {
+			self.db
+				.global
+				.insert(EMERGENCY_PASSWORD_USED, sha256::hash(password));
+
+			self.services
+				.users
+				.set_password(user_id, Some(&utils::random_string(PASSWORD_LENGTH)))
+				.await?;
+		}
+
+		Ok(())
+	}
+
+	/// Whether the configured single-use emergency password was used already.
+	/// Changing the password in the config makes it usable again.
+	async fn is_consumed(&self) -> bool {
+		let Some(password) = self.services.config.emergency_password.as_deref() else {
+			return false;
+		};
+
+		self.services.config.emergency_password_single_use
+			&& self
+				.db
+				.global
+				.get(EMERGENCY_PASSWORD_USED)
+				.await
+				.is_ok_and(|used| *used == sha256::hash(password))
+	}
+
 	/// Sets the emergency password and push rules for the server user account
 	/// in case emergency password is set
 	async fn set_emergency_access(&self) -> Result {
@@ -1,33 +1,84 @@
 mod execute;
+mod rules;
+mod stats;
+#[cfg(test)]
+mod tests;
 
-use std::sync::Arc;
+use std::{
+	sync::{Arc, Mutex},
+	time::Duration,
+};
 
+use async_trait::async_trait;
+use tokio::time::sleep;
 use tuwunel_core::{Result, Server};
+use tuwunel_database::Map;
 
-use crate::{Dep, client, resolver, server_keys};
+pub use self::{rules::FederationRule, stats::ServerStats};
+use crate::{Dep, client, globals, resolver, server_keys};
 
 pub struct Service {
 	services: Services,
+	db: Data,
+	stats: Mutex<stats::Stats>,
 }
 
 struct Services {
 	server: Arc<Server>,
 	client: Dep<client::Service>,
+	globals: Dep<globals::Service>,
 	resolver: Dep<resolver::Service>,
 	server_keys: Dep<server_keys::Service>,
 }
 
+struct Data {
+	servername_federationrule: Arc<Map>,
+	servername_federationstats: Arc<Map>,
+}
+
+#[async_trait]
 impl crate::Service for Service {
 	fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 		Ok(Arc::new(Self {
 			services: Services {
 				server: args.server.clone(),
 				client: args.depend::<client::Service>("client"),
+				globals: args.depend::<globals::Service>("globals"),
 				resolver: args.depend::<resolver::Service>("resolver"),
 				server_keys: args.depend::<server_keys::Service>("server_keys"),
 			},
+			db: Data {
+				servername_federationrule: args.db["servername_federationrule"].clone(),
+				servername_federationstats: args.db["servername_federationstats"].clone(),
+			},
+			stats: Default::default(),
 		}))
 	}
 
+	async fn worker(self: Arc<Self>) -> Result {
+		let interval = self
+			.services
+			.server
+			.config
+			.federation_stats_interval;
+		if interval == 0 || self.services.globals.is_read_only() {
+			return Ok(());
+		}
+
+		self.load_stats().await;
+		let interval = Duration::from_secs(interval);
+		while self.services.server.running() {
+			tokio::select! {
+				() = sleep(interval) => {},
+				() = self.services.server.until_shutdown() => break,
+			}
+
+			self.persist_stats();
+		}
+
+		self.persist_stats();
+		Ok(())
+	}
+
 	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
 }
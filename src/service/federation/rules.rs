@@ -0,0 +1,93 @@
+//! Federation rules set at runtime by administrators, consulted alongside
+//! `forbidden_remote_server_names`.
+
+use std::fmt;
+
+use futures::{Stream, StreamExt};
+use ruma::ServerName;
+use tuwunel_core::{
+	implement,
+	utils::stream::{ReadyExt, TryIgnore},
+};
+use tuwunel_database::Deserialized;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FederationRule {
+	/// Federate with the server even if `forbidden_remote_server_names`
+	/// matches it.
+	Allow,
+
+	/// Refuse federation with the server in both directions.
+	Block,
+}
+
+impl FederationRule {
+	#[must_use]
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			| Self::Allow => "allow",
+			| Self::Block => "block",
+		}
+	}
+
+	fn parse(rule: &str) -> Option<Self> {
+		match rule {
+			| "allow" => Some(Self::Allow),
+			| "block" => Some(Self::Block),
+			| _ => None,
+		}
+	}
+}
+
+impl fmt::Display for FederationRule {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { f.write_str(self.as_str()) }
+}
+
+/// Whether federation with `server` is permitted, by its runtime rule or
+/// otherwise by `forbidden_remote_server_names`.
+#[implement(super::Service)]
+pub async fn is_federation_allowed(&self, server: &ServerName) -> bool {
+	match self.get_rule(server).await {
+		| Some(rule) => rule == FederationRule::Allow,
+		| None => !self
+			.services
+			.server
+			.config
+			.forbidden_remote_server_names
+			.is_match(server.host()),
+	}
+}
+
+#[implement(super::Service)]
+pub fn set_rule(&self, server: &ServerName, rule: FederationRule) {
+	self.db
+		.servername_federationrule
+		.insert(server, rule.as_str());
+}
+
+#[implement(super::Service)]
+pub fn remove_rule(&self, server: &ServerName) {
+	self.db.servername_federationrule.remove(server);
+}
+
+#[implement(super::Service)]
+pub async fn get_rule(&self, server: &ServerName) -> Option<FederationRule> {
+	self.db
+		.servername_federationrule
+		.get(server)
+		.await
+		.deserialized::<&str>()
+		.ok()
+		.and_then(FederationRule::parse)
+}
+
+#[implement(super::Service)]
+pub fn rules(&self) -> impl Stream<Item = (&ServerName, FederationRule)> + Send + '_ {
+	self.db
+		.servername_federationrule
+		.stream()
+		.ignore_err()
+		.ready_filter_map(|(server, rule): (&ServerName, &str)| {
+			FederationRule::parse(rule).map(|rule| (server, rule))
+		})
+}
@@ -0,0 +1,319 @@
+//! Statistics of the transactions exchanged with each remote server, counted
+//! in memory and persisted periodically by the service worker.
+
+use std::{
+	collections::{HashMap, HashSet, VecDeque},
+	fmt::{self, Write},
+	sync::atomic::Ordering,
+	time::Duration,
+};
+
+use futures::StreamExt;
+use ruma::{OwnedServerName, ServerName};
+use serde::{Deserialize, Serialize};
+use tuwunel_core::{
+	debug, implement,
+	metrics::{write_sample, write_type},
+	utils::{stream::TryIgnore, time::now_millis},
+};
+use tuwunel_database::Json;
+
+/// Round-trip times kept per server for the median latency.
+const LATENCY_SAMPLES: usize = 64;
+
+#[derive(Default)]
+pub(super) struct Stats {
+	servers: HashMap<OwnedServerName, ServerStats>,
+
+	/// Servers whose statistics changed since they were last persisted.
+	dirty: HashSet<OwnedServerName>,
+}
+
+/// Transactions exchanged with a remote server.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct ServerStats {
+	pub pdus_received: u64,
+	pub edus_received: u64,
+
+	/// Size of the PDUs and EDUs received, in bytes.
+	pub bytes_received: u64,
+
+	/// PDUs received which could not be accepted.
+	pub pdus_rejected: u64,
+
+	pub pdus_sent: u64,
+	pub edus_sent: u64,
+
+	/// Size of the PDUs and EDUs sent, in bytes.
+	pub bytes_sent: u64,
+
+	/// Transactions sent which failed.
+	pub send_failures: u64,
+
+	/// When a transaction was last received, in milliseconds since the epoch.
+	pub last_received: Option<u64>,
+
+	/// When a transaction was last sent successfully, in milliseconds since
+	/// the epoch.
+	pub last_sent: Option<u64>,
+
+	/// Round-trip times of the latest transactions sent, in milliseconds.
+	#[serde(default)]
+	latencies: VecDeque<u64>,
+}
+
+impl ServerStats {
+	/// Median round-trip time of the latest transactions sent.
+	#[must_use]
+	pub fn median_latency(&self) -> Option<Duration> {
+		let mut latencies: Vec<_> = self.latencies.iter().copied().collect();
+		latencies.sort_unstable();
+		latencies
+			.get(latencies.len() / 2)
+			.copied()
+			.map(Duration::from_millis)
+	}
+
+	pub(super) fn push_latency(&mut self, elapsed: Duration) {
+		if self.latencies.len() >= LATENCY_SAMPLES {
+			self.latencies.pop_front();
+		}
+
+		self.latencies
+			.push_back(elapsed.as_millis().try_into().unwrap_or(u64::MAX));
+	}
+
+	/// Adds persisted statistics to those counted since startup.
+	pub(super) fn absorb(&mut self, persisted: Self) {
+		self.pdus_received = self
+			.pdus_received
+			.saturating_add(persisted.pdus_received);
+		self.edus_received = self
+			.edus_received
+			.saturating_add(persisted.edus_received);
+		self.bytes_received = self
+			.bytes_received
+			.saturating_add(persisted.bytes_received);
+		self.pdus_rejected = self
+			.pdus_rejected
+			.saturating_add(persisted.pdus_rejected);
+		self.pdus_sent = self.pdus_sent.saturating_add(persisted.pdus_sent);
+		self.edus_sent = self.edus_sent.saturating_add(persisted.edus_sent);
+		self.bytes_sent = self
+			.bytes_sent
+			.saturating_add(persisted.bytes_sent);
+		self.send_failures = self
+			.send_failures
+			.saturating_add(persisted.send_failures);
+		self.last_received = self.last_received.max(persisted.last_received);
+		self.last_sent = self.last_sent.max(persisted.last_sent);
+
+		let mut latencies = persisted.latencies;
+		latencies.append(&mut self.latencies);
+		latencies.drain(..latencies.len().saturating_sub(LATENCY_SAMPLES));
+		self.latencies = latencies;
+	}
+}
+
+/// Counts a transaction received from `origin`.
+#[implement(super::Service)]
+pub fn record_received(
+	&self,
+	origin: &ServerName,
+	pdus: usize,
+	edus: usize,
+	bytes: usize,
+	rejected: usize,
+) {
+	let metrics = &self.services.server.metrics;
+	metrics
+		.federation_pdus_received
+		.fetch_add(as_u64(pdus), Ordering::Relaxed);
+	metrics
+		.federation_edus_received
+		.fetch_add(as_u64(edus), Ordering::Relaxed);
+	metrics
+		.federation_bytes_received
+		.fetch_add(as_u64(bytes), Ordering::Relaxed);
+
+	self.update_stats(origin, |stats| {
+		stats.pdus_received = stats.pdus_received.saturating_add(as_u64(pdus));
+		stats.edus_received = stats.edus_received.saturating_add(as_u64(edus));
+		stats.bytes_received = stats.bytes_received.saturating_add(as_u64(bytes));
+		stats.pdus_rejected = stats
+			.pdus_rejected
+			.saturating_add(as_u64(rejected));
+		stats.last_received = Some(now_millis());
+	});
+}
+
+/// Counts a transaction sent to `destination`, which took `elapsed` to be
+/// answered or to fail.
+#[implement(super::Service)]
+pub fn record_sent(
+	&self,
+	destination: &ServerName,
+	pdus: usize,
+	edus: usize,
+	bytes: usize,
+	elapsed: Duration,
+	succeeded: bool,
+) {
+	let metrics = &self.services.server.metrics;
+	if !succeeded {
+		metrics
+			.federation_send_failures
+			.fetch_add(1, Ordering::Relaxed);
+
+		self.update_stats(destination, |stats| {
+			stats.send_failures = stats.send_failures.saturating_add(1);
+		});
+
+		return;
+	}
+
+	metrics
+		.federation_pdus_sent
+		.fetch_add(as_u64(pdus), Ordering::Relaxed);
+	metrics
+		.federation_edus_sent
+		.fetch_add(as_u64(edus), Ordering::Relaxed);
+	metrics
+		.federation_bytes_sent
+		.fetch_add(as_u64(bytes), Ordering::Relaxed);
+
+	self.update_stats(destination, |stats| {
+		stats.pdus_sent = stats.pdus_sent.saturating_add(as_u64(pdus));
+		stats.edus_sent = stats.edus_sent.saturating_add(as_u64(edus));
+		stats.bytes_sent = stats.bytes_sent.saturating_add(as_u64(bytes));
+		stats.last_sent = Some(now_millis());
+		stats.push_latency(elapsed);
+	});
+}
+
+/// Statistics of a remote server.
+#[implement(super::Service)]
+#[must_use]
+pub fn server_stats(&self, server: &ServerName) -> Option<ServerStats> {
+	self.stats
+		.lock()
+		.expect("locked")
+		.servers
+		.get(server)
+		.cloned()
+}
+
+/// Statistics of every remote server federated with.
+#[implement(super::Service)]
+#[must_use]
+pub fn all_stats(&self) -> Vec<(OwnedServerName, ServerStats)> {
+	self.stats
+		.lock()
+		.expect("locked")
+		.servers
+		.iter()
+		.map(|(server, stats)| (server.clone(), stats.clone()))
+		.collect()
+}
+
+#[implement(super::Service)]
+fn update_stats<F>(&self, server: &ServerName, update: F)
+where
+	F: FnOnce(&mut ServerStats),
+{
+	let mut stats = self.stats.lock().expect("locked");
+	update(
+		stats
+			.servers
+			.entry(server.to_owned())
+			.or_default(),
+	);
+	stats.dirty.insert(server.to_owned());
+}
+
+/// Loads the persisted statistics, adding them to any counted before the
+/// worker started.
+#[implement(super::Service)]
+pub(super) async fn load_stats(&self) {
+	let persisted: Vec<(OwnedServerName, ServerStats)> = self
+		.db
+		.servername_federationstats
+		.stream()
+		.ignore_err()
+		.map(|(server, stats): (&ServerName, ServerStats)| (server.to_owned(), stats))
+		.collect()
+		.await;
+
+	debug!(servers = persisted.len(), "Loaded federation statistics");
+	let mut stats = self.stats.lock().expect("locked");
+	for (server, persisted) in persisted {
+		stats
+			.servers
+			.entry(server)
+			.or_default()
+			.absorb(persisted);
+	}
+}
+
+/// Writes the statistics which changed since they were last persisted. They
+/// are copied out first so the lock is not held while writing.
+#[implement(super::Service)]
+pub(super) fn persist_stats(&self) {
+	let changed: Vec<(OwnedServerName, ServerStats)> = {
+		let mut stats = self.stats.lock().expect("locked");
+		let dirty = std::mem::take(&mut stats.dirty);
+		dirty
+			.into_iter()
+			.filter_map(|server| {
+				let server_stats = stats.servers.get(&server)?.clone();
+				Some((server, server_stats))
+			})
+			.collect()
+	};
+
+	for (server, server_stats) in &changed {
+		self.db
+			.servername_federationstats
+			.raw_put(server.as_str(), Json(server_stats));
+	}
+}
+
+/// Writes the statistics of each remote server in the Prometheus text
+/// exposition format, labelled by server name.
+#[implement(super::Service)]
+pub fn export_stats<W: Write>(&self, out: &mut W) -> fmt::Result {
+	type Value = fn(&ServerStats) -> Option<u64>;
+
+	let metrics: [(&str, &str, Value); 9] = [
+		("federation_server_pdus_received", "counter", |stats| Some(stats.pdus_received)),
+		("federation_server_edus_received", "counter", |stats| Some(stats.edus_received)),
+		("federation_server_bytes_received", "counter", |stats| {
+			Some(stats.bytes_received)
+		}),
+		("federation_server_pdus_rejected", "counter", |stats| Some(stats.pdus_rejected)),
+		("federation_server_pdus_sent", "counter", |stats| Some(stats.pdus_sent)),
+		("federation_server_edus_sent", "counter", |stats| Some(stats.edus_sent)),
+		("federation_server_bytes_sent", "counter", |stats| Some(stats.bytes_sent)),
+		("federation_server_send_failures", "counter", |stats| Some(stats.send_failures)),
+		("federation_server_latency_median_ms", "gauge", |stats| {
+			stats
+				.median_latency()
+				.map(|latency| latency.as_millis().try_into().unwrap_or(u64::MAX))
+		}),
+	];
+
+	let mut servers = self.all_stats();
+	servers.sort_by(|(a, _), (b, _)| a.cmp(b));
+	for (name, kind, value) in metrics {
+		write_type(out, name, kind)?;
+		for (server, stats) in &servers {
+			if let Some(value) = value(stats) {
+				write_sample(out, name, &[("server", server.as_str())], value)?;
+			}
+		}
+	}
+
+	Ok(())
+}
+
+fn as_u64(count: usize) -> u64 { count.try_into().unwrap_or(u64::MAX) }
@@ -0,0 +1,71 @@
+use std::time::Duration;
+
+use super::stats::ServerStats;
+
+fn with_latencies(millis: &[u64]) -> ServerStats {
+	let mut stats = ServerStats::default();
+	for &ms in millis {
+		stats.push_latency(Duration::from_millis(ms));
+	}
+
+	stats
+}
+
+#[test]
+fn median_latency_empty() {
+	assert_eq!(ServerStats::default().median_latency(), None);
+}
+
+#[test]
+fn median_latency_unsorted() {
+	let stats = with_latencies(&[300, 100, 200]);
+	assert_eq!(stats.median_latency(), Some(Duration::from_millis(200)));
+}
+
+#[test]
+fn median_latency_latest_samples() {
+	let mut millis = vec![1; 64];
+	millis.extend([1000; 40]);
+	let stats = with_latencies(&millis);
+	assert_eq!(stats.median_latency(), Some(Duration::from_millis(1000)));
+}
+
+#[test]
+fn absorb_sums_counters() {
+	let mut stats = ServerStats {
+		pdus_received: 2,
+		bytes_sent: 10,
+		send_failures: 1,
+		last_received: Some(50),
+		last_sent: None,
+		..Default::default()
+	};
+
+	stats.absorb(ServerStats {
+		pdus_received: 3,
+		bytes_sent: u64::MAX,
+		send_failures: 4,
+		last_received: Some(20),
+		last_sent: Some(30),
+		..Default::default()
+	});
+
+	assert_eq!(stats.pdus_received, 5);
+	assert_eq!(stats.bytes_sent, u64::MAX);
+	assert_eq!(stats.send_failures, 5);
+	assert_eq!(stats.last_received, Some(50));
+	assert_eq!(stats.last_sent, Some(30));
+}
+
+#[test]
+fn absorb_keeps_latest_latencies() {
+	// Persisted samples are older than those taken since startup, so the
+	// latter are kept when there are too many.
+	let mut stats = with_latencies(&[1000; 40]);
+	stats.absorb(with_latencies(&[1; 64]));
+	assert_eq!(stats.median_latency(), Some(Duration::from_millis(1000)));
+
+	let mut stats = with_latencies(&[1000; 10]);
+	stats.absorb(with_latencies(&[1; 64]));
+	assert_eq!(stats.median_latency(), Some(Duration::from_millis(1)));
+}
@@ -0,0 +1,79 @@
+//! Policy on the localparts of new usernames and room aliases; see
+//! `max_username_length` and the options following it.
+
+#[cfg(test)]
+mod tests;
+
+use regex::RegexSet;
+use tuwunel_core::{Err, Result, implement};
+
+/// Checks a username about to be registered against the configured length,
+/// allowed patterns and reserved prefixes. Reserved prefixes are permitted
+/// when `privileged`, i.e. for appservices.
+#[implement(super::Service)]
+pub fn check_username_policy(&self, localpart: &str, privileged: bool) -> Result {
+	let config = &self.server.config;
+	let max = config.max_username_length;
+	if localpart.len() > max {
+		return Err!(Request(InvalidUsername("Username is longer than {max} bytes.")));
+	}
+
+	if !matches_allowed(&config.allowed_username_patterns, localpart) {
+		return Err!(Request(InvalidUsername(
+			"Username contains characters not allowed on this server."
+		)));
+	}
+
+	if let Some(prefix) = reserved_prefix(&config.reserved_username_prefixes, localpart) {
+		if !privileged {
+			return Err!(Request(Exclusive(
+				"Usernames starting with {prefix:?} are reserved on this server."
+			)));
+		}
+	}
+
+	Ok(())
+}
+
+/// Checks the localpart of a room alias about to be created against the
+/// configured length, allowed patterns and reserved prefixes. Reserved
+/// prefixes are permitted when `privileged`, i.e. for appservices and server
+/// admins.
+#[implement(super::Service)]
+pub fn check_alias_policy(&self, localpart: &str, privileged: bool) -> Result {
+	let config = &self.server.config;
+	let max = config.max_alias_length;
+	if localpart.len() > max {
+		return Err!(Request(InvalidParam("Room alias is longer than {max} bytes.")));
+	}
+
+	if !matches_allowed(&config.allowed_alias_patterns, localpart) {
+		return Err!(Request(InvalidParam(
+			"Room alias contains characters not allowed on this server."
+		)));
+	}
+
+	if let Some(prefix) = reserved_prefix(&config.reserved_alias_prefixes, localpart) {
+		if !privileged {
+			return Err!(Request(Exclusive(
+				"Room aliases starting with {prefix:?} are reserved on this server."
+			)));
+		}
+	}
+
+	Ok(())
+}
+
+fn matches_allowed(allowed: &RegexSet, localpart: &str) -> bool {
+	allowed.is_empty() || allowed.is_match(localpart)
+}
+
+/// The configured prefix the localpart starts with, ignoring case; usernames
+/// are lowercased before they get here, while prefixes are written freely.
+fn reserved_prefix<'a>(reserved: &'a [String], localpart: &str) -> Option<&'a str> {
+	let localpart = localpart.to_lowercase();
+	reserved
+		.iter()
+		.map(String::as_str)
+		.find(|prefix| localpart.starts_with(&prefix.to_lowercase()))
+}
@@ -0,0 +1,40 @@
+use regex::RegexSet;
+
+use super::{matches_allowed, reserved_prefix};
+
+fn reserved() -> Vec<String> { vec!["Admin".to_owned(), "_bridge_".to_owned()] }
+
+#[test]
+fn allowed_without_patterns() {
+	assert!(matches_allowed(&RegexSet::empty(), "anything.goes"));
+}
+
+#[test]
+fn allowed_by_any_pattern() {
+	let allowed = RegexSet::new([r"^[a-z0-9]+$", r"^bot-[a-z]+$"]).expect("valid patterns");
+
+	assert!(matches_allowed(&allowed, "alice42"));
+	assert!(matches_allowed(&allowed, "bot-news"));
+	assert!(!matches_allowed(&allowed, "alice.smith"));
+	assert!(!matches_allowed(&allowed, "bot-"));
+}
+
+#[test]
+fn reserved_prefix_ignores_case() {
+	assert_eq!(reserved_prefix(&reserved(), "admin"), Some("Admin"));
+	assert_eq!(reserved_prefix(&reserved(), "administrator"), Some("Admin"));
+	assert_eq!(reserved_prefix(&reserved(), "ADMIN-room"), Some("Admin"));
+	assert_eq!(reserved_prefix(&reserved(), "_Bridge_irc"), Some("_bridge_"));
+}
+
+#[test]
+fn reserved_prefix_only_at_start() {
+	assert_eq!(reserved_prefix(&reserved(), "notadmin"), None);
+	assert_eq!(reserved_prefix(&reserved(), "my_bridge_"), None);
+	assert_eq!(reserved_prefix(&reserved(), "adm"), None);
+}
+
+#[test]
+fn reserved_prefix_none_configured() {
+	assert_eq!(reserved_prefix(&[], "admin"), None);
+}
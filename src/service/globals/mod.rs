@@ -1,4 +1,6 @@
 mod data;
+mod localpart;
+mod wal_archive;
 
 use std::{
 	collections::HashMap,
@@ -98,6 +100,20 @@ async fn clear_cache(&self) {
 			.clear();
 	}
 
+	async fn worker(self: Arc<Self>) -> Result {
+		if self.is_read_only()
+			|| self
+				.server
+				.config
+				.database_wal_archive_path
+				.is_none()
+		{
+			return Ok(());
+		}
+
+		self.wal_archive_worker().await
+	}
+
 	fn name(&self) -> &str { service::make_name(std::module_path!()) }
 }
 
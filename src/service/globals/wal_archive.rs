@@ -0,0 +1,54 @@
+//! Periodic archiving of the database write-ahead log; see
+//! `database_wal_archive_path`.
+
+use std::time::Duration;
+
+use tokio::time::sleep;
+use tuwunel_core::{Result, debug, error, implement};
+
+#[implement(super::Service)]
+pub(super) async fn wal_archive_worker(&self) -> Result {
+	let interval = Duration::from_secs(
+		self.server
+			.config
+			.database_wal_archive_interval
+			.max(1),
+	);
+
+	let db = self.db.db.clone();
+	let next = self
+		.server
+		.runtime()
+		.spawn_blocking(move || db.db.wal_archive_next())
+		.await?;
+
+	let Ok(mut next) = next.inspect_err(|e| error!("Database WAL archiving disabled: {e}"))
+	else {
+		return Ok(());
+	};
+
+	debug!(next, ?interval, "Archiving database WAL...");
+	loop {
+		// Archive once more after shutdown is requested so the archive covers
+		// writes made while stopping.
+		let db = self.db.db.clone();
+		next = self
+			.server
+			.runtime()
+			.spawn_blocking(move || db.db.wal_archive(next))
+			.await?
+			.inspect_err(|e| error!("Failed to archive database WAL: {e}"))
+			.unwrap_or(next);
+
+		if !self.server.running() {
+			break;
+		}
+
+		tokio::select! {
+			() = sleep(interval) => {},
+			() = self.server.until_shutdown() => {},
+		}
+	}
+
+	Ok(())
+}
@@ -1,6 +1,6 @@
 use std::{collections::BTreeMap, sync::Arc};
 
-use futures::StreamExt;
+use futures::{Stream, StreamExt};
 use ruma::{
 	OwnedRoomId, RoomId, UserId,
 	api::client::backup::{BackupAlgorithm, KeyBackupData, RoomKeyBackup},
@@ -82,6 +82,41 @@ pub async fn delete_backup(&self, user_id: &UserId, version: &str) {
 		.await;
 }
 
+/// Delete the user's oldest backup versions so at most `retain` remain.
+/// Returns the number of versions deleted.
+#[implement(Service)]
+pub async fn prune_backups(&self, user_id: &UserId, retain: usize) -> usize {
+	let mut versions: Vec<String> = self
+		.backup_versions(user_id)
+		.map(ToOwned::to_owned)
+		.collect()
+		.await;
+
+	versions.sort_by_key(|version| version.parse::<u64>().unwrap_or_default());
+	let excess = versions.len().saturating_sub(retain);
+	for version in &versions[..excess] {
+		self.delete_backup(user_id, version).await;
+	}
+
+	excess
+}
+
+/// All backup versions of the user, in storage order.
+#[implement(Service)]
+pub fn backup_versions<'a>(
+	&'a self,
+	user_id: &'a UserId,
+) -> impl Stream<Item = &'a str> + Send + 'a {
+	type Key<'a> = (Ignore, &'a str);
+
+	let prefix = (user_id, Interfix);
+	self.db
+		.backupid_algorithm
+		.keys_prefix(&prefix)
+		.ignore_err()
+		.map(|(_, version): Key<'_>| version)
+}
+
 #[implement(Service)]
 pub async fn update_backup<'a>(
 	&self,
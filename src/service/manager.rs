@@ -75,12 +75,28 @@ pub(super) async fn start(self: Arc<Self>) -> Result<()> {
 	}
 
 	pub(super) async fn stop(&self) {
-		if let Some(manager) = self.manager.lock().await.take() {
-			debug!("Waiting for service manager...");
-			if let Err(e) = manager.await {
+		let Some(mut manager) = self.manager.lock().await.take() else {
+			return;
+		};
+
+		let timeout = Duration::from_secs(self.server.config.shutdown_drain_timeout);
+		debug!(?timeout, "Waiting for service manager...");
+		match tokio::time::timeout(timeout, &mut manager).await {
+			| Ok(Ok(_)) => return,
+			| Ok(Err(e)) => {
 				error!("Manager shutdown error: {e:?}");
-			}
+				return;
+			},
+			| Err(_) => warn!(
+				"Service workers did not stop within {}; aborting them.",
+				time::pretty(timeout)
+			),
 		}
+
+		// The manager holds the workers lock while it waits on them.
+		manager.abort();
+		_ = manager.await;
+		self.workers.lock().await.shutdown().await;
 	}
 
 	async fn worker(&self) -> Result<()> {
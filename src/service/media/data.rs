@@ -4,14 +4,18 @@
 use ruma::{Mxc, OwnedMxcUri, UserId, http_headers::ContentDisposition};
 use tuwunel_core::{
 	Err, Result, debug, debug_info, err,
-	utils::{ReadyExt, str_from_bytes, stream::TryIgnore, string_from_bytes},
+	utils::{self, ReadyExt, str_from_bytes, stream::TryIgnore, string_from_bytes},
 };
-use tuwunel_database::{Database, Interfix, Map, serialize_key};
+use tuwunel_database::{Database, Deserialized, Interfix, Map, serialize_key};
 
 use super::{preview::UrlPreviewData, thumbnail::Dim};
 
+const LEGACY_MEDIA_FREEZE: &[u8] = b"legacy_media_freeze";
+
 pub(crate) struct Data {
+	global: Arc<Map>,
 	mediaid_file: Arc<Map>,
+	mediaid_quarantine: Arc<Map>,
 	mediaid_user: Arc<Map>,
 	url_previews: Arc<Map>,
 }
@@ -26,12 +30,31 @@ pub(super) struct Metadata {
 impl Data {
 	pub(super) fn new(db: &Arc<Database>) -> Self {
 		Self {
+			global: db["global"].clone(),
 			mediaid_file: db["mediaid_file"].clone(),
+			mediaid_quarantine: db["mediaid_quarantine"].clone(),
 			mediaid_user: db["mediaid_user"].clone(),
 			url_previews: db["url_previews"].clone(),
 		}
 	}
 
+	/// The time legacy media was frozen, recording it as now if it was not
+	/// frozen before.
+	pub(super) async fn legacy_media_freeze(&self) -> u64 {
+		if let Ok(frozen) = self
+			.global
+			.get(LEGACY_MEDIA_FREEZE)
+			.await
+			.deserialized()
+		{
+			return frozen;
+		}
+
+		let now = utils::millis_since_unix_epoch();
+		self.global.raw_put(LEGACY_MEDIA_FREEZE, now);
+		now
+	}
+
 	pub(super) fn create_file_metadata(
 		&self,
 		mxc: &Mxc<'_>,
@@ -43,7 +66,8 @@ pub(super) fn create_file_metadata(
 		let dim: &[u32] = &[dim.width, dim.height];
 		let key = (mxc, dim, content_disposition, content_type);
 		let key = serialize_key(key)?;
-		self.mediaid_file.insert(&key, []);
+		self.mediaid_file
+			.raw_put(&key, utils::millis_since_unix_epoch());
 		if let Some(user) = user {
 			let key = (mxc, user);
 			self.mediaid_user.put_raw(key, user);
@@ -79,6 +103,16 @@ pub(super) async fn delete_file_mxc(&self, mxc: &Mxc<'_>) {
 			.await;
 	}
 
+	/// When the file was stored, in milliseconds since the epoch. None for
+	/// files stored before the time was recorded.
+	pub(super) async fn file_stored(&self, key: &[u8]) -> Option<u64> {
+		self.mediaid_file
+			.get(key)
+			.await
+			.deserialized()
+			.ok()
+	}
+
 	/// Searches for all files with the given MXC
 	pub(super) async fn search_mxc_metadata_prefix(&self, mxc: &Mxc<'_>) -> Result<Vec<Vec<u8>>> {
 		debug!("MXC URI: {mxc}");
@@ -153,6 +187,25 @@ pub(super) async fn get_all_user_mxcs(&self, user_id: &UserId) -> Vec<OwnedMxcUr
 			.await
 	}
 
+	/// Marks an MXC as quarantined at the given time.
+	pub(super) fn quarantine(&self, mxc: &Mxc<'_>, timestamp: u64) {
+		self.mediaid_quarantine
+			.raw_put(mxc.to_string(), timestamp);
+	}
+
+	/// Releases an MXC from quarantine.
+	pub(super) fn unquarantine(&self, mxc: &Mxc<'_>) {
+		self.mediaid_quarantine.remove(&mxc.to_string());
+	}
+
+	/// Whether an MXC has been quarantined.
+	pub(super) async fn is_quarantined(&self, mxc: &Mxc<'_>) -> bool {
+		self.mediaid_quarantine
+			.get(&mxc.to_string())
+			.await
+			.is_ok()
+	}
+
 	/// Gets all the media keys in our database (this includes all the metadata
 	/// associated with it such as width, height, content-type, etc)
 	pub(crate) async fn get_all_media_keys(&self) -> Vec<Vec<u8>> {
@@ -5,10 +5,15 @@
 mod remote;
 mod tests;
 mod thumbnail;
-use std::{path::PathBuf, sync::Arc, time::SystemTime};
+use std::{
+	path::PathBuf,
+	sync::Arc,
+	time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
 use async_trait::async_trait;
 use base64::{Engine as _, engine::general_purpose};
+use loole::{Receiver, Sender};
 use ruma::{Mxc, OwnedMxcUri, UserId, http_headers::ContentDisposition};
 use tokio::{
 	fs,
@@ -31,8 +36,19 @@ pub struct FileMeta {
 	pub content_disposition: Option<ContentDisposition>,
 }
 
+/// A file uploaded by a local user, as listed for administration.
+#[derive(Debug)]
+pub struct UserMedia {
+	pub mxc: OwnedMxcUri,
+	pub content_type: Option<String>,
+	pub size: u64,
+	pub uploaded: Option<SystemTime>,
+}
+
 pub struct Service {
 	url_preview_mutex: MutexMap<String, ()>,
+	thumbnail_sizes: Vec<Dim>,
+	pregenerate: (Sender<OwnedMxcUri>, Receiver<OwnedMxcUri>),
 	pub(super) db: Data,
 	services: Services,
 }
@@ -53,11 +69,17 @@ struct Services {
 /// Default cross-origin resource policy.
 pub const CORP_CROSS_ORIGIN: &str = "cross-origin";
 
+/// Uploads waiting for their thumbnails to be pregenerated; beyond this they
+/// are generated on first view instead.
+const PREGENERATE_QUEUE_LIMIT: usize = 256;
+
 #[async_trait]
 impl crate::Service for Service {
 	fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 		Ok(Arc::new(Self {
 			url_preview_mutex: MutexMap::new(),
+			thumbnail_sizes: Dim::parse_sizes(&args.server.config.thumbnail_sizes)?,
+			pregenerate: loole::bounded(PREGENERATE_QUEUE_LIMIT),
 			db: Data::new(args.db),
 			services: Services {
 				server: args.server.clone(),
@@ -71,9 +93,29 @@ fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 	async fn worker(self: Arc<Self>) -> Result<()> {
 		self.create_media_dir().await?;
 
+		let config = &self.services.server.config;
+		if config.allow_legacy_media && config.freeze_legacy_media {
+			let frozen = self.db.legacy_media_freeze().await;
+			debug!(?frozen, "Legacy media endpoints are frozen");
+		}
+
+		// One upload at a time, so pregeneration never takes more than one
+		// thread from requests.
+		let receiver = self.pregenerate.1.clone();
+		while let Ok(mxc) = receiver.recv_async().await {
+			self.pregenerate(&mxc).await;
+		}
+
 		Ok(())
 	}
 
+	fn interrupt(&self) {
+		let (sender, _) = &self.pregenerate;
+		if !sender.is_closed() {
+			sender.close();
+		}
+	}
+
 	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
 }
 
@@ -159,6 +201,10 @@ pub async fn delete_from_user(&self, user: &UserId) -> Result<usize> {
 
 	/// Downloads a file.
 	pub async fn get(&self, mxc: &Mxc<'_>) -> Result<Option<FileMeta>> {
+		if self.db.is_quarantined(mxc).await {
+			return Ok(None);
+		}
+
 		match self
 			.db
 			.search_file_metadata(mxc, &Dim::default())
@@ -181,6 +227,69 @@ pub async fn get(&self, mxc: &Mxc<'_>) -> Result<Option<FileMeta>> {
 		}
 	}
 
+	/// Quarantines a file: it remains stored but is no longer served to
+	/// clients or federation, nor fetched again when remote.
+	pub fn quarantine(&self, mxc: &Mxc<'_>) {
+		debug_info!(?mxc, "Quarantining media");
+		self.db
+			.quarantine(mxc, utils::millis_since_unix_epoch());
+	}
+
+	/// Releases a file from quarantine so it is served and fetched again.
+	pub fn unquarantine(&self, mxc: &Mxc<'_>) {
+		debug_info!(?mxc, "Releasing media from quarantine");
+		self.db.unquarantine(mxc);
+	}
+
+	/// Whether the file has been quarantined.
+	pub async fn is_quarantined(&self, mxc: &Mxc<'_>) -> bool {
+		self.db.is_quarantined(mxc).await
+	}
+
+	/// Gets all the MXC URIs uploaded by a user.
+	pub async fn get_user_mxcs(&self, user: &UserId) -> Vec<OwnedMxcUri> {
+		self.db.get_all_user_mxcs(user).await
+	}
+
+	/// Gets the files uploaded by a user with their content type, and the size
+	/// and upload time of the stored original. The upload time is unknown for
+	/// files stored before it was recorded. Thumbnails are not included.
+	pub async fn get_user_media(&self, user: &UserId) -> Vec<UserMedia> {
+		let mut media = Vec::new();
+		for mxc in self.get_user_mxcs(user).await {
+			let Ok(parsed) = Mxc::try_from(mxc.as_str()) else {
+				continue;
+			};
+
+			let Ok(metadata) = self
+				.db
+				.search_file_metadata(&parsed, &Dim::default())
+				.await
+			else {
+				continue;
+			};
+
+			let size = fs::metadata(self.get_media_file(&metadata.key))
+				.await
+				.map_or(0, |file| file.len());
+
+			let uploaded = self
+				.db
+				.file_stored(&metadata.key)
+				.await
+				.and_then(|stored| UNIX_EPOCH.checked_add(Duration::from_millis(stored)));
+
+			media.push(UserMedia {
+				content_type: metadata.content_type,
+				size,
+				uploaded,
+				mxc,
+			});
+		}
+
+		media
+	}
+
 	/// Gets all the MXC URIs in our media database
 	pub async fn get_all_mxcs(&self) -> Result<Vec<OwnedMxcUri>> {
 		let all_keys = self.db.get_all_media_keys().await;
@@ -383,6 +492,10 @@ async fn create_media_file(&self, key: &[u8]) -> Result<fs::File> {
 
 	#[inline]
 	pub async fn get_metadata(&self, mxc: &Mxc<'_>) -> Option<FileMeta> {
+		if self.db.is_quarantined(mxc).await {
+			return None;
+		}
+
 		self.db
 			.search_file_metadata(mxc, &Dim::default())
 			.await
@@ -394,6 +507,38 @@ pub async fn get_metadata(&self, mxc: &Mxc<'_>) -> Option<FileMeta> {
 			.ok()
 	}
 
+	/// Fails with M_NOT_FOUND for media which the legacy unauthenticated
+	/// endpoints may not serve because it was stored after they were frozen.
+	pub async fn check_legacy_freeze(&self, mxc: &Mxc<'_>) -> Result {
+		if !self.services.server.config.freeze_legacy_media {
+			return Ok(());
+		}
+
+		let frozen = self.db.legacy_media_freeze().await;
+		for key in self
+			.db
+			.search_mxc_metadata_prefix(mxc)
+			.await
+			.unwrap_or_default()
+		{
+			// Files without a time were stored before it was recorded, which
+			// was before any freeze.
+			if self
+				.db
+				.file_stored(&key)
+				.await
+				.is_none_or(|stored| stored < frozen)
+			{
+				return Ok(());
+			}
+		}
+
+		Err!(Request(NotFound(debug_warn!(
+			%mxc,
+			"Media stored after the legacy media freeze requires authentication."
+		))))
+	}
+
 	#[inline]
 	#[must_use]
 	pub fn get_media_file(&self, key: &[u8]) -> PathBuf { self.get_media_file_sha256(key) }
@@ -80,7 +80,12 @@ pub async fn get_url_preview(&self, url: &Url) -> Result<UrlPreviewData> {
 #[implement(Service)]
 async fn request_url_preview(&self, url: &Url) -> Result<UrlPreviewData> {
 	if let Ok(ip) = IPAddress::parse(url.host_str().expect("URL previously validated")) {
-		if !self.services.client.valid_cidr_range(&ip) {
+		if !self
+			.services
+			.client
+			.url_preview_ranges
+			.allowed(&ip)
+		{
 			return Err!(Request(Forbidden("Requesting from this address is forbidden")));
 		}
 	}
@@ -94,7 +99,12 @@ async fn request_url_preview(&self, url: &Url) -> Result<UrlPreviewData> {
 		debug!(?url, "URL preview response remote address: {:?}", remote_addr);
 
 		if let Ok(ip) = IPAddress::parse(remote_addr.ip().to_string()) {
-			if !self.services.client.valid_cidr_range(&ip) {
+			if !self
+				.services
+				.client
+				.url_preview_ranges
+				.allowed(&ip)
+			{
 				return Err!(Request(Forbidden("Requesting from this address is forbidden")));
 			}
 		}
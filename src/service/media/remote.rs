@@ -30,6 +30,7 @@ pub async fn fetch_remote_thumbnail(
 	dim: &Dim,
 ) -> Result<FileMeta> {
 	self.check_fetch_authorized(mxc)?;
+	self.check_fetch_quarantined(mxc).await?;
 
 	let result = self
 		.fetch_thumbnail_authenticated(mxc, user, server, timeout_ms, dim)
@@ -53,6 +54,7 @@ pub async fn fetch_remote_content(
 	timeout_ms: Duration,
 ) -> Result<FileMeta> {
 	self.check_fetch_authorized(mxc)?;
+	self.check_fetch_quarantined(mxc).await?;
 
 	let result = self
 		.fetch_content_authenticated(mxc, user, server, timeout_ms)
@@ -366,8 +368,9 @@ pub async fn fetch_remote_thumbnail_legacy(
 		media_id: &body.media_id,
 	};
 
-	self.check_legacy_freeze()?;
+	self.check_legacy_fetch()?;
 	self.check_fetch_authorized(&mxc)?;
+	self.check_fetch_quarantined(&mxc).await?;
 	let response = self
 		.services
 		.sending
@@ -406,8 +409,9 @@ pub async fn fetch_remote_content_legacy(
 	allow_redirect: bool,
 	timeout_ms: Duration,
 ) -> Result<media::get_content::v3::Response, Error> {
-	self.check_legacy_freeze()?;
+	self.check_legacy_fetch()?;
 	self.check_fetch_authorized(mxc)?;
+	self.check_fetch_quarantined(mxc).await?;
 	let response = self
 		.services
 		.sending
@@ -463,11 +467,20 @@ fn check_fetch_authorized(&self, mxc: &Mxc<'_>) -> Result<()> {
 }
 
 #[implement(super::Service)]
-fn check_legacy_freeze(&self) -> Result<()> {
-	self.services
-		.server
-		.config
-		.freeze_legacy_media
+async fn check_fetch_quarantined(&self, mxc: &Mxc<'_>) -> Result<()> {
+	if self.db.is_quarantined(mxc).await {
+		debug_warn!(%mxc, "Received request for quarantined media");
+		return Err!(Request(NotFound("Media not found.")));
+	}
+
+	Ok(())
+}
+
+/// Remote media not yet cached is newer than the freeze, so the legacy
+/// endpoints do not fetch it while frozen.
+#[implement(super::Service)]
+fn check_legacy_fetch(&self) -> Result<()> {
+	(!self.services.server.config.freeze_legacy_media)
 		.then_some(())
 		.ok_or(err!(Request(NotFound("Remote media is frozen."))))
 }
@@ -1,5 +1,9 @@
 #![cfg(test)]
 
+use ruma::media::Method;
+
+use super::Dim;
+
 #[tokio::test]
 #[cfg(disable)] //TODO: fixme
 async fn long_file_names_works() {
@@ -103,3 +107,65 @@ fn get_url_preview(&self, _url: &str) -> Option<UrlPreviewData> { todo!() }
 		r.to_str().unwrap().len()
 	);
 }
+
+fn sizes() -> Vec<Dim> {
+	let sizes = ["800x600 scale", "32x32 crop", "96x96 crop", "320x240", "640x480 scale"];
+
+	Dim::parse_sizes(&sizes.map(ToOwned::to_owned)).expect("valid sizes")
+}
+
+#[test]
+fn dim_from_str() {
+	assert_eq!("32x32 crop".parse::<Dim>().unwrap(), Dim::new(32, 32, Some(Method::Crop)));
+	assert_eq!(" 800x600 scale ".parse::<Dim>().unwrap(), Dim::new(800, 600, None));
+	assert_eq!("320x240".parse::<Dim>().unwrap(), Dim::new(320, 240, Some(Method::Scale)));
+}
+
+#[test]
+fn dim_from_str_invalid() {
+	assert!("".parse::<Dim>().is_err());
+	assert!("32".parse::<Dim>().is_err());
+	assert!("32x".parse::<Dim>().is_err());
+	assert!("x32".parse::<Dim>().is_err());
+	assert!("0x32 crop".parse::<Dim>().is_err());
+	assert!("32x0".parse::<Dim>().is_err());
+	assert!("-32x32".parse::<Dim>().is_err());
+	assert!("32x32 stretch".parse::<Dim>().is_err());
+}
+
+#[test]
+fn parse_sizes_ordered_by_area() {
+	let areas: Vec<_> = sizes()
+		.iter()
+		.map(|size| (size.width, size.height))
+		.collect();
+
+	assert_eq!(areas, [(32, 32), (96, 96), (320, 240), (640, 480), (800, 600)]);
+}
+
+#[test]
+fn parse_sizes_invalid() {
+	let sizes = ["32x32 crop".to_owned(), "96x96 squash".to_owned()];
+
+	assert!(Dim::parse_sizes(&sizes).is_err());
+	assert!(Dim::parse_sizes(&[]).unwrap().is_empty());
+}
+
+#[test]
+fn normalized_smallest_containing() {
+	let sizes = sizes();
+
+	assert_eq!(Dim::new(1, 1, None).normalized(&sizes), Dim::new(32, 32, Some(Method::Crop)));
+	assert_eq!(Dim::new(32, 32, None).normalized(&sizes), Dim::new(32, 32, Some(Method::Crop)));
+	assert_eq!(Dim::new(33, 20, None).normalized(&sizes), Dim::new(96, 96, Some(Method::Crop)));
+	assert_eq!(Dim::new(567, 567, None).normalized(&sizes), Dim::new(800, 600, None));
+	assert_eq!(Dim::new(700, 100, None).normalized(&sizes), Dim::new(800, 600, None));
+}
+
+#[test]
+fn normalized_original_when_larger_than_all() {
+	let sizes = sizes();
+
+	assert_eq!(Dim::new(801, 600, None).normalized(&sizes), Dim::default());
+	assert_eq!(Dim::new(100, 100, None).normalized(&[]), Dim::default());
+}
@@ -5,19 +5,19 @@
 //! inclusion of dependencies and nulls out results using the existing interface
 //! when not featured.
 
-use std::{cmp, num::Saturating as Sat};
+use std::{cmp, num::Saturating as Sat, str::FromStr};
 
-use ruma::{Mxc, UInt, UserId, http_headers::ContentDisposition, media::Method};
+use ruma::{Mxc, MxcUri, UInt, UserId, http_headers::ContentDisposition, media::Method};
 use tokio::{
 	fs,
 	io::{AsyncReadExt, AsyncWriteExt},
 };
-use tuwunel_core::{Result, checked, err, implement};
+use tuwunel_core::{Err, Error, Result, checked, debug, debug_warn, err, implement};
 
 use super::{FileMeta, data::Metadata};
 
 /// Dimension specification for a thumbnail.
-#[derive(Debug)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Dim {
 	pub width: u32,
 	pub height: u32,
@@ -52,8 +52,10 @@ pub async fn upload_thumbnail(
 	/// Here's an example on how it works:
 	///
 	/// - Client requests an image with width=567, height=567
-	/// - Server rounds that up to (800, 600), so it doesn't have to save too
-	///   many thumbnails
+	/// - Server rounds that up to (800, 600), the smallest configured size
+	///   containing it, so it doesn't have to save too many thumbnails
+	/// - If (800, 600) isn't cached but a larger size using the same method is,
+	///   that one is sent instead
 	/// - Server rounds that up again to (958, 600) to fix the aspect ratio
 	///   (only for width,height>96)
 	/// - Server creates the thumbnail and sends it to the user
@@ -62,25 +64,151 @@ pub async fn upload_thumbnail(
 	/// which crops the image afterwards.
 	#[tracing::instrument(skip(self), name = "thumbnail", level = "debug")]
 	pub async fn get_thumbnail(&self, mxc: &Mxc<'_>, dim: &Dim) -> Result<Option<FileMeta>> {
+		if self.db.is_quarantined(mxc).await {
+			return Ok(None);
+		}
+
 		// 0, 0 because that's the original file
-		let dim = dim.normalized();
-
-		match self.db.search_file_metadata(mxc, &dim).await {
-			| Ok(metadata) => self.get_thumbnail_saved(metadata).await,
-			| _ => match self
-				.db
-				.search_file_metadata(mxc, &Dim::default())
-				.await
-			{
-				| Ok(metadata) =>
-					self.get_thumbnail_generate(mxc, &dim, metadata)
-						.await,
-				| _ => Ok(None),
-			},
+		let dim = dim.normalized(&self.thumbnail_sizes);
+
+		if let Ok(metadata) = self.db.search_file_metadata(mxc, &dim).await {
+			return self.get_thumbnail_saved(metadata).await;
+		}
+
+		if let Some(metadata) = self.get_thumbnail_nearest(mxc, &dim).await {
+			return self.get_thumbnail_saved(metadata).await;
+		}
+
+		match self
+			.db
+			.search_file_metadata(mxc, &Dim::default())
+			.await
+		{
+			| Ok(metadata) =>
+				self.get_thumbnail_generate(mxc, &dim, metadata)
+					.await,
+			| _ => Ok(None),
+		}
+	}
+
+	/// Queues the generation of every configured thumbnail size of a file, so
+	/// that they are cached before first requested. Uploads are dropped from
+	/// pregeneration while the queue is full.
+	pub fn pregenerate_thumbnails(&self, mxc: &Mxc<'_>) {
+		if !cfg!(feature = "media_thumbnail")
+			|| !self.services.server.config.thumbnail_pregenerate
+		{
+			return;
+		}
+
+		let (sender, _) = &self.pregenerate;
+		if let Err(e) = sender.try_send(mxc.to_string().into()) {
+			debug_warn!(%mxc, "Thumbnails not pregenerated: {e}");
 		}
 	}
 }
 
+/// Generates the configured thumbnail sizes of an image not yet cached,
+/// decoding it once for all of them.
+#[cfg(feature = "media_thumbnail")]
+#[implement(super::Service)]
+pub(super) async fn pregenerate(&self, mxc: &MxcUri) {
+	let Ok(mxc) = Mxc::try_from(mxc.as_str()) else {
+		return;
+	};
+
+	let Ok(metadata) = self
+		.db
+		.search_file_metadata(&mxc, &Dim::default())
+		.await
+	else {
+		return;
+	};
+
+	let is_image = metadata
+		.content_type
+		.as_deref()
+		.is_some_and(|content_type| content_type.starts_with("image/"));
+
+	if !is_image {
+		return;
+	}
+
+	let mut missing = Vec::new();
+	for dim in &self.thumbnail_sizes {
+		if self
+			.db
+			.search_file_metadata(&mxc, dim)
+			.await
+			.is_err()
+		{
+			missing.push(dim);
+		}
+	}
+
+	if missing.is_empty() {
+		return;
+	}
+
+	let mut content = Vec::new();
+	let path = self.get_media_file(&metadata.key);
+	let read = async {
+		fs::File::open(path)
+			.await?
+			.read_to_end(&mut content)
+			.await
+	};
+
+	if let Err(e) = read.await {
+		debug_warn!(%mxc, "Failed to read media to pregenerate thumbnails: {e}");
+		return;
+	}
+
+	let Ok(image) = image::load_from_memory(&content) else {
+		return;
+	};
+
+	for dim in missing {
+		if dim.width > image.width() || dim.height > image.height() {
+			continue;
+		}
+
+		if let Err(e) = self
+			.save_thumbnail(&mxc, dim, &metadata, &image)
+			.await
+		{
+			debug_warn!(%mxc, ?dim, "Failed to pregenerate thumbnail: {e}");
+			return;
+		}
+	}
+
+	debug!(%mxc, "Pregenerated thumbnails");
+}
+
+#[cfg(not(feature = "media_thumbnail"))]
+#[implement(super::Service)]
+pub(super) async fn pregenerate(&self, _mxc: &MxcUri) {}
+
+/// The smallest cached thumbnail larger than the requested size using the
+/// same method, served instead of generating the requested size on demand.
+#[implement(super::Service)]
+async fn get_thumbnail_nearest(&self, mxc: &Mxc<'_>, dim: &Dim) -> Option<Metadata> {
+	let larger = self.thumbnail_sizes.iter().filter(|size| {
+		*size != dim
+			&& size.method == dim.method
+			&& size.width >= dim.width
+			&& size.height >= dim.height
+	});
+
+	for size in larger {
+		if let Ok(metadata) = self.db.search_file_metadata(mxc, size).await {
+			return Some(metadata);
+		}
+	}
+
+	None
+}
+
 /// Using saved thumbnail
 #[implement(super::Service)]
 #[tracing::instrument(name = "saved", level = "debug", skip(self, data))]
@@ -121,14 +249,31 @@ async fn get_thumbnail_generate(
 		return Ok(Some(into_filemeta(data, content)));
 	}
 
+	let thumbnail_bytes = self
+		.save_thumbnail(mxc, dim, &data, &image)
+		.await?;
+
+	Ok(Some(into_filemeta(data, thumbnail_bytes)))
+}
+
+/// Generates a thumbnail of the decoded image and saves it in the database so
+/// we don't have to generate it again next time.
+#[cfg(feature = "media_thumbnail")]
+#[implement(super::Service)]
+async fn save_thumbnail(
+	&self,
+	mxc: &Mxc<'_>,
+	dim: &Dim,
+	data: &Metadata,
+	image: &image::DynamicImage,
+) -> Result<Vec<u8>> {
 	let mut thumbnail_bytes = Vec::new();
-	let thumbnail = thumbnail_generate(&image, dim)?;
+	let thumbnail = thumbnail_generate(image, dim)?;
 	let mut cursor = std::io::Cursor::new(&mut thumbnail_bytes);
 	thumbnail
 		.write_to(&mut cursor, image::ImageFormat::Png)
 		.map_err(|error| err!(error!(?error, "Error writing PNG thumbnail.")))?;
 
-	// Save thumbnail in database so we don't have to generate it again next time
 	let thumbnail_key = self.db.create_file_metadata(
 		mxc,
 		None,
@@ -140,7 +285,7 @@ async fn get_thumbnail_generate(
 	let mut f = self.create_media_file(&thumbnail_key).await?;
 	f.write_all(&thumbnail_bytes).await?;
 
-	Ok(Some(into_filemeta(data, thumbnail_bytes)))
+	Ok(thumbnail_bytes)
 }
 
 #[cfg(not(feature = "media_thumbnail"))]
@@ -238,19 +383,28 @@ pub fn scaled(&self, image: &Self) -> Result<Self> {
 		})
 	}
 
-	/// Returns width, height of the thumbnail and whether it should be cropped.
-	/// Returns None when the server should send the original file.
-	/// Ignores the input Method.
+	/// Returns the smallest of the sizes, which are ordered by area, containing
+	/// the requested width and height. Returns the default (original file)
+	/// when none does. Ignores the input Method.
 	#[must_use]
-	pub fn normalized(&self) -> Self {
-		match (self.width, self.height) {
-			| (0..=32, 0..=32) => Self::new(32, 32, Some(Method::Crop)),
-			| (0..=96, 0..=96) => Self::new(96, 96, Some(Method::Crop)),
-			| (0..=320, 0..=240) => Self::new(320, 240, Some(Method::Scale)),
-			| (0..=640, 0..=480) => Self::new(640, 480, Some(Method::Scale)),
-			| (0..=800, 0..=600) => Self::new(800, 600, Some(Method::Scale)),
-			| _ => Self::default(),
-		}
+	pub fn normalized(&self, sizes: &[Self]) -> Self {
+		sizes
+			.iter()
+			.find(|size| self.width <= size.width && self.height <= size.height)
+			.cloned()
+			.unwrap_or_default()
+	}
+
+	/// Parses the configured `thumbnail_sizes`, ordered by area.
+	pub fn parse_sizes(sizes: &[String]) -> Result<Vec<Self>> {
+		let mut sizes = sizes
+			.iter()
+			.map(|size| size.parse())
+			.collect::<Result<Vec<Self>>>()
+			.map_err(|e| err!(Config("thumbnail_sizes", "{e}")))?;
+
+		sizes.sort_by_key(|size| u64::from(size.width).saturating_mul(size.height.into()));
+		Ok(sizes)
 	}
 
 	/// Returns true if the method is Crop.
@@ -259,6 +413,40 @@ pub fn normalized(&self) -> Self {
 	pub fn crop(&self) -> bool { self.method == Method::Crop }
 }
 
+impl FromStr for Dim {
+	type Err = Error;
+
+	/// Parses a size as `WIDTHxHEIGHT crop` or `WIDTHxHEIGHT scale`.
+	fn from_str(s: &str) -> Result<Self> {
+		let (size, method) = s
+			.trim()
+			.split_once(' ')
+			.unwrap_or((s.trim(), "scale"));
+		let method = match method.trim() {
+			| "crop" => Method::Crop,
+			| "scale" => Method::Scale,
+			| method => return Err!("Unknown thumbnail method {method:?} in {s:?}."),
+		};
+
+		let (width, height) = size
+			.split_once('x')
+			.ok_or_else(|| err!("Thumbnail size {s:?} is not WIDTHxHEIGHT."))?;
+
+		let width = width
+			.parse()
+			.map_err(|e| err!("Thumbnail width in {s:?} is invalid: {e}"))?;
+		let height = height
+			.parse()
+			.map_err(|e| err!("Thumbnail height in {s:?} is invalid: {e}"))?;
+
+		if width == 0 || height == 0 {
+			return Err!("Thumbnail size {s:?} must not be empty.");
+		}
+
+		Ok(Self::new(width, height, Some(method)))
+	}
+}
+
 impl Default for Dim {
 	#[inline]
 	fn default() -> Self {
@@ -5,19 +5,23 @@
 use ruma::{
 	OwnedUserId, RoomId, UserId,
 	events::{
-		GlobalAccountDataEventType, push_rules::PushRulesEvent, room::member::MembershipState,
+		GlobalAccountDataEventType, TimelineEventType, push_rules::PushRulesEvent,
+		room::member::MembershipState,
 	},
 	push::Ruleset,
 };
 use tuwunel_core::{
 	Err, Result, debug, debug_info, debug_warn, error, info,
+	matrix::{PduCount, PduEvent},
 	result::NotFound,
 	utils::{
 		IterStream, ReadyExt,
 		stream::{TryExpect, TryIgnore},
+		u64_from_u8,
 	},
 	warn,
 };
+use tuwunel_database::Batch;
 
 use crate::{Services, media};
 
@@ -65,6 +69,8 @@ async fn fresh(services: &Services) -> Result<()> {
 	db["global"].insert(b"retroactively_fix_bad_data_from_roomuserid_joined", []);
 	db["global"].insert(b"fix_referencedevents_missing_sep", []);
 	db["global"].insert(b"fix_readreceiptid_readreceipt_duplicates", []);
+	db["global"].insert(b"index_relation_depth", []);
+	db["global"].insert(b"index_annotations", []);
 
 	// Create the admin room and server user on first run
 	crate::admin::create_admin_room(services)
@@ -142,6 +148,22 @@ async fn migrate(services: &Services) -> Result<()> {
 		fix_readreceiptid_readreceipt_duplicates(services).await?;
 	}
 
+	if db["global"]
+		.get(b"index_relation_depth")
+		.await
+		.is_not_found()
+	{
+		index_relation_depth(services).await?;
+	}
+
+	if db["global"]
+		.get(b"index_annotations")
+		.await
+		.is_not_found()
+	{
+		index_annotations(services).await?;
+	}
+
 	if services.globals.db.database_version().await < 17 {
 		services.globals.db.bump_database_version(17);
 		info!("Migration: Bumped database version to 17");
@@ -582,3 +604,77 @@ async fn fix_readreceiptid_readreceipt_duplicates(services: &Services) -> Result
 	db["global"].insert(b"fix_readreceiptid_readreceipt_duplicates", []);
 	db.db.sort()
 }
+
+/// Relations used to be recorded only from the target to the direct relation.
+/// Record them from both ends along with their depth, and the relations each
+/// event has through others, so recursive relations include older events.
+async fn index_relation_depth(services: &Services) -> Result {
+	warn!("Indexing the depth of relations between events...");
+
+	let db = &services.db;
+	let cork = db.cork_and_sync();
+
+	// Relations are added in the order of the events relating, so the
+	// relations of each target are complete when those relating to it follow.
+	let mut relations: Vec<(u64, u64)> = db["tofrom_relation"]
+		.raw_keys()
+		.expect_ok()
+		.map(|key| (u64_from_u8(&key[8..16]), u64_from_u8(&key[0..8])))
+		.collect()
+		.await;
+
+	relations.sort_unstable();
+	for &(from, to) in &relations {
+		let mut batch = Batch::default();
+		services
+			.rooms
+			.pdu_metadata
+			.add_relation(&mut batch, PduCount::Normal(from), PduCount::Normal(to))
+			.await;
+
+		batch.commit();
+	}
+
+	drop(cork);
+	info!(total = relations.len(), "Indexed the depth of relations.");
+
+	db["global"].insert(b"index_relation_depth", []);
+	db.db.sort()
+}
+
+/// Annotations used not to be indexed. Index the reactions already in the
+/// timeline, so they are counted and sending them again is refused. Redacted
+/// reactions have no relation left and are skipped.
+async fn index_annotations(services: &Services) -> Result {
+	warn!("Indexing annotations...");
+
+	let db = &services.db;
+	let cork = db.cork_and_sync();
+
+	let mut batch = Batch::default();
+	let mut total: usize = 0;
+	db["pduid_pdu"]
+		.raw_stream()
+		.ignore_err()
+		.ready_filter_map(|(_, pdu)| serde_json::from_slice::<PduEvent>(pdu).ok())
+		.ready_filter(|pdu| pdu.kind == TimelineEventType::Reaction)
+		.ready_for_each(|pdu| {
+			services
+				.rooms
+				.pdu_metadata
+				.add_annotation(&mut batch, &pdu);
+
+			total = total.saturating_add(1);
+			if batch.len() >= 1024 {
+				std::mem::take(&mut batch).commit();
+			}
+		})
+		.await;
+
+	batch.commit();
+	drop(cork);
+	info!(total, "Indexed annotations.");
+
+	db["global"].insert(b"index_annotations", []);
+	db.db.sort()
+}
@@ -22,10 +22,12 @@
 pub mod rooms;
 pub mod sending;
 pub mod server_keys;
+pub mod sso;
 pub mod sync;
 pub mod transaction_ids;
 pub mod uiaa;
 pub mod users;
+pub mod warmup;
 
 pub(crate) use service::{Args, Dep, Service};
 
@@ -8,7 +8,7 @@
 };
 use tuwunel_database::{Deserialized, Json, Map};
 
-use super::Presence;
+use super::{Presence, REFRESH_TIMEOUT};
 use crate::{Dep, globals, users};
 
 pub(crate) struct Data {
@@ -58,7 +58,7 @@ pub(super) async fn set_presence(
 		currently_active: Option<bool>,
 		last_active_ago: Option<UInt>,
 		status_msg: Option<String>,
-	) -> Result<()> {
+	) -> Result<bool> {
 		let last_presence = self.get_presence(user_id).await;
 		let state_changed = match last_presence {
 			| Err(_) => true,
@@ -81,6 +81,13 @@ pub(super) async fn set_presence(
 			},
 		};
 
+		let currently_active_changed = match last_presence {
+			| Err(_) => true,
+			| Ok((_, ref presence)) =>
+				presence.content.currently_active.unwrap_or(false)
+					!= currently_active.unwrap_or(false),
+		};
+
 		let now = utils::millis_since_unix_epoch();
 		let last_last_active_ts = match last_presence {
 			| Err(_) => 0,
@@ -98,13 +105,20 @@ pub(super) async fn set_presence(
 			| Some(last_active_ago) => now.saturating_sub(last_active_ago.into()),
 		};
 
+		let unchanged = !status_msg_changed && !state_changed && !currently_active_changed;
+
 		// TODO: tighten for state flicker?
-		if !status_msg_changed && !state_changed && last_active_ts < last_last_active_ts {
+		if unchanged && last_active_ts < last_last_active_ts {
 			debug_warn!(
 				"presence spam {user_id:?} last_active_ts:{last_active_ts:?} < \
 				 {last_last_active_ts:?}",
 			);
-			return Ok(());
+			return Ok(false);
+		}
+
+		// Nothing but the last activity changed, and only recently.
+		if unchanged && last_active_ts.saturating_sub(last_last_active_ts) < REFRESH_TIMEOUT {
+			return Ok(false);
 		}
 
 		let status_msg = if status_msg.as_ref().is_some_and(String::is_empty) {
@@ -132,7 +146,7 @@ pub(super) async fn set_presence(
 			self.presenceid_presence.remove(&key);
 		}
 
-		Ok(())
+		Ok(true)
 	}
 
 	pub(super) async fn remove_presence(&self, user_id: &UserId) {
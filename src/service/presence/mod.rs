@@ -1,20 +1,30 @@
 mod data;
 mod presence;
+#[cfg(test)]
+mod tests;
+mod timers;
 
-use std::{sync::Arc, time::Duration};
+use std::{collections::HashSet, sync::Arc, time::Duration};
 
 use async_trait::async_trait;
-use futures::{Stream, StreamExt, TryFutureExt, stream::FuturesUnordered};
+use futures::{Stream, StreamExt, TryFutureExt};
 use loole::{Receiver, Sender};
 use ruma::{OwnedUserId, UInt, UserId, events::presence::PresenceEvent, presence::PresenceState};
-use tokio::time::sleep;
+use tokio::time::{Instant, sleep_until};
 use tuwunel_core::{
-	Error, Result, Server, checked, debug, debug_warn, error, result::LogErr, trace,
+	Error, Result, Server, checked, debug, debug_warn, error,
+	result::LogErr,
+	trace,
+	utils::{IterStream, ReadyExt},
 };
 use tuwunel_database::Database;
 
-use self::{data::Data, presence::Presence};
-use crate::{Dep, globals, users};
+use self::{data::Data, presence::Presence, timers::Timers};
+use crate::{Dep, globals, rooms, sending, users};
+
+/// Presence which only refreshes the last activity is written at most this
+/// often, in milliseconds.
+const REFRESH_TIMEOUT: u64 = 60 * 1000;
 
 pub struct Service {
 	timer_channel: (Sender<TimerType>, Receiver<TimerType>),
@@ -29,6 +39,8 @@ struct Services {
 	server: Arc<Server>,
 	db: Arc<Database>,
 	globals: Dep<globals::Service>,
+	sending: Dep<sending::Service>,
+	state_cache: Dep<rooms::state_cache::Service>,
 	users: Dep<users::Service>,
 }
 
@@ -50,6 +62,8 @@ fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 				server: args.server.clone(),
 				db: args.db.clone(),
 				globals: args.depend::<globals::Service>("globals"),
+				sending: args.depend::<sending::Service>("sending"),
+				state_cache: args.depend::<rooms::state_cache::Service>("rooms::state_cache"),
 				users: args.depend::<users::Service>("users"),
 			},
 		}))
@@ -58,17 +72,27 @@ fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 	async fn worker(self: Arc<Self>) -> Result<()> {
 		let receiver = self.timer_channel.1.clone();
 
-		let mut presence_timers = FuturesUnordered::new();
+		let mut timers = Timers::default();
 		while !receiver.is_closed() {
+			let next_deadline = timers.next_deadline();
 			tokio::select! {
-				Some(user_id) = presence_timers.next() => {
-					self.process_presence_timer(&user_id).await.log_err().ok();
+				() = sleep_until(next_deadline.unwrap_or_else(Instant::now)), if next_deadline.is_some() => {
+					let mut timed_out = Vec::new();
+					for user_id in timers.expire(Instant::now()) {
+						if self.process_presence_timer(&user_id).await.log_err().unwrap_or(false) {
+							timed_out.push(user_id);
+						}
+					}
+
+					self.flush_presence(&timed_out).await.log_err().ok();
 				},
 				event = receiver.recv_async() => match event {
 					Err(_) => break,
 					Ok((user_id, timeout)) => {
-						debug!("Adding timer {}: {user_id} timeout:{timeout:?}", presence_timers.len());
-						presence_timers.push(presence_timer(user_id, timeout));
+						debug!("Adding timer {}: {user_id} timeout:{timeout:?}", timers.len());
+						if let Some(deadline) = Instant::now().checked_add(timeout) {
+							timers.insert(user_id, deadline);
+						}
 					},
 				},
 			}
@@ -100,8 +124,6 @@ pub async fn get_presence(&self, user_id: &UserId) -> Result<PresenceEvent> {
 	/// Pings the presence of the given user in the given room, setting the
 	/// specified state.
 	pub async fn ping_presence(&self, user_id: &UserId, new_state: &PresenceState) -> Result<()> {
-		const REFRESH_TIMEOUT: u64 = 60 * 1000;
-
 		let last_presence = self.db.get_presence(user_id).await;
 		let state_changed = match last_presence {
 			| Err(_) => true,
@@ -133,6 +155,12 @@ pub async fn ping_presence(&self, user_id: &UserId, new_state: &PresenceState) -
 	}
 
 	/// Adds a presence event which will be saved until a new event replaces it.
+	/// Bots have no presence.
+	///
+	/// Online and unavailable users are timed out to unavailable and offline
+	/// once `presence_idle_timeout_s` and `presence_offline_timeout_s` pass
+	/// since their last activity. Presence which changes nothing is not
+	/// written.
 	pub async fn set_presence(
 		&self,
 		user_id: &UserId,
@@ -141,34 +169,39 @@ pub async fn set_presence(
 		last_active_ago: Option<UInt>,
 		status_msg: Option<String>,
 	) -> Result<()> {
+		if self.services.users.is_bot(user_id).await {
+			return Ok(());
+		}
+
 		let presence_state = match state.as_str() {
 			| "" => &PresenceState::Offline, // default an empty string to 'offline'
 			| &_ => state,
 		};
 
-		self.db
+		let written = self
+			.db
 			.set_presence(user_id, presence_state, currently_active, last_active_ago, status_msg)
 			.await?;
 
-		if (self.timeout_remote_users || self.services.globals.user_is_local(user_id))
-			&& user_id != self.services.globals.server_user
-		{
-			let timeout = match presence_state {
-				| PresenceState::Online =>
-					self.services
-						.server
-						.config
-						.presence_idle_timeout_s,
-				| _ =>
-					self.services
-						.server
-						.config
-						.presence_offline_timeout_s,
-			};
+		if !written {
+			return Ok(());
+		}
+
+		let timeout = match presence_state {
+			| PresenceState::Online => Some(self.idle_timeout),
+			| PresenceState::Unavailable => Some(self.offline_timeout),
+			| _ => None,
+		};
 
+		if let Some(timeout) = timeout.filter(|_| {
+			(self.timeout_remote_users || self.services.globals.user_is_local(user_id))
+				&& user_id != self.services.globals.server_user
+		}) {
+			let last_active_ago = last_active_ago.map_or(0, u64::from);
+			let timeout = Duration::from_millis(timeout.saturating_sub(last_active_ago));
 			self.timer_channel
 				.0
-				.send((user_id.to_owned(), Duration::from_secs(timeout)))
+				.send((user_id.to_owned(), timeout))
 				.map_err(|e| {
 					error!("Failed to add presence timer: {}", e);
 					Error::bad_database("Failed to add presence timer")
@@ -257,7 +290,9 @@ pub async fn from_json_bytes_to_event(
 		Ok(event)
 	}
 
-	async fn process_presence_timer(&self, user_id: &OwnedUserId) -> Result<()> {
+	/// Moves the user's presence on when it has timed out. Returns whether it
+	/// changed.
+	async fn process_presence_timer(&self, user_id: &OwnedUserId) -> Result<bool> {
 		let mut presence_state = PresenceState::Offline;
 		let mut last_active_ago = None;
 		let mut status_msg = None;
@@ -283,17 +318,60 @@ async fn process_presence_timer(&self, user_id: &OwnedUserId) -> Result<()> {
 			 state = {new_state:?}"
 		);
 
-		if let Some(new_state) = new_state {
-			self.set_presence(user_id, &new_state, Some(false), last_active_ago, status_msg)
-				.await?;
-		}
+		let Some(new_state) = new_state else {
+			return Ok(false);
+		};
 
-		Ok(())
+		self.set_presence(user_id, &new_state, Some(false), last_active_ago, status_msg)
+			.await?;
+
+		Ok(true)
 	}
-}
 
-async fn presence_timer(user_id: OwnedUserId, timeout: Duration) -> OwnedUserId {
-	sleep(timeout).await;
+	/// Send the presence of local users which timed out together to the
+	/// servers sharing a room with them, since no other activity would prompt
+	/// a transaction. Each room is visited once however many of the users are
+	/// in it.
+	async fn flush_presence(&self, user_ids: &[OwnedUserId]) -> Result {
+		if !self
+			.services
+			.server
+			.config
+			.allow_outgoing_presence
+		{
+			return Ok(());
+		}
+
+		let rooms: HashSet<_> = user_ids
+			.iter()
+			.filter(|user_id| self.services.globals.user_is_local(user_id))
+			.stream()
+			.flat_map(|user_id| self.services.state_cache.rooms_joined(user_id))
+			.map(ToOwned::to_owned)
+			.collect()
+			.await;
+
+		if rooms.is_empty() {
+			return Ok(());
+		}
 
-	user_id
+		let servers: HashSet<_> = rooms
+			.iter()
+			.stream()
+			.flat_map(|room_id| self.services.state_cache.room_servers(room_id))
+			.ready_filter(|server_name| !self.services.globals.server_is_ours(server_name))
+			.map(ToOwned::to_owned)
+			.collect()
+			.await;
+
+		self.services
+			.sending
+			.flush_servers(
+				servers
+					.iter()
+					.map(|server_name| &**server_name)
+					.stream(),
+			)
+			.await
+	}
 }
@@ -0,0 +1,89 @@
+use std::time::Duration;
+
+use ruma::{OwnedUserId, owned_user_id};
+use tokio::time::Instant;
+
+use super::timers::Timers;
+
+fn after(now: Instant, secs: u64) -> Instant {
+	now.checked_add(Duration::from_secs(secs))
+		.expect("valid deadline")
+}
+
+fn sorted(mut users: Vec<OwnedUserId>) -> Vec<OwnedUserId> {
+	users.sort();
+	users
+}
+
+#[test]
+fn expire_in_deadline_order() {
+	let now = Instant::now();
+	let (alice, bob, carol) = (
+		owned_user_id!("@alice:example.com"),
+		owned_user_id!("@bob:example.com"),
+		owned_user_id!("@carol:example.com"),
+	);
+
+	let mut timers = Timers::default();
+	timers.insert(alice.clone(), after(now, 30));
+	timers.insert(bob.clone(), after(now, 10));
+	timers.insert(carol.clone(), after(now, 20));
+
+	assert_eq!(timers.len(), 3);
+	assert_eq!(timers.next_deadline(), Some(after(now, 10)));
+	assert!(timers.expire(now).is_empty());
+
+	assert_eq!(sorted(timers.expire(after(now, 20))), [bob, carol]);
+	assert_eq!(timers.len(), 1);
+	assert_eq!(timers.next_deadline(), Some(after(now, 30)));
+
+	assert_eq!(timers.expire(after(now, 30)), [alice]);
+	assert_eq!(timers.len(), 0);
+	assert_eq!(timers.next_deadline(), None);
+}
+
+#[test]
+fn rearm_later_replaces_deadline() {
+	let now = Instant::now();
+	let alice = owned_user_id!("@alice:example.com");
+
+	let mut timers = Timers::default();
+	timers.insert(alice.clone(), after(now, 10));
+	timers.insert(alice.clone(), after(now, 60));
+
+	assert_eq!(timers.len(), 1);
+	assert_eq!(timers.next_deadline(), Some(after(now, 60)));
+	assert!(timers.expire(after(now, 10)).is_empty());
+	assert_eq!(timers.expire(after(now, 60)), [alice]);
+}
+
+#[test]
+fn rearm_earlier_replaces_deadline() {
+	let now = Instant::now();
+	let alice = owned_user_id!("@alice:example.com");
+
+	let mut timers = Timers::default();
+	timers.insert(alice.clone(), after(now, 60));
+	timers.insert(alice.clone(), after(now, 5));
+
+	assert_eq!(timers.next_deadline(), Some(after(now, 5)));
+	assert_eq!(timers.expire(after(now, 5)), [alice]);
+	assert!(timers.expire(after(now, 60)).is_empty());
+}
+
+#[test]
+fn rearm_one_of_shared_deadline() {
+	let now = Instant::now();
+	let (alice, bob) = (owned_user_id!("@alice:example.com"), owned_user_id!("@bob:example.com"));
+
+	let mut timers = Timers::default();
+	timers.insert(alice.clone(), after(now, 10));
+	timers.insert(bob.clone(), after(now, 10));
+
+	// Moving one user off the shared deadline keeps the other on it.
+	timers.insert(bob.clone(), after(now, 20));
+
+	assert_eq!(timers.next_deadline(), Some(after(now, 10)));
+	assert_eq!(timers.expire(after(now, 10)), [alice]);
+	assert_eq!(timers.expire(after(now, 20)), [bob]);
+}
@@ -0,0 +1,55 @@
+use std::collections::{BTreeMap, HashMap, HashSet, btree_map::Entry};
+
+use ruma::OwnedUserId;
+use tokio::time::Instant;
+
+/// Presence timers ordered by deadline. A user has at most one timer; arming
+/// it again replaces the previous deadline.
+#[derive(Default)]
+pub(super) struct Timers {
+	deadlines: BTreeMap<Instant, HashSet<OwnedUserId>>,
+	users: HashMap<OwnedUserId, Instant>,
+}
+
+impl Timers {
+	pub(super) fn insert(&mut self, user_id: OwnedUserId, deadline: Instant) {
+		if let Some(previous) = self.users.insert(user_id.clone(), deadline) {
+			if let Entry::Occupied(mut entry) = self.deadlines.entry(previous) {
+				entry.get_mut().remove(&user_id);
+				if entry.get().is_empty() {
+					entry.remove();
+				}
+			}
+		}
+
+		self.deadlines
+			.entry(deadline)
+			.or_default()
+			.insert(user_id);
+	}
+
+	/// Remove and return the users whose deadline has passed.
+	pub(super) fn expire(&mut self, now: Instant) -> Vec<OwnedUserId> {
+		let mut expired = Vec::new();
+		while let Some(entry) = self.deadlines.first_entry() {
+			if *entry.key() > now {
+				break;
+			}
+
+			for user_id in entry.remove() {
+				self.users.remove(&user_id);
+				expired.push(user_id);
+			}
+		}
+
+		expired
+	}
+
+	#[inline]
+	pub(super) fn next_deadline(&self) -> Option<Instant> {
+		self.deadlines.keys().next().copied()
+	}
+
+	#[inline]
+	pub(super) fn len(&self) -> usize { self.users.len() }
+}
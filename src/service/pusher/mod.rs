@@ -1,5 +1,8 @@
-use std::{fmt::Debug, mem, sync::Arc};
+mod notifications;
 
+use std::{fmt::Debug, mem, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
 use bytes::BytesMut;
 use futures::{Stream, StreamExt};
 use ipaddress::IPAddress;
@@ -23,8 +26,9 @@
 	serde::Raw,
 	uint,
 };
+use tokio::time::sleep;
 use tuwunel_core::{
-	Err, Result, debug_warn, err,
+	Err, Result, Server, debug, debug_warn, err,
 	matrix::Event,
 	trace,
 	utils::{stream::TryIgnore, string_from_bytes},
@@ -32,14 +36,19 @@
 };
 use tuwunel_database::{Deserialized, Ignore, Interfix, Json, Map};
 
+pub use self::notifications::Notified;
 use crate::{Dep, client, globals, rooms, sending, users};
 
+/// How often notifications older than `notification_max_age` are deleted.
+const NOTIFICATION_PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60 * 6);
+
 pub struct Service {
 	db: Data,
 	services: Services,
 }
 
 struct Services {
+	server: Arc<Server>,
 	globals: Dep<globals::Service>,
 	client: Dep<client::Service>,
 	state_accessor: Dep<rooms::state_accessor::Service>,
@@ -51,16 +60,20 @@ struct Services {
 struct Data {
 	senderkey_pusher: Arc<Map>,
 	pushkey_deviceid: Arc<Map>,
+	useridcount_notification: Arc<Map>,
 }
 
+#[async_trait]
 impl crate::Service for Service {
 	fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 		Ok(Arc::new(Self {
 			db: Data {
 				senderkey_pusher: args.db["senderkey_pusher"].clone(),
 				pushkey_deviceid: args.db["pushkey_deviceid"].clone(),
+				useridcount_notification: args.db["useridcount_notification"].clone(),
 			},
 			services: Services {
+				server: args.server.clone(),
 				globals: args.depend::<globals::Service>("globals"),
 				client: args.depend::<client::Service>("client"),
 				state_accessor: args
@@ -72,6 +85,28 @@ fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 		}))
 	}
 
+	async fn worker(self: Arc<Self>) -> Result {
+		let max_age = self.services.server.config.notification_max_age;
+		if max_age == 0 || self.services.globals.is_read_only() {
+			return Ok(());
+		}
+
+		let max_age = Duration::from_secs(max_age);
+		while self.services.server.running() {
+			let pruned = self.prune_notifications(max_age).await;
+			if pruned > 0 {
+				debug!("Deleted {pruned} expired notifications.");
+			}
+
+			tokio::select! {
+				() = sleep(NOTIFICATION_PRUNE_INTERVAL) => {},
+				() = self.services.server.until_shutdown() => break,
+			}
+		}
+
+		Ok(())
+	}
+
 	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
 }
 
@@ -120,7 +155,7 @@ pub async fn set_pusher(
 					if let Ok(ip) =
 						IPAddress::parse(url.host_str().expect("URL previously validated"))
 					{
-						if !self.services.client.valid_cidr_range(&ip) {
+						if !self.services.client.pusher_ranges.allowed(&ip) {
 							return Err!(Request(InvalidParam(
 								warn!(%url, "HTTP pusher URL is a forbidden remote address")
 							)));
@@ -220,7 +255,7 @@ pub async fn send_request<T>(&self, dest: &str, request: T) -> Result<T::Incomin
 		if let Some(url_host) = reqwest_request.url().host_str() {
 			trace!("Checking request URL for IP");
 			if let Ok(ip) = IPAddress::parse(url_host) {
-				if !self.services.client.valid_cidr_range(&ip) {
+				if !self.services.client.pusher_ranges.allowed(&ip) {
 					return Err!(BadServerResponse("Not allowed to send requests to this IP"));
 				}
 			}
@@ -240,7 +275,7 @@ pub async fn send_request<T>(&self, dest: &str, request: T) -> Result<T::Incomin
 				trace!("Checking response destination's IP");
 				if let Some(remote_addr) = response.remote_addr() {
 					if let Ok(ip) = IPAddress::parse(remote_addr.ip().to_string()) {
-						if !self.services.client.valid_cidr_range(&ip) {
+						if !self.services.client.pusher_ranges.allowed(&ip) {
 							return Err!(BadServerResponse(
 								"Not allowed to send requests to this IP"
 							));
@@ -417,7 +452,7 @@ async fn send_notice<E>(
 				if let Ok(ip) =
 					IPAddress::parse(url.host_str().expect("URL previously validated"))
 				{
-					if !self.services.client.valid_cidr_range(&ip) {
+					if !self.services.client.pusher_ranges.allowed(&ip) {
 						return Err!(Request(InvalidParam(
 							warn!(%url, "HTTP pusher URL is a forbidden remote address")
 						)));
@@ -0,0 +1,110 @@
+//! History of events which notified each local user, for the
+//! `GET /notifications` endpoint.
+
+use std::time::Duration;
+
+use futures::{Stream, StreamExt};
+use ruma::{
+	EventId, MilliSecondsSinceUnixEpoch, OwnedEventId, OwnedRoomId, OwnedUserId, RoomId, UserId,
+	push::Action,
+};
+use serde::{Deserialize, Serialize};
+use tuwunel_core::{
+	implement,
+	utils::{self, ReadyExt, stream::TryIgnore},
+};
+use tuwunel_database::{Interfix, Json};
+
+/// An event which notified a user, with the push actions it matched.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Notified {
+	pub room_id: OwnedRoomId,
+	pub event_id: OwnedEventId,
+	pub actions: Vec<Action>,
+	pub highlight: bool,
+	pub ts: MilliSecondsSinceUnixEpoch,
+}
+
+/// Record that an event at timeline position `count` notified the user.
+#[implement(super::Service)]
+pub fn add_notification(
+	&self,
+	user_id: &UserId,
+	count: u64,
+	room_id: &RoomId,
+	event_id: &EventId,
+	actions: &[Action],
+) {
+	let highlight = actions.iter().any(|action| action.is_highlight());
+
+	let notified = Notified {
+		room_id: room_id.to_owned(),
+		event_id: event_id.to_owned(),
+		actions: actions.to_vec(),
+		highlight,
+		ts: MilliSecondsSinceUnixEpoch::now(),
+	};
+
+	self.db
+		.useridcount_notification
+		.put((user_id, count), Json(notified));
+}
+
+/// Notifications of a user before timeline position `until`, newest first.
+#[implement(super::Service)]
+pub fn notifications_until<'a>(
+	&'a self,
+	user_id: &'a UserId,
+	until: u64,
+) -> impl Stream<Item = (u64, Notified)> + Send + 'a {
+	let start = (user_id, until.saturating_sub(1));
+	self.db
+		.useridcount_notification
+		.rev_stream_from(&start)
+		.ignore_err()
+		.ready_take_while(move |((user, _), _): &((&UserId, u64), Notified)| *user == user_id)
+		.map(|((_, count), notified)| (count, notified))
+}
+
+/// Delete the notifications older than `max_age`. Each user's notifications
+/// are ordered by their timeline position, so only the expired ones at the
+/// front are read. Returns the number deleted.
+#[implement(super::Service)]
+pub async fn prune_notifications(&self, max_age: Duration) -> usize {
+	let max_age = u64::try_from(max_age.as_millis()).unwrap_or(u64::MAX);
+	let cutoff = utils::millis_since_unix_epoch().saturating_sub(max_age);
+
+	let users: Vec<OwnedUserId> = self
+		.services
+		.users
+		.stream()
+		.map(ToOwned::to_owned)
+		.collect()
+		.await;
+
+	let mut pruned: usize = 0;
+	for user_id in &users {
+		let prefix = (user_id, Interfix);
+		let expired: Vec<u64> = self
+			.db
+			.useridcount_notification
+			.stream_prefix(&prefix)
+			.ignore_err()
+			.ready_take_while(|(_, notified): &((&UserId, u64), Notified)| {
+				u64::from(notified.ts.get()) < cutoff
+			})
+			.map(|((_, count), _)| count)
+			.collect()
+			.await;
+
+		for count in &expired {
+			self.db
+				.useridcount_notification
+				.del((user_id, count));
+		}
+
+		pruned = pruned.saturating_add(expired.len());
+	}
+
+	pruned
+}
@@ -110,7 +110,13 @@ pub async fn resolve_actual_dest(
 		Ok(CachedDest {
 			dest: actual_dest,
 			host: host.uri_string(),
-			expire: CachedDest::default_expire(),
+			expire: CachedDest::default_expire(
+				self.services
+					.server
+					.config
+					.resolver_destination_ttl,
+			),
+			pinned: false,
 		})
 	}
 
@@ -305,7 +311,9 @@ async fn query_and_cache_override(
 					.set_override(untername, &CachedOverride {
 						ips: override_ip.into_iter().take(MAX_IPS).collect(),
 						port,
-						expire: CachedOverride::default_expire(),
+						expire: CachedOverride::default_expire(
+							self.services.server.config.resolver_override_ttl,
+						),
 						overriding: (hostname != untername)
 							.then_some(hostname.into())
 							.inspect(|_| debug_info!("{untername:?} overridden by {hostname:?}")),
@@ -404,7 +412,7 @@ fn validate_dest_ip_literal(&self, dest: &ServerName) -> Result<()> {
 	}
 
 	pub(crate) fn validate_ip(&self, ip: &IPAddress) -> Result<()> {
-		if !self.services.client.valid_cidr_range(ip) {
+		if !self.services.client.federation_ranges.allowed(ip) {
 			return Err!(BadServerResponse("Not allowed to send requests to this IP"));
 		}
 
@@ -1,4 +1,8 @@
-use std::{net::IpAddr, sync::Arc, time::SystemTime};
+use std::{
+	net::IpAddr,
+	sync::Arc,
+	time::{Duration, SystemTime},
+};
 
 use futures::{Stream, StreamExt, future::join};
 use ruma::ServerName;
@@ -7,7 +11,7 @@
 	Result,
 	arrayvec::ArrayVec,
 	at, err, implement,
-	utils::{math::Expected, rand, stream::TryIgnore},
+	utils::{math::Expected, rand, stream::TryIgnore, time::timepoint_from_now},
 };
 use tuwunel_database::{Cbor, Deserialized, Map};
 
@@ -23,6 +27,11 @@ pub struct CachedDest {
 	pub dest: FedDest,
 	pub host: String,
 	pub expire: SystemTime,
+
+	/// Set by an administrator; the entry is used regardless of `expire` until
+	/// it is removed.
+	#[serde(default)]
+	pub pinned: bool,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -125,11 +134,13 @@ pub fn overrides(&self) -> impl Stream<Item = (&ServerName, CachedOverride)> + S
 impl CachedDest {
 	#[inline]
 	#[must_use]
-	pub fn valid(&self) -> bool { self.expire > SystemTime::now() }
+	pub fn valid(&self) -> bool { self.pinned || self.expire > SystemTime::now() }
 
+	/// Expiration for a new entry; `ttl` in seconds overrides the randomized
+	/// default.
 	#[must_use]
-	pub(crate) fn default_expire() -> SystemTime {
-		rand::time_from_now_secs(60 * 60 * 18..60 * 60 * 36)
+	pub(crate) fn default_expire(ttl: Option<u64>) -> SystemTime {
+		expire_after(ttl).unwrap_or_else(|| rand::time_from_now_secs(60 * 60 * 18..60 * 60 * 36))
 	}
 
 	#[inline]
@@ -139,6 +150,7 @@ pub fn size(&self) -> usize {
 			.size()
 			.expected_add(self.host.len())
 			.expected_add(size_of_val(&self.expire))
+			.expected_add(size_of_val(&self.pinned))
 	}
 }
 
@@ -147,12 +159,19 @@ impl CachedOverride {
 	#[must_use]
 	pub fn valid(&self) -> bool { self.expire > SystemTime::now() }
 
+	/// Expiration for a new entry; `ttl` in seconds overrides the randomized
+	/// default.
 	#[must_use]
-	pub(crate) fn default_expire() -> SystemTime {
-		rand::time_from_now_secs(60 * 60 * 6..60 * 60 * 12)
+	pub(crate) fn default_expire(ttl: Option<u64>) -> SystemTime {
+		expire_after(ttl).unwrap_or_else(|| rand::time_from_now_secs(60 * 60 * 6..60 * 60 * 12))
 	}
 
 	#[inline]
 	#[must_use]
 	pub fn size(&self) -> usize { size_of_val(self) }
 }
+
+fn expire_after(ttl: Option<u64>) -> Option<SystemTime> {
+	ttl.map(Duration::from_secs)
+		.and_then(|ttl| timepoint_from_now(ttl).ok())
+}
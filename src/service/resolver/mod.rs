@@ -6,12 +6,16 @@
 mod tests;
 mod well_known;
 
-use std::sync::Arc;
+use std::{sync::Arc, time::SystemTime};
 
 use async_trait::async_trait;
+use ruma::ServerName;
 use tuwunel_core::{Result, Server, arrayvec::ArrayString, utils::MutexMap};
 
-use self::{cache::Cache, dns::Resolver};
+use self::{
+	cache::{Cache, CachedDest},
+	dns::Resolver,
+};
 use crate::{Dep, client};
 
 pub struct Service {
@@ -56,3 +60,21 @@ async fn clear_cache(&self) {
 
 	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
 }
+
+impl Service {
+	/// Pin the destination of `server_name` to `dest` (an IP address or
+	/// hostname with optional port), bypassing resolution until the entry is
+	/// removed. The `Host` header defaults to `dest`.
+	pub fn pin_destination(&self, server_name: &ServerName, dest: &str, host: Option<&str>) {
+		let dest = fed::get_ip_with_port(dest).unwrap_or_else(|| fed::add_port_to_hostname(dest));
+		let host = host.map_or_else(|| dest.uri_string(), ToOwned::to_owned);
+
+		self.cache
+			.set_destination(server_name, &CachedDest {
+				dest,
+				host,
+				expire: SystemTime::now(),
+				pinned: true,
+			});
+	}
+}
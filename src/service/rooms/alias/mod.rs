@@ -1,17 +1,26 @@
 mod remote;
 
-use std::sync::Arc;
+use std::{
+	fmt::Write,
+	sync::{Arc, Mutex},
+	time::{Duration, Instant},
+};
 
+use async_trait::async_trait;
 use futures::{Stream, StreamExt, TryFutureExt};
+use lru_cache::LruCache;
 use ruma::{
-	OwnedRoomId, OwnedServerName, OwnedUserId, RoomAliasId, RoomId, RoomOrAliasId, UserId,
+	OwnedRoomAliasId, OwnedRoomId, OwnedServerName, OwnedUserId, RoomAliasId, RoomId,
+	RoomOrAliasId, UserId,
 	events::{
 		StateEventType,
 		room::power_levels::{RoomPowerLevels, RoomPowerLevelsEventContent},
 	},
 };
 use tuwunel_core::{
-	Err, Result, Server, err,
+	Err, Result, Server,
+	config::CacheKind,
+	debug, err,
 	matrix::Event,
 	utils::{ReadyExt, stream::TryIgnore},
 };
@@ -22,8 +31,17 @@
 pub struct Service {
 	db: Data,
 	services: Services,
+	resolve_cache: Mutex<ResolveCache>,
+}
+
+/// Remote alias resolution; `None` records a failed resolution.
+struct Resolution {
+	resolved: Option<(OwnedRoomId, Vec<OwnedServerName>)>,
+	expires: Instant,
 }
 
+type ResolveCache = LruCache<OwnedRoomAliasId, Resolution>;
+
 struct Data {
 	alias_userid: Arc<Map>,
 	alias_roomid: Arc<Map>,
@@ -39,8 +57,14 @@ struct Services {
 	state_accessor: Dep<rooms::state_accessor::Service>,
 }
 
+#[async_trait]
 impl crate::Service for Service {
 	fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
+		let cache_size = args
+			.server
+			.config
+			.cache_capacity(CacheKind::Alias)?;
+
 		Ok(Arc::new(Self {
 			db: Data {
 				alias_userid: args.db["alias_userid"].clone(),
@@ -56,9 +80,23 @@ fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 				state_accessor: args
 					.depend::<rooms::state_accessor::Service>("rooms::state_accessor"),
 			},
+			resolve_cache: Mutex::new(LruCache::new(cache_size)),
 		}))
 	}
 
+	async fn memory_usage(&self, out: &mut (dyn Write + Send)) -> Result {
+		let (len, cap) = {
+			let cache = self.resolve_cache.lock()?;
+			(cache.len(), cache.capacity())
+		};
+
+		writeln!(out, "alias_resolve_cache: {len} / {cap}")?;
+
+		Ok(())
+	}
+
+	async fn clear_cache(&self) { self.resolve_cache.lock().expect("locked").clear(); }
+
 	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
 }
 
@@ -76,6 +114,8 @@ pub fn set_alias(
 			return Err!(Request(Forbidden("Only the server user can set this alias")));
 		}
 
+		self.invalidate(alias);
+
 		// Comes first as we don't want a stuck alias
 		self.db
 			.alias_userid
@@ -101,6 +141,8 @@ pub async fn remove_alias(&self, alias: &RoomAliasId, user_id: &UserId) -> Resul
 			return Err!(Request(Forbidden("User is not permitted to remove this alias.")));
 		}
 
+		self.invalidate(alias);
+
 		let alias = alias.alias();
 		let Ok(room_id) = self.db.alias_roomid.get(&alias).await else {
 			return Err!(Request(NotFound("Alias does not exist or is invalid.")));
@@ -156,9 +198,19 @@ pub async fn resolve_alias(
 		};
 
 		if !server_is_ours && !servers_contains_ours() {
-			return self
+			let has_hints = servers
+				.as_ref()
+				.is_some_and(|servers| !servers.is_empty());
+			if let Some(resolved) = self.cached(room_alias, has_hints) {
+				return resolved;
+			}
+
+			let resolved = self
 				.remote_resolve(room_alias, servers.unwrap_or_default())
 				.await;
+
+			self.cache(room_alias, resolved.as_ref().ok());
+			return resolved;
 		}
 
 		let room_id = match self.resolve_local_alias(room_alias).await {
@@ -172,6 +224,62 @@ pub async fn resolve_alias(
 		)
 	}
 
+	/// Drop any cached resolution of the alias.
+	pub fn invalidate(&self, alias: &RoomAliasId) {
+		self.resolve_cache
+			.lock()
+			.expect("locked")
+			.remove(alias);
+	}
+
+	/// Unexpired cached resolution of a remote alias. Failures are not
+	/// reported when the caller supplied servers which may succeed where the
+	/// alias server did not.
+	fn cached(
+		&self,
+		alias: &RoomAliasId,
+		has_hints: bool,
+	) -> Option<Result<(OwnedRoomId, Vec<OwnedServerName>)>> {
+		let mut cache = self.resolve_cache.lock().expect("locked");
+		let entry = cache.get_mut(alias)?;
+		if entry.expires <= Instant::now() {
+			cache.remove(alias);
+			return None;
+		}
+
+		match &entry.resolved {
+			| Some(resolved) => Some(Ok(resolved.clone())),
+			| None if has_hints => None,
+			| None => {
+				debug!(?alias, "Cached failure resolving alias");
+				Some(Err!(Request(NotFound(
+					"No servers could assist in resolving the room alias"
+				))))
+			},
+		}
+	}
+
+	fn cache(&self, alias: &RoomAliasId, resolved: Option<&(OwnedRoomId, Vec<OwnedServerName>)>) {
+		let config = &self.services.server.config;
+		let ttl = if resolved.is_some() {
+			config.alias_cache_ttl
+		} else {
+			config.alias_cache_negative_ttl
+		};
+
+		if ttl == 0 {
+			return;
+		}
+
+		self.resolve_cache
+			.lock()
+			.expect("locked")
+			.insert(alias.to_owned(), Resolution {
+				resolved: resolved.cloned(),
+				expires: Instant::now() + Duration::from_secs(ttl),
+			});
+	}
+
 	#[tracing::instrument(skip(self), level = "debug")]
 	pub async fn resolve_local_alias(&self, alias: &RoomAliasId) -> Result<OwnedRoomId> {
 		self.db
@@ -4,7 +4,7 @@
 };
 
 use lru_cache::LruCache;
-use tuwunel_core::{Err, Result, err, utils, utils::math::usize_from_f64};
+use tuwunel_core::{Err, Result, config::CacheKind, err, utils};
 use tuwunel_database::Map;
 
 use crate::rooms::short::ShortEventId;
@@ -17,9 +17,10 @@ pub(super) struct Data {
 impl Data {
 	pub(super) fn new(args: &crate::Args<'_>) -> Self {
 		let db = &args.db;
-		let config = &args.server.config;
-		let cache_size = f64::from(config.auth_chain_cache_capacity);
-		let cache_size = usize_from_f64(cache_size * config.cache_capacity_modifier)
+		let cache_size = args
+			.server
+			.config
+			.cache_capacity(CacheKind::AuthChain)
 			.expect("valid cache size");
 		Self {
 			shorteventid_authchain: db["shorteventid_authchain"].clone(),
@@ -2,17 +2,18 @@
 
 use std::{
 	collections::{BTreeSet, HashSet, VecDeque},
-	fmt::Debug,
+	fmt::{Debug, Write},
 	sync::Arc,
 	time::Instant,
 };
 
+use async_trait::async_trait;
 use futures::{FutureExt, Stream, StreamExt, TryFutureExt, TryStreamExt};
 use ruma::{EventId, OwnedEventId, RoomId};
 use tuwunel_core::{
 	Err, Result, at, debug, debug_error, implement, trace,
 	utils::{
-		IterStream,
+		IterStream, bytes,
 		stream::{ReadyExt, TryBroadbandExt},
 	},
 	validated, warn,
@@ -33,6 +34,7 @@ struct Services {
 
 type Bucket<'a> = BTreeSet<(u64, &'a EventId)>;
 
+#[async_trait]
 impl crate::Service for Service {
 	fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 		Ok(Arc::new(Self {
@@ -44,6 +46,35 @@ fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 		}))
 	}
 
+	async fn memory_usage(&self, out: &mut (dyn Write + Send)) -> Result {
+		let (len, cap, bytes) = {
+			let cache = self.db.auth_chain_cache.lock()?;
+			let bytes = cache
+				.iter()
+				.map(|(key, val)| {
+					size_of_val(key.as_slice())
+						.saturating_add(size_of_val(&**val))
+						.saturating_add(size_of::<(Vec<u64>, Arc<[ShortEventId]>)>())
+				})
+				.fold(0_usize, usize::saturating_add);
+
+			(cache.len(), cache.capacity(), bytes)
+		};
+
+		let bytes = bytes::pretty(bytes);
+		writeln!(out, "auth_chain_cache: {len}/{cap} ({bytes})")?;
+
+		Ok(())
+	}
+
+	async fn clear_cache(&self) {
+		self.db
+			.auth_chain_cache
+			.lock()
+			.expect("locked")
+			.clear();
+	}
+
 	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
 }
 
@@ -260,12 +291,3 @@ pub fn get_cache_usage(&self) -> (usize, usize) {
 
 	(cache.len(), cache.capacity())
 }
-
-#[implement(Service)]
-pub fn clear_cache(&self) {
-	self.db
-		.auth_chain_cache
-		.lock()
-		.expect("locked")
-		.clear();
-}
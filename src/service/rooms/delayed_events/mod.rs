@@ -0,0 +1,305 @@
+//! Events sent after a delay unless cancelled or restarted first (MSC4140);
+//! see `max_event_delay`.
+
+#[cfg(test)]
+mod tests;
+
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use futures::{Stream, StreamExt};
+use ruma::{OwnedRoomId, OwnedUserId, UserId, events::TimelineEventType};
+use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue as RawJsonValue;
+use tokio::{sync::Notify, time::sleep};
+use tuwunel_core::{
+	Err, Result, Server, debug, debug_warn, err, implement,
+	matrix::pdu::PduBuilder,
+	utils::{
+		self,
+		stream::{ReadyExt, TryIgnore},
+	},
+};
+use tuwunel_database::{Deserialized, Interfix, Json, Map};
+
+use crate::{Dep, rooms};
+
+pub struct Service {
+	services: Services,
+	db: Data,
+	scheduled: Notify,
+}
+
+struct Services {
+	server: Arc<Server>,
+	state: Dep<rooms::state::Service>,
+	timeline: Dep<rooms::timeline::Service>,
+}
+
+struct Data {
+	sendat_userdelayid: Arc<Map>,
+	userdelayid_delayedevent: Arc<Map>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct DelayedEvent {
+	pub room_id: OwnedRoomId,
+
+	#[serde(rename = "type")]
+	pub event_type: TimelineEventType,
+
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub state_key: Option<String>,
+
+	pub content: Box<RawJsonValue>,
+
+	/// Delay in milliseconds.
+	pub delay: u64,
+
+	/// When the delay was last (re)started, in milliseconds since the epoch.
+	pub running_since: u64,
+}
+
+impl DelayedEvent {
+	/// When the event is due, in milliseconds since the epoch.
+	#[must_use]
+	pub fn send_at(&self) -> u64 { self.running_since.saturating_add(self.delay) }
+}
+
+/// Length of generated delay IDs.
+const DELAY_ID_LENGTH: usize = 24;
+
+/// Longest sleep between checks for due events when none are scheduled.
+const IDLE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+#[async_trait]
+impl crate::Service for Service {
+	fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
+		Ok(Arc::new(Self {
+			services: Services {
+				server: args.server.clone(),
+				state: args.depend::<rooms::state::Service>("rooms::state"),
+				timeline: args.depend::<rooms::timeline::Service>("rooms::timeline"),
+			},
+			db: Data {
+				sendat_userdelayid: args.db["sendat_userdelayid"].clone(),
+				userdelayid_delayedevent: args.db["userdelayid_delayedevent"].clone(),
+			},
+			scheduled: Notify::new(),
+		}))
+	}
+
+	async fn worker(self: Arc<Self>) -> Result {
+		while self.services.server.running() {
+			let now = utils::millis_since_unix_epoch();
+			let wait = next_wait(now, self.send_due(now).await);
+
+			tokio::select! {
+				() = sleep(wait) => {},
+				() = self.scheduled.notified() => {},
+				() = self.services.server.until_shutdown() => break,
+			}
+		}
+
+		Ok(())
+	}
+
+	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
+}
+
+/// Schedule an event to be sent by the user after `delay`. Returns the delay
+/// ID by which it can be cancelled, restarted or sent early.
+#[implement(Service)]
+pub async fn schedule(
+	&self,
+	user_id: &UserId,
+	room_id: OwnedRoomId,
+	event_type: TimelineEventType,
+	state_key: Option<String>,
+	content: Box<RawJsonValue>,
+	delay: Duration,
+) -> Result<String> {
+	let config = &self.services.server.config;
+	check_delay(delay, Duration::from_secs(config.max_event_delay))?;
+
+	let pending = self.delayed_events(user_id).count().await;
+	if pending >= config.max_delayed_events_per_user {
+		return Err!(Request(Forbidden("Too many delayed events are pending.")));
+	}
+
+	let delay_id = utils::random_string(DELAY_ID_LENGTH);
+	let event = DelayedEvent {
+		room_id,
+		event_type,
+		state_key,
+		content,
+		delay: delay.as_millis().try_into()?,
+		running_since: utils::millis_since_unix_epoch(),
+	};
+
+	self.put(user_id, &delay_id, &event);
+
+	Ok(delay_id)
+}
+
+/// Start the delay of a pending event over from now.
+#[implement(Service)]
+pub async fn restart(&self, user_id: &UserId, delay_id: &str) -> Result {
+	let mut event = self.get(user_id, delay_id).await?;
+	self.unschedule(user_id, delay_id, &event);
+
+	event.running_since = utils::millis_since_unix_epoch();
+	self.put(user_id, delay_id, &event);
+
+	Ok(())
+}
+
+/// Drop a pending event without sending it.
+#[implement(Service)]
+pub async fn cancel(&self, user_id: &UserId, delay_id: &str) -> Result {
+	let event = self.get(user_id, delay_id).await?;
+	self.unschedule(user_id, delay_id, &event);
+	self.db
+		.userdelayid_delayedevent
+		.del((user_id, delay_id));
+
+	Ok(())
+}
+
+/// Send a pending event now instead of after its delay.
+#[implement(Service)]
+pub async fn send_now(&self, user_id: &UserId, delay_id: &str) -> Result {
+	let event = self.get(user_id, delay_id).await?;
+	self.cancel(user_id, delay_id).await?;
+	self.send(user_id, event).await
+}
+
+/// The user's pending events with their delay IDs.
+#[implement(Service)]
+pub fn delayed_events<'a>(
+	&'a self,
+	user_id: &'a UserId,
+) -> impl Stream<Item = (String, DelayedEvent)> + Send + 'a {
+	let prefix = (user_id, Interfix);
+	self.db
+		.userdelayid_delayedevent
+		.stream_prefix(&prefix)
+		.ignore_err()
+		.map(|((_, delay_id), event): ((&UserId, &str), DelayedEvent)| {
+			(delay_id.to_owned(), event)
+		})
+}
+
+#[implement(Service)]
+async fn get(&self, user_id: &UserId, delay_id: &str) -> Result<DelayedEvent> {
+	self.db
+		.userdelayid_delayedevent
+		.qry(&(user_id, delay_id))
+		.await
+		.deserialized()
+		.map_err(|_| err!(Request(NotFound("No delayed event with this ID."))))
+}
+
+#[implement(Service)]
+fn put(&self, user_id: &UserId, delay_id: &str, event: &DelayedEvent) {
+	let send_at = event.send_at();
+	self.db
+		.userdelayid_delayedevent
+		.put((user_id, delay_id), Json(event));
+	self.db
+		.sendat_userdelayid
+		.put_raw((send_at, user_id, delay_id), []);
+
+	self.scheduled.notify_one();
+}
+
+#[implement(Service)]
+fn unschedule(&self, user_id: &UserId, delay_id: &str, event: &DelayedEvent) {
+	let send_at = event.send_at();
+	self.db
+		.sendat_userdelayid
+		.del((send_at, user_id, delay_id));
+}
+
+/// Send the events due at `now` or earlier. Returns when the next event is
+/// due, if any.
+#[implement(Service)]
+async fn send_due(&self, now: u64) -> Option<u64> {
+	let scheduled: Vec<(u64, OwnedUserId, String)> = self
+		.db
+		.sendat_userdelayid
+		.keys()
+		.ignore_err()
+		.ready_take_while(|&(send_at, ..): &(u64, &UserId, &str)| send_at <= now)
+		.map(|(send_at, user_id, delay_id)| (send_at, user_id.to_owned(), delay_id.to_owned()))
+		.collect()
+		.await;
+
+	for (send_at, user_id, delay_id) in scheduled {
+		self.db
+			.sendat_userdelayid
+			.del((send_at, &user_id, &delay_id));
+
+		if let Err(e) = self.send_now(&user_id, &delay_id).await {
+			debug_warn!(%user_id, %delay_id, "Failed to send delayed event: {e}");
+		}
+	}
+
+	self.db
+		.sendat_userdelayid
+		.keys()
+		.ignore_err()
+		.map(|(send_at, ..): (u64, &UserId, &str)| send_at)
+		.next()
+		.await
+}
+
+#[implement(Service)]
+async fn send(&self, user_id: &UserId, event: DelayedEvent) -> Result {
+	let room_id = event.room_id;
+	let state_lock = self.services.state.mutex.lock(&room_id).await;
+	let event_id = self
+		.services
+		.timeline
+		.build_and_append_pdu(
+			PduBuilder {
+				event_type: event.event_type,
+				content: event.content,
+				state_key: event.state_key.as_deref().map(Into::into),
+				..Default::default()
+			},
+			user_id,
+			&room_id,
+			&state_lock,
+		)
+		.await?;
+
+	debug!(%user_id, %room_id, %event_id, "Sent delayed event.");
+
+	Ok(())
+}
+
+/// Fails unless delayed events are enabled and the delay is within the
+/// maximum.
+fn check_delay(delay: Duration, max_delay: Duration) -> Result {
+	if max_delay.is_zero() {
+		return Err!(Request(Forbidden("Delayed events are not enabled on this server.")));
+	}
+
+	if delay > max_delay {
+		return Err!(Request(InvalidParam(
+			"Delay exceeds the maximum of {} milliseconds.",
+			max_delay.as_millis()
+		)));
+	}
+
+	Ok(())
+}
+
+/// How long the worker sleeps before the next event is due, or until it checks
+/// again when none are scheduled.
+fn next_wait(now: u64, next: Option<u64>) -> Duration {
+	next.map_or(IDLE_INTERVAL, |send_at| {
+		Duration::from_millis(send_at.saturating_sub(now)).min(IDLE_INTERVAL)
+	})
+}
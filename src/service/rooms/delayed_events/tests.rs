@@ -0,0 +1,44 @@
+use std::time::Duration;
+
+use ruma::owned_room_id;
+use serde_json::value::RawValue as RawJsonValue;
+
+use super::{DelayedEvent, IDLE_INTERVAL, check_delay, next_wait};
+
+#[test]
+fn delay_within_maximum() {
+	let max_delay = Duration::from_secs(60);
+
+	assert!(check_delay(Duration::ZERO, max_delay).is_ok());
+	assert!(check_delay(max_delay, max_delay).is_ok());
+	assert!(check_delay(max_delay + Duration::from_millis(1), max_delay).is_err());
+	assert!(check_delay(Duration::from_secs(1), Duration::ZERO).is_err());
+}
+
+#[test]
+fn send_at_after_restart() {
+	let mut event = DelayedEvent {
+		room_id: owned_room_id!("!room:example.com"),
+		event_type: "m.room.message".into(),
+		state_key: None,
+		content: RawJsonValue::from_string("{}".to_owned()).unwrap(),
+		delay: 5_000,
+		running_since: 1_000,
+	};
+
+	assert_eq!(event.send_at(), 6_000);
+
+	event.running_since = 4_000;
+	assert_eq!(event.send_at(), 9_000);
+
+	event.running_since = u64::MAX;
+	assert_eq!(event.send_at(), u64::MAX);
+}
+
+#[test]
+fn wait_until_next_due() {
+	assert_eq!(next_wait(1_000, None), IDLE_INTERVAL);
+	assert_eq!(next_wait(1_000, Some(1_500)), Duration::from_millis(500));
+	assert_eq!(next_wait(1_000, Some(500)), Duration::ZERO);
+	assert_eq!(next_wait(0, Some(u64::MAX)), IDLE_INTERVAL);
+}
@@ -0,0 +1,87 @@
+//! Presentation of published rooms in our directory set by administrators.
+
+use futures::{Stream, StreamExt};
+use ruma::{OwnedRoomId, RoomId};
+use serde::{Deserialize, Serialize};
+use tuwunel_core::{
+	implement,
+	utils::stream::{ReadyExt, TryIgnore},
+};
+use tuwunel_database::{Deserialized, Json};
+
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Curation {
+	/// Position among the rooms listed ahead of all others, lowest first.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub pin: Option<u64>,
+
+	/// Shown in the directory instead of the room's topic.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub description: Option<String>,
+}
+
+#[implement(super::Service)]
+pub async fn curation(&self, room_id: &RoomId) -> Curation {
+	self.db
+		.publicroomid_curation
+		.get(room_id)
+		.await
+		.deserialized()
+		.unwrap_or_default()
+}
+
+#[implement(super::Service)]
+pub fn set_curation(&self, room_id: &RoomId, curation: &Curation) {
+	if *curation == Curation::default() {
+		self.db.publicroomid_curation.remove(room_id);
+	} else {
+		self.db
+			.publicroomid_curation
+			.raw_put(room_id, Json(curation));
+	}
+}
+
+/// Publish the room and list it ahead of unpinned rooms, at `position` or
+/// after the other pinned rooms.
+#[implement(super::Service)]
+pub async fn pin(&self, room_id: &RoomId, position: Option<u64>) {
+	let position = match position {
+		| Some(position) => position,
+		| None =>
+			self.pinned()
+				.ready_fold(0_u64, |next, (_, pin)| next.max(pin.saturating_add(1)))
+				.await,
+	};
+
+	let mut curation = self.curation(room_id).await;
+	curation.pin = Some(position);
+	self.set_curation(room_id, &curation);
+	self.set_public(room_id);
+}
+
+#[implement(super::Service)]
+pub async fn unpin(&self, room_id: &RoomId) {
+	let mut curation = self.curation(room_id).await;
+	curation.pin = None;
+	self.set_curation(room_id, &curation);
+}
+
+#[implement(super::Service)]
+pub async fn set_description(&self, room_id: &RoomId, description: Option<String>) {
+	let mut curation = self.curation(room_id).await;
+	curation.description = description;
+	self.set_curation(room_id, &curation);
+}
+
+/// Pinned rooms with their positions, unordered.
+#[implement(super::Service)]
+pub fn pinned(&self) -> impl Stream<Item = (OwnedRoomId, u64)> + Send + '_ {
+	self.db
+		.publicroomid_curation
+		.stream()
+		.ignore_err()
+		.ready_filter_map(|(room_id, curation): (&RoomId, Curation)| {
+			curation.pin.map(|pin| (room_id.to_owned(), pin))
+		})
+		.boxed()
+}
@@ -1,3 +1,5 @@
+mod curation;
+
 use std::sync::Arc;
 
 use futures::Stream;
@@ -5,12 +7,15 @@
 use tuwunel_core::{Result, implement, utils::stream::TryIgnore};
 use tuwunel_database::Map;
 
+pub use self::curation::Curation;
+
 pub struct Service {
 	db: Data,
 }
 
 struct Data {
 	publicroomids: Arc<Map>,
+	publicroomid_curation: Arc<Map>,
 }
 
 impl crate::Service for Service {
@@ -18,6 +23,7 @@ fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 		Ok(Arc::new(Self {
 			db: Data {
 				publicroomids: args.db["publicroomids"].clone(),
+				publicroomid_curation: args.db["publicroomid_curation"].clone(),
 			},
 		}))
 	}
@@ -29,7 +35,10 @@ fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
 pub fn set_public(&self, room_id: &RoomId) { self.db.publicroomids.insert(room_id, []); }
 
 #[implement(Service)]
-pub fn set_not_public(&self, room_id: &RoomId) { self.db.publicroomids.remove(room_id); }
+pub fn set_not_public(&self, room_id: &RoomId) {
+	self.db.publicroomids.remove(room_id);
+	self.db.publicroomid_curation.remove(room_id);
+}
 
 #[implement(Service)]
 pub fn public_rooms(&self) -> impl Stream<Item = &RoomId> + Send {
@@ -1,46 +1,147 @@
+use std::sync::Arc;
+
 use ruma::{
-	RoomId, ServerName,
+	OwnedServerName, RoomId, ServerName,
 	events::{StateEventType, room::server_acl::RoomServerAclEventContent},
 };
 use tuwunel_core::{Err, Result, debug, implement, trace, warn};
 
-/// Returns Ok if the acl allows the server
+use crate::rooms::short::{ShortEventId, ShortStateHash};
+
+/// Room server ACL which passed validation, cached per room by `acl_check`.
+pub(super) struct Acl(RoomServerAclEventContent);
+
+/// Cached ACL of a room: the state it was last checked at, the ACL event in
+/// that state, and the ACL, if any applies.
+pub(super) type CachedAcl = (ShortStateHash, Option<ShortEventId>, Option<Arc<Acl>>);
+
+/// Returns Ok if the acl allows the server. Remote servers are always denied
+/// by rooms created with `m.federate` set to false.
 #[implement(super::Service)]
 #[tracing::instrument(skip_all, level = "debug")]
 pub async fn acl_check(&self, server_name: &ServerName, room_id: &RoomId) -> Result {
-	let Ok(acl_event_content) = self
-		.services
-		.state_accessor
-		.room_state_get_content(room_id, &StateEventType::RoomServerAcl, "")
-		.await
-		.map(|c: RoomServerAclEventContent| c)
-		.inspect(|acl| trace!(%room_id, "ACL content found: {acl:?}"))
-		.inspect_err(|e| trace!(%room_id, "No ACL content found: {e:?}"))
-	else {
-		return Ok(());
-	};
-
-	if acl_event_content.allow.is_empty() {
-		warn!(%room_id, "Ignoring broken ACL event (allow key is empty)");
-		return Ok(());
+	if !self.services.globals.server_is_ours(server_name)
+		&& !self
+			.services
+			.state_accessor
+			.is_federated(room_id)
+			.await
+	{
+		debug!("Server {server_name} was denied by m.federate in {room_id}");
+		return Err!(Request(Forbidden("Room is not federated")));
 	}
 
-	if acl_event_content
-		.deny
-		.contains(&String::from("*"))
-		&& acl_event_content
-			.allow
-			.contains(&String::from("*"))
-	{
-		warn!(%room_id, "Ignoring broken ACL event (allow key and deny key both contain wildcard \"*\"");
+	let Some(acl) = self.room_acl(room_id).await else {
 		return Ok(());
-	}
+	};
 
-	if acl_event_content.is_allowed(server_name) {
+	if acl.is_allowed(server_name) {
 		trace!("server {server_name} is allowed by ACL");
 		Ok(())
 	} else {
 		debug!("Server {server_name} was denied by room ACL in {room_id}");
+		self.count_acl_reject(server_name);
 		Err!(Request(Forbidden("Server was denied by room ACL")))
 	}
 }
+
+/// Servers denied by room ACLs, with the number of PDUs and EDUs rejected
+/// from each since startup.
+#[implement(super::Service)]
+pub fn acl_rejects(&self) -> Vec<(OwnedServerName, u64)> {
+	let mut rejects: Vec<_> = self
+		.acl_rejects
+		.lock()
+		.expect("locked")
+		.iter()
+		.map(|(server_name, count)| (server_name.clone(), *count))
+		.collect();
+
+	rejects.sort_by(|a, b| b.1.cmp(&a.1));
+	rejects
+}
+
+#[implement(super::Service)]
+fn count_acl_reject(&self, server_name: &ServerName) {
+	let mut rejects = self.acl_rejects.lock().expect("locked");
+	let count = rejects.entry(server_name.to_owned()).or_default();
+
+	*count = count.saturating_add(1);
+}
+
+/// The room's ACL at its current state. The cached ACL is reused until the
+/// ACL event in the room's state changes.
+#[implement(super::Service)]
+async fn room_acl(&self, room_id: &RoomId) -> Option<Arc<Acl>> {
+	let shortstatehash = self
+		.services
+		.state
+		.get_room_shortstatehash(room_id)
+		.await
+		.ok()?;
+
+	let cached = self
+		.acl_cache
+		.read()
+		.expect("locked for reading")
+		.get(room_id)
+		.cloned();
+
+	if let Some((cached_hash, _, acl)) = &cached {
+		if *cached_hash == shortstatehash {
+			return acl.clone();
+		}
+	}
+
+	let event_type = StateEventType::RoomServerAcl;
+	let shorteventid = self
+		.services
+		.state_accessor
+		.state_get_shortid(shortstatehash, &event_type, "")
+		.await
+		.ok();
+
+	let acl = match cached {
+		| Some((_, cached_id, acl)) if cached_id == shorteventid => acl,
+		| _ => self
+			.services
+			.state_accessor
+			.state_get_content(shortstatehash, &event_type, "")
+			.await
+			.inspect(|acl| trace!(%room_id, "ACL content found: {acl:?}"))
+			.inspect_err(|e| trace!(%room_id, "No ACL content found: {e:?}"))
+			.ok()
+			.and_then(|content: RoomServerAclEventContent| Acl::new(room_id, content))
+			.map(Arc::new),
+	};
+
+	self.acl_cache
+		.write()
+		.expect("locked for writing")
+		.insert(room_id.to_owned(), (shortstatehash, shorteventid, acl.clone()));
+
+	acl
+}
+
+impl Acl {
+	/// None when the ACL is broken and so ignored.
+	pub(super) fn new(room_id: &RoomId, content: RoomServerAclEventContent) -> Option<Self> {
+		if content.allow.is_empty() {
+			warn!(%room_id, "Ignoring broken ACL event (allow key is empty)");
+			return None;
+		}
+
+		let wildcard = String::from("*");
+		if content.deny.contains(&wildcard) && content.allow.contains(&wildcard) {
+			warn!(%room_id, "Ignoring broken ACL event (allow key and deny key both contain wildcard \"*\"");
+			return None;
+		}
+
+		Some(Self(content))
+	}
+
+	#[inline]
+	pub(super) fn is_allowed(&self, server_name: &ServerName) -> bool {
+		self.0.is_allowed(server_name)
+	}
+}
@@ -216,6 +216,7 @@ pub(super) async fn fetch_and_handle_outliers<'a, Pdu, Events>(
 				room_id,
 				value.clone(),
 				true,
+				None,
 			))
 			.await
 			{
@@ -7,7 +7,10 @@
 	FutureExt, TryFutureExt, TryStreamExt,
 	future::{OptionFuture, try_join5},
 };
-use ruma::{CanonicalJsonValue, EventId, RoomId, ServerName, UserId, events::StateEventType};
+use ruma::{
+	CanonicalJsonValue, EventId, RoomId, ServerName, UserId, events::StateEventType,
+	signatures::Verified,
+};
 use tuwunel_core::{
 	Err, Result, debug, debug::INFO_SPAN_LEVEL, defer, err, implement, matrix::Event,
 	utils::stream::IterStream, warn,
@@ -43,19 +46,35 @@
 /// 14. Check if the event passes auth based on the "current state" of the room,
 ///     if not soft fail it
 #[implement(super::Service)]
+pub async fn handle_incoming_pdu<'a>(
+	&self,
+	origin: &'a ServerName,
+	room_id: &'a RoomId,
+	event_id: &'a EventId,
+	value: BTreeMap<String, CanonicalJsonValue>,
+	is_timeline_event: bool,
+) -> Result<Option<RawPduId>> {
+	self.handle_incoming_verified_pdu(origin, room_id, event_id, value, is_timeline_event, None)
+		.await
+}
+
+/// As `handle_incoming_pdu`, with the result of checking signatures and the
+/// content hash when already done by `verify_incoming_pdu`.
+#[implement(super::Service)]
 #[tracing::instrument(
 	name = "pdu",
 	level = INFO_SPAN_LEVEL,
 	skip_all,
 	fields(%room_id, %event_id),
 )]
-pub async fn handle_incoming_pdu<'a>(
+pub async fn handle_incoming_verified_pdu<'a>(
 	&self,
 	origin: &'a ServerName,
 	room_id: &'a RoomId,
 	event_id: &'a EventId,
 	value: BTreeMap<String, CanonicalJsonValue>,
 	is_timeline_event: bool,
+	verified: Option<Result<Verified>>,
 ) -> Result<Option<RawPduId>> {
 	// 1. Skip the PDU if we already have it as a timeline event
 	if let Ok(pdu_id) = self.services.timeline.get_pdu_id(event_id).await {
@@ -111,7 +130,7 @@ pub async fn handle_incoming_pdu<'a>(
 	}
 
 	let (incoming_pdu, val) = self
-		.handle_outlier_pdu(origin, create_event, event_id, room_id, value, false)
+		.handle_outlier_pdu(origin, create_event, event_id, room_id, value, false, verified)
 		.await?;
 
 	// 8. if not timeline event: stop
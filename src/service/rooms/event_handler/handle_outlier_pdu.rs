@@ -3,6 +3,7 @@
 use futures::future::ready;
 use ruma::{
 	CanonicalJsonObject, CanonicalJsonValue, EventId, RoomId, ServerName, events::StateEventType,
+	signatures::Verified,
 };
 use tuwunel_core::{
 	Err, Result, debug, debug_info, err, implement,
@@ -22,6 +23,7 @@ pub(super) async fn handle_outlier_pdu<'a, Pdu>(
 	room_id: &'a RoomId,
 	mut value: CanonicalJsonObject,
 	auth_events_known: bool,
+	verified: Option<Result<Verified>>,
 ) -> Result<(PduEvent, BTreeMap<String, CanonicalJsonValue>)>
 where
 	Pdu: Event + Send + Sync,
@@ -33,15 +35,20 @@ pub(super) async fn handle_outlier_pdu<'a, Pdu>(
 
 	// 2. Check signatures, otherwise drop
 	// 3. check content hash, redact if doesn't match
+	// (unless already checked by the caller)
 	let room_version_id = get_room_version_id(create_event)?;
-	let mut incoming_pdu = match self
-		.services
-		.server_keys
-		.verify_event(&value, Some(&room_version_id))
-		.await
-	{
-		| Ok(ruma::signatures::Verified::All) => value,
-		| Ok(ruma::signatures::Verified::Signatures) => {
+	let verified = match verified {
+		| Some(verified) => verified,
+		| None =>
+			self.services
+				.server_keys
+				.verify_event(&value, Some(&room_version_id))
+				.await,
+	};
+
+	let mut incoming_pdu = match verified {
+		| Ok(Verified::All) => value,
+		| Ok(Verified::Signatures) => {
 			// Redact
 			debug_info!("Calculated hash does not match (redaction): {event_id}");
 			let Ok(obj) = ruma::canonical_json::redact(value, &room_version_id, None) else {
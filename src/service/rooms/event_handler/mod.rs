@@ -8,18 +8,20 @@
 mod parse_incoming_pdu;
 mod resolve_state;
 mod state_at_incoming;
+#[cfg(test)]
+mod tests;
 mod upgrade_outlier_pdu;
 
 use std::{
 	collections::HashMap,
 	fmt::Write,
-	sync::{Arc, RwLock as StdRwLock},
+	sync::{Arc, Mutex as StdMutex, RwLock as StdRwLock},
 	time::Instant,
 };
 
 use async_trait::async_trait;
 use ruma::{
-	OwnedEventId, OwnedRoomId, RoomId, RoomVersionId,
+	OwnedEventId, OwnedRoomId, OwnedServerName, RoomId, RoomVersionId,
 	events::room::create::RoomCreateEventContent,
 };
 use tuwunel_core::{
@@ -28,11 +30,14 @@
 	utils::MutexMap,
 };
 
+use self::acl_check::CachedAcl;
 use crate::{Dep, globals, rooms, sending, server_keys};
 
 pub struct Service {
 	pub mutex_federation: RoomMutexMap,
 	pub federation_handletime: StdRwLock<HandleTimeMap>,
+	acl_cache: StdRwLock<AclCacheMap>,
+	acl_rejects: StdMutex<HashMap<OwnedServerName, u64>>,
 	services: Services,
 }
 
@@ -54,6 +59,7 @@ struct Services {
 
 type RoomMutexMap = MutexMap<OwnedRoomId, ()>;
 type HandleTimeMap = HashMap<OwnedRoomId, (OwnedEventId, Instant)>;
+type AclCacheMap = HashMap<OwnedRoomId, CachedAcl>;
 
 #[async_trait]
 impl crate::Service for Service {
@@ -61,6 +67,8 @@ fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 		Ok(Arc::new(Self {
 			mutex_federation: RoomMutexMap::new(),
 			federation_handletime: HandleTimeMap::new().into(),
+			acl_cache: AclCacheMap::new().into(),
+			acl_rejects: HashMap::new().into(),
 			services: Services {
 				globals: args.depend::<globals::Service>("globals"),
 				sending: args.depend::<sending::Service>("sending"),
@@ -92,9 +100,23 @@ async fn memory_usage(&self, out: &mut (dyn Write + Send)) -> Result {
 			.len();
 		writeln!(out, "federation_handletime: {federation_handletime}")?;
 
+		let acl_cache = self
+			.acl_cache
+			.read()
+			.expect("locked for reading")
+			.len();
+		writeln!(out, "acl_cache: {acl_cache}")?;
+
 		Ok(())
 	}
 
+	async fn clear_cache(&self) {
+		self.acl_cache
+			.write()
+			.expect("locked for writing")
+			.clear();
+	}
+
 	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
 }
 
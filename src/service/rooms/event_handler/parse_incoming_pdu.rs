@@ -1,4 +1,7 @@
-use ruma::{CanonicalJsonObject, CanonicalJsonValue, OwnedEventId, OwnedRoomId};
+use ruma::{
+	CanonicalJsonObject, CanonicalJsonValue, OwnedEventId, OwnedRoomId, RoomId,
+	signatures::Verified,
+};
 use serde_json::value::RawValue as RawJsonValue;
 use tuwunel_core::{
 	Result, err, implement, matrix::event::gen_event_id_canonical_json, result::FlatOk,
@@ -31,3 +34,24 @@ pub async fn parse_incoming_pdu(&self, pdu: &RawJsonValue) -> Result<Parsed> {
 
 	Ok((room_id, event_id, value))
 }
+
+/// Check the signatures and content hash of a parsed PDU ahead of handling it,
+/// on the blocking pool; see `handle_incoming_verified_pdu`.
+#[implement(super::Service)]
+pub async fn verify_incoming_pdu(
+	&self,
+	room_id: &RoomId,
+	value: &CanonicalJsonObject,
+) -> Result<Verified> {
+	let room_version_id = self
+		.services
+		.state
+		.get_room_version(room_id)
+		.await
+		.map_err(|_| err!("Server is not in room {room_id}"))?;
+
+	self.services
+		.server_keys
+		.verify_event_blocking(value, &room_version_id)
+		.await
+}
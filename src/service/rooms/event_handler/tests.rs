@@ -0,0 +1,75 @@
+use ruma::{events::room::server_acl::RoomServerAclEventContent, room_id, server_name};
+
+use super::acl_check::Acl;
+
+fn acl(allow: &[&str], deny: &[&str], allow_ip_literals: bool) -> Option<Acl> {
+	let content = RoomServerAclEventContent::new(
+		allow_ip_literals,
+		allow
+			.iter()
+			.copied()
+			.map(ToOwned::to_owned)
+			.collect(),
+		deny.iter()
+			.copied()
+			.map(ToOwned::to_owned)
+			.collect(),
+	);
+
+	Acl::new(room_id!("!room:example.com"), content)
+}
+
+#[test]
+fn broken_acl_ignored() {
+	assert!(acl(&[], &[], true).is_none());
+	assert!(acl(&[], &["evil.example"], true).is_none());
+	assert!(acl(&["*"], &["*"], true).is_none());
+}
+
+#[test]
+fn deny_overrides_allow() {
+	let acl = acl(&["*"], &["evil.example", "*.evil.example"], true).unwrap();
+
+	assert!(acl.is_allowed(server_name!("example.com")));
+	assert!(!acl.is_allowed(server_name!("evil.example")));
+	assert!(!acl.is_allowed(server_name!("matrix.evil.example")));
+	assert!(acl.is_allowed(server_name!("notevil.example")));
+}
+
+#[test]
+fn allow_list_only() {
+	let acl = acl(&["example.com", "*.example.com"], &[], true).unwrap();
+
+	assert!(acl.is_allowed(server_name!("example.com")));
+	assert!(acl.is_allowed(server_name!("matrix.example.com")));
+	assert!(!acl.is_allowed(server_name!("example.org")));
+	assert!(!acl.is_allowed(server_name!("badexample.com")));
+}
+
+#[test]
+fn single_character_wildcard() {
+	let acl = acl(&["matrix?.example.com"], &[], true).unwrap();
+
+	assert!(acl.is_allowed(server_name!("matrix1.example.com")));
+	assert!(!acl.is_allowed(server_name!("matrix.example.com")));
+	assert!(!acl.is_allowed(server_name!("matrix12.example.com")));
+}
+
+#[test]
+fn port_ignored() {
+	let acl = acl(&["example.com"], &[], true).unwrap();
+
+	assert!(acl.is_allowed(server_name!("example.com:8448")));
+}
+
+#[test]
+fn ip_literals() {
+	let allowed = acl(&["*"], &[], true).unwrap();
+	assert!(allowed.is_allowed(server_name!("192.0.2.1")));
+	assert!(allowed.is_allowed(server_name!("[2001:db8::1]:8448")));
+
+	let denied = acl(&["*"], &[], false).unwrap();
+	assert!(!denied.is_allowed(server_name!("192.0.2.1")));
+	assert!(!denied.is_allowed(server_name!("[2001:db8::1]:8448")));
+	assert!(denied.is_allowed(server_name!("example.com")));
+}
@@ -0,0 +1,213 @@
+#[cfg(test)]
+mod tests;
+
+use std::{
+	collections::HashMap,
+	fmt::Write,
+	hash::Hash,
+	sync::{Arc, Mutex},
+	time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use ruma::{
+	OwnedRoomId, OwnedServerName, OwnedUserId, RoomId, UserId,
+	api::client::error::{ErrorKind, RetryAfter},
+};
+use tuwunel_core::{Error, Result, Server, debug_info, implement};
+
+use crate::{Dep, appservice, globals};
+
+/// Limits on the rate of membership changes (joins, invites and knocks) by
+/// each user and into each room, counted over fixed windows. Callers check
+/// before making a change and count it once it has been made, so refused and
+/// invalid requests do not use up the limits.
+pub struct Service {
+	server: Arc<Server>,
+	services: Services,
+	users: Mutex<Windows<OwnedUserId>>,
+	rooms: Mutex<Windows<(OwnedRoomId, Sender)>>,
+}
+
+struct Services {
+	appservice: Dep<appservice::Service>,
+	globals: Dep<globals::Service>,
+}
+
+/// Whose changes share a room's window: local users, local appservice users,
+/// and the users of each remote server are counted apart, so that no one of
+/// them can exhaust the limit for the others.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub(super) enum Sender {
+	Local,
+	Appservice,
+	Remote(OwnedServerName),
+}
+
+pub(super) type Windows<K> = HashMap<K, Window>;
+
+#[derive(Clone, Copy, Debug)]
+pub(super) struct Window {
+	start: Instant,
+	count: u32,
+}
+
+/// Expired windows are only pruned once there are this many.
+const PRUNE_THRESHOLD: usize = 4096;
+
+#[async_trait]
+impl crate::Service for Service {
+	fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
+		Ok(Arc::new(Self {
+			server: args.server.clone(),
+			services: Services {
+				appservice: args.depend::<appservice::Service>("appservice"),
+				globals: args.depend::<globals::Service>("globals"),
+			},
+			users: Mutex::default(),
+			rooms: Mutex::default(),
+		}))
+	}
+
+	async fn memory_usage(&self, out: &mut (dyn Write + Send)) -> Result {
+		let users = self.users.lock()?.len();
+		let rooms = self.rooms.lock()?.len();
+		writeln!(out, "membership_limit_users: {users}")?;
+		writeln!(out, "membership_limit_rooms: {rooms}")?;
+
+		Ok(())
+	}
+
+	async fn clear_cache(&self) {
+		self.users.lock().expect("locked").clear();
+		self.rooms.lock().expect("locked").clear();
+	}
+
+	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
+}
+
+/// Refuse a membership change by the user into the room when either has
+/// reached its limit for the current window. The change is not counted; call
+/// [`Service::count`] once it has been made.
+#[implement(Service)]
+pub async fn check(&self, user_id: &UserId, room_id: &RoomId) -> Result {
+	let sender = self.sender(user_id).await;
+	let (user_limit, room_limit) = self.limits(&sender);
+	let period = self.period();
+	let now = Instant::now();
+
+	let user = user_id.to_owned();
+	let user_wait = retry_after(&self.users.lock()?, &user, now, period, user_limit);
+
+	let room = (room_id.to_owned(), sender);
+	let room_wait = retry_after(&self.rooms.lock()?, &room, now, period, room_limit);
+
+	let Some(retry_after) = user_wait.max(room_wait) else {
+		return Ok(());
+	};
+
+	debug_info!(%user_id, %room_id, ?retry_after, "Membership change rate limited.");
+	Err(Error::Request(
+		ErrorKind::LimitExceeded {
+			retry_after: Some(RetryAfter::Delay(retry_after)),
+		},
+		"Too many membership changes; try again later.".into(),
+		http::StatusCode::TOO_MANY_REQUESTS,
+	))
+}
+
+/// Count a membership change the user has made into the room.
+#[implement(Service)]
+pub async fn count(&self, user_id: &UserId, room_id: &RoomId) {
+	let sender = self.sender(user_id).await;
+	let (user_limit, room_limit) = self.limits(&sender);
+	let period = self.period();
+	let now = Instant::now();
+
+	if user_limit > 0 {
+		record(&mut self.users.lock().expect("locked"), user_id.to_owned(), now, period);
+	}
+
+	if room_limit > 0 {
+		record(
+			&mut self.rooms.lock().expect("locked"),
+			(room_id.to_owned(), sender),
+			now,
+			period,
+		);
+	}
+}
+
+#[implement(Service)]
+async fn sender(&self, user_id: &UserId) -> Sender {
+	if !self.services.globals.user_is_local(user_id) {
+		Sender::Remote(user_id.server_name().to_owned())
+	} else if self
+		.services
+		.appservice
+		.is_exclusive_user_id(user_id)
+		.await
+	{
+		Sender::Appservice
+	} else {
+		Sender::Local
+	}
+}
+
+/// The limits for a user and for the room's window of their kind of sender.
+#[implement(Service)]
+fn limits(&self, sender: &Sender) -> (u32, u32) {
+	let config = &self.server.config;
+	match sender {
+		| Sender::Appservice => (
+			config.membership_rate_limit_appservice_user,
+			config.membership_rate_limit_appservice_room,
+		),
+		| Sender::Local | Sender::Remote(_) =>
+			(config.membership_rate_limit_user, config.membership_rate_limit_room),
+	}
+}
+
+#[implement(Service)]
+fn period(&self) -> Duration { Duration::from_secs(self.server.config.membership_rate_window) }
+
+/// How long until the key may make another change, when it has reached the
+/// limit for its current window; a limit of 0 is unlimited.
+pub(super) fn retry_after<K>(
+	windows: &Windows<K>,
+	key: &K,
+	now: Instant,
+	period: Duration,
+	limit: u32,
+) -> Option<Duration>
+where
+	K: Eq + Hash,
+{
+	if limit == 0 {
+		return None;
+	}
+
+	let window = windows.get(key)?;
+	let elapsed = now.saturating_duration_since(window.start);
+	(elapsed < period && window.count >= limit).then(|| period.saturating_sub(elapsed))
+}
+
+/// Count a change in the key's window, started anew when the last has ended.
+pub(super) fn record<K>(windows: &mut Windows<K>, key: K, now: Instant, period: Duration)
+where
+	K: Eq + Hash,
+{
+	if windows.len() >= PRUNE_THRESHOLD {
+		windows.retain(|_, window| now.saturating_duration_since(window.start) < period);
+	}
+
+	let window = windows
+		.entry(key)
+		.or_insert(Window { start: now, count: 0 });
+
+	if now.saturating_duration_since(window.start) >= period {
+		*window = Window { start: now, count: 0 };
+	}
+
+	window.count = window.count.saturating_add(1);
+}
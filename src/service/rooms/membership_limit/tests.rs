@@ -0,0 +1,72 @@
+use std::time::{Duration, Instant};
+
+use ruma::{owned_room_id, owned_server_name};
+
+use super::{Sender, Windows, record, retry_after};
+
+const PERIOD: Duration = Duration::from_secs(60);
+
+#[test]
+fn unlimited() {
+	let mut windows = Windows::new();
+	let now = Instant::now();
+	for _ in 0..100 {
+		record(&mut windows, "a", now, PERIOD);
+	}
+
+	assert_eq!(retry_after(&windows, &"a", now, PERIOD, 0), None);
+}
+
+#[test]
+fn limited_after_count() {
+	let mut windows = Windows::new();
+	let now = Instant::now();
+	assert_eq!(retry_after(&windows, &"a", now, PERIOD, 2), None);
+
+	record(&mut windows, "a", now, PERIOD);
+	assert_eq!(retry_after(&windows, &"a", now, PERIOD, 2), None);
+
+	record(&mut windows, "a", now, PERIOD);
+	let later = now + Duration::from_secs(15);
+	assert_eq!(retry_after(&windows, &"a", later, PERIOD, 2), Some(Duration::from_secs(45)));
+	assert_eq!(retry_after(&windows, &"b", later, PERIOD, 2), None);
+}
+
+#[test]
+fn checking_does_not_count() {
+	let mut windows = Windows::new();
+	let now = Instant::now();
+	record(&mut windows, "a", now, PERIOD);
+	for _ in 0..10 {
+		assert_eq!(retry_after(&windows, &"a", now, PERIOD, 2), None);
+	}
+}
+
+#[test]
+fn window_restarts() {
+	let mut windows = Windows::new();
+	let now = Instant::now();
+	record(&mut windows, "a", now, PERIOD);
+	assert!(retry_after(&windows, &"a", now, PERIOD, 1).is_some());
+
+	let later = now + PERIOD;
+	assert_eq!(retry_after(&windows, &"a", later, PERIOD, 1), None);
+
+	record(&mut windows, "a", later, PERIOD);
+	assert_eq!(retry_after(&windows, &"a", later, PERIOD, 1), Some(PERIOD));
+}
+
+#[test]
+fn senders_counted_apart() {
+	let mut windows = Windows::new();
+	let now = Instant::now();
+	let room = owned_room_id!("!room:example.com");
+	let remote = Sender::Remote(owned_server_name!("remote.example.com"));
+	let other = Sender::Remote(owned_server_name!("other.example.com"));
+
+	record(&mut windows, (room.clone(), remote.clone()), now, PERIOD);
+	assert!(retry_after(&windows, &(room.clone(), remote), now, PERIOD, 1).is_some());
+	assert_eq!(retry_after(&windows, &(room.clone(), other), now, PERIOD, 1), None);
+	assert_eq!(retry_after(&windows, &(room.clone(), Sender::Local), now, PERIOD, 1), None);
+	assert_eq!(retry_after(&windows, &(room, Sender::Appservice), now, PERIOD, 1), None);
+}
@@ -1,8 +1,10 @@
 pub mod alias;
 pub mod auth_chain;
+pub mod delayed_events;
 pub mod directory;
 pub mod event_handler;
 pub mod lazy_loading;
+pub mod membership_limit;
 pub mod metadata;
 pub mod outlier;
 pub mod pdu_metadata;
@@ -17,6 +19,7 @@
 pub mod threads;
 pub mod timeline;
 pub mod typing;
+pub mod usage;
 pub mod user;
 
 use std::sync::Arc;
@@ -24,9 +27,11 @@
 pub struct Service {
 	pub alias: Arc<alias::Service>,
 	pub auth_chain: Arc<auth_chain::Service>,
+	pub delayed_events: Arc<delayed_events::Service>,
 	pub directory: Arc<directory::Service>,
 	pub event_handler: Arc<event_handler::Service>,
 	pub lazy_loading: Arc<lazy_loading::Service>,
+	pub membership_limit: Arc<membership_limit::Service>,
 	pub metadata: Arc<metadata::Service>,
 	pub outlier: Arc<outlier::Service>,
 	pub pdu_metadata: Arc<pdu_metadata::Service>,
@@ -41,5 +46,6 @@ pub struct Service {
 	pub threads: Arc<threads::Service>,
 	pub timeline: Arc<timeline::Service>,
 	pub typing: Arc<typing::Service>,
+	pub usage: Arc<usage::Service>,
 	pub user: Arc<user::Service>,
 }
@@ -0,0 +1,149 @@
+//! Annotations (`m.annotation` relations, such as reactions), which are
+//! indexed by their target, key and sender to keep them unique and to count
+//! them for aggregation.
+
+use std::collections::BTreeMap;
+
+use ruma::{EventId, OwnedEventId, RoomId, UserId, events::TimelineEventType};
+use serde::Deserialize;
+use serde_json::{json, value::RawValue as RawJsonValue};
+use tuwunel_core::{
+	Err, Error, Result, implement,
+	matrix::{Event, pdu::PduEvent},
+	utils::ReadyExt,
+};
+use tuwunel_database::Batch;
+
+#[derive(Deserialize)]
+struct ExtractAnnotation {
+	#[serde(rename = "m.relates_to")]
+	relates_to: Annotation,
+}
+
+#[derive(Deserialize)]
+struct Annotation {
+	rel_type: String,
+	event_id: OwnedEventId,
+	key: String,
+}
+
+const ANNOTATION: &str = "m.annotation";
+
+/// Check an annotation a local user is about to send: its target must exist
+/// in the room, and the user must not have annotated it with the same key.
+/// Content which is not an annotation passes.
+#[implement(super::Service)]
+pub async fn check_annotation(
+	&self,
+	room_id: &RoomId,
+	sender: &UserId,
+	event_type: &TimelineEventType,
+	content: &RawJsonValue,
+) -> Result {
+	if *event_type != TimelineEventType::Reaction {
+		return Ok(());
+	}
+
+	let Some(annotation) = parse(content) else {
+		return Ok(());
+	};
+
+	let target = self
+		.services
+		.timeline
+		.get_pdu(&annotation.event_id)
+		.await;
+
+	if !target.is_ok_and(|target| target.room_id() == room_id) {
+		return Err!(Request(NotFound("Annotated event not found in this room.")));
+	}
+
+	if self
+		.db
+		.get_annotation(&annotation.event_id, &annotation.key, sender)
+		.await
+		.is_ok()
+	{
+		return Err(duplicate_annotation());
+	}
+
+	Ok(())
+}
+
+/// Stage indexing a reaction appended to the timeline.
+#[implement(super::Service)]
+pub fn add_annotation<'a, Pdu: Event>(&'a self, batch: &mut Batch<'a>, pdu: &Pdu) {
+	if let Some(annotation) = parse(pdu.content()) {
+		self.db.add_annotation(
+			batch,
+			&annotation.event_id,
+			&annotation.key,
+			pdu.sender(),
+			pdu.event_id(),
+		);
+	}
+}
+
+/// Remove an annotation from the index when it is redacted.
+#[implement(super::Service)]
+pub fn remove_annotation<Pdu: Event>(&self, pdu: &Pdu) {
+	if *pdu.kind() != TimelineEventType::Reaction {
+		return;
+	}
+
+	if let Some(annotation) = parse(pdu.content()) {
+		self.db
+			.remove_annotation(&annotation.event_id, &annotation.key, pdu.sender());
+	}
+}
+
+/// Number of senders of each key annotating the event.
+#[implement(super::Service)]
+pub async fn annotation_counts(&self, target: &EventId) -> BTreeMap<String, u64> {
+	self.db
+		.annotations(target)
+		.ready_fold(BTreeMap::new(), |mut counts, key| {
+			let count: &mut u64 = counts.entry(key.to_owned()).or_default();
+			*count = count.saturating_add(1);
+			counts
+		})
+		.await
+}
+
+/// Bundle the counts of the annotations of the event with it, as the
+/// `m.annotation` aggregation of its `unsigned` relations.
+#[implement(super::Service)]
+pub async fn bundle_annotations(&self, pdu: &mut PduEvent) -> Result {
+	let counts = self.annotation_counts(pdu.event_id()).await;
+	if counts.is_empty() {
+		return Ok(());
+	}
+
+	let chunk: Vec<_> = counts
+		.into_iter()
+		.map(|(key, count)| {
+			json!({
+				"type": TimelineEventType::Reaction,
+				"key": key,
+				"count": count,
+			})
+		})
+		.collect();
+
+	pdu.add_aggregation(ANNOTATION, json!({ "chunk": chunk }))
+}
+
+fn parse(content: &RawJsonValue) -> Option<Annotation> {
+	serde_json::from_str::<ExtractAnnotation>(content.get())
+		.ok()
+		.map(|content| content.relates_to)
+		.filter(|annotation| annotation.rel_type == ANNOTATION)
+}
+
+fn duplicate_annotation() -> Error {
+	Error::custom_request(
+		"M_DUPLICATE_ANNOTATION",
+		"You have already annotated this event with this key.",
+		http::StatusCode::BAD_REQUEST,
+	)
+}
@@ -1,8 +1,9 @@
-use std::{mem::size_of, sync::Arc};
+use std::sync::Arc;
 
 use futures::{Stream, StreamExt};
-use ruma::{EventId, RoomId, UserId, api::Direction};
+use ruma::{EventId, OwnedEventId, RoomId, UserId, api::Direction};
 use tuwunel_core::{
+	Result,
 	arrayvec::ArrayVec,
 	matrix::{Event, PduCount},
 	result::LogErr,
@@ -12,7 +13,7 @@
 		u64_from_u8,
 	},
 };
-use tuwunel_database::Map;
+use tuwunel_database::{Batch, Deserialized, Ignore, Interfix, Map};
 
 use crate::{
 	Dep, rooms,
@@ -23,6 +24,8 @@
 };
 
 pub(super) struct Data {
+	fromto_relation: Arc<Map>,
+	targetkeysender_eventid: Arc<Map>,
 	tofrom_relation: Arc<Map>,
 	referencedevents: Arc<Map>,
 	softfailedeventids: Arc<Map>,
@@ -37,6 +40,8 @@ impl Data {
 	pub(super) fn new(args: &crate::Args<'_>) -> Self {
 		let db = &args.db;
 		Self {
+			fromto_relation: db["fromto_relation"].clone(),
+			targetkeysender_eventid: db["targetkeysender_eventid"].clone(),
 			tofrom_relation: db["tofrom_relation"].clone(),
 			referencedevents: db["referencedevents"].clone(),
 			softfailedeventids: db["softfailedeventids"].clone(),
@@ -46,22 +51,43 @@ pub(super) fn new(args: &crate::Args<'_>) -> Self {
 		}
 	}
 
-	pub(super) fn add_relation(&self, from: u64, to: u64) {
-		const BUFSIZE: usize = size_of::<u64>() * 2;
-
+	/// Stage recording that `from` relates to `to` at `depth`: directly at 1,
+	/// or through as many other events.
+	pub(super) fn add_relation<'a>(
+		&'a self,
+		batch: &mut Batch<'a>,
+		from: u64,
+		to: u64,
+		depth: u8,
+	) {
 		let key: &[u64] = &[to, from];
-		self.tofrom_relation
-			.aput_raw::<BUFSIZE, _, _>(key, []);
+		batch.put_raw(&self.tofrom_relation, key, [depth]);
+
+		let key: &[u64] = &[from, to];
+		batch.put_raw(&self.fromto_relation, key, [depth]);
 	}
 
+	/// Events which `from` relates to, with the depth of each relation.
+	pub(super) fn relates_to(&self, from: u64) -> impl Stream<Item = (u64, u8)> + Send + '_ {
+		let prefix = from.to_be_bytes();
+		self.fromto_relation
+			.raw_stream_from(&prefix)
+			.ignore_err()
+			.ready_take_while(move |(key, _)| key.starts_with(&prefix))
+			.map(|(key, val)| (u64_from_u8(&key[8..16]), relation_depth(val)))
+	}
+
+	/// Events relating to the target within `max_depth`, in the direction of
+	/// pagination starting after `from`.
 	pub(super) fn get_relations<'a>(
 		&'a self,
 		user_id: &'a UserId,
 		shortroomid: ShortRoomId,
 		target: ShortEventId,
 		from: PduCount,
+		max_depth: u8,
 		dir: Direction,
-	) -> impl Stream<Item = (PduCount, impl Event)> + Send + '_ {
+	) -> impl Stream<Item = (PduCount, impl Event)> + Send + 'a {
 		let mut current = ArrayVec::<u8, 16>::new();
 		current.extend(target.to_be_bytes());
 		current.extend(
@@ -73,16 +99,17 @@ pub(super) fn get_relations<'a>(
 		match dir {
 			| Direction::Forward => self
 				.tofrom_relation
-				.raw_keys_from(current)
+				.raw_stream_from(current)
 				.boxed(),
 			| Direction::Backward => self
 				.tofrom_relation
-				.rev_raw_keys_from(current)
+				.rev_raw_stream_from(current)
 				.boxed(),
 		}
 		.ignore_err()
-		.ready_take_while(move |key| key.starts_with(&target.to_be_bytes()))
-		.map(|to_from| u64_from_u8(&to_from[8..16]))
+		.ready_take_while(move |(key, _)| key.starts_with(&target.to_be_bytes()))
+		.ready_filter(move |(_, val)| relation_depth(val) <= max_depth)
+		.map(|(to_from, _)| u64_from_u8(&to_from[8..16]))
 		.map(PduCount::from_unsigned)
 		.wide_filter_map(move |shorteventid| async move {
 			let pdu_id: RawPduId = PduId { shortroomid, shorteventid }.into();
@@ -105,6 +132,46 @@ pub(super) fn get_relations<'a>(
 		})
 	}
 
+	pub(super) fn add_annotation<'a>(
+		&'a self,
+		batch: &mut Batch<'a>,
+		target: &EventId,
+		key: &str,
+		sender: &UserId,
+		event_id: &EventId,
+	) {
+		batch.put(&self.targetkeysender_eventid, (target, key, sender), event_id);
+	}
+
+	pub(super) fn remove_annotation(&self, target: &EventId, key: &str, sender: &UserId) {
+		self.targetkeysender_eventid
+			.del((target, key, sender));
+	}
+
+	pub(super) async fn get_annotation(
+		&self,
+		target: &EventId,
+		key: &str,
+		sender: &UserId,
+	) -> Result<OwnedEventId> {
+		self.targetkeysender_eventid
+			.qry(&(target, key, sender))
+			.await
+			.deserialized()
+	}
+
+	/// Keys of the annotations of the target, once for each sender.
+	pub(super) fn annotations<'a>(
+		&'a self,
+		target: &'a EventId,
+	) -> impl Stream<Item = &'a str> + Send + 'a {
+		let prefix = (target, Interfix);
+		self.targetkeysender_eventid
+			.keys_prefix(&prefix)
+			.ignore_err()
+			.map(|(_, key, _): (Ignore, &str, Ignore)| key)
+	}
+
 	#[inline]
 	pub(super) fn mark_as_referenced<'a, I>(&self, room_id: &RoomId, event_ids: I)
 	where
@@ -131,4 +198,38 @@ pub(super) async fn is_event_soft_failed(&self, event_id: &EventId) -> bool {
 			.await
 			.is_ok()
 	}
+
+	/// Removes every relation to and from the event at `count`.
+	pub(super) async fn purge_relations(&self, count: u64) {
+		for map in [&self.tofrom_relation, &self.fromto_relation] {
+			map.keys_prefix_raw(&count)
+				.ignore_err()
+				.ready_for_each(|key| map.remove(key))
+				.await;
+		}
+	}
+
+	/// Removes the annotations of the event and its soft-failed mark.
+	pub(super) async fn purge_event(&self, event_id: &EventId) {
+		let prefix = (event_id, Interfix);
+		self.targetkeysender_eventid
+			.keys_prefix_raw(&prefix)
+			.ignore_err()
+			.ready_for_each(|key| self.targetkeysender_eventid.remove(key))
+			.await;
+
+		self.softfailedeventids.remove(event_id);
+	}
+
+	pub(super) async fn purge_room(&self, room_id: &RoomId) {
+		let prefix = (room_id, Interfix);
+		self.referencedevents
+			.keys_prefix_raw(&prefix)
+			.ignore_err()
+			.ready_for_each(|key| self.referencedevents.remove(key))
+			.await;
+	}
 }
+
+/// Relations recorded before depths were stored are direct.
+pub(super) fn relation_depth(val: &[u8]) -> u8 { val.first().copied().unwrap_or(1) }
@@ -1,16 +1,25 @@
+mod annotation;
 mod data;
+#[cfg(test)]
+mod tests;
+
 use std::sync::Arc;
 
-use futures::{StreamExt, future::try_join};
+use futures::{Stream, StreamExt, future::try_join};
 use ruma::{EventId, RoomId, UserId, api::Direction};
 use tuwunel_core::{
 	Result,
 	matrix::{Event, PduCount},
 };
+use tuwunel_database::Batch;
 
 use self::data::Data;
 use crate::{Dep, rooms};
 
+/// Deepest indirect relation indexed, and so the furthest relations are
+/// followed when recursing; the spec recommends at least 3.
+pub const MAX_RELATION_DEPTH: u8 = 3;
+
 pub struct Service {
 	services: Services,
 	db: Data,
@@ -36,79 +45,50 @@ fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
 }
 
 impl Service {
-	#[tracing::instrument(skip(self, from, to), level = "debug")]
-	pub fn add_relation(&self, from: PduCount, to: PduCount) {
-		match (from, to) {
-			| (PduCount::Normal(f), PduCount::Normal(t)) => self.db.add_relation(f, t),
-			| _ => {
-				// TODO: Relations with backfilled pdus
-			},
+	/// Stage recording that `from` relates to `to`, and indirectly to the
+	/// events `to` relates to, up to `MAX_RELATION_DEPTH`.
+	#[tracing::instrument(skip(self, batch, from, to), level = "debug")]
+	pub async fn add_relation<'a>(&'a self, batch: &mut Batch<'a>, from: PduCount, to: PduCount) {
+		let (PduCount::Normal(from), PduCount::Normal(to)) = (from, to) else {
+			// TODO: Relations with backfilled pdus
+			return;
+		};
+
+		let ancestors: Vec<_> = self.db.relates_to(to).collect().await;
+
+		self.db.add_relation(batch, from, to, 1);
+		for (ancestor, depth) in indirect(ancestors) {
+			self.db.add_relation(batch, from, ancestor, depth);
 		}
 	}
 
-	#[allow(clippy::too_many_arguments)]
+	/// Events relating to the target within `max_depth`, ordered for
+	/// pagination in `dir` starting after `from`.
 	pub async fn get_relations<'a>(
 		&'a self,
 		user_id: &'a UserId,
 		room_id: &'a RoomId,
 		target: &'a EventId,
 		from: PduCount,
-		limit: usize,
 		max_depth: u8,
 		dir: Direction,
-	) -> Vec<(PduCount, impl Event)> {
+	) -> impl Stream<Item = (PduCount, impl Event)> + Send + 'a {
 		let room_id = self.services.short.get_shortroomid(room_id);
 
 		let target = self.services.timeline.get_pdu_count(target);
 
-		let Ok((room_id, target)) = try_join(room_id, target).await else {
-			return Vec::new();
-		};
-
-		let target = match target {
-			| PduCount::Normal(c) => c,
-			// TODO: Support backfilled relations
-			| _ => 0, // This will result in an empty iterator
-		};
-
-		let mut pdus: Vec<_> = self
-			.db
-			.get_relations(user_id, room_id, target, from, dir)
-			.collect()
-			.await;
-
-		let mut stack: Vec<_> = pdus
-			.iter()
-			.filter(|_| max_depth > 0)
-			.map(|pdu| (pdu.clone(), 1))
-			.collect();
-
-		'limit: while let Some(stack_pdu) = stack.pop() {
-			let target = match stack_pdu.0.0 {
-				| PduCount::Normal(c) => c,
+		// Unknown rooms and targets result in an empty stream.
+		let (room_id, target) = try_join(room_id, target)
+			.await
+			.map(|(room_id, target)| match target {
+				| PduCount::Normal(c) => (room_id, c),
 				// TODO: Support backfilled relations
-				| PduCount::Backfilled(_) => 0, // This will result in an empty iterator
-			};
-
-			let relations: Vec<_> = self
-				.db
-				.get_relations(user_id, room_id, target, from, dir)
-				.collect()
-				.await;
-
-			for relation in relations {
-				if stack_pdu.1 < max_depth {
-					stack.push((relation.clone(), stack_pdu.1.saturating_add(1)));
-				}
-
-				pdus.push(relation);
-				if pdus.len() >= limit {
-					break 'limit;
-				}
-			}
-		}
+				| PduCount::Backfilled(_) => (room_id, 0),
+			})
+			.unwrap_or_default();
 
-		pdus
+		self.db
+			.get_relations(user_id, room_id, target, from, max_depth, dir)
 	}
 
 	#[tracing::instrument(skip_all, level = "debug")]
@@ -138,4 +118,32 @@ pub fn mark_event_soft_failed(&self, event_id: &EventId) {
 	pub async fn is_event_soft_failed(&self, event_id: &EventId) -> bool {
 		self.db.is_event_soft_failed(event_id).await
 	}
+
+	/// Removes the relations, annotations and soft-failed mark of an event, as
+	/// part of purging its room from the database.
+	#[tracing::instrument(skip(self), level = "debug")]
+	pub async fn purge_event(&self, count: PduCount, event_id: &EventId) {
+		if let PduCount::Normal(count) = count {
+			self.db.purge_relations(count).await;
+		}
+
+		self.db.purge_event(event_id).await;
+	}
+
+	/// Removes the record of which events of a room are referenced, as part of
+	/// purging the room from the database.
+	#[tracing::instrument(skip(self), level = "debug")]
+	pub async fn purge_room(&self, room_id: &RoomId) { self.db.purge_room(room_id).await; }
+}
+
+/// Relations of an event relating to another through that event's own
+/// relations, each one step deeper, up to `MAX_RELATION_DEPTH`.
+fn indirect<I>(ancestors: I) -> impl Iterator<Item = (u64, u8)>
+where
+	I: IntoIterator<Item = (u64, u8)>,
+{
+	ancestors
+		.into_iter()
+		.filter(|&(_, depth)| depth < MAX_RELATION_DEPTH)
+		.map(|(ancestor, depth)| (ancestor, depth.saturating_add(1)))
 }
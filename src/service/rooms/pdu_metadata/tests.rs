@@ -0,0 +1,45 @@
+use std::collections::BTreeMap;
+
+use super::{MAX_RELATION_DEPTH, data::relation_depth, indirect};
+
+#[test]
+fn depth_of_legacy_relation() {
+	assert_eq!(relation_depth(&[]), 1);
+	assert_eq!(relation_depth(&[2]), 2);
+}
+
+#[test]
+fn indirect_one_step_deeper() {
+	let ancestors = [(10, 1), (5, 2)];
+	let found: Vec<_> = indirect(ancestors).collect();
+	assert_eq!(found, [(10, 2), (5, 3)]);
+}
+
+#[test]
+fn indirect_bounded_by_max_depth() {
+	let ancestors = [(10, 1), (5, MAX_RELATION_DEPTH)];
+	let found: Vec<_> = indirect(ancestors).collect();
+	assert_eq!(found, [(10, 2)]);
+	assert!(
+		found
+			.iter()
+			.all(|&(_, depth)| depth <= MAX_RELATION_DEPTH)
+	);
+}
+
+#[test]
+fn indirect_chain() {
+	// Each event relates to the one before: 1 <- 2 <- 3 <- 4 <- 5.
+	let mut relates_to: BTreeMap<u64, Vec<(u64, u8)>> = BTreeMap::new();
+	for (from, to) in [(2, 1), (3, 2), (4, 3), (5, 4)] {
+		let ancestors = relates_to.get(&to).cloned().unwrap_or_default();
+		let relations = std::iter::once((to, 1))
+			.chain(indirect(ancestors))
+			.collect();
+
+		relates_to.insert(from, relations);
+	}
+
+	assert_eq!(relates_to[&5], [(4, 1), (3, 2), (2, 3)]);
+	assert_eq!(relates_to[&3], [(2, 1), (1, 2)]);
+}
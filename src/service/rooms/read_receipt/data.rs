@@ -10,7 +10,7 @@
 	Result,
 	utils::{ReadyExt, stream::TryIgnore},
 };
-use tuwunel_database::{Deserialized, Json, Map};
+use tuwunel_database::{Deserialized, Interfix, Json, Map};
 
 use crate::{Dep, globals};
 
@@ -120,4 +120,19 @@ pub(super) async fn last_privateread_update(
 			.deserialized()
 			.unwrap_or(0)
 	}
+
+	/// Removes every public and private receipt in a room.
+	pub(super) async fn purge_room(&self, room_id: &RoomId) {
+		let prefix = (room_id, Interfix);
+		for map in [
+			&self.readreceiptid_readreceipt,
+			&self.roomuserid_privateread,
+			&self.roomuserid_lastprivatereadupdate,
+		] {
+			map.keys_prefix_raw(&prefix)
+				.ignore_err()
+				.ready_for_each(|key| map.remove(key))
+				.await;
+		}
+	}
 }
@@ -1,4 +1,6 @@
 mod data;
+#[cfg(test)]
+mod tests;
 
 use std::{collections::BTreeMap, sync::Arc};
 
@@ -137,6 +139,30 @@ pub fn private_read_set(&self, room_id: &RoomId, user_id: &UserId, count: u64) {
 		self.db.private_read_set(room_id, user_id, count);
 	}
 
+	/// Moves the private read marker forward to PDU `private`, or to the
+	/// public receipt `read` given with it when that is later. Markers
+	/// arriving out of order never move it backwards. Returns whether it moved.
+	#[tracing::instrument(skip(self), level = "debug")]
+	pub async fn private_read_advance(
+		&self,
+		room_id: &RoomId,
+		user_id: &UserId,
+		private: u64,
+		read: Option<u64>,
+	) -> bool {
+		let current = self
+			.private_read_get_count(room_id, user_id)
+			.await
+			.ok();
+
+		let Some(count) = advance(current, private, read) else {
+			return false;
+		};
+
+		self.private_read_set(room_id, user_id, count);
+		true
+	}
+
 	/// Returns the private read marker PDU count.
 	#[inline]
 	#[tracing::instrument(skip(self), level = "debug")]
@@ -157,6 +183,22 @@ pub async fn last_privateread_update(&self, user_id: &UserId, room_id: &RoomId)
 			.last_privateread_update(user_id, room_id)
 			.await
 	}
+
+	/// Removes every receipt in a room, as part of purging the room from the
+	/// database.
+	#[inline]
+	pub async fn purge_room(&self, room_id: &RoomId) { self.db.purge_room(room_id).await; }
+}
+
+/// Position the private read marker moves to from `current` when given
+/// `private` and optionally the public receipt `read`: the later of both, or
+/// None when that is not ahead of `current`.
+fn advance(current: Option<u64>, private: u64, read: Option<u64>) -> Option<u64> {
+	let count = read.map_or(private, |read| read.max(private));
+
+	current
+		.is_none_or(|current| count > current)
+		.then_some(count)
 }
 
 #[must_use]
@@ -0,0 +1,66 @@
+use super::advance;
+
+/// Applies each `(private, read)` pair of a `/read_markers` request in turn,
+/// returning the marker after each and whether it moved.
+fn apply(requests: &[(u64, Option<u64>)]) -> Vec<(Option<u64>, bool)> {
+	let mut current = None;
+	requests
+		.iter()
+		.map(|&(private, read)| {
+			let moved = advance(current, private, read);
+			current = moved.or(current);
+			(current, moved.is_some())
+		})
+		.collect()
+}
+
+#[test]
+fn first_marker_sets() {
+	assert_eq!(advance(None, 0, None), Some(0));
+	assert_eq!(advance(None, 42, None), Some(42));
+}
+
+#[test]
+fn out_of_order_markers_never_move_back() {
+	let applied = apply(&[(5, None), (3, None), (8, None), (8, None), (7, None), (12, None)]);
+
+	assert_eq!(applied, [
+		(Some(5), true),
+		(Some(5), false),
+		(Some(8), true),
+		(Some(8), false),
+		(Some(8), false),
+		(Some(12), true),
+	]);
+}
+
+#[test]
+fn later_public_receipt_carries_private_marker() {
+	// The public receipt is later than the private one in the same request.
+	assert_eq!(advance(Some(10), 11, Some(20)), Some(20));
+
+	// The public receipt is earlier and does not hold the private one back.
+	assert_eq!(advance(Some(10), 20, Some(11)), Some(20));
+}
+
+#[test]
+fn stale_public_receipt_does_not_move_marker() {
+	// Both receipts are behind the current marker.
+	assert_eq!(advance(Some(30), 11, Some(20)), None);
+
+	// Only the public receipt is ahead; it still moves the marker once.
+	assert_eq!(advance(Some(30), 11, Some(40)), Some(40));
+}
+
+#[test]
+fn mixed_out_of_order_requests() {
+	let applied = apply(&[(5, Some(9)), (7, None), (6, Some(12)), (12, Some(12)), (15, Some(1))]);
+
+	assert_eq!(applied, [
+		(Some(9), true),
+		(Some(9), false),
+		(Some(12), true),
+		(Some(12), false),
+		(Some(15), true),
+	]);
+}
@@ -12,7 +12,7 @@
 		stream::{TryIgnore, WidebandExt},
 	},
 };
-use tuwunel_database::{Map, keyval::Val};
+use tuwunel_database::{Batch, Map, keyval::Val};
 
 use crate::{
 	Dep, rooms,
@@ -68,9 +68,16 @@ fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
 }
 
+/// Stage indexing the words of the message.
 #[implement(Service)]
-pub fn index_pdu(&self, shortroomid: ShortRoomId, pdu_id: &RawPduId, message_body: &str) {
-	let batch = tokenize(message_body)
+pub fn index_pdu<'a>(
+	&'a self,
+	batch: &mut Batch<'a>,
+	shortroomid: ShortRoomId,
+	pdu_id: &RawPduId,
+	message_body: &str,
+) {
+	tokenize(message_body)
 		.map(|word| {
 			let mut key = shortroomid.to_be_bytes().to_vec();
 			key.extend_from_slice(word.as_bytes());
@@ -78,11 +85,7 @@ pub fn index_pdu(&self, shortroomid: ShortRoomId, pdu_id: &RawPduId, message_bod
 			key.extend_from_slice(pdu_id.as_ref()); // TODO: currently we save the room id a second time here
 			key
 		})
-		.collect::<Vec<_>>();
-
-	self.db
-		.tokenids
-		.insert_batch(batch.iter().map(|k| (k.as_slice(), &[])));
+		.for_each(|key| batch.insert(&self.db.tokenids, &key, b""));
 }
 
 #[implement(Service)]
@@ -100,6 +103,17 @@ pub fn deindex_pdu(&self, shortroomid: ShortRoomId, pdu_id: &RawPduId, message_b
 	}
 }
 
+/// Remove every search index entry of a room.
+#[implement(Service)]
+pub async fn purge_room(&self, shortroomid: ShortRoomId) {
+	self.db
+		.tokenids
+		.keys_prefix_raw(&shortroomid)
+		.ignore_err()
+		.ready_for_each(|key| self.db.tokenids.remove(key))
+		.await;
+}
+
 #[implement(Service)]
 pub async fn search_pdus<'a>(
 	&'a self,
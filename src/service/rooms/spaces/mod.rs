@@ -2,13 +2,18 @@
 #[cfg(test)]
 mod tests;
 
-use std::{fmt::Write, sync::Arc};
+use std::{
+	collections::{BTreeSet, VecDeque},
+	fmt::Write,
+	sync::Arc,
+	time::{Duration, Instant},
+};
 
 use async_trait::async_trait;
 use futures::{FutureExt, Stream, StreamExt, TryFutureExt, pin_mut, stream::FuturesUnordered};
 use lru_cache::LruCache;
 use ruma::{
-	OwnedEventId, OwnedRoomId, OwnedServerName, RoomId, ServerName, UserId,
+	OwnedEventId, OwnedRoomId, OwnedServerName, OwnedUserId, RoomId, ServerName, UserId,
 	api::{
 		client::space::SpaceHierarchyRoomsChunk,
 		federation::{
@@ -25,11 +30,12 @@
 };
 use tokio::sync::{Mutex, MutexGuard};
 use tuwunel_core::{
-	Err, Error, Event, PduEvent, Result, implement,
+	Err, Error, Event, PduEvent, Result, Server,
+	config::CacheKind,
+	debug, implement,
 	utils::{
 		IterStream,
 		future::{BoolExt, TryExtExt},
-		math::usize_from_f64,
 		stream::{BroadbandExt, ReadyExt},
 	},
 };
@@ -40,9 +46,11 @@
 pub struct Service {
 	services: Services,
 	pub roomid_spacehierarchy_cache: Mutex<Cache>,
+	pagination_cache: Mutex<PaginationCache>,
 }
 
 struct Services {
+	server: Arc<Server>,
 	state_accessor: Dep<rooms::state_accessor::Service>,
 	state_cache: Dep<rooms::state_cache::Service>,
 	state: Dep<rooms::state::Service>,
@@ -55,6 +63,21 @@ pub struct CachedSpaceHierarchySummary {
 	summary: SpaceHierarchyParentSummary,
 }
 
+pub struct CacheEntry {
+	summary: Option<CachedSpaceHierarchySummary>,
+
+	/// Entries learned over federation are refetched after this time.
+	expires: Option<Instant>,
+}
+
+/// Traversal state of a client hierarchy request, kept so the next page can
+/// resume without walking the tree again.
+#[derive(Clone, Debug, Default)]
+pub struct PaginationState {
+	pub queue: VecDeque<(OwnedRoomId, Vec<OwnedServerName>)>,
+	pub parents: BTreeSet<OwnedRoomId>,
+}
+
 #[allow(clippy::large_enum_variant)]
 pub enum SummaryAccessibility {
 	Accessible(SpaceHierarchyParentSummary),
@@ -68,16 +91,18 @@ pub enum Identifier<'a> {
 	ServerName(&'a ServerName),
 }
 
-type Cache = LruCache<OwnedRoomId, Option<CachedSpaceHierarchySummary>>;
+type Cache = LruCache<OwnedRoomId, CacheEntry>;
+type PaginationCache = LruCache<(OwnedUserId, String), PaginationState>;
 
 #[async_trait]
 impl crate::Service for Service {
 	fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 		let config = &args.server.config;
-		let cache_size = f64::from(config.roomid_spacehierarchy_cache_capacity);
-		let cache_size = cache_size * config.cache_capacity_modifier;
+		let cache_size = config.cache_capacity(CacheKind::SpaceHierarchy)?;
+		let pagination_cache_size = config.cache_capacity(CacheKind::SpacePagination)?;
 		Ok(Arc::new(Self {
 			services: Services {
+				server: args.server.clone(),
 				state_accessor: args
 					.depend::<rooms::state_accessor::Service>("rooms::state_accessor"),
 				state_cache: args.depend::<rooms::state_cache::Service>("rooms::state_cache"),
@@ -87,7 +112,8 @@ fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 				timeline: args.depend::<rooms::timeline::Service>("rooms::timeline"),
 				sending: args.depend::<sending::Service>("sending"),
 			},
-			roomid_spacehierarchy_cache: Mutex::new(LruCache::new(usize_from_f64(cache_size)?)),
+			roomid_spacehierarchy_cache: Mutex::new(LruCache::new(cache_size)),
+			pagination_cache: Mutex::new(LruCache::new(pagination_cache_size)),
 		}))
 	}
 
@@ -98,7 +124,10 @@ async fn memory_usage(&self, out: &mut (dyn Write + Send)) -> Result {
 			.await
 			.len();
 
+		let pagination_cache = self.pagination_cache.lock().await.len();
+
 		writeln!(out, "roomid_spacehierarchy_cache: {roomid_spacehierarchy_cache}")?;
+		writeln!(out, "hierarchy_pagination_cache: {pagination_cache}")?;
 
 		Ok(())
 	}
@@ -108,6 +137,8 @@ async fn clear_cache(&self) {
 			.lock()
 			.await
 			.clear();
+
+		self.pagination_cache.lock().await.clear();
 	}
 
 	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
@@ -120,13 +151,7 @@ pub async fn get_summary_and_children_local(
 	current_room: &RoomId,
 	identifier: &Identifier<'_>,
 ) -> Result<Option<SummaryAccessibility>> {
-	match self
-		.roomid_spacehierarchy_cache
-		.lock()
-		.await
-		.get_mut(current_room)
-		.as_ref()
-	{
+	match cache_get(&mut *self.roomid_spacehierarchy_cache.lock().await, current_room) {
 		| None => (), // cache miss
 		| Some(None) => return Ok(None),
 		| Some(Some(cached)) => {
@@ -170,10 +195,10 @@ pub async fn get_summary_and_children_local(
 	self.roomid_spacehierarchy_cache
 		.lock()
 		.await
-		.insert(
-			current_room.to_owned(),
-			Some(CachedSpaceHierarchySummary { summary: summary.clone() }),
-		);
+		.insert(current_room.to_owned(), CacheEntry {
+			summary: Some(CachedSpaceHierarchySummary { summary: summary.clone() }),
+			expires: None,
+		});
 
 	Ok(Some(SummaryAccessibility::Accessible(summary)))
 }
@@ -202,11 +227,12 @@ async fn get_summary_and_children_federation(
 		})
 		.collect();
 
+	let expires = Some(Instant::now() + self.remote_cache_ttl());
 	let Some(Ok(response)) = requests.next().await else {
 		self.roomid_spacehierarchy_cache
 			.lock()
 			.await
-			.insert(current_room.to_owned(), None);
+			.insert(current_room.to_owned(), CacheEntry { summary: None, expires });
 
 		return Ok(None);
 	};
@@ -215,10 +241,10 @@ async fn get_summary_and_children_federation(
 	self.roomid_spacehierarchy_cache
 		.lock()
 		.await
-		.insert(
-			current_room.to_owned(),
-			Some(CachedSpaceHierarchySummary { summary: summary.clone() }),
-		);
+		.insert(current_room.to_owned(), CacheEntry {
+			summary: Some(CachedSpaceHierarchySummary { summary: summary.clone() }),
+			expires,
+		});
 
 	response
 		.children
@@ -294,7 +320,8 @@ fn get_space_child_events<'a>(
 }
 
 /// Gets the summary of a space using either local or remote (federation)
-/// sources
+/// sources. Each remote server queried is deducted from `remote_budget`; once
+/// it is exhausted rooms which are not known locally are skipped.
 #[implement(Service)]
 pub async fn get_summary_and_children_client(
 	&self,
@@ -302,6 +329,7 @@ pub async fn get_summary_and_children_client(
 	suggested_only: bool,
 	user_id: &UserId,
 	via: &[OwnedServerName],
+	remote_budget: &mut usize,
 ) -> Result<Option<SummaryAccessibility>> {
 	let identifier = Identifier::UserId(user_id);
 
@@ -312,10 +340,76 @@ pub async fn get_summary_and_children_client(
 		return Ok(Some(response));
 	}
 
+	if *remote_budget == 0 {
+		debug!(?current_room, "Remote hierarchy request budget exhausted; skipping");
+		return Ok(None);
+	}
+
+	let via = &via[..via.len().min(*remote_budget)];
+	*remote_budget = remote_budget.saturating_sub(via.len());
+
 	self.get_summary_and_children_federation(current_room, suggested_only, user_id, via)
 		.await
 }
 
+/// Budget of remote servers a single client hierarchy request may query.
+#[implement(Service)]
+#[must_use]
+pub fn remote_request_budget(&self) -> usize {
+	self.services
+		.server
+		.config
+		.hierarchy_max_remote_requests
+}
+
+#[implement(Service)]
+fn remote_cache_ttl(&self) -> Duration {
+	Duration::from_secs(
+		self.services
+			.server
+			.config
+			.hierarchy_remote_cache_ttl,
+	)
+}
+
+/// Store the traversal state behind a pagination token issued to a user.
+#[implement(Service)]
+pub async fn pagination_put(&self, user_id: &UserId, token: &str, state: PaginationState) {
+	self.pagination_cache
+		.lock()
+		.await
+		.insert((user_id.to_owned(), token.to_owned()), state);
+}
+
+/// Take the traversal state behind a pagination token, if still retained.
+#[implement(Service)]
+pub async fn pagination_take(&self, user_id: &UserId, token: &str) -> Option<PaginationState> {
+	self.pagination_cache
+		.lock()
+		.await
+		.remove(&(user_id.to_owned(), token.to_owned()))
+}
+
+/// Cache lookup honoring expiry; expired entries are evicted and reported as
+/// a miss.
+fn cache_get<'a>(
+	cache: &'a mut Cache,
+	room_id: &RoomId,
+) -> Option<Option<&'a CachedSpaceHierarchySummary>> {
+	if cache
+		.get_mut(room_id)?
+		.expires
+		.is_some_and(|expires| expires <= Instant::now())
+	{
+		cache.remove(room_id);
+		return None;
+	}
+
+	cache
+		.get_mut(room_id)
+		.map(|entry| entry.summary.as_ref())
+}
+
 #[implement(Service)]
 async fn get_room_summary(
 	&self,
@@ -574,7 +668,10 @@ async fn cache_insert(
 		room_version,
 	};
 
-	cache.insert(current_room.to_owned(), Some(CachedSpaceHierarchySummary { summary }));
+	cache.insert(current_room.to_owned(), CacheEntry {
+		summary: Some(CachedSpaceHierarchySummary { summary }),
+		expires: Some(Instant::now() + self.remote_cache_ttl()),
+	});
 }
 
 // Here because cannot implement `From` across ruma-federation-api and
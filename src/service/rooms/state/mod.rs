@@ -1,4 +1,4 @@
-use std::{collections::HashMap, fmt::Write, iter::once, sync::Arc};
+use std::{collections::HashMap, fmt::Write, iter::once, sync::Arc, time::Duration};
 
 use async_trait::async_trait;
 use futures::{
@@ -12,13 +12,15 @@
 	},
 	serde::Raw,
 };
+use tokio::time::sleep;
 use tuwunel_core::{
-	Event, PduEvent, Result, err,
+	Event, PduEvent, Result, Server, err,
 	result::FlatOk,
 	state_res::{self, StateMap},
 	utils::{
-		IterStream, MutexMap, MutexMapGuard, ReadyExt, calculate_hash,
+		IterStream, LockStats, MutexMap, MutexMapGuard, ReadyExt, calculate_hash,
 		stream::{BroadbandExt, TryIgnore},
+		time,
 	},
 	warn,
 };
@@ -39,6 +41,7 @@ pub struct Service {
 }
 
 struct Services {
+	server: Arc<Server>,
 	globals: Dep<globals::Service>,
 	short: Dep<rooms::short::Service>,
 	spaces: Dep<rooms::spaces::Service>,
@@ -63,6 +66,7 @@ fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 		Ok(Arc::new(Self {
 			mutex: RoomMutexMap::new(),
 			services: Services {
+				server: args.server.clone(),
 				globals: args.depend::<globals::Service>("globals"),
 				short: args.depend::<rooms::short::Service>("rooms::short"),
 				spaces: args.depend::<rooms::spaces::Service>("rooms::spaces"),
@@ -81,9 +85,52 @@ fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 		}))
 	}
 
+	async fn worker(self: Arc<Self>) -> Result {
+		let timeout = self
+			.services
+			.server
+			.config
+			.room_lock_watchdog_timeout;
+		if timeout == 0 {
+			return Ok(());
+		}
+
+		let threshold = Duration::from_secs(timeout);
+		let period = threshold.div_f32(4.0);
+		while self.services.server.running() {
+			tokio::select! {
+				() = sleep(period) => {},
+				() = self.services.server.until_shutdown() => break,
+			}
+
+			for (room_id, held) in self.mutex.held_longer_than(threshold) {
+				warn!(
+					%room_id,
+					held = %time::pretty(held),
+					"Room state lock held beyond watchdog timeout; possible deadlock."
+				);
+			}
+		}
+
+		Ok(())
+	}
+
 	async fn memory_usage(&self, out: &mut (dyn Write + Send)) -> Result {
 		let mutex = self.mutex.len();
+		let LockStats {
+			acquired,
+			contended,
+			wait_total,
+			wait_max,
+		} = self.mutex.stats();
+
 		writeln!(out, "state_mutex: {mutex}")?;
+		writeln!(
+			out,
+			"state_mutex_waits: {contended}/{acquired} (total {}, max {})",
+			time::pretty(wait_total),
+			time::pretty(wait_max),
+		)?;
 
 		Ok(())
 	}
@@ -379,6 +426,26 @@ pub fn set_room_state(
 			.raw_aput::<BUFSIZE, _, _>(room_id, shortstatehash);
 	}
 
+	/// Forget the room's current state and forward extremities, as part of
+	/// purging the room from the database.
+	#[tracing::instrument(skip(self, _mutex_lock), level = "debug")]
+	pub async fn delete_room_state(
+		&self,
+		room_id: &RoomId,
+		// Take mutex guard to make sure users get the room state mutex
+		_mutex_lock: &RoomMutexGuard,
+	) {
+		let prefix = (room_id, Interfix);
+		self.db
+			.roomid_pduleaves
+			.keys_prefix_raw(&prefix)
+			.ignore_err()
+			.ready_for_each(|key| self.db.roomid_pduleaves.remove(key))
+			.await;
+
+		self.db.roomid_shortstatehash.remove(room_id);
+	}
+
 	/// Returns the room's version.
 	#[tracing::instrument(skip(self), level = "debug")]
 	pub async fn get_room_version(&self, room_id: &RoomId) -> Result<RoomVersionId> {
@@ -1,8 +1,12 @@
+mod power_guard;
 mod room_state;
 mod server_can;
 mod state;
 mod user_can;
 
+#[cfg(test)]
+mod tests;
+
 use std::sync::Arc;
 
 use async_trait::async_trait;
@@ -28,7 +32,7 @@
 use tuwunel_core::{Result, err};
 use tuwunel_database::Map;
 
-use crate::{Dep, rooms};
+use crate::{Dep, globals, rooms};
 
 pub struct Service {
 	services: Services,
@@ -36,6 +40,7 @@ pub struct Service {
 }
 
 struct Services {
+	globals: Dep<globals::Service>,
 	short: Dep<rooms::short::Service>,
 	state: Dep<rooms::state::Service>,
 	state_compressor: Dep<rooms::state_compressor::Service>,
@@ -52,6 +57,7 @@ impl crate::Service for Service {
 	fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 		Ok(Arc::new(Self {
 			services: Services {
+				globals: args.depend::<globals::Service>("globals"),
 				state_cache: args.depend::<rooms::state_cache::Service>("rooms::state_cache"),
 				timeline: args.depend::<rooms::timeline::Service>("rooms::timeline"),
 				short: args.depend::<rooms::short::Service>("rooms::short"),
@@ -136,6 +142,14 @@ pub async fn get_join_rules(&self, room_id: &RoomId) -> JoinRule {
 			.map_or(JoinRule::Invite, |c: RoomJoinRulesEventContent| c.join_rule)
 	}
 
+	/// Checks if the room may federate; false when created with `m.federate`
+	/// set to false. Rooms whose create event is unknown are assumed to.
+	pub async fn is_federated(&self, room_id: &RoomId) -> bool {
+		self.room_state_get_content(room_id, &StateEventType::RoomCreate, "")
+			.await
+			.map_or(true, |content: RoomCreateEventContent| content.federate)
+	}
+
 	pub async fn get_room_type(&self, room_id: &RoomId) -> Result<RoomType> {
 		self.room_state_get_content(room_id, &StateEventType::RoomCreate, "")
 			.await
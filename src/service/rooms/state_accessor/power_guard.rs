@@ -0,0 +1,87 @@
+use ruma::{
+	Int, RoomId, UserId,
+	events::{
+		StateEventType, TimelineEventType,
+		room::{
+			member::{MembershipState, RoomMemberEventContent},
+			power_levels::{RoomPowerLevels, RoomPowerLevelsEventContent},
+		},
+	},
+};
+use tuwunel_core::{Err, Result, implement, pdu::PduBuilder};
+
+/// Checks the sender has the power level needed to send the event, failing
+/// with which level falls short rather than when the event is authorized. The
+/// server user never raises its own power; an admin of the room has to.
+#[implement(super::Service)]
+pub async fn ensure_power_to_send(
+	&self,
+	pdu_builder: &PduBuilder,
+	sender: &UserId,
+	room_id: &RoomId,
+) -> Result {
+	let Ok(content) = self
+		.room_state_get_content::<RoomPowerLevelsEventContent>(
+			room_id,
+			&StateEventType::RoomPowerLevels,
+			"",
+		)
+		.await
+	else {
+		// Only the creator has power without power levels; left to auth.
+		return Ok(());
+	};
+
+	let power_levels = RoomPowerLevels::from(content);
+	let (action, required) = required_power(&power_levels, pdu_builder, sender);
+	let current = power_levels.for_user(sender);
+	if current < required {
+		return Err!(Request(Forbidden(
+			"{sender} has power level {current} in {room_id}, but {action} requires {required}. \
+			 A room admin has to raise it first."
+		)));
+	}
+
+	Ok(())
+}
+
+/// What the event does, and the power level it requires.
+pub(super) fn required_power(
+	power_levels: &RoomPowerLevels,
+	pdu_builder: &PduBuilder,
+	sender: &UserId,
+) -> (String, Int) {
+	let event_type = &pdu_builder.event_type;
+	let target = pdu_builder.state_key.as_deref();
+	let membership = (*event_type == TimelineEventType::RoomMember)
+		.then(|| serde_json::from_str::<RoomMemberEventContent>(pdu_builder.content.get()).ok())
+		.flatten()
+		.map(|content| content.membership);
+
+	let target_name = target.unwrap_or_default();
+	match membership {
+		| Some(MembershipState::Ban) => (format!("banning {target_name}"), power_levels.ban),
+		| Some(MembershipState::Invite) =>
+			(format!("inviting {target_name}"), power_levels.invite),
+		| Some(MembershipState::Leave) if target != Some(sender.as_str()) =>
+			(format!("kicking {target_name}"), power_levels.kick),
+		| Some(_) => (format!("changing membership of {target_name}"), Int::MIN),
+		| None if *event_type == TimelineEventType::RoomRedaction =>
+			("redacting".to_owned(), power_levels.redact),
+		| None => {
+			let default = if target.is_some() {
+				power_levels.state_default
+			} else {
+				power_levels.events_default
+			};
+
+			let required = power_levels
+				.events
+				.get(event_type)
+				.copied()
+				.unwrap_or(default);
+
+			(format!("sending {event_type}"), required)
+		},
+	}
+}
@@ -0,0 +1,104 @@
+use ruma::{
+	event_id,
+	events::room::{
+		member::{MembershipState, RoomMemberEventContent},
+		message::RoomMessageEventContent,
+		name::RoomNameEventContent,
+		power_levels::{RoomPowerLevels, RoomPowerLevelsEventContent},
+		redaction::RoomRedactionEventContent,
+		topic::RoomTopicEventContent,
+	},
+	int, user_id,
+};
+use tuwunel_core::pdu::PduBuilder;
+
+use super::power_guard::required_power;
+
+fn power_levels() -> RoomPowerLevels {
+	let mut content = RoomPowerLevelsEventContent::default();
+	content.ban = int!(60);
+	content.kick = int!(55);
+	content.invite = int!(10);
+	content.redact = int!(40);
+	content.state_default = int!(50);
+	content.events_default = int!(5);
+	content.events.clear();
+	content
+		.events
+		.insert("m.room.name".into(), int!(70));
+
+	RoomPowerLevels::from(content)
+}
+
+fn membership(target: &str, membership: MembershipState) -> PduBuilder {
+	PduBuilder::state(target.to_owned(), &RoomMemberEventContent::new(membership))
+}
+
+#[test]
+fn required_power_moderation() {
+	let power_levels = power_levels();
+	let sender = user_id!("@conduit:example.com");
+	let target = "@alice:example.com";
+
+	let (action, required) =
+		required_power(&power_levels, &membership(target, MembershipState::Ban), sender);
+	assert_eq!(action, format!("banning {target}"));
+	assert_eq!(required, int!(60));
+
+	let (action, required) =
+		required_power(&power_levels, &membership(target, MembershipState::Leave), sender);
+	assert_eq!(action, format!("kicking {target}"));
+	assert_eq!(required, int!(55));
+
+	let (_, required) =
+		required_power(&power_levels, &membership(target, MembershipState::Invite), sender);
+	assert_eq!(required, int!(10));
+}
+
+#[test]
+fn required_power_own_membership() {
+	let power_levels = power_levels();
+	let sender = user_id!("@conduit:example.com");
+
+	let (_, required) = required_power(
+		&power_levels,
+		&membership(sender.as_str(), MembershipState::Leave),
+		sender,
+	);
+	assert!(required <= int!(0));
+
+	let (_, required) = required_power(
+		&power_levels,
+		&membership(sender.as_str(), MembershipState::Join),
+		sender,
+	);
+	assert!(required <= int!(0));
+}
+
+#[test]
+fn required_power_redaction() {
+	let redaction = PduBuilder::timeline(&RoomRedactionEventContent {
+		redacts: Some(event_id!("$event:example.com").to_owned()),
+		reason: None,
+	});
+
+	let (action, required) =
+		required_power(&power_levels(), &redaction, user_id!("@conduit:example.com"));
+	assert_eq!(action, "redacting");
+	assert_eq!(required, int!(40));
+}
+
+#[test]
+fn required_power_events() {
+	let power_levels = power_levels();
+	let sender = user_id!("@conduit:example.com");
+
+	let name = PduBuilder::state(String::new(), &RoomNameEventContent::new("Room".into()));
+	assert_eq!(required_power(&power_levels, &name, sender).1, int!(70));
+
+	let topic = PduBuilder::state(String::new(), &RoomTopicEventContent::new("Topic".into()));
+	assert_eq!(required_power(&power_levels, &topic, sender).1, int!(50));
+
+	let message = PduBuilder::timeline(&RoomMessageEventContent::text_plain("hello"));
+	assert_eq!(required_power(&power_levels, &message, sender).1, int!(5));
+}
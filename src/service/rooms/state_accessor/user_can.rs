@@ -1,5 +1,6 @@
+use futures::StreamExt;
 use ruma::{
-	EventId, RoomId, UserId,
+	EventId, Int, OwnedUserId, RoomId, UserId,
 	events::{
 		StateEventType, TimelineEventType,
 		room::{
@@ -9,7 +10,12 @@
 		},
 	},
 };
-use tuwunel_core::{Err, Result, implement, matrix::Event, pdu::PduBuilder};
+use tuwunel_core::{
+	Err, Result, implement,
+	matrix::Event,
+	pdu::PduBuilder,
+	utils::stream::{IterStream, ReadyExt},
+};
 
 use crate::rooms::state::RoomMutexGuard;
 
@@ -181,3 +187,86 @@ pub async fn user_can_invite(
 		.await
 		.is_ok()
 }
+
+/// Selects the local user to authorise a restricted join of `joining_user`
+/// when this server is resident in the room. Of the joined local users able
+/// to invite, the one with the highest power level is preferred so the join
+/// remains valid should lesser powers be demoted concurrently.
+#[implement(super::Service)]
+pub async fn restricted_join_authoriser(
+	&self,
+	room_id: &RoomId,
+	joining_user: &UserId,
+	state_lock: &RoomMutexGuard,
+) -> Option<OwnedUserId> {
+	let power_levels: Option<RoomPowerLevels> = self
+		.room_state_get_content::<RoomPowerLevelsEventContent>(
+			room_id,
+			&StateEventType::RoomPowerLevels,
+			"",
+		)
+		.await
+		.map(Into::into)
+		.ok();
+
+	let mut candidates: Vec<(Int, OwnedUserId)> = self
+		.services
+		.state_cache
+		.local_users_in_room(room_id)
+		.ready_filter(|user_id| *user_id != joining_user)
+		.map(|user_id| {
+			let power = power_levels
+				.as_ref()
+				.map_or_else(Int::default, |power_levels| power_levels.for_user(user_id));
+
+			(power, user_id.to_owned())
+		})
+		.ready_filter(|(power, _)| {
+			power_levels
+				.as_ref()
+				.is_none_or(|power_levels| *power >= power_levels.invite)
+		})
+		.collect()
+		.await;
+
+	candidates.sort_by(|(a, _), (b, _)| b.cmp(a));
+	candidates
+		.into_iter()
+		.stream()
+		.filter_map(|(_, user_id)| async move {
+			self.user_can_invite(room_id, &user_id, joining_user, state_lock)
+				.await
+				.then_some(user_id)
+		})
+		.boxed()
+		.next()
+		.await
+}
+
+/// Checks whether `user_id` may authorise a restricted join into the room: a
+/// local user who is joined and has the power level to invite. Without power
+/// levels any joined member may invite.
+#[implement(super::Service)]
+pub async fn user_can_authorise_join(&self, room_id: &RoomId, user_id: &UserId) -> bool {
+	if !self.services.globals.user_is_local(user_id) {
+		return false;
+	}
+
+	if !self
+		.services
+		.state_cache
+		.is_joined(user_id, room_id)
+		.await
+	{
+		return false;
+	}
+
+	self.room_state_get_content::<RoomPowerLevelsEventContent>(
+		room_id,
+		&StateEventType::RoomPowerLevels,
+		"",
+	)
+	.await
+	.map(RoomPowerLevels::from)
+	.map_or(true, |power_levels| power_levels.for_user(user_id) >= power_levels.invite)
+}
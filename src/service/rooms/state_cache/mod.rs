@@ -1,3 +1,5 @@
+mod suppressed;
+mod tombstone;
 mod update;
 mod via;
 
@@ -13,22 +15,28 @@
 	serde::Raw,
 };
 use tuwunel_core::{
-	Result, implement,
+	Result, Server, implement,
 	result::LogErr,
 	utils::{ReadyExt, stream::TryIgnore},
 	warn,
 };
 use tuwunel_database::{Deserialized, Ignore, Interfix, Map};
 
+pub use self::{
+	suppressed::SuppressedInvite,
+	tombstone::{FOLLOW_ROOM_UPGRADES, UpgradeHook},
+};
 use crate::{Dep, account_data, appservice::RegistrationInfo, config, globals, rooms, users};
 
 pub struct Service {
 	appservice_in_room_cache: AppServiceInRoomCache,
+	upgrade_hook: RwLock<Option<Arc<UpgradeHook>>>,
 	services: Services,
 	db: Data,
 }
 
 struct Services {
+	server: Arc<Server>,
 	account_data: Dep<account_data::Service>,
 	config: Dep<config::Service>,
 	globals: Dep<globals::Service>,
@@ -52,6 +60,7 @@ struct Data {
 	userroomid_joined: Arc<Map>,
 	userroomid_leftstate: Arc<Map>,
 	userroomid_knockedstate: Arc<Map>,
+	userroomid_suppressedinvite: Arc<Map>,
 }
 
 type AppServiceInRoomCache = RwLock<HashMap<OwnedRoomId, HashMap<String, bool>>>;
@@ -62,7 +71,9 @@ impl crate::Service for Service {
 	fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 		Ok(Arc::new(Self {
 			appservice_in_room_cache: RwLock::new(HashMap::new()),
+			upgrade_hook: RwLock::new(None),
 			services: Services {
+				server: args.server.clone(),
 				account_data: args.depend::<account_data::Service>("account_data"),
 				config: args.depend::<config::Service>("config"),
 				globals: args.depend::<globals::Service>("globals"),
@@ -86,6 +97,7 @@ fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 				userroomid_joined: args.db["userroomid_joined"].clone(),
 				userroomid_leftstate: args.db["userroomid_leftstate"].clone(),
 				userroomid_knockedstate: args.db["userroomid_knockedstate"].clone(),
+				userroomid_suppressedinvite: args.db["userroomid_suppressedinvite"].clone(),
 			},
 		}))
 	}
@@ -261,6 +273,20 @@ pub fn local_users_in_room<'a>(
 		.ready_filter(|user| self.services.globals.user_is_local(user))
 }
 
+/// Whether any of our users is joined or invited to the room.
+#[implement(Service)]
+#[tracing::instrument(skip(self), level = "debug")]
+pub async fn has_local_members(&self, room_id: &RoomId) -> bool {
+	let is_local = |user: &UserId| self.services.globals.user_is_local(user);
+
+	self.room_members(room_id)
+		.ready_any(is_local)
+		.await || self
+		.room_members_invited(room_id)
+		.ready_any(is_local)
+		.await
+}
+
 /// Returns an iterator of all our local joined users in a room who are
 /// active (not deactivated, not guest)
 #[implement(Service)]
@@ -0,0 +1,109 @@
+//! Invites held from their recipients; see `invite_unshared_servers`.
+
+use futures::{Stream, StreamExt};
+use ruma::{
+	OwnedRoomId, OwnedServerName, OwnedUserId, RoomId, ServerName, UserId,
+	events::{
+		AnyStrippedStateEvent,
+		room::member::{MembershipState, RoomMemberEventContent},
+	},
+	serde::Raw,
+};
+use serde::{Deserialize, Serialize};
+use tuwunel_core::{
+	Err, Result, implement,
+	utils::{millis_since_unix_epoch, stream::TryIgnore},
+};
+use tuwunel_database::{Deserialized, Json};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SuppressedInvite {
+	pub sender: OwnedUserId,
+
+	pub invite_state: Vec<Raw<AnyStrippedStateEvent>>,
+
+	pub via: Option<Vec<OwnedServerName>>,
+
+	/// When the invite was received, in milliseconds since the epoch.
+	pub received: u64,
+}
+
+/// Whether we and the server are both in any room.
+#[implement(super::Service)]
+pub async fn shares_room_with(&self, server: &ServerName) -> bool {
+	let our_server = self.services.globals.server_name();
+
+	self.server_rooms(server)
+		.any(|room_id| self.server_in_room(our_server, room_id))
+		.await
+}
+
+#[implement(super::Service)]
+pub fn suppress_invite(
+	&self,
+	user_id: &UserId,
+	room_id: &RoomId,
+	sender: &UserId,
+	invite_state: Vec<Raw<AnyStrippedStateEvent>>,
+	via: Option<Vec<OwnedServerName>>,
+) {
+	let invite = SuppressedInvite {
+		sender: sender.to_owned(),
+		invite_state,
+		via,
+		received: millis_since_unix_epoch(),
+	};
+
+	self.db
+		.userroomid_suppressedinvite
+		.put((user_id, room_id), Json(invite));
+}
+
+#[implement(super::Service)]
+pub fn suppressed_invites(
+	&self,
+) -> impl Stream<Item = (OwnedUserId, OwnedRoomId, SuppressedInvite)> + Send + '_ {
+	self.db
+		.userroomid_suppressedinvite
+		.stream()
+		.ignore_err()
+		.map(|((user_id, room_id), invite): ((&UserId, &RoomId), SuppressedInvite)| {
+			(user_id.to_owned(), room_id.to_owned(), invite)
+		})
+}
+
+/// Deliver a suppressed invite to its recipient.
+#[implement(super::Service)]
+pub async fn release_invite(&self, user_id: &UserId, room_id: &RoomId) -> Result {
+	let Ok(invite) = self
+		.db
+		.userroomid_suppressedinvite
+		.qry(&(user_id, room_id))
+		.await
+		.deserialized::<SuppressedInvite>()
+	else {
+		return Err!(Request(NotFound("No suppressed invite for {user_id} to {room_id}.")));
+	};
+
+	self.update_membership(
+		room_id,
+		user_id,
+		RoomMemberEventContent::new(MembershipState::Invite),
+		&invite.sender,
+		Some(invite.invite_state),
+		invite.via,
+		true,
+	)
+	.await?;
+
+	self.discard_invite(user_id, room_id);
+
+	Ok(())
+}
+
+#[implement(super::Service)]
+pub fn discard_invite(&self, user_id: &UserId, room_id: &RoomId) {
+	self.db
+		.userroomid_suppressedinvite
+		.del((user_id, room_id));
+}
@@ -0,0 +1,133 @@
+//! Following room upgrades on behalf of local users.
+
+use std::{collections::BTreeMap, sync::Arc};
+
+use futures::{StreamExt, future::BoxFuture};
+use ruma::{
+	OwnedRoomId, OwnedServerName, OwnedUserId, RoomId, UserId,
+	events::{
+		GlobalAccountDataEventType, RoomAccountDataEventType,
+		room::tombstone::RoomTombstoneEventContent,
+		tag::{TagEvent, TagEventContent, TagInfo, TagName},
+	},
+};
+use serde::Deserialize;
+use tuwunel_core::{Result, debug, implement, warn};
+
+/// Joins a local user to the replacement of an upgraded room. Arguments are
+/// the user, the predecessor room, the replacement room and servers to join
+/// through. Installed by the client API, which owns the join implementation.
+pub type UpgradeHook = dyn Fn(OwnedUserId, OwnedRoomId, OwnedRoomId, Vec<OwnedServerName>) -> BoxFuture<'static, Result>
+	+ Send
+	+ Sync;
+
+/// Global account data by which a user overrides `follow_room_upgrades`.
+pub const FOLLOW_ROOM_UPGRADES: &str = "chat.tuwunel.follow_room_upgrades";
+
+#[derive(Deserialize)]
+struct FollowRoomUpgradesEvent {
+	content: FollowRoomUpgrades,
+}
+
+#[derive(Deserialize)]
+struct FollowRoomUpgrades {
+	enabled: bool,
+}
+
+/// Install or remove the upgrade hook.
+#[implement(super::Service)]
+pub fn set_upgrade_hook(&self, hook: Option<Arc<UpgradeHook>>) {
+	*self
+		.upgrade_hook
+		.write()
+		.expect("locked for writing") = hook;
+}
+
+/// Called when a room is tombstoned; joins the local members who follow
+/// room upgrades to the replacement room in the background.
+#[implement(super::Service)]
+#[tracing::instrument(level = "debug", skip(self, content))]
+pub async fn follow_tombstone(
+	&self,
+	room_id: &RoomId,
+	sender: &UserId,
+	content: &RoomTombstoneEventContent,
+) {
+	let Some(hook) = self
+		.upgrade_hook
+		.read()
+		.expect("locked for reading")
+		.clone()
+	else {
+		return;
+	};
+
+	let replacement = &content.replacement_room;
+	let via: Vec<OwnedServerName> = Some(sender.server_name())
+		.into_iter()
+		.chain(replacement.server_name())
+		.map(ToOwned::to_owned)
+		.collect();
+
+	let users: Vec<OwnedUserId> = self
+		.local_users_in_room(room_id)
+		.map(ToOwned::to_owned)
+		.collect()
+		.await;
+
+	for user_id in users {
+		if !self.follows_room_upgrades(&user_id).await
+			|| self.is_joined(&user_id, replacement).await
+		{
+			continue;
+		}
+
+		debug!(%user_id, %replacement, "Following room upgrade");
+		let join = hook(user_id.clone(), room_id.to_owned(), replacement.clone(), via.clone());
+		self.services.server.runtime().spawn(async move {
+			if let Err(e) = join.await {
+				warn!(%user_id, "Failed to follow room upgrade: {e}");
+			}
+		});
+	}
+}
+
+/// Tag a room `m.lowpriority` for a user, keeping any other tags.
+#[implement(super::Service)]
+pub async fn mark_low_priority(&self, room_id: &RoomId, user_id: &UserId) -> Result {
+	let mut event = self
+		.services
+		.account_data
+		.get_room(room_id, user_id, RoomAccountDataEventType::Tag)
+		.await
+		.unwrap_or_else(|_| TagEvent {
+			content: TagEventContent { tags: BTreeMap::new() },
+		});
+
+	event
+		.content
+		.tags
+		.insert(TagName::LowPriority, TagInfo::new());
+
+	self.services
+		.account_data
+		.update(
+			Some(room_id),
+			user_id,
+			RoomAccountDataEventType::Tag,
+			&serde_json::to_value(event)?,
+		)
+		.await
+}
+
+#[implement(super::Service)]
+async fn follows_room_upgrades(&self, user_id: &UserId) -> bool {
+	self.services
+		.account_data
+		.get_global::<FollowRoomUpgradesEvent>(
+			user_id,
+			GlobalAccountDataEventType::from(FOLLOW_ROOM_UPGRADES),
+		)
+		.await
+		.map_or(self.services.config.follow_room_upgrades, |event| event.content.enabled)
+}
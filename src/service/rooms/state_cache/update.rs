@@ -2,10 +2,9 @@
 
 use futures::StreamExt;
 use ruma::{
-	OwnedServerName, RoomId, UserId,
+	OwnedServerName, OwnedUserId, RoomId, UserId,
 	events::{
-		AnyStrippedStateEvent, AnySyncStateEvent, GlobalAccountDataEventType,
-		RoomAccountDataEventType, StateEventType,
+		AnyStrippedStateEvent, AnySyncStateEvent, GlobalAccountDataEventType, StateEventType,
 		direct::DirectEvent,
 		room::{
 			create::RoomCreateEventContent,
@@ -14,8 +13,12 @@
 	},
 	serde::Raw,
 };
-use tuwunel_core::{Result, implement, is_not_empty, utils::ReadyExt, warn};
-use tuwunel_database::{Json, serialize_key};
+use tuwunel_core::{
+	Result, implement, is_not_empty,
+	utils::{IterStream, ReadyExt, stream::TryIgnore},
+	warn,
+};
+use tuwunel_database::{Batch, Ignore, Interfix, Json, serialize_key};
 
 /// Update current membership data.
 #[implement(super::Service)]
@@ -71,24 +74,11 @@ pub async fn update_membership(
 					.await
 					.map(|content: RoomCreateEventContent| content.predecessor)
 				{
-					// Copy old tags to new room
-					if let Ok(tag_event) = self
-						.services
+					// Copy old tags, fully read marker and other room account data
+					self.services
 						.account_data
-						.get_room(&predecessor.room_id, user_id, RoomAccountDataEventType::Tag)
-						.await
-					{
-						self.services
-							.account_data
-							.update(
-								Some(room_id),
-								user_id,
-								RoomAccountDataEventType::Tag,
-								&tag_event,
-							)
-							.await
-							.ok();
-					}
+						.copy_room(&predecessor.room_id, room_id, user_id)
+						.await;
 
 					// Copy direct chat flag
 					if let Ok(mut direct_event) = self
@@ -243,27 +233,21 @@ pub fn mark_as_joined(&self, user_id: &UserId, room_id: &RoomId) {
 	let roomuser_id = (room_id, user_id);
 	let roomuser_id = serialize_key(roomuser_id).expect("failed to serialize roomuser_id");
 
-	self.db.userroomid_joined.insert(&userroom_id, []);
-	self.db.roomuserid_joined.insert(&roomuser_id, []);
+	let mut batch = Batch::default();
+	batch.insert(&self.db.userroomid_joined, &userroom_id, []);
+	batch.insert(&self.db.roomuserid_joined, &roomuser_id, []);
 
-	self.db
-		.userroomid_invitestate
-		.remove(&userroom_id);
-	self.db
-		.roomuserid_invitecount
-		.remove(&roomuser_id);
+	batch.remove(&self.db.userroomid_invitestate, &userroom_id);
+	batch.remove(&self.db.roomuserid_invitecount, &roomuser_id);
 
-	self.db.userroomid_leftstate.remove(&userroom_id);
-	self.db.roomuserid_leftcount.remove(&roomuser_id);
+	batch.remove(&self.db.userroomid_leftstate, &userroom_id);
+	batch.remove(&self.db.roomuserid_leftcount, &roomuser_id);
 
-	self.db
-		.userroomid_knockedstate
-		.remove(&userroom_id);
-	self.db
-		.roomuserid_knockedcount
-		.remove(&roomuser_id);
+	batch.remove(&self.db.userroomid_knockedstate, &userroom_id);
+	batch.remove(&self.db.roomuserid_knockedcount, &roomuser_id);
 
-	self.db.roomid_inviteviaservers.remove(room_id);
+	batch.remove(&self.db.roomid_inviteviaservers, room_id);
+	batch.commit();
 }
 
 /// Direct DB function to directly mark a user as left. It is not
@@ -281,31 +265,25 @@ pub fn mark_as_left(&self, user_id: &UserId, room_id: &RoomId) {
 	// (timo) TODO
 	let leftstate = Vec::<Raw<AnySyncStateEvent>>::new();
 
-	self.db
-		.userroomid_leftstate
-		.raw_put(&userroom_id, Json(leftstate));
-	self.db
-		.roomuserid_leftcount
-		.raw_aput::<8, _, _>(&roomuser_id, self.services.globals.next_count().unwrap());
+	let mut batch = Batch::default();
+	batch.raw_put(&self.db.userroomid_leftstate, &userroom_id, Json(leftstate));
+	batch.raw_put(
+		&self.db.roomuserid_leftcount,
+		&roomuser_id,
+		self.services.globals.next_count().unwrap(),
+	);
 
-	self.db.userroomid_joined.remove(&userroom_id);
-	self.db.roomuserid_joined.remove(&roomuser_id);
+	batch.remove(&self.db.userroomid_joined, &userroom_id);
+	batch.remove(&self.db.roomuserid_joined, &roomuser_id);
 
-	self.db
-		.userroomid_invitestate
-		.remove(&userroom_id);
-	self.db
-		.roomuserid_invitecount
-		.remove(&roomuser_id);
+	batch.remove(&self.db.userroomid_invitestate, &userroom_id);
+	batch.remove(&self.db.roomuserid_invitecount, &roomuser_id);
 
-	self.db
-		.userroomid_knockedstate
-		.remove(&userroom_id);
-	self.db
-		.roomuserid_knockedcount
-		.remove(&roomuser_id);
+	batch.remove(&self.db.userroomid_knockedstate, &userroom_id);
+	batch.remove(&self.db.roomuserid_knockedcount, &roomuser_id);
 
-	self.db.roomid_inviteviaservers.remove(room_id);
+	batch.remove(&self.db.roomid_inviteviaservers, room_id);
+	batch.commit();
 }
 
 /// Direct DB function to directly mark a user as knocked. It is not
@@ -325,27 +303,29 @@ pub fn mark_as_knocked(
 	let roomuser_id = (room_id, user_id);
 	let roomuser_id = serialize_key(roomuser_id).expect("failed to serialize roomuser_id");
 
-	self.db
-		.userroomid_knockedstate
-		.raw_put(&userroom_id, Json(knocked_state.unwrap_or_default()));
-	self.db
-		.roomuserid_knockedcount
-		.raw_aput::<8, _, _>(&roomuser_id, self.services.globals.next_count().unwrap());
+	let mut batch = Batch::default();
+	batch.raw_put(
+		&self.db.userroomid_knockedstate,
+		&userroom_id,
+		Json(knocked_state.unwrap_or_default()),
+	);
+	batch.raw_put(
+		&self.db.roomuserid_knockedcount,
+		&roomuser_id,
+		self.services.globals.next_count().unwrap(),
+	);
 
-	self.db.userroomid_joined.remove(&userroom_id);
-	self.db.roomuserid_joined.remove(&roomuser_id);
+	batch.remove(&self.db.userroomid_joined, &userroom_id);
+	batch.remove(&self.db.roomuserid_joined, &roomuser_id);
 
-	self.db
-		.userroomid_invitestate
-		.remove(&userroom_id);
-	self.db
-		.roomuserid_invitecount
-		.remove(&roomuser_id);
+	batch.remove(&self.db.userroomid_invitestate, &userroom_id);
+	batch.remove(&self.db.roomuserid_invitecount, &roomuser_id);
 
-	self.db.userroomid_leftstate.remove(&userroom_id);
-	self.db.roomuserid_leftcount.remove(&roomuser_id);
+	batch.remove(&self.db.userroomid_leftstate, &userroom_id);
+	batch.remove(&self.db.roomuserid_leftcount, &roomuser_id);
 
-	self.db.roomid_inviteviaservers.remove(room_id);
+	batch.remove(&self.db.roomid_inviteviaservers, room_id);
+	batch.commit();
 }
 
 /// Makes a user forget a room.
@@ -359,6 +339,82 @@ pub fn forget(&self, room_id: &RoomId, user_id: &UserId) {
 	self.db.roomuserid_leftcount.del(roomuser_id);
 }
 
+/// Remove every membership record of a room for all users and servers, as
+/// part of purging the room from the database. Returns the users who had a
+/// membership, so their other records of the room can be purged too.
+#[implement(super::Service)]
+#[tracing::instrument(level = "debug", skip(self))]
+pub async fn purge_room(&self, room_id: &RoomId) -> Vec<OwnedUserId> {
+	let prefix = (room_id, Interfix);
+	let roomuser_maps = [
+		&self.db.roomuserid_joined,
+		&self.db.roomuserid_invitecount,
+		&self.db.roomuserid_leftcount,
+		&self.db.roomuserid_knockedcount,
+	];
+
+	let mut members: HashSet<OwnedUserId> = roomuser_maps
+		.into_iter()
+		.stream()
+		.flat_map(|map| {
+			map.keys_prefix(&prefix)
+				.ignore_err()
+				.map(|(_, user_id): (Ignore, &UserId)| user_id.to_owned())
+		})
+		.collect()
+		.await;
+
+	self.suppressed_invites()
+		.ready_filter(|(_, invite_room_id, _)| *invite_room_id == room_id)
+		.ready_for_each(|(user_id, ..)| {
+			self.discard_invite(&user_id, room_id);
+			members.insert(user_id);
+		})
+		.await;
+
+	for user_id in &members {
+		let userroom_id = (user_id, room_id);
+		self.db.userroomid_joined.del(userroom_id);
+		self.db.userroomid_invitestate.del(userroom_id);
+		self.db.userroomid_leftstate.del(userroom_id);
+		self.db.userroomid_knockedstate.del(userroom_id);
+		self.db.roomuseroncejoinedids.del(userroom_id);
+	}
+
+	for map in roomuser_maps {
+		map.keys_prefix_raw(&prefix)
+			.ignore_err()
+			.ready_for_each(|key| map.remove(key))
+			.await;
+	}
+
+	let servers: Vec<OwnedServerName> = self
+		.room_servers(room_id)
+		.map(ToOwned::to_owned)
+		.collect()
+		.await;
+
+	for server in &servers {
+		self.db.roomserverids.del((room_id, server));
+		self.db.serverroomids.del((server, room_id));
+	}
+
+	self.db.roomid_joinedcount.remove(room_id);
+	self.db.roomid_invitedcount.remove(room_id);
+	self.db.roomid_inviteviaservers.remove(room_id);
+
+	// update_joined_count() keeps the room's knock count in this map under the
+	// bare room ID, apart from the (room_id, user_id) keys removed above.
+	self.db.roomuserid_knockedcount.remove(room_id);
+
+	self.appservice_in_room_cache
+		.write()
+		.expect("locked")
+		.remove(room_id);
+
+	members.into_iter().collect()
+}
+
 #[implement(super::Service)]
 #[tracing::instrument(level = "debug", skip(self))]
 fn mark_as_once_joined(&self, user_id: &UserId, room_id: &RoomId) {
@@ -381,25 +437,27 @@ pub async fn mark_as_invited(
 	let userroom_id = (user_id, room_id);
 	let userroom_id = serialize_key(userroom_id).expect("failed to serialize userroom_id");
 
-	self.db
-		.userroomid_invitestate
-		.raw_put(&userroom_id, Json(last_state.unwrap_or_default()));
-	self.db
-		.roomuserid_invitecount
-		.raw_aput::<8, _, _>(&roomuser_id, self.services.globals.next_count().unwrap());
+	let mut batch = Batch::default();
+	batch.raw_put(
+		&self.db.userroomid_invitestate,
+		&userroom_id,
+		Json(last_state.unwrap_or_default()),
+	);
+	batch.raw_put(
+		&self.db.roomuserid_invitecount,
+		&roomuser_id,
+		self.services.globals.next_count().unwrap(),
+	);
 
-	self.db.userroomid_joined.remove(&userroom_id);
-	self.db.roomuserid_joined.remove(&roomuser_id);
+	batch.remove(&self.db.userroomid_joined, &userroom_id);
+	batch.remove(&self.db.roomuserid_joined, &roomuser_id);
 
-	self.db.userroomid_leftstate.remove(&userroom_id);
-	self.db.roomuserid_leftcount.remove(&roomuser_id);
+	batch.remove(&self.db.userroomid_leftstate, &userroom_id);
+	batch.remove(&self.db.roomuserid_leftcount, &roomuser_id);
 
-	self.db
-		.userroomid_knockedstate
-		.remove(&userroom_id);
-	self.db
-		.roomuserid_knockedcount
-		.remove(&roomuser_id);
+	batch.remove(&self.db.userroomid_knockedstate, &userroom_id);
+	batch.remove(&self.db.roomuserid_knockedcount, &roomuser_id);
+	batch.commit();
 
 	if let Some(servers) = invite_via.filter(is_not_empty!()) {
 		self.add_servers_invite_via(room_id, servers)
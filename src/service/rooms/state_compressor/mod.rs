@@ -12,8 +12,10 @@
 use tuwunel_core::{
 	Result,
 	arrayvec::ArrayVec,
-	at, checked, err, expected, implement, utils,
-	utils::{bytes, math::usize_from_f64, stream::IterStream},
+	at, checked,
+	config::CacheKind,
+	err, expected, implement, utils,
+	utils::{bytes, stream::IterStream},
 };
 use tuwunel_database::Map;
 
@@ -70,10 +72,9 @@ pub struct HashSetCompressStateEvent {
 impl crate::Service for Service {
 	fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 		let config = &args.server.config;
-		let cache_capacity =
-			f64::from(config.stateinfo_cache_capacity) * config.cache_capacity_modifier;
+		let cache_capacity = config.cache_capacity(CacheKind::StateInfo)?;
 		Ok(Arc::new(Self {
-			stateinfo_cache: LruCache::new(usize_from_f64(cache_capacity)?).into(),
+			stateinfo_cache: LruCache::new(cache_capacity).into(),
 			db: Data {
 				shortstatehash_statediff: args.db["shortstatehash_statediff"].clone(),
 			},
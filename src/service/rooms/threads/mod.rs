@@ -14,7 +14,7 @@
 		stream::{TryIgnore, WidebandExt},
 	},
 };
-use tuwunel_database::{Deserialized, Map};
+use tuwunel_database::{Batch, Deserialized, Map};
 
 use crate::{Dep, rooms, rooms::short::ShortRoomId};
 
@@ -49,7 +49,14 @@ fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
 }
 
 impl Service {
-	pub async fn add_to_thread<E>(&self, root_event_id: &EventId, event: &E) -> Result
+	/// Stage adding the event to the thread: updating the summary bundled with
+	/// the root and the thread's participants.
+	pub async fn add_to_thread<'a, E>(
+		&'a self,
+		batch: &mut Batch<'a>,
+		root_event_id: &EventId,
+		event: &E,
+	) -> Result
 	where
 		E: Event + Send + Sync,
 	{
@@ -119,8 +126,7 @@ pub async fn add_to_thread<E>(&self, root_event_id: &EventId, event: &E) -> Resu
 
 			self.services
 				.timeline
-				.replace_pdu(&root_id, &root_pdu_json)
-				.await?;
+				.stage_replace_pdu(batch, &root_id, &root_pdu_json);
 		}
 
 		let mut users = Vec::new();
@@ -134,7 +140,9 @@ pub async fn add_to_thread<E>(&self, root_event_id: &EventId, event: &E) -> Resu
 		}
 		users.push(event.sender().to_owned());
 
-		self.update_participants(&root_id, &users)
+		self.update_participants(batch, &root_id, &users);
+
+		Ok(())
 	}
 
 	pub async fn threads_until<'a>(
@@ -182,20 +190,19 @@ pub async fn threads_until<'a>(
 		Ok(stream)
 	}
 
-	pub(super) fn update_participants(
-		&self,
+	pub(super) fn update_participants<'a>(
+		&'a self,
+		batch: &mut Batch<'a>,
 		root_id: &RawPduId,
 		participants: &[OwnedUserId],
-	) -> Result {
+	) {
 		let users = participants
 			.iter()
 			.map(|user| user.as_bytes())
 			.collect::<Vec<_>>()
 			.join(&[0xFF][..]);
 
-		self.db.threadid_userids.insert(root_id, &users);
-
-		Ok(())
+		batch.insert(&self.db.threadid_userids, root_id, &users);
 	}
 
 	pub(super) async fn get_participants(&self, root_id: &RawPduId) -> Result<Vec<OwnedUserId>> {
@@ -205,4 +212,15 @@ pub(super) async fn get_participants(&self, root_id: &RawPduId) -> Result<Vec<Ow
 			.await
 			.deserialized()
 	}
+
+	/// Removes the participants of every thread in a room, as part of purging
+	/// the room from the database.
+	pub async fn purge_room(&self, shortroomid: ShortRoomId) {
+		self.db
+			.threadid_userids
+			.keys_prefix_raw(&shortroomid)
+			.ignore_err()
+			.ready_for_each(|key| self.db.threadid_userids.remove(key))
+			.await;
+	}
 }
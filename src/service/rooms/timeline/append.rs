@@ -14,6 +14,7 @@
 			member::{MembershipState, RoomMemberEventContent},
 			power_levels::RoomPowerLevelsEventContent,
 			redaction::RoomRedactionEventContent,
+			tombstone::RoomTombstoneEventContent,
 		},
 	},
 	push::{Action, Ruleset, Tweak},
@@ -24,8 +25,10 @@
 		event::Event,
 		pdu::{PduCount, PduEvent, PduId, RawPduId},
 	},
+	result::LogErr,
 	utils::{self, ReadyExt},
 };
+use tuwunel_database::Batch;
 
 use super::{ExtractBody, ExtractRelatesTo, ExtractRelatesToEventId, RoomMutexGuard};
 use crate::{appservice::NamespaceRegex, rooms::state_compressor::CompressedState};
@@ -158,30 +161,6 @@ pub async fn append_pdu<'a, Leafs>(
 		.set_forward_extremities(pdu.room_id(), leafs, state_lock)
 		.await;
 
-	let insert_lock = self.mutex_insert.lock(pdu.room_id()).await;
-
-	let count1 = self.services.globals.next_count().unwrap();
-
-	// Mark as read first so the sending client doesn't get a notification even if
-	// appending fails
-	self.services
-		.read_receipt
-		.private_read_set(pdu.room_id(), pdu.sender(), count1);
-
-	self.services
-		.user
-		.reset_notification_counts(pdu.sender(), pdu.room_id());
-
-	let count2 = PduCount::Normal(self.services.globals.next_count().unwrap());
-	let pdu_id: RawPduId = PduId { shortroomid, shorteventid: count2 }.into();
-
-	// Insert pdu
-	self.db
-		.append_pdu(&pdu_id, pdu, &pdu_json, count2)
-		.await;
-
-	drop(insert_lock);
-
 	// See if the event matches any known pushers via power level
 	let power_levels: RoomPowerLevelsEventContent = self
 		.services
@@ -201,6 +180,7 @@ pub async fn append_pdu<'a, Leafs>(
 			.collect()
 			.await;
 
+	let mut pushes = Vec::with_capacity(push_target.len().saturating_add(1));
 	let mut notifies = Vec::with_capacity(push_target.len().saturating_add(1));
 	let mut highlights = Vec::with_capacity(push_target.len().saturating_add(1));
 
@@ -220,39 +200,30 @@ pub async fn append_pdu<'a, Leafs>(
 	}
 
 	let serialized = pdu.to_format();
-	for user in &push_target {
+	for user in push_target {
 		let rules_for_user = self
 			.services
 			.account_data
-			.get_global(user, GlobalAccountDataEventType::PushRules)
+			.get_global(&user, GlobalAccountDataEventType::PushRules)
 			.await
 			.map_or_else(
-				|_| Ruleset::server_default(user),
+				|_| Ruleset::server_default(&user),
 				|ev: PushRulesEvent| ev.content.global,
 			);
 
-		let mut highlight = false;
-		let mut notify = false;
-
-		for action in self
+		let actions = self
 			.services
 			.pusher
-			.get_actions(user, &rules_for_user, &power_levels, &serialized, pdu.room_id())
-			.await
-		{
-			match action {
-				| Action::Notify => notify = true,
-				| Action::SetTweak(Tweak::Highlight(true)) => {
-					highlight = true;
-				},
-				| _ => {},
-			}
+			.get_actions(&user, &rules_for_user, &power_levels, &serialized, pdu.room_id())
+			.await;
 
-			// Break early if both conditions are true
-			if notify && highlight {
-				break;
-			}
-		}
+		let notify = actions
+			.iter()
+			.any(|action| matches!(action, Action::Notify));
+
+		let highlight = actions
+			.iter()
+			.any(|action| matches!(action, Action::SetTweak(Tweak::Highlight(true))));
 
 		if notify {
 			notifies.push(user.clone());
@@ -262,20 +233,138 @@ pub async fn append_pdu<'a, Leafs>(
 			highlights.push(user.clone());
 		}
 
+		pushes.push((user, actions, notify));
+	}
+
+	let thread_id = pdu
+		.get_content::<ExtractRelatesTo>()
+		.ok()
+		.and_then(|content| match content.relates_to {
+			| Relation::Thread(thread) => Some(thread.event_id),
+			| _ => None,
+		});
+
+	// Replies don't have event_id as a top level field, so are found apart.
+	let in_reply_to = pdu
+		.get_content::<ExtractRelatesTo>()
+		.ok()
+		.and_then(|content| match content.relates_to {
+			| Relation::Reply { in_reply_to } => Some(in_reply_to.event_id),
+			| _ => None,
+		});
+
+	let mut related = Vec::new();
+	for event_id in pdu
+		.get_content::<ExtractRelatesToEventId>()
+		.ok()
+		.map(|content| content.relates_to.event_id)
+		.into_iter()
+		.chain(in_reply_to)
+	{
+		if let Ok(count) = self.get_pdu_count(&event_id).await {
+			related.push(count);
+		}
+	}
+
+	let body = pdu
+		.get_content::<ExtractBody>()
+		.ok()
+		.and_then(|content| content.body)
+		.filter(|_| *pdu.kind() == TimelineEventType::RoomMessage);
+
+	let insert_lock = self.mutex_insert.lock(pdu.room_id()).await;
+
+	let count1 = self.services.globals.next_count().unwrap();
+
+	// Mark as read first so the sending client doesn't get a notification even if
+	// appending fails
+	self.services
+		.read_receipt
+		.private_read_set(pdu.room_id(), pdu.sender(), count1);
+
+	self.services
+		.user
+		.reset_notification_counts(pdu.sender(), pdu.room_id())
+		.await;
+
+	let count2 = PduCount::Normal(self.services.globals.next_count().unwrap());
+	let pdu_id: RawPduId = PduId { shortroomid, shorteventid: count2 }.into();
+
+	// Insert pdu along with every index of it in one write, so it's never
+	// visible partly indexed.
+	let mut batch = Batch::default();
+	self.db
+		.append_pdu(&mut batch, &pdu_id, pdu, &pdu_json, count2);
+
+	self.db.increment_notification_counts(
+		&mut batch,
+		pdu.room_id(),
+		thread_id.as_deref(),
+		&notifies,
+		&highlights,
+	);
+
+	if let Some(body) = &body {
+		self.services
+			.search
+			.index_pdu(&mut batch, shortroomid, &pdu_id, body);
+	}
+
+	if *pdu.kind() == TimelineEventType::Reaction {
+		self.services
+			.pdu_metadata
+			.add_annotation(&mut batch, pdu);
+	}
+
+	for count in related {
+		self.services
+			.pdu_metadata
+			.add_relation(&mut batch, count2, count)
+			.await;
+	}
+
+	if let Some(thread_id) = &thread_id {
+		self.services
+			.threads
+			.add_to_thread(&mut batch, thread_id, pdu)
+			.await
+			.log_err()
+			.ok();
+	}
+
+	batch.commit();
+
+	self.services
+		.usage
+		.add_pdu(pdu.room_id(), &pdu_json)
+		.await;
+
+	drop(insert_lock);
+
+	for (user, actions, notify) in pushes {
+		if notify {
+			self.services.pusher.add_notification(
+				&user,
+				count2.into_unsigned(),
+				pdu.room_id(),
+				pdu.event_id(),
+				actions,
+			);
+		}
+
 		self.services
 			.pusher
-			.get_pushkeys(user)
+			.get_pushkeys(&user)
 			.ready_for_each(|push_key| {
 				self.services
 					.sending
-					.send_pdu_push(&pdu_id, user, push_key.to_owned())
+					.send_pdu_push(&pdu_id, &user, push_key.to_owned())
 					.expect("TODO: replace with future");
 			})
 			.await;
 	}
 
-	self.db
-		.increment_notification_counts(pdu.room_id(), notifies, highlights);
+	self.schedule_expiry(pdu).await;
 
 	match *pdu.kind() {
 		| TimelineEventType::RoomRedaction => {
@@ -297,6 +386,8 @@ pub async fn append_pdu<'a, Leafs>(
 						{
 							self.redact_pdu(redact_id, pdu, shortroomid)
 								.await?;
+
+							self.db.index_redaction(&pdu_id, redact_id);
 						}
 					}
 				},
@@ -311,11 +402,21 @@ pub async fn append_pdu<'a, Leafs>(
 						{
 							self.redact_pdu(redact_id, pdu, shortroomid)
 								.await?;
+
+							self.db.index_redaction(&pdu_id, redact_id);
 						}
 					}
 				},
 			}
 		},
+		| TimelineEventType::RoomTombstone =>
+			if pdu.state_key() == Some("") {
+				let content: RoomTombstoneEventContent = pdu.get_content()?;
+				self.services
+					.state_cache
+					.follow_tombstone(pdu.room_id(), pdu.sender(), &content)
+					.await;
+			},
 		| TimelineEventType::SpaceChild =>
 			if let Some(_state_key) = pdu.state_key() {
 				self.services
@@ -359,13 +460,8 @@ pub async fn append_pdu<'a, Leafs>(
 					.await?;
 			}
 		},
-		| TimelineEventType::RoomMessage => {
-			let content: ExtractBody = pdu.get_content()?;
-			if let Some(body) = content.body {
-				self.services
-					.search
-					.index_pdu(shortroomid, &pdu_id, &body);
-
+		| TimelineEventType::RoomMessage =>
+			if let Some(body) = body {
 				if self
 					.services
 					.admin
@@ -376,41 +472,8 @@ pub async fn append_pdu<'a, Leafs>(
 						.admin
 						.command(body, Some((pdu.event_id()).into()))?;
 				}
-			}
-		},
-		| _ => {},
-	}
-
-	if let Ok(content) = pdu.get_content::<ExtractRelatesToEventId>() {
-		if let Ok(related_pducount) = self
-			.get_pdu_count(&content.relates_to.event_id)
-			.await
-		{
-			self.services
-				.pdu_metadata
-				.add_relation(count2, related_pducount);
-		}
-	}
-
-	if let Ok(content) = pdu.get_content::<ExtractRelatesTo>() {
-		match content.relates_to {
-			| Relation::Reply { in_reply_to } => {
-				// We need to do it again here, because replies don't have
-				// event_id as a top level field
-				if let Ok(related_pducount) = self.get_pdu_count(&in_reply_to.event_id).await {
-					self.services
-						.pdu_metadata
-						.add_relation(count2, related_pducount);
-				}
-			},
-			| Relation::Thread(thread) => {
-				self.services
-					.threads
-					.add_to_thread(&thread.event_id, pdu)
-					.await?;
 			},
-			| _ => {}, // TODO: Aggregate other types
-		}
+		| _ => {},
 	}
 
 	for appservice in self.services.appservice.read().await.values() {
@@ -19,6 +19,7 @@
 	utils::{IterStream, ReadyExt},
 	validated, warn,
 };
+use tuwunel_database::Batch;
 
 use super::ExtractBody;
 
@@ -196,19 +197,21 @@ pub async fn backfill_pdu(&self, origin: &ServerName, pdu: Box<RawJsonValue>) ->
 	.into();
 
 	// Insert pdu
+	let mut batch = Batch::default();
 	self.db
-		.prepend_backfill_pdu(&pdu_id, &event_id, &value);
-
-	drop(insert_lock);
+		.prepend_backfill_pdu(&mut batch, &pdu_id, &event_id, &value);
 
 	if pdu.kind == TimelineEventType::RoomMessage {
 		let content: ExtractBody = pdu.get_content()?;
 		if let Some(body) = content.body {
 			self.services
 				.search
-				.index_pdu(shortroomid, &pdu_id, &body);
+				.index_pdu(&mut batch, shortroomid, &pdu_id, &body);
 		}
 	}
+
+	batch.commit();
+	drop(insert_lock);
 	drop(mutex_lock);
 
 	debug!("Prepended backfill pdu");
@@ -132,6 +132,15 @@ pub async fn build_and_append_pdu(
 		.state
 		.set_room_state(pdu.room_id(), statehashid, state_lock);
 
+	if !self
+		.services
+		.state_accessor
+		.is_federated(pdu.room_id())
+		.await
+	{
+		return Ok(pdu.event_id().to_owned());
+	}
+
 	let mut servers: HashSet<OwnedServerName> = self
 		.services
 		.state_cache
@@ -1,24 +1,37 @@
 use std::{borrow::Borrow, sync::Arc};
 
-use futures::{FutureExt, Stream, TryFutureExt, TryStreamExt, future::select_ok, pin_mut};
-use ruma::{CanonicalJsonObject, EventId, OwnedUserId, RoomId, UserId, api::Direction};
+use futures::{
+	FutureExt, Stream, StreamExt, TryFutureExt, TryStreamExt, future::select_ok, pin_mut,
+};
+use ruma::{
+	CanonicalJsonObject, EventId, OwnedEventId, OwnedUserId, RoomId, UserId, api::Direction,
+};
 use tuwunel_core::{
 	Err, PduCount, PduEvent, Result, at, err,
 	result::{LogErr, NotFound},
-	utils,
-	utils::stream::TryReadyExt,
+	utils::{
+		ReadyExt,
+		stream::{TryIgnore, TryReadyExt},
+	},
 };
-use tuwunel_database::{Database, Deserialized, Json, KeyVal, Map};
+use tuwunel_database::{Batch, Database, Deserialized, Json, KeyVal, Map};
 
-use super::{PduId, RawPduId};
+use super::{ExtractEventId, PduId, RawPduId};
 use crate::{Dep, rooms, rooms::short::ShortRoomId};
 
 pub(super) struct Data {
+	eventid_expiresat: Arc<Map>,
 	eventid_outlierpdu: Arc<Map>,
 	eventid_pduid: Arc<Map>,
+	eventid_unredactedpdu: Arc<Map>,
+	expiresat_eventid: Arc<Map>,
 	pduid_pdu: Arc<Map>,
+	pduid_redactedeventid: Arc<Map>,
+	redactedexpiresat_eventid: Arc<Map>,
 	userroomid_highlightcount: Arc<Map>,
 	userroomid_notificationcount: Arc<Map>,
+	userroomthreadid_highlightcount: Arc<Map>,
+	userroomthreadid_notificationcount: Arc<Map>,
 	pub(super) db: Arc<Database>,
 	services: Services,
 }
@@ -33,11 +46,18 @@ impl Data {
 	pub(super) fn new(args: &crate::Args<'_>) -> Self {
 		let db = &args.db;
 		Self {
+			eventid_expiresat: db["eventid_expiresat"].clone(),
 			eventid_outlierpdu: db["eventid_outlierpdu"].clone(),
 			eventid_pduid: db["eventid_pduid"].clone(),
+			eventid_unredactedpdu: db["eventid_unredactedpdu"].clone(),
+			expiresat_eventid: db["expiresat_eventid"].clone(),
 			pduid_pdu: db["pduid_pdu"].clone(),
+			pduid_redactedeventid: db["pduid_redactedeventid"].clone(),
+			redactedexpiresat_eventid: db["redactedexpiresat_eventid"].clone(),
 			userroomid_highlightcount: db["userroomid_highlightcount"].clone(),
 			userroomid_notificationcount: db["userroomid_notificationcount"].clone(),
+			userroomthreadid_highlightcount: db["userroomthreadid_highlightcount"].clone(),
+			userroomthreadid_notificationcount: db["userroomthreadid_notificationcount"].clone(),
 			db: args.db.clone(),
 			services: Services {
 				short: args.depend::<rooms::short::Service>("rooms::short"),
@@ -99,6 +119,14 @@ pub(super) async fn get_pdu_json(&self, event_id: &EventId) -> Result<CanonicalJ
 		select_ok([accepted, outlier]).await.map(at!(0))
 	}
 
+	/// Returns an outlier pdu.
+	pub(super) async fn get_outlier_pdu(&self, event_id: &EventId) -> Result<PduEvent> {
+		self.eventid_outlierpdu
+			.get(event_id)
+			.await
+			.deserialized()
+	}
+
 	/// Returns the json of a pdu.
 	pub(super) async fn get_non_outlier_pdu_json(
 		&self,
@@ -179,8 +207,10 @@ pub(super) async fn get_pdu_json_from_id(
 		self.pduid_pdu.get(pdu_id).await.deserialized()
 	}
 
-	pub(super) async fn append_pdu(
-		&self,
+	/// Stage the event in the timeline.
+	pub(super) fn append_pdu<'a>(
+		&'a self,
+		batch: &mut Batch<'a>,
 		pdu_id: &RawPduId,
 		pdu: &PduEvent,
 		json: &CanonicalJsonObject,
@@ -188,22 +218,182 @@ pub(super) async fn append_pdu(
 	) {
 		debug_assert!(matches!(count, PduCount::Normal(_)), "PduCount not Normal");
 
-		self.pduid_pdu.raw_put(pdu_id, Json(json));
-		self.eventid_pduid
-			.insert(pdu.event_id.as_bytes(), pdu_id);
-		self.eventid_outlierpdu
-			.remove(pdu.event_id.as_bytes());
+		batch.raw_put(&self.pduid_pdu, pdu_id, Json(json));
+		batch.insert(&self.eventid_pduid, pdu.event_id.as_bytes(), pdu_id);
+		batch.remove(&self.eventid_outlierpdu, pdu.event_id.as_bytes());
 	}
 
-	pub(super) fn prepend_backfill_pdu(
-		&self,
+	/// Stage the backfilled event in the timeline.
+	pub(super) fn prepend_backfill_pdu<'a>(
+		&'a self,
+		batch: &mut Batch<'a>,
 		pdu_id: &RawPduId,
 		event_id: &EventId,
 		json: &CanonicalJsonObject,
 	) {
-		self.pduid_pdu.raw_put(pdu_id, Json(json));
-		self.eventid_pduid.insert(event_id, pdu_id);
-		self.eventid_outlierpdu.remove(event_id);
+		batch.raw_put(&self.pduid_pdu, pdu_id, Json(json));
+		batch.insert(&self.eventid_pduid, event_id, pdu_id);
+		batch.remove(&self.eventid_outlierpdu, event_id);
+	}
+
+	/// Removes every timeline event of a room along with its event ID
+	/// mappings, expiry and retained original content. Returns the count and
+	/// ID of each event removed.
+	pub(super) async fn purge_pdus(
+		&self,
+		shortroomid: ShortRoomId,
+	) -> Vec<(PduCount, Option<OwnedEventId>)> {
+		let events: Vec<(RawPduId, Option<OwnedEventId>)> = self
+			.pduid_pdu
+			.stream_prefix_raw(&shortroomid)
+			.ignore_err()
+			.map(|(pdu_id, pdu)| {
+				let event_id = serde_json::from_slice::<ExtractEventId>(pdu)
+					.ok()
+					.map(|pdu| pdu.event_id);
+
+				(pdu_id.into(), event_id)
+			})
+			.collect()
+			.await;
+
+		self.pduid_redactedeventid
+			.keys_prefix_raw(&shortroomid)
+			.ignore_err()
+			.ready_for_each(|key| self.pduid_redactedeventid.remove(key))
+			.await;
+
+		for (pdu_id, event_id) in &events {
+			self.pduid_pdu.remove(pdu_id);
+			if let Some(event_id) = event_id {
+				self.eventid_pduid.remove(event_id);
+				self.eventid_outlierpdu.remove(event_id);
+				self.eventid_unredactedpdu.remove(event_id);
+				if let Ok(expires_at) = self.get_expiry(event_id).await {
+					self.expiresat_eventid.del((expires_at, event_id));
+					self.eventid_expiresat.remove(event_id);
+				}
+			}
+		}
+
+		events
+			.into_iter()
+			.map(|(pdu_id, event_id)| (pdu_id.pdu_count(), event_id))
+			.collect()
+	}
+
+	pub(super) fn index_redaction(&self, pdu_id: &RawPduId, redacts: &EventId) {
+		self.pduid_redactedeventid
+			.insert(pdu_id, redacts.as_bytes());
+	}
+
+	/// Returns the redactions of a room with the events they redacted, oldest
+	/// first.
+	pub(super) fn redactions(
+		&self,
+		shortroomid: ShortRoomId,
+	) -> impl Stream<Item = (RawPduId, OwnedEventId)> + Send + '_ {
+		self.pduid_redactedeventid
+			.stream_prefix_raw(&shortroomid)
+			.ignore_err()
+			.ready_filter_map(|(pdu_id, redacts)| {
+				let redacts = EventId::parse(std::str::from_utf8(redacts).ok()?).ok()?;
+				Some((RawPduId::from(pdu_id), redacts))
+			})
+	}
+
+	/// Overwrites the stored outlier pdu.
+	pub(super) fn replace_outlier_pdu(&self, event_id: &EventId, pdu_json: &CanonicalJsonObject) {
+		self.eventid_outlierpdu
+			.raw_put(event_id, Json(pdu_json));
+	}
+
+	/// Keeps the original json of a redacted pdu until `expires_at`
+	/// (milliseconds since the epoch).
+	pub(super) fn retain_unredacted(
+		&self,
+		event_id: &EventId,
+		pdu_json: &CanonicalJsonObject,
+		expires_at: u64,
+	) {
+		self.eventid_unredactedpdu
+			.raw_put(event_id, Json(pdu_json));
+		self.redactedexpiresat_eventid
+			.put_raw((expires_at, event_id), []);
+	}
+
+	/// Returns the original json of a redacted pdu while it is retained.
+	pub(super) async fn get_unredacted_pdu_json(
+		&self,
+		event_id: &EventId,
+	) -> Result<CanonicalJsonObject> {
+		self.eventid_unredactedpdu
+			.get(event_id)
+			.await
+			.deserialized()
+	}
+
+	/// Deletes the original json of redacted pdus retained until `until`
+	/// (milliseconds since the epoch) or earlier. Returns the number deleted.
+	pub(super) async fn purge_unredacted(&self, until: u64) -> usize {
+		let expired: Vec<(u64, OwnedEventId)> = self
+			.redactedexpiresat_eventid
+			.keys()
+			.ignore_err()
+			.ready_take_while(|&(expires_at, _): &(u64, &EventId)| expires_at <= until)
+			.map(|(expires_at, event_id)| (expires_at, event_id.to_owned()))
+			.collect()
+			.await;
+
+		for (expires_at, event_id) in &expired {
+			self.eventid_unredactedpdu.remove(event_id);
+			self.redactedexpiresat_eventid
+				.del((expires_at, event_id));
+		}
+
+		expired.len()
+	}
+
+	/// Records when a self-destructing pdu expires (milliseconds since the
+	/// epoch).
+	pub(super) fn set_expiry(&self, event_id: &EventId, expires_at: u64) {
+		self.eventid_expiresat
+			.raw_put(event_id, expires_at);
+		self.expiresat_eventid
+			.put_raw((expires_at, event_id), []);
+	}
+
+	/// Returns when a self-destructing pdu expires.
+	pub(super) async fn get_expiry(&self, event_id: &EventId) -> Result<u64> {
+		self.eventid_expiresat
+			.get(event_id)
+			.await
+			.deserialized()
+	}
+
+	/// Forgets when a self-destructing pdu expires, once it has been redacted.
+	pub(super) fn delete_expiry(&self, event_id: &EventId) {
+		self.eventid_expiresat.remove(event_id);
+	}
+
+	/// Takes the pdus which expire at `until` (milliseconds since the epoch)
+	/// or earlier from the schedule, with their expiry times. The times are
+	/// kept until deleted with `delete_expiry`.
+	pub(super) async fn take_expired(&self, until: u64) -> Vec<(u64, OwnedEventId)> {
+		let expired: Vec<(u64, OwnedEventId)> = self
+			.expiresat_eventid
+			.keys()
+			.ignore_err()
+			.ready_take_while(|&(expires_at, _): &(u64, &EventId)| expires_at <= until)
+			.map(|(expires_at, event_id)| (expires_at, event_id.to_owned()))
+			.collect()
+			.await;
+
+		for (expires_at, event_id) in &expired {
+			self.expiresat_eventid.del((expires_at, event_id));
+		}
+
+		expired
 	}
 
 	/// Removes a pdu and creates a new one with the same id.
@@ -221,6 +411,16 @@ pub(super) async fn replace_pdu(
 		Ok(())
 	}
 
+	/// Stage replacing a pdu, keeping its id.
+	pub(super) fn stage_replace_pdu<'a>(
+		&'a self,
+		batch: &mut Batch<'a>,
+		pdu_id: &RawPduId,
+		pdu_json: &CanonicalJsonObject,
+	) {
+		batch.raw_put(&self.pduid_pdu, pdu_id, Json(pdu_json));
+	}
+
 	/// Returns an iterator over all events and their tokens in a room that
 	/// happened before the event with id `until` in reverse-chronological
 	/// order.
@@ -272,26 +472,46 @@ fn each_pdu((pdu_id, pdu): KeyVal<'_>, user_id: Option<&UserId>) -> Result<PdusI
 		Ok((pdu_id.pdu_count(), pdu))
 	}
 
-	pub(super) fn increment_notification_counts(
-		&self,
+	/// Stage incrementing the counts of the users notified of an event. Counts
+	/// of events in a thread are also kept for the thread, under the thread
+	/// root.
+	///
+	/// The counts are read when staged and written on commit, so the caller
+	/// holds the room's insert lock until the batch is committed. Receipts
+	/// reset the counts under the same lock, so a reset is never undone by an
+	/// append.
+	pub(super) fn increment_notification_counts<'a>(
+		&'a self,
+		batch: &mut Batch<'a>,
 		room_id: &RoomId,
-		notifies: Vec<OwnedUserId>,
-		highlights: Vec<OwnedUserId>,
+		thread_id: Option<&EventId>,
+		notifies: &[OwnedUserId],
+		highlights: &[OwnedUserId],
 	) {
-		let _cork = self.db.cork();
-
 		for user in notifies {
 			let mut userroom_id = user.as_bytes().to_vec();
 			userroom_id.push(0xFF);
 			userroom_id.extend_from_slice(room_id.as_bytes());
-			increment(&self.userroomid_notificationcount, &userroom_id);
+			batch.increment(&self.userroomid_notificationcount, &userroom_id);
+
+			if let Some(thread_id) = thread_id {
+				userroom_id.push(0xFF);
+				userroom_id.extend_from_slice(thread_id.as_bytes());
+				batch.increment(&self.userroomthreadid_notificationcount, &userroom_id);
+			}
 		}
 
 		for user in highlights {
 			let mut userroom_id = user.as_bytes().to_vec();
 			userroom_id.push(0xFF);
 			userroom_id.extend_from_slice(room_id.as_bytes());
-			increment(&self.userroomid_highlightcount, &userroom_id);
+			batch.increment(&self.userroomid_highlightcount, &userroom_id);
+
+			if let Some(thread_id) = thread_id {
+				userroom_id.push(0xFF);
+				userroom_id.extend_from_slice(thread_id.as_bytes());
+				batch.increment(&self.userroomthreadid_highlightcount, &userroom_id);
+			}
 		}
 	}
 
@@ -317,10 +537,3 @@ async fn count_to_id(
 		Ok(pdu_id.into())
 	}
 }
-
-//TODO: this is an ABA
-fn increment(db: &Arc<Map>, key: &[u8]) {
-	let old = db.get_blocking(key);
-	let new = utils::increment(old.ok().as_deref());
-	db.insert(key, new);
-}
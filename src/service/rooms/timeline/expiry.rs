@@ -0,0 +1,131 @@
+//! Self-destructing events (MSC2228); see `max_event_ttl`.
+
+use std::num::Saturating as Sat;
+
+use ruma::{EventId, RoomId};
+use serde::Deserialize;
+use serde_json::json;
+use tuwunel_core::{
+	Result, debug_warn, implement,
+	matrix::{event::Event, pdu::PduEvent},
+	utils,
+};
+
+/// State event by which a room opts in to self-destructing events.
+const SELF_DESTRUCT_EVENT_TYPE: &str = "im.tuwunel.self_destruct";
+
+#[derive(Deserialize)]
+struct SelfDestructContent {
+	#[serde(default)]
+	enabled: bool,
+}
+
+#[derive(Deserialize)]
+struct ExtractSelfDestructAfter {
+	/// When the event expires, in milliseconds since the epoch.
+	#[serde(rename = "org.matrix.self_destruct_after")]
+	self_destruct_after: u64,
+}
+
+/// Schedule the expiry of a self-destructing event, when its room has opted
+/// in, no later than `max_event_ttl` after it was sent. State events never
+/// expire.
+#[implement(super::Service)]
+pub(super) async fn schedule_expiry(&self, pdu: &PduEvent) {
+	let max_ttl = self.services.server.config.max_event_ttl;
+	if max_ttl == 0 || pdu.state_key().is_some() {
+		return;
+	}
+
+	let Ok(content) = pdu.get_content::<ExtractSelfDestructAfter>() else {
+		return;
+	};
+
+	if !self.self_destruct_enabled(pdu.room_id()).await {
+		return;
+	}
+
+	let expires_at = expiry(pdu.origin_server_ts.into(), content.self_destruct_after, max_ttl);
+	self.db.set_expiry(pdu.event_id(), expires_at);
+}
+
+/// Whether the room has opted in to self-destructing events.
+#[implement(super::Service)]
+pub async fn self_destruct_enabled(&self, room_id: &RoomId) -> bool {
+	self.services
+		.state_accessor
+		.room_state_get_content(room_id, &SELF_DESTRUCT_EVENT_TYPE.into(), "")
+		.await
+		.is_ok_and(|content: SelfDestructContent| content.enabled)
+}
+
+/// Whether the event has self-destructed, even if the expiry worker has not
+/// yet redacted it.
+#[implement(super::Service)]
+pub async fn is_expired(&self, event_id: &EventId) -> bool {
+	if self.services.server.config.max_event_ttl == 0 {
+		return false;
+	}
+
+	self.db
+		.get_expiry(event_id)
+		.await
+		.is_ok_and(|expires_at| expires_at <= utils::millis_since_unix_epoch())
+}
+
+/// Redact the self-destructing events which have expired, without retaining
+/// their content. Returns the number redacted.
+#[implement(super::Service)]
+pub async fn expire_pdus(&self) -> usize {
+	let expired = self
+		.db
+		.take_expired(utils::millis_since_unix_epoch())
+		.await;
+
+	let mut count: usize = 0;
+	for (expires_at, event_id) in &expired {
+		match self.expire_pdu(event_id, *expires_at).await {
+			| Ok(()) => count = count.saturating_add(1),
+			| Err(e) => debug_warn!(%event_id, "Failed to redact expired event: {e}"),
+		}
+
+		self.db.delete_expiry(event_id);
+	}
+
+	count
+}
+
+#[implement(super::Service)]
+async fn expire_pdu(&self, event_id: &EventId, expires_at: u64) -> Result {
+	let pdu = self.get_pdu(event_id).await?;
+	let shortroomid = self
+		.services
+		.short
+		.get_shortroomid(pdu.room_id())
+		.await?;
+
+	// Clients are shown a redaction by the sender at the time of expiry.
+	let reason = json!({
+		"type": "m.room.redaction",
+		"room_id": pdu.room_id(),
+		"sender": pdu.sender(),
+		"origin_server_ts": expires_at,
+		"redacts": event_id,
+		"content": {
+			"redacts": event_id,
+			"reason": "Self-destructed",
+		},
+	});
+
+	self.redact_pdu_because(event_id, reason, shortroomid, false)
+		.await
+}
+
+/// When an event sent at `origin_server_ts` asking to self-destruct at
+/// `self_destruct_after` expires, no later than `max_ttl` seconds after it was
+/// sent. Both times are in milliseconds since the epoch.
+pub(super) fn expiry(origin_server_ts: u64, self_destruct_after: u64, max_ttl: u64) -> u64 {
+	let latest = Sat(origin_server_ts) + Sat(max_ttl) * Sat(1000);
+
+	self_destruct_after.min(latest.0)
+}
@@ -3,9 +3,17 @@
 mod build;
 mod create;
 mod data;
+mod expiry;
+mod purge;
 mod redact;
+#[cfg(test)]
+mod tests;
 
-use std::{fmt::Write, sync::Arc};
+use std::{
+	fmt::Write,
+	sync::Arc,
+	time::{Duration, Instant},
+};
 
 use async_trait::async_trait;
 use futures::{Future, Stream, TryStreamExt, pin_mut};
@@ -14,9 +22,10 @@
 	events::room::encrypted::Relation,
 };
 use serde::Deserialize;
+use tokio::time::sleep;
 pub use tuwunel_core::matrix::pdu::{PduId, RawPduId};
 use tuwunel_core::{
-	Result, Server, at, err,
+	Result, Server, at, debug, err,
 	matrix::{
 		event::Event,
 		pdu::{PduCount, PduEvent},
@@ -24,6 +33,7 @@
 	utils::{MutexMap, MutexMapGuard, future::TryExtExt, stream::TryIgnore},
 	warn,
 };
+use tuwunel_database::Batch;
 
 use self::data::Data;
 pub use self::data::PdusIterItem;
@@ -65,6 +75,7 @@ struct Services {
 	appservice: Dep<appservice::Service>,
 	admin: Dep<admin::Service>,
 	alias: Dep<rooms::alias::Service>,
+	directory: Dep<rooms::directory::Service>,
 	globals: Dep<globals::Service>,
 	short: Dep<rooms::short::Service>,
 	state: Dep<rooms::state::Service>,
@@ -81,8 +92,15 @@ struct Services {
 	search: Dep<rooms::search::Service>,
 	spaces: Dep<rooms::spaces::Service>,
 	event_handler: Dep<rooms::event_handler::Service>,
+	usage: Dep<rooms::usage::Service>,
 }
 
+/// How often retained content of redacted events is checked for expiry.
+const REDACTION_PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// How often self-destructing events are checked for expiry.
+const EVENT_EXPIRY_INTERVAL: Duration = Duration::from_secs(60);
+
 type RoomMutexMap = MutexMap<OwnedRoomId, ()>;
 pub type RoomMutexGuard = MutexMapGuard<OwnedRoomId, ()>;
 
@@ -96,6 +114,7 @@ fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 				appservice: args.depend::<appservice::Service>("appservice"),
 				admin: args.depend::<admin::Service>("admin"),
 				alias: args.depend::<rooms::alias::Service>("rooms::alias"),
+				directory: args.depend::<rooms::directory::Service>("rooms::directory"),
 				globals: args.depend::<globals::Service>("globals"),
 				short: args.depend::<rooms::short::Service>("rooms::short"),
 				state: args.depend::<rooms::state::Service>("rooms::state"),
@@ -114,12 +133,43 @@ fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 				spaces: args.depend::<rooms::spaces::Service>("rooms::spaces"),
 				event_handler: args
 					.depend::<rooms::event_handler::Service>("rooms::event_handler"),
+				usage: args.depend::<rooms::usage::Service>("rooms::usage"),
 			},
 			db: Data::new(&args),
 			mutex_insert: RoomMutexMap::new(),
 		}))
 	}
 
+	async fn worker(self: Arc<Self>) -> Result {
+		if self.services.globals.is_read_only() {
+			return Ok(());
+		}
+
+		let mut last_purge: Option<Instant> = None;
+		while self.services.server.running() {
+			if last_purge.is_none_or(|last| last.elapsed() >= REDACTION_PURGE_INTERVAL) {
+				let purged = self.purge_unredacted().await;
+				if purged > 0 {
+					debug!("Deleted the retained content of {purged} redacted events.");
+				}
+
+				last_purge = Some(Instant::now());
+			}
+
+			let expired = self.expire_pdus().await;
+			if expired > 0 {
+				debug!("Redacted {expired} expired self-destructing events.");
+			}
+
+			tokio::select! {
+				() = sleep(EVENT_EXPIRY_INTERVAL) => {},
+				() = self.services.server.until_shutdown() => break,
+			}
+		}
+
+		Ok(())
+	}
+
 	async fn memory_usage(&self, out: &mut (dyn Write + Send)) -> Result {
 		let mutex_insert = self.mutex_insert.len();
 		writeln!(out, "insert_mutex: {mutex_insert}")?;
@@ -234,6 +284,17 @@ pub async fn replace_pdu(&self, pdu_id: &RawPduId, pdu_json: &CanonicalJsonObjec
 		self.db.replace_pdu(pdu_id, pdu_json).await
 	}
 
+	/// Stage replacing an existing pdu, keeping its id.
+	#[tracing::instrument(skip(self, batch), level = "debug")]
+	pub fn stage_replace_pdu<'a>(
+		&'a self,
+		batch: &mut Batch<'a>,
+		pdu_id: &RawPduId,
+		pdu_json: &CanonicalJsonObject,
+	) {
+		self.db.stage_replace_pdu(batch, pdu_id, pdu_json);
+	}
+
 	/// Returns an iterator over all PDUs in a room. Unknown rooms produce no
 	/// items.
 	#[inline]
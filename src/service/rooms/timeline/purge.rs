@@ -0,0 +1,99 @@
+use futures::StreamExt;
+use ruma::{OwnedRoomAliasId, RoomId};
+use tuwunel_core::{Err, Result, implement, info};
+
+/// Delete a room without local members from the database: its timeline and
+/// the indexes of its events, search index, current state, memberships,
+/// receipts, room account data, local aliases and directory listing. State
+/// snapshots and short IDs, which may be shared with other rooms, are kept.
+/// Returns the number of events removed.
+#[implement(super::Service)]
+#[tracing::instrument(skip(self), level = "debug")]
+pub async fn purge_room(&self, room_id: &RoomId) -> Result<usize> {
+	let shortroomid = self
+		.services
+		.short
+		.get_shortroomid(room_id)
+		.await?;
+
+	let state_lock = self.services.state.mutex.lock(room_id).await;
+	let insert_lock = self.mutex_insert.lock(room_id).await;
+
+	// One of our users may have joined since the caller looked.
+	if self
+		.services
+		.state_cache
+		.has_local_members(room_id)
+		.await
+	{
+		return Err!(Request(Forbidden("Room {room_id} has local members.")));
+	}
+
+	let aliases: Vec<OwnedRoomAliasId> = self
+		.services
+		.alias
+		.local_aliases_for_room(room_id)
+		.map(ToOwned::to_owned)
+		.collect()
+		.await;
+
+	for alias in &aliases {
+		self.services
+			.alias
+			.remove_alias(alias, &self.services.globals.server_user)
+			.await?;
+	}
+
+	self.services.directory.set_not_public(room_id);
+
+	let events = self.db.purge_pdus(shortroomid).await;
+	for (count, event_id) in &events {
+		if let Some(event_id) = event_id {
+			self.services
+				.pdu_metadata
+				.purge_event(*count, event_id)
+				.await;
+		}
+	}
+
+	self.services
+		.pdu_metadata
+		.purge_room(room_id)
+		.await;
+	self.services
+		.threads
+		.purge_room(shortroomid)
+		.await;
+	self.services.search.purge_room(shortroomid).await;
+	self.services
+		.state
+		.delete_room_state(room_id, &state_lock)
+		.await;
+
+	let members = self
+		.services
+		.state_cache
+		.purge_room(room_id)
+		.await;
+	self.services
+		.user
+		.purge_room(room_id, shortroomid, &members)
+		.await;
+	self.services
+		.read_receipt
+		.purge_room(room_id)
+		.await;
+	self.services
+		.account_data
+		.purge_room(room_id)
+		.await;
+	self.services.usage.forget(room_id);
+
+	drop(insert_lock);
+	drop(state_lock);
+
+	let count = events.len();
+	info!(%room_id, "Purged {count} events of room");
+
+	Ok(count)
+}
@@ -1,8 +1,12 @@
-use ruma::EventId;
+use std::num::Saturating as Sat;
+
+use futures::{Stream, StreamExt, TryFutureExt};
+use ruma::{CanonicalJsonObject, EventId, OwnedEventId, RoomId};
+use serde_json::Value as JsonValue;
 use tuwunel_core::{
 	Result, err, implement,
-	matrix::event::Event,
-	utils::{self},
+	matrix::{event::Event, pdu::PduEvent},
+	utils::{self, stream::TryIgnore},
 };
 
 use super::ExtractBody;
@@ -16,11 +20,27 @@ pub async fn redact_pdu<Pdu: Event + Send + Sync>(
 	event_id: &EventId,
 	reason: &Pdu,
 	shortroomid: ShortRoomId,
+) -> Result {
+	self.redact_pdu_because(event_id, reason.to_value(), shortroomid, true)
+		.await
+}
+
+/// Replace a PDU with the redacted form, with `reason` as the event which
+/// redacted it. The original content is kept for review only when `retain`.
+#[implement(super::Service)]
+pub(super) async fn redact_pdu_because(
+	&self,
+	event_id: &EventId,
+	reason: JsonValue,
+	shortroomid: ShortRoomId,
+	retain: bool,
 ) -> Result {
 	// TODO: Don't reserialize, keep original json
 	let Ok(pdu_id) = self.get_pdu_id(event_id).await else {
-		// If event does not exist, just noop
-		return Ok(());
+		// Not in the timeline; an outlier copy may still hold the content.
+		return self
+			.redact_outlier_pdu(event_id, reason, retain)
+			.await;
 	};
 
 	let mut pdu = self
@@ -45,7 +65,12 @@ pub async fn redact_pdu<Pdu: Event + Send + Sync>(
 		.get_room_version(pdu.room_id())
 		.await?;
 
-	pdu.redact(&room_version_id, reason.to_value())?;
+	if retain {
+		self.retain_unredacted(&pdu)?;
+	}
+
+	self.services.pdu_metadata.remove_annotation(&pdu);
+	pdu.redact(&room_version_id, reason)?;
 
 	let obj = utils::to_canonical_object(&pdu).map_err(|e| {
 		err!(Database(error!(?event_id, ?e, "Failed to convert PDU to canonical JSON")))
@@ -53,3 +78,98 @@ pub async fn redact_pdu<Pdu: Event + Send + Sync>(
 
 	self.replace_pdu(&pdu_id, &obj).await
 }
+
+/// Replace an outlier PDU with the redacted form. Does nothing when no
+/// outlier is stored for the event.
+#[implement(super::Service)]
+async fn redact_outlier_pdu(
+	&self,
+	event_id: &EventId,
+	reason: JsonValue,
+	retain: bool,
+) -> Result {
+	let Ok(mut pdu) = self.db.get_outlier_pdu(event_id).await else {
+		return Ok(());
+	};
+
+	let room_version_id = self
+		.services
+		.state
+		.get_room_version(pdu.room_id())
+		.await?;
+
+	if retain {
+		self.retain_unredacted(&pdu)?;
+	}
+
+	pdu.redact(&room_version_id, reason)?;
+
+	let obj = utils::to_canonical_object(&pdu).map_err(|e| {
+		err!(Database(error!(?event_id, ?e, "Failed to convert PDU to canonical JSON")))
+	})?;
+
+	self.db.replace_outlier_pdu(event_id, &obj);
+
+	Ok(())
+}
+
+/// Keep the original content of a PDU about to be redacted for
+/// `redaction_retention_period`, after which the retention worker deletes it.
+/// A PDU which is already redacted has nothing left to keep, and must not
+/// replace the original retained by its first redaction.
+#[implement(super::Service)]
+fn retain_unredacted(&self, pdu: &PduEvent) -> Result {
+	let period = self
+		.services
+		.server
+		.config
+		.redaction_retention_period;
+
+	if period == 0 || pdu.is_redacted() {
+		return Ok(());
+	}
+
+	let obj = utils::to_canonical_object(pdu)?;
+	let expires_at = Sat(utils::millis_since_unix_epoch()) + Sat(period) * Sat(1000);
+	self.db
+		.retain_unredacted(pdu.event_id(), &obj, expires_at.0);
+
+	Ok(())
+}
+
+/// Original json of a redacted PDU, while it is still retained.
+#[implement(super::Service)]
+pub async fn get_unredacted_pdu_json(&self, event_id: &EventId) -> Result<CanonicalJsonObject> {
+	self.db.get_unredacted_pdu_json(event_id).await
+}
+
+/// Delete the retained original content of redactions older than
+/// `redaction_retention_period`. Returns the number deleted.
+#[implement(super::Service)]
+pub async fn purge_unredacted(&self) -> usize {
+	self.db
+		.purge_unredacted(utils::millis_since_unix_epoch())
+		.await
+}
+
+/// Returns the redactions applied in a room, oldest first, each with the ID
+/// of the event it redacted.
+#[implement(super::Service)]
+pub fn redactions<'a>(
+	&'a self,
+	room_id: &'a RoomId,
+) -> impl Stream<Item = (PduEvent, OwnedEventId)> + Send + 'a {
+	self.services
+		.short
+		.get_shortroomid(room_id)
+		.map_ok(move |shortroomid| {
+			self.db
+				.redactions(shortroomid)
+				.filter_map(move |(pdu_id, redacts)| async move {
+					let pdu = self.get_pdu_from_id(&pdu_id).await.ok()?;
+					Some(Ok((pdu, redacts)))
+				})
+		})
+		.try_flatten_stream()
+		.ignore_err()
+}
@@ -0,0 +1,26 @@
+use super::expiry::expiry;
+
+const SENT: u64 = 1_700_000_000_000;
+
+#[test]
+fn expiry_as_requested_within_max_ttl() {
+	assert_eq!(expiry(SENT, SENT + 60_000, 3600), SENT + 60_000);
+}
+
+#[test]
+fn expiry_capped_by_max_ttl() {
+	assert_eq!(expiry(SENT, SENT + 7_200_000, 3600), SENT + 3_600_000);
+	assert_eq!(expiry(SENT, u64::MAX, 3600), SENT + 3_600_000);
+}
+
+#[test]
+fn expiry_in_the_past() {
+	// Already expired when received; redacted on the next check.
+	assert_eq!(expiry(SENT, SENT - 1, 3600), SENT - 1);
+	assert_eq!(expiry(SENT, 0, 3600), 0);
+}
+
+#[test]
+fn expiry_saturates() {
+	assert_eq!(expiry(u64::MAX - 1, u64::MAX, u64::MAX), u64::MAX);
+}
@@ -1,22 +1,35 @@
-use std::{collections::BTreeMap, sync::Arc};
+use std::{
+	collections::{BTreeMap, BTreeSet},
+	sync::{Arc, Mutex},
+	time::Duration,
+};
 
+use async_trait::async_trait;
 use futures::StreamExt;
 use ruma::{
 	OwnedRoomId, OwnedUserId, RoomId, UserId,
 	api::federation::transactions::edu::{Edu, TypingContent},
 	events::SyncEphemeralRoomEvent,
 };
-use tokio::sync::{RwLock, broadcast};
+use tokio::{
+	sync::{Notify, RwLock, broadcast},
+	time::sleep,
+};
 use tuwunel_core::{
-	Result, Server, debug_info, trace,
-	utils::{self, IterStream},
+	Result, Server, debug_info, debug_warn, trace,
+	utils::{self, IterStream, ReadyExt},
 };
 
-use crate::{Dep, globals, sending, sending::EduBuf, users};
+use crate::{Dep, globals, rooms, sending, sending::EduBuf, users};
 
 pub struct Service {
 	server: Arc<Server>,
 	services: Services,
+	/// Timeouts of typing users in order, so they expire even when nothing
+	/// reads the room's typing state. Entries are not removed when the user
+	/// stops or types again; the timeout in `typing` is checked on expiry.
+	timeouts: Mutex<BTreeSet<(u64, OwnedRoomId, OwnedUserId)>>,
+	timeout_added: Notify,
 	/// u64 is unix timestamp of timeout
 	pub typing: RwLock<BTreeMap<OwnedRoomId, BTreeMap<OwnedUserId, u64>>>,
 	/// timestamp of the last change to typing users
@@ -27,9 +40,14 @@ pub struct Service {
 struct Services {
 	globals: Dep<globals::Service>,
 	sending: Dep<sending::Service>,
+	state_cache: Dep<rooms::state_cache::Service>,
 	users: Dep<users::Service>,
 }
 
+/// Longest sleep between checks for expired typing when none are pending.
+const IDLE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+#[async_trait]
 impl crate::Service for Service {
 	fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 		Ok(Arc::new(Self {
@@ -37,26 +55,57 @@ fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 			services: Services {
 				globals: args.depend::<globals::Service>("globals"),
 				sending: args.depend::<sending::Service>("sending"),
+				state_cache: args.depend::<rooms::state_cache::Service>("rooms::state_cache"),
 				users: args.depend::<users::Service>("users"),
 			},
+			timeouts: Mutex::default(),
+			timeout_added: Notify::new(),
 			typing: RwLock::new(BTreeMap::new()),
 			last_typing_update: RwLock::new(BTreeMap::new()),
 			typing_update_sender: broadcast::channel(100).0,
 		}))
 	}
 
+	async fn worker(self: Arc<Self>) -> Result {
+		while self.server.running() {
+			let now = utils::millis_since_unix_epoch();
+			let next = self.expire_due(now).await;
+			let wait = next.map_or(IDLE_INTERVAL, |timeout| {
+				Duration::from_millis(timeout.saturating_sub(now)).min(IDLE_INTERVAL)
+			});
+
+			tokio::select! {
+				() = sleep(wait) => {},
+				() = self.timeout_added.notified() => {},
+				() = self.server.until_shutdown() => break,
+			}
+		}
+
+		Ok(())
+	}
+
 	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
 }
 
 impl Service {
 	/// Sets a user as typing until the timeout timestamp is reached or
-	/// roomtyping_remove is called.
+	/// roomtyping_remove is called. Users not joined to the room are ignored.
 	pub async fn typing_add(
 		&self,
 		user_id: &UserId,
 		room_id: &RoomId,
 		timeout: u64,
 	) -> Result<()> {
+		if !self
+			.services
+			.state_cache
+			.is_joined(user_id, room_id)
+			.await
+		{
+			debug_warn!("ignoring typing of {user_id:?} not joined to {room_id:?}");
+			return Ok(());
+		}
+
 		debug_info!("typing started {user_id:?} in {room_id:?} timeout:{timeout:?}");
 		// update clients
 		self.typing
@@ -66,6 +115,14 @@ pub async fn typing_add(
 			.or_default()
 			.insert(user_id.to_owned(), timeout);
 
+		self.timeouts.lock().expect("locked").insert((
+			timeout,
+			room_id.to_owned(),
+			user_id.to_owned(),
+		));
+
+		self.timeout_added.notify_one();
+
 		self.last_typing_update
 			.write()
 			.await
@@ -130,6 +187,34 @@ pub async fn wait_for_update(&self, room_id: &RoomId) {
 		}
 	}
 
+	/// Removes typing which timed out by `now` from the rooms with timeouts
+	/// due. Returns the next timeout, if any.
+	async fn expire_due(&self, now: u64) -> Option<u64> {
+		let mut rooms = BTreeSet::new();
+		let next = {
+			let mut timeouts = self.timeouts.lock().expect("locked");
+			while timeouts
+				.first()
+				.is_some_and(|(timeout, ..)| *timeout < now)
+			{
+				if let Some((_, room_id, _)) = timeouts.pop_first() {
+					rooms.insert(room_id);
+				}
+			}
+
+			timeouts.first().map(|(timeout, ..)| *timeout)
+		};
+
+		for room_id in &rooms {
+			self.typings_maintain(room_id)
+				.await
+				.inspect_err(|e| debug_warn!("failed to expire typing in {room_id:?}: {e}"))
+				.ok();
+		}
+
+		next
+	}
+
 	/// Makes sure that typing events with old timestamps get removed.
 	async fn typings_maintain(&self, room_id: &RoomId) -> Result<()> {
 		let current_timestamp = utils::millis_since_unix_epoch();
@@ -247,9 +332,26 @@ async fn federation_send(
 		let mut buf = EduBuf::new();
 		serde_json::to_writer(&mut buf, &edu).expect("Serialized Edu::Typing");
 
+		let allowed = &self.server.config.outgoing_typing_servers;
+		if allowed.is_empty() {
+			self.services
+				.sending
+				.send_edu_room(room_id, buf)
+				.await?;
+
+			return Ok(());
+		}
+
+		let servers = self
+			.services
+			.state_cache
+			.room_servers(room_id)
+			.ready_filter(|server_name| !self.services.globals.server_is_ours(server_name))
+			.ready_filter(|server_name| allowed.is_match(server_name.as_str()));
+
 		self.services
 			.sending
-			.send_edu_room(room_id, buf)
+			.send_edu_servers(servers, buf)
 			.await?;
 
 		Ok(())
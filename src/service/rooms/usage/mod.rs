@@ -0,0 +1,235 @@
+use std::{
+	collections::HashMap,
+	fmt::Write,
+	sync::{Arc, Mutex},
+};
+
+use async_trait::async_trait;
+use futures::StreamExt;
+use ruma::{CanonicalJsonObject, OwnedRoomId, RoomId};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use tuwunel_core::{Result, implement, utils::stream::TryIgnore};
+use tuwunel_database::{Deserialized, Json, Map};
+
+use crate::{Dep, rooms};
+
+/// Approximate per-room storage accounting.
+///
+/// Totals are computed by scanning a room's timeline the first time they are
+/// requested and are then kept current as events are appended. They are
+/// stored in the database, so the scan is not repeated after a restart.
+pub struct Service {
+	db: Data,
+	services: Services,
+	cache: Mutex<HashMap<OwnedRoomId, Usage>>,
+}
+
+struct Data {
+	pduid_pdu: Arc<Map>,
+	roomid_usage: Arc<Map>,
+}
+
+struct Services {
+	short: Dep<rooms::short::Service>,
+	state: Dep<rooms::state::Service>,
+	state_accessor: Dep<rooms::state_accessor::Service>,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+pub struct Usage {
+	/// Number of timeline events stored.
+	pub pdus: u64,
+
+	/// Serialized size of the stored timeline events.
+	pub pdu_bytes: u64,
+
+	/// Number of media references (`mxc://` URLs) in event content.
+	pub media: u64,
+
+	/// Number of events in the room's current state.
+	#[serde(skip)]
+	pub state_events: u64,
+}
+
+#[async_trait]
+impl crate::Service for Service {
+	fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
+		Ok(Arc::new(Self {
+			db: Data {
+				pduid_pdu: args.db["pduid_pdu"].clone(),
+				roomid_usage: args.db["roomid_usage"].clone(),
+			},
+			services: Services {
+				short: args.depend::<rooms::short::Service>("rooms::short"),
+				state: args.depend::<rooms::state::Service>("rooms::state"),
+				state_accessor: args
+					.depend::<rooms::state_accessor::Service>("rooms::state_accessor"),
+			},
+			cache: Mutex::default(),
+		}))
+	}
+
+	async fn memory_usage(&self, out: &mut (dyn Write + Send)) -> Result {
+		let cache = self.cache.lock()?.len();
+		writeln!(out, "room_usage_cache: {cache}")?;
+
+		Ok(())
+	}
+
+	async fn clear_cache(&self) { self.cache.lock().expect("locked").clear(); }
+
+	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
+}
+
+/// Storage used by a room. The timeline is scanned on the first request for a
+/// room; subsequent requests are answered from the running totals.
+#[implement(Service)]
+pub async fn usage(&self, room_id: &RoomId) -> Result<Usage> {
+	let mut usage = match self.totals(room_id).await {
+		| Some(usage) => usage,
+		| None => {
+			let usage = self.scan(room_id).await?;
+			self.db.roomid_usage.raw_put(room_id, Json(usage));
+			self.cache
+				.lock()?
+				.insert(room_id.to_owned(), usage);
+
+			usage
+		},
+	};
+
+	usage.state_events = self.state_events(room_id).await;
+
+	Ok(usage)
+}
+
+/// Account for an event appended to the room's timeline. Rooms which have not
+/// been scanned yet are ignored; they are counted in full on first request.
+/// The caller holds the room's insert lock, so updates are not lost.
+#[implement(Service)]
+pub async fn add_pdu(&self, room_id: &RoomId, pdu_json: &CanonicalJsonObject) {
+	let Some(mut usage) = self.totals(room_id).await else {
+		return;
+	};
+
+	let bytes = serde_json::to_vec(pdu_json).map_or(0, |json| json.len());
+	let media = pdu_json
+		.get("content")
+		.map(|content| serde_json::to_value(content).unwrap_or_default())
+		.as_ref()
+		.map_or(0, count_media);
+
+	usage.pdus = usage.pdus.saturating_add(1);
+	usage.pdu_bytes = usage
+		.pdu_bytes
+		.saturating_add(bytes.try_into().unwrap_or(0));
+	usage.media = usage.media.saturating_add(media);
+
+	self.db.roomid_usage.raw_put(room_id, Json(usage));
+	self.cache
+		.lock()
+		.expect("locked")
+		.insert(room_id.to_owned(), usage);
+}
+
+/// Drop the running totals of a room, e.g. after it was purged.
+#[implement(Service)]
+pub fn forget(&self, room_id: &RoomId) {
+	self.db.roomid_usage.remove(room_id);
+	self.cache.lock().expect("locked").remove(room_id);
+}
+
+/// Running totals of a room, from the cache or else the database; None when
+/// the room has not been scanned yet.
+#[implement(Service)]
+async fn totals(&self, room_id: &RoomId) -> Option<Usage> {
+	let cached = self
+		.cache
+		.lock()
+		.expect("locked")
+		.get(room_id)
+		.copied();
+
+	if cached.is_some() {
+		return cached;
+	}
+
+	let usage: Usage = self
+		.db
+		.roomid_usage
+		.get(room_id)
+		.await
+		.deserialized()
+		.ok()?;
+
+	self.cache
+		.lock()
+		.expect("locked")
+		.insert(room_id.to_owned(), usage);
+
+	Some(usage)
+}
+
+#[implement(Service)]
+async fn scan(&self, room_id: &RoomId) -> Result<Usage> {
+	let shortroomid = self
+		.services
+		.short
+		.get_shortroomid(room_id)
+		.await?;
+
+	let usage = self
+		.db
+		.pduid_pdu
+		.stream_prefix_raw(&shortroomid)
+		.ignore_err()
+		.fold(Usage::default(), |mut usage, (_, pdu)| async move {
+			let media = serde_json::from_slice::<JsonValue>(pdu)
+				.ok()
+				.as_ref()
+				.and_then(|pdu| pdu.get("content"))
+				.map_or(0, count_media);
+
+			usage.pdus = usage.pdus.saturating_add(1);
+			usage.pdu_bytes = usage
+				.pdu_bytes
+				.saturating_add(pdu.len().try_into().unwrap_or(0));
+			usage.media = usage.media.saturating_add(media);
+			usage
+		})
+		.await;
+
+	Ok(usage)
+}
+
+#[implement(Service)]
+async fn state_events(&self, room_id: &RoomId) -> u64 {
+	let Ok(shortstatehash) = self
+		.services
+		.state
+		.get_room_shortstatehash(room_id)
+		.await
+	else {
+		return 0;
+	};
+
+	self.services
+		.state_accessor
+		.state_full_shortids(shortstatehash)
+		.ignore_err()
+		.count()
+		.await
+		.try_into()
+		.unwrap_or(0)
+}
+
+/// Count `mxc://` URLs anywhere in an event's content.
+fn count_media(value: &JsonValue) -> u64 {
+	match value {
+		| JsonValue::String(s) => u64::from(s.starts_with("mxc://")),
+		| JsonValue::Array(a) => a.iter().map(count_media).sum(),
+		| JsonValue::Object(o) => o.values().map(count_media).sum(),
+		| _ => 0,
+	}
+}
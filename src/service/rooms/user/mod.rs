@@ -1,10 +1,20 @@
-use std::sync::Arc;
+#[cfg(test)]
+mod tests;
 
-use ruma::{RoomId, UserId};
-use tuwunel_core::{Result, implement};
-use tuwunel_database::{Database, Deserialized, Map};
+use std::{collections::BTreeMap, sync::Arc};
 
-use crate::{Dep, globals, rooms, rooms::short::ShortStateHash};
+use futures::{StreamExt, future::join};
+use ruma::{EventId, OwnedEventId, OwnedUserId, RoomId, UserId};
+use tuwunel_core::{
+	Result, implement,
+	utils::stream::{ReadyExt, TryIgnore},
+};
+use tuwunel_database::{Database, Deserialized, Interfix, Map};
+
+use crate::{
+	Dep, globals, rooms,
+	rooms::short::{ShortRoomId, ShortStateHash},
+};
 
 pub struct Service {
 	db: Data,
@@ -15,6 +25,8 @@ struct Data {
 	db: Arc<Database>,
 	userroomid_notificationcount: Arc<Map>,
 	userroomid_highlightcount: Arc<Map>,
+	userroomthreadid_notificationcount: Arc<Map>,
+	userroomthreadid_highlightcount: Arc<Map>,
 	roomuserid_lastnotificationread: Arc<Map>,
 	roomsynctoken_shortstatehash: Arc<Map>,
 }
@@ -31,6 +43,10 @@ fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 				db: args.db.clone(),
 				userroomid_notificationcount: args.db["userroomid_notificationcount"].clone(),
 				userroomid_highlightcount: args.db["userroomid_highlightcount"].clone(),
+				userroomthreadid_notificationcount: args.db["userroomthreadid_notificationcount"]
+					.clone(),
+				userroomthreadid_highlightcount: args.db["userroomthreadid_highlightcount"]
+					.clone(),
 				roomuserid_lastnotificationread: args.db["userroomid_highlightcount"].clone(),
 				roomsynctoken_shortstatehash: args.db["roomsynctoken_shortstatehash"].clone(),
 			},
@@ -45,15 +61,101 @@ fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
 }
 
+/// Reset the counts of the room for an unthreaded receipt, including the
+/// counts of every thread. The caller holds the room's insert lock, under
+/// which appends increment the counts.
+#[implement(Service)]
+pub async fn reset_notification_counts(&self, user_id: &UserId, room_id: &RoomId) {
+	// The counts of threads are included in the counts of the room, so the
+	// threads need only be looked for when the room has unread notifications.
+	// Usually it has none, such as when the user sends another event.
+	let (notifications, highlights) = join(
+		self.notification_count(user_id, room_id),
+		self.highlight_count(user_id, room_id),
+	)
+	.await;
+
+	if notifications > 0 || highlights > 0 {
+		let threads = self
+			.thread_notification_counts(user_id, room_id)
+			.await;
+
+		for thread_id in threads.keys() {
+			self.del_thread_notification_counts(user_id, room_id, thread_id);
+		}
+	}
+
+	self.set_notification_counts(user_id, room_id, 0, 0);
+}
+
+/// Reset the counts of the main timeline for a receipt on `main`, leaving the
+/// counts of threads. The caller holds the room's insert lock.
+#[implement(Service)]
+pub async fn reset_main_notification_counts(&self, user_id: &UserId, room_id: &RoomId) {
+	let (notifications, highlights) = sum_counts(
+		self.thread_notification_counts(user_id, room_id)
+			.await
+			.into_values(),
+	);
+
+	self.set_notification_counts(user_id, room_id, notifications, highlights);
+}
+
+/// Reset the counts of one thread for a receipt in that thread, taking them
+/// off the counts of the room. The caller holds the room's insert lock, so the
+/// counts read are not changed by an append or another reset meanwhile.
+#[implement(Service)]
+pub async fn reset_thread_notification_counts(
+	&self,
+	user_id: &UserId,
+	room_id: &RoomId,
+	thread_id: &EventId,
+) {
+	let key = (user_id, room_id, thread_id);
+	let thread_notifications: u64 = self
+		.db
+		.userroomthreadid_notificationcount
+		.qry(&key)
+		.await
+		.deserialized()
+		.unwrap_or(0);
+
+	let thread_highlights: u64 = self
+		.db
+		.userroomthreadid_highlightcount
+		.qry(&key)
+		.await
+		.deserialized()
+		.unwrap_or(0);
+
+	let room = join(
+		self.notification_count(user_id, room_id),
+		self.highlight_count(user_id, room_id),
+	)
+	.await;
+
+	let (notifications, highlights) =
+		without_thread(room, (thread_notifications, thread_highlights));
+
+	self.del_thread_notification_counts(user_id, room_id, thread_id);
+	self.set_notification_counts(user_id, room_id, notifications, highlights);
+}
+
 #[implement(Service)]
-pub fn reset_notification_counts(&self, user_id: &UserId, room_id: &RoomId) {
+fn set_notification_counts(
+	&self,
+	user_id: &UserId,
+	room_id: &RoomId,
+	notifications: u64,
+	highlights: u64,
+) {
 	let userroom_id = (user_id, room_id);
 	self.db
 		.userroomid_highlightcount
-		.put(userroom_id, 0_u64);
+		.put(userroom_id, highlights);
 	self.db
 		.userroomid_notificationcount
-		.put(userroom_id, 0_u64);
+		.put(userroom_id, notifications);
 
 	let roomuser_id = (room_id, user_id);
 	let count = self.services.globals.next_count().unwrap();
@@ -62,6 +164,20 @@ pub fn reset_notification_counts(&self, user_id: &UserId, room_id: &RoomId) {
 		.put(roomuser_id, count);
 }
 
+#[implement(Service)]
+fn del_thread_notification_counts(
+	&self,
+	user_id: &UserId,
+	room_id: &RoomId,
+	thread_id: &EventId,
+) {
+	let key = (user_id, room_id, thread_id);
+	self.db
+		.userroomthreadid_notificationcount
+		.del(key);
+	self.db.userroomthreadid_highlightcount.del(key);
+}
+
 #[implement(Service)]
 pub async fn notification_count(&self, user_id: &UserId, room_id: &RoomId) -> u64 {
 	let key = (user_id, room_id);
@@ -84,6 +200,38 @@ pub async fn highlight_count(&self, user_id: &UserId, room_id: &RoomId) -> u64 {
 		.unwrap_or(0)
 }
 
+/// Notification and highlight counts of each thread in the room with unread
+/// notifications. These are included in the counts of the room.
+#[implement(Service)]
+pub async fn thread_notification_counts(
+	&self,
+	user_id: &UserId,
+	room_id: &RoomId,
+) -> BTreeMap<OwnedEventId, (u64, u64)> {
+	let prefix = (user_id, room_id, Interfix);
+	let mut counts: BTreeMap<OwnedEventId, (u64, u64)> = self
+		.db
+		.userroomthreadid_notificationcount
+		.stream_prefix(&prefix)
+		.ignore_err()
+		.map(|((_, _, thread_id), count): ((&UserId, &RoomId, &EventId), u64)| {
+			(thread_id.to_owned(), (count, 0))
+		})
+		.collect()
+		.await;
+
+	self.db
+		.userroomthreadid_highlightcount
+		.stream_prefix(&prefix)
+		.ignore_err()
+		.ready_for_each(|((_, _, thread_id), count): ((&UserId, &RoomId, &EventId), u64)| {
+			counts.entry(thread_id.to_owned()).or_default().1 = count;
+		})
+		.await;
+
+	counts
+}
+
 #[implement(Service)]
 pub async fn last_notification_read(&self, user_id: &UserId, room_id: &RoomId) -> u64 {
 	let key = (room_id, user_id);
@@ -135,3 +283,64 @@ pub async fn get_token_shortstatehash(
 		.await
 		.deserialized()
 }
+
+/// Removes the notification counts of the room's members and its sync token
+/// snapshots, as part of purging the room from the database.
+#[implement(Service)]
+pub async fn purge_room(
+	&self,
+	room_id: &RoomId,
+	shortroomid: ShortRoomId,
+	members: &[OwnedUserId],
+) {
+	for user_id in members {
+		let userroom_id = (user_id, room_id);
+		self.db
+			.userroomid_notificationcount
+			.del(userroom_id);
+		self.db.userroomid_highlightcount.del(userroom_id);
+
+		let prefix = (user_id, room_id, Interfix);
+		for map in [
+			&self.db.userroomthreadid_notificationcount,
+			&self.db.userroomthreadid_highlightcount,
+		] {
+			map.keys_prefix_raw(&prefix)
+				.ignore_err()
+				.ready_for_each(|key| map.remove(key))
+				.await;
+		}
+	}
+
+	let prefix = (room_id, Interfix);
+	self.db
+		.roomuserid_lastnotificationread
+		.keys_prefix_raw(&prefix)
+		.ignore_err()
+		.ready_for_each(|key| {
+			self.db
+				.roomuserid_lastnotificationread
+				.remove(key)
+		})
+		.await;
+
+	self.db
+		.roomsynctoken_shortstatehash
+		.keys_prefix_raw(&shortroomid)
+		.ignore_err()
+		.ready_for_each(|key| self.db.roomsynctoken_shortstatehash.remove(key))
+		.await;
+}
+
+/// Total counts of the threads, which are what remains of the counts of the
+/// room after a receipt on the main timeline.
+pub(super) fn sum_counts(threads: impl IntoIterator<Item = (u64, u64)>) -> (u64, u64) {
+	threads
+		.into_iter()
+		.fold((0, 0), |(n, h), (tn, th)| (n.saturating_add(tn), h.saturating_add(th)))
+}
+
+/// Counts of the room after those of one of its threads are taken off.
+pub(super) fn without_thread((n, h): (u64, u64), (tn, th): (u64, u64)) -> (u64, u64) {
+	(n.saturating_sub(tn), h.saturating_sub(th))
+}
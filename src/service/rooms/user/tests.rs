@@ -0,0 +1,20 @@
+use super::{sum_counts, without_thread};
+
+#[test]
+fn main_receipt_leaves_thread_counts() {
+	assert_eq!(sum_counts([]), (0, 0));
+	assert_eq!(sum_counts([(3, 1), (2, 0), (5, 2)]), (10, 3));
+	assert_eq!(sum_counts([(u64::MAX, 0), (1, 1)]), (u64::MAX, 1));
+}
+
+#[test]
+fn thread_receipt_takes_thread_counts_off_room() {
+	assert_eq!(without_thread((10, 3), (4, 1)), (6, 2));
+	assert_eq!(without_thread((10, 3), (0, 0)), (10, 3));
+}
+
+#[test]
+fn thread_receipt_never_underflows() {
+	// Counts from before threads were counted separately.
+	assert_eq!(without_thread((2, 0), (4, 1)), (0, 0));
+}
@@ -1,11 +1,13 @@
 use std::{fmt::Debug, mem};
 
 use bytes::BytesMut;
+use http::StatusCode;
 use reqwest::Client;
 use ruma::api::{
 	IncomingResponse, MatrixVersion, OutgoingRequest, SendAccessToken, appservice::Registration,
+	client::error::ErrorKind,
 };
-use tuwunel_core::{Err, Result, debug_error, err, trace, utils, warn};
+use tuwunel_core::{Error, Result, err, trace, warn};
 
 /// Sends a request to an appservice
 ///
@@ -63,13 +65,7 @@ pub(crate) async fn send_request<T>(
 	let mut response = client
 		.execute(reqwest_request)
 		.await
-		.map_err(|e| {
-			warn!(
-				"Could not send request to appservice \"{}\" at {dest}: {e:?}",
-				registration.id
-			);
-			e
-		})?;
+		.map_err(|e| connection_error(&registration.id, &dest, &e))?;
 
 	// reqwest::Response -> http::Response conversion
 	let status = response.status();
@@ -83,14 +79,26 @@ pub(crate) async fn send_request<T>(
 			.expect("http::response::Builder is usable"),
 	);
 
-	let body = response.bytes().await?; // TODO: handle timeout
+	let body = response
+		.bytes()
+		.await
+		.map_err(|e| connection_error(&registration.id, &dest, &e))?;
 
 	if !status.is_success() {
-		debug_error!("Appservice response bytes: {:?}", utils::string_from_bytes(&body));
-		return Err!(BadServerResponse(warn!(
+		// The body is only logged; errors from here may be relayed to clients and
+		// remote servers, which must not see what the appservice returned.
+		let body = String::from_utf8_lossy(&body);
+		warn!(
+			?body,
 			"Appservice \"{}\" returned unsuccessful HTTP response {status} at {dest}",
 			registration.id
-		)));
+		);
+
+		return Err(Error::Request(
+			ErrorKind::BadStatus { status: Some(status), body: None },
+			format!("Appservice returned unsuccessful HTTP response {status}").into(),
+			StatusCode::BAD_GATEWAY,
+		));
 	}
 
 	let response = T::IncomingResponse::try_from_http_response(
@@ -106,3 +114,15 @@ pub(crate) async fn send_request<T>(
 		)))
 	})
 }
+
+/// Failure to reach the appservice, distinguishing timeouts as the ping
+/// endpoint (MSC2659) requires.
+fn connection_error(id: &str, dest: &str, e: &reqwest::Error) -> Error {
+	warn!("Could not send request to appservice \"{id}\" at {dest}: {e:?}");
+
+	if e.is_timeout() {
+		err!(Request(ConnectionTimeout("Appservice timed out: {e}")))
+	} else {
+		err!(Request(ConnectionFailed("Could not connect to appservice: {e}")))
+	}
+}
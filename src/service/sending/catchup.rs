@@ -0,0 +1,198 @@
+use std::{
+	collections::{BTreeMap, VecDeque, hash_map::Entry},
+	time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use futures::StreamExt;
+use ruma::{OwnedServerName, ServerName};
+use tuwunel_core::{
+	at, debug, implement,
+	matrix::pdu::{PduCount, PduId, ShortRoomId},
+	utils::{ReadyExt, time::now_millis},
+};
+
+use super::{Destination, SendingEvent, data::QueueItem};
+
+/// Progress of a destination catching up on the events queued for it while
+/// it or this server was down.
+#[derive(Debug)]
+pub struct CatchUp {
+	/// When the destination entered catch-up.
+	pub since: SystemTime,
+
+	/// Rooms whose latest queued event has been sent.
+	pub rooms: usize,
+
+	/// Catch-up transactions composed so far.
+	pub transactions: usize,
+
+	/// Latest queued event of each room yet to be sent; gathered from the
+	/// queue once, when the first catch-up transaction is composed.
+	pending: Option<VecDeque<QueueItem>>,
+}
+
+/// Latest queued event of each room, with its position in the room.
+pub(super) type Latest = BTreeMap<ShortRoomId, (PduCount, QueueItem)>;
+
+/// Restores the destinations which were catching up when the server stopped.
+#[implement(super::Service)]
+pub(super) async fn load_catchup(&self) {
+	let catchups: Vec<_> = self.db.catchups().collect().await;
+	if !self.server.config.sender_catchup {
+		for (server_name, _) in &catchups {
+			self.db.delete_catchup(server_name);
+		}
+
+		return;
+	}
+
+	let mut catchup = self.catchup.lock().expect("locked");
+	for (server_name, since) in catchups {
+		debug!(%server_name, "Resuming catch-up");
+		catchup.insert(server_name, CatchUp::new(since));
+	}
+}
+
+/// Puts the destination into catch-up: its next transactions carry only the
+/// latest queued event of each room, and the remaining backlog is sent in
+/// order after every room has been covered.
+#[implement(super::Service)]
+pub(super) fn start_catchup(&self, server_name: &ServerName) {
+	if !self.server.config.sender_catchup {
+		return;
+	}
+
+	let since = now_millis();
+	let entered = match self
+		.catchup
+		.lock()
+		.expect("locked")
+		.entry(server_name.to_owned())
+	{
+		| Entry::Occupied(_) => false,
+		| Entry::Vacant(entry) => {
+			entry.insert(CatchUp::new(since));
+			true
+		},
+	};
+
+	if entered {
+		debug!(%server_name, "Entering catch-up");
+		self.db.set_catchup(server_name, since);
+	}
+}
+
+/// Composes the next catch-up transaction for the destination from the
+/// latest queued event of rooms not yet covered. None when the destination
+/// is not catching up, or has just finished doing so.
+#[implement(super::Service)]
+pub(super) async fn select_catchup(
+	&self,
+	server_name: &ServerName,
+	limit: usize,
+) -> Option<Vec<QueueItem>> {
+	let gathered = self
+		.catchup
+		.lock()
+		.expect("locked")
+		.get(server_name)?
+		.pending
+		.is_some();
+
+	let pending = if gathered {
+		None
+	} else {
+		let latest = self
+			.db
+			.queued_requests(&Destination::Federation(server_name.to_owned()))
+			.ready_fold(Latest::new(), note_latest)
+			.await;
+
+		Some(pending(latest))
+	};
+
+	let mut catchup = self.catchup.lock().expect("locked");
+	let state = catchup.get_mut(server_name)?;
+	let pending = state
+		.pending
+		.get_or_insert_with(|| pending.unwrap_or_default());
+
+	if pending.is_empty() {
+		debug!(
+			%server_name,
+			rooms = state.rooms,
+			transactions = state.transactions,
+			"Finished catch-up",
+		);
+
+		catchup.remove(server_name);
+		drop(catchup);
+
+		self.db.delete_catchup(server_name);
+		return None;
+	}
+
+	let events: Vec<_> = pending
+		.drain(..limit.min(pending.len()))
+		.collect();
+
+	state.rooms = state.rooms.saturating_add(events.len());
+	state.transactions = state.transactions.saturating_add(1);
+	Some(events)
+}
+
+/// Destinations currently catching up.
+#[implement(super::Service)]
+#[must_use]
+pub fn catchup_status(&self) -> Vec<(OwnedServerName, CatchUp)> {
+	self.catchup
+		.lock()
+		.expect("locked")
+		.iter()
+		.map(|(server_name, state)| {
+			let status = CatchUp {
+				since: state.since,
+				rooms: state.rooms,
+				transactions: state.transactions,
+				pending: None,
+			};
+
+			(server_name.clone(), status)
+		})
+		.collect()
+}
+
+impl CatchUp {
+	fn new(since: u64) -> Self {
+		Self {
+			since: UNIX_EPOCH
+				.checked_add(Duration::from_millis(since))
+				.unwrap_or_else(SystemTime::now),
+			rooms: 0,
+			transactions: 0,
+			pending: None,
+		}
+	}
+}
+
+/// Keeps the queued item when it is the latest event of its room so far.
+pub(super) fn note_latest(mut latest: Latest, (key, event): QueueItem) -> Latest {
+	let SendingEvent::Pdu(pdu_id) = &event else {
+		return latest;
+	};
+
+	let PduId { shortroomid, shorteventid } = (*pdu_id).into();
+	if latest
+		.get(&shortroomid)
+		.is_none_or(|(count, _)| shorteventid > *count)
+	{
+		latest.insert(shortroomid, (shorteventid, (key, event)));
+	}
+
+	latest
+}
+
+/// The events to send in catch-up, one per room.
+pub(super) fn pending(latest: Latest) -> VecDeque<QueueItem> {
+	latest.into_values().map(at!(1)).collect()
+}
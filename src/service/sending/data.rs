@@ -2,11 +2,12 @@
 
 use futures::{Stream, StreamExt};
 use ruma::{OwnedServerName, ServerName, UserId};
+use serde::{Deserialize, Serialize};
 use tuwunel_core::{
 	Error, Result, at, utils,
-	utils::{ReadyExt, stream::TryIgnore},
+	utils::{ReadyExt, stream::TryIgnore, time::now_millis},
 };
-use tuwunel_database::{Database, Deserialized, Map};
+use tuwunel_database::{Database, Deserialized, Json, Map};
 
 use super::{Destination, SendingEvent};
 use crate::{Dep, globals};
@@ -17,8 +18,10 @@
 pub(super) type Key = Vec<u8>;
 
 pub struct Data {
+	appserviceid_txnstatus: Arc<Map>,
 	servercurrentevent_data: Arc<Map>,
 	servernameevent_data: Arc<Map>,
+	servername_catchup: Arc<Map>,
 	servername_educount: Arc<Map>,
 	pub(super) db: Arc<Database>,
 	services: Services,
@@ -28,12 +31,33 @@ struct Services {
 	globals: Dep<globals::Service>,
 }
 
+/// Outcome of the transactions sent to an appservice, kept across restarts.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct AppserviceStatus {
+	/// When the appservice last accepted a transaction, in milliseconds since
+	/// the epoch.
+	pub last_success: Option<u64>,
+
+	/// ID of the last transaction accepted.
+	pub last_txn_id: Option<String>,
+
+	/// Transactions which failed since the last one accepted.
+	pub failures: u32,
+
+	/// When the last transaction failed, in milliseconds since the epoch.
+	pub last_failure: Option<u64>,
+
+	pub last_error: Option<String>,
+}
+
 impl Data {
 	pub(super) fn new(args: &crate::Args<'_>) -> Self {
 		let db = &args.db;
 		Self {
+			appserviceid_txnstatus: db["appserviceid_txnstatus"].clone(),
 			servercurrentevent_data: db["servercurrentevent_data"].clone(),
 			servernameevent_data: db["servernameevent_data"].clone(),
+			servername_catchup: db["servername_catchup"].clone(),
 			servername_educount: db["servername_educount"].clone(),
 			db: args.db.clone(),
 			services: Services {
@@ -169,6 +193,57 @@ pub fn queued_requests(
 			})
 	}
 
+	pub async fn appservice_status(&self, id: &str) -> AppserviceStatus {
+		self.appserviceid_txnstatus
+			.get(id)
+			.await
+			.deserialized()
+			.unwrap_or_default()
+	}
+
+	pub(super) fn appservice_succeeded(&self, id: &str, txn_id: &str) {
+		let status = AppserviceStatus {
+			last_success: Some(now_millis()),
+			last_txn_id: Some(txn_id.to_owned()),
+			..Default::default()
+		};
+
+		self.appserviceid_txnstatus
+			.raw_put(id, Json(status));
+	}
+
+	pub(super) async fn appservice_failed(&self, id: &str, error: &Error) {
+		let mut status = self.appservice_status(id).await;
+		status.failures = status.failures.saturating_add(1);
+		status.last_failure = Some(now_millis());
+		status.last_error = Some(error.to_string());
+
+		self.appserviceid_txnstatus
+			.raw_put(id, Json(status));
+	}
+
+	pub(super) fn delete_appservice_status(&self, id: &str) {
+		self.appserviceid_txnstatus.remove(id);
+	}
+
+	/// Destinations catching up, with when each entered catch-up in
+	/// milliseconds since the epoch.
+	pub(super) fn catchups(&self) -> impl Stream<Item = (OwnedServerName, u64)> + Send + '_ {
+		self.servername_catchup
+			.stream()
+			.ignore_err()
+			.map(|(server_name, since): (&ServerName, u64)| (server_name.to_owned(), since))
+	}
+
+	pub(super) fn set_catchup(&self, server_name: &ServerName, since: u64) {
+		self.servername_catchup
+			.raw_put(server_name, since);
+	}
+
+	pub(super) fn delete_catchup(&self, server_name: &ServerName) {
+		self.servername_catchup.remove(server_name);
+	}
+
 	pub(super) fn set_latest_educount(&self, server_name: &ServerName, last_count: u64) {
 		self.servername_educount
 			.raw_put(server_name, last_count);
@@ -1,19 +1,23 @@
 mod appservice;
+mod catchup;
 mod data;
 mod dest;
 mod sender;
+#[cfg(test)]
+mod tests;
 
 use std::{
+	collections::HashMap,
 	fmt::Debug,
 	hash::{DefaultHasher, Hash, Hasher},
 	iter::once,
-	sync::Arc,
+	sync::{Arc, Mutex},
 };
 
 use async_trait::async_trait;
 use futures::{FutureExt, Stream, StreamExt};
 use ruma::{
-	RoomId, ServerName, UserId,
+	OwnedServerName, RoomId, ServerName, UserId,
 	api::{OutgoingRequest, appservice::Registration},
 };
 use tokio::{task, task::JoinSet};
@@ -26,6 +30,8 @@
 
 use self::data::Data;
 pub use self::{
+	catchup::CatchUp,
+	data::AppserviceStatus,
 	dest::Destination,
 	sender::{EDU_LIMIT, PDU_LIMIT},
 };
@@ -39,6 +45,7 @@ pub struct Service {
 	server: Arc<Server>,
 	services: Services,
 	channels: Vec<(loole::Sender<Msg>, loole::Receiver<Msg>)>,
+	catchup: Mutex<HashMap<OwnedServerName, CatchUp>>,
 }
 
 struct Services {
@@ -103,10 +110,13 @@ fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 			channels: (0..num_senders)
 				.map(|_| loole::unbounded())
 				.collect(),
+			catchup: Mutex::default(),
 		}))
 	}
 
 	async fn worker(self: Arc<Self>) -> Result {
+		self.load_catchup().await;
+
 		let mut senders =
 			self.channels
 				.iter()
@@ -379,6 +389,8 @@ pub async fn cleanup_events(
 					.delete_all_requests_for(&Destination::Appservice(appservice_id.to_owned()))
 					.await;
 
+				self.db.delete_appservice_status(appservice_id);
+
 				Ok(())
 			},
 			| _ => {
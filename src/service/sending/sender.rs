@@ -15,6 +15,7 @@
 	join, pin_mut,
 	stream::FuturesUnordered,
 };
+use regex::RegexSet;
 use ruma::{
 	CanonicalJsonObject, MilliSecondsSinceUnixEpoch, OwnedRoomId, OwnedServerName, OwnedUserId,
 	RoomId, RoomVersionId, ServerName, UInt,
@@ -38,6 +39,7 @@
 	uint,
 };
 use serde_json::value::{RawValue as RawJsonValue, to_raw_value};
+use tokio::time::sleep;
 use tuwunel_core::{
 	Error, Event, Result, debug, err, error,
 	result::LogErr,
@@ -70,7 +72,6 @@ enum TransactionStatus {
 const SELECT_PRESENCE_LIMIT: usize = 256;
 const SELECT_RECEIPT_LIMIT: usize = 256;
 const SELECT_EDU_LIMIT: usize = EDU_LIMIT - 2;
-const DEQUEUE_LIMIT: usize = 48;
 
 pub const PDU_LIMIT: usize = 50;
 pub const EDU_LIMIT: usize = 100;
@@ -116,12 +117,18 @@ async fn work_loop<'a>(
 			.map(|(_, receiver)| receiver.clone())
 			.expect("Missing channel for sender worker");
 
+		let max_inflight = self
+			.server
+			.config
+			.sender_max_inflight_transactions;
+
 		while !receiver.is_closed() {
+			let accepting = max_inflight == 0 || futures.len() < max_inflight;
 			tokio::select! {
 				Some(response) = futures.next() => {
 					self.handle_response(response, futures, statuses).await;
 				},
-				request = receiver.recv_async() => match request {
+				request = receiver.recv_async(), if accepting => match request {
 					Ok(request) => self.handle_request(request, futures, statuses).await,
 					Err(_) => return,
 				},
@@ -168,13 +175,24 @@ async fn handle_response_ok<'a>(
 		let _cork = self.db.db.cork();
 		self.db.delete_all_active_requests_for(dest).await;
 
+		// Destinations catching up are sent the latest event of each room first
+		let catchup = match dest {
+			| Destination::Federation(server_name) =>
+				self.select_catchup(server_name, self.transaction_pdu_limit())
+					.await,
+			| _ => None,
+		};
+
 		// Find events that have been added since starting the last request
-		let new_events = self
-			.db
-			.queued_requests(dest)
-			.take(DEQUEUE_LIMIT)
-			.collect::<Vec<_>>()
-			.await;
+		let new_events = match catchup {
+			| Some(events) => events,
+			| None =>
+				self.db
+					.queued_requests(dest)
+					.take(self.transaction_pdu_limit())
+					.collect::<Vec<_>>()
+					.await,
+		};
 
 		// Insert any pdus we found
 		if !new_events.is_empty() {
@@ -184,7 +202,16 @@ async fn handle_response_ok<'a>(
 				.into_iter()
 				.map(|(_, event)| event)
 				.collect();
-			futures.push(self.send_events(dest.clone(), new_events_vec));
+			let interval = self.server.config.sender_transaction_interval_ms;
+
+			let send = self.send_events(dest.clone(), new_events_vec);
+			futures.push(if interval > 0 {
+				sleep(Duration::from_millis(interval))
+					.then(|()| send)
+					.boxed()
+			} else {
+				send
+			});
 		} else {
 			statuses.remove(dest);
 		}
@@ -260,8 +287,12 @@ async fn startup_netburst<'a>(
 				continue;
 			}
 
+			// Appservices must receive every event, so their transactions are resumed in
+			// full regardless of the netburst settings.
+			let appservice = matches!(dest, Destination::Appservice(_));
 			let entry = txns.entry(dest.clone()).or_default();
-			if self.server.config.startup_netburst_keep >= 0 && entry.len() >= keep {
+			if !appservice && self.server.config.startup_netburst_keep >= 0 && entry.len() >= keep
+			{
 				warn!("Dropping unsent event {dest:?} {:?}", String::from_utf8_lossy(&key));
 				self.db.delete_active_request(&key);
 			} else {
@@ -270,7 +301,16 @@ async fn startup_netburst<'a>(
 		}
 
 		for (dest, events) in txns {
-			if self.server.config.startup_netburst && !events.is_empty() {
+			// Whatever queued up behind an unfinished transaction while this server
+			// was down is sent by catch-up, whether or not the transaction is resumed.
+			if let Destination::Federation(server_name) = &dest {
+				if !events.is_empty() {
+					self.start_catchup(server_name);
+				}
+			}
+
+			let appservice = matches!(dest, Destination::Appservice(_));
+			if (self.server.config.startup_netburst || appservice) && !events.is_empty() {
 				statuses.insert(dest.clone(), TransactionStatus::Running);
 				futures.push(self.send_events(dest.clone(), events));
 			}
@@ -302,8 +342,13 @@ async fn select_events(
 		let _cork = self.db.db.cork();
 		let mut events = Vec::new();
 
-		// Must retry any previous transaction for this remote.
+		// Must retry any previous transaction for this remote, which has been
+		// unreachable; whatever queued up meanwhile is sent by catch-up.
 		if retry {
+			if let Destination::Federation(server_name) = dest {
+				self.start_catchup(server_name);
+			}
+
 			self.db
 				.active_requests_for(dest)
 				.ready_for_each(|(_, e)| events.push(e))
@@ -336,6 +381,20 @@ async fn select_events(
 		Ok(Some(events))
 	}
 
+	fn transaction_pdu_limit(&self) -> usize {
+		self.server
+			.config
+			.sender_transaction_pdu_limit
+			.clamp(1, PDU_LIMIT)
+	}
+
+	fn transaction_edu_limit(&self) -> usize {
+		self.server
+			.config
+			.sender_transaction_edu_limit
+			.clamp(1, SELECT_EDU_LIMIT)
+	}
+
 	fn select_events_current(
 		&self,
 		dest: &Destination,
@@ -385,17 +444,21 @@ async fn select_edus(&self, server_name: &ServerName) -> Result<(EduVec, u64)> {
 		let device_changes =
 			self.select_edus_device_changes(server_name, batch, &max_edu_count, &events_len);
 
-		let receipts: OptionFuture<_> = self
-			.server
-			.config
-			.allow_outgoing_read_receipts
+		let config = &self.server.config;
+		let allowed =
+			|servers: &RegexSet| servers.is_empty() || servers.is_match(server_name.as_str());
+
+		let allow_receipts = config.allow_outgoing_read_receipts
+			&& allowed(&config.outgoing_read_receipts_servers);
+
+		let allow_presence =
+			config.allow_outgoing_presence && allowed(&config.outgoing_presence_servers);
+
+		let receipts: OptionFuture<_> = allow_receipts
 			.then(|| self.select_edus_receipts(server_name, batch, &max_edu_count))
 			.into();
 
-		let presence: OptionFuture<_> = self
-			.server
-			.config
-			.allow_outgoing_presence
+		let presence: OptionFuture<_> = allow_presence
 			.then(|| self.select_edus_presence(server_name, batch, &max_edu_count))
 			.into();
 
@@ -464,7 +527,9 @@ async fn select_edus_device_changes(
 					.expect("failed to serialize device list update to JSON");
 
 				events.push(buf);
-				if events_len.fetch_add(1, Ordering::Relaxed) >= SELECT_EDU_LIMIT - 1 {
+				if events_len.fetch_add(1, Ordering::Relaxed)
+					>= self.transaction_edu_limit().saturating_sub(1)
+				{
 					return events;
 				}
 			}
@@ -767,8 +832,14 @@ async fn send_events_dest_appservice(
 		)
 		.await
 		{
-			| Ok(_) => Ok(Destination::Appservice(id)),
-			| Err(e) => Err((Destination::Appservice(id), e)),
+			| Ok(_) => {
+				self.db.appservice_succeeded(&id, txn_id);
+				Ok(Destination::Appservice(id))
+			},
+			| Err(e) => {
+				self.db.appservice_failed(&id, &e).await;
+				Err((Destination::Appservice(id), e))
+			},
 		}
 	}
 
@@ -899,8 +970,10 @@ async fn send_events_dest_federation(
 			.map(|raw| raw.get().as_bytes())
 			.chain(edus.iter().map(|raw| raw.json().get().as_bytes()));
 
+		let bytes = preimage.clone().map(<[u8]>::len).sum();
 		let txn_hash = calculate_hash(preimage);
 		let txn_id = &*URL_SAFE_NO_PAD.encode(txn_hash);
+		let (pdu_count, edu_count) = (pdus.len(), edus.len());
 		let request = send_transaction_message::v1::Request {
 			transaction_id: txn_id.into(),
 			origin: self.server.name.clone(),
@@ -909,12 +982,22 @@ async fn send_events_dest_federation(
 			edus,
 		};
 
+		let started = Instant::now();
 		let result = self
 			.services
 			.federation
 			.execute_on(&self.services.client.sender, &server, request)
 			.await;
 
+		self.services.federation.record_sent(
+			&server,
+			pdu_count,
+			edu_count,
+			bytes,
+			started.elapsed(),
+			result.is_ok(),
+		);
+
 		for (event_id, result) in result.iter().flat_map(|resp| resp.pdus.iter()) {
 			if let Err(e) = result {
 				warn!(
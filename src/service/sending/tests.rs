@@ -0,0 +1,50 @@
+use tuwunel_core::matrix::pdu::{PduCount, PduId, RawPduId};
+
+use super::{
+	SendingEvent,
+	catchup::{Latest, note_latest, pending},
+	data::QueueItem,
+};
+
+fn pdu(shortroomid: u64, count: u64) -> QueueItem {
+	let pdu_id: RawPduId = PduId {
+		shortroomid,
+		shorteventid: PduCount::Normal(count),
+	}
+	.into();
+
+	(pdu_id.as_bytes().to_vec(), SendingEvent::Pdu(pdu_id))
+}
+
+fn edu() -> QueueItem { (b"edu".to_vec(), SendingEvent::Edu(b"{}".as_slice().into())) }
+
+fn select<I>(queue: I) -> Vec<QueueItem>
+where
+	I: IntoIterator<Item = QueueItem>,
+{
+	pending(queue.into_iter().fold(Latest::new(), note_latest)).into()
+}
+
+#[test]
+fn catchup_latest_of_each_room() {
+	let queue = [pdu(1, 10), pdu(2, 11), pdu(1, 12), pdu(2, 13), pdu(3, 14)];
+	assert_eq!(select(queue), [pdu(1, 12), pdu(2, 13), pdu(3, 14)]);
+}
+
+#[test]
+fn catchup_out_of_order() {
+	let queue = [pdu(1, 12), pdu(1, 10), pdu(1, 11)];
+	assert_eq!(select(queue), [pdu(1, 12)]);
+}
+
+#[test]
+fn catchup_skips_edus() {
+	let queue = [edu(), pdu(1, 10), edu()];
+	assert_eq!(select(queue), [pdu(1, 10)]);
+}
+
+#[test]
+fn catchup_empty_queue() {
+	assert!(select([]).is_empty());
+	assert!(select([edu()]).is_empty());
+}
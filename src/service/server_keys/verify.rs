@@ -66,6 +66,25 @@ pub async fn verify_event(
 	ruma::signatures::verify_event(&keys, event, room_version).map_err(Into::into)
 }
 
+/// Like `verify_event`, with the signature and hash checks run on the blocking
+/// pool so that many events can be verified in parallel.
+#[implement(super::Service)]
+pub async fn verify_event_blocking(
+	&self,
+	event: &CanonicalJsonObject,
+	room_version: &RoomVersionId,
+) -> Result<Verified> {
+	let keys = self.get_event_keys(event, room_version).await?;
+	let event = event.clone();
+	let room_version = room_version.clone();
+	self.services
+		.server
+		.runtime()
+		.spawn_blocking(move || ruma::signatures::verify_event(&keys, &event, &room_version))
+		.await?
+		.map_err(Into::into)
+}
+
 #[implement(super::Service)]
 pub async fn verify_json(
 	&self,
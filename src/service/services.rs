@@ -1,12 +1,16 @@
 use std::{
 	any::Any,
 	collections::BTreeMap,
+	fmt::Write,
 	sync::{Arc, RwLock},
 };
 
 use futures::{Stream, StreamExt, TryStreamExt};
 use tokio::sync::Mutex;
-use tuwunel_core::{Result, Server, debug, debug_info, info, trace, utils::stream::IterStream};
+use tuwunel_core::{
+	Result, Server, debug, debug_info, err, info, result::LogErr, trace,
+	utils::stream::IterStream,
+};
 use tuwunel_database::Database;
 
 use crate::{
@@ -14,7 +18,7 @@
 	manager::Manager,
 	media, presence, pusher, resolver, rooms, sending, server_keys, service,
 	service::{Args, Map, Service},
-	sync, transaction_ids, uiaa, users,
+	sso, sync, transaction_ids, uiaa, users, warmup,
 };
 
 pub struct Services {
@@ -34,10 +38,12 @@ pub struct Services {
 	pub federation: Arc<federation::Service>,
 	pub sending: Arc<sending::Service>,
 	pub server_keys: Arc<server_keys::Service>,
+	pub sso: Arc<sso::Service>,
 	pub sync: Arc<sync::Service>,
 	pub transaction_ids: Arc<transaction_ids::Service>,
 	pub uiaa: Arc<uiaa::Service>,
 	pub users: Arc<users::Service>,
+	pub warmup: Arc<warmup::Service>,
 
 	manager: Mutex<Option<Arc<Manager>>>,
 	pub(crate) service: Arc<Map>,
@@ -78,9 +84,11 @@ macro_rules! build {
 			rooms: rooms::Service {
 				alias: build!(rooms::alias::Service),
 				auth_chain: build!(rooms::auth_chain::Service),
+				delayed_events: build!(rooms::delayed_events::Service),
 				directory: build!(rooms::directory::Service),
 				event_handler: build!(rooms::event_handler::Service),
 				lazy_loading: build!(rooms::lazy_loading::Service),
+				membership_limit: build!(rooms::membership_limit::Service),
 				metadata: build!(rooms::metadata::Service),
 				outlier: build!(rooms::outlier::Service),
 				pdu_metadata: build!(rooms::pdu_metadata::Service),
@@ -95,15 +103,18 @@ macro_rules! build {
 				threads: build!(rooms::threads::Service),
 				timeline: build!(rooms::timeline::Service),
 				typing: build!(rooms::typing::Service),
+				usage: build!(rooms::usage::Service),
 				user: build!(rooms::user::Service),
 			},
 			federation: build!(federation::Service),
 			sending: build!(sending::Service),
 			server_keys: build!(server_keys::Service),
+			sso: build!(sso::Service),
 			sync: build!(sync::Service),
 			transaction_ids: build!(transaction_ids::Service),
 			uiaa: build!(uiaa::Service),
 			users: build!(users::Service),
+			warmup: build!(warmup::Service),
 
 			manager: Mutex::new(None),
 			service,
@@ -156,6 +167,12 @@ pub async fn stop(&self) {
 			manager.stop().await;
 		}
 
+		// Persist whatever the workers wrote while stopping, such as the state of
+		// the federation sending queue, before the database is closed.
+		if !self.db.is_read_only() {
+			self.db.db.sync().log_err().ok();
+		}
+
 		self.admin.set_services(None);
 
 		debug_info!("Services shutdown complete.");
@@ -177,11 +194,32 @@ pub async fn clear_cache(&self) {
 			.await;
 	}
 
+	/// Clear the caches of a single service by name (e.g. `rooms::auth_chain`).
+	pub async fn clear_cache_of(&self, name: &str) -> Result {
+		let service = self
+			.service
+			.read()
+			.expect("locked for reading")
+			.get(name)
+			.and_then(|val| val.0.upgrade())
+			.ok_or_else(|| err!(Request(NotFound("No service named {name:?}"))))?;
+
+		service.clear_cache().await;
+
+		Ok(())
+	}
+
 	pub async fn memory_usage(&self) -> Result<String> {
 		self.services()
 			.map(Ok)
 			.try_fold(String::new(), |mut out, service| async move {
-				service.memory_usage(&mut out).await?;
+				let mut usage = String::new();
+				service.memory_usage(&mut usage).await?;
+				if !usage.is_empty() {
+					writeln!(out, "{}:", service.name())?;
+					out.push_str(&usage);
+				}
+
 				Ok(out)
 			})
 			.await
@@ -0,0 +1,245 @@
+//! Single sign-on through an upstream OpenID Connect provider, using the
+//! authorization code flow; see `[global.sso]`.
+
+mod provider;
+#[cfg(test)]
+mod tests;
+
+use std::{
+	collections::HashMap,
+	net::IpAddr,
+	sync::{Arc, Mutex, RwLock},
+	time::{Duration, Instant},
+};
+
+use ruma::{OwnedUserId, UserId, api::client::error::ErrorKind};
+use tuwunel_core::{Err, Error, Result, Server, debug, err, implement, utils};
+use tuwunel_database::{Deserialized, Map};
+use url::Url;
+
+use crate::{Dep, client};
+
+pub struct Service {
+	services: Services,
+	db: Data,
+	metadata: RwLock<Option<Arc<provider::Metadata>>>,
+	sessions: Mutex<HashMap<String, Session>>,
+}
+
+struct Services {
+	server: Arc<Server>,
+	client: Dep<client::Service>,
+}
+
+struct Data {
+	oidcsubject_userid: Arc<Map>,
+}
+
+/// A login started at the redirect endpoint, keyed by its `state`.
+struct Session {
+	redirect_url: String,
+	nonce: String,
+	started: Instant,
+	client: IpAddr,
+}
+
+/// A user authenticated by the provider.
+#[derive(Debug)]
+pub struct SsoIdentity {
+	/// The provider's stable identifier of the user.
+	pub subject: String,
+
+	/// Value of the configured `username_claim`.
+	pub username: Option<String>,
+
+	/// Value of the configured `displayname_claim`.
+	pub displayname: Option<String>,
+
+	/// Where the client asked to be sent once logged in.
+	pub redirect_url: String,
+}
+
+/// Time the user has to authenticate with the provider.
+const SESSION_TTL: Duration = Duration::from_secs(600);
+
+const STATE_LENGTH: usize = 32;
+
+/// Logins which may be in progress at once.
+const MAX_SESSIONS: usize = 4096;
+
+/// Logins which may be in progress at once from one address.
+const MAX_SESSIONS_PER_CLIENT: usize = 8;
+
+const CALLBACK_PATH: &str = "/_matrix/client/v3/login/sso/callback";
+
+impl crate::Service for Service {
+	fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
+		Ok(Arc::new(Self {
+			services: Services {
+				server: args.server.clone(),
+				client: args.depend::<client::Service>("client"),
+			},
+			db: Data {
+				oidcsubject_userid: args.db["oidcsubject_userid"].clone(),
+			},
+			metadata: RwLock::default(),
+			sessions: Mutex::default(),
+		}))
+	}
+
+	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
+}
+
+/// Starts a login from the client's address, returning the provider's URL to
+/// send the user to. The client is sent back to `redirect_url` once the
+/// provider has authenticated the user.
+#[implement(Service)]
+pub async fn authorization_url(&self, redirect_url: &str, client: IpAddr) -> Result<Url> {
+	let config = &self.services.server.config.sso;
+	let metadata = self.metadata().await?;
+	let state = utils::random_string(STATE_LENGTH);
+	let nonce = utils::random_string(STATE_LENGTH);
+
+	let mut url = metadata.authorization_endpoint.clone();
+	url.query_pairs_mut()
+		.append_pair("response_type", "code")
+		.append_pair("client_id", self.client_id()?)
+		.append_pair("redirect_uri", self.callback_url()?.as_str())
+		.append_pair("scope", &config.scopes)
+		.append_pair("state", &state)
+		.append_pair("nonce", &nonce);
+
+	let mut sessions = self.sessions.lock().expect("locked");
+	sessions.retain(|_, session| session.started.elapsed() < SESSION_TTL);
+	let from_client = sessions
+		.values()
+		.filter(|session| session.client == client)
+		.count();
+
+	if sessions.len() >= MAX_SESSIONS || from_client >= MAX_SESSIONS_PER_CLIENT {
+		return Err(Error::Request(
+			ErrorKind::LimitExceeded { retry_after: None },
+			"Too many SSO logins in progress.".into(),
+			http::StatusCode::TOO_MANY_REQUESTS,
+		));
+	}
+
+	sessions.insert(state, Session {
+		redirect_url: redirect_url.to_owned(),
+		nonce,
+		started: Instant::now(),
+		client,
+	});
+
+	Ok(url)
+}
+
+/// Completes the login with the `state` and `code` the provider sent the user
+/// back with. Each login can only be completed once.
+#[implement(Service)]
+pub async fn authenticate(&self, state: &str, code: &str) -> Result<SsoIdentity> {
+	let session = self
+		.sessions
+		.lock()
+		.expect("locked")
+		.remove(state)
+		.filter(|session| session.started.elapsed() < SESSION_TTL)
+		.ok_or_else(|| err!(Request(Forbidden("Unknown or expired SSO session."))))?;
+
+	let metadata = self.metadata().await?;
+	let tokens = self.exchange_code(&metadata, code).await?;
+	let mut claims = self.id_token_claims(&metadata, &tokens.id_token, &session.nonce)?;
+
+	let config = &self.services.server.config.sso;
+	if !claims.contains_key(&config.username_claim) {
+		if let Some(userinfo) = self
+			.userinfo(&metadata, &tokens.access_token, &claims.sub)
+			.await?
+		{
+			for (claim, value) in userinfo {
+				claims.other.entry(claim).or_insert(value);
+			}
+		}
+	}
+
+	debug!(subject = %claims.sub, "Authenticated by SSO provider");
+	Ok(SsoIdentity {
+		username: claims.string(&config.username_claim),
+		displayname: claims.string(&config.displayname_claim),
+		subject: claims.sub,
+		redirect_url: session.redirect_url,
+	})
+}
+
+/// The user linked to the provider's `subject`.
+#[implement(Service)]
+pub async fn user_for_subject(&self, subject: &str) -> Result<OwnedUserId> {
+	self.db
+		.oidcsubject_userid
+		.qry(&(self.issuer()?.as_str(), subject))
+		.await
+		.deserialized()
+}
+
+/// Links the provider's `subject` to the user, who logs in as them from then
+/// on.
+#[implement(Service)]
+pub fn link_subject(&self, subject: &str, user_id: &UserId) -> Result {
+	self.db
+		.oidcsubject_userid
+		.put((self.issuer()?.as_str(), subject), user_id);
+
+	Ok(())
+}
+
+#[implement(Service)]
+fn callback_url(&self) -> Result<Url> {
+	let Some(client) = self
+		.services
+		.server
+		.config
+		.well_known
+		.client
+		.as_ref()
+	else {
+		return Err!(Config("well_known.client", "Required for the SSO callback URL."));
+	};
+
+	let url = format!("{}{CALLBACK_PATH}", client.as_str().trim_end_matches('/'));
+	Url::parse(&url).map_err(|e| err!(Config("well_known.client", "Invalid URL: {e}")))
+}
+
+#[implement(Service)]
+fn issuer(&self) -> Result<&Url> {
+	self.services
+		.server
+		.config
+		.sso
+		.issuer
+		.as_ref()
+		.ok_or_else(|| err!(Config("sso.issuer", "SSO issuer is not set.")))
+}
+
+#[implement(Service)]
+fn client_id(&self) -> Result<&str> {
+	self.services
+		.server
+		.config
+		.sso
+		.client_id
+		.as_deref()
+		.ok_or_else(|| err!(Config("sso.client_id", "SSO client ID is not set.")))
+}
+
+/// Parses the client's `redirectUrl`, refusing schemes which would run in the
+/// page of this server.
+pub fn client_url(redirect_url: &str) -> Result<Url> {
+	let url = Url::parse(redirect_url)
+		.map_err(|e| err!(Request(InvalidParam("Invalid redirectUrl: {e}"))))?;
+
+	if matches!(url.scheme(), "javascript" | "data") {
+		return Err!(Request(InvalidParam("Invalid redirectUrl scheme.")));
+	}
+
+	Ok(url)
+}
@@ -0,0 +1,227 @@
+//! Requests to the OpenID Connect provider.
+
+use std::{sync::Arc, time::Duration};
+
+use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+use http::header::CONTENT_TYPE;
+use serde::{Deserialize, de::DeserializeOwned};
+use serde_json::{Map as JsonMap, Value};
+use tuwunel_core::{Err, Result, debug, err, error, implement, utils::time::now_millis};
+use url::{Url, form_urlencoded};
+
+/// The provider's configuration from its discovery document.
+#[derive(Debug, Deserialize)]
+pub(super) struct Metadata {
+	issuer: String,
+	pub(super) authorization_endpoint: Url,
+	token_endpoint: Url,
+	userinfo_endpoint: Option<Url>,
+}
+
+#[derive(Deserialize)]
+pub(super) struct TokenResponse {
+	pub(super) access_token: String,
+	pub(super) id_token: String,
+}
+
+/// Claims of an ID token, or of the userinfo response.
+#[derive(Deserialize)]
+pub(super) struct Claims {
+	pub(super) sub: String,
+
+	#[serde(flatten)]
+	pub(super) other: JsonMap<String, Value>,
+}
+
+#[derive(Deserialize)]
+struct IdTokenClaims {
+	iss: String,
+	aud: Audience,
+	exp: u64,
+	nonce: Option<String>,
+
+	#[serde(flatten)]
+	claims: Claims,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub(super) enum Audience {
+	One(String),
+	Many(Vec<String>),
+}
+
+impl Claims {
+	pub(super) fn contains_key(&self, claim: &str) -> bool { self.other.contains_key(claim) }
+
+	/// The value of a claim, when it is a string.
+	pub(super) fn string(&self, claim: &str) -> Option<String> {
+		self.other
+			.get(claim)
+			.and_then(Value::as_str)
+			.map(ToOwned::to_owned)
+	}
+}
+
+impl Audience {
+	pub(super) fn contains(&self, client_id: &str) -> bool {
+		match self {
+			| Self::One(aud) => aud == client_id,
+			| Self::Many(auds) => auds.iter().any(|aud| aud == client_id),
+		}
+	}
+}
+
+/// The provider's discovery document, fetched on first use.
+#[implement(super::Service)]
+pub(super) async fn metadata(&self) -> Result<Arc<Metadata>> {
+	if let Some(metadata) = self.metadata.read().expect("locked").clone() {
+		return Ok(metadata);
+	}
+
+	let issuer = self.issuer()?;
+	let url =
+		format!("{}/.well-known/openid-configuration", issuer.as_str().trim_end_matches('/'));
+
+	debug!(%url, "Fetching SSO provider configuration");
+	let metadata: Metadata = self.request(self.http().get(&url)).await?;
+
+	// The issuer in the document must be the one configured (OpenID Connect
+	// Discovery 1.0, section 4.3).
+	if metadata.issuer.trim_end_matches('/') != issuer.as_str().trim_end_matches('/') {
+		return Err!(BadServerResponse(error!(
+			issuer = %metadata.issuer,
+			"SSO provider configuration is for another issuer"
+		)));
+	}
+
+	let metadata = Arc::new(metadata);
+	*self.metadata.write().expect("locked") = Some(metadata.clone());
+
+	Ok(metadata)
+}
+
+/// Exchanges the authorization code for the user's tokens.
+#[implement(super::Service)]
+pub(super) async fn exchange_code(
+	&self,
+	metadata: &Metadata,
+	code: &str,
+) -> Result<TokenResponse> {
+	let config = &self.services.server.config.sso;
+	let mut form = form_urlencoded::Serializer::new(String::new());
+	form.append_pair("grant_type", "authorization_code")
+		.append_pair("code", code)
+		.append_pair("redirect_uri", self.callback_url()?.as_str())
+		.append_pair("client_id", self.client_id()?);
+
+	if let Some(client_secret) = config.client_secret.as_deref() {
+		form.append_pair("client_secret", client_secret);
+	}
+
+	let request = self
+		.http()
+		.post(metadata.token_endpoint.clone())
+		.header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+		.body(form.finish());
+
+	self.request(request).await
+}
+
+/// Decodes and validates the ID token. Its signature is not checked: the token
+/// was received directly from the token endpoint over TLS, which OpenID
+/// Connect Core 1.0 (section 3.1.3.7) allows in place of it.
+#[implement(super::Service)]
+pub(super) fn id_token_claims(
+	&self,
+	metadata: &Metadata,
+	id_token: &str,
+	nonce: &str,
+) -> Result<Claims> {
+	validate_id_token(id_token, &metadata.issuer, self.client_id()?, nonce, now_millis())
+}
+
+pub(super) fn validate_id_token(
+	id_token: &str,
+	issuer: &str,
+	client_id: &str,
+	nonce: &str,
+	now: u64,
+) -> Result<Claims> {
+	let invalid =
+		|reason: &str| err!(BadServerResponse(error!("Invalid SSO ID token: {reason}")));
+
+	let payload = id_token
+		.split('.')
+		.nth(1)
+		.ok_or_else(|| invalid("not a JWT"))?;
+
+	let payload = URL_SAFE_NO_PAD
+		.decode(payload)
+		.map_err(|e| invalid(&e.to_string()))?;
+
+	let token: IdTokenClaims =
+		serde_json::from_slice(&payload).map_err(|e| invalid(&e.to_string()))?;
+
+	if token.iss != issuer {
+		return Err(invalid("issued by another issuer"));
+	}
+
+	if !token.aud.contains(client_id) {
+		return Err(invalid("issued to another client"));
+	}
+
+	if token.exp.saturating_mul(1000) < now {
+		return Err(invalid("expired"));
+	}
+
+	if token.nonce.as_deref() != Some(nonce) {
+		return Err(invalid("nonce does not match"));
+	}
+
+	Ok(token.claims)
+}
+
+/// Claims from the userinfo endpoint, for providers which leave claims out of
+/// the ID token.
+#[implement(super::Service)]
+pub(super) async fn userinfo(
+	&self,
+	metadata: &Metadata,
+	access_token: &str,
+	subject: &str,
+) -> Result<Option<JsonMap<String, Value>>> {
+	let Some(url) = metadata.userinfo_endpoint.clone() else {
+		return Ok(None);
+	};
+
+	let userinfo: Claims = self
+		.request(self.http().get(url).bearer_auth(access_token))
+		.await?;
+
+	// The userinfo must be for the user of the ID token (OpenID Connect Core
+	// 1.0, section 5.3.2).
+	if userinfo.sub != subject {
+		return Err!(BadServerResponse(error!("SSO userinfo is for another user")));
+	}
+
+	Ok(Some(userinfo.other))
+}
+
+#[implement(super::Service)]
+fn http(&self) -> &reqwest::Client { &self.services.client.default }
+
+#[implement(super::Service)]
+async fn request<T: DeserializeOwned>(&self, request: reqwest::RequestBuilder) -> Result<T> {
+	let timeout = Duration::from_secs(self.services.server.config.sso.timeout);
+	let response = request
+		.timeout(timeout)
+		.send()
+		.await?
+		.error_for_status()?
+		.bytes()
+		.await?;
+
+	serde_json::from_slice(&response)
+		.map_err(|e| err!(BadServerResponse(error!("Invalid SSO provider response: {e}"))))
+}
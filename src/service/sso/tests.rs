@@ -0,0 +1,99 @@
+use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+use serde_json::{Value, json};
+
+use super::{
+	client_url,
+	provider::{Audience, validate_id_token},
+};
+
+const ISSUER: &str = "https://idp.example.com";
+const CLIENT_ID: &str = "tuwunel";
+const NONCE: &str = "n0nce";
+const NOW: u64 = 1_700_000_000_000;
+
+fn id_token(claims: &Value) -> String {
+	let header = URL_SAFE_NO_PAD.encode(br#"{"alg":"RS256"}"#);
+	let payload = URL_SAFE_NO_PAD.encode(claims.to_string());
+
+	format!("{header}.{payload}.signature")
+}
+
+fn claims() -> Value {
+	json!({
+		"iss": ISSUER,
+		"aud": CLIENT_ID,
+		"exp": NOW / 1000 + 60,
+		"nonce": NONCE,
+		"sub": "subject-1",
+		"preferred_username": "alice",
+	})
+}
+
+fn validate(claims: &Value) -> tuwunel_core::Result<super::provider::Claims> {
+	validate_id_token(&id_token(claims), ISSUER, CLIENT_ID, NONCE, NOW)
+}
+
+#[test]
+fn id_token_valid() {
+	let claims = validate(&claims()).expect("valid ID token");
+
+	assert_eq!(claims.sub, "subject-1");
+	assert_eq!(claims.string("preferred_username").as_deref(), Some("alice"));
+}
+
+#[test]
+fn id_token_audience_list() {
+	let mut claims = claims();
+	claims["aud"] = json!(["other", CLIENT_ID]);
+
+	assert!(validate(&claims).is_ok());
+}
+
+#[test]
+fn id_token_rejected() {
+	let cases = [
+		("iss", json!("https://evil.example.com")),
+		("aud", json!("other")),
+		("aud", json!(["other", "another"])),
+		("exp", json!(NOW / 1000 - 60)),
+		("nonce", json!("replayed")),
+	];
+
+	for (claim, value) in cases {
+		let mut claims = claims();
+		claims[claim] = value;
+		assert!(validate(&claims).is_err(), "{claim} should be checked");
+	}
+
+	let mut claims = claims();
+	claims.as_object_mut().unwrap().remove("nonce");
+	assert!(validate(&claims).is_err(), "nonce is required");
+
+	assert!(validate_id_token("not-a-jwt", ISSUER, CLIENT_ID, NONCE, NOW).is_err());
+	assert!(validate_id_token("a.!!!.c", ISSUER, CLIENT_ID, NONCE, NOW).is_err());
+}
+
+#[test]
+fn audience_contains() {
+	let one: Audience = serde_json::from_value(json!(CLIENT_ID)).unwrap();
+	assert!(one.contains(CLIENT_ID));
+	assert!(!one.contains("other"));
+
+	let many: Audience = serde_json::from_value(json!(["other", CLIENT_ID])).unwrap();
+	assert!(many.contains(CLIENT_ID));
+	assert!(many.contains("other"));
+	assert!(!many.contains("another"));
+
+	let none: Audience = serde_json::from_value(json!([])).unwrap();
+	assert!(!none.contains(CLIENT_ID));
+}
+
+#[test]
+fn client_url_schemes() {
+	assert!(client_url("https://app.example.com/login").is_ok());
+	assert!(client_url("im.fluffychat://login").is_ok());
+
+	assert!(client_url("javascript:alert(1)").is_err());
+	assert!(client_url("data:text/html,<script></script>").is_err());
+	assert!(client_url("not a url").is_err());
+}
@@ -0,0 +1,96 @@
+//! UIA sessions already spent on a single-use grant, such as issuing a login
+//! token, so one completed session cannot be replayed for another grant. They
+//! are kept in the database so a restart does not allow a replay.
+
+use std::{collections::HashMap, sync::Mutex};
+
+use futures::StreamExt;
+use ruma::{DeviceId, UserId};
+use tuwunel_core::{
+	implement,
+	utils::{ReadyExt, stream::TryIgnore, time::now_millis},
+};
+
+use super::RequestKey;
+
+/// How long a consumed session is remembered, in milliseconds; completed
+/// sessions are deleted well before this, so it only needs to outlive requests
+/// racing on one.
+const RETENTION: u64 = 1000 * 60 * 60 * 24;
+
+/// Sessions consumed since startup, marked before the database is consulted so
+/// requests racing on one session cannot both pass.
+#[derive(Default)]
+pub(super) struct Consumed {
+	sessions: Mutex<HashMap<RequestKey, u64>>,
+}
+
+type Key<'a> = (&'a UserId, &'a DeviceId, &'a str);
+
+/// Spend a completed session on a single-use grant. Returns false if the
+/// session was already spent, in which case the grant must be refused.
+#[implement(super::Service)]
+pub async fn consume_session(
+	&self,
+	user_id: &UserId,
+	device_id: &DeviceId,
+	session: &str,
+) -> bool {
+	let now = now_millis();
+	let key = (user_id.to_owned(), device_id.to_owned(), session.to_owned());
+	if !self.consumed.consume(key, now) {
+		return false;
+	}
+
+	let key: Key<'_> = (user_id, device_id, session);
+	if self
+		.db
+		.userdevicesessionid_consumed
+		.qry(&key)
+		.await
+		.is_ok()
+	{
+		return false;
+	}
+
+	self.prune_consumed(now).await;
+	self.db.userdevicesessionid_consumed.put(key, now);
+
+	true
+}
+
+/// Forget the sessions consumed longer than the retention ago.
+#[implement(super::Service)]
+async fn prune_consumed(&self, now: u64) {
+	let expired: Vec<RequestKey> = self
+		.db
+		.userdevicesessionid_consumed
+		.stream()
+		.ignore_err()
+		.ready_filter_map(|((user_id, device_id, session), consumed): (Key<'_>, u64)| {
+			(!retained(consumed, now))
+				.then(|| (user_id.to_owned(), device_id.to_owned(), session.to_owned()))
+		})
+		.collect()
+		.await;
+
+	for (user_id, device_id, session) in &expired {
+		let key: Key<'_> = (user_id, device_id, session);
+		self.db.userdevicesessionid_consumed.del(key);
+	}
+}
+
+impl Consumed {
+	/// Marks the session consumed. Returns false if it already was.
+	pub(super) fn consume(&self, key: RequestKey, now: u64) -> bool {
+		let mut sessions = self.sessions.lock().expect("locked");
+		sessions.retain(|_, &mut consumed| retained(consumed, now));
+		sessions.insert(key, now).is_none()
+	}
+}
+
+/// Whether a session consumed at `consumed` is still remembered at `now`, both
+/// in milliseconds since the epoch.
+pub(super) fn retained(consumed: u64, now: u64) -> bool {
+	now.saturating_sub(consumed) < RETENTION
+}
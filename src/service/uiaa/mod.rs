@@ -1,3 +1,7 @@
+mod consumed;
+#[cfg(test)]
+mod tests;
+
 use std::{
 	collections::{BTreeMap, HashSet},
 	sync::{Arc, RwLock},
@@ -16,21 +20,24 @@
 };
 use tuwunel_database::{Deserialized, Json, Map};
 
-use crate::{Dep, config, globals, users};
+use crate::{Dep, config, emergency, globals, users};
 
 pub struct Service {
 	userdevicesessionid_uiaarequest: RwLock<RequestMap>,
+	consumed: consumed::Consumed,
 	db: Data,
 	services: Services,
 }
 
 struct Services {
+	emergency: Dep<emergency::Service>,
 	globals: Dep<globals::Service>,
 	users: Dep<users::Service>,
 	config: Dep<config::Service>,
 }
 
 struct Data {
+	userdevicesessionid_consumed: Arc<Map>,
 	userdevicesessionid_uiaainfo: Arc<Map>,
 }
 
@@ -39,14 +46,21 @@ struct Data {
 
 pub const SESSION_ID_LENGTH: usize = 32;
 
+/// Stage completed with a code from the user's authenticator app or one of
+/// their recovery codes, given as `code` in the auth dict.
+pub const TOTP_AUTH_TYPE: &str = "org.tuwunel.totp";
+
 impl crate::Service for Service {
 	fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 		Ok(Arc::new(Self {
 			userdevicesessionid_uiaarequest: RwLock::new(RequestMap::new()),
+			consumed: consumed::Consumed::default(),
 			db: Data {
+				userdevicesessionid_consumed: args.db["userdevicesessionid_consumed"].clone(),
 				userdevicesessionid_uiaainfo: args.db["userdevicesessionid_uiaainfo"].clone(),
 			},
 			services: Services {
+				emergency: args.depend::<emergency::Service>("emergency"),
 				globals: args.depend::<globals::Service>("globals"),
 				users: args.depend::<users::Service>("users"),
 				config: args.depend::<config::Service>("config"),
@@ -160,7 +174,13 @@ pub async fn try_auth(
 			// Check if password is correct
 			let user_id = user_id_from_username;
 			if let Ok(hash) = self.services.users.password_hash(&user_id).await {
-				let hash_matches = hash::verify_password(password, &hash).is_ok();
+				let pepper = self
+					.services
+					.config
+					.synapse_password_pepper
+					.as_deref();
+
+				let hash_matches = hash::verify_password(password, &hash, pepper).is_ok();
 				if !hash_matches {
 					uiaainfo.auth_error = Some(StandardErrorBody {
 						kind: ErrorKind::forbidden(),
@@ -171,6 +191,13 @@ pub async fn try_auth(
 				}
 			}
 
+			// The server user's password is the emergency password, which may
+			// have expired or be single-use.
+			self.services
+				.emergency
+				.password_used(&user_id)
+				.await?;
+
 			// Password was correct! Let's add it to `completed`
 			uiaainfo.completed.push(AuthType::Password);
 		},
@@ -192,6 +219,31 @@ pub async fn try_auth(
 		| AuthData::Dummy(_) => {
 			uiaainfo.completed.push(AuthType::Dummy);
 		},
+		| auth if auth.auth_type() == Some(AuthType::from(TOTP_AUTH_TYPE)) => {
+			let data = auth.data();
+			let code = data
+				.get("code")
+				.and_then(serde_json::Value::as_str)
+				.unwrap_or_default();
+
+			if !self
+				.services
+				.users
+				.verify_totp(user_id, code)
+				.await?
+			{
+				uiaainfo.auth_error = Some(StandardErrorBody {
+					kind: ErrorKind::forbidden(),
+					message: "Invalid two-factor code.".to_owned(),
+				});
+
+				return Ok((false, uiaainfo));
+			}
+
+			uiaainfo
+				.completed
+				.push(AuthType::from(TOTP_AUTH_TYPE));
+		},
 		| k => error!("type not supported: {:?}", k),
 	}
 
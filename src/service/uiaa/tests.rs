@@ -0,0 +1,50 @@
+use ruma::user_id;
+
+use super::{
+	RequestKey,
+	consumed::{Consumed, retained},
+};
+
+const DAY: u64 = 1000 * 60 * 60 * 24;
+
+fn key(device: &str, session: &str) -> RequestKey {
+	(user_id!("@alice:example.com").to_owned(), device.into(), session.to_owned())
+}
+
+#[test]
+fn session_consumed_once() {
+	let consumed = Consumed::default();
+	let now = 1_000_000;
+
+	assert!(consumed.consume(key("DEVICE", "session"), now));
+	assert!(!consumed.consume(key("DEVICE", "session"), now));
+	assert!(!consumed.consume(key("DEVICE", "session"), now + 60_000));
+}
+
+#[test]
+fn sessions_consumed_independently() {
+	let consumed = Consumed::default();
+	let now = 1_000_000;
+
+	assert!(consumed.consume(key("DEVICE", "first"), now));
+	assert!(consumed.consume(key("DEVICE", "second"), now));
+	assert!(consumed.consume(key("OTHER", "first"), now));
+	assert!(!consumed.consume(key("OTHER", "first"), now));
+}
+
+#[test]
+fn consumed_sessions_forgotten_after_retention() {
+	let consumed = Consumed::default();
+	let now = 1_000_000;
+
+	assert!(consumed.consume(key("DEVICE", "session"), now));
+	assert!(consumed.consume(key("DEVICE", "session"), now + DAY + 1));
+}
+
+#[test]
+fn retention_of_persisted_sessions() {
+	assert!(retained(DAY, DAY));
+	assert!(retained(DAY, DAY + DAY - 1));
+	assert!(!retained(DAY, DAY + DAY));
+	assert!(retained(DAY, 0), "clock going backwards keeps the session");
+}
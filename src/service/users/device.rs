@@ -1,5 +1,3 @@
-use std::sync::Arc;
-
 use futures::{Stream, StreamExt};
 use ruma::{
 	DeviceId, MilliSecondsSinceUnixEpoch, UserId, api::client::device::Device,
@@ -8,9 +6,9 @@
 use serde_json::json;
 use tuwunel_core::{
 	Err, Result, at, implement,
-	utils::{self, ReadyExt, stream::TryIgnore},
+	utils::{ReadyExt, stream::TryIgnore},
 };
-use tuwunel_database::{Deserialized, Ignore, Interfix, Json, Map};
+use tuwunel_database::{Batch, Deserialized, Ignore, Interfix, Json};
 
 /// Adds a new device to a user.
 #[implement(super::Service)]
@@ -36,7 +34,9 @@ pub async fn create_device(
 		last_seen_ts: Some(MilliSecondsSinceUnixEpoch::now()),
 	};
 
-	increment(&self.db.userid_devicelistversion, user_id.as_bytes());
+	self.db
+		.userid_devicelistversion
+		.increment(user_id.as_bytes());
 	self.db.userdeviceid_metadata.put(key, Json(val));
 	self.set_token(user_id, device_id, token).await
 }
@@ -44,7 +44,9 @@ pub async fn create_device(
 /// Removes a device from a user.
 #[implement(super::Service)]
 pub async fn remove_device(&self, user_id: &UserId, device_id: &DeviceId) {
+	let device_lock = self.device_mutex.lock(user_id).await;
 	let userdeviceid = (user_id, device_id);
+	let mut batch = Batch::default();
 
 	// Remove tokens
 	if let Ok(old_token) = self
@@ -53,8 +55,8 @@ pub async fn remove_device(&self, user_id: &UserId, device_id: &DeviceId) {
 		.qry(&userdeviceid)
 		.await
 	{
-		self.db.userdeviceid_token.del(userdeviceid);
-		self.db.token_userdeviceid.remove(&old_token);
+		batch.del(&self.db.userdeviceid_token, userdeviceid);
+		batch.remove(&self.db.token_userdeviceid, &old_token);
 	}
 
 	// Remove todevice events
@@ -63,14 +65,21 @@ pub async fn remove_device(&self, user_id: &UserId, device_id: &DeviceId) {
 		.todeviceid_events
 		.keys_prefix_raw(&prefix)
 		.ignore_err()
-		.ready_for_each(|key| self.db.todeviceid_events.remove(key))
+		.ready_for_each(|key| batch.remove(&self.db.todeviceid_events, key))
 		.await;
 
 	// TODO: Remove onetimekeys
 
-	increment(&self.db.userid_devicelistversion, user_id.as_bytes());
+	batch.del(&self.db.userdeviceid_connections, userdeviceid);
+	batch.del(&self.db.userdeviceid_impersonationexpiresat, userdeviceid);
+	batch.del(&self.db.userdeviceid_metadata, userdeviceid);
+	batch.commit();
+	drop(device_lock);
+
+	self.db
+		.userid_devicelistversion
+		.increment(user_id.as_bytes());
 
-	self.db.userdeviceid_metadata.del(userdeviceid);
 	self.mark_device_key_update(user_id).await;
 }
 
@@ -165,7 +174,10 @@ pub async fn update_device_metadata(
 	device_id: &DeviceId,
 	device: &Device,
 ) -> Result {
-	increment(&self.db.userid_devicelistversion, user_id.as_bytes());
+	let _lock = self.device_mutex.lock(user_id).await;
+	self.db
+		.userid_devicelistversion
+		.increment(user_id.as_bytes());
 
 	let key = (user_id, device_id);
 	self.db
@@ -210,10 +222,3 @@ pub fn all_devices_metadata<'a>(
 		.ignore_err()
 		.map(|(_, val): (Ignore, Device)| val)
 }
-
-//TODO: this is an ABA
-fn increment(db: &Arc<Map>, key: &[u8]) {
-	let old = db.get_blocking(key);
-	let new = utils::increment(old.ok().as_deref());
-	db.insert(key, new);
-}
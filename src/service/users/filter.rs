@@ -0,0 +1,120 @@
+//! Sync filters uploaded by clients. Filters are keyed by a digest of their
+//! content so re-uploading an identical filter reuses its ID, and filters
+//! which go unused for `filter_max_age` are deleted.
+
+use std::time::Duration;
+
+use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+use futures::StreamExt;
+use ruma::{OwnedUserId, UserId, api::client::filter::FilterDefinition};
+use tuwunel_core::{
+	Result, debug_info, implement,
+	utils::{self, ReadyExt, hash::sha256, stream::TryIgnore},
+};
+use tuwunel_database::{Deserialized, Json};
+
+/// Bytes of the content digest used for the filter ID.
+const FILTER_ID_LENGTH: usize = 12;
+
+/// The last use of a filter is recorded at most this often.
+const LAST_USED_RESOLUTION: Duration = Duration::from_secs(60 * 60 * 24);
+
+/// Creates a new sync filter, or finds the user's identical filter. Returns
+/// the filter id.
+#[implement(super::Service)]
+pub fn create_filter(&self, user_id: &UserId, filter: &FilterDefinition) -> Result<String> {
+	let content = serde_json::to_vec(filter)?;
+	let digest = sha256::hash(&content);
+	let filter_id = URL_SAFE_NO_PAD.encode(&digest[..FILTER_ID_LENGTH]);
+
+	let key = (user_id, &filter_id);
+	self.db.userfilterid_filter.put(key, Json(filter));
+	self.db
+		.userfilterid_lastused
+		.put(key, utils::millis_since_unix_epoch());
+
+	Ok(filter_id)
+}
+
+#[implement(super::Service)]
+pub async fn get_filter(&self, user_id: &UserId, filter_id: &str) -> Result<FilterDefinition> {
+	let key = (user_id, filter_id);
+	let filter = self
+		.db
+		.userfilterid_filter
+		.qry(&key)
+		.await
+		.deserialized()?;
+
+	let now = utils::millis_since_unix_epoch();
+	let resolution = u64::try_from(LAST_USED_RESOLUTION.as_millis()).unwrap_or(u64::MAX);
+	let stale = self
+		.db
+		.userfilterid_lastused
+		.qry(&key)
+		.await
+		.deserialized::<u64>()
+		.is_none_or(|last_used| now.saturating_sub(last_used) > resolution);
+
+	if stale {
+		self.db.userfilterid_lastused.put(key, now);
+	}
+
+	Ok(filter)
+}
+
+/// Delete filters which have not been used within `max_age`. Filters stored
+/// before usage was tracked are treated as used now. Returns the number
+/// deleted.
+#[implement(super::Service)]
+pub async fn prune_filters(&self, max_age: Duration) -> usize {
+	let now = utils::millis_since_unix_epoch();
+	let max_age = u64::try_from(max_age.as_millis()).unwrap_or(u64::MAX);
+
+	let untracked: Vec<(OwnedUserId, String)> = self
+		.db
+		.userfilterid_filter
+		.keys()
+		.ignore_err()
+		.map(|(user_id, filter_id): (&UserId, &str)| (user_id.to_owned(), filter_id.to_owned()))
+		.filter_map(|key| async move {
+			self.db
+				.userfilterid_lastused
+				.qry(&key)
+				.await
+				.is_err()
+				.then_some(key)
+		})
+		.collect()
+		.await;
+
+	for (user_id, filter_id) in &untracked {
+		self.db
+			.userfilterid_lastused
+			.put((user_id, filter_id), now);
+	}
+
+	let unused: Vec<(OwnedUserId, String)> = self
+		.db
+		.userfilterid_lastused
+		.stream()
+		.ignore_err()
+		.ready_filter_map(|((user_id, filter_id), last_used): ((&UserId, &str), u64)| {
+			(now.saturating_sub(last_used) > max_age)
+				.then(|| (user_id.to_owned(), filter_id.to_owned()))
+		})
+		.collect()
+		.await;
+
+	for (user_id, filter_id) in &unused {
+		let key = (user_id, filter_id);
+		self.db.userfilterid_filter.del(key);
+		self.db.userfilterid_lastused.del(key);
+	}
+
+	if !unused.is_empty() {
+		debug_info!("Deleted {} unused sync filters.", unused.len());
+	}
+
+	unused.len()
+}
@@ -0,0 +1,118 @@
+//! Short-lived access tokens minted by admins to act as a user while
+//! reproducing problems. Each token belongs to its own device, whose ID has a
+//! prefix clients may not choose. Revoking removes only the token.
+
+use std::{num::Saturating as Sat, time::Duration};
+
+use futures::StreamExt;
+use ruma::{DeviceId, OwnedDeviceId, UserId};
+use tuwunel_core::{
+	Result, implement,
+	utils::{self, ReadyExt},
+};
+use tuwunel_database::Deserialized;
+
+/// Device ID prefix of impersonation devices, reserved so that clients cannot
+/// create devices which look like one; only these are checked for expiration.
+const IMPERSONATION_DEVICE_PREFIX: &str = "TUWUNEL_IMPERSONATION_";
+
+const DEVICE_DISPLAY_NAME: &str = "Admin impersonation";
+
+const TOKEN_LENGTH: usize = 32;
+
+/// Create a device for the user with an access token expiring after `ttl`.
+/// Returns the device ID, the token and its expiration in milliseconds since
+/// the epoch.
+#[implement(super::Service)]
+pub async fn create_impersonation_token(
+	&self,
+	user_id: &UserId,
+	ttl: Duration,
+) -> Result<(OwnedDeviceId, String, u64)> {
+	let device_id: OwnedDeviceId =
+		format!("{IMPERSONATION_DEVICE_PREFIX}{}", utils::random_string(8)).into();
+
+	let token = utils::random_string(TOKEN_LENGTH);
+	let ttl = u64::try_from(ttl.as_millis()).unwrap_or(u64::MAX);
+	let expires_at = (Sat(utils::millis_since_unix_epoch()) + Sat(ttl)).0;
+
+	self.create_device(user_id, &device_id, &token, Some(DEVICE_DISPLAY_NAME.to_owned()), None)
+		.await?;
+
+	self.db
+		.userdeviceid_impersonationexpiresat
+		.put((user_id, &*device_id), expires_at);
+
+	Ok((device_id, token, expires_at))
+}
+
+/// Revoke the access token of every impersonation device of the user, expired
+/// or not. Returns the number revoked.
+#[implement(super::Service)]
+pub async fn revoke_impersonation_tokens(&self, user_id: &UserId) -> usize {
+	let devices: Vec<OwnedDeviceId> = self
+		.all_device_ids(user_id)
+		.ready_filter(|device_id| is_impersonation_device(device_id))
+		.map(ToOwned::to_owned)
+		.collect()
+		.await;
+
+	let mut revoked = 0_usize;
+	for device_id in &devices {
+		if self
+			.revoke_impersonation_token(user_id, device_id)
+			.await
+		{
+			revoked = revoked.saturating_add(1);
+		}
+	}
+
+	revoked
+}
+
+/// Remove the access token of an impersonation device, keeping the device.
+/// Returns whether it had one.
+#[implement(super::Service)]
+pub(super) async fn revoke_impersonation_token(
+	&self,
+	user_id: &UserId,
+	device_id: &DeviceId,
+) -> bool {
+	let key = (user_id, device_id);
+	self.db
+		.userdeviceid_impersonationexpiresat
+		.del(key);
+
+	let Ok(token) = self.db.userdeviceid_token.qry(&key).await else {
+		return false;
+	};
+
+	self.db.userdeviceid_token.del(key);
+	self.db.token_userdeviceid.remove(&token);
+
+	true
+}
+
+/// Whether the device is an impersonation device past its expiration.
+#[implement(super::Service)]
+pub async fn is_impersonation_expired(&self, user_id: &UserId, device_id: &DeviceId) -> bool {
+	if !is_impersonation_device(device_id) {
+		return false;
+	}
+
+	self.db
+		.userdeviceid_impersonationexpiresat
+		.qry(&(user_id, device_id))
+		.await
+		.deserialized::<u64>()
+		.is_ok_and(|expires_at| expires_at < utils::millis_since_unix_epoch())
+}
+
+/// Whether the device ID has the prefix reserved for impersonation devices;
+/// clients may not choose such an ID.
+#[must_use]
+pub fn is_impersonation_device(device_id: &DeviceId) -> bool {
+	device_id
+		.as_str()
+		.starts_with(IMPERSONATION_DEVICE_PREFIX)
+}
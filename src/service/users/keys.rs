@@ -12,7 +12,7 @@
 	Err, Error, Result, err, implement,
 	utils::{ReadyExt, stream::TryIgnore, string::Unquoted},
 };
-use tuwunel_database::{Deserialized, Ignore, Json};
+use tuwunel_database::{Batch, Deserialized, Ignore, Json};
 
 #[implement(super::Service)]
 pub async fn add_one_time_key(
@@ -248,6 +248,32 @@ pub async fn add_cross_signing_keys(
 	Ok(())
 }
 
+/// Remove the user's master, self-signing and user-signing keys so their
+/// clients can set up cross-signing again. Returns the number removed.
+#[implement(super::Service)]
+pub async fn remove_cross_signing_keys(&self, user_id: &UserId) -> usize {
+	let maps = [
+		&self.db.userid_masterkeyid,
+		&self.db.userid_selfsigningkeyid,
+		&self.db.userid_usersigningkeyid,
+	];
+
+	let mut removed: usize = 0;
+	for map in maps {
+		if let Ok(key_id) = map.get(user_id).await {
+			self.db.keyid_key.remove(&*key_id);
+			map.remove(user_id);
+			removed = removed.saturating_add(1);
+		}
+	}
+
+	if removed > 0 {
+		self.mark_device_key_update(user_id).await;
+	}
+
+	removed
+}
+
 #[implement(super::Service)]
 pub async fn sign_key(
 	&self,
@@ -340,7 +366,11 @@ fn keys_changed_user_or_room<'a>(
 
 #[implement(super::Service)]
 pub async fn mark_device_key_update(&self, user_id: &UserId) {
+	// Device list and signing key updates from a remote user's server end here.
+	self.invalidate_remote_keys(user_id);
+
 	let count = self.services.globals.next_count().unwrap();
+	let mut batch = Batch::default();
 
 	self.services
 			.state_cache
@@ -349,12 +379,13 @@ pub async fn mark_device_key_update(&self, user_id: &UserId) {
 			.filter(|room_id| self.services.state_accessor.is_encrypted_room(room_id))
 			.ready_for_each(|room_id| {
 				let key = (room_id, count);
-				self.db.keychangeid_userid.put_raw(key, user_id);
+				batch.put_raw(&self.db.keychangeid_userid, key, user_id);
 			})
 			.await;
 
 	let key = (user_id, count);
-	self.db.keychangeid_userid.put_raw(key, user_id);
+	batch.put_raw(&self.db.keychangeid_userid, key, user_id);
+	batch.commit();
 }
 
 #[implement(super::Service)]
@@ -0,0 +1,113 @@
+//! Recently seen addresses of each device.
+
+use std::{
+	collections::HashMap,
+	sync::Mutex,
+	time::{Duration, Instant},
+};
+
+use ruma::{DeviceId, MilliSecondsSinceUnixEpoch, OwnedDeviceId, OwnedUserId, UserId};
+use serde::{Deserialize, Serialize};
+use tuwunel_core::implement;
+use tuwunel_database::{Deserialized, Json};
+
+/// Distinct address and user-agent pairs remembered for each device.
+const MAX_CONNECTIONS: usize = 16;
+
+/// Requests from an unchanged address are recorded at most this often.
+const UPDATE_INTERVAL: Duration = Duration::from_secs(60 * 5);
+
+/// Devices remembered as recently recorded; beyond this, entries older than
+/// `UPDATE_INTERVAL` are pruned, or all of them when none are.
+const MAX_RECENT: usize = 65_536;
+
+pub(super) type Recent = Mutex<HashMap<(OwnedUserId, OwnedDeviceId), (String, Instant)>>;
+
+/// A device's use of the server from one address and client.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Connection {
+	pub ip: String,
+	pub user_agent: Option<String>,
+	pub last_seen: MilliSecondsSinceUnixEpoch,
+}
+
+#[derive(Default, Deserialize, Serialize)]
+struct Connections {
+	connections: Vec<Connection>,
+}
+
+/// Record a request from a device, updating its last seen address and time.
+#[implement(super::Service)]
+pub async fn update_last_seen(
+	&self,
+	user_id: &UserId,
+	device_id: &DeviceId,
+	ip: &str,
+	user_agent: Option<&str>,
+) {
+	let recent = (user_id.to_owned(), device_id.to_owned());
+	{
+		let mut last_seen = self.last_seen.lock().expect("locked");
+		if last_seen
+			.get(&recent)
+			.is_some_and(|(last_ip, at)| last_ip == ip && at.elapsed() < UPDATE_INTERVAL)
+		{
+			return;
+		}
+
+		if last_seen.len() >= MAX_RECENT {
+			last_seen.retain(|_, (_, at)| at.elapsed() < UPDATE_INTERVAL);
+		}
+
+		if last_seen.len() >= MAX_RECENT {
+			last_seen.clear();
+		}
+
+		last_seen.insert(recent, (ip.to_owned(), Instant::now()));
+	}
+
+	// Serialized with other changes to the device, which would otherwise be
+	// lost, or restore a removed device.
+	let _lock = self.device_mutex.lock(user_id).await;
+	let Ok(mut device) = self.get_device_metadata(user_id, device_id).await else {
+		return;
+	};
+
+	let now = MilliSecondsSinceUnixEpoch::now();
+	let mut connections = self.connections(user_id, device_id).await;
+	connections.retain(|connection| {
+		connection.ip != ip || connection.user_agent.as_deref() != user_agent
+	});
+
+	connections.insert(0, Connection {
+		ip: ip.to_owned(),
+		user_agent: user_agent.map(ToOwned::to_owned),
+		last_seen: now,
+	});
+
+	connections.truncate(MAX_CONNECTIONS);
+
+	let key = (user_id, device_id);
+	self.db
+		.userdeviceid_connections
+		.put(key, Json(Connections { connections }));
+
+	// Not a change to the device list; its version is left alone.
+	device.last_seen_ip = Some(ip.to_owned());
+	device.last_seen_ts = Some(now);
+	self.db
+		.userdeviceid_metadata
+		.put(key, Json(device));
+}
+
+/// Recent connections of a device, most recent first.
+#[implement(super::Service)]
+pub async fn connections(&self, user_id: &UserId, device_id: &DeviceId) -> Vec<Connection> {
+	self.db
+		.userdeviceid_connections
+		.qry(&(user_id, device_id))
+		.await
+		.deserialized::<Connections>()
+		.unwrap_or_default()
+		.connections
+}
@@ -1,34 +1,75 @@
 mod device;
+mod filter;
+mod impersonation;
 mod keys;
+mod last_seen;
 mod ldap;
+mod openid;
+mod passkey;
+mod password_provider;
 mod profile;
+mod ratelimit;
+mod registration_throttle;
+mod remote_keys;
+mod totp;
+mod validity;
 
-use std::sync::Arc;
+#[cfg(test)]
+mod tests;
 
+use std::{fmt::Write, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
 use futures::{Stream, StreamExt, TryFutureExt};
 use ruma::{
 	DeviceId, OwnedDeviceId, OwnedMxcUri, OwnedUserId, UserId,
-	api::client::filter::FilterDefinition,
 	events::{GlobalAccountDataEventType, ignored_user_list::IgnoredUserListEvent},
 };
+use tokio::time::sleep;
 use tuwunel_core::{
-	Err, Result, Server, debug_warn, err, is_equal_to, trace,
-	utils::{self, ReadyExt, stream::TryIgnore},
+	Err, Result, Server,
+	config::CacheKind,
+	err, is_equal_to, trace,
+	utils::{self, MutexMap, ReadyExt, stream::TryIgnore},
 };
 use tuwunel_database::{Deserialized, Json, Map};
 
-pub use self::keys::parse_master_key;
-use crate::{Dep, account_data, admin, globals, rooms};
+pub use self::{
+	impersonation::is_impersonation_device,
+	keys::parse_master_key,
+	last_seen::Connection,
+	openid::{OpenIdRestrictions, OpenIdVerifier},
+	password_provider::{PasswordProviderAuth, PasswordProviderOutcome},
+	ratelimit::RateLimit,
+	registration_throttle::RegistrationThrottle,
+	remote_keys::RemoteKeys,
+	totp::TotpEnrollment,
+};
+use crate::{Dep, account_data, admin, client, globals, rooms};
+
+/// How often filters unused beyond `filter_max_age` are deleted, and renewal
+/// links are sent for accounts about to expire.
+const MAINTENANCE_INTERVAL: Duration = Duration::from_secs(60 * 60 * 6);
 
 pub struct Service {
 	services: Services,
 	db: Data,
+	account_expirations: validity::Expirations,
+	device_mutex: MutexMap<OwnedUserId, ()>,
+	last_seen: last_seen::Recent,
+	ratelimit: ratelimit::Buckets,
+	remote_keys: remote_keys::Cache,
+	registration_throttle_mutex: MutexMap<String, ()>,
+	totp_mutex: MutexMap<OwnedUserId, ()>,
+	#[cfg(feature = "passkey")]
+	passkey_ceremonies: passkey::Ceremonies,
 }
 
 struct Services {
 	server: Arc<Server>,
 	account_data: Dep<account_data::Service>,
 	admin: Dep<admin::Service>,
+	client: Dep<client::Service>,
 	globals: Dep<globals::Service>,
 	state_accessor: Dep<rooms::state_accessor::Service>,
 	state_cache: Dep<rooms::state_cache::Service>,
@@ -38,33 +79,49 @@ struct Data {
 	keychangeid_userid: Arc<Map>,
 	keyid_key: Arc<Map>,
 	onetimekeyid_onetimekeys: Arc<Map>,
+	renewaltoken_userid: Arc<Map>,
+	subnet_registrationthrottle: Arc<Map>,
 	openidtoken_expiresatuserid: Arc<Map>,
 	logintoken_expiresatuserid: Arc<Map>,
 	todeviceid_events: Arc<Map>,
 	token_userdeviceid: Arc<Map>,
+	userdeviceid_connections: Arc<Map>,
+	userdeviceid_impersonationexpiresat: Arc<Map>,
 	userdeviceid_metadata: Arc<Map>,
 	userdeviceid_token: Arc<Map>,
 	userfilterid_filter: Arc<Map>,
+	userfilterid_lastused: Arc<Map>,
 	userid_avatarurl: Arc<Map>,
 	userid_blurhash: Arc<Map>,
+	userid_bot: Arc<Map>,
 	userid_devicelistversion: Arc<Map>,
 	userid_displayname: Arc<Map>,
+	userid_expiresat: Arc<Map>,
 	userid_lastonetimekeyupdate: Arc<Map>,
 	userid_masterkeyid: Arc<Map>,
+	userid_passkeys: Arc<Map>,
 	userid_password: Arc<Map>,
 	userid_origin: Arc<Map>,
+	userid_ratelimit: Arc<Map>,
+	userid_renewaltoken: Arc<Map>,
 	userid_selfsigningkeyid: Arc<Map>,
+	userid_totp: Arc<Map>,
 	userid_usersigningkeyid: Arc<Map>,
 	useridprofilekey_value: Arc<Map>,
 }
 
+#[async_trait]
 impl crate::Service for Service {
 	fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
+		let config = &args.server.config;
+		let remote_keys_cache_size = config.cache_capacity(CacheKind::RemoteDeviceKeys)?;
+
 		Ok(Arc::new(Self {
 			services: Services {
 				server: args.server.clone(),
 				account_data: args.depend::<account_data::Service>("account_data"),
 				admin: args.depend::<admin::Service>("admin"),
+				client: args.depend::<client::Service>("client"),
 				globals: args.depend::<globals::Service>("globals"),
 				state_accessor: args
 					.depend::<rooms::state_accessor::Service>("rooms::state_accessor"),
@@ -74,28 +131,88 @@ fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
 				keychangeid_userid: args.db["keychangeid_userid"].clone(),
 				keyid_key: args.db["keyid_key"].clone(),
 				onetimekeyid_onetimekeys: args.db["onetimekeyid_onetimekeys"].clone(),
+				renewaltoken_userid: args.db["renewaltoken_userid"].clone(),
+				subnet_registrationthrottle: args.db["subnet_registrationthrottle"].clone(),
 				openidtoken_expiresatuserid: args.db["openidtoken_expiresatuserid"].clone(),
 				logintoken_expiresatuserid: args.db["logintoken_expiresatuserid"].clone(),
 				todeviceid_events: args.db["todeviceid_events"].clone(),
 				token_userdeviceid: args.db["token_userdeviceid"].clone(),
+				userdeviceid_connections: args.db["userdeviceid_connections"].clone(),
+				userdeviceid_impersonationexpiresat: args.db
+					["userdeviceid_impersonationexpiresat"]
+					.clone(),
 				userdeviceid_metadata: args.db["userdeviceid_metadata"].clone(),
 				userdeviceid_token: args.db["userdeviceid_token"].clone(),
 				userfilterid_filter: args.db["userfilterid_filter"].clone(),
+				userfilterid_lastused: args.db["userfilterid_lastused"].clone(),
 				userid_avatarurl: args.db["userid_avatarurl"].clone(),
 				userid_blurhash: args.db["userid_blurhash"].clone(),
+				userid_bot: args.db["userid_bot"].clone(),
 				userid_devicelistversion: args.db["userid_devicelistversion"].clone(),
 				userid_displayname: args.db["userid_displayname"].clone(),
+				userid_expiresat: args.db["userid_expiresat"].clone(),
 				userid_lastonetimekeyupdate: args.db["userid_lastonetimekeyupdate"].clone(),
 				userid_masterkeyid: args.db["userid_masterkeyid"].clone(),
+				userid_passkeys: args.db["userid_passkeys"].clone(),
 				userid_password: args.db["userid_password"].clone(),
 				userid_origin: args.db["userid_origin"].clone(),
+				userid_ratelimit: args.db["userid_ratelimit"].clone(),
+				userid_renewaltoken: args.db["userid_renewaltoken"].clone(),
 				userid_selfsigningkeyid: args.db["userid_selfsigningkeyid"].clone(),
+				userid_totp: args.db["userid_totp"].clone(),
 				userid_usersigningkeyid: args.db["userid_usersigningkeyid"].clone(),
 				useridprofilekey_value: args.db["useridprofilekey_value"].clone(),
 			},
+			account_expirations: Default::default(),
+			device_mutex: MutexMap::new(),
+			last_seen: Default::default(),
+			ratelimit: Default::default(),
+			remote_keys: remote_keys::Cache::new(remote_keys_cache_size),
+			registration_throttle_mutex: MutexMap::new(),
+			totp_mutex: MutexMap::new(),
+			#[cfg(feature = "passkey")]
+			passkey_ceremonies: Default::default(),
 		}))
 	}
 
+	async fn worker(self: Arc<Self>) -> Result {
+		if self.services.globals.is_read_only() {
+			return Ok(());
+		}
+
+		let max_age = self.services.server.config.filter_max_age;
+		while self.services.server.running() {
+			if max_age > 0 {
+				self.prune_filters(Duration::from_secs(max_age))
+					.await;
+			}
+
+			self.send_renewal_notices().await;
+			tokio::select! {
+				() = sleep(MAINTENANCE_INTERVAL) => {},
+				() = self.services.server.until_shutdown() => break,
+			}
+		}
+
+		Ok(())
+	}
+
+	async fn memory_usage(&self, out: &mut (dyn Write + Send)) -> Result {
+		let (len, cap) = self.remote_keys.usage();
+
+		writeln!(out, "remote_device_keys_cache: {len} / {cap}")?;
+
+		Ok(())
+	}
+
+	async fn clear_cache(&self) {
+		self.remote_keys.clear();
+		self.account_expirations
+			.write()
+			.expect("locked")
+			.clear();
+	}
+
 	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
 }
 
@@ -138,6 +255,7 @@ pub async fn create(
 			|| self.db.userid_origin.insert(user_id, "password"),
 			|origin| self.db.userid_origin.insert(user_id, origin),
 		);
+		self.renew_account(user_id, None);
 		self.set_password(user_id, password).await
 	}
 
@@ -153,6 +271,7 @@ pub async fn deactivate_account(&self, user_id: &UserId) -> Result {
 		// Systems like changing the password without logging in should check if the
 		// account is deactivated.
 		self.set_password(user_id, None).await?;
+		self.db.userid_passkeys.remove(user_id);
 
 		// TODO: Unhook 3PID
 		Ok(())
@@ -174,6 +293,22 @@ pub async fn is_deactivated(&self, user_id: &UserId) -> Result<bool> {
 			.await
 	}
 
+	/// Check if the account is flagged as a bot or service account. These are
+	/// left out of the user directory, presence and admin notices, and are not
+	/// rate limited unless given an override.
+	pub async fn is_bot(&self, user_id: &UserId) -> bool {
+		self.db.userid_bot.exists(user_id).await.is_ok()
+	}
+
+	/// Flag or unflag the account as a bot or service account.
+	pub fn set_bot(&self, user_id: &UserId, bot: bool) {
+		if bot {
+			self.db.userid_bot.insert(user_id, []);
+		} else {
+			self.db.userid_bot.remove(user_id);
+		}
+	}
+
 	/// Check if account is active, infallible
 	pub async fn is_active(&self, user_id: &UserId) -> bool {
 		!self.is_deactivated(user_id).await.unwrap_or(true)
@@ -190,11 +325,24 @@ pub async fn count(&self) -> usize { self.db.userid_password.count().await }
 
 	/// Find out which user an access token belongs to.
 	pub async fn find_from_token(&self, token: &str) -> Result<(OwnedUserId, OwnedDeviceId)> {
-		self.db
+		let (user_id, device_id): (OwnedUserId, OwnedDeviceId) = self
+			.db
 			.token_userdeviceid
 			.get(token)
 			.await
-			.deserialized()
+			.deserialized()?;
+
+		if self
+			.is_impersonation_expired(&user_id, &device_id)
+			.await
+		{
+			self.revoke_impersonation_token(&user_id, &device_id)
+				.await;
+
+			return Err!(Request(Forbidden("Access token has expired.")));
+		}
+
+		Ok((user_id, device_id))
 	}
 
 	/// Returns an iterator over all users on this homeserver (offered for
@@ -262,7 +410,9 @@ pub async fn set_password(&self, user_id: &UserId, password: Option<&str>) -> Re
 		}
 
 		password
-			.map(utils::hash::password)
+			.map(|password| {
+				utils::hash::password(password, &self.services.server.config.password_params())
+			})
 			.transpose()
 			.map_err(|e| {
 				err!(Request(InvalidParam("Password does not meet the requirements: {e}")))
@@ -376,86 +526,23 @@ pub async fn set_token(&self, user_id: &UserId, device_id: &DeviceId, token: &st
 		Ok(())
 	}
 
-	/// Creates a new sync filter. Returns the filter id.
-	pub fn create_filter(&self, user_id: &UserId, filter: &FilterDefinition) -> String {
-		let filter_id = utils::random_string(4);
-
-		let key = (user_id, &filter_id);
-		self.db.userfilterid_filter.put(key, Json(filter));
-
-		filter_id
+	/// Creates a short-lived login token, which can be used to log in using the
+	/// `m.login.token` mechanism.
+	pub fn create_login_token(&self, user_id: &UserId, token: &str) -> u64 {
+		let expires_in = self.services.server.config.login_token_ttl;
+		self.create_login_token_expiring(user_id, token, expires_in)
 	}
 
-	pub async fn get_filter(
+	/// Creates a login token valid for `expires_in` milliseconds rather than
+	/// the configured `login_token_ttl`.
+	pub fn create_login_token_expiring(
 		&self,
 		user_id: &UserId,
-		filter_id: &str,
-	) -> Result<FilterDefinition> {
-		let key = (user_id, filter_id);
-		self.db
-			.userfilterid_filter
-			.qry(&key)
-			.await
-			.deserialized()
-	}
-
-	/// Creates an OpenID token, which can be used to prove that a user has
-	/// access to an account (primarily for integrations)
-	pub fn create_openid_token(&self, user_id: &UserId, token: &str) -> Result<u64> {
+		token: &str,
+		expires_in: u64,
+	) -> u64 {
 		use std::num::Saturating as Sat;
 
-		let expires_in = self.services.server.config.openid_token_ttl;
-		let expires_at = Sat(utils::millis_since_unix_epoch()) + Sat(expires_in) * Sat(1000);
-
-		let mut value = expires_at.0.to_be_bytes().to_vec();
-		value.extend_from_slice(user_id.as_bytes());
-
-		self.db
-			.openidtoken_expiresatuserid
-			.insert(token.as_bytes(), value.as_slice());
-
-		Ok(expires_in)
-	}
-
-	/// Find out which user an OpenID access token belongs to.
-	pub async fn find_from_openid_token(&self, token: &str) -> Result<OwnedUserId> {
-		let Ok(value) = self
-			.db
-			.openidtoken_expiresatuserid
-			.get(token)
-			.await
-		else {
-			return Err!(Request(Unauthorized("OpenID token is unrecognised")));
-		};
-
-		let (expires_at_bytes, user_bytes) = value.split_at(0_u64.to_be_bytes().len());
-		let expires_at =
-			u64::from_be_bytes(expires_at_bytes.try_into().map_err(|e| {
-				err!(Database("expires_at in openid_userid is invalid u64. {e}"))
-			})?);
-
-		if expires_at < utils::millis_since_unix_epoch() {
-			debug_warn!("OpenID token is expired, removing");
-			self.db
-				.openidtoken_expiresatuserid
-				.remove(token.as_bytes());
-
-			return Err!(Request(Unauthorized("OpenID token is expired")));
-		}
-
-		let user_string = utils::string_from_bytes(user_bytes)
-			.map_err(|e| err!(Database("User ID in openid_userid is invalid unicode. {e}")))?;
-
-		OwnedUserId::try_from(user_string)
-			.map_err(|e| err!(Database("User ID in openid_userid is invalid. {e}")))
-	}
-
-	/// Creates a short-lived login token, which can be used to log in using the
-	/// `m.login.token` mechanism.
-	pub fn create_login_token(&self, user_id: &UserId, token: &str) -> u64 {
-		use std::num::Saturating as Sat;
-
-		let expires_in = self.services.server.config.login_token_ttl;
 		let expires_at = Sat(utils::millis_since_unix_epoch()) + Sat(expires_in);
 
 		let value = (expires_at.0, user_id);
@@ -501,4 +588,52 @@ pub async fn search_ldap(&self, _user_id: &UserId) -> Result<Vec<(String, bool)>
 	pub async fn auth_ldap(&self, _user_dn: &str, _password: &str) -> Result {
 		Err!(FeatureDisabled("ldap"))
 	}
+
+	#[cfg(not(feature = "passkey"))]
+	pub fn passkeys_available(&self) -> bool { false }
+
+	#[cfg(not(feature = "passkey"))]
+	pub async fn start_passkey_registration(
+		&self,
+		_user_id: &UserId,
+	) -> Result<serde_json::Value> {
+		Err!(FeatureDisabled("passkey"))
+	}
+
+	#[cfg(not(feature = "passkey"))]
+	pub async fn finish_passkey_registration(
+		&self,
+		_user_id: &UserId,
+		_credential: serde_json::Value,
+		_name: Option<String>,
+	) -> Result {
+		Err!(FeatureDisabled("passkey"))
+	}
+
+	#[cfg(not(feature = "passkey"))]
+	pub async fn start_passkey_login(
+		&self,
+		_user_id: &UserId,
+	) -> Result<(String, serde_json::Value)> {
+		Err!(FeatureDisabled("passkey"))
+	}
+
+	#[cfg(not(feature = "passkey"))]
+	pub async fn finish_passkey_login(
+		&self,
+		_session: &str,
+		_credential: serde_json::Value,
+	) -> Result<OwnedUserId> {
+		Err!(FeatureDisabled("passkey"))
+	}
+
+	#[cfg(not(feature = "passkey"))]
+	pub async fn list_passkeys(&self, _user_id: &UserId) -> Result<Vec<serde_json::Value>> {
+		Err!(FeatureDisabled("passkey"))
+	}
+
+	#[cfg(not(feature = "passkey"))]
+	pub async fn delete_passkey(&self, _user_id: &UserId, _id: &str) -> Result {
+		Err!(FeatureDisabled("passkey"))
+	}
 }
@@ -0,0 +1,223 @@
+//! OpenID tokens, which prove to a third-party service that it is talking to
+//! a user of this server. A token may be restricted to one audience, the
+//! service's server name or client ID, and to a set of scopes. The service
+//! names itself and the scope it needs when it asks who the token belongs to,
+//! so a token given to one service cannot be replayed against another.
+
+use std::num::Saturating as Sat;
+
+use ruma::{OwnedUserId, UserId};
+use tuwunel_core::{Err, Result, debug_warn, err, implement, utils};
+
+/// Separates the user ID, audience and scopes in stored tokens; it cannot
+/// occur in UTF-8.
+const SEPARATOR: u8 = 0xFF;
+
+const AUDIENCE_MAX_LEN: usize = 255;
+
+/// The restrictions a client asked for when requesting a token.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct OpenIdRestrictions {
+	/// The server name or client ID of the only service the token is for.
+	pub audience: Option<String>,
+
+	/// Scopes the service may ask the token to be valid for; any when empty.
+	pub scopes: Vec<String>,
+}
+
+/// What the service verifying a token says about itself.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct OpenIdVerifier<'a> {
+	pub audience: Option<&'a str>,
+
+	/// Space-separated scopes the service needs.
+	pub scope: Option<&'a str>,
+}
+
+impl OpenIdRestrictions {
+	/// Validates the restrictions from a client's request; `scope` is
+	/// space-separated like an OAuth 2.0 scope.
+	pub fn parse(audience: Option<&str>, scope: Option<&str>) -> Result<Self> {
+		if let Some(audience) = audience {
+			if audience.is_empty()
+				|| audience.len() > AUDIENCE_MAX_LEN
+				|| audience.contains(|c: char| c.is_whitespace() || c.is_control())
+			{
+				return Err!(Request(InvalidParam(
+					"audience must be a server name or client ID"
+				)));
+			}
+		}
+
+		let scopes: Vec<_> = scope
+			.unwrap_or_default()
+			.split(' ')
+			.filter(|scope| !scope.is_empty())
+			.map(ToOwned::to_owned)
+			.collect();
+
+		if !scopes.iter().all(|scope| is_scope_token(scope)) {
+			return Err!(Request(InvalidParam("scope contains an invalid scope token")));
+		}
+
+		Ok(Self {
+			audience: audience.map(ToOwned::to_owned),
+			scopes,
+		})
+	}
+
+	/// Whether the service may accept the token.
+	#[must_use]
+	pub fn permits(&self, verifier: &OpenIdVerifier<'_>) -> bool {
+		let audience_permitted = self
+			.audience
+			.as_deref()
+			.is_none_or(|audience| verifier.audience == Some(audience));
+
+		let requested: Vec<_> = verifier
+			.scope
+			.unwrap_or_default()
+			.split(' ')
+			.filter(|scope| !scope.is_empty())
+			.collect();
+
+		let scope_permitted = self.scopes.is_empty()
+			|| (!requested.is_empty()
+				&& requested
+					.iter()
+					.all(|scope| self.scopes.iter().any(|granted| granted == scope)));
+
+		audience_permitted && scope_permitted
+	}
+}
+
+/// Creates an OpenID token, which can be used to prove that a user has access
+/// to an account (primarily for integrations).
+#[implement(super::Service)]
+pub fn create_openid_token(
+	&self,
+	user_id: &UserId,
+	token: &str,
+	restrictions: &OpenIdRestrictions,
+) -> Result<u64> {
+	let expires_in = self.services.server.config.openid_token_ttl;
+	let expires_at = Sat(utils::millis_since_unix_epoch()) + Sat(expires_in) * Sat(1000);
+
+	let value = encode(expires_at.0, user_id, restrictions);
+	self.db
+		.openidtoken_expiresatuserid
+		.insert(token.as_bytes(), value.as_slice());
+
+	Ok(expires_in)
+}
+
+/// Find out which user an OpenID access token belongs to, when the verifying
+/// service is within the token's restrictions.
+#[implement(super::Service)]
+pub async fn find_from_openid_token(
+	&self,
+	token: &str,
+	verifier: &OpenIdVerifier<'_>,
+) -> Result<OwnedUserId> {
+	let Ok(value) = self
+		.db
+		.openidtoken_expiresatuserid
+		.get(token)
+		.await
+	else {
+		return Err!(Request(Unauthorized("OpenID token is unrecognised")));
+	};
+
+	let (expires_at, user_id, restrictions) = decode(&value)?;
+	if expires_at < utils::millis_since_unix_epoch() {
+		debug_warn!("OpenID token is expired, removing");
+		self.db
+			.openidtoken_expiresatuserid
+			.remove(token.as_bytes());
+
+		return Err!(Request(Unauthorized("OpenID token is expired")));
+	}
+
+	if !restrictions.permits(verifier) {
+		debug_warn!(
+			%user_id,
+			audience = ?verifier.audience,
+			scope = ?verifier.scope,
+			"OpenID token used outside its restrictions"
+		);
+		return Err!(Request(Unauthorized("OpenID token was issued for another service")));
+	}
+
+	Ok(user_id)
+}
+
+pub(super) fn encode(
+	expires_at: u64,
+	user_id: &UserId,
+	restrictions: &OpenIdRestrictions,
+) -> Vec<u8> {
+	let mut value = expires_at.to_be_bytes().to_vec();
+	value.extend_from_slice(user_id.as_bytes());
+	if restrictions != &OpenIdRestrictions::default() {
+		value.push(SEPARATOR);
+		value.extend_from_slice(
+			restrictions
+				.audience
+				.as_deref()
+				.unwrap_or_default()
+				.as_bytes(),
+		);
+		value.push(SEPARATOR);
+		value.extend_from_slice(restrictions.scopes.join(" ").as_bytes());
+	}
+
+	value
+}
+
+pub(super) fn decode(value: &[u8]) -> Result<(u64, OwnedUserId, OpenIdRestrictions)> {
+	let (expires_at_bytes, rest) = value
+		.split_at_checked(size_of::<u64>())
+		.ok_or_else(|| err!(Database("OpenID token in openid_userid is truncated.")))?;
+
+	let expires_at = u64::from_be_bytes(
+		expires_at_bytes
+			.try_into()
+			.map_err(|e| err!(Database("expires_at in openid_userid is invalid u64. {e}")))?,
+	);
+
+	let mut parts = rest.splitn(3, |&b| b == SEPARATOR);
+	let user_string = utils::string_from_bytes(parts.next().unwrap_or_default())
+		.map_err(|e| err!(Database("User ID in openid_userid is invalid unicode. {e}")))?;
+
+	let user_id = OwnedUserId::try_from(user_string)
+		.map_err(|e| err!(Database("User ID in openid_userid is invalid. {e}")))?;
+
+	let mut restriction = || {
+		parts
+			.next()
+			.map(utils::string_from_bytes)
+			.transpose()
+			.map_err(|e| err!(Database("Restriction in openid_userid is invalid unicode. {e}")))
+			.map(Option::unwrap_or_default)
+	};
+
+	let audience = restriction()?;
+	let scopes = restriction()?;
+	let restrictions = OpenIdRestrictions {
+		audience: (!audience.is_empty()).then_some(audience),
+		scopes: scopes
+			.split(' ')
+			.filter(|scope| !scope.is_empty())
+			.map(ToOwned::to_owned)
+			.collect(),
+	};
+
+	Ok((expires_at, user_id, restrictions))
+}
+
+/// A `scope-token` of RFC 6749, section 3.3.
+fn is_scope_token(scope: &str) -> bool {
+	scope
+		.bytes()
+		.all(|b| matches!(b, 0x21 | 0x23..=0x5B | 0x5D..=0x7E))
+}
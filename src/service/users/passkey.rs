@@ -0,0 +1,306 @@
+#![cfg(feature = "passkey")]
+
+//! Passkey (WebAuthn) credentials for passwordless login of local users.
+//! Ceremony state lives in memory; a ceremony not finished within
+//! [`CEREMONY_TIMEOUT`] must be restarted.
+
+use std::{
+	collections::HashMap,
+	sync::Mutex,
+	time::{Duration, Instant},
+};
+
+use ruma::{OwnedUserId, UserId, api::client::error::ErrorKind};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use tuwunel_core::{
+	Err, Error, Result, err, implement,
+	utils::{self, hash::sha256},
+};
+use tuwunel_database::{Deserialized, Json};
+use webauthn_rs::prelude::{
+	Passkey, PasskeyAuthentication, PasskeyRegistration, PublicKeyCredential,
+	RegisterPublicKeyCredential, Uuid, Webauthn, WebauthnBuilder,
+};
+
+const CEREMONY_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// Limit on concurrent unauthenticated login ceremonies.
+const MAX_PENDING_LOGINS: usize = 1024;
+
+const SESSION_LENGTH: usize = 32;
+
+#[derive(Default)]
+pub(super) struct Ceremonies {
+	registrations: Mutex<HashMap<OwnedUserId, (PasskeyRegistration, Instant)>>,
+	logins: Mutex<HashMap<String, (OwnedUserId, PasskeyAuthentication, Instant)>>,
+}
+
+#[derive(Default, Deserialize, Serialize)]
+struct Passkeys {
+	passkeys: Vec<StoredPasskey>,
+}
+
+#[derive(Deserialize, Serialize)]
+struct StoredPasskey {
+	name: Option<String>,
+	created: u64,
+	passkey: Passkey,
+}
+
+/// Whether passkeys are configured with `passkey_origin`.
+#[implement(super::Service)]
+pub fn passkeys_available(&self) -> bool {
+	self.services
+		.server
+		.config
+		.passkey_origin
+		.is_some()
+}
+
+/// Begin registering a passkey for the user. Returns the creation challenge
+/// for the client to pass to `navigator.credentials.create()`.
+#[implement(super::Service)]
+pub async fn start_passkey_registration(&self, user_id: &UserId) -> Result<JsonValue> {
+	let exclude: Vec<_> = self
+		.get_passkeys(user_id)
+		.await
+		.passkeys
+		.iter()
+		.map(|stored| stored.passkey.cred_id().clone())
+		.collect();
+
+	let displayname = self
+		.displayname(user_id)
+		.await
+		.unwrap_or_else(|_| user_id.localpart().to_owned());
+
+	let (challenge, state) = self
+		.webauthn()?
+		.start_passkey_registration(
+			user_uuid(user_id),
+			user_id.as_str(),
+			&displayname,
+			Some(exclude),
+		)
+		.map_err(|e| err!(Request(Unknown("Failed to start passkey registration: {e}"))))?;
+
+	let mut registrations = self
+		.passkey_ceremonies
+		.registrations
+		.lock()
+		.expect("locked");
+
+	registrations.retain(|_, (_, started)| started.elapsed() < CEREMONY_TIMEOUT);
+	registrations.insert(user_id.to_owned(), (state, Instant::now()));
+
+	Ok(serde_json::to_value(challenge)?)
+}
+
+/// Complete registration with the client's attestation, storing the passkey.
+#[implement(super::Service)]
+pub async fn finish_passkey_registration(
+	&self,
+	user_id: &UserId,
+	credential: JsonValue,
+	name: Option<String>,
+) -> Result {
+	let credential: RegisterPublicKeyCredential = serde_json::from_value(credential)
+		.map_err(|e| err!(Request(BadJson("Invalid passkey credential: {e}"))))?;
+
+	let (state, started) = self
+		.passkey_ceremonies
+		.registrations
+		.lock()
+		.expect("locked")
+		.remove(user_id)
+		.ok_or_else(|| err!(Request(NotFound("No passkey registration in progress."))))?;
+
+	if started.elapsed() >= CEREMONY_TIMEOUT {
+		return Err!(Request(Forbidden("Passkey registration has expired.")));
+	}
+
+	let passkey = self
+		.webauthn()?
+		.finish_passkey_registration(&credential, &state)
+		.map_err(|e| err!(Request(Forbidden("Passkey registration failed: {e}"))))?;
+
+	let mut passkeys = self.get_passkeys(user_id).await;
+	passkeys.passkeys.push(StoredPasskey {
+		name,
+		created: utils::millis_since_unix_epoch(),
+		passkey,
+	});
+
+	self.put_passkeys(user_id, &passkeys);
+
+	Ok(())
+}
+
+/// Begin a login with one of the user's passkeys. Returns the session to
+/// present at `/login` and the request challenge for
+/// `navigator.credentials.get()`.
+#[implement(super::Service)]
+pub async fn start_passkey_login(&self, user_id: &UserId) -> Result<(String, JsonValue)> {
+	let passkeys: Vec<Passkey> = self
+		.get_passkeys(user_id)
+		.await
+		.passkeys
+		.into_iter()
+		.map(|stored| stored.passkey)
+		.collect();
+
+	if passkeys.is_empty() || !self.is_active_local(user_id).await {
+		return Err!(Request(Forbidden("Passkey login is not available for this user.")));
+	}
+
+	let (challenge, state) = self
+		.webauthn()?
+		.start_passkey_authentication(&passkeys)
+		.map_err(|e| err!(Request(Unknown("Failed to start passkey login: {e}"))))?;
+
+	let mut logins = self
+		.passkey_ceremonies
+		.logins
+		.lock()
+		.expect("locked");
+
+	logins.retain(|_, (_, _, started)| started.elapsed() < CEREMONY_TIMEOUT);
+	if logins.len() >= MAX_PENDING_LOGINS {
+		return Err(Error::Request(
+			ErrorKind::LimitExceeded { retry_after: None },
+			"Too many passkey logins in progress.".into(),
+			http::StatusCode::TOO_MANY_REQUESTS,
+		));
+	}
+
+	let session = utils::random_string(SESSION_LENGTH);
+	logins.insert(session.clone(), (user_id.to_owned(), state, Instant::now()));
+
+	Ok((session, serde_json::to_value(challenge)?))
+}
+
+/// Complete a login with the client's assertion. Returns the authenticated
+/// user.
+#[implement(super::Service)]
+pub async fn finish_passkey_login(
+	&self,
+	session: &str,
+	credential: JsonValue,
+) -> Result<OwnedUserId> {
+	let credential: PublicKeyCredential = serde_json::from_value(credential)
+		.map_err(|e| err!(Request(BadJson("Invalid passkey credential: {e}"))))?;
+
+	let (user_id, state, started) = self
+		.passkey_ceremonies
+		.logins
+		.lock()
+		.expect("locked")
+		.remove(session)
+		.ok_or_else(|| err!(Request(Forbidden("Unknown passkey login session."))))?;
+
+	if started.elapsed() >= CEREMONY_TIMEOUT {
+		return Err!(Request(Forbidden("Passkey login has expired.")));
+	}
+
+	let result = self
+		.webauthn()?
+		.finish_passkey_authentication(&credential, &state)
+		.map_err(|e| err!(Request(Forbidden("Passkey login failed: {e}"))))?;
+
+	// Record the authenticator's signature counter to detect cloned keys.
+	let mut passkeys = self.get_passkeys(&user_id).await;
+	if passkeys
+		.passkeys
+		.iter_mut()
+		.filter_map(|stored| stored.passkey.update_credential(&result))
+		.any(|updated| updated)
+	{
+		self.put_passkeys(&user_id, &passkeys);
+	}
+
+	Ok(user_id)
+}
+
+/// The user's passkeys as `id`, `name` and `created` (milliseconds since the
+/// epoch).
+#[implement(super::Service)]
+pub async fn list_passkeys(&self, user_id: &UserId) -> Result<Vec<JsonValue>> {
+	Ok(self
+		.get_passkeys(user_id)
+		.await
+		.passkeys
+		.iter()
+		.map(|stored| {
+			serde_json::json!({
+				"id": stored.passkey.cred_id(),
+				"name": stored.name,
+				"created": stored.created,
+			})
+		})
+		.collect())
+}
+
+/// Remove one of the user's passkeys by its credential ID as listed.
+#[implement(super::Service)]
+pub async fn delete_passkey(&self, user_id: &UserId, id: &str) -> Result {
+	let id = serde_json::Value::String(id.to_owned());
+	let mut passkeys = self.get_passkeys(user_id).await;
+	let count = passkeys.passkeys.len();
+	passkeys.passkeys.retain(|stored| {
+		serde_json::to_value(stored.passkey.cred_id())
+			.ok()
+			.as_ref() != Some(&id)
+	});
+
+	if passkeys.passkeys.len() == count {
+		return Err!(Request(NotFound("No such passkey.")));
+	}
+
+	self.put_passkeys(user_id, &passkeys);
+
+	Ok(())
+}
+
+#[implement(super::Service)]
+fn webauthn(&self) -> Result<Webauthn> {
+	let origin = self
+		.services
+		.server
+		.config
+		.passkey_origin
+		.as_ref()
+		.ok_or_else(|| err!(Request(Forbidden("Passkeys are not enabled."))))?;
+
+	let rp_id = origin
+		.host_str()
+		.ok_or_else(|| err!(Config("passkey_origin", "Origin has no host.")))?;
+
+	WebauthnBuilder::new(rp_id, origin)
+		.map(|builder| builder.rp_name(self.services.globals.server_name().as_str()))
+		.and_then(WebauthnBuilder::build)
+		.map_err(|e| err!(Config("passkey_origin", "Invalid passkey configuration: {e}")))
+}
+
+#[implement(super::Service)]
+async fn get_passkeys(&self, user_id: &UserId) -> Passkeys {
+	self.db
+		.userid_passkeys
+		.get(user_id)
+		.await
+		.deserialized()
+		.unwrap_or_default()
+}
+
+#[implement(super::Service)]
+fn put_passkeys(&self, user_id: &UserId, passkeys: &Passkeys) {
+	self.db
+		.userid_passkeys
+		.raw_put(user_id, Json(passkeys));
+}
+
+/// Stable WebAuthn user handle, derived so it need not be stored.
+fn user_uuid(user_id: &UserId) -> Uuid {
+	let hash = sha256::hash(user_id.as_bytes());
+	Uuid::from_slice(&hash[..16]).expect("sixteen bytes make a uuid")
+}
@@ -0,0 +1,93 @@
+//! Password checks by an external HTTP provider; see
+//! `[global.password_provider]`.
+
+use std::time::Duration;
+
+use http::header::CONTENT_TYPE;
+use ruma::UserId;
+use serde::{Deserialize, Serialize};
+use tuwunel_core::{Result, debug, err, error, implement};
+
+#[derive(Serialize)]
+struct PasswordCheck<'a> {
+	user_id: &'a UserId,
+	password: &'a str,
+}
+
+/// The provider's response to a password check.
+#[derive(Debug, Deserialize)]
+pub struct PasswordProviderAuth {
+	/// Whether the password is the user's.
+	pub authenticated: bool,
+
+	/// Grants or revokes server admin when present.
+	#[serde(default)]
+	pub admin: Option<bool>,
+
+	/// Display name for an account created on first login.
+	#[serde(default)]
+	pub displayname: Option<String>,
+}
+
+/// What a login does with the provider's answer.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PasswordProviderOutcome {
+	/// The provider accepted the password.
+	Authenticated,
+
+	/// The provider refused the password or failed, and the local password is
+	/// checked instead.
+	CheckLocal,
+
+	/// The provider refused the password.
+	Refused,
+
+	/// The provider failed; the login fails with its error.
+	Failed,
+}
+
+impl PasswordProviderOutcome {
+	#[must_use]
+	pub fn of(auth: &Result<PasswordProviderAuth>, fallback_to_local: bool) -> Self {
+		match auth {
+			| Ok(auth) if auth.authenticated => Self::Authenticated,
+			| _ if fallback_to_local => Self::CheckLocal,
+			| Ok(_) => Self::Refused,
+			| Err(_) => Self::Failed,
+		}
+	}
+}
+
+/// Asks the configured password provider whether the password is the user's.
+/// Fails when the provider cannot be reached or its response is invalid.
+#[implement(super::Service)]
+pub async fn auth_password_provider(
+	&self,
+	user_id: &UserId,
+	password: &str,
+) -> Result<PasswordProviderAuth> {
+	let config = &self.services.server.config.password_provider;
+	let url = config.url.as_ref().ok_or_else(|| {
+		err!(Config("password_provider.url", "Password provider URL is not set."))
+	})?;
+
+	debug!(%url, "Checking password with provider");
+	let body = serde_json::to_vec(&PasswordCheck { user_id, password })?;
+	let response = self
+		.services
+		.client
+		.default
+		.post(url.clone())
+		.timeout(Duration::from_secs(config.timeout))
+		.header(CONTENT_TYPE, "application/json")
+		.body(body)
+		.send()
+		.await?
+		.error_for_status()?
+		.bytes()
+		.await?;
+
+	serde_json::from_slice(&response).map_err(|e| {
+		err!(BadServerResponse(error!(%url, "Invalid password provider response: {e}")))
+	})
+}
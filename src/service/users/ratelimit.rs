@@ -0,0 +1,145 @@
+//! Rate limiting of the messages sent by each local user; see
+//! `message_rate_limit_per_second`. Overrides for particular users, such as
+//! bots, are stored in the database.
+
+use std::{
+	collections::HashMap,
+	sync::Mutex,
+	time::{Duration, Instant},
+};
+
+use ruma::{
+	OwnedUserId, UserId,
+	api::client::error::{ErrorKind, RetryAfter},
+};
+use serde::{Deserialize, Serialize};
+use tuwunel_core::{Error, Result, debug_info, implement};
+use tuwunel_database::{Deserialized, Json};
+
+/// Token buckets of users who have sent messages recently.
+pub(super) type Buckets = Mutex<HashMap<OwnedUserId, Bucket>>;
+
+/// Full buckets are only pruned once there are this many.
+const PRUNE_THRESHOLD: usize = 4096;
+
+/// Rate at which a user may send messages.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub struct RateLimit {
+	/// Sustained messages per second; zero is unlimited.
+	pub per_second: f64,
+
+	/// Messages which may be sent at once after a pause.
+	pub burst: u32,
+}
+
+#[derive(Clone, Copy)]
+pub(super) struct Bucket {
+	tokens: f64,
+	updated: Instant,
+	limit: RateLimit,
+}
+
+impl Bucket {
+	/// Whether the bucket has refilled to its burst, so dropping it loses
+	/// nothing.
+	fn is_full(&self, now: Instant) -> bool {
+		let elapsed = now.duration_since(self.updated).as_secs_f64();
+		elapsed.mul_add(self.limit.per_second, self.tokens) >= f64::from(self.limit.burst.max(1))
+	}
+}
+
+/// Take one message from the user's bucket, refusing it when the bucket is
+/// empty. Appservice users and bots are exempt unless they have an override.
+#[implement(super::Service)]
+pub async fn check_message_rate(&self, user_id: &UserId, appservice: bool) -> Result {
+	let stored = self.rate_limit_override(user_id).await;
+	if stored.is_none() && (appservice || self.is_bot(user_id).await) {
+		return Ok(());
+	}
+
+	let limit = stored.unwrap_or_else(|| self.default_rate_limit());
+	if limit.per_second <= 0.0 {
+		return Ok(());
+	}
+
+	let now = Instant::now();
+	let burst = f64::from(limit.burst.max(1));
+	let mut buckets = self.ratelimit.lock().expect("locked");
+	if buckets.len() >= PRUNE_THRESHOLD {
+		buckets.retain(|_, bucket| !bucket.is_full(now));
+	}
+
+	let bucket = buckets
+		.entry(user_id.to_owned())
+		.or_insert(Bucket { tokens: burst, updated: now, limit });
+
+	let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+	bucket.tokens = elapsed
+		.mul_add(limit.per_second, bucket.tokens)
+		.min(burst);
+	bucket.updated = now;
+	bucket.limit = limit;
+
+	if bucket.tokens < 1.0 {
+		let retry_after = Duration::from_secs_f64((1.0 - bucket.tokens) / limit.per_second);
+		debug_info!(%user_id, ?retry_after, "Message rate limited.");
+		return Err(Error::Request(
+			ErrorKind::LimitExceeded {
+				retry_after: Some(RetryAfter::Delay(retry_after)),
+			},
+			"Too many messages; try again later.".into(),
+			http::StatusCode::TOO_MANY_REQUESTS,
+		));
+	}
+
+	bucket.tokens -= 1.0;
+
+	Ok(())
+}
+
+/// The rate limit of users without an override.
+#[implement(super::Service)]
+#[must_use]
+pub fn default_rate_limit(&self) -> RateLimit {
+	let config = &self.services.server.config;
+	RateLimit {
+		per_second: config.message_rate_limit_per_second,
+		burst: config.message_rate_limit_burst,
+	}
+}
+
+/// The user's stored rate limit override, if any.
+#[implement(super::Service)]
+pub async fn rate_limit_override(&self, user_id: &UserId) -> Option<RateLimit> {
+	self.db
+		.userid_ratelimit
+		.get(user_id)
+		.await
+		.deserialized()
+		.ok()
+}
+
+/// Store a rate limit override for the user, replacing the default.
+#[implement(super::Service)]
+pub fn set_rate_limit_override(&self, user_id: &UserId, limit: RateLimit) {
+	self.db
+		.userid_ratelimit
+		.raw_put(user_id, Json(limit));
+
+	self.clear_rate_limit_bucket(user_id);
+}
+
+/// Remove the user's rate limit override, returning them to the default.
+#[implement(super::Service)]
+pub fn reset_rate_limit_override(&self, user_id: &UserId) {
+	self.db.userid_ratelimit.remove(user_id);
+	self.clear_rate_limit_bucket(user_id);
+}
+
+#[implement(super::Service)]
+fn clear_rate_limit_bucket(&self, user_id: &UserId) {
+	self.ratelimit
+		.lock()
+		.expect("locked")
+		.remove(user_id);
+}
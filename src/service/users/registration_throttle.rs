@@ -0,0 +1,197 @@
+//! Registration throttling by the subnet of the connecting peer, stored in the
+//! database so that restarting does not reset it; see
+//! `registration_throttle_attempts`.
+
+use std::{
+	net::{IpAddr, Ipv4Addr, Ipv6Addr},
+	time::Duration,
+};
+
+use futures::{Stream, StreamExt};
+use ruma::api::client::error::{ErrorKind, RetryAfter};
+use serde::{Deserialize, Serialize};
+use tuwunel_core::{
+	Error, Result,
+	config::NoticeCategory,
+	debug_info, implement,
+	utils::{millis_since_unix_epoch, stream::TryIgnore},
+};
+use tuwunel_database::{Deserialized, Json};
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct RegistrationThrottle {
+	/// Start of the current window, in milliseconds since the epoch.
+	pub window_start: u64,
+
+	/// Failed attempts within the current window.
+	pub attempts: u32,
+
+	/// End of the lockout, in milliseconds since the epoch.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub locked_until: Option<u64>,
+}
+
+/// Refuse registration from the client while its subnet is locked out.
+#[implement(super::Service)]
+pub async fn check_registration_throttle(&self, client: IpAddr) -> Result {
+	if self
+		.services
+		.server
+		.config
+		.registration_throttle_attempts
+		== 0
+	{
+		return Ok(());
+	}
+
+	let subnet = self.registration_subnet(client);
+	let now = millis_since_unix_epoch();
+	let Some(locked_until) = self
+		.registration_throttle(&subnet)
+		.await
+		.and_then(|throttle| throttle.locked_until)
+		.filter(|&locked_until| locked_until > now)
+	else {
+		return Ok(());
+	};
+
+	let retry_after = Duration::from_millis(locked_until.saturating_sub(now));
+	debug_info!(%client, %subnet, ?retry_after, "Registration throttled.");
+
+	Err(Error::Request(
+		ErrorKind::LimitExceeded {
+			retry_after: Some(RetryAfter::Delay(retry_after)),
+		},
+		"Too many registration attempts; try again later.".into(),
+		http::StatusCode::TOO_MANY_REQUESTS,
+	))
+}
+
+/// Count a failed registration attempt from the client, locking its subnet
+/// out once the failures within the window reach the limit.
+#[implement(super::Service)]
+pub async fn count_registration_failure(&self, client: IpAddr) {
+	let config = &self.services.server.config;
+	if config.registration_throttle_attempts == 0 {
+		return;
+	}
+
+	let subnet = self.registration_subnet(client);
+	let _lock = self
+		.registration_throttle_mutex
+		.lock(&subnet)
+		.await;
+
+	let now = millis_since_unix_epoch();
+	let throttle = count_failure(
+		self.registration_throttle(&subnet).await,
+		now,
+		config
+			.registration_throttle_window
+			.saturating_mul(1000),
+		config.registration_throttle_attempts,
+		config
+			.registration_throttle_lockout
+			.saturating_mul(1000),
+	);
+
+	if throttle.attempts == config.registration_throttle_attempts {
+		self.services
+			.admin
+			.category_notice(
+				NoticeCategory::Registration,
+				&format!(
+					"Registration from {subnet} locked out after {} failed attempts.",
+					throttle.attempts
+				),
+			)
+			.await;
+	}
+
+	self.db
+		.subnet_registrationthrottle
+		.put(subnet.as_str(), Json(throttle));
+}
+
+/// Subnets with registration attempts counted, including locked out ones.
+#[implement(super::Service)]
+pub fn registration_throttles(
+	&self,
+) -> impl Stream<Item = (String, RegistrationThrottle)> + Send + '_ {
+	self.db
+		.subnet_registrationthrottle
+		.stream()
+		.ignore_err()
+		.map(|(subnet, throttle): (&str, RegistrationThrottle)| (subnet.to_owned(), throttle))
+}
+
+#[implement(super::Service)]
+pub fn clear_registration_throttle(&self, subnet: &str) {
+	self.db.subnet_registrationthrottle.del(subnet);
+}
+
+#[implement(super::Service)]
+async fn registration_throttle(&self, subnet: &str) -> Option<RegistrationThrottle> {
+	self.db
+		.subnet_registrationthrottle
+		.get(subnet)
+		.await
+		.deserialized()
+		.ok()
+}
+
+/// Subnet of the client counted as one, e.g. `203.0.113.0/24`.
+#[implement(super::Service)]
+pub fn registration_subnet(&self, client: IpAddr) -> String {
+	let config = &self.services.server.config;
+	subnet(
+		client,
+		config.registration_throttle_ipv4_prefix,
+		config.registration_throttle_ipv6_prefix,
+	)
+}
+
+/// The client's address masked to the prefix length of its family. IPv4
+/// addresses mapped into IPv6 count as IPv4.
+pub(super) fn subnet(client: IpAddr, ipv4_prefix: u8, ipv6_prefix: u8) -> String {
+	match client.to_canonical() {
+		| IpAddr::V4(ip) => {
+			let prefix = ipv4_prefix.min(32);
+			let mask = u32::MAX
+				.checked_shl(32_u32.saturating_sub(prefix.into()))
+				.unwrap_or(0);
+
+			format!("{}/{prefix}", Ipv4Addr::from(u32::from(ip) & mask))
+		},
+		| IpAddr::V6(ip) => {
+			let prefix = ipv6_prefix.min(128);
+			let mask = u128::MAX
+				.checked_shl(128_u32.saturating_sub(prefix.into()))
+				.unwrap_or(0);
+
+			format!("{}/{prefix}", Ipv6Addr::from(u128::from(ip) & mask))
+		},
+	}
+}
+
+/// The subnet's throttle after one more failure at `now`. A failure after the
+/// window ends starts a new window; reaching `max_attempts` locks the subnet
+/// out for `lockout` milliseconds.
+pub(super) fn count_failure(
+	throttle: Option<RegistrationThrottle>,
+	now: u64,
+	window: u64,
+	max_attempts: u32,
+	lockout: u64,
+) -> RegistrationThrottle {
+	let mut throttle = throttle
+		.filter(|throttle| throttle.window_start.saturating_add(window) > now)
+		.unwrap_or(RegistrationThrottle { window_start: now, ..Default::default() });
+
+	throttle.attempts = throttle.attempts.saturating_add(1);
+	if throttle.attempts >= max_attempts {
+		throttle.locked_until = Some(now.saturating_add(lockout));
+	}
+
+	throttle
+}
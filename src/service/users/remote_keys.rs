@@ -0,0 +1,102 @@
+//! Device keys of remote users fetched for `/keys/query`, answered from
+//! memory until `remote_device_keys_max_staleness` passes or the user's
+//! server announces a change. Master keys are stored in the database when
+//! fetched and are not part of this cache.
+
+use std::{
+	collections::BTreeMap,
+	sync::Mutex,
+	time::{Duration, Instant},
+};
+
+use lru_cache::LruCache;
+use ruma::{
+	OwnedDeviceId, OwnedUserId, UserId,
+	encryption::{CrossSigningKey, DeviceKeys},
+	serde::Raw,
+};
+use tuwunel_core::implement;
+
+pub(super) struct Cache(Mutex<LruCache<OwnedUserId, (Instant, RemoteKeys)>>);
+
+/// Keys of a remote user's devices as last returned by their server.
+#[derive(Clone, Debug, Default)]
+pub struct RemoteKeys {
+	pub device_keys: BTreeMap<OwnedDeviceId, Raw<DeviceKeys>>,
+	pub self_signing_key: Option<Raw<CrossSigningKey>>,
+}
+
+impl Cache {
+	pub(super) fn new(capacity: usize) -> Self { Self(Mutex::new(LruCache::new(capacity))) }
+
+	/// The keys of the user fetched no longer than `max_staleness` before
+	/// `now`. Stale keys are forgotten.
+	pub(super) fn get(
+		&self,
+		user_id: &UserId,
+		now: Instant,
+		max_staleness: Duration,
+	) -> Option<RemoteKeys> {
+		let mut cache = self.0.lock().expect("locked");
+		let (fetched, keys) = cache.get_mut(user_id)?;
+		if now.saturating_duration_since(*fetched) > max_staleness {
+			cache.remove(user_id);
+			return None;
+		}
+
+		Some(keys.clone())
+	}
+
+	pub(super) fn insert(&self, user_id: &UserId, keys: RemoteKeys, now: Instant) {
+		self.0
+			.lock()
+			.expect("locked")
+			.insert(user_id.to_owned(), (now, keys));
+	}
+
+	pub(super) fn remove(&self, user_id: &UserId) {
+		self.0.lock().expect("locked").remove(user_id);
+	}
+
+	pub(super) fn clear(&self) { self.0.lock().expect("locked").clear(); }
+
+	/// Number of users cached and the capacity.
+	pub(super) fn usage(&self) -> (usize, usize) {
+		let cache = self.0.lock().expect("locked");
+		(cache.len(), cache.capacity())
+	}
+}
+
+/// The cached keys of a remote user, unless they are older than the
+/// configured staleness.
+#[implement(super::Service)]
+pub fn cached_remote_keys(&self, user_id: &UserId) -> Option<RemoteKeys> {
+	self.remote_keys
+		.get(user_id, Instant::now(), self.max_staleness()?)
+}
+
+/// Caches the keys of a remote user returned by their server for a query of
+/// all their devices.
+#[implement(super::Service)]
+pub fn cache_remote_keys(&self, user_id: &UserId, keys: RemoteKeys) {
+	if self.max_staleness().is_none() {
+		return;
+	}
+
+	self.remote_keys
+		.insert(user_id, keys, Instant::now());
+}
+
+#[implement(super::Service)]
+pub fn invalidate_remote_keys(&self, user_id: &UserId) { self.remote_keys.remove(user_id); }
+
+#[implement(super::Service)]
+fn max_staleness(&self) -> Option<Duration> {
+	let max_staleness = self
+		.services
+		.server
+		.config
+		.remote_device_keys_max_staleness;
+
+	(max_staleness > 0).then(|| Duration::from_secs(max_staleness))
+}
@@ -0,0 +1,303 @@
+use std::{
+	net::IpAddr,
+	time::{Duration, Instant},
+};
+
+use ruma::{device_id, user_id};
+use tuwunel_core::{Result, err};
+
+use super::{
+	impersonation::is_impersonation_device,
+	openid::{self, OpenIdRestrictions, OpenIdVerifier},
+	password_provider::{PasswordProviderAuth, PasswordProviderOutcome},
+	registration_throttle::{RegistrationThrottle, count_failure, subnet},
+	remote_keys::{Cache, RemoteKeys},
+};
+
+const MAX_STALENESS: Duration = Duration::from_secs(300);
+
+#[test]
+fn remote_keys_served_until_stale() {
+	let cache = Cache::new(10);
+	let user_id = user_id!("@alice:remote.example");
+	let fetched = Instant::now();
+
+	cache.insert(user_id, RemoteKeys::default(), fetched);
+
+	assert!(
+		cache
+			.get(user_id, fetched, MAX_STALENESS)
+			.is_some()
+	);
+	assert!(
+		cache
+			.get(user_id, fetched + MAX_STALENESS, MAX_STALENESS)
+			.is_some()
+	);
+	assert!(
+		cache
+			.get(user_id, fetched + MAX_STALENESS + Duration::from_secs(1), MAX_STALENESS)
+			.is_none()
+	);
+}
+
+#[test]
+fn stale_remote_keys_forgotten() {
+	let cache = Cache::new(10);
+	let user_id = user_id!("@alice:remote.example");
+	let fetched = Instant::now();
+
+	cache.insert(user_id, RemoteKeys::default(), fetched);
+	let later = fetched + MAX_STALENESS * 2;
+	assert!(cache.get(user_id, later, MAX_STALENESS).is_none());
+
+	// Gone even for a caller allowing older keys.
+	assert!(
+		cache
+			.get(user_id, later, MAX_STALENESS * 4)
+			.is_none()
+	);
+	assert_eq!(cache.usage().0, 0);
+}
+
+#[test]
+fn refetched_remote_keys_fresh_again() {
+	let cache = Cache::new(10);
+	let user_id = user_id!("@alice:remote.example");
+	let fetched = Instant::now();
+
+	cache.insert(user_id, RemoteKeys::default(), fetched);
+	let refetched = fetched + MAX_STALENESS;
+	cache.insert(user_id, RemoteKeys::default(), refetched);
+
+	assert!(
+		cache
+			.get(user_id, refetched + MAX_STALENESS, MAX_STALENESS)
+			.is_some()
+	);
+}
+
+/// Removing a user's cached keys, as done when their server announces a device
+/// list or signing key change, leaves other users' keys cached.
+#[test]
+fn remote_keys_removed_per_user() {
+	let cache = Cache::new(10);
+	let alice = user_id!("@alice:remote.example");
+	let bob = user_id!("@bob:remote.example");
+	let now = Instant::now();
+
+	cache.insert(alice, RemoteKeys::default(), now);
+	cache.insert(bob, RemoteKeys::default(), now);
+	cache.remove(alice);
+
+	assert!(cache.get(alice, now, MAX_STALENESS).is_none());
+	assert!(cache.get(bob, now, MAX_STALENESS).is_some());
+}
+
+#[test]
+fn openid_restrictions_parse() {
+	let restrictions =
+		OpenIdRestrictions::parse(Some("integrations.example"), Some("read  write")).unwrap();
+
+	assert_eq!(restrictions.audience.as_deref(), Some("integrations.example"));
+	assert_eq!(restrictions.scopes, ["read", "write"]);
+
+	assert!(OpenIdRestrictions::parse(Some(""), None).is_err());
+	assert!(OpenIdRestrictions::parse(Some("two words"), None).is_err());
+	assert!(OpenIdRestrictions::parse(None, Some("quo\"te")).is_err());
+	assert_eq!(OpenIdRestrictions::parse(None, None).unwrap(), OpenIdRestrictions::default());
+}
+
+#[test]
+fn openid_unrestricted_permits_any_verifier() {
+	let restrictions = OpenIdRestrictions::default();
+
+	assert!(restrictions.permits(&OpenIdVerifier::default()));
+	assert!(restrictions.permits(&OpenIdVerifier {
+		audience: Some("integrations.example"),
+		scope: Some("read"),
+	}));
+}
+
+#[test]
+fn openid_audience_must_match() {
+	let restrictions = OpenIdRestrictions::parse(Some("integrations.example"), None).unwrap();
+
+	assert!(restrictions.permits(&OpenIdVerifier {
+		audience: Some("integrations.example"),
+		scope: None,
+	}));
+	assert!(!restrictions.permits(&OpenIdVerifier {
+		audience: Some("other.example"),
+		scope: None,
+	}));
+	assert!(!restrictions.permits(&OpenIdVerifier::default()));
+}
+
+#[test]
+fn openid_scope_must_be_granted() {
+	let restrictions = OpenIdRestrictions::parse(None, Some("read write")).unwrap();
+
+	assert!(restrictions.permits(&OpenIdVerifier { audience: None, scope: Some("read") }));
+	assert!(restrictions.permits(&OpenIdVerifier {
+		audience: None,
+		scope: Some("write read"),
+	}));
+	assert!(!restrictions.permits(&OpenIdVerifier {
+		audience: None,
+		scope: Some("read admin"),
+	}));
+	assert!(!restrictions.permits(&OpenIdVerifier::default()));
+}
+
+#[test]
+fn openid_token_value_roundtrip() {
+	let user_id = user_id!("@alice:example.com");
+	let restrictions =
+		OpenIdRestrictions::parse(Some("integrations.example"), Some("read")).unwrap();
+
+	for restrictions in [OpenIdRestrictions::default(), restrictions] {
+		let value = openid::encode(1234, user_id, &restrictions);
+		let (expires_at, decoded_user_id, decoded) = openid::decode(&value).unwrap();
+
+		assert_eq!(expires_at, 1234);
+		assert_eq!(decoded_user_id, user_id);
+		assert_eq!(decoded, restrictions);
+	}
+}
+
+#[test]
+fn openid_token_value_audience_only() {
+	let mut value = 1234_u64.to_be_bytes().to_vec();
+	value.extend_from_slice(b"@alice:example.com\xFFintegrations.example");
+
+	let (_, _, restrictions) = openid::decode(&value).unwrap();
+
+	assert_eq!(restrictions.audience.as_deref(), Some("integrations.example"));
+	assert!(restrictions.scopes.is_empty());
+}
+
+#[test]
+fn registration_subnet_ipv4() {
+	let ip: IpAddr = "203.0.113.77".parse().unwrap();
+
+	assert_eq!(subnet(ip, 24, 64), "203.0.113.0/24");
+	assert_eq!(subnet(ip, 16, 64), "203.0.0.0/16");
+	assert_eq!(subnet(ip, 32, 64), "203.0.113.77/32");
+	assert_eq!(subnet(ip, 0, 64), "0.0.0.0/0");
+	assert_eq!(subnet(ip, 40, 64), "203.0.113.77/32");
+}
+
+#[test]
+fn registration_subnet_ipv6() {
+	let ip: IpAddr = "2001:db8:1:2:3:4:5:6".parse().unwrap();
+
+	assert_eq!(subnet(ip, 24, 64), "2001:db8:1:2::/64");
+	assert_eq!(subnet(ip, 24, 48), "2001:db8:1::/48");
+	assert_eq!(subnet(ip, 24, 128), "2001:db8:1:2:3:4:5:6/128");
+	assert_eq!(subnet(ip, 24, 0), "::/0");
+}
+
+#[test]
+fn registration_subnet_ipv4_mapped() {
+	let ip: IpAddr = "::ffff:203.0.113.77".parse().unwrap();
+
+	assert_eq!(subnet(ip, 24, 64), "203.0.113.0/24");
+}
+
+#[test]
+fn registration_failures_lock_out_at_limit() {
+	const WINDOW: u64 = 1000;
+	const LOCKOUT: u64 = 5000;
+
+	let first = count_failure(None, 100, WINDOW, 3, LOCKOUT);
+	assert_eq!(first.window_start, 100);
+	assert_eq!(first.attempts, 1);
+	assert_eq!(first.locked_until, None);
+
+	let second = count_failure(Some(first), 200, WINDOW, 3, LOCKOUT);
+	assert_eq!(second.attempts, 2);
+	assert_eq!(second.locked_until, None);
+
+	let third = count_failure(Some(second), 300, WINDOW, 3, LOCKOUT);
+	assert_eq!(third.attempts, 3);
+	assert_eq!(third.locked_until, Some(300 + LOCKOUT));
+}
+
+#[test]
+fn registration_failures_window_restarts() {
+	let throttle = RegistrationThrottle {
+		window_start: 100,
+		attempts: 2,
+		locked_until: None,
+	};
+
+	let next = count_failure(Some(throttle), 1100, 1000, 3, 5000);
+	assert_eq!(next.window_start, 1100);
+	assert_eq!(next.attempts, 1);
+	assert_eq!(next.locked_until, None);
+}
+
+#[test]
+fn password_provider_response_minimal() {
+	let auth: PasswordProviderAuth = serde_json::from_str(r#"{"authenticated":true}"#).unwrap();
+
+	assert!(auth.authenticated);
+	assert_eq!(auth.admin, None);
+	assert_eq!(auth.displayname, None);
+}
+
+#[test]
+fn password_provider_response_full() {
+	let auth: PasswordProviderAuth = serde_json::from_str(
+		r#"{"authenticated":true,"admin":false,"displayname":"Alice","extra":1}"#,
+	)
+	.unwrap();
+
+	assert!(auth.authenticated);
+	assert_eq!(auth.admin, Some(false));
+	assert_eq!(auth.displayname.as_deref(), Some("Alice"));
+}
+
+#[test]
+fn password_provider_response_invalid() {
+	assert!(serde_json::from_str::<PasswordProviderAuth>(r#"{"admin":true}"#).is_err());
+	assert!(serde_json::from_str::<PasswordProviderAuth>(r#"{"authenticated":"yes"}"#).is_err());
+}
+
+fn provider_auth(authenticated: bool) -> Result<PasswordProviderAuth> {
+	Ok(PasswordProviderAuth {
+		authenticated,
+		admin: None,
+		displayname: None,
+	})
+}
+
+#[test]
+fn password_provider_outcome_without_fallback() {
+	use PasswordProviderOutcome::*;
+
+	let failed: Result<PasswordProviderAuth> = Err(err!("unreachable"));
+
+	assert_eq!(PasswordProviderOutcome::of(&provider_auth(true), false), Authenticated);
+	assert_eq!(PasswordProviderOutcome::of(&provider_auth(false), false), Refused);
+	assert_eq!(PasswordProviderOutcome::of(&failed, false), Failed);
+}
+
+#[test]
+fn password_provider_outcome_with_fallback() {
+	use PasswordProviderOutcome::*;
+
+	let failed: Result<PasswordProviderAuth> = Err(err!("unreachable"));
+
+	assert_eq!(PasswordProviderOutcome::of(&provider_auth(true), true), Authenticated);
+	assert_eq!(PasswordProviderOutcome::of(&provider_auth(false), true), CheckLocal);
+	assert_eq!(PasswordProviderOutcome::of(&failed, true), CheckLocal);
+}
+
+#[test]
+fn impersonation_device_prefix() {
+	assert!(is_impersonation_device(device_id!("TUWUNEL_IMPERSONATION_abcdefgh")));
+	assert!(!is_impersonation_device(device_id!("ABCDEFGHIJ")));
+	assert!(!is_impersonation_device(device_id!("tuwunel_impersonation_abcdefgh")));
+}
@@ -0,0 +1,210 @@
+//! TOTP two-factor authentication: enrollment, verification and single-use
+//! recovery codes. Secrets are encrypted at rest with `totp_encryption_key`,
+//! and recovery codes are stored as SHA-256 digests keyed with it.
+
+use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+use ruma::UserId;
+use serde::{Deserialize, Serialize};
+use tuwunel_core::{
+	Err, Result, err, implement,
+	utils::{
+		self,
+		hash::{self, sha256},
+		secret, totp,
+	},
+};
+use tuwunel_database::{Deserialized, Json};
+use url::Url;
+
+/// Bytes of secret, the size of an HMAC-SHA1 key.
+const SECRET_LEN: usize = 20;
+
+const RECOVERY_CODES: usize = 10;
+
+const RECOVERY_CODE_LEN: usize = 12;
+
+/// Secret for a user to add to their authenticator app.
+pub struct TotpEnrollment {
+	/// Base32 secret for manual entry.
+	pub secret: String,
+
+	/// `otpauth://` URI, usually shown as a QR code.
+	pub uri: String,
+}
+
+#[derive(Deserialize, Serialize)]
+struct Totp {
+	/// Secret sealed with `totp_encryption_key`.
+	secret: Vec<u8>,
+
+	/// Whether the user has entered a code proving their app is set up;
+	/// unconfirmed enrollments are not enforced.
+	confirmed: bool,
+
+	/// Step of the last accepted code, so that code is not accepted again.
+	last_step: u64,
+
+	/// Keyed digests of the unused recovery codes; see `recovery_digest`.
+	recovery_codes: Vec<String>,
+}
+
+/// Whether the server has TOTP enabled.
+#[implement(super::Service)]
+pub fn totp_available(&self) -> bool {
+	self.services
+		.server
+		.config
+		.totp_encryption_key
+		.is_some()
+}
+
+/// Whether the user has confirmed a TOTP enrollment, making it required.
+#[implement(super::Service)]
+pub async fn totp_enabled(&self, user_id: &UserId) -> bool {
+	self.totp_available()
+		&& self
+			.get_totp(user_id)
+			.await
+			.is_ok_and(|totp| totp.confirmed)
+}
+
+/// Start enrolling the user, replacing any unconfirmed enrollment. It takes
+/// effect once confirmed with a code from the app.
+#[implement(super::Service)]
+pub async fn enroll_totp(&self, user_id: &UserId) -> Result<TotpEnrollment> {
+	if self.totp_enabled(user_id).await {
+		return Err!(Request(Forbidden("Two-factor authentication is already enabled.")));
+	}
+
+	let mut plain = [0_u8; SECRET_LEN];
+	secret::random_bytes(&mut plain)?;
+
+	self.put_totp(user_id, &Totp {
+		secret: secret::seal(self.totp_key()?, user_id.as_bytes(), &plain)?,
+		confirmed: false,
+		last_step: 0,
+		recovery_codes: Vec::new(),
+	});
+
+	let issuer = self.services.globals.server_name().as_str();
+	let secret = totp::base32(&plain);
+	let mut uri = Url::parse("otpauth://totp/").expect("valid otpauth URI");
+	uri.set_path(&format!("{issuer}:{user_id}"));
+	uri.query_pairs_mut()
+		.append_pair("secret", &secret)
+		.append_pair("issuer", issuer);
+
+	Ok(TotpEnrollment { secret, uri: uri.into() })
+}
+
+/// Complete enrollment with a code from the app. Returns the recovery codes,
+/// which are not retrievable afterwards.
+#[implement(super::Service)]
+pub async fn confirm_totp(&self, user_id: &UserId, code: &str) -> Result<Vec<String>> {
+	let _lock = self.totp_mutex.lock(user_id).await;
+
+	let mut totp = self
+		.get_totp(user_id)
+		.await
+		.map_err(|_| err!(Request(NotFound("No two-factor enrollment in progress."))))?;
+
+	if totp.confirmed {
+		return Err!(Request(Forbidden("Two-factor authentication is already enabled.")));
+	}
+
+	let Some(step) = self.check_code(user_id, &totp, code)? else {
+		return Err!(Request(Forbidden("Invalid two-factor code.")));
+	};
+
+	let codes: Vec<String> = (0..RECOVERY_CODES)
+		.map(|_| utils::random_string(RECOVERY_CODE_LEN))
+		.collect();
+
+	totp.confirmed = true;
+	totp.last_step = step;
+	totp.recovery_codes = codes
+		.iter()
+		.map(|code| self.recovery_digest(user_id, code))
+		.collect::<Result<_>>()?;
+
+	self.put_totp(user_id, &totp);
+
+	Ok(codes)
+}
+
+/// Check a code from the user's app, or one of their recovery codes which is
+/// then used up. Codes are accepted at most once, even when the same code is
+/// given in concurrent requests.
+#[implement(super::Service)]
+pub async fn verify_totp(&self, user_id: &UserId, code: &str) -> Result<bool> {
+	let _lock = self.totp_mutex.lock(user_id).await;
+
+	let mut totp = self.get_totp(user_id).await?;
+	if let Some(step) = self.check_code(user_id, &totp, code)? {
+		if step <= totp.last_step {
+			return Ok(false);
+		}
+
+		totp.last_step = step;
+		self.put_totp(user_id, &totp);
+		return Ok(true);
+	}
+
+	let digest = self.recovery_digest(user_id, code.trim())?;
+	let Some(used) = totp
+		.recovery_codes
+		.iter()
+		.position(|recovery| hash::constant_time_eq(digest.as_bytes(), recovery.as_bytes()))
+	else {
+		return Ok(false);
+	};
+
+	totp.recovery_codes.swap_remove(used);
+	self.put_totp(user_id, &totp);
+	Ok(true)
+}
+
+/// Remove the user's enrollment, confirmed or not.
+#[implement(super::Service)]
+pub fn disable_totp(&self, user_id: &UserId) { self.db.userid_totp.remove(user_id); }
+
+#[implement(super::Service)]
+fn check_code(&self, user_id: &UserId, totp: &Totp, code: &str) -> Result<Option<u64>> {
+	let plain = secret::open(self.totp_key()?, user_id.as_bytes(), &totp.secret)?;
+
+	Ok(totp::verify(&plain, code, utils::millis_since_unix_epoch() / 1000))
+}
+
+/// Recovery codes are random, so unlike passwords they need no slow hash; a
+/// digest keyed with `totp_encryption_key` keeps a wrong code cheap to reject
+/// while the user's lock is held.
+#[implement(super::Service)]
+fn recovery_digest(&self, user_id: &UserId, code: &str) -> Result<String> {
+	let inputs = [self.totp_key()?.as_bytes(), user_id.as_bytes(), code.as_bytes()];
+
+	Ok(URL_SAFE_NO_PAD.encode(sha256::delimited(inputs.into_iter())))
+}
+
+#[implement(super::Service)]
+fn totp_key(&self) -> Result<&str> {
+	self.services
+		.server
+		.config
+		.totp_encryption_key
+		.as_deref()
+		.ok_or_else(|| err!(Request(Forbidden("Two-factor authentication is not enabled."))))
+}
+
+#[implement(super::Service)]
+async fn get_totp(&self, user_id: &UserId) -> Result<Totp> {
+	self.db
+		.userid_totp
+		.get(user_id)
+		.await
+		.deserialized()
+}
+
+#[implement(super::Service)]
+fn put_totp(&self, user_id: &UserId, totp: &Totp) {
+	self.db.userid_totp.raw_put(user_id, Json(totp));
+}
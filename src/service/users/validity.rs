@@ -0,0 +1,248 @@
+//! Account validity: accounts expire `account_validity_period` after
+//! registration or their last renewal. Users are sent a link renewing their
+//! account `account_validity_renewal_notice` before it expires.
+
+use std::{
+	collections::HashMap,
+	num::Saturating as Sat,
+	sync::RwLock,
+	time::{Duration, UNIX_EPOCH},
+};
+
+use futures::{Stream, StreamExt};
+use ruma::{OwnedUserId, UserId};
+use tuwunel_core::{
+	Err, Result, debug_info, err, implement,
+	utils::{self, ReadyExt, stream::TryIgnore},
+	warn,
+};
+use tuwunel_database::Deserialized;
+
+/// Expirations read since startup, so requests need not read them again.
+pub(super) type Expirations = RwLock<HashMap<OwnedUserId, u64>>;
+
+/// Path of the renewal links, relative to `well_known.client`.
+const RENEWAL_PATH: &str = "/_tuwunel/account_validity/renew";
+
+const RENEWAL_TOKEN_LENGTH: usize = 32;
+
+/// Whether the account has passed its expiration. Accounts without one, such
+/// as those created before the feature was enabled, start their validity
+/// period now.
+#[implement(super::Service)]
+pub async fn is_account_expired(&self, user_id: &UserId) -> bool {
+	if self.validity_period().is_none() || user_id == self.services.globals.server_user {
+		return false;
+	}
+
+	match self.account_expires_at(user_id).await {
+		| Some(expires_at) => expires_at < utils::millis_since_unix_epoch(),
+		| None => {
+			self.renew_account(user_id, None);
+			false
+		},
+	}
+}
+
+/// Extend the account's validity from now by `period`, or by
+/// `account_validity_period`. Returns the new expiration in milliseconds since
+/// the epoch, or `None` when no period is given or configured. A renewal link
+/// sent before is no longer accepted, and a new one is sent before the new
+/// expiration.
+#[implement(super::Service)]
+pub fn renew_account(&self, user_id: &UserId, period: Option<Duration>) -> Option<u64> {
+	let period = period.or_else(|| self.validity_period())?;
+	let period = u64::try_from(period.as_millis()).unwrap_or(u64::MAX);
+	let expires_at = (Sat(utils::millis_since_unix_epoch()) + Sat(period)).0;
+
+	self.db
+		.userid_expiresat
+		.raw_put(user_id, expires_at);
+
+	self.db.userid_renewaltoken.remove(user_id);
+	self.account_expirations
+		.write()
+		.expect("locked")
+		.insert(user_id.to_owned(), expires_at);
+
+	Some(expires_at)
+}
+
+/// Renew the account a renewal link was sent to. Returns the user and the
+/// new expiration in milliseconds since the epoch.
+#[implement(super::Service)]
+pub async fn renew_account_with_token(&self, token: &str) -> Result<(OwnedUserId, u64)> {
+	let user_id: OwnedUserId = self
+		.db
+		.renewaltoken_userid
+		.get(token)
+		.await
+		.deserialized()
+		.map_err(|_| err!(Request(Forbidden("Invalid or expired renewal link."))))?;
+
+	self.db.renewaltoken_userid.remove(token);
+
+	// Only the latest link sent to the user, and only until the account is
+	// renewed some other way.
+	let latest: Option<String> = self
+		.db
+		.userid_renewaltoken
+		.get(&user_id)
+		.await
+		.deserialized()
+		.ok();
+
+	if latest.as_deref() != Some(token) {
+		return Err!(Request(Forbidden("Invalid or expired renewal link.")));
+	}
+
+	let expires_at = self
+		.renew_account(&user_id, None)
+		.ok_or_else(|| err!(Request(Forbidden("Account validity is not enabled."))))?;
+
+	debug_info!(%user_id, "Account renewed through its renewal link");
+
+	Ok((user_id, expires_at))
+}
+
+/// Expiration of the account in milliseconds since the epoch.
+#[implement(super::Service)]
+pub async fn account_expires_at(&self, user_id: &UserId) -> Option<u64> {
+	let cached = self
+		.account_expirations
+		.read()
+		.expect("locked")
+		.get(user_id)
+		.copied();
+
+	if cached.is_some() {
+		return cached;
+	}
+
+	let expires_at: u64 = self
+		.db
+		.userid_expiresat
+		.get(user_id)
+		.await
+		.deserialized()
+		.ok()?;
+
+	self.account_expirations
+		.write()
+		.expect("locked")
+		.insert(user_id.to_owned(), expires_at);
+
+	Some(expires_at)
+}
+
+/// Accounts expiring before `until` (milliseconds since the epoch), including
+/// those already expired.
+#[implement(super::Service)]
+pub fn accounts_expiring_before(
+	&self,
+	until: u64,
+) -> impl Stream<Item = (&UserId, u64)> + Send + '_ {
+	self.db
+		.userid_expiresat
+		.stream()
+		.ignore_err()
+		.ready_filter_map(move |(user_id, expires_at): (&UserId, u64)| {
+			(expires_at < until).then_some((user_id, expires_at))
+		})
+}
+
+/// Send a renewal link to the users of accounts expiring within
+/// `account_validity_renewal_notice`, once for each expiration.
+#[implement(super::Service)]
+pub(super) async fn send_renewal_notices(&self) {
+	let notice = self
+		.services
+		.server
+		.config
+		.account_validity_renewal_notice;
+
+	if self.validity_period().is_none() || notice == 0 {
+		return;
+	}
+
+	let now = utils::millis_since_unix_epoch();
+	let until = now.saturating_add(notice.saturating_mul(1000));
+	let expiring: Vec<(OwnedUserId, u64)> = self
+		.accounts_expiring_before(until)
+		.ready_filter(|&(_, expires_at)| expires_at >= now)
+		.map(|(user_id, expires_at)| (user_id.to_owned(), expires_at))
+		.collect()
+		.await;
+
+	for (user_id, expires_at) in expiring {
+		if user_id == self.services.globals.server_user
+			|| self
+				.db
+				.userid_renewaltoken
+				.exists(&user_id)
+				.await
+				.is_ok() || !self.is_active_local(&user_id).await
+		{
+			continue;
+		}
+
+		if let Err(e) = self
+			.send_renewal_notice(&user_id, expires_at)
+			.await
+		{
+			warn!(%user_id, "Failed to send account renewal link: {e}");
+		}
+	}
+}
+
+#[implement(super::Service)]
+async fn send_renewal_notice(&self, user_id: &UserId, expires_at: u64) -> Result {
+	let Some(client) = self
+		.services
+		.server
+		.config
+		.well_known
+		.client
+		.as_ref()
+	else {
+		return Err!(Config("well_known.client", "Required for account renewal links."));
+	};
+
+	let token = utils::random_string(RENEWAL_TOKEN_LENGTH);
+	let link = format!("{}{RENEWAL_PATH}?token={token}", client.as_str().trim_end_matches('/'));
+
+	let expires_at = UNIX_EPOCH
+		.checked_add(Duration::from_millis(expires_at))
+		.unwrap_or(UNIX_EPOCH);
+
+	let body = format!(
+		"Your account {user_id} expires on {}. To keep using it, renew it by opening this link: \
+		 {link}",
+		utils::time::format(expires_at, "%Y-%m-%d %H:%M UTC"),
+	);
+
+	self.db
+		.renewaltoken_userid
+		.insert(&token, user_id);
+	self.db
+		.userid_renewaltoken
+		.insert(user_id, &token);
+
+	self.services
+		.admin
+		.send_direct_message(user_id, &body)
+		.await?;
+
+	debug_info!(%user_id, "Sent account renewal link");
+
+	Ok(())
+}
+
+#[implement(super::Service)]
+fn validity_period(&self) -> Option<Duration> {
+	self.services
+		.server
+		.config
+		.account_validity_period
+		.map(Duration::from_secs)
+}
@@ -0,0 +1,189 @@
+//! Preloading of frequently used data after startup; see `warmup` in
+//! `[global.caches]`.
+
+use std::{
+	collections::BTreeSet,
+	sync::Arc,
+	time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use futures::{FutureExt, StreamExt};
+use ruma::{OwnedRoomId, OwnedServerName, OwnedUserId};
+use tuwunel_core::{
+	Result, Server, debug, implement, info,
+	utils::{self, ReadyExt, stream::IterStream},
+};
+
+use crate::{Dep, globals, rooms, server_keys, users};
+
+pub struct Service {
+	services: Services,
+}
+
+struct Services {
+	server: Arc<Server>,
+	globals: Dep<globals::Service>,
+	server_keys: Dep<server_keys::Service>,
+	state: Dep<rooms::state::Service>,
+	state_accessor: Dep<rooms::state_accessor::Service>,
+	state_cache: Dep<rooms::state_cache::Service>,
+	users: Dep<users::Service>,
+}
+
+/// Devices seen within this long are considered recent.
+const RECENT_DEVICE_AGE: Duration = Duration::from_secs(60 * 60 * 24 * 7);
+
+#[async_trait]
+impl crate::Service for Service {
+	fn build(args: crate::Args<'_>) -> Result<Arc<Self>> {
+		Ok(Arc::new(Self {
+			services: Services {
+				server: args.server.clone(),
+				globals: args.depend::<globals::Service>("globals"),
+				server_keys: args.depend::<server_keys::Service>("server_keys"),
+				state: args.depend::<rooms::state::Service>("rooms::state"),
+				state_accessor: args
+					.depend::<rooms::state_accessor::Service>("rooms::state_accessor"),
+				state_cache: args.depend::<rooms::state_cache::Service>("rooms::state_cache"),
+				users: args.depend::<users::Service>("users"),
+			},
+		}))
+	}
+
+	async fn worker(self: Arc<Self>) -> Result {
+		if self.services.server.config.caches.warmup {
+			self.warmup().await;
+		}
+
+		Ok(())
+	}
+
+	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
+}
+
+#[implement(Service)]
+async fn warmup(&self) {
+	let timer = Instant::now();
+	debug!("Warming caches...");
+
+	let rooms: Vec<OwnedRoomId> = self
+		.services
+		.state_cache
+		.server_rooms(self.services.globals.server_name())
+		.map(ToOwned::to_owned)
+		.collect()
+		.await;
+
+	let events = self.warm_room_state(&rooms).await;
+	let servers = self.warm_server_keys(&rooms).await;
+	let devices = self.warm_device_keys().await;
+
+	info!(
+		rooms = rooms.len(),
+		events,
+		servers,
+		devices,
+		elapsed = ?timer.elapsed(),
+		"Cache warmup complete.",
+	);
+}
+
+/// Load the current state of our rooms, returning the number of state events.
+#[implement(Service)]
+async fn warm_room_state(&self, rooms: &[OwnedRoomId]) -> usize {
+	rooms
+		.iter()
+		.stream()
+		.ready_take_while(|_| self.services.server.running())
+		.filter_map(|room_id| {
+			self.services
+				.state
+				.get_room_shortstatehash(room_id)
+				.map(Result::ok)
+		})
+		.then(|shortstatehash| {
+			self.services
+				.state_accessor
+				.state_full_pdus(shortstatehash)
+				.count()
+		})
+		.ready_fold(0_usize, usize::saturating_add)
+		.await
+}
+
+/// Load the signing keys of the servers in our rooms, returning the number of
+/// servers.
+#[implement(Service)]
+async fn warm_server_keys(&self, rooms: &[OwnedRoomId]) -> usize {
+	let mut servers = BTreeSet::<OwnedServerName>::new();
+	for room_id in rooms {
+		if !self.services.server.running() {
+			break;
+		}
+
+		self.services
+			.state_cache
+			.room_servers(room_id)
+			.ready_for_each(|server| {
+				servers.insert(server.to_owned());
+			})
+			.await;
+	}
+
+	servers
+		.iter()
+		.stream()
+		.ready_take_while(|_| self.services.server.running())
+		.then(|server| self.services.server_keys.verify_keys_for(server))
+		.count()
+		.await
+}
+
+/// Load the keys of local devices seen recently, returning the number of
+/// devices.
+#[implement(Service)]
+async fn warm_device_keys(&self) -> usize {
+	let max_age = u64::try_from(RECENT_DEVICE_AGE.as_millis()).unwrap_or(u64::MAX);
+	let recent = utils::millis_since_unix_epoch().saturating_sub(max_age);
+
+	let users: Vec<OwnedUserId> = self
+		.services
+		.users
+		.list_local_users()
+		.map(ToOwned::to_owned)
+		.collect()
+		.await;
+
+	let mut devices = 0_usize;
+	for user_id in &users {
+		if !self.services.server.running() {
+			break;
+		}
+
+		let device_ids: Vec<_> = self
+			.services
+			.users
+			.all_devices_metadata(user_id)
+			.ready_filter(|device| {
+				device
+					.last_seen_ts
+					.is_some_and(|ts| u64::from(ts.get()) >= recent)
+			})
+			.map(|device| device.device_id)
+			.collect()
+			.await;
+
+		for device_id in &device_ids {
+			_ = self
+				.services
+				.users
+				.get_device_keys(user_id, device_id)
+				.await;
+		}
+
+		devices = devices.saturating_add(device_ids.len());
+	}
+
+	devices
+}